@@ -0,0 +1,114 @@
+use crate::units::UsdcAmount;
+use ethers::types::{Address, U256};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// How much USDC to approve when a spender's allowance runs short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApprovalPolicy {
+    /// One-time unlimited approval.
+    #[default]
+    Max,
+    /// Approve this many USDC at a time, topping up when a trade needs more.
+    Bounded(Decimal),
+}
+
+impl ApprovalPolicy {
+    /// The allowance to approve when `current` does not cover `needed` base units, or `None`
+    /// when no approval is required. A bounded approval never grants less than `needed`.
+    pub fn top_up(&self, current: U256, needed: U256) -> Option<U256> {
+        if current >= needed {
+            return None;
+        }
+        match self {
+            ApprovalPolicy::Max => Some(U256::MAX),
+            ApprovalPolicy::Bounded(amount) => Some(UsdcAmount(*amount).to_units().unwrap_or(U256::MAX).max(needed)),
+        }
+    }
+}
+
+/// Last known USDC allowance per spender, so execution only reads the chain when the cached
+/// allowance cannot cover a trade. Spending decrements the cache the way the token does.
+#[derive(Debug, Default)]
+pub struct AllowanceCache {
+    allowances: Mutex<HashMap<Address, U256>>,
+}
+
+impl AllowanceCache {
+    fn allowances(&self) -> std::sync::MutexGuard<'_, HashMap<Address, U256>> {
+        self.allowances.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn covers(&self, spender: Address, units: U256) -> bool {
+        self.allowances().get(&spender).is_some_and(|a| *a >= units)
+    }
+
+    pub fn set(&self, spender: Address, allowance: U256) {
+        self.allowances().insert(spender, allowance);
+    }
+
+    /// Records `units` pulled by `spender`. Unlimited approvals are never decremented by the token.
+    pub fn spend(&self, spender: Address, units: U256) {
+        if let Some(allowance) = self.allowances().get_mut(&spender) {
+            if *allowance != U256::MAX {
+                *allowance = allowance.saturating_sub(units);
+            }
+        }
+    }
+}
+
+/// Operators known to hold ERC-1155 approval over the wallet's outcome tokens. Approval for all
+/// is never spent, so an operator once seen approved stays approved.
+#[derive(Debug, Default)]
+pub struct OperatorCache {
+    approved: Mutex<HashSet<Address>>,
+}
+
+impl OperatorCache {
+    fn approved(&self) -> std::sync::MutexGuard<'_, HashSet<Address>> {
+        self.approved.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_approved(&self, operator: Address) -> bool {
+        self.approved().contains(&operator)
+    }
+
+    pub fn set_approved(&self, operator: Address) {
+        self.approved().insert(operator);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_top_up_policies() {
+        let needed = U256::from(50_000_000u64);
+        assert_eq!(ApprovalPolicy::Max.top_up(U256::zero(), U256::one()), Some(U256::MAX));
+        assert_eq!(ApprovalPolicy::Max.top_up(needed, needed), None);
+
+        let bounded = ApprovalPolicy::Bounded(dec!(1000));
+        assert_eq!(bounded.top_up(U256::zero(), needed), Some(U256::from(1_000_000_000u64)));
+        // A trade larger than the bound is approved for exactly what it needs
+        let large = U256::from(2_000_000_000u64);
+        assert_eq!(bounded.top_up(U256::from(1_000_000_000u64), large), Some(large));
+    }
+
+    #[test]
+    fn test_cache_spends_bounded_allowances_only() {
+        let cache = AllowanceCache::default();
+        let (bounded, unlimited) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        cache.set(bounded, U256::from(100));
+        cache.set(unlimited, U256::MAX);
+        assert!(cache.covers(bounded, U256::from(100)));
+
+        cache.spend(bounded, U256::from(60));
+        cache.spend(unlimited, U256::from(60));
+        assert!(!cache.covers(bounded, U256::from(50)));
+        assert!(cache.covers(unlimited, U256::MAX));
+        assert!(!cache.covers(Address::repeat_byte(3), U256::one()));
+    }
+}
@@ -1,24 +1,60 @@
-use super::shared_types::{Market, Condition, RebalancingOpportunity, CombinatorialOpportunity, Direction, DependencyGraph, Entity, PatternType, Dependency};
+use crate::candidate_index::CandidateIndex;
+use crate::entities::{extract_entities, parse_number};
+use crate::fees::FeeModel;
+use crate::order_book::{BookSide, OrderBook, PriceLevel};
+use crate::topic_classifier::{MarketCategory, TopicClassifier};
+use crate::similarity::{SimilarityBackend, TitleSimilarity};
+use super::shared_types::{Market, Condition, RebalancingOpportunity, CombinatorialOpportunity, BasketOpportunity, LadderOpportunity, MultiLegOpportunity, Leg, Direction, DependencyGraph, LivePrices, MarketRegistry, Entity, OutcomeKind, PatternType, Dependency};
 use rust_decimal::Decimal;
+use chrono::{Datelike, Duration, NaiveDate};
 use rust_decimal_macros::dec;
 use std::collections::{HashMap, HashSet};
 use regex::Regex;
-use strsim::normalized_damerau_levenshtein;
 use lazy_static::lazy_static;
+use rayon::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("unknown market: {0}")]
+    UnknownMarket(String),
+    #[error("market {0} has no YES condition")]
+    MissingYesCondition(String),
+    #[error("unknown dependency pattern: {0}")]
+    UnknownPattern(String),
+    #[error("unknown strategy: {0}")]
+    UnknownStrategy(String),
+}
 
 lazy_static! {
-    static ref RE_RANGE: Regex = Regex::new(r"(\d+\.?\d*)\s*-\s*(\d+\.?\d*)%?").unwrap();
-    static ref RE_GREATER_THAN: Regex = Regex::new(r">(\d+\.?\d*)%?").unwrap();
-    static ref RE_LESS_THAN: Regex = Regex::new(r"<(\d+\.?\d*)%?").unwrap();
+    static ref RE_DEADLINE: Regex = Regex::new(
+        r"\b(by|before)\s+(?:the\s+)?(?:end\s+(?:of\s+)?)?(?:(jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)\b(?:\s+(\d{1,2})(?:st|nd|rd|th)?\b)?,?\s*)?(\d{4}\b)?"
+    ).unwrap();
+    static ref RE_THRESHOLD: Regex = Regex::new(
+        r"\b(above|over|exceeds?|hits?|reach(?:es)?|at least|greater than|below|under|less than|(?:dips?|falls?|drops?) to)\s+\$?(\d{1,3}(?:[, ]\d{3})+|\d+(?:\.\d+)?)\s*(k|mm|m|bn|b)?\b"
+    ).unwrap();
+    static ref RE_GAME_NUMBER: Regex = Regex::new(r"\bgame\s+(\d+)\b").unwrap();
+    static ref RE_BEST_OF: Regex = Regex::new(r"\bbest\s+of\s+(\d+)\b").unwrap();
+    // Normalized titles lose the decimal point, so "-6_5" reads back as "-6 5"
+    static ref RE_SPREAD: Regex = Regex::new(r"\b(?:spread|covers?)\b\D*?(-)?(\d+(?:\.\d+)?)(?:\s(5)\b)?").unwrap();
+    static ref RE_WIN_VERB: Regex = Regex::new(r"\b(?:beats?|defeats?|wins?)\b").unwrap();
 }
 
-/// Trait for different dependency patterns as per the design summary
-trait DependencyPattern {
+/// Words marking a sports market that is about something other than who wins (totals, props).
+const NON_RESULT_WORDS: [&str; 12] = ["total", "totals", "o", "over", "under", "first", "half", "quarter", "period", "inning", "points", "mvp"];
+
+/// A rule that recognises one way a condition in one market constrains a condition in another.
+/// Implement it to teach the engine a new relationship and add it with `PatternRegistry::register`.
+pub trait DependencyPattern: Send + Sync {
+    /// Identifier used to disable the pattern from config.
+    fn name(&self) -> &'static str;
     fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, shared_entities: &HashSet<Entity>) -> Option<Dependency>;
 }
 
 struct WinnerMarginPattern;
 impl DependencyPattern for WinnerMarginPattern {
+    fn name(&self) -> &'static str { "winner_margin" }
+
     fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, shared_entities: &HashSet<Entity>) -> Option<Dependency> {
         let t1 = m1.title.to_lowercase();
         let t2 = m2.title.to_lowercase();
@@ -26,19 +62,22 @@ impl DependencyPattern for WinnerMarginPattern {
         let is_winner_m = t1.contains("win") || t1.contains("winner") || t1.contains("victory");
         let is_margin_m = t2.contains("margin") || t2.contains("points") || t2.contains("by");
 
-        if (is_winner_m && is_margin_m) || (is_margin_m && is_winner_m) {
+        if is_winner_m && is_margin_m {
+            let side1: HashSet<Entity> = extract_entities(&t1).union(&extract_entities(&c1.name)).cloned().collect();
+            let side2: HashSet<Entity> = extract_entities(&t2).union(&extract_entities(&c2.name)).cloned().collect();
             for entity in shared_entities {
-                if let Entity::Candidate(name) = entity {
-                    let name_lower = name.to_lowercase();
-                    let m1_rel = t1.contains(&name_lower) || c1.name.to_lowercase().contains(&name_lower);
-                    let m2_rel = t2.contains(&name_lower) || c2.name.to_lowercase().contains(&name_lower);
+                if let Entity::Candidate(_) = entity {
+                    let m1_rel = side1.contains(entity);
+                    let m2_rel = side2.contains(entity);
                     
                     if m1_rel && m2_rel && c1.outcome == Some(true) && c2.outcome == Some(true) {
-                        if is_winner_m && is_margin_m {
-                             return Some(Dependency { pattern: PatternType::WinnerMargin, direction: Direction::C2ImpliesC1 });
-                        } else {
-                             return Some(Dependency { pattern: PatternType::WinnerMargin, direction: Direction::C1ImpliesC2 });
-                        }
+                        let direction = if is_winner_m && is_margin_m { Direction::C2ImpliesC1 } else { Direction::C1ImpliesC2 };
+                        return Some(Dependency {
+                            pattern: PatternType::WinnerMargin,
+                            direction,
+                            confidence: 0.6,
+                            explanation: format!("{:?} in winner and margin titles", entity),
+                        });
                     }
                 }
             }
@@ -47,17 +86,370 @@ impl DependencyPattern for WinnerMarginPattern {
     }
 }
 
+/// Two markets naming different contenders for the same prize ("Trump wins the presidency"
+/// vs "Harris wins the presidency") cannot both resolve YES, so YES_A implies NO_B. A violation
+/// is YES_A + YES_B > 1, which shows up as P(YES_A) > P(NO_B); fees are left to the profit model.
+struct ComplementPattern;
+impl DependencyPattern for ComplementPattern {
+    fn name(&self) -> &'static str { "complement" }
+
+    fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+        if m1.end_date.date_naive() != m2.end_date.date_naive() { return None; }
+        let t1 = m1.title.to_lowercase();
+        let t2 = m2.title.to_lowercase();
+        if !t1.contains("win") || !t2.contains("win") { return None; }
+
+        let (contenders1, context1) = split_contenders(&t1);
+        let (contenders2, context2) = split_contenders(&t2);
+        if contenders1.len() != 1 || contenders2.len() != 1 || contenders1 == contenders2 { return None; }
+        if context1.is_empty() || context1 != context2 { return None; }
+
+        let direction = match (c1.outcome, c2.outcome) {
+            (Some(true), Some(false)) => Direction::C1ImpliesC2,
+            (Some(false), Some(true)) => Direction::C2ImpliesC1,
+            _ => return None,
+        };
+        Some(Dependency {
+            pattern: PatternType::Complement,
+            direction,
+            confidence: 0.8,
+            explanation: format!("{} vs {} for {}", entity_names(&contenders1), entity_names(&contenders2), entity_names(&context1)),
+        })
+    }
+}
+
+/// Sorted, comma-separated entity names for explanations.
+fn entity_names(entities: &HashSet<Entity>) -> String {
+    let mut names: Vec<String> = entities.iter().map(|e| format!("{:?}", e)).collect();
+    names.sort();
+    names.join(", ")
+}
+
+/// Splits a title's entities into who is competing (candidates, teams) and what for
+/// (events, locations). Numbers are dropped.
+fn split_contenders(title: &str) -> (HashSet<Entity>, HashSet<Entity>) {
+    extract_entities(title)
+        .into_iter()
+        .filter(|e| !matches!(e, Entity::NumericalValue(_)))
+        .partition(|e| matches!(e, Entity::Candidate(_) | Entity::Team(_)))
+}
+
+/// Sports results as bounds on one team's margin over the other: a win is margin > 0, covering
+/// -6.5 is margin > 6.5 and covering +6.5 is margin > -6.5. So a minus-spread cover implies the
+/// win, the win implies every plus-spread cover, and one team winning implies the other does
+/// not. The deciding game of a series has the series winner, so the two compare as well.
+struct SportsPattern;
+impl DependencyPattern for SportsPattern {
+    fn name(&self) -> &'static str { "sports" }
+
+    fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+        let k1 = sports_claim(m1, c1)?;
+        let k2 = sports_claim(m2, c2)?;
+        if k1.matchup != k2.matchup { return None; }
+        let comparable = match (k1.contest, k2.contest) {
+            (Contest::Series, Contest::Series) => true,
+            (Contest::Game(g1), Contest::Game(g2)) => g1 == g2 && m1.end_date.date_naive() == m2.end_date.date_naive(),
+            (Contest::Series, Contest::Game(_)) => k2.deciding,
+            (Contest::Game(_), Contest::Series) => k1.deciding,
+        };
+        if !comparable { return None; }
+
+        let pattern = if k1.contest == Contest::Series || k2.contest == Contest::Series {
+            PatternType::Series
+        } else if !k1.bound.is_zero() || !k2.bound.is_zero() {
+            PatternType::Spread
+        } else {
+            PatternType::Complement
+        };
+        let direction = if k1.implies(&k2) {
+            Direction::C1ImpliesC2
+        } else if k2.implies(&k1) {
+            Direction::C2ImpliesC1
+        } else {
+            return None;
+        };
+        // Series comparisons assume best of 7 when the title doesn't say
+        let confidence = if pattern == PatternType::Series { 0.8 } else { 0.9 };
+        let explanation = format!("{} vs {}: {} ⇒ {}", k1.matchup[0], k1.matchup[1], k1.describe(), k2.describe());
+        Some(Dependency { pattern, direction, confidence, explanation })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Contest {
+    Series,
+    /// A single game, numbered when the title says which game of a series it is.
+    Game(Option<u32>),
+}
+
+/// What a sports condition resolving YES says about `matchup[0]`'s margin over `matchup[1]`.
+#[derive(Debug, PartialEq)]
+struct SportsClaim {
+    /// Both teams, sorted.
+    matchup: [String; 2],
+    contest: Contest,
+    /// Game n of a best-of-n series (best of 7 unless the title says otherwise).
+    deciding: bool,
+    /// Margin above `bound` when true, below it when false.
+    above: bool,
+    bound: Decimal,
+    /// Whether a margin of exactly `bound` is excluded.
+    strict: bool,
+}
+
+impl SportsClaim {
+    fn implies(&self, other: &SportsClaim) -> bool {
+        if self.above != other.above { return false; }
+        let tighter = if self.above { self.bound > other.bound } else { self.bound < other.bound };
+        tighter || (self.bound == other.bound && (self.strict || !other.strict))
+    }
+
+    fn describe(&self) -> String {
+        let contest = match self.contest {
+            Contest::Series => "series".to_string(),
+            Contest::Game(Some(n)) => format!("game {}", n),
+            Contest::Game(None) => "game".to_string(),
+        };
+        let op = match (self.above, self.strict) {
+            (true, true) => ">",
+            (true, false) => ">=",
+            (false, true) => "<",
+            (false, false) => "<=",
+        };
+        format!("{} margin {} {}", contest, op, self.bound)
+    }
+}
+
+fn team_entities(text: &str) -> Vec<String> {
+    let mut teams: Vec<String> = extract_entities(text)
+        .into_iter()
+        .filter_map(|e| match e { Entity::Team(team) => Some(team), _ => None })
+        .collect();
+    teams.sort();
+    teams
+}
+
+/// The two teams a market is about, from its title and team-named outcomes.
+fn sports_matchup(market: &Market) -> Option<[String; 2]> {
+    let mut teams = team_entities(&market.title);
+    teams.extend(market.conditions.iter().flat_map(|c| team_entities(&c.name)));
+    teams.sort();
+    teams.dedup();
+    <[String; 2]>::try_from(teams).ok()
+}
+
+/// Reads a moneyline, spread or series condition. Conditions named after a team ("Lakers")
+/// claim that team's result; YES/NO conditions claim the title's subject's result or its
+/// negation.
+fn sports_claim(market: &Market, condition: &Condition) -> Option<SportsClaim> {
+    let title = market.title.to_lowercase().replace(['_', '?', ':', '(', ')', '/'], " ");
+    if title.split_whitespace().any(|w| NON_RESULT_WORDS.contains(&w)) { return None; }
+    let matchup = sports_matchup(market)?;
+
+    // Spread line as held by the team named before it
+    let spread = RE_SPREAD.captures(&title).and_then(|caps| {
+        let whole = caps.get(2)?;
+        let [team] = <[String; 1]>::try_from(team_entities(&title[..whole.start()])).ok()?;
+        let mut line = Decimal::from_str_exact(whole.as_str()).ok()?;
+        if caps.get(3).is_some() { line += dec!(0.5); }
+        Some((team, if caps.get(1).is_some() { -line } else { line }))
+    });
+
+    let (team, line, holds) = match (condition.outcome, <[String; 1]>::try_from(team_entities(&condition.name))) {
+        (None, Ok([team])) => {
+            let line = match &spread {
+                Some((spread_team, line)) if *spread_team == team => *line,
+                Some((_, line)) => -*line,
+                None => Decimal::ZERO,
+            };
+            (team, line, true)
+        }
+        (Some(holds), _) => match spread {
+            Some((team, line)) => (team, line, holds),
+            None => {
+                let verb = RE_WIN_VERB.find(&title)?;
+                let [team] = <[String; 1]>::try_from(team_entities(&title[..verb.start()])).ok()?;
+                (team, Decimal::ZERO, holds)
+            }
+        },
+        _ => return None,
+    };
+
+    let contest = match RE_GAME_NUMBER.captures(&title) {
+        Some(caps) => Contest::Game(caps[1].parse().ok()),
+        None if title.contains("series") => Contest::Series,
+        None => Contest::Game(None),
+    };
+    let best_of: u32 = RE_BEST_OF.captures(&title).and_then(|caps| caps[1].parse().ok()).unwrap_or(7);
+
+    // `team` covering `line` means its margin exceeds -line
+    let (above, bound) = if team == matchup[0] { (true, -line) } else { (false, line) };
+    Some(SportsClaim {
+        deciding: contest == Contest::Game(Some(best_of)),
+        matchup,
+        contest,
+        above: above == holds,
+        bound,
+        strict: holds,
+    })
+}
+
+/// "X by March" resolving YES means "X by June" does too, so the earlier deadline implies
+/// the later one whenever the titles agree once the deadline phrase is removed.
+struct DeadlinePattern;
+impl DependencyPattern for DeadlinePattern {
+    fn name(&self) -> &'static str { "deadline" }
+
+    fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+        if c1.outcome != Some(true) || c2.outcome != Some(true) { return None; }
+        let (d1, stem1) = market_deadline(m1, c1)?;
+        let (d2, stem2) = market_deadline(m2, c2)?;
+        if stem1.is_empty() || stem1 != stem2 || d1 == d2 { return None; }
+        let direction = if d1 < d2 { Direction::C1ImpliesC2 } else { Direction::C2ImpliesC1 };
+        Some(Dependency {
+            pattern: PatternType::Deadline,
+            direction,
+            confidence: 0.9,
+            explanation: format!("'{}' by {} vs {}", stem1, d1, d2),
+        })
+    }
+}
+
+/// Deadline from the title, falling back to the condition name ("Will X happen?" / "by June").
+/// Dates without a year take the year of the market's end date.
+fn market_deadline(market: &Market, condition: &Condition) -> Option<(NaiveDate, String)> {
+    parse_deadline(&market.title, market.end_date.year())
+        .or_else(|| parse_deadline(&format!("{} {}", market.title, condition.name), market.end_date.year()))
+}
+
+/// Finds the first "by|before [the end [of]] <month> [day][,] [year]" phrase and returns the last
+/// day it allows plus the text with the phrase removed. "by" is inclusive of the whole period,
+/// "before" ends the day before the period starts.
+fn parse_deadline(text: &str, default_year: i32) -> Option<(NaiveDate, String)> {
+    let text = text.to_lowercase().replace(['_', '?'], " ");
+    for caps in RE_DEADLINE.captures_iter(&text) {
+        let month = caps.get(2).map(|m| month_number(m.as_str()));
+        let day = caps.get(3).and_then(|d| d.as_str().parse::<u32>().ok());
+        let year = caps.get(4).and_then(|y| y.as_str().parse::<i32>().ok());
+        let inclusive = &caps[1] == "by";
+
+        let deadline = match (month, day, year) {
+            (None, _, None) => continue,
+            (None, _, Some(year)) if inclusive => NaiveDate::from_ymd_opt(year, 12, 31)?,
+            (None, _, Some(year)) => NaiveDate::from_ymd_opt(year - 1, 12, 31)?,
+            (Some(month), Some(day), year) => {
+                let date = NaiveDate::from_ymd_opt(year.unwrap_or(default_year), month, day)?;
+                if inclusive { date } else { date.pred_opt()? }
+            }
+            (Some(month), None, year) => {
+                let first = NaiveDate::from_ymd_opt(year.unwrap_or(default_year), month, 1)?;
+                if inclusive {
+                    let next = if month == 12 { NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)? } else { NaiveDate::from_ymd_opt(first.year(), month + 1, 1)? };
+                    next.pred_opt()?
+                } else {
+                    first.pred_opt()?
+                }
+            }
+        };
+
+        let whole = caps.get(0)?;
+        let stem = format!("{} {}", &text[..whole.start()], &text[whole.end()..]);
+        return Some((deadline, stem.split_whitespace().collect::<Vec<_>>().join(" ")));
+    }
+    None
+}
+
+fn month_number(name: &str) -> u32 {
+    const PREFIXES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    PREFIXES.iter().position(|p| name.starts_with(p)).map_or(1, |i| i as u32 + 1)
+}
+
+/// The title with its deadline phrase removed, for markets that have one.
+pub(crate) fn deadline_stem(market: &Market) -> Option<String> {
+    parse_deadline(&market.title, market.end_date.year()).map(|(_, stem)| stem).filter(|stem| !stem.is_empty())
+}
+
+/// Both markets ask the same question with different deadlines.
+fn is_deadline_ladder(m1: &Market, m2: &Market) -> bool {
+    match (parse_deadline(&m1.title, m1.end_date.year()), parse_deadline(&m2.title, m2.end_date.year())) {
+        (Some((d1, s1)), Some((d2, s2))) => d1 != d2 && !s1.is_empty() && s1 == s2,
+        _ => false,
+    }
+}
+
+/// "BTC above $100k" implies "BTC above $90k"; "BTC below $80k" implies "BTC below $90k".
+/// Both titles must use the same direction and agree once the threshold is removed.
+struct ThresholdPattern;
+impl DependencyPattern for ThresholdPattern {
+    fn name(&self) -> &'static str { "threshold" }
+
+    fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+        if c1.outcome != Some(true) || c2.outcome != Some(true) { return None; }
+        let t1 = market_threshold(m1, c1)?;
+        let t2 = market_threshold(m2, c2)?;
+        if t1.stem.is_empty() || t1.stem != t2.stem || t1.upward != t2.upward || t1.value == t2.value { return None; }
+        // Upward: the higher bar is harder to clear. Downward: the lower one is.
+        let c1_stricter = if t1.upward { t1.value > t2.value } else { t1.value < t2.value };
+        let direction = if c1_stricter { Direction::C1ImpliesC2 } else { Direction::C2ImpliesC1 };
+        Some(Dependency {
+            pattern: PatternType::Threshold,
+            direction,
+            confidence: 0.9,
+            explanation: format!("'{}' {} {} vs {}", t1.stem, if t1.upward { "above" } else { "below" }, t1.value, t2.value),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Threshold {
+    value: Decimal,
+    /// true for above/hits/reaches, false for below/dips to.
+    upward: bool,
+    /// Text with the direction word and number removed.
+    stem: String,
+}
+
+fn market_threshold(market: &Market, condition: &Condition) -> Option<Threshold> {
+    parse_threshold(&market.title).or_else(|| parse_threshold(&format!("{} {}", market.title, condition.name)))
+}
+
+/// Parses "above $100k", "reaches 100,000", "dips to 2.5m" and the like. Normalized titles
+/// turn "100,000" into "100_000", which is read back as a thousands separator.
+fn parse_threshold(text: &str) -> Option<Threshold> {
+    let text = text.to_lowercase().replace(['_', '?'], " ");
+    let caps = RE_THRESHOLD.captures(&text)?;
+    let upward = !matches!(&caps[1], "below" | "under" | "less than") && !caps[1].ends_with(" to");
+    let digits: String = caps[2].chars().filter(|c| !matches!(c, ',' | ' ')).collect();
+    let value = parse_number(&format!("{}{}", digits, caps.get(3).map_or("", |s| s.as_str())))?;
+    let whole = caps.get(0)?;
+    let stem = format!("{} {}", &text[..whole.start()], &text[whole.end()..]);
+    Some(Threshold { value, upward, stem: stem.split_whitespace().collect::<Vec<_>>().join(" ") })
+}
+
+fn is_threshold_ladder(m1: &Market, m2: &Market) -> bool {
+    match (parse_threshold(&m1.title), parse_threshold(&m2.title)) {
+        (Some(t1), Some(t2)) => t1.value != t2.value && t1.upward == t2.upward && !t1.stem.is_empty() && t1.stem == t2.stem,
+        _ => false,
+    }
+}
+
 struct SubsetImplicationPattern;
 impl DependencyPattern for SubsetImplicationPattern {
+    fn name(&self) -> &'static str { "subset" }
+
     fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+        let subset = |direction, broad: &str, narrow: &str| Some(Dependency {
+            pattern: PatternType::SubsetImplication,
+            direction,
+            confidence: 0.5,
+            explanation: format!("'{}' contains '{}'", narrow, broad),
+        });
         if m2.title.contains(&m1.title) && m1.title != m2.title {
             if c1.outcome == c2.outcome && c1.outcome == Some(true) {
-                return Some(Dependency { pattern: PatternType::SubsetImplication, direction: Direction::C2ImpliesC1 });
-            }
-        } else if m1.title.contains(&m2.title) && m1.title != m2.title {
-            if c1.outcome == c2.outcome && c1.outcome == Some(true) {
-                return Some(Dependency { pattern: PatternType::SubsetImplication, direction: Direction::C1ImpliesC2 });
+                return subset(Direction::C2ImpliesC1, &m1.title, &m2.title);
             }
+        } else if m1.title.contains(&m2.title) && m1.title != m2.title && c1.outcome == c2.outcome && c1.outcome == Some(true) {
+            return subset(Direction::C1ImpliesC2, &m2.title, &m1.title);
         }
         None
     }
@@ -65,6 +457,8 @@ impl DependencyPattern for SubsetImplicationPattern {
 
 struct StateNationalPattern;
 impl DependencyPattern for StateNationalPattern {
+    fn name(&self) -> &'static str { "state_national" }
+
     fn matches(&self, m1: &Market, _c1: &Condition, m2: &Market, _c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
         let t1 = m1.title.to_lowercase();
         let t2 = m2.title.to_lowercase();
@@ -72,8 +466,14 @@ impl DependencyPattern for StateNationalPattern {
         let is_state = |t: &str| t.contains("win") && (t.contains("pennsylvania") || t.contains("georgia") || t.contains("arizona"));
         let is_national = |t: &str| t.contains("win") && (t.contains("election") || t.contains("presidency"));
         
+        // A swing state is a proxy for the national result, not a guarantee of it
         if is_state(&t1) && is_national(&t2) {
-             return Some(Dependency { pattern: PatternType::SubsetImplication, direction: Direction::C1ImpliesC2 });
+             return Some(Dependency {
+                 pattern: PatternType::SubsetImplication,
+                 direction: Direction::C1ImpliesC2,
+                 confidence: 0.3,
+                 explanation: "swing state win vs national win".to_string(),
+             });
         }
         None
     }
@@ -81,6 +481,8 @@ impl DependencyPattern for StateNationalPattern {
 
 struct BalanceOfPowerPattern;
 impl DependencyPattern for BalanceOfPowerPattern {
+    fn name(&self) -> &'static str { "balance_of_power" }
+
     fn matches(&self, m1: &Market, _c1: &Condition, m2: &Market, _c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
         let t1 = m1.title.to_lowercase();
         let t2 = m2.title.to_lowercase();
@@ -89,7 +491,12 @@ impl DependencyPattern for BalanceOfPowerPattern {
         let is_senate = t2.contains("senate");
         
         if is_pres && is_senate {
-             return Some(Dependency { pattern: PatternType::SubsetImplication, direction: Direction::C1ImpliesC2 });
+             return Some(Dependency {
+                 pattern: PatternType::SubsetImplication,
+                 direction: Direction::C1ImpliesC2,
+                 confidence: 0.2,
+                 explanation: "presidency vs senate control".to_string(),
+             });
         }
         None
     }
@@ -97,113 +504,198 @@ impl DependencyPattern for BalanceOfPowerPattern {
 
 struct NumericRangePattern;
 impl DependencyPattern for NumericRangePattern {
+    fn name(&self) -> &'static str { "numeric_range" }
+
     fn matches(&self, _m1: &Market, c1: &Condition, _m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
-        let r1 = parse_range(&c1.name)?;
-        let r2 = parse_range(&c2.name)?;
+        let (&OutcomeKind::Range { low: l1, high: h1 }, &OutcomeKind::Range { low: l2, high: h2 }) = (&c1.kind, &c2.kind) else { return None };
+
+        let direction = if l1 >= l2 && h1 <= h2 && (l1 > l2 || h1 < h2) {
+            Direction::C1ImpliesC2
+        } else if l2 >= l1 && h2 <= h1 && (l2 > l1 || h2 < h1) {
+            Direction::C2ImpliesC1
+        } else {
+            return None;
+        };
+        Some(Dependency {
+            pattern: PatternType::NumericRange,
+            direction,
+            confidence: 0.7,
+            explanation: format!("'{}' vs '{}'", c1.name, c2.name),
+        })
+    }
+}
+
+lazy_static! {
+    static ref BUILTIN_PATTERNS: PatternRegistry = PatternRegistry::default();
+}
+
+/// The dependency patterns tried, in order, for each condition pair, plus the confidence a
+/// match needs to count. Built once and shared; the first enabled pattern to match wins.
+pub struct PatternRegistry {
+    patterns: Vec<Box<dyn DependencyPattern>>,
+    disabled: HashSet<&'static str>,
+    min_confidence: f64,
+    /// How far apart two markets' expiries may be for their titles to be compared.
+    expiry_tolerance: Duration,
+    /// How titles are compared.
+    similarity: SimilarityBackend,
+}
 
-        if r1.0 >= r2.0 && r1.1 <= r2.1 && (r1.0 > r2.0 || r1.1 < r2.1) {
-            return Some(Dependency { pattern: PatternType::NumericRange, direction: Direction::C1ImpliesC2 });
-        } else if r2.0 >= r1.0 && r2.1 <= r1.1 && (r2.0 > r1.0 || r2.1 < r1.1) {
-            return Some(Dependency { pattern: PatternType::NumericRange, direction: Direction::C2ImpliesC1 });
+impl Default for PatternRegistry {
+    fn default() -> Self {
+        Self {
+            patterns: vec![
+                Box::new(SportsPattern),
+                Box::new(WinnerMarginPattern),
+                Box::new(ComplementPattern),
+                Box::new(DeadlinePattern),
+                Box::new(ThresholdPattern),
+                Box::new(SubsetImplicationPattern),
+                Box::new(NumericRangePattern),
+                Box::new(StateNationalPattern),
+                Box::new(BalanceOfPowerPattern),
+            ],
+            disabled: HashSet::new(),
+            min_confidence: 0.5,
+            expiry_tolerance: Duration::hours(24),
+            similarity: SimilarityBackend::default(),
         }
-        None
     }
 }
 
-fn parse_range(name: &str) -> Option<(Decimal, Decimal)> {
-    if let Some(caps) = RE_RANGE.captures(name) {
-        let start = caps.get(1).unwrap().as_str().parse::<Decimal>().ok()?;
-        let end = caps.get(2).unwrap().as_str().parse::<Decimal>().ok()?;
-        return Some((start, end));
+impl std::fmt::Debug for PatternRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PatternRegistry")
+            .field("enabled", &self.enabled().collect::<Vec<_>>())
+            .field("min_confidence", &self.min_confidence)
+            .field("expiry_tolerance", &self.expiry_tolerance)
+            .field("similarity", &self.similarity)
+            .finish()
     }
-    if let Some(caps) = RE_GREATER_THAN.captures(name) {
-        let val = caps.get(1).unwrap().as_str().parse::<Decimal>().ok()?;
-        return Some((val, Decimal::from(1_000_000)));
+}
+
+impl PatternRegistry {
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
     }
-    if let Some(caps) = RE_LESS_THAN.captures(name) {
-        let val = caps.get(1).unwrap().as_str().parse::<Decimal>().ok()?;
-        return Some((Decimal::from(0), val));
+
+    pub fn with_expiry_tolerance(mut self, expiry_tolerance: Duration) -> Self {
+        self.expiry_tolerance = expiry_tolerance;
+        self
     }
-    None
-}
 
-pub fn analyze_dependency(m1: &Market, c1: &Condition, m2: &Market, c2: &Condition) -> Option<Dependency> {
-    if m1.id == m2.id { return None; }
-    let entities1 = extract_entities(&m1.title);
-    let entities2 = extract_entities(&m2.title);
-    let shared: HashSet<_> = entities1.intersection(&entities2).cloned().collect();
-    if shared.is_empty() && !m1.title.contains(&m2.title) && !m2.title.contains(&m1.title) {
-        return None;
+    pub fn expiry_tolerance(&self) -> Duration {
+        self.expiry_tolerance
     }
 
-    let patterns: Vec<Box<dyn DependencyPattern>> = vec![
-        Box::new(WinnerMarginPattern),
-        Box::new(SubsetImplicationPattern),
-        Box::new(NumericRangePattern),
-        Box::new(StateNationalPattern),
-        Box::new(BalanceOfPowerPattern),
-    ];
+    pub fn with_similarity(mut self, similarity: SimilarityBackend) -> Self {
+        self.similarity = similarity;
+        self
+    }
 
-    for pattern in patterns {
-        if let Some(dep) = pattern.matches(m1, c1, m2, c2, &shared) {
-            return Some(dep);
-        }
+    pub fn similarity(&self) -> SimilarityBackend {
+        self.similarity
     }
-    None
-}
 
-fn extract_entities(title: &str) -> HashSet<Entity> {
-    let mut entities = HashSet::new();
-    let keywords = [
-        "trump", "biden", "harris", "walz", "vance", 
-        "bitcoin", "eth", "solana", "fed", "inflation",
-        "lakers", "warriors", "celtics", "knicks",
-        "iran", "israel", "ukraine", "russia"
-    ];
-    
-    // Split by underscores (as normalized) and remove punctuation
-    for word in title.split('_') {
-        let clean_word = word.trim_matches(|c: char| !c.is_alphanumeric());
-        if keywords.contains(&clean_word) {
-            entities.insert(Entity::Candidate(clean_word.to_string()));
+    /// Adds a pattern after the built-in ones. A pattern with an existing name replaces it.
+    pub fn register(&mut self, pattern: Box<dyn DependencyPattern>) {
+        match self.patterns.iter().position(|p| p.name() == pattern.name()) {
+            Some(i) => self.patterns[i] = pattern,
+            None => self.patterns.push(pattern),
         }
     }
-    entities
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), EngineError> {
+        let name = self.patterns.iter().map(|p| p.name()).find(|n| *n == name)
+            .ok_or_else(|| EngineError::UnknownPattern(name.to_string()))?;
+        if enabled { self.disabled.remove(name); } else { self.disabled.insert(name); }
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.patterns.iter().map(|p| p.name())
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.names().filter(|n| !self.disabled.contains(n))
+    }
+
+    /// First dependency between `c1` and `c2` found by an enabled pattern with enough confidence.
+    pub fn analyze(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition) -> Option<Dependency> {
+        let shared = shared_entities(m1, m2);
+        if !worth_comparing(m1, m2, &shared) { return None; }
+        self.patterns.iter()
+            .filter(|p| !self.disabled.contains(p.name()))
+            .filter_map(|p| p.matches(m1, c1, m2, c2, &shared))
+            .find(|dep| dep.confidence >= self.min_confidence)
+    }
+}
+
+fn shared_entities(m1: &Market, m2: &Market) -> HashSet<Entity> {
+    extract_entities(&m1.title).intersection(&extract_entities(&m2.title)).cloned().collect()
+}
+
+fn worth_comparing(m1: &Market, m2: &Market, shared: &HashSet<Entity>) -> bool {
+    if m1.id == m2.id { return false; }
+    // A shared number or year alone says nothing about whether two markets are related
+    let shares_named = shared.iter().any(|e| !matches!(e, Entity::NumericalValue(_)));
+    shares_named || m1.title.contains(&m2.title) || m2.title.contains(&m1.title)
+        || is_deadline_ladder(m1, m2) || is_threshold_ladder(m1, m2)
+}
+
+/// Dependency found by the built-in patterns, whatever its confidence.
+pub fn analyze_dependency(m1: &Market, c1: &Condition, m2: &Market, c2: &Condition) -> Option<Dependency> {
+    let shared = shared_entities(m1, m2);
+    if !worth_comparing(m1, m2, &shared) { return None; }
+    BUILTIN_PATTERNS.patterns.iter().find_map(|p| p.matches(m1, c1, m2, c2, &shared))
 }
 
 pub fn find_combinatorial_opportunities(
     markets: &[Market],
     dependency_graph: &DependencyGraph,
+    patterns: &PatternRegistry,
 ) -> Vec<CombinatorialOpportunity> {
     let mut opportunities = Vec::new();
     let market_map: HashMap<String, &Market> = markets.iter().map(|m| (m.id.clone(), m)).collect();
 
     for (market_id_1, market_id_2) in &dependency_graph.related_markets {
         if let (Some(m1), Some(m2)) = (market_map.get(market_id_1), market_map.get(market_id_2)) {
-            opportunities.extend(check_combinatorial_pair(m1, m2));
+            opportunities.extend(check_combinatorial_pair(m1, m2, patterns));
         }
     }
     opportunities
 }
 
 /// Efficiently checks just two markets for combinatorial arbitrage
-pub fn check_combinatorial_pair(m1: &Market, m2: &Market) -> Vec<CombinatorialOpportunity> {
+pub fn check_combinatorial_pair(m1: &Market, m2: &Market, patterns: &PatternRegistry) -> Vec<CombinatorialOpportunity> {
     let mut opportunities = Vec::new();
     for c1 in &m1.conditions {
         for c2 in &m2.conditions {
-            if let Some(dep) = analyze_dependency(m1, c1, m2, c2) {
-                let (implying_c, implied_c) = match dep.direction {
-                    Direction::C1ImpliesC2 => (c1, c2),
-                    Direction::C2ImpliesC1 => (c2, c1),
+            if let Some(dep) = patterns.analyze(m1, c1, m2, c2) {
+                let ((implying_m, implying_c), (implied_m, implied_c)) = match dep.direction {
+                    Direction::C1ImpliesC2 => ((m1, c1), (m2, c2)),
+                    Direction::C2ImpliesC1 => ((m2, c2), (m1, c1)),
                 };
 
                 if implying_c.price > implied_c.price {
+                    let complement = match implying_m.conditions.as_slice() {
+                        [a, b] => Some(if a.asset_id == implying_c.asset_id { &b.asset_id } else { &a.asset_id }.clone()),
+                        _ => None,
+                    };
                     opportunities.push(CombinatorialOpportunity {
                         market_id_1: m1.id.clone(),
                         market_id_2: m2.id.clone(),
                         condition_name_1: implying_c.name.clone(),
                         condition_name_2: implied_c.name.clone(),
+                        implying_asset_id: implying_c.asset_id.clone(),
+                        implied_asset_id: implied_c.asset_id.clone(),
+                        implying_complement_asset_id: complement,
                         profit: implying_c.price - implied_c.price,
+                        implying_fees: implying_m.fees,
+                        implied_fees: implied_m.fees,
+                        confidence: dep.confidence,
+                        explanation: dep.explanation,
                     });
                 }
             }
@@ -212,59 +704,514 @@ pub fn check_combinatorial_pair(m1: &Market, m2: &Market) -> Vec<CombinatorialOp
     opportunities
 }
 
-pub fn check_rebalancing(market: &Market) -> Option<RebalancingOpportunity> {
-    let sum_prices: Decimal = market.conditions.iter().map(|c| c.price).sum();
-    let fee_threshold = dec!(0.02);
+/// Every condition-level implication between two related markets, as (implying, implied) tokens.
+pub(crate) fn pair_implications(m1: &Market, m2: &Market, patterns: &PatternRegistry) -> Vec<(String, String)> {
+    let mut edges = Vec::new();
+    for c1 in &m1.conditions {
+        for c2 in &m2.conditions {
+            if c1.asset_id.is_empty() || c2.asset_id.is_empty() { continue; }
+            if let Some(dep) = patterns.analyze(m1, c1, m2, c2) {
+                match dep.direction {
+                    Direction::C1ImpliesC2 => edges.push((c1.asset_id.clone(), c2.asset_id.clone())),
+                    Direction::C2ImpliesC1 => edges.push((c2.asset_id.clone(), c1.asset_id.clone())),
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Links every related pair of markets. Only pairs from the `CandidateIndex` are compared,
+/// in parallel; later additions go through `DependencyGraph::add_market`.
+pub fn build_dependency_graph(markets: &[Market], similarity: &TitleSimilarity, similarity_cutoff: f64, patterns: &PatternRegistry) -> DependencyGraph {
+    let related: Vec<_> = CandidateIndex::build(markets, patterns.expiry_tolerance()).pairs(markets, 0)
+        .into_par_iter()
+        .filter(|&(i, j)| are_markets_related(&markets[i], &markets[j], similarity, similarity_cutoff, patterns.expiry_tolerance()))
+        .map(|(i, j)| (i, j, pair_implications(&markets[i], &markets[j], patterns)))
+        .collect();
+
+    let mut graph = DependencyGraph::default();
+    for (i, j, edges) in related {
+        graph.related_markets.push((markets[i].id.clone(), markets[j].id.clone()));
+        for (from, to) in &edges {
+            graph.add_implication(from, to);
+        }
+    }
+    graph
+}
+
+/// Searches implication chains of up to `max_depth` edges that pass through `asset_id`.
+/// A chain A ⇒ ... ⇒ Z is violated when P(A) > P(Z); a cycle means every member is
+/// equivalent, so any price spread inside it is an arb. Direct (two-leg) violations are
+/// left to `check_combinatorial_pair`.
+pub fn find_multi_leg_opportunities(
+    graph: &DependencyGraph,
+    markets: &MarketRegistry,
+    asset_id: &str,
+    max_depth: usize,
+    live: &LivePrices,
+) -> Vec<MultiLegOpportunity> {
+    let leg_for = |asset: &str| -> Option<Leg> {
+        let id = markets.resolve(asset)?;
+        let market = markets.get(id.market)?;
+        let condition = market.conditions.get(id.index)?;
+        Some(Leg { market_id: market.id.clone(), condition_name: condition.name.clone(), asset_id: asset.to_string(), price: live.price_of(condition) })
+    };
+
+    // Every chain through `asset_id` starts at one of its ancestors (or itself)
+    let mut starts: Vec<String> = vec![asset_id.to_string()];
+    let mut frontier = vec![asset_id.to_string()];
+    for _ in 0..max_depth.saturating_sub(1) {
+        let mut next = Vec::new();
+        for node in &frontier {
+            for parent in graph.implied_by.get(node).into_iter().flatten() {
+                if !starts.contains(parent) {
+                    starts.push(parent.clone());
+                    next.push(parent.clone());
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    let mut opportunities = Vec::new();
+    let mut seen_cycles: HashSet<Vec<String>> = HashSet::new();
+    for start in &starts {
+        let mut path = vec![start.clone()];
+        walk_chains(graph, asset_id, max_depth, &mut path, &leg_for, &mut seen_cycles, &mut opportunities);
+    }
+    opportunities
+}
+
+fn walk_chains(
+    graph: &DependencyGraph,
+    through: &str,
+    max_depth: usize,
+    path: &mut Vec<String>,
+    leg_for: &dyn Fn(&str) -> Option<Leg>,
+    seen_cycles: &mut HashSet<Vec<String>>,
+    out: &mut Vec<MultiLegOpportunity>,
+) {
+    if path.len() > max_depth { return; }
+    let current = path.last().cloned().unwrap_or_default();
 
-    if sum_prices < (dec!(1) - fee_threshold) {
-        Some(RebalancingOpportunity {
-            market_id: market.id.clone(),
-            profit: dec!(1) - sum_prices,
-            opportunity_type: "Long".to_string(),
-        })
-    } else if sum_prices > (dec!(1) + fee_threshold) {
-        Some(RebalancingOpportunity {
-            market_id: market.id.clone(),
-            profit: sum_prices - dec!(1),
-            opportunity_type: "Short".to_string(),
-        })
+    for next in graph.implications.get(&current).into_iter().flatten() {
+        if next == &path[0] {
+            // Closed a cycle: all members must trade at the same price
+            if path.len() >= 2 && path.iter().any(|a| a == through) {
+                let mut key = path.clone();
+                key.sort();
+                if seen_cycles.insert(key) {
+                    if let Some(legs) = path.iter().map(|a| leg_for(a)).collect::<Option<Vec<_>>>() {
+                        let max = legs.iter().map(|l| l.price).max().unwrap_or_default();
+                        let min = legs.iter().map(|l| l.price).min().unwrap_or_default();
+                        if max > min {
+                            out.push(MultiLegOpportunity { legs, is_cycle: true, profit: max - min });
+                        }
+                    }
+                }
+            }
+            continue;
+        }
+        if path.contains(next) { continue; }
+
+        path.push(next.clone());
+        if path.len() >= 3 && path.iter().any(|a| a == through) {
+            if let (Some(first), Some(last)) = (leg_for(&path[0]), leg_for(next)) {
+                if first.price > last.price {
+                    if let Some(legs) = path.iter().map(|a| leg_for(a)).collect::<Option<Vec<_>>>() {
+                        out.push(MultiLegOpportunity { legs, is_cycle: false, profit: first.price - last.price });
+                    }
+                }
+            }
+        }
+        walk_chains(graph, through, max_depth, path, leg_for, seen_cycles, out);
+        path.pop();
+    }
+}
+
+/// Flags a market whose complete set can be bought below $1 or sold above it by more than
+/// `fees.min_edge` after taker fees, priced on the books: asks for a Long (buy every outcome
+/// and merge), bids for a Short (split and sell every outcome). The one merge or split
+/// transaction's gas is spread over the sets. Every outcome needs a book.
+pub fn check_rebalancing(market: &Market, books: &HashMap<String, OrderBook>, fees: &FeeModel) -> Option<RebalancingOpportunity> {
+    if market.conditions.len() < 2 { return None; }
+    let ladders = |side| market.conditions.iter()
+        .map(|c| books.get(&c.asset_id).map(|book| book.levels(side)))
+        .collect::<Option<Vec<_>>>();
+    let opportunity = |opportunity_type: &str, size: Decimal, profit: Decimal, limit_prices: Vec<Decimal>| RebalancingOpportunity {
+        market_id: market.id.clone(),
+        profit,
+        opportunity_type: opportunity_type.to_string(),
+        size,
+        limit_prices,
+    };
+
+    // Per-set cost of buying, or proceeds of selling, every leg at `prices`
+    let fee = |prices: &[Decimal]| prices.iter().map(|&p| fees.taker_fee(market, p, Decimal::ONE)).sum::<Decimal>();
+    let buy = |prices: &[Decimal]| prices.iter().sum::<Decimal>() + fee(prices);
+    let sell = |prices: &[Decimal]| prices.iter().sum::<Decimal>() - fee(prices);
+
+    if let Some((steps, limits)) = ladders(BookSide::Ask).and_then(|l| walk_complete_sets(&l, buy, |c| c < dec!(1) - fees.min_edge)) {
+        let (sets, cost) = steps[steps.len() - 1];
+        let profit = dec!(1) - cost / sets - fees.amortized_gas(1, sets);
+        return (profit > Decimal::ZERO).then(|| opportunity("Long", sets, profit, limits));
+    }
+    let (steps, limits) = ladders(BookSide::Bid).and_then(|l| walk_complete_sets(&l, sell, |p| p > dec!(1) + fees.min_edge))?;
+    let (sets, proceeds) = steps[steps.len() - 1];
+    let profit = proceeds / sets - dec!(1) - fees.amortized_gas(1, sets);
+    (profit > Decimal::ZERO).then(|| opportunity("Short", sets, profit, limits))
+}
+
+/// Running (sets, value) of a walk over complete sets, one entry per step.
+type SetSteps = Vec<(Decimal, Decimal)>;
+
+/// Walks every outcome's ladder (top of book first) in step, taking complete sets while the
+/// `per_set` value of the current levels stays `profitable`. Returns the running sets and their
+/// total value after each step, one step per level boundary, and the last level used on each leg.
+fn walk_complete_sets(
+    ladders: &[Vec<PriceLevel>],
+    per_set: impl Fn(&[Decimal]) -> Decimal,
+    profitable: impl Fn(Decimal) -> bool,
+) -> Option<(SetSteps, Vec<Decimal>)> {
+    let mut depth = vec![0; ladders.len()];
+    let mut remaining: Vec<Decimal> = ladders.iter().map(|l| l.first().map_or(Decimal::ZERO, |level| level.size)).collect();
+    let (mut sets, mut total) = (Decimal::ZERO, Decimal::ZERO);
+    let (mut steps, mut limits) = (Vec::new(), Vec::new());
+    while let Some(levels) = ladders.iter().zip(&depth).map(|(l, &d)| l.get(d)).collect::<Option<Vec<_>>>() {
+        let prices: Vec<Decimal> = levels.iter().map(|level| level.price).collect();
+        let marginal = per_set(&prices);
+        if !profitable(marginal) { break; }
+        let take = remaining.iter().copied().min().unwrap_or_default();
+        sets += take;
+        total += take * marginal;
+        steps.push((sets, total));
+        limits = prices;
+        for (i, left) in remaining.iter_mut().enumerate() {
+            *left -= take;
+            if left.is_zero() {
+                depth[i] += 1;
+                *left = ladders[i].get(depth[i]).map_or(Decimal::ZERO, |level| level.size);
+            }
+        }
+    }
+    (!sets.is_zero()).then_some((steps, limits))
+}
+
+/// Groups market indices by `neg_risk_market_id`. Only baskets with at least two members are returned.
+pub fn group_neg_risk_baskets(markets: &[Market]) -> HashMap<String, Vec<usize>> {
+    let mut baskets: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, market) in markets.iter().enumerate() {
+        if let Some(neg_id) = &market.neg_risk_market_id {
+            baskets.entry(neg_id.clone()).or_default().push(idx);
+        }
+    }
+    baskets.retain(|_, members| members.len() > 1);
+    baskets
+}
+
+/// Exactly one market in a neg-risk basket resolves YES, so the YES prices across the
+/// whole basket should sum to 1. Every member must quote a YES price to be checked. When
+/// every leg has a book the basket is also sized on them, and dropped if no depth pays.
+pub fn check_neg_risk_basket(basket: &[&Market], books: &HashMap<String, OrderBook>, fees: &FeeModel) -> Option<BasketOpportunity> {
+    let neg_risk_market_id = basket.first()?.neg_risk_market_id.clone()?;
+    let mut yes_price_sum = Decimal::ZERO;
+    let mut fee = Decimal::ZERO;
+    for market in basket {
+        let yes = market.conditions.iter().find(|c| c.outcome == Some(true))?;
+        yes_price_sum += yes.price;
+        fee += fees.taker_fee(market, yes.price, Decimal::ONE);
+    }
+
+    // Profit per set once every leg's taker fee is paid
+    let (profit, opportunity_type) = if yes_price_sum + fee < dec!(1) - fees.min_edge {
+        (dec!(1) - yes_price_sum - fee, "Long")
+    } else if yes_price_sum - fee > dec!(1) + fees.min_edge {
+        (yes_price_sum - fee - dec!(1), "Short")
     } else {
-        None
+        return None;
+    };
+
+    // A Short needs every member's NO to trade
+    let long = opportunity_type == "Long";
+    let legs = basket.iter()
+        .map(|m| m.conditions.iter().find(|c| c.outcome == Some(long)).map(|c| Leg {
+            market_id: m.id.clone(),
+            condition_name: c.name.clone(),
+            asset_id: c.asset_id.clone(),
+            price: c.price,
+        }))
+        .collect::<Option<Vec<_>>>()?;
+    let mut op = BasketOpportunity {
+        neg_risk_market_id,
+        market_ids: basket.iter().map(|m| m.id.clone()).collect(),
+        yes_price_sum,
+        profit,
+        opportunity_type: opportunity_type.to_string(),
+        legs,
+        question_ids: basket.iter().map(|m| m.question_id.clone()).collect(),
+        frontier: Vec::new(),
+    };
+    if let Some((frontier, limits)) = basket_frontier(basket, books, long, fees) {
+        op.frontier = frontier;
+        let (size, profit) = op.best_size().filter(|&(_, profit)| profit > Decimal::ZERO)?;
+        op.profit = profit / size;
+        for (leg, limit) in op.legs.iter_mut().zip(limits) {
+            leg.price = limit;
+        }
     }
+    Some(op)
 }
 
-pub fn are_markets_related(m1: &Market, m2: &Market) -> bool {
-    if m1.id == m2.id || m1.end_date != m2.end_date { return false; }
+/// Walks the asks of every leg of a neg-risk basket in step and returns the (sets, expected
+/// profit) frontier, one point per level boundary while the next set still clears
+/// `fees.min_edge` after taker fees. A Long buys every YES for the $1 one of them pays; a Short
+/// buys every NO and converts the n of them into n - 1 dollars, the conversion's gas spread over
+/// the sets. The thinnest leg bounds the size. Also returns the deepest ask taken on each leg,
+/// the limit that fills the whole frontier. `None` when a leg has no book.
+pub fn basket_frontier(basket: &[&Market], books: &HashMap<String, OrderBook>, long: bool, fees: &FeeModel) -> Option<(SetSteps, Vec<Decimal>)> {
+    let legs = basket.iter()
+        .map(|m| m.conditions.iter().find(|c| c.outcome == Some(long)).map(|c| (*m, c)))
+        .collect::<Option<Vec<_>>>()?;
+    let ladders = legs.iter()
+        .map(|(_, c)| books.get(&c.asset_id).map(|book| book.levels(BookSide::Ask)))
+        .collect::<Option<Vec<_>>>()?;
+    let (payout, gas) = if long { (Decimal::ONE, Decimal::ZERO) } else { (Decimal::from(basket.len() - 1), fees.gas_cost) };
+    let cost = |prices: &[Decimal]| legs.iter().zip(prices).map(|((market, _), &p)| p + fees.taker_fee(market, p, Decimal::ONE)).sum::<Decimal>();
+    let Some((steps, limits)) = walk_complete_sets(&ladders, cost, |c| c < payout - fees.min_edge) else { return Some((Vec::new(), Vec::new())) };
+    Some((steps.into_iter().map(|(sets, cost)| (sets, sets * payout - cost - gas)).collect(), limits))
+}
+
+/// Ladder a crypto price-threshold market belongs to: its title with the threshold removed,
+/// the direction and the end date.
+pub fn threshold_ladder_key(market: &Market) -> Option<String> {
+    if TopicClassifier::classify(market) != MarketCategory::Crypto { return None; }
+    let threshold = parse_threshold(&market.title)?;
+    if threshold.stem.is_empty() { return None; }
+    Some(format!("{} {} {}", threshold.stem, if threshold.upward { "above" } else { "below" }, market.end_date.date_naive()))
+}
+
+/// Groups crypto threshold markets into ladders, loosest rung first. Only ladders with at
+/// least two rungs are returned.
+pub fn group_threshold_ladders(markets: &[Market]) -> HashMap<String, Vec<usize>> {
+    let mut ladders: HashMap<String, Vec<(Decimal, usize)>> = HashMap::new();
+    for (idx, market) in markets.iter().enumerate() {
+        if let (Some(key), Some(threshold)) = (threshold_ladder_key(market), parse_threshold(&market.title)) {
+            // Downward ladders get looser as the bar rises
+            let looseness = if threshold.upward { -threshold.value } else { threshold.value };
+            ladders.entry(key).or_default().push((looseness, idx));
+        }
+    }
+    ladders.into_iter()
+        .filter(|(_, rungs)| rungs.len() > 1)
+        .map(|(key, mut rungs)| {
+            rungs.sort_by_key(|rung| std::cmp::Reverse(rung.0));
+            (key, rungs.into_iter().map(|(_, idx)| idx).collect())
+        })
+        .collect()
+}
+
+/// YES prices must not rise as a ladder's bar tightens. Every looser/stricter pair whose
+/// YES + NO cost plus taker fees leaves more than `fees.min_edge` below the guaranteed payout
+/// of 1 is an inversion; pairs are taken widest first and each rung is traded at most once.
+pub fn check_threshold_ladder(ladder: &[&Market], fees: &FeeModel) -> Option<LadderOpportunity> {
+    let key = threshold_ladder_key(ladder.first()?)?;
+    let mut quotes = Vec::with_capacity(ladder.len());
+    for market in ladder {
+        let yes = market.conditions.iter().find(|c| c.outcome == Some(true))?;
+        let no = market.conditions.iter().find(|c| c.outcome == Some(false))?;
+        quotes.push((market, yes, no));
+    }
+
+    let mut inversions = Vec::new();
+    for looser in 0..quotes.len() {
+        for stricter in (looser + 1)..quotes.len() {
+            let (yes, no) = (quotes[looser].1.price, quotes[stricter].2.price);
+            // An unquoted side is not a price
+            if yes.is_zero() || no.is_zero() { continue; }
+            let fee = fees.taker_fee(quotes[looser].0, yes, Decimal::ONE) + fees.taker_fee(quotes[stricter].0, no, Decimal::ONE);
+            let edge = dec!(1) - yes - no - fee;
+            if edge > fees.min_edge {
+                inversions.push((edge, looser, stricter));
+            }
+        }
+    }
+    inversions.sort_by_key(|inversion| std::cmp::Reverse(inversion.0));
+
+    let leg = |market: &Market, condition: &Condition| Leg {
+        market_id: market.id.clone(),
+        condition_name: condition.name.clone(),
+        asset_id: condition.asset_id.clone(),
+        price: condition.price,
+    };
+    let mut used = HashSet::new();
+    let mut legs = Vec::new();
+    let mut profit = Decimal::ZERO;
+    for (edge, looser, stricter) in inversions {
+        if used.contains(&looser) || used.contains(&stricter) { continue; }
+        used.extend([looser, stricter]);
+        legs.push(leg(quotes[looser].0, quotes[looser].1));
+        legs.push(leg(quotes[stricter].0, quotes[stricter].2));
+        profit += edge;
+    }
+    (!legs.is_empty()).then_some(LadderOpportunity { ladder: key, legs, profit })
+}
+
+/// Whether two markets stop trading within `tolerance` of each other.
+pub fn expires_within(m1: &Market, m2: &Market, tolerance: Duration) -> bool {
+    (m1.end_date - m2.end_date).abs() <= tolerance
+}
+
+/// Markets worth checking for dependencies: one event, one sports matchup, a deadline ladder,
+/// or a shared tag with titles scoring above `similarity_cutoff` and expiries `expiry_tolerance`
+/// apart at most.
+pub fn are_markets_related(m1: &Market, m2: &Market, similarity: &TitleSimilarity, similarity_cutoff: f64, expiry_tolerance: Duration) -> bool {
+    if m1.id == m2.id { return false; }
+    // Markets of one event are related by construction
+    if m1.event_id.is_some() && m1.event_id == m2.event_id { return true; }
     let tags1: HashSet<_> = m1.tags.iter().collect();
     let tags2: HashSet<_> = m2.tags.iter().collect();
     if tags1.is_disjoint(&tags2) { return false; }
-    normalized_damerau_levenshtein(&m1.title, &m2.title) > 0.6
+    // Moneyline, spread and series markets on one matchup, whatever their end dates
+    if let (Some(a), Some(b)) = (sports_matchup(m1), sports_matchup(m2)) {
+        if a == b { return true; }
+    }
+    // Deadline ladders end on different dates by construction
+    if !expires_within(m1, m2, expiry_tolerance) { return is_deadline_ladder(m1, m2); }
+    similarity.score(&m1.title, &m2.title) > similarity_cutoff
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::shared_types::{Market, Condition};
+    use crate::fees::FeeSchedule;
+    use crate::shared_types::{Condition, Market, Opportunity};
     use rust_decimal_macros::dec;
-    use chrono::NaiveDate;
+    use chrono::{NaiveDate, TimeZone, Utc};
 
     #[test]
     fn test_rebalancing_detection() {
         let market = Market {
             id: "test".to_string(),
             title: "Test Market".to_string(),
-            end_date: NaiveDate::from_ymd_opt(2024, 11, 5).unwrap(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
             conditions: vec![
-                Condition { name: "Yes".to_string(), price: dec!(0.4), outcome: Some(true), asset_id: "1".to_string() },
-                Condition { name: "No".to_string(), price: dec!(0.4), outcome: Some(false), asset_id: "2".to_string() },
+                Condition { name: "Yes".to_string(), price: dec!(0.4), outcome: Some(true), asset_id: "1".to_string(), kind: OutcomeKind::Other, last_updated: None },
+                Condition { name: "No".to_string(), price: dec!(0.4), outcome: Some(false), asset_id: "2".to_string(), kind: OutcomeKind::Other, last_updated: None },
             ],
-            neg_risk_market_id: None,
-            tags: vec![],
+            ..Market::default()
         };
         
-        let opp = check_rebalancing(&market).unwrap();
-        assert_eq!(opp.profit, dec!(0.2));
+        let book = |bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]| {
+            let levels = |l: &[(Decimal, Decimal)]| l.iter().map(|&(price, size)| PriceLevel { price, size }).collect::<Vec<_>>();
+            let mut book = OrderBook::default();
+            book.apply_snapshot(&levels(bids), &levels(asks));
+            book
+        };
+        let mut books = HashMap::from([("1".to_string(), book(&[(dec!(0.38), dec!(50))], &[(dec!(0.40), dec!(100)), (dec!(0.45), dec!(200))]))]);
+        // Last prices sum to 0.8, but without a book for every leg nothing is executable
+        assert!(check_rebalancing(&market, &books, &FeeModel::default()).is_none());
+
+        // Sets are taken while the summed asks stay below 0.98: 100 at 0.82, then 50 at 0.87
+        books.insert("2".to_string(), book(&[(dec!(0.39), dec!(80))], &[(dec!(0.42), dec!(150)), (dec!(0.60), dec!(500))]));
+        let opp = check_rebalancing(&market, &books, &FeeModel::default()).unwrap();
         assert_eq!(opp.opportunity_type, "Long");
+        assert_eq!((opp.size, opp.limit_prices.clone()), (dec!(150), vec![dec!(0.45), dec!(0.42)]));
+        assert_eq!((opp.profit * opp.size).round_dp(6), dec!(24.5));
+
+        // A 20% taker rate eats the edge: 0.82 + 0.2 * 0.82 > 0.98
+        let mut taxed = market.clone();
+        taxed.fees = Some(FeeSchedule { maker_bps: Decimal::ZERO, taker_bps: dec!(2000) });
+        assert!(check_rebalancing(&taxed, &books, &FeeModel::default()).is_none());
+
+        // Bids summing above 1.02 are sold as split sets: 10 at 1.07, then 20 at 1.04
+        books.insert("1".to_string(), book(&[(dec!(0.55), dec!(10)), (dec!(0.52), dec!(20))], &[(dec!(0.60), dec!(10))]));
+        books.insert("2".to_string(), book(&[(dec!(0.52), dec!(40))], &[(dec!(0.60), dec!(10))]));
+        let opp = check_rebalancing(&market, &books, &FeeModel::default()).unwrap();
+        assert_eq!((opp.opportunity_type.as_str(), opp.size, opp.profit), ("Short", dec!(30), dec!(0.05)));
+        assert_eq!(opp.limit_prices, vec![dec!(0.52), dec!(0.52)]);
+    }
+
+    #[test]
+    fn test_neg_risk_basket_short() {
+        let member = |id: &str, yes: Decimal| Market {
+            id: id.to_string(),
+            title: id.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![
+                Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: format!("{}-y", id), kind: OutcomeKind::Other, last_updated: None },
+                Condition { name: "No".to_string(), price: dec!(1) - yes, outcome: Some(false), asset_id: format!("{}-n", id), kind: OutcomeKind::Other, last_updated: None },
+            ],
+            neg_risk_market_id: Some("0xbasket".to_string()),
+            ..Market::default()
+        };
+        let markets = vec![member("a", dec!(0.5)), member("b", dec!(0.4)), member("c", dec!(0.2))];
+        let baskets = group_neg_risk_baskets(&markets);
+        let basket: Vec<&Market> = baskets["0xbasket"].iter().map(|&i| &markets[i]).collect();
+
+        let opp = check_neg_risk_basket(&basket, &HashMap::new(), &FeeModel::default()).unwrap();
+        assert_eq!(opp.yes_price_sum, dec!(1.1));
+        assert_eq!(opp.opportunity_type, "Short");
+        assert_eq!(opp.market_ids.len(), 3);
+        assert_eq!(opp.legs.iter().map(|l| l.price).collect::<Vec<_>>(), vec![dec!(0.5), dec!(0.6), dec!(0.8)]);
+        assert!(opp.frontier.is_empty());
+
+        // NO asks of 0.5/0.6/0.8 cost 1.9 a set against the 2 the conversion returns; the
+        // thinnest leg stops the walk at 30 sets, and 0.9 NO at the next level no longer pays
+        let book = |asks: &[(Decimal, Decimal)]| {
+            let mut book = OrderBook::default();
+            book.apply_snapshot(&[], &asks.iter().map(|&(price, size)| PriceLevel { price, size }).collect::<Vec<_>>());
+            book
+        };
+        let books: HashMap<String, OrderBook> = [
+            ("a-n", book(&[(dec!(0.5), dec!(100))])),
+            ("b-n", book(&[(dec!(0.6), dec!(10)), (dec!(0.64), dec!(20)), (dec!(0.9), dec!(50))])),
+            ("c-n", book(&[(dec!(0.8), dec!(100))])),
+        ].into_iter().map(|(id, book)| (id.to_string(), book)).collect();
+        let fees = FeeModel { gas_cost: dec!(0.1), min_edge: dec!(0.05), ..FeeModel::default() };
+        let (frontier, limits) = basket_frontier(&basket, &books, false, &fees).unwrap();
+        assert_eq!(frontier, vec![(dec!(10), dec!(0.9)), (dec!(30), dec!(2.1))]);
+        assert_eq!(limits, vec![dec!(0.5), dec!(0.64), dec!(0.8)]);
+        let opp = check_neg_risk_basket(&basket, &books, &fees).unwrap();
+        assert_eq!(opp.legs.iter().map(|l| (l.asset_id.as_str(), l.price)).collect::<Vec<_>>(), vec![("a-n", dec!(0.5)), ("b-n", dec!(0.64)), ("c-n", dec!(0.8))]);
+        assert_eq!((opp.best_size(), opp.profit), (Some((dec!(30), dec!(2.1))), dec!(0.07)));
+        assert_eq!(opp.expected_profit(dec!(20)), dec!(1.5));
+        assert_eq!(opp.expected_profit(dec!(100)), dec!(2.1));
+
+        // Sized in USDC like every other kind: a set costs 1.94 at the legs' limits
+        let opp = Opportunity::neg_risk_basket(&basket, opp, &fees);
+        assert_eq!(opp.max_amount(), Some(dec!(58.2)));
+        assert_eq!(opp.expected_profit(dec!(38.8)), dec!(1.5));
+
+        // A leg without depth that pays sinks the basket
+        let thin: HashMap<String, OrderBook> = books.into_iter().map(|(id, b)| if id == "b-n" { (id, book(&[(dec!(0.9), dec!(50))])) } else { (id, b) }).collect();
+        assert!(check_neg_risk_basket(&basket, &thin, &fees).is_none());
+    }
+
+    #[test]
+    fn test_three_leg_chain_violation() {
+        let market = |id: &str, price: Decimal| Market {
+            id: id.to_string(),
+            title: id.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price, outcome: Some(true), asset_id: id.to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            ..Market::default()
+        };
+        // a ⇒ b ⇒ c, priced so that only the end-to-end chain is violated
+        let markets = MarketRegistry::new(vec![market("a", dec!(0.5)), market("b", dec!(0.55)), market("c", dec!(0.45))]);
+        let mut graph = DependencyGraph::default();
+        graph.add_implication("a", "b");
+        graph.add_implication("b", "c");
+
+        let ops = find_multi_leg_opportunities(&graph, &markets, "b", 4, &LivePrices::default());
+        assert_eq!(ops.len(), 1);
+        assert!(!ops[0].is_cycle);
+        assert_eq!(ops[0].legs.iter().map(|l| l.asset_id.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        assert_eq!(ops[0].profit, dec!(0.05));
+
+        graph.add_implication("c", "a");
+        let ops = find_multi_leg_opportunities(&graph, &markets, "a", 4, &LivePrices::default());
+        assert!(ops.iter().any(|o| o.is_cycle && o.profit == dec!(0.10)));
     }
 
     #[test]
@@ -272,18 +1219,16 @@ mod tests {
         let m1 = Market {
             id: "m1".to_string(),
             title: "trump_margin".to_string(),
-            end_date: NaiveDate::from_ymd_opt(2024, 11, 5).unwrap(),
-            conditions: vec![Condition { name: "5-10%".to_string(), price: dec!(0.6), outcome: Some(true), asset_id: "1".to_string() }],
-            neg_risk_market_id: None,
-            tags: vec![],
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "5-10%".to_string(), price: dec!(0.6), outcome: Some(true), asset_id: "1".to_string(), kind: OutcomeKind::Range { low: dec!(5), high: dec!(10) }, last_updated: None }],
+            ..Market::default()
         };
         let m2 = Market {
             id: "m2".to_string(),
             title: "trump_margin".to_string(),
-            end_date: NaiveDate::from_ymd_opt(2024, 11, 5).unwrap(),
-            conditions: vec![Condition { name: "0-20%".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: "2".to_string() }],
-            neg_risk_market_id: None,
-            tags: vec![],
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "0-20%".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: "2".to_string(), kind: OutcomeKind::Range { low: dec!(0), high: dec!(20) }, last_updated: None }],
+            ..Market::default()
         };
         
         let dep = analyze_dependency(&m1, &m1.conditions[0], &m2, &m2.conditions[0]).unwrap();
@@ -300,14 +1245,13 @@ mod tests {
 
                 title: "trump_win_presidential_election".to_string(),
 
-                end_date: NaiveDate::from_ymd_opt(2024, 11, 5).unwrap(),
+                end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
 
-                conditions: vec![Condition { name: "Donald Trump".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: "1".to_string() }],
+                conditions: vec![Condition { name: "Donald Trump".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: "1".to_string(), kind: OutcomeKind::Other, last_updated: None }],
 
-                neg_risk_market_id: None,
 
-                tags: vec![],
 
+                ..Market::default()
             };
 
             let m2 = Market {
@@ -316,14 +1260,13 @@ mod tests {
 
                 title: "trump_margin_victory".to_string(),
 
-                end_date: NaiveDate::from_ymd_opt(2024, 11, 5).unwrap(),
+                end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
 
-                conditions: vec![Condition { name: "5-10%".to_string(), price: dec!(0.6), outcome: Some(true), asset_id: "2".to_string() }],
+                conditions: vec![Condition { name: "5-10%".to_string(), price: dec!(0.6), outcome: Some(true), asset_id: "2".to_string(), kind: OutcomeKind::Range { low: dec!(5), high: dec!(10) }, last_updated: None }],
 
-                neg_risk_market_id: None,
 
-                tags: vec![],
 
+                ..Market::default()
             };
 
             
@@ -334,6 +1277,220 @@ mod tests {
 
         }
 
+    #[test]
+    fn test_complement_across_markets() {
+        let market = |id: &str, title: &str, yes: Decimal| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![
+                Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: format!("{}-y", id), kind: OutcomeKind::Other, last_updated: None },
+                Condition { name: "No".to_string(), price: dec!(1) - yes, outcome: Some(false), asset_id: format!("{}-n", id), kind: OutcomeKind::Other, last_updated: None },
+            ],
+            ..Market::default()
+        };
+        let trump = market("a", "will_trump_win_the_presidential_election", dec!(0.6));
+        let harris = market("b", "will_harris_win_the_presidential_election", dec!(0.5));
+
+        // YES_a + YES_b = 1.1: either YES leg is dearer than the other market's NO
+        let ops = check_combinatorial_pair(&trump, &harris, &PatternRegistry::default());
+        let legs: HashSet<(&str, &str)> = ops.iter().map(|o| (o.implying_asset_id.as_str(), o.implied_asset_id.as_str())).collect();
+        assert_eq!(legs, HashSet::from([("a-y", "b-n"), ("b-y", "a-n")]));
+        assert!(ops.iter().all(|o| o.profit == dec!(0.1)));
+
+        // Different prizes are not complements
+        let senate = market("c", "will_harris_win_pennsylvania", dec!(0.5));
+        assert!(check_combinatorial_pair(&trump, &senate, &PatternRegistry::default()).is_empty());
+
+        // A swing state only hints at the national result, so it falls under the default cutoff
+        let pennsylvania = market("d", "will_trump_win_pennsylvania", dec!(0.7));
+        assert!(check_combinatorial_pair(&pennsylvania, &trump, &PatternRegistry::default()).is_empty());
+        let ops = check_combinatorial_pair(&pennsylvania, &trump, &PatternRegistry::default().with_min_confidence(0.0));
+        assert!(!ops.is_empty() && ops.iter().all(|o| o.confidence < 0.5));
+        assert!(ops[0].explanation.contains("swing state"));
+        let dep = analyze_dependency(&trump, &trump.conditions[0], &harris, &harris.conditions[1]).unwrap();
+        assert!(dep.confidence >= 0.5 && dep.explanation.contains("trump"), "{}", dep.explanation);
+    }
+
+    #[test]
+    fn test_pattern_registry_disable_and_register() {
+        struct SameDay;
+        impl DependencyPattern for SameDay {
+            fn name(&self) -> &'static str { "same_day" }
+            fn matches(&self, m1: &Market, c1: &Condition, m2: &Market, c2: &Condition, _shared: &HashSet<Entity>) -> Option<Dependency> {
+                (m1.end_date == m2.end_date && c1.outcome == Some(true) && c2.outcome == Some(true)).then(|| Dependency {
+                    pattern: PatternType::SubsetImplication,
+                    direction: Direction::C1ImpliesC2,
+                    confidence: 1.0,
+                    explanation: "same day".to_string(),
+                })
+            }
+        }
+
+        let market = |id: &str, title: &str, yes: Decimal| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: id.to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            ..Market::default()
+        };
+        let trump = market("a", "will_trump_win_the_presidential_election", dec!(0.6));
+        let trump_pa = market("b", "will_trump_win_the_presidential_election_and_pennsylvania", dec!(0.7));
+
+        let mut registry = PatternRegistry::default();
+        assert_eq!(check_combinatorial_pair(&trump_pa, &trump, &registry).len(), 1);
+        registry.set_enabled("subset", false).unwrap();
+        assert!(check_combinatorial_pair(&trump_pa, &trump, &registry).is_empty());
+        assert!(registry.set_enabled("astrology", false).is_err());
+
+        // Custom patterns run after the built-ins
+        registry.register(Box::new(SameDay));
+        let ops = check_combinatorial_pair(&trump_pa, &trump, &registry);
+        assert_eq!((ops.len(), ops[0].explanation.as_str()), (1, "same day"));
+        assert_eq!(registry.enabled().last(), Some("same_day"));
+    }
+
+    #[test]
+    fn test_deadline_ladder_implication() {
+        let market = |id: &str, title: &str, end: (i32, u32, u32), yes: Decimal| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(end.0, end.1, end.2, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: id.to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            tags: vec!["Economy".to_string()],
+            ..Market::default()
+        };
+        let march = market("m", "government_shut_down_by_end_march", (2025, 3, 31), dec!(0.4));
+        let june = market("j", "government_shut_down_before_july_2025", (2025, 6, 30), dec!(0.3));
+        assert_eq!(parse_deadline(&march.title, 2025).unwrap().0, NaiveDate::from_ymd_opt(2025, 3, 31).unwrap());
+        assert_eq!(parse_deadline(&june.title, 2025).unwrap().0, NaiveDate::from_ymd_opt(2025, 6, 30).unwrap());
+        assert!(are_markets_related(&march, &june, &TitleSimilarity::Levenshtein, 0.99, Duration::zero()));
+
+        let dep = analyze_dependency(&june, &june.conditions[0], &march, &march.conditions[0]).unwrap();
+        assert_eq!(dep.direction, Direction::C2ImpliesC1);
+        let ops = check_combinatorial_pair(&march, &june, &PatternRegistry::default());
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].implying_asset_id, "m");
+        assert_eq!(ops[0].profit, dec!(0.1));
+
+        // Sharing an event outweighs disjoint tags and dissimilar titles
+        let mut recession = market("r", "us_recession_in_2025", (2025, 12, 31), dec!(0.2));
+        recession.tags = vec!["Recession".to_string()];
+        let mut shutdown = march.clone();
+        assert!(!are_markets_related(&shutdown, &recession, &TitleSimilarity::Levenshtein, 0.5, Duration::zero()));
+        shutdown.event_id = Some("e1".to_string());
+        recession.event_id = Some("e1".to_string());
+        assert!(are_markets_related(&shutdown, &recession, &TitleSimilarity::Levenshtein, 0.99, Duration::zero()));
+
+        // Near-identical markets expiring hours apart are related once the tolerance covers the gap
+        recession.event_id = None;
+        let mut later = recession.clone();
+        later.id = "l".to_string();
+        later.end_date += Duration::hours(6);
+        assert!(!are_markets_related(&recession, &later, &TitleSimilarity::Levenshtein, 0.9, Duration::zero()));
+        assert!(are_markets_related(&recession, &later, &TitleSimilarity::Levenshtein, 0.9, Duration::hours(12)));
     }
 
-    
\ No newline at end of file
+    #[test]
+    fn test_sports_winner_spread_and_series_implications() {
+        let market = |id: &str, title: &str, end_day: u32, outcomes: [(&str, Option<bool>, Decimal); 2]| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2025, 5, end_day, 0, 0, 0).unwrap(),
+            conditions: outcomes.iter().map(|(name, outcome, price)| Condition { name: name.to_string(), price: *price, outcome: *outcome, asset_id: format!("{}-{}", id, name), kind: OutcomeKind::Other, last_updated: None }).collect(),
+            tags: vec!["NBA".to_string()],
+            ..Market::default()
+        };
+        let teams = |lakers: Decimal| [("lakers", None, lakers), ("celtics", None, Decimal::ONE - lakers)];
+        let yes_no = |yes: Decimal| [("yes", Some(true), yes), ("no", Some(false), Decimal::ONE - yes)];
+        let moneyline = market("ml", "lakers_vs_celtics_game_3", 10, teams(dec!(0.55)));
+        let spread = market("sp", "spread_lakers_-5_5_game_3", 10, teams(dec!(0.6)));
+        let celtics_win = market("cw", "celtics_beat_lakers_game_3", 10, yes_no(dec!(0.4)));
+        let lakers_win = market("lw", "lakers_beat_celtics_game_3", 10, yes_no(dec!(0.5)));
+        let direction = |m1: &Market, c1: usize, m2: &Market, c2: usize| analyze_dependency(m1, &m1.conditions[c1], m2, &m2.conditions[c2]).map(|d| d.direction);
+
+        // Lakers -5.5 => Lakers win => Lakers +5.5 would follow; Celtics win => Celtics +5.5
+        assert_eq!(direction(&spread, 0, &moneyline, 0), Some(Direction::C1ImpliesC2));
+        assert_eq!(direction(&moneyline, 1, &spread, 1), Some(Direction::C1ImpliesC2));
+        assert_eq!(direction(&moneyline, 1, &spread, 0), None);
+        // One team winning excludes the other
+        assert_eq!(direction(&celtics_win, 0, &moneyline, 0), None);
+        assert_eq!(direction(&celtics_win, 0, &lakers_win, 1), Some(Direction::C1ImpliesC2));
+        assert_eq!(direction(&moneyline, 0, &celtics_win, 1), Some(Direction::C1ImpliesC2));
+        assert!(matches!(analyze_dependency(&celtics_win, &celtics_win.conditions[0], &lakers_win, &lakers_win.conditions[1]).unwrap().pattern, PatternType::Complement));
+
+        // Only the deciding game settles the series
+        let series = market("se", "lakers_vs_celtics_series_winner", 20, teams(dec!(0.5)));
+        let game_7 = market("g7", "lakers_vs_celtics_game_7", 18, teams(dec!(0.45)));
+        assert!(are_markets_related(&series, &game_7, &TitleSimilarity::Levenshtein, 0.99, Duration::zero()));
+        assert_eq!(direction(&game_7, 0, &series, 0), Some(Direction::C1ImpliesC2));
+        assert_eq!(direction(&moneyline, 0, &series, 0), None);
+
+        // Lakers -5.5 priced above the Lakers moneyline, and Celtics +5.5 below the Celtics one
+        let ops = check_combinatorial_pair(&spread, &moneyline, &PatternRegistry::default());
+        assert_eq!(
+            ops.iter().map(|o| (o.implying_asset_id.as_str(), o.implied_asset_id.as_str(), o.profit)).collect::<Vec<_>>(),
+            [("sp-lakers", "ml-lakers", dec!(0.05)), ("ml-celtics", "sp-celtics", dec!(0.05))]
+        );
+        // Each is hedged with the other side of its implying market
+        assert_eq!(ops.iter().map(|o| o.implying_complement_asset_id.as_deref()).collect::<Vec<_>>(), [Some("sp-celtics"), Some("ml-lakers")]);
+    }
+
+    #[test]
+    fn test_crypto_threshold_ladder_inversions() {
+        let market = |id: &str, title: &str, tag: &str, yes: Decimal, no: Decimal| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2025, 6, 30, 0, 0, 0).unwrap(),
+            conditions: vec![
+                Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: format!("{}-y", id), kind: OutcomeKind::Other, last_updated: None },
+                Condition { name: "No".to_string(), price: no, outcome: Some(false), asset_id: format!("{}-n", id), kind: OutcomeKind::Other, last_updated: None },
+            ],
+            tags: vec![tag.to_string()],
+            ..Market::default()
+        };
+        let markets = vec![
+            market("110", "bitcoin_above_110k_on_june_30", "Crypto", dec!(0.3), dec!(0.7)),
+            market("90", "bitcoin_above_90k_on_june_30", "Crypto", dec!(0.7), dec!(0.3)),
+            market("120", "bitcoin_above_120k_on_june_30", "Crypto", dec!(0.38), dec!(0.62)),
+            market("100", "bitcoin_above_100k_on_june_30", "Crypto", dec!(0.75), dec!(0.25)),
+            market("eth", "ethereum_above_4k_on_june_30", "Crypto", dec!(0.5), dec!(0.5)),
+            market("nba", "lakers_above_50_wins_on_june_30", "NBA", dec!(0.5), dec!(0.5)),
+        ];
+        let ladders = group_threshold_ladders(&markets);
+        assert_eq!(ladders.len(), 1);
+        let rungs = ladders.values().next().unwrap();
+        assert_eq!(rungs.iter().map(|&i| markets[i].id.as_str()).collect::<Vec<_>>(), ["90", "100", "110", "120"]);
+
+        // 120k YES over 110k YES is the widest gap, then 100k over 90k
+        let ladder: Vec<_> = rungs.iter().map(|&i| &markets[i]).collect();
+        let op = check_threshold_ladder(&ladder, &FeeModel { min_edge: dec!(0.01), ..FeeModel::default() }).unwrap();
+        assert_eq!(op.legs.iter().map(|l| l.asset_id.as_str()).collect::<Vec<_>>(), ["110-y", "120-n", "90-y", "100-n"]);
+        assert_eq!((op.profit, op.cost()), (dec!(0.13), dec!(1.87)));
+        assert_eq!(check_threshold_ladder(&ladder, &FeeModel { min_edge: dec!(0.06), ..FeeModel::default() }).unwrap().profit, dec!(0.08));
+        assert!(check_threshold_ladder(&ladder, &FeeModel { min_edge: dec!(0.1), ..FeeModel::default() }).is_none());
+    }
+
+    #[test]
+    fn test_threshold_ladder_implication() {
+        let market = |id: &str, title: &str, yes: Decimal| Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2025, 12, 31, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price: yes, outcome: Some(true), asset_id: id.to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            ..Market::default()
+        };
+        assert_eq!(parse_threshold("btc_above_100_000_2025").unwrap().value, dec!(100000));
+        let high = market("h", "Will BTC be above $100k in 2025?", dec!(0.5));
+        let low = market("l", "Will BTC be above $90,000 in 2025?", dec!(0.45));
+        let dep = analyze_dependency(&high, &high.conditions[0], &low, &low.conditions[0]).unwrap();
+        assert_eq!(dep.direction, Direction::C1ImpliesC2);
+        assert_eq!(check_combinatorial_pair(&high, &low, &PatternRegistry::default())[0].profit, dec!(0.05));
+
+        // Downside thresholds run the other way, and mixed directions are unrelated
+        let dip = market("d", "Will BTC dip to $60k in 2025?", dec!(0.3));
+        let deeper = market("x", "Will BTC dip to $50k in 2025?", dec!(0.2));
+        assert_eq!(analyze_dependency(&dip, &dip.conditions[0], &deeper, &deeper.conditions[0]).unwrap().direction, Direction::C2ImpliesC1);
+        assert!(analyze_dependency(&high, &high.conditions[0], &dip, &dip.conditions[0]).is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::blockchain::{OrderFilledFilter, VwapCalculator};
+use crate::units::{from_units, TOKEN_DECIMALS};
+use crate::market_state::MarketState;
+use crate::order_book::{OrderBook, PriceLevel};
+use crate::shared_types::{Market, Opportunity};
+use crate::strategy::{MarketView, StrategyRegistry};
+use crate::fill_ingest::FillStore;
+use crate::opportunity_store::{OpportunityKind, StoreError};
+use crate::price_series::{to_updates, BucketInterval};
+use crate::recorder::{list_segments, read_segment, to_price_updates};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BacktestError {
+    #[error("failed to read recording: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("bad record on line {line}: {source}")]
+    Record { line: usize, source: serde_json::Error },
+    #[error("failed to read fill store: {0}")]
+    Store(#[from] StoreError),
+}
+
+/// One price observation, as written by the stream recorder (one JSON object per line).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedUpdate {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+    pub asset_id: String,
+    pub price: Decimal,
+}
+
+/// Recordings carry last prices only, so each outcome is quoted at its last price on both
+/// sides, deep enough to fill `notional` USDC.
+fn last_price_books<'a>(markets: impl IntoIterator<Item = &'a Market>, notional: Decimal) -> HashMap<String, OrderBook> {
+    markets.into_iter().flat_map(|m| &m.conditions).map(|c| {
+        let size = if c.price > Decimal::ZERO { (notional / c.price).max(Decimal::ONE) } else { Decimal::ONE };
+        let level = [PriceLevel { price: c.price, size }];
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&level, &level);
+        (c.asset_id.clone(), book)
+    }).collect()
+}
+
+/// Reads a recording: a directory of stream-recorder segments, a single `.jsonl.gz`
+/// segment, a plain JSONL file of `RecordedUpdate`s, or an `ingest_fills` database, replayed
+/// as one-minute VWAP bars of the on-chain fills.
+pub fn load_updates(path: &Path) -> Result<Vec<RecordedUpdate>, BacktestError> {
+    if path.extension().is_some_and(|ext| ext == "db") {
+        let fills = FillStore::open(&path.to_string_lossy())?.fills_between(0, i64::MAX as u64)?;
+        return Ok(to_updates(&VwapCalculator::bucketed(&fills, BucketInterval::OneMinute)));
+    }
+    let segments = if path.is_dir() {
+        list_segments(path)?
+    } else if path.to_string_lossy().ends_with(".gz") {
+        vec![path.to_path_buf()]
+    } else {
+        return load_jsonl(path);
+    };
+    let mut updates = Vec::new();
+    for segment in segments {
+        updates.extend(to_price_updates(&read_segment(&segment)?));
+    }
+    updates.sort_by_key(|u| u.timestamp_ms);
+    Ok(updates)
+}
+
+/// Blank lines are skipped; anything else must parse.
+fn load_jsonl(path: &Path) -> Result<Vec<RecordedUpdate>, BacktestError> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut updates: Vec<RecordedUpdate> = Vec::new();
+    for (idx, line) in file.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        updates.push(serde_json::from_str(&line).map_err(|source| BacktestError::Record { line: idx + 1, source })?);
+    }
+    updates.sort_by_key(|u| u.timestamp_ms);
+    Ok(updates)
+}
+
+/// Turns an on-chain fill of `asset_id` into a price observation. One side of every fill is
+/// USDC and the other outcome tokens, so the price is the smaller of the two amount ratios.
+pub fn update_from_fill(fill: &OrderFilledFilter, asset_id: &str, timestamp_ms: i64) -> Option<RecordedUpdate> {
+    let maker = from_units(fill.maker_fill_amount, TOKEN_DECIMALS)?;
+    let taker = from_units(fill.taker_fill_amount, TOKEN_DECIMALS)?;
+    if maker.is_zero() || taker.is_zero() {
+        return None;
+    }
+    let price = (maker / taker).min(taker / maker);
+    Some(RecordedUpdate { timestamp_ms, asset_id: asset_id.to_string(), price })
+}
+
+/// Assumptions applied to every simulated trade.
+#[derive(Debug, Clone)]
+pub struct FillModel {
+    /// Notional committed to each opportunity, in USDC.
+    pub trade_size: Decimal,
+    /// Share of `trade_size` assumed to actually fill.
+    pub fill_ratio: Decimal,
+    /// Edge lost to slippage, as a fraction of notional.
+    pub slippage: Decimal,
+    /// Flat cost per trade (gas, fees), in USDC.
+    pub cost_per_trade: Decimal,
+    /// The same opportunity is not traded again within this window, since the first trade
+    /// would have consumed the mispricing.
+    pub cooldown_ms: i64,
+}
+
+impl Default for FillModel {
+    fn default() -> Self {
+        Self { trade_size: dec!(100), fill_ratio: dec!(0.8), slippage: dec!(0.005), cost_per_trade: dec!(0.05), cooldown_ms: 60_000 }
+    }
+}
+
+impl FillModel {
+    fn pnl(&self, profit_margin: Decimal) -> (Decimal, Decimal) {
+        let filled = self.trade_size * self.fill_ratio;
+        (filled, filled * (profit_margin - self.slippage) - self.cost_per_trade)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedTrade {
+    pub timestamp_ms: i64,
+    pub kind: OpportunityKind,
+    pub key: String,
+    pub profit_margin: Decimal,
+    pub filled: Decimal,
+    pub pnl: Decimal,
+    pub cumulative_pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StrategyStats {
+    pub detected: u64,
+    pub traded: u64,
+    pub pnl: Decimal,
+}
+
+#[derive(Debug, Default)]
+pub struct BacktestReport {
+    pub updates: usize,
+    pub strategies: BTreeMap<&'static str, StrategyStats>,
+    pub trades: Vec<SimulatedTrade>,
+    pub fill_model: FillModel,
+}
+
+impl BacktestReport {
+    pub fn total_pnl(&self) -> Decimal {
+        self.trades.last().map(|t| t.cumulative_pnl).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = &self.fill_model;
+        writeln!(f, "Replayed {} updates", self.updates)?;
+        writeln!(
+            f,
+            "Fill model: size={} fill_ratio={} slippage={} cost/trade={} cooldown={}ms",
+            m.trade_size, m.fill_ratio, m.slippage, m.cost_per_trade, m.cooldown_ms
+        )?;
+        for (kind, stats) in &self.strategies {
+            writeln!(f, "  {:<16} detected={:<6} traded={:<6} pnl={}", kind, stats.detected, stats.traded, stats.pnl.round_dp(2))?;
+        }
+        write!(f, "Cumulative PnL: {}", self.total_pnl().round_dp(2))
+    }
+}
+
+/// What makes two detections the same opportunity for the cooldown.
+fn replay_key(opportunity: &Opportunity) -> String {
+    match opportunity {
+        Opportunity::Rebalancing { op, .. } => op.market_id.clone(),
+        Opportunity::NegRiskBasket { op, .. } => op.neg_risk_market_id.clone(),
+        Opportunity::ThresholdLadder { op, .. } => op.ladder.clone(),
+        _ => opportunity.meta().asset_ids.join("|"),
+    }
+}
+
+/// Replays recorded prices through the same strategies the live loop uses.
+pub struct Backtester {
+    state: MarketState,
+    strategies: StrategyRegistry,
+    fill_model: FillModel,
+    last_traded: HashMap<String, i64>,
+    report: BacktestReport,
+}
+
+impl Backtester {
+    pub fn new(state: MarketState, strategies: StrategyRegistry, fill_model: FillModel) -> Self {
+        let report = BacktestReport { fill_model: fill_model.clone(), ..BacktestReport::default() };
+        Self { state, strategies, fill_model, last_traded: HashMap::new(), report }
+    }
+
+    pub fn run(mut self, updates: impl IntoIterator<Item = RecordedUpdate>) -> BacktestReport {
+        for update in updates {
+            self.apply(&update);
+        }
+        self.report
+    }
+
+    fn apply(&mut self, update: &RecordedUpdate) {
+        self.report.updates += 1;
+        let Some(id) = self.state.markets.resolve(&update.asset_id) else { return };
+        let at = chrono::DateTime::from_timestamp_millis(update.timestamp_ms).unwrap_or_default();
+        self.state.set_price(&update.asset_id, update.price, at);
+        let Some(market) = self.state.priced(id.market) else { return };
+
+        let related: Vec<Market> = self.state.adjacency.get(&id.market).into_iter().flatten().filter_map(|&r| self.state.priced(r)).collect();
+        let books = last_price_books(std::iter::once(&market).chain(&related), self.fill_model.trade_size);
+        let view = MarketView { state: &self.state, id: id.market, market: &market, asset_id: &update.asset_id, books: &books, now: at };
+        let found: Vec<_> = self.strategies.evaluate(&view).into_iter()
+            .map(|o| (o.kind(), replay_key(&o), o.margin()))
+            .collect();
+        for (kind, key, margin) in found {
+            self.simulate(update.timestamp_ms, kind, key, margin);
+        }
+    }
+
+    fn simulate(&mut self, timestamp_ms: i64, kind: OpportunityKind, key: String, profit_margin: Decimal) {
+        self.report.strategies.entry(kind.as_str()).or_default().detected += 1;
+
+        let key = format!("{}:{}", kind.as_str(), key);
+        if let Some(&last) = self.last_traded.get(&key) {
+            if timestamp_ms - last < self.fill_model.cooldown_ms {
+                return;
+            }
+        }
+        self.last_traded.insert(key.clone(), timestamp_ms);
+
+        let (filled, pnl) = self.fill_model.pnl(profit_margin);
+        let cumulative_pnl = self.report.total_pnl() + pnl;
+        let stats = self.report.strategies.entry(kind.as_str()).or_default();
+        stats.traded += 1;
+        stats.pnl += pnl;
+        self.report.trades.push(SimulatedTrade { timestamp_ms, kind, key, profit_margin, filled, pnl, cumulative_pnl });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fees::FeeModel;
+    use crate::shared_types::{Condition, Market, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_replay_trades_once_per_cooldown_and_accumulates_pnl() {
+        let condition = |name: &str, price: Decimal, asset_id: &str| Condition { name: name.to_string(), price, outcome: None, asset_id: asset_id.to_string(), kind: OutcomeKind::Other, last_updated: None };
+        let state = MarketState::build(vec![Market {
+            id: "m1".to_string(),
+            title: "fed_cut_rates".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 12, 18, 0, 0, 0).unwrap(),
+            conditions: vec![condition("Yes", dec!(0.5), "1"), condition("No", dec!(0.5), "2")],
+            ..Market::default()
+        }], 0.6, Default::default());
+        let model = FillModel { trade_size: dec!(100), fill_ratio: dec!(1), slippage: dec!(0), cost_per_trade: dec!(0), cooldown_ms: 1_000 };
+        let update = |ts: i64, asset: &str, price: Decimal| RecordedUpdate { timestamp_ms: ts, asset_id: asset.to_string(), price };
+
+        let strategies = StrategyRegistry::new(FeeModel::default(), Default::default(), 4);
+        let report = Backtester::new(state, strategies, model).run(vec![
+            update(0, "1", dec!(0.45)),   // sum 0.95 -> trade
+            update(500, "2", dec!(0.5)),  // still 0.95, inside cooldown
+            update(2_000, "2", dec!(0.45)), // sum 0.90 -> trade again
+            update(3_000, "unknown", dec!(0.1)),
+        ]);
+
+        let stats = &report.strategies["rebalancing"];
+        assert_eq!((stats.detected, stats.traded), (3, 2));
+        assert_eq!(report.total_pnl(), dec!(5) + dec!(10));
+        assert_eq!(report.updates, 4);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::blockchain::ExecutionError;
+use crate::execution_guard::GuardedExecutor;
+use crate::notifier::{Alert, NotifierHandle};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, warn};
+
+/// Spendable wallet balances: USDC collateral and POL for gas, in whole tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WalletBalances {
+    pub usdc: Decimal,
+    pub pol: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceLimits {
+    /// POL that must remain after paying for a transaction.
+    pub min_pol: Decimal,
+    /// Alert thresholds.
+    pub low_pol: Decimal,
+    pub low_usdc: Decimal,
+}
+
+impl Default for BalanceLimits {
+    fn default() -> Self {
+        Self { min_pol: dec!(1), low_pol: dec!(5), low_usdc: dec!(50) }
+    }
+}
+
+/// A balance that has just dropped below its alert threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowBalance {
+    pub asset: &'static str,
+    pub balance: Decimal,
+    pub threshold: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    balances: Option<WalletBalances>,
+    usdc_low: bool,
+    pol_low: bool,
+}
+
+/// Last known wallet balances, refreshed from chain by a poller and debited locally as trades
+/// spend them, so the executor can refuse a trade before it reverts.
+#[derive(Debug, Default)]
+pub struct BalanceTracker {
+    limits: BalanceLimits,
+    state: Mutex<TrackerState>,
+}
+
+impl BalanceTracker {
+    pub fn new(limits: BalanceLimits) -> Self {
+        Self { limits, state: Mutex::default() }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TrackerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn balances(&self) -> Option<WalletBalances> {
+        self.state().balances
+    }
+
+    /// Stores freshly read balances and returns those that crossed below their alert threshold
+    /// since the last update. A balance has to recover before it alerts again.
+    pub fn update(&self, balances: WalletBalances) -> Vec<LowBalance> {
+        let mut guard = self.state();
+        let state = &mut *guard;
+        state.balances = Some(balances);
+        let mut crossed = Vec::new();
+        for (asset, balance, threshold, low) in [
+            ("USDC", balances.usdc, self.limits.low_usdc, &mut state.usdc_low),
+            ("POL", balances.pol, self.limits.low_pol, &mut state.pol_low),
+        ] {
+            let is_low = balance < threshold;
+            if is_low && !*low {
+                crossed.push(LowBalance { asset, balance, threshold });
+            }
+            *low = is_low;
+        }
+        crossed
+    }
+
+    /// Refuses a trade that spends more USDC than the wallet holds or leaves less than the POL
+    /// reserve after gas. Passes until the first refresh, since nothing is known yet.
+    pub fn check(&self, usdc: Decimal, gas_pol: Decimal) -> Result<(), ExecutionError> {
+        let Some(balances) = self.state().balances else { return Ok(()) };
+        if usdc > balances.usdc {
+            return Err(ExecutionError::InsufficientBalance { asset: "USDC", needed: usdc, available: balances.usdc });
+        }
+        let pol_needed = gas_pol + self.limits.min_pol;
+        if pol_needed > balances.pol {
+            return Err(ExecutionError::InsufficientBalance { asset: "POL", needed: pol_needed, available: balances.pol });
+        }
+        Ok(())
+    }
+
+    /// Debits a trade from the cached balances until the next refresh.
+    pub fn spend(&self, usdc: Decimal, pol: Decimal) {
+        if let Some(balances) = self.state().balances.as_mut() {
+            balances.usdc = (balances.usdc - usdc).max(Decimal::ZERO);
+            balances.pol = (balances.pol - pol).max(Decimal::ZERO);
+        }
+    }
+}
+
+/// Re-reads every pool wallet's balances every `interval` and raises a low-balance alert once
+/// per crossing.
+pub fn spawn_poller(executor: Arc<GuardedExecutor>, interval: Duration, alerts: NotifierHandle) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let (low, failed) = executor.executor().refresh_balances().await;
+            for (wallet, LowBalance { asset, balance, threshold }) in low {
+                warn!(?wallet, asset, %balance, %threshold, "wallet balance low");
+                alerts.notify(Alert::LowBalance { wallet: format!("{:?}", wallet), asset, balance, threshold });
+            }
+            for (wallet, e) in failed {
+                warn!(?wallet, error = %e, "balance refresh failed");
+            }
+            for wallet in executor.executor().wallets() {
+                if let Some(b) = wallet.balances().balances() {
+                    debug!(wallet = ?wallet.address(), usdc = %b.usdc, pol = %b.pol, "wallet balances refreshed");
+                }
+            }
+            sleep(interval).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_refuses_overspend_and_gas_below_reserve() {
+        let tracker = BalanceTracker::new(BalanceLimits { min_pol: dec!(1), low_pol: dec!(5), low_usdc: dec!(50) });
+        assert!(tracker.check(dec!(1_000_000), dec!(100)).is_ok());
+
+        tracker.update(WalletBalances { usdc: dec!(100), pol: dec!(2) });
+        assert!(tracker.check(dec!(100), dec!(0.5)).is_ok());
+        assert!(matches!(tracker.check(dec!(100.01), Decimal::ZERO), Err(ExecutionError::InsufficientBalance { asset: "USDC", .. })));
+        assert!(matches!(tracker.check(Decimal::ZERO, dec!(1.5)), Err(ExecutionError::InsufficientBalance { asset: "POL", .. })));
+
+        tracker.spend(dec!(60), dec!(0.5));
+        assert!(tracker.check(dec!(41), Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_low_balance_alerts_once_per_crossing() {
+        let tracker = BalanceTracker::new(BalanceLimits::default());
+        let low = tracker.update(WalletBalances { usdc: dec!(40), pol: dec!(10) });
+        assert_eq!(low, vec![LowBalance { asset: "USDC", balance: dec!(40), threshold: dec!(50) }]);
+        assert!(tracker.update(WalletBalances { usdc: dec!(30), pol: dec!(10) }).is_empty());
+
+        tracker.update(WalletBalances { usdc: dec!(80), pol: dec!(10) });
+        assert_eq!(tracker.update(WalletBalances { usdc: dec!(45), pol: dec!(4) }).len(), 2);
+    }
+}
@@ -0,0 +1,45 @@
+use polymarket_bot::blockchain::{BlockchainCollector, ProviderPool};
+use polymarket_bot::config::BotConfig;
+use polymarket_bot::fill_ingest::{FillIngestor, FillStore, ORDER_FILLED_CHECKPOINT};
+use polymarket_bot::market_fetcher::{fetch_markets, MarketFilter};
+use polymarket_bot::rest_client;
+use polymarket_bot::token_resolver::TokenResolver;
+use dotenv::dotenv;
+use std::env;
+
+/// Backfills `OrderFilled` events into FILLS_DB_PATH, resuming from the stored checkpoint.
+/// Usage: ingest_fills [start_block]
+/// The start block is required on the first run and ignored once a checkpoint exists. Fills
+/// are tagged with their market and outcome from the open markets on Gamma; fills of markets
+/// that were unknown at ingestion are tagged on a later run.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let config = BotConfig::load()?;
+    rest_client::configure_gamma(config.gamma_client());
+    let rpc_urls = config.rpc_urls();
+    if rpc_urls.is_empty() {
+        return Err("POLYGON_RPC_URL not set".into());
+    }
+    let start_block = env::args().nth(1).map(|arg| arg.parse::<u64>()).transpose()?;
+
+    let store = FillStore::open(&config.execution.fills_db_path)?;
+    let start_block = match (store.checkpoint(ORDER_FILLED_CHECKPOINT)?, start_block) {
+        (Some(_), start) => start.unwrap_or_default(),
+        (None, Some(start)) => start,
+        (None, None) => return Err("usage: ingest_fills <start_block> (required until a checkpoint exists)".into()),
+    };
+
+    let resolver = TokenResolver::from_markets(&fetch_markets(&MarketFilter::default()).await?);
+    println!("Resolving fills against {} outcome tokens", resolver.len());
+    let retagged = store.enrich(&resolver)?;
+
+    let collector = BlockchainCollector::from_pool(ProviderPool::new(&rpc_urls, env::var("DRPC_API_KEY").ok(), config.rpc_pool_config())?)?;
+    let ingestor = FillIngestor::new(collector, store, config.ingest_config()).with_resolver(resolver);
+    let summary = ingestor.run(start_block).await?;
+    println!(
+        "Ingested {} fills from blocks {}..={} into {} ({} ranges bisected, {} earlier fills tagged)",
+        summary.fills, summary.from_block, summary.to_block, config.execution.fills_db_path, summary.bisections, retagged
+    );
+    Ok(())
+}
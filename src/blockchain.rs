@@ -1,107 +1,1587 @@
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::hex;
 use rust_decimal::Decimal;
 use std::sync::Arc;
 use std::str::FromStr;
 use std::env;
 use reqwest::header::{HeaderMap, HeaderValue};
 use url::Url;
+use crate::shared_types::{BasketOpportunity, CombinatorialOpportunity, LadderOpportunity, Market, MultiLegOpportunity, RebalancingOpportunity};
+use crate::execution_planner::{at_mid, flatten_order, hedge_order, iceberg_slices, plan_combinatorial, unwind_order, ExecutionStyle, LegSubmission, PlannerConfig, RollbackPolicy};
+use crate::profit_model::ExecutionEstimate;
+use crate::clob_client::{ClobClient, ClobError, OrderRequest, OrderSide, OrderStatus, TimeInForce};
+use std::collections::HashMap;
+use crate::gas::{wei_to_pol, GasOracle, GasPolicy};
+use crate::balances::{BalanceLimits, BalanceTracker, LowBalance, WalletBalances};
+use crate::approvals::{AllowanceCache, ApprovalPolicy, OperatorCache};
+use crate::submission::{PublicRpc, SubmissionBackend, SubmissionRouter};
+use crate::redemption::RedemptionTarget;
+use crate::order_book::SharedOrderBooks;
+use crate::quoting::{quote_prices, QuotingConfig};
+use crate::opportunity_store::OpportunityKind;
+use crate::units::{from_units, ShareAmount, UsdcAmount, TOKEN_DECIMALS};
+use crate::signer::{SignerError, WalletSigner};
+use crate::proxy_wallet::ProxyWallet;
+use crate::metrics::{RPC_ENDPOINT_LATENCY, RPC_FAILOVERS, SIMULATED_REVERTS};
+use async_trait::async_trait;
+use rust_decimal::RoundingStrategy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, info, instrument, warn};
+
+#[derive(Debug, Error)]
+pub enum ExecutionError {
+    #[error("invalid RPC URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("HTTP client error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("provider error: {0}")]
+    Provider(#[from] ProviderError),
+    #[error("wallet error: {0}")]
+    Wallet(#[from] WalletError),
+    #[error("signer error: {0}")]
+    Signer(#[from] SignerError),
+    #[error("contract call failed: {0}")]
+    Contract(String),
+    #[error("transaction dropped from mempool")]
+    Dropped,
+    #[error("invalid amount: {0}")]
+    InvalidAmount(Decimal),
+    #[error("gas cost ${cost} exceeds allowed share of expected profit ${profit}")]
+    GasTooExpensive { cost: Decimal, profit: Decimal },
+    #[error("trading paused by circuit breaker for another {0:?}")]
+    Paused(std::time::Duration),
+    #[error("trading halted by kill switch: {0}")]
+    Halted(String),
+    #[error("simulation reverted: {0}")]
+    SimulationReverted(String),
+    #[error("transaction {0:?} reverted")]
+    Reverted(H256),
+    #[error("CLOB error: {0}")]
+    Clob(#[from] ClobError),
+    #[error("only {filled} of {wanted} complete sets filled")]
+    Unfilled { filled: Decimal, wanted: Decimal },
+    #[error("insufficient {asset}: need {needed}, have {available}")]
+    InsufficientBalance { asset: &'static str, needed: Decimal, available: Decimal },
+    #[error("legs left unbalanced by {excess} shares: {reason}")]
+    Unbalanced { reason: String, excess: Decimal },
+    #[error("second leg failed ({reason}), unwound {unwound} shares")]
+    RolledBack { reason: String, unwound: Decimal },
+    #[error("{stage} failed after earlier legs executed: {source}")]
+    PartiallyExecuted { stage: &'static str, source: Box<ExecutionError> },
+    #[error("{left} shares still held after flattening")]
+    Unflattened { left: Decimal },
+}
+
+impl ExecutionError {
+    /// RPC and mempool failures are transient; configuration, wallet and revert errors are not.
+    /// Nothing is retried once a leg has executed, since a retry would repeat that leg.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ExecutionError::Provider(_) | ExecutionError::Http(_) | ExecutionError::Dropped)
+    }
+}
+
+// Solidity's built-in `Error(string)` and `Panic(uint256)` selectors
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Reads revert data as a require message, a panic code or an unknown custom error selector.
+pub fn decode_revert(data: &[u8]) -> String {
+    let Some((selector, args)) = data.split_first_chunk::<4>() else {
+        return if data.is_empty() { "no revert data".to_string() } else { format!("0x{}", hex::encode(data)) };
+    };
+    match *selector {
+        ERROR_SELECTOR => match abi::decode(&[abi::ParamType::String], args).ok().and_then(|t| t.into_iter().next()?.into_string()) {
+            Some(message) => message,
+            None => format!("malformed Error(string) 0x{}", hex::encode(args)),
+        },
+        PANIC_SELECTOR => {
+            let code = abi::decode(&[abi::ParamType::Uint(256)], args).ok().and_then(|t| t.into_iter().next()?.into_uint()).unwrap_or_default();
+            let meaning = match code.low_u64() {
+                0x01 => "assertion failed",
+                0x11 => "arithmetic overflow",
+                0x12 => "division by zero",
+                0x32 => "index out of bounds",
+                _ => "panic",
+            };
+            format!("{} (panic 0x{:x})", meaning, code)
+        }
+        _ => format!("custom error 0x{}", hex::encode(selector)),
+    }
+}
+
+/// The innermost failed frame of a `callTracer` trace, with its decoded revert reason.
+fn failed_frame(frame: &CallFrame) -> Option<String> {
+    frame.calls.iter().flatten().find_map(failed_frame).or_else(|| {
+        let error = frame.error.as_ref()?;
+        let target = frame.to.as_ref().and_then(NameOrAddress::as_address).map_or("?".to_string(), |a| format!("{:?}", a));
+        Some(match frame.output.as_ref().filter(|o| !o.is_empty()) {
+            Some(output) => format!("{} in call to {}", decode_revert(output), target),
+            None => format!("{} in call to {}", error, target),
+        })
+    })
+}
+
+fn parse_address(s: &str) -> Result<Address, ExecutionError> {
+    Address::from_str(s).map_err(|e| ExecutionError::Config(format!("bad address {}: {}", s, e)))
+}
+
+fn drpc_headers(drpc_key: Option<String>) -> Result<HeaderMap, ExecutionError> {
+    let mut headers = HeaderMap::new();
+    if let Some(key) = drpc_key {
+        // dRPC uses Drpc-Key header for authentication
+        let value = HeaderValue::from_str(&key).map_err(|e| ExecutionError::Config(format!("bad dRPC key: {}", e)))?;
+        headers.insert("Drpc-Key", value);
+    }
+    Ok(headers)
+}
+
+/// How a pool handle orders the healthy endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// Spreads reads across endpoints.
+    RoundRobin,
+    /// Lowest measured latency first, for execution.
+    Fastest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Endpoints this many blocks behind the highest head seen are skipped.
+    pub max_block_lag: u64,
+    /// An endpoint that fails at the transport level is skipped for this long.
+    pub failure_cooldown: Duration,
+    pub health_check_interval: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self { max_block_lag: 5, failure_cooldown: Duration::from_secs(30), health_check_interval: Duration::from_secs(15) }
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    /// Moving average of round trips, in milliseconds; unknown until the first response.
+    latency_ms: Option<f64>,
+    /// Head block at the last health check.
+    block: Option<u64>,
+    down_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    /// Host only, so keys in paths or query strings stay out of logs and metrics.
+    label: String,
+    transport: Http,
+    health: Mutex<EndpointHealth>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    next: AtomicUsize,
+    config: PoolConfig,
+}
+
+// Weight of the newest sample in the latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// JSON-RPC transport over several HTTP endpoints. Each request goes to the first healthy
+/// endpoint in the handle's `Selection` order and fails over to the next on transport errors;
+/// error responses from a node come straight back, since another node would answer the same.
+/// Endpoints that failed recently or lag the highest head are only tried once the rest have
+/// failed. Clones share endpoints and health.
+#[derive(Debug, Clone)]
+pub struct ProviderPool {
+    inner: Arc<PoolInner>,
+    selection: Selection,
+}
+
+impl ProviderPool {
+    /// `urls` in order of preference; the first is used until latencies are measured.
+    pub fn new(urls: &[String], drpc_key: Option<String>, config: PoolConfig) -> Result<Self, ExecutionError> {
+        if urls.is_empty() {
+            return Err(ExecutionError::Config("RPC pool needs at least one URL".to_string()));
+        }
+        let http_client = reqwest::Client::builder()
+            .default_headers(drpc_headers(drpc_key)?)
+            .build()?;
+        let endpoints = urls.iter().map(|url| {
+            let url = Url::from_str(url)?;
+            let label = url.host_str().unwrap_or("rpc").to_string();
+            Ok(Endpoint { label, transport: Http::new_with_client(url, http_client.clone()), health: Mutex::default() })
+        }).collect::<Result<Vec<_>, ExecutionError>>()?;
+        Ok(Self { inner: Arc::new(PoolInner { endpoints, next: AtomicUsize::new(0), config }), selection: Selection::RoundRobin })
+    }
+
+    /// A handle on the same endpoints that prefers the lowest latency.
+    pub fn fastest(&self) -> Self {
+        Self { inner: self.inner.clone(), selection: Selection::Fastest }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.endpoints.is_empty()
+    }
+
+    /// Endpoint indices in the order a request tries them: healthy ones by selection, then
+    /// the rest, soonest out of cooldown first.
+    fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health: Vec<(Option<f64>, Option<u64>, Option<Instant>)> = self.inner.endpoints.iter()
+            .map(|e| {
+                let h = e.health.lock().unwrap();
+                (h.latency_ms, h.block, h.down_until)
+            })
+            .collect();
+        let tip = health.iter().filter_map(|(_, block, _)| *block).max();
+        let (mut healthy, mut unhealthy): (Vec<usize>, Vec<usize>) = (0..health.len()).partition(|&i| {
+            let (_, block, down_until) = health[i];
+            let lagging = matches!((tip, block), (Some(tip), Some(block)) if tip - block > self.inner.config.max_block_lag);
+            !lagging && down_until.is_none_or(|until| until <= now)
+        });
+        match self.selection {
+            Selection::RoundRobin if !healthy.is_empty() => {
+                let start = self.inner.next.fetch_add(1, Ordering::Relaxed) % healthy.len();
+                healthy.rotate_left(start);
+            }
+            Selection::RoundRobin => {}
+            // Stable, so unmeasured endpoints keep their configured order
+            Selection::Fastest => healthy.sort_by(|&a, &b| health[a].0.unwrap_or(f64::MAX).total_cmp(&health[b].0.unwrap_or(f64::MAX))),
+        }
+        unhealthy.sort_by_key(|&i| health[i].2);
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    fn record_success(&self, index: usize, elapsed: Duration) {
+        let endpoint = &self.inner.endpoints[index];
+        let sample = elapsed.as_secs_f64() * 1000.0;
+        let mut health = endpoint.health.lock().unwrap();
+        let latency = health.latency_ms.map_or(sample, |avg| avg + LATENCY_SMOOTHING * (sample - avg));
+        health.latency_ms = Some(latency);
+        health.down_until = None;
+        RPC_ENDPOINT_LATENCY.with_label_values(&[&endpoint.label]).set(latency);
+    }
+
+    fn record_failure(&self, index: usize) {
+        let endpoint = &self.inner.endpoints[index];
+        endpoint.health.lock().unwrap().down_until = Some(Instant::now() + self.inner.config.failure_cooldown);
+        RPC_FAILOVERS.with_label_values(&[&endpoint.label]).inc();
+    }
+
+    /// Polls every endpoint's head block, refreshing latency and block lag.
+    pub async fn check_health(&self) {
+        let probes = self.inner.endpoints.iter().enumerate().map(|(i, endpoint)| async move {
+            let started = Instant::now();
+            match JsonRpcClient::request::<_, U64>(&endpoint.transport, "eth_blockNumber", ()).await {
+                Ok(block) => {
+                    self.record_success(i, started.elapsed());
+                    endpoint.health.lock().unwrap().block = Some(block.as_u64());
+                }
+                Err(e) => {
+                    warn!(endpoint = %endpoint.label, error = %e, "RPC health check failed");
+                    self.record_failure(i);
+                }
+            }
+        });
+        futures::future::join_all(probes).await;
+    }
+
+    pub fn spawn_health_checks(&self) -> JoinHandle<()> {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            loop {
+                pool.check_health().await;
+                sleep(pool.inner.config.health_check_interval).await;
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for ProviderPool {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, HttpClientError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Serialized once so every endpoint gets the same request
+        let params = serde_json::to_value(params).map_err(|err| HttpClientError::SerdeJson { err, text: String::new() })?;
+        let mut last_error = None;
+        for index in self.candidates() {
+            let endpoint = &self.inner.endpoints[index];
+            let started = Instant::now();
+            match JsonRpcClient::request(&endpoint.transport, method, &params).await {
+                Ok(response) => {
+                    self.record_success(index, started.elapsed());
+                    return Ok(response);
+                }
+                Err(e @ HttpClientError::JsonRpcError(_)) => {
+                    self.record_success(index, started.elapsed());
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!(endpoint = %endpoint.label, method, error = %e, "RPC request failed, trying next endpoint");
+                    self.record_failure(index);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("pool has at least one endpoint"))
+    }
+}
 
 // Polymarket CTF Exchange (Proxy) Address (Default: Mainnet)
-const DEFAULT_CTF_EXCHANGE_ADDRESS: &str = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E";
+pub const DEFAULT_CTF_EXCHANGE_ADDRESS: &str = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E";
+
+/// CTF_EXCHANGE_ADDRESS, or the mainnet exchange.
+pub(crate) fn ctf_exchange_address() -> Result<Address, ExecutionError> {
+    parse_address(&env::var("CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_CTF_EXCHANGE_ADDRESS.to_string()))
+}
+
+// Polymarket NegRisk CTF Exchange Address (Default: Mainnet), which settles neg-risk markets
+pub const DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS: &str = "0xC5d563A36AE78145C45a50134d48A1215220f80a";
+
+/// NEG_RISK_CTF_EXCHANGE_ADDRESS, or the mainnet neg-risk exchange.
+pub(crate) fn neg_risk_exchange_address() -> Result<Address, ExecutionError> {
+    parse_address(&env::var("NEG_RISK_CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS.to_string()))
+}
 
 abigen!(
     CtfExchange,
     r#"[
-        event OrderFilled(bytes32 indexed orderHash, address indexed maker, address indexed taker, uint256 makerFillAmount, uint256 takerFillAmount, uint256 fee)
-        function splitPosition(bytes32 conditionId, bytes32 parentCollectionId, bytes32 collectionId, uint256[] partition, uint256 amount) external
-        function mergePositions(bytes32 conditionId, bytes32 parentCollectionId, bytes32 collectionId, uint256[] partition, uint256 amount) external
+        event OrderFilled(bytes32 indexed orderHash, address indexed maker, address indexed taker, uint256 makerAssetId, uint256 takerAssetId, uint256 makerFillAmount, uint256 takerFillAmount, uint256 fee)
+    ]"#
+);
+
+// Polymarket NegRiskAdapter Address (Default: Mainnet)
+pub const DEFAULT_NEG_RISK_ADAPTER_ADDRESS: &str = "0xd91E80cF2E7be2e162c6513ceD06f1dD0dA35296";
+
+abigen!(
+    NegRiskAdapter,
+    r#"[
+        function convertPositions(bytes32 _marketId, uint256 _indexSet, uint256 _amount) external
+        function splitPosition(bytes32 _conditionId, uint256 _amount) external
+        function mergePositions(bytes32 _conditionId, uint256 _amount) external
+        function redeemPositions(bytes32 _conditionId, uint256[] _amounts) external
+    ]"#
+);
+
+// Gnosis ConditionalTokens (ERC-1155 outcome tokens) Address (Default: Mainnet)
+pub const DEFAULT_CONDITIONAL_TOKENS_ADDRESS: &str = "0x4D97DCd97eC945f40cF65F87097ACe5EA0476045";
+
+abigen!(
+    ConditionalTokens,
+    r#"[
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[])
+        function isApprovedForAll(address owner, address operator) external view returns (bool)
+        function setApprovalForAll(address operator, bool approved) external
+        function splitPosition(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] partition, uint256 amount) external
+        function mergePositions(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] partition, uint256 amount) external
+        function redeemPositions(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] indexSets) external
+        event PositionSplit(address indexed stakeholder, address collateralToken, bytes32 indexed parentCollectionId, bytes32 indexed conditionId, uint256[] partition, uint256 amount)
+        event PositionsMerge(address indexed stakeholder, address collateralToken, bytes32 indexed parentCollectionId, bytes32 indexed conditionId, uint256[] partition, uint256 amount)
+        event ConditionResolution(bytes32 indexed conditionId, address indexed oracle, bytes32 indexed questionId, uint256 outcomeSlotCount, uint256[] payoutNumerators)
+        event PayoutRedemption(address indexed redeemer, address indexed collateralToken, bytes32 indexed parentCollectionId, bytes32 conditionId, uint256[] indexSets, uint256 payout)
+    ]"#
+);
+
+// Bridged USDC (USDC.e), the collateral of every Polymarket condition (Default: Mainnet)
+pub const DEFAULT_USDC_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+abigen!(
+    Erc20,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
     ]"#
 );
 
+// Max ids per balanceOfBatch call, keeps eth_call payloads small
+const BALANCE_BATCH_SIZE: usize = 200;
+const FILL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 // Type alias for our middleware stack (Provider + Wallet)
-type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+type Client = SignerMiddleware<Provider<ProviderPool>, WalletSigner>;
 
 pub struct TradeExecutor {
-    #[allow(dead_code)]
     client: Arc<Client>,
-    #[allow(dead_code)]
     contract: CtfExchange<Client>,
+    /// Exchange that settles neg-risk markets' CLOB orders and pulls USDC for their buys.
+    neg_risk_exchange: Address,
+    neg_risk_adapter: NegRiskAdapter<Client>,
+    conditional_tokens: ConditionalTokens<Client>,
+    usdc: Erc20<Client>,
+    approvals: ApprovalPolicy,
+    allowances: AllowanceCache,
+    operators: OperatorCache,
+    balances: BalanceTracker,
+    planner: PlannerConfig,
+    submission: SubmissionRouter,
+    /// Authenticated CLOB client for the order book legs of split/merge trades.
+    clob: Option<Arc<ClobClient>>,
+    /// Live books, for quoting and mid-price execution.
+    books: Option<SharedOrderBooks>,
+    /// When set, Long rebalancing sets are bought by quoting inside the spread.
+    quoting: Option<QuotingConfig>,
+    /// How each strategy's legs meet the book; unlisted strategies take.
+    styles: HashMap<OpportunityKind, ExecutionStyle>,
+    /// How long each strategy's taking legs work the book; unlisted strategies rest until cancelled.
+    time_in_force: HashMap<OpportunityKind, TimeInForce>,
+    gas: GasOracle,
+    /// When set, transactions are built and gas-estimated but never broadcast.
+    dry_run: bool,
+    /// Proxy holding the funds; every call is relayed through it.
+    proxy: Option<ProxyWallet>,
 }
 
 impl TradeExecutor {
-    pub async fn new(rpc_url: &str, private_key: &str, drpc_key: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
-        let url = Url::from_str(rpc_url)?;
-        
-        let mut headers = HeaderMap::new();
-        if let Some(key) = drpc_key {
-            // dRPC uses Drpc-Key header for authentication
-            headers.insert("Drpc-Key", HeaderValue::from_str(&key)?);
-        }
-
-        let http_client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
+    pub async fn new(rpc_url: &str, private_key: &str, drpc_key: Option<String>) -> Result<Self, ExecutionError> {
+        Self::from_pool(ProviderPool::new(&[rpc_url.to_string()], drpc_key, PoolConfig::default())?, WalletSigner::from_key(private_key)?).await
+    }
 
-        let http_provider = Http::new_with_client(url, http_client);
-        let provider = Provider::new(http_provider);
+    /// Executes through `pool`, lowest-latency endpoint first, signing with `wallet`.
+    pub async fn from_pool(pool: ProviderPool, wallet: WalletSigner) -> Result<Self, ExecutionError> {
+        let provider = Provider::new(pool.fastest());
         let chain_id: U256 = provider.get_chainid().await?;
+        let gas = GasOracle::new(Arc::new(provider.clone()), GasPolicy::default());
+        let submission = SubmissionRouter::new(Arc::new(PublicRpc::new(provider.clone())));
         
-        let wallet = private_key.parse::<LocalWallet>()?.with_chain_id(chain_id.as_u64());
+        let wallet = wallet.with_chain_id(chain_id.as_u64());
         let client = Arc::new(SignerMiddleware::new(provider, wallet));
         
-        let address_str = env::var("CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_CTF_EXCHANGE_ADDRESS.to_string());
-        let address = Address::from_str(&address_str)?;
-        let contract = CtfExchange::new(address, client.clone());
+        let contract = CtfExchange::new(ctf_exchange_address()?, client.clone());
+        let neg_risk_exchange = neg_risk_exchange_address()?;
+
+        let adapter_str = env::var("NEG_RISK_ADAPTER_ADDRESS").unwrap_or_else(|_| DEFAULT_NEG_RISK_ADAPTER_ADDRESS.to_string());
+        let neg_risk_adapter = NegRiskAdapter::new(parse_address(&adapter_str)?, client.clone());
+
+        let ctf_str = env::var("CONDITIONAL_TOKENS_ADDRESS").unwrap_or_else(|_| DEFAULT_CONDITIONAL_TOKENS_ADDRESS.to_string());
+        let conditional_tokens = ConditionalTokens::new(parse_address(&ctf_str)?, client.clone());
+
+        let usdc_str = env::var("USDC_ADDRESS").unwrap_or_else(|_| DEFAULT_USDC_ADDRESS.to_string());
+        let usdc = Erc20::new(parse_address(&usdc_str)?, client.clone());
 
-        Ok(Self { client, contract })
+        Ok(Self { client, contract, neg_risk_exchange, neg_risk_adapter, conditional_tokens, usdc, approvals: ApprovalPolicy::default(), allowances: AllowanceCache::default(), operators: OperatorCache::default(), balances: BalanceTracker::default(), planner: PlannerConfig::default(), submission, clob: None, books: None, quoting: None, styles: HashMap::new(), time_in_force: HashMap::new(), gas, dry_run: false, proxy: None })
     }
 
-    pub async fn execute_rebalancing(&self, condition_id: &str, amount: Decimal) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
-        println!("🚀 [EXECUTION] Rebalancing Condition: {} Amount: {}", condition_id, amount);
-        // This would call splitPosition or mergePositions based on the rebalancing type
-        // For now, we simulate success until the specific contract interaction is finalized
-        Ok(TransactionReceipt::default())
+    pub fn with_approval_policy(mut self, approvals: ApprovalPolicy) -> Self {
+        self.approvals = approvals;
+        self
+    }
+
+    pub fn with_balance_limits(mut self, limits: BalanceLimits) -> Self {
+        self.balances = BalanceTracker::new(limits);
+        self
+    }
+
+    pub fn with_planner(mut self, planner: PlannerConfig) -> Self {
+        self.planner = planner;
+        self
+    }
+
+    /// Routes trades expected to make at least `min_profit` through `relay` instead of the public RPC.
+    pub fn with_private_relay(mut self, relay: Arc<dyn SubmissionBackend>, min_profit: Decimal) -> Self {
+        self.submission = self.submission.with_private(relay, min_profit);
+        self
+    }
+
+    pub fn with_clob(mut self, clob: Arc<ClobClient>) -> Self {
+        self.clob = Some(clob);
+        self
+    }
+
+    pub fn with_order_books(mut self, books: SharedOrderBooks) -> Self {
+        self.books = Some(books);
+        self
+    }
+
+    /// Buys Long rebalancing sets as a maker instead of crossing the spread on every leg.
+    /// Needs the order books.
+    pub fn with_quoting(mut self, config: QuotingConfig) -> Self {
+        self.quoting = Some(config);
+        self
+    }
+
+    /// Mid-price styles fall back to taking without the order books.
+    pub fn with_execution_styles(mut self, styles: HashMap<OpportunityKind, ExecutionStyle>) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    pub fn with_time_in_force(mut self, time_in_force: HashMap<OpportunityKind, TimeInForce>) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn with_gas_policy(mut self, policy: GasPolicy) -> Self {
+        self.gas.policy = policy;
+        self
+    }
+
+    /// Shares another executor's fee oracle, so a wallet pool polls gas once.
+    pub fn with_gas_oracle(mut self, gas: GasOracle) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Trades from the funds of `proxy`, which the signing key must control.
+    pub async fn with_proxy_wallet(mut self, proxy: ProxyWallet) -> Result<Self, ExecutionError> {
+        proxy.verify_owner(self.client.clone(), self.client.address()).await?;
+        self.proxy = Some(proxy);
+        Ok(self)
+    }
+
+    /// The address holding the wallet's USDC and positions: its proxy when it has one.
+    pub fn address(&self) -> Address {
+        self.proxy.map_or(self.client.address(), |p| p.address)
+    }
+
+    pub fn gas_oracle(&self) -> &GasOracle {
+        &self.gas
+    }
+
+    pub fn balances(&self) -> &BalanceTracker {
+        &self.balances
+    }
+
+    /// Reads the wallet's USDC and POL balances into the balance tracker and returns those that
+    /// have just dropped below their alert threshold.
+    pub async fn refresh_balances(&self) -> Result<Vec<LowBalance>, ExecutionError> {
+        let usdc = self.usdc.balance_of(self.address()).call().await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        // Gas is always paid by the signing key
+        let pol = self.client.get_balance(self.client.address(), None).await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        Ok(self.balances.update(WalletBalances { usdc: UsdcAmount::from_units(usdc).map_or(Decimal::MAX, UsdcAmount::value), pol: wei_to_pol(pol) }))
+    }
+
+    /// Reads the wallet's ERC-1155 outcome token balances for `asset_ids`, in shares.
+    pub async fn fetch_balances(&self, asset_ids: &[String]) -> Result<HashMap<String, Decimal>, ExecutionError> {
+        let owner = self.address();
+        let mut balances = HashMap::new();
+        for chunk in asset_ids.chunks(BALANCE_BATCH_SIZE) {
+            let ids = chunk.iter()
+                .map(|id| U256::from_dec_str(id).map_err(|e| ExecutionError::Config(format!("bad asset id {}: {}", id, e))))
+                .collect::<Result<Vec<_>, _>>()?;
+            let amounts = self.conditional_tokens.balance_of_batch(vec![owner; ids.len()], ids)
+                .call()
+                .await
+                .map_err(|e| ExecutionError::Contract(e.to_string()))?;
+            for (id, units) in chunk.iter().zip(amounts) {
+                balances.insert(id.clone(), ShareAmount::from_units(units).unwrap_or_default().value());
+            }
+        }
+        Ok(balances)
+    }
+
+    /// Runs the transaction with `eth_call` against the latest block. A revert comes back as
+    /// `SimulationReverted` with its decoded reason, from `debug_traceCall` when the node strips
+    /// revert data from `eth_call` and supports tracing.
+    async fn simulate<D: abi::Detokenize>(&self, call: &ContractCall<Client, D>) -> Result<(), ExecutionError> {
+        // Raw bytes, since a proxy-relayed call no longer returns what `D` decodes
+        let data = match self.client.call(&call.tx, None).await.map_err(ContractError::<Client>::from_middleware_error) {
+            Ok(_) => return Ok(()),
+            Err(ContractError::Revert(data)) => data,
+            Err(e) => return Err(ExecutionError::Contract(e.to_string())),
+        };
+        let mut reason = decode_revert(&data);
+        if data.is_empty() {
+            let options = GethDebugTracingCallOptions {
+                tracing_options: GethDebugTracingOptions {
+                    tracer: Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            match self.client.provider().debug_trace_call(call.tx.clone(), None, options).await {
+                Ok(GethTrace::Known(GethTraceFrame::CallTracer(frame))) => reason = failed_frame(&frame).unwrap_or(reason),
+                Ok(_) => {}
+                Err(e) => debug!(error = %e, "debug_traceCall unavailable"),
+            }
+        }
+        SIMULATED_REVERTS.inc();
+        warn!(to = ?call.tx.to(), %reason, "transaction would revert, not sent");
+        Err(ExecutionError::SimulationReverted(reason))
+    }
+
+    /// Relays `call` through the proxy wallet if there is one, simulates it, estimates gas,
+    /// applies the gas policy against `expected_profit`, then signs it with the oracle's
+    /// EIP-1559 fees and broadcasts it through the backend the router picks for that profit.
+    /// Setup transactions pass `None`, skip the profit check and go out publicly. In dry-run
+    /// mode the call is logged and a default receipt returned.
+    async fn send_call<D: abi::Detokenize>(&self, mut call: ContractCall<Client, D>, expected_profit: Option<Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        if let (Some(proxy), Some(NameOrAddress::Address(to))) = (self.proxy, call.tx.to().cloned()) {
+            let (target, data) = proxy.route(self.client.address(), to, call.tx.data().cloned().unwrap_or_default())?;
+            call.tx.set_to(target);
+            call.tx.set_data(data);
+        }
+        self.simulate(&call).await?;
+        let gas_units = call.estimate_gas().await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        let quote = self.gas.current().await?;
+        let gas_cost = match expected_profit {
+            Some(profit) => self.gas.policy.check(&quote, gas_units, profit)?,
+            None => quote.cost_usd(gas_units, self.gas.policy.pol_usd),
+        };
+        let gas_pol = quote.cost_pol(gas_units);
+        self.balances.check(Decimal::ZERO, gas_pol)?;
+        if let TypedTransaction::Eip1559(tx) = &mut call.tx {
+            tx.max_fee_per_gas = Some(quote.max_fee);
+            tx.max_priority_fee_per_gas = Some(quote.priority_fee);
+        }
+
+        if self.dry_run {
+            let calldata = call.calldata().unwrap_or_default();
+            info!(to = ?call.tx.to(), %gas_units, %gas_cost, %calldata, "dry run: transaction not broadcast");
+            return Ok(TransactionReceipt::default());
+        }
+        let mut tx = call.tx;
+        tx.set_gas(gas_units);
+        self.client.fill_transaction(&mut tx, None).await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        let signature = self.client.signer().sign_transaction(&tx).await?;
+        let backend = self.submission.select(expected_profit);
+        let hash = backend.submit(tx.rlp_signed(&signature)).await?;
+        info!(backend = backend.name(), tx = ?hash, %gas_cost, "transaction submitted");
+        let receipt = PendingTransaction::new(hash, self.client.provider()).await?.ok_or(ExecutionError::Dropped)?;
+        if receipt.status != Some(U64::one()) {
+            return Err(ExecutionError::Reverted(receipt.transaction_hash));
+        }
+        let gas_paid = receipt.gas_used.zip(receipt.effective_gas_price).map_or(gas_pol, |(used, price)| wei_to_pol(used.saturating_mul(price)));
+        self.balances.spend(Decimal::ZERO, gas_paid);
+        Ok(receipt)
+    }
+
+    /// Every contract that pulls the wallet's USDC: the CTF Exchange and NegRisk CTF Exchange for
+    /// CLOB buys, the ConditionalTokens contract for splits and the NegRiskAdapter for neg-risk
+    /// splits.
+    fn usdc_spenders(&self) -> [Address; 4] {
+        [self.contract.address(), self.neg_risk_exchange, self.conditional_tokens.address(), self.neg_risk_adapter.address()]
+    }
+
+    /// Every contract that moves the wallet's outcome tokens: the CTF Exchange and NegRisk CTF
+    /// Exchange for CLOB sells, and the NegRiskAdapter for neg-risk merges, redemptions and
+    /// conversions.
+    fn token_operators(&self) -> [Address; 3] {
+        [self.contract.address(), self.neg_risk_exchange, self.neg_risk_adapter.address()]
+    }
+
+    /// The exchange whose CLOB buys in `market` pull USDC.
+    fn exchange_for(&self, market: &Market) -> Address {
+        if market.neg_risk_market_id.is_some() { self.neg_risk_exchange } else { self.contract.address() }
+    }
+
+    /// Reads the USDC allowance of every spender and the outcome-token approval of every operator
+    /// and grants those missing, so the first arb does not stall on an approval or revert on a
+    /// missing one.
+    pub async fn ensure_approvals(&self) -> Result<(), ExecutionError> {
+        for spender in self.usdc_spenders() {
+            self.ensure_usdc_allowance(spender, U256::one()).await?;
+        }
+        for operator in self.token_operators() {
+            self.ensure_operator(operator).await?;
+        }
+        Ok(())
     }
 
-    pub async fn execute_combinatorial(&self, market_1: &str, market_2: &str, amount: Decimal) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
-        println!("🚀 [EXECUTION] Combinatorial Trade: {} -> {} Amount: {}", market_1, market_2, amount);
-        // This would execute the two legs of the trade on the CTF Exchange
+    /// Makes sure `operator` may move the wallet's outcome tokens, granting approval for all when
+    /// it may not. The chain is only read until the approval is first seen.
+    async fn ensure_operator(&self, operator: Address) -> Result<(), ExecutionError> {
+        if self.operators.is_approved(operator) {
+            return Ok(());
+        }
+        let approved = self.conditional_tokens.is_approved_for_all(self.address(), operator)
+            .call()
+            .await
+            .map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        if !approved {
+            info!(?operator, "approving outcome tokens");
+            self.send_call(self.conditional_tokens.set_approval_for_all(operator, true), None).await?;
+        }
+        self.operators.set_approved(operator);
+        Ok(())
+    }
+
+    /// Makes sure `spender` may pull `units` of USDC, approving per the approval policy when the
+    /// allowance falls short. The chain is only read when the cached allowance does not cover it.
+    async fn ensure_usdc_allowance(&self, spender: Address, units: U256) -> Result<(), ExecutionError> {
+        if self.allowances.covers(spender, units) {
+            return Ok(());
+        }
+        let allowance = self.usdc.allowance(self.address(), spender)
+            .call()
+            .await
+            .map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        match self.approvals.top_up(allowance, units) {
+            Some(approval) => {
+                info!(?spender, %allowance, %approval, "approving USDC");
+                self.send_call(self.usdc.approve(spender, approval), None).await?;
+                self.allowances.set(spender, approval);
+            }
+            None => self.allowances.set(spender, allowance),
+        }
+        Ok(())
+    }
+
+    /// Splits `units` of USDC into one complete set of outcome tokens per unit. Neg-risk
+    /// conditions hold wrapped collateral, so they go through the NegRiskAdapter.
+    async fn split(&self, market: &Market, condition: H256, units: U256, expected_profit: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let spender = if market.neg_risk_market_id.is_some() { self.neg_risk_adapter.address() } else { self.conditional_tokens.address() };
+        self.ensure_usdc_allowance(spender, units).await?;
+        let receipt = if market.neg_risk_market_id.is_some() {
+            self.send_call(self.neg_risk_adapter.split_position(condition.0, units), Some(expected_profit)).await?
+        } else {
+            let call = self.conditional_tokens.split_position(self.usdc.address(), [0u8; 32], condition.0, partition(market.conditions.len()), units);
+            self.send_call(call, Some(expected_profit)).await?
+        };
+        self.allowances.spend(spender, units);
+        if !self.dry_run {
+            let split = receipt.logs.iter()
+                .filter_map(|log| parse_log::<PositionSplitFilter>(log.clone()).ok())
+                .find(|event| event.condition_id == condition.0)
+                .ok_or_else(|| ExecutionError::Contract(format!("no PositionSplit event in {:?}", receipt.transaction_hash)))?;
+            info!(amount = %ShareAmount::from_units(split.amount).unwrap_or_default(), tx = ?receipt.transaction_hash, "split complete sets");
+        }
+        Ok(receipt)
+    }
+
+    /// Merges `units` complete sets back into USDC. Unwinds pass no expected profit, skipping
+    /// the gas policy's profit check.
+    async fn merge(&self, market: &Market, condition: H256, units: U256, expected_profit: Option<Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        let receipt = if market.neg_risk_market_id.is_some() {
+            self.send_call(self.neg_risk_adapter.merge_positions(condition.0, units), expected_profit).await?
+        } else {
+            let call = self.conditional_tokens.merge_positions(self.usdc.address(), [0u8; 32], condition.0, partition(market.conditions.len()), units);
+            self.send_call(call, expected_profit).await?
+        };
+        if !self.dry_run {
+            let merge = receipt.logs.iter()
+                .filter_map(|log| parse_log::<PositionsMergeFilter>(log.clone()).ok())
+                .find(|event| event.condition_id == condition.0)
+                .ok_or_else(|| ExecutionError::Contract(format!("no PositionsMerge event in {:?}", receipt.transaction_hash)))?;
+            info!(amount = %ShareAmount::from_units(merge.amount).unwrap_or_default(), tx = ?receipt.transaction_hash, "merged complete sets");
+        }
+        Ok(receipt)
+    }
+
+    /// Redeems the wallet's tokens in resolved markets for USDC: one balance read for the whole
+    /// batch, then one `redeemPositions` per market where the wallet holds a paying outcome.
+    /// Returns the USDC redeemed per market, zero for markets with nothing to redeem.
+    pub async fn redeem_positions(&self, targets: &[RedemptionTarget]) -> Result<Vec<(String, Result<Decimal, ExecutionError>)>, ExecutionError> {
+        let asset_ids: Vec<String> = targets.iter().flat_map(|t| t.market.conditions.iter().map(|c| c.asset_id.clone())).collect();
+        let held = self.fetch_balances(&asset_ids).await?;
+        let mut results = Vec::with_capacity(targets.len());
+        for target in targets {
+            let payout = target.payout(&held);
+            let result = if payout.is_zero() { Ok(payout) } else { self.redeem(target, &held).await.map(|_| payout) };
+            results.push((target.market.id.clone(), result));
+        }
+        Ok(results)
+    }
+
+    /// Neg-risk conditions hold wrapped collateral and are redeemed through the NegRiskAdapter,
+    /// which takes the amounts to burn instead of index sets.
+    async fn redeem(&self, target: &RedemptionTarget, held: &HashMap<String, Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        let market = &target.market;
+        let condition = H256::from_str(&market.condition_id)
+            .map_err(|e| ExecutionError::Config(format!("bad condition id {:?}: {}", market.condition_id, e)))?;
+        let receipt = if market.neg_risk_market_id.is_some() {
+            let amounts = market.conditions.iter()
+                .map(|c| {
+                    let shares = held.get(&c.asset_id).copied().unwrap_or_default();
+                    ShareAmount(shares).to_units().ok_or(ExecutionError::InvalidAmount(shares))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            self.send_call(self.neg_risk_adapter.redeem_positions(condition.0, amounts), None).await?
+        } else {
+            let call = self.conditional_tokens.redeem_positions(self.usdc.address(), [0u8; 32], condition.0, partition(market.conditions.len()));
+            self.send_call(call, None).await?
+        };
+        info!(market_id = %market.id, tx = ?receipt.transaction_hash, "redeemed resolved positions");
+        Ok(receipt)
+    }
+
+    /// Waits up to the planner's leg timeout for each `(order_id, size)` to fill, cancels what is
+    /// still resting and returns the matched size per order. Dry-run orders have no id and count
+    /// as filled.
+    async fn poll_fills(&self, clob: &ClobClient, orders: &[(String, Decimal)]) -> Result<Vec<Decimal>, ExecutionError> {
+        self.poll_fills_within(clob, orders, self.planner.leg_timeout).await
+    }
+
+    async fn poll_fills_within(&self, clob: &ClobClient, orders: &[(String, Decimal)], timeout: Duration) -> Result<Vec<Decimal>, ExecutionError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut states = Vec::with_capacity(orders.len());
+            for (id, wanted) in orders {
+                if id.is_empty() {
+                    states.push((OrderStatus::Matched, *wanted));
+                    continue;
+                }
+                let order = clob.get_order_status(id).await?;
+                states.push(order.map(|o| (o.status, o.size_matched)).unwrap_or((OrderStatus::Unknown, Decimal::ZERO)));
+            }
+            let resting = |(status, size): &(OrderStatus, Decimal), wanted: Decimal| *size < wanted && matches!(status, OrderStatus::Live | OrderStatus::Delayed);
+            let pending = orders.iter().zip(&states).any(|((_, wanted), state)| resting(state, *wanted));
+            if !pending || Instant::now() >= deadline {
+                for ((id, wanted), state) in orders.iter().zip(&states) {
+                    if resting(state, *wanted) {
+                        if let Err(e) = clob.cancel_order(id).await {
+                            warn!(order_id = %id, error = %e, "failed to cancel unfilled leg");
+                        }
+                    }
+                }
+                return Ok(orders.iter().zip(&states).map(|((_, wanted), (_, size))| (*size).min(*wanted)).collect());
+            }
+            sleep(FILL_POLL_INTERVAL).await;
+        }
+    }
+
+    fn clob(&self) -> Result<&ClobClient, ExecutionError> {
+        self.clob.as_deref().ok_or_else(|| ExecutionError::Config("order book legs need an authenticated CLOB client".to_string()))
+    }
+
+    /// Executes a single-market rebalancing arb. A "Short" market (outcomes sum above $1) is
+    /// split into complete sets whose legs are then sold on the CLOB; a "Long" market (sum
+    /// below $1) has every leg bought on the CLOB, by taking or by quoting, and the matched
+    /// sets merged back into USDC.
+    #[instrument(name = "execute", skip_all, fields(kind = "rebalancing", market_id = %op.market_id, side = %op.opportunity_type, %amount))]
+    pub async fn execute_rebalancing(&self, market: &Market, op: &RebalancingOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        info!("executing rebalancing");
+        let plan = plan_rebalancing(market, op, amount)?;
+        let clob = self.clob()?;
+        let condition = H256::from_str(&market.condition_id)
+            .map_err(|e| ExecutionError::Config(format!("bad condition id {:?}: {}", market.condition_id, e)))?;
+        let expected_profit = op.profit * plan.sets;
+
+        match plan.side {
+            OrderSide::Sell => {
+                let units = ShareAmount(plan.sets).to_units().ok_or(ExecutionError::InvalidAmount(plan.sets))?;
+                self.balances.check(plan.sets, Decimal::ZERO)?;
+                let receipt = self.split(market, condition, units, expected_profit).await?;
+                self.balances.spend(plan.sets, Decimal::ZERO);
+                let tif = self.tif(OpportunityKind::Rebalancing);
+                for (leg, &price) in market.conditions.iter().zip(&op.limit_prices) {
+                    clob.place_order(&leg.asset_id, price, plan.sets, OrderSide::Sell, tif).await
+                        .map_err(|e| ExecutionError::PartiallyExecuted { stage: "sell legs", source: Box::new(e.into()) })?;
+                }
+                Ok(receipt)
+            }
+            OrderSide::Buy => {
+                let sum: Decimal = op.limit_prices.iter().sum();
+                self.balances.check(plan.sets * sum, Decimal::ZERO)?;
+                let cost = UsdcAmount(plan.sets * sum).to_units().ok_or(ExecutionError::InvalidAmount(amount))?;
+                let exchange = self.exchange_for(market);
+                self.ensure_usdc_allowance(exchange, cost).await?;
+                let (fills, spent) = match (&self.quoting, &self.books) {
+                    (Some(config), Some(books)) => self.quote_legs(clob, market, op, plan.sets, config, books).await?,
+                    _ => self.take_legs(clob, market, op, plan.sets).await?,
+                };
+                self.balances.spend(spent, Decimal::ZERO);
+                let partial = |stage, e: ExecutionError| ExecutionError::PartiallyExecuted { stage, source: Box::new(e) };
+                let filled = fills.into_iter().min().unwrap_or_default();
+                self.allowances.spend(exchange, cost);
+                if filled.is_zero() {
+                    return Err(ExecutionError::Unfilled { filled, wanted: plan.sets });
+                }
+                if filled < plan.sets {
+                    warn!(%filled, wanted = %plan.sets, "buy legs partially filled, merging what matched");
+                }
+                let units = ShareAmount(filled).to_units().ok_or(ExecutionError::InvalidAmount(filled))?;
+                self.merge(market, condition, units, Some(op.profit * filled)).await.map_err(|e| partial("merge", e))
+            }
+        }
+    }
+
+    /// Buys `sets` of every leg at no more than the opportunity's limit prices, in the
+    /// rebalancing execution style. Returns each leg's fill and the USDC spent at most.
+    async fn take_legs(&self, clob: &ClobClient, market: &Market, op: &RebalancingOpportunity, sets: Decimal) -> Result<(Vec<Decimal>, Decimal), ExecutionError> {
+        let tif = self.tif(OpportunityKind::Rebalancing);
+        let orders: Vec<OrderRequest> = market.conditions.iter().zip(&op.limit_prices)
+            .map(|(leg, &price)| OrderRequest { asset_id: leg.asset_id.clone(), price, size: sets, side: OrderSide::Buy, tif })
+            .collect();
+        let fills = self.fill_legs(clob, &orders, self.style(OpportunityKind::Rebalancing)).await?;
+        let spent = fills.iter().zip(&op.limit_prices).map(|(fill, price)| fill * price).sum();
+        Ok((fills, spent))
+    }
+
+    fn style(&self, kind: OpportunityKind) -> ExecutionStyle {
+        self.styles.get(&kind).copied().unwrap_or_default()
+    }
+
+    fn tif(&self, kind: OpportunityKind) -> TimeInForce {
+        self.time_in_force.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Works `orders`, traded together, into the book in `style` and returns each one's fill.
+    /// Nothing has filled when the first order is rejected, so that error is returned as is;
+    /// any later failure is a partial execution. Orders resting at the mid expire on the exchange
+    /// with their timeout; what crosses keeps the orders' own time in force.
+    async fn fill_legs(&self, clob: &ClobClient, orders: &[OrderRequest], style: ExecutionStyle) -> Result<Vec<Decimal>, ExecutionError> {
+        let partial = |stage, e: ExecutionError| ExecutionError::PartiallyExecuted { stage, source: Box::new(e) };
+        match (style, &self.books) {
+            (ExecutionStyle::MidThenCross { timeout }, Some(books)) => {
+                let resting: Vec<OrderRequest> = {
+                    let books = books.read().await;
+                    let tif = TimeInForce::good_for(timeout);
+                    orders.iter().map(|order| OrderRequest { tif, ..at_mid(order, books.get(&order.asset_id)) }).collect()
+                };
+                let mut filled = self.cross(clob, &resting, timeout).await?;
+                let (mut legs, mut rest) = (Vec::new(), Vec::new());
+                for (i, (order, fill)) in orders.iter().zip(&filled).enumerate() {
+                    if *fill < order.size {
+                        legs.push(i);
+                        rest.push(OrderRequest { size: order.size - fill, ..order.clone() });
+                    }
+                }
+                if !rest.is_empty() {
+                    debug!(legs = rest.len(), "mid orders timed out, crossing the rest");
+                    let crossed = self.cross(clob, &rest, self.planner.leg_timeout).await.map_err(|e| partial("cross", e))?;
+                    for (i, fill) in legs.into_iter().zip(crossed) {
+                        filled[i] += fill;
+                    }
+                }
+                Ok(filled)
+            }
+            (ExecutionStyle::Iceberg { threshold, slice }, _) => {
+                let rounds = iceberg_slices(orders, threshold, slice);
+                let mut filled = vec![Decimal::ZERO; orders.len()];
+                for (round, slices) in rounds.iter().enumerate() {
+                    let fills = match self.cross(clob, slices, self.planner.leg_timeout).await {
+                        Ok(fills) => fills,
+                        Err(e) if round == 0 => return Err(e),
+                        Err(e) => return Err(partial("iceberg slice", e)),
+                    };
+                    let short = fills.iter().zip(slices).any(|(fill, order)| *fill < order.size);
+                    for (total, fill) in filled.iter_mut().zip(fills) {
+                        *total += fill;
+                    }
+                    if short {
+                        info!(round = round + 1, rounds = rounds.len(), "slice filled short, stopping");
+                        break;
+                    }
+                }
+                Ok(filled)
+            }
+            _ => self.cross(clob, orders, self.planner.leg_timeout).await,
+        }
+    }
+
+    /// Places `orders` and waits up to `timeout` for them to fill, cancelling what still rests.
+    async fn cross(&self, clob: &ClobClient, orders: &[OrderRequest], timeout: Duration) -> Result<Vec<Decimal>, ExecutionError> {
+        let mut placed = Vec::with_capacity(orders.len());
+        for order in orders {
+            match (clob.place_order(&order.asset_id, order.price, order.size, order.side, order.tif).await, placed.is_empty()) {
+                (Ok(response), _) => placed.push((response.order_id, order.size)),
+                (Err(e), true) => return Err(e.into()),
+                (Err(e), false) => return Err(ExecutionError::PartiallyExecuted { stage: "legs", source: Box::new(e.into()) }),
+            }
+        }
+        self.poll_fills_within(clob, &placed, timeout).await
+            .map_err(|e| ExecutionError::PartiallyExecuted { stage: "fill tracking", source: Box::new(e) })
+    }
+
+    /// Rests a bid for `sets` inside the spread on every leg, repricing them as the book moves.
+    /// Once any quote fills, the others are pulled and the missing legs are taken at the
+    /// opportunity's limit prices, which the filled quote already beat. Quotes that see no fill
+    /// within the TTL are pulled, and expire on the exchange soon after should the pull fail.
+    /// Returns each leg's fill and the USDC spent.
+    async fn quote_legs(
+        &self,
+        clob: &ClobClient,
+        market: &Market,
+        op: &RebalancingOpportunity,
+        sets: Decimal,
+        config: &QuotingConfig,
+        books: &SharedOrderBooks,
+    ) -> Result<(Vec<Decimal>, Decimal), ExecutionError> {
+        let legs = market.conditions.len();
+        let mut quotes: Vec<Option<(String, Decimal)>> = vec![None; legs];
+        let (mut filled, mut spent) = (vec![Decimal::ZERO; legs], Decimal::ZERO);
+        let mut failure = None;
+        let deadline = Instant::now() + config.quote_ttl;
+        'quoting: while Instant::now() < deadline {
+            let Some(prices) = quote_prices(market, &*books.read().await, &op.limit_prices) else {
+                warn!("a leg lost its book, pulling quotes");
+                break;
+            };
+            for (i, price) in prices.into_iter().enumerate() {
+                if quotes[i].as_ref().is_some_and(|(_, quoted)| *quoted == price) {
+                    continue;
+                }
+                if let Some((id, quoted)) = quotes[i].take() {
+                    match self.pull_quote(clob, &id, sets).await {
+                        Ok(matched) if matched.is_zero() => {}
+                        Ok(matched) => {
+                            filled[i] += matched;
+                            spent += matched * quoted;
+                            break 'quoting;
+                        }
+                        Err(e) => {
+                            failure = Some(e);
+                            break 'quoting;
+                        }
+                    }
+                }
+                match clob.place_order(&market.conditions[i].asset_id, price, sets, OrderSide::Buy, TimeInForce::good_for(config.quote_ttl)).await {
+                    Ok(response) => quotes[i] = Some((response.order_id, price)),
+                    Err(e) => {
+                        failure = Some(e.into());
+                        break 'quoting;
+                    }
+                }
+            }
+            sleep(config.reprice_interval).await;
+            for (id, _) in quotes.iter().flatten() {
+                match quote_matched(clob, id, sets).await {
+                    Ok(matched) if matched.is_zero() => {}
+                    Ok(_) => break 'quoting,
+                    Err(e) => {
+                        failure = Some(e);
+                        break 'quoting;
+                    }
+                }
+            }
+        }
+
+        // Matches that landed before a cancel still count
+        for (i, quote) in quotes.iter_mut().enumerate() {
+            if let Some((id, price)) = quote.take() {
+                match self.pull_quote(clob, &id, sets).await {
+                    Ok(matched) => {
+                        filled[i] += matched;
+                        spent += matched * price;
+                    }
+                    Err(e) => warn!(order_id = %id, error = %e, "could not read a pulled quote's fill"),
+                }
+            }
+        }
+        let target = filled.iter().copied().max().unwrap_or_default();
+        if target.is_zero() {
+            return Err(failure.unwrap_or(ExecutionError::Unfilled { filled: target, wanted: sets }));
+        }
+
+        let (mut orders, mut taken) = (Vec::new(), Vec::new());
+        let tif = self.tif(OpportunityKind::Rebalancing);
+        for (i, (leg, &price)) in market.conditions.iter().zip(&op.limit_prices).enumerate() {
+            let missing = target - filled[i];
+            if missing.is_zero() {
+                continue;
+            }
+            match clob.place_order(&leg.asset_id, price, missing, OrderSide::Buy, tif).await {
+                Ok(response) => {
+                    orders.push((response.order_id, missing));
+                    taken.push(i);
+                }
+                Err(e) => warn!(asset_id = %leg.asset_id, error = %e, "failed to take a missing leg"),
+            }
+        }
+        info!(filled = %target, taking = orders.len(), "quote filled, taking the missing legs");
+        let fills = self.poll_fills(clob, &orders).await
+            .map_err(|e| ExecutionError::PartiallyExecuted { stage: "fill tracking", source: Box::new(e) })?;
+        for (i, fill) in taken.into_iter().zip(fills) {
+            filled[i] += fill;
+            spent += fill * op.limit_prices[i];
+        }
+        Ok((filled, spent))
+    }
+
+    /// Cancels a resting quote and returns how much of it matched. A dry-run quote has no id
+    /// and counts as filled.
+    async fn pull_quote(&self, clob: &ClobClient, id: &str, size: Decimal) -> Result<Decimal, ExecutionError> {
+        if !id.is_empty() {
+            if let Err(e) = clob.cancel_order(id).await {
+                // Usually already filled; the status read below settles it
+                warn!(order_id = %id, error = %e, "failed to cancel quote");
+            }
+        }
+        quote_matched(clob, id, size).await
+    }
+
+    /// Buys the implied condition and hedges it by buying the implying condition's complement, so
+    /// nothing is ever sold short. Legs go out sequentially or as one batch per the planner; if
+    /// one leg fills further than the other, the excess is handled by the rollback policy so a
+    /// failed second leg does not leave naked exposure. Under the hedge policy a short second leg
+    /// is topped up before anything is unwound.
+    #[instrument(name = "execute", skip_all, fields(kind = "combinatorial", market_id_1 = %op.market_id_1, market_id_2 = %op.market_id_2, %amount))]
+    pub async fn execute_combinatorial(&self, op: &CombinatorialOpportunity, estimate: &ExecutionEstimate, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        info!("executing combinatorial trade");
+        if op.implying_complement_asset_id.is_none() {
+            return Err(ExecutionError::Config(format!("market {} has no complement to hedge with", op.market_id_1)));
+        }
+        let plan = plan_combinatorial(op, estimate, amount, self.tif(OpportunityKind::Combinatorial)).ok_or(ExecutionError::InvalidAmount(amount))?;
+        let clob = self.clob()?;
+        self.balances.check(plan.first.size * (plan.first.price + plan.second.price), Decimal::ZERO)?;
+        let mut exchanges = Vec::with_capacity(2);
+        for leg in [&plan.first, &plan.second] {
+            let exchange = clob.exchange_for(&leg.asset_id).await?;
+            let cost = UsdcAmount(leg.size * leg.price).to_units().ok_or(ExecutionError::InvalidAmount(amount))?;
+            self.ensure_usdc_allowance(exchange, cost).await?;
+            exchanges.push((exchange, leg.price));
+        }
+
+        let (filled_first, filled_second, failure) = match self.planner.submission {
+            LegSubmission::Sequential => {
+                let first = clob.place_order(&plan.first.asset_id, plan.first.price, plan.first.size, plan.first.side, plan.first.tif).await?;
+                let filled_first = self.poll_fills(clob, &[(first.order_id, plan.first.size)]).await?[0];
+                if filled_first.is_zero() {
+                    return Err(ExecutionError::Unfilled { filled: filled_first, wanted: plan.first.size });
+                }
+                // Only hedge what the first leg actually bought
+                match clob.place_order(&plan.second.asset_id, plan.second.price, filled_first, plan.second.side, plan.second.tif).await {
+                    Ok(second) => match self.poll_fills(clob, &[(second.order_id, filled_first)]).await {
+                        Ok(fills) => (filled_first, fills[0], None),
+                        Err(e) => return Err(ExecutionError::PartiallyExecuted { stage: "second leg", source: Box::new(e) }),
+                    },
+                    Err(e) => (filled_first, Decimal::ZERO, Some(ExecutionError::from(e))),
+                }
+            }
+            LegSubmission::Batch => {
+                let responses = clob.place_orders(&[plan.first.clone(), plan.second.clone()]).await?;
+                let failure = responses.iter().find(|r| !r.success).map(|r| ExecutionError::Clob(ClobError::OrderRejected(r.error_msg.clone())));
+                let (mut legs, mut orders) = (Vec::new(), Vec::new());
+                for (leg, (response, size)) in responses.into_iter().zip([plan.first.size, plan.second.size]).enumerate() {
+                    if response.success {
+                        legs.push(leg);
+                        orders.push((response.order_id, size));
+                    }
+                }
+                if orders.is_empty() {
+                    return Err(failure.unwrap_or(ExecutionError::Unfilled { filled: Decimal::ZERO, wanted: plan.first.size }));
+                }
+                let fills = self.poll_fills(clob, &orders).await
+                    .map_err(|e| ExecutionError::PartiallyExecuted { stage: "batched legs", source: Box::new(e) })?;
+                // A rejected leg filled nothing
+                let mut filled = [Decimal::ZERO; 2];
+                for (leg, fill) in legs.into_iter().zip(fills) {
+                    filled[leg] = fill;
+                }
+                (filled[0], filled[1], failure)
+            }
+        };
+
+        let (filled_second, failure) = match self.planner.rollback {
+            RollbackPolicy::Hedge { max_slippage } => match hedge_order(&plan, filled_first, filled_second, max_slippage) {
+                Some(order) => {
+                    let hedged = filled_second + self.hedge(clob, &order).await?;
+                    // A pair brought back into balance has nothing left to report
+                    (hedged, failure.filter(|_| hedged != filled_first))
+                }
+                None => (filled_second, failure),
+            },
+            _ => (filled_second, failure),
+        };
+        for ((exchange, price), filled) in exchanges.into_iter().zip([filled_first, filled_second]) {
+            self.balances.spend(filled * price, Decimal::ZERO);
+            self.allowances.spend(exchange, UsdcAmount(filled * price).to_units().unwrap_or_default());
+        }
+        if filled_first == filled_second {
+            return match failure {
+                Some(e) => Err(e),
+                None if filled_first.is_zero() => Err(ExecutionError::Unfilled { filled: Decimal::ZERO, wanted: plan.first.size }),
+                None => Ok(TransactionReceipt::default()),
+            };
+        }
+        let reason = match failure {
+            Some(e) => e.to_string(),
+            None => format!("legs filled {} and {} of {}", filled_first, filled_second, plan.first.size),
+        };
+        match self.planner.rollback {
+            RollbackPolicy::Hold => {
+                warn!(%filled_first, %filled_second, %reason, "pair left unbalanced, holding excess");
+                Err(ExecutionError::Unbalanced { reason, excess: (filled_first - filled_second).abs() })
+            }
+            RollbackPolicy::Unwind { max_slippage } | RollbackPolicy::Hedge { max_slippage } => {
+                let order = unwind_order(&plan, filled_first, filled_second, max_slippage).expect("fills differ");
+                warn!(asset_id = %order.asset_id, side = order.side.as_str(), size = %order.size, price = %order.price, %reason, "unwinding unhedged leg");
+                clob.place_order(&order.asset_id, order.price, order.size, order.side, order.tif).await
+                    .map_err(|e| ExecutionError::PartiallyExecuted { stage: "rollback", source: Box::new(e.into()) })?;
+                Err(ExecutionError::RolledBack { reason, unwound: order.size })
+            }
+        }
+    }
+
+    /// Places a top-up of the second leg and returns how much of it filled within the leg timeout.
+    /// A rejected top-up filled nothing, so the unwind can still flatten the first leg.
+    async fn hedge(&self, clob: &ClobClient, order: &OrderRequest) -> Result<Decimal, ExecutionError> {
+        warn!(asset_id = %order.asset_id, side = order.side.as_str(), size = %order.size, price = %order.price, "second leg filled short, topping it up");
+        let placed = match clob.place_order(&order.asset_id, order.price, order.size, order.side, order.tif).await {
+            Ok(placed) => placed,
+            Err(e) => {
+                warn!(error = %e, "failed to place hedge");
+                return Ok(Decimal::ZERO);
+            }
+        };
+        let fills = self.poll_fills(clob, &[(placed.order_id, order.size)]).await
+            .map_err(|e| ExecutionError::PartiallyExecuted { stage: "hedge", source: Box::new(e) })?;
+        Ok(fills[0])
+    }
+
+    /// Buys into another trader's position with a single limit order, waiting up to the leg
+    /// timeout for it to fill. Whatever is still resting then is cancelled.
+    #[instrument(name = "execute", skip_all, fields(kind = "copy", asset_id = %order.asset_id, price = %order.price, size = %order.size))]
+    pub async fn execute_copy_trade(&self, order: &OrderRequest) -> Result<TransactionReceipt, ExecutionError> {
+        info!("mirroring entry");
+        let clob = self.clob()?;
+        let notional = order.price * order.size;
+        self.balances.check(notional, Decimal::ZERO)?;
+        let cost = UsdcAmount(notional).to_units().ok_or(ExecutionError::InvalidAmount(notional))?;
+        let exchange = clob.exchange_for(&order.asset_id).await?;
+        self.ensure_usdc_allowance(exchange, cost).await?;
+        let placed = clob.place_order(&order.asset_id, order.price, order.size, OrderSide::Buy, order.tif).await?;
+        let filled = self.poll_fills(clob, &[(placed.order_id, order.size)]).await?[0];
+        self.balances.spend(filled * order.price, Decimal::ZERO);
+        self.allowances.spend(exchange, UsdcAmount(filled * order.price).to_units().unwrap_or_default());
+        if filled.is_zero() {
+            return Err(ExecutionError::Unfilled { filled, wanted: order.size });
+        }
         Ok(TransactionReceipt::default())
     }
+
+    /// Multi-leg chains have no execution yet: shorting the dearest implying leg and buying the
+    /// cheapest implied one is refused without placing anything, so no fill is ever booked.
+    #[instrument(name = "execute", skip_all, fields(kind = "multi_leg", %amount))]
+    pub async fn execute_multi_leg(&self, op: &MultiLegOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let chain: Vec<&str> = op.legs.iter().map(|l| l.market_id.as_str()).collect();
+        warn!(chain = %chain.join(" -> "), "refusing multi-leg trade");
+        Err(ExecutionError::Config("multi-leg execution is not implemented".to_string()))
+    }
+
+    /// Buys every leg of a threshold-ladder inversion with `amount` USDC, the same number of
+    /// shares each, in one batch or in the ladder's execution style. Legs that fill unevenly are
+    /// held rather than unwound.
+    #[instrument(name = "execute", skip_all, fields(kind = "threshold_ladder", ladder = %op.ladder, %amount))]
+    pub async fn execute_threshold_ladder(&self, op: &LadderOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        info!(legs = op.legs.len(), "executing threshold ladder");
+        let clob = self.clob()?;
+        let cost = op.cost();
+        if cost.is_zero() {
+            return Err(ExecutionError::InvalidAmount(amount));
+        }
+        let shares = (amount / cost).round_dp_with_strategy(2, RoundingStrategy::ToZero);
+        self.balances.check(shares * cost, Decimal::ZERO)?;
+        let units = UsdcAmount(shares * cost).to_units().filter(|u| !u.is_zero()).ok_or(ExecutionError::InvalidAmount(amount))?;
+        // The rungs of a ladder are listed under one event, so they settle on the same exchange
+        let exchange = clob.exchange_for(&op.legs[0].asset_id).await?;
+        self.ensure_usdc_allowance(exchange, units).await?;
+
+        let tif = self.tif(OpportunityKind::ThresholdLadder);
+        let requests: Vec<OrderRequest> = op.legs.iter()
+            .map(|leg| OrderRequest { asset_id: leg.asset_id.clone(), price: leg.price, size: shares, side: OrderSide::Buy, tif })
+            .collect();
+        let (filled, failure) = match self.style(OpportunityKind::ThresholdLadder) {
+            ExecutionStyle::Taker => self.batch_legs(clob, &requests).await?,
+            style => (self.fill_legs(clob, &requests, style).await?, None),
+        };
+        let spent: Decimal = filled.iter().zip(&op.legs).map(|(fill, leg)| fill * leg.price).sum();
+        self.balances.spend(spent, Decimal::ZERO);
+        self.allowances.spend(exchange, UsdcAmount(spent).to_units().unwrap_or_default());
+
+        let (least, most) = (filled.iter().copied().min().unwrap_or_default(), filled.iter().copied().max().unwrap_or_default());
+        if least == most {
+            return match failure {
+                Some(e) => Err(e),
+                None if least.is_zero() => Err(ExecutionError::Unfilled { filled: least, wanted: shares }),
+                None => Ok(TransactionReceipt::default()),
+            };
+        }
+        let reason = match failure {
+            Some(e) => e.to_string(),
+            None => format!("legs filled between {} and {} of {}", least, most, shares),
+        };
+        warn!(%least, %most, %reason, "ladder left unbalanced, holding excess");
+        Err(ExecutionError::Unbalanced { reason, excess: most - least })
+    }
+
+    /// Places `requests` in one batch and waits for them to fill. Rejected legs fill nothing and
+    /// the first rejection is returned alongside the fills.
+    async fn batch_legs(&self, clob: &ClobClient, requests: &[OrderRequest]) -> Result<(Vec<Decimal>, Option<ExecutionError>), ExecutionError> {
+        let responses = clob.place_orders(requests).await?;
+        let failure = responses.iter().find(|r| !r.success).map(|r| ExecutionError::Clob(ClobError::OrderRejected(r.error_msg.clone())));
+        let (mut legs, mut orders) = (Vec::new(), Vec::new());
+        for (leg, (response, request)) in responses.into_iter().zip(requests).enumerate() {
+            if response.success {
+                legs.push(leg);
+                orders.push((response.order_id, request.size));
+            }
+        }
+        if orders.is_empty() {
+            let wanted = requests.first().map_or(Decimal::ZERO, |r| r.size);
+            return Err(failure.unwrap_or(ExecutionError::Unfilled { filled: Decimal::ZERO, wanted }));
+        }
+        let fills = self.poll_fills(clob, &orders).await
+            .map_err(|e| ExecutionError::PartiallyExecuted { stage: "ladder legs", source: Box::new(e) })?;
+        let mut filled = vec![Decimal::ZERO; requests.len()];
+        for (leg, fill) in legs.into_iter().zip(fills) {
+            filled[leg] = fill;
+        }
+        Ok((filled, failure))
+    }
+
+    /// Unwinds `held` shares of `market`'s outcomes, keyed by asset id. Complete sets are merged
+    /// back into USDC, then what is left of each outcome is sold on the CLOB below its best bid
+    /// by the rollback slippage (at the bid under the hold policy); sales still resting after the
+    /// leg timeout are cancelled. Returns the merge receipt, or an empty one when nothing merged.
+    #[instrument(name = "execute", skip_all, fields(kind = "flatten", market_id = %market.id))]
+    pub async fn flatten_position(&self, market: &Market, held: &HashMap<String, Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        let shares: Vec<Decimal> = market.conditions.iter().map(|c| held.get(&c.asset_id).copied().unwrap_or_default()).collect();
+        // A lone outcome is no set; merging needs every partition
+        let sets = if shares.len() > 1 { shares.iter().copied().min().unwrap_or_default() } else { Decimal::ZERO };
+        info!(%sets, "flattening position");
+        let mut receipt = TransactionReceipt::default();
+        if sets > Decimal::ZERO {
+            let condition = H256::from_str(&market.condition_id)
+                .map_err(|e| ExecutionError::Config(format!("bad condition id {:?}: {}", market.condition_id, e)))?;
+            let units = ShareAmount(sets).to_units().ok_or(ExecutionError::InvalidAmount(sets))?;
+            receipt = self.merge(market, condition, units, None).await?;
+        }
+
+        let max_slippage = match self.planner.rollback {
+            RollbackPolicy::Unwind { max_slippage } | RollbackPolicy::Hedge { max_slippage } => max_slippage,
+            RollbackPolicy::Hold => Decimal::ZERO,
+        };
+        let orders: Vec<OrderRequest> = {
+            let books = match &self.books {
+                Some(books) => Some(books.read().await),
+                None => None,
+            };
+            market.conditions.iter().zip(&shares)
+                .filter_map(|(c, held)| flatten_order(&c.asset_id, held - sets, books.as_ref().and_then(|b| b.get(&c.asset_id)), c.price, max_slippage))
+                .collect()
+        };
+        if orders.is_empty() {
+            return Ok(receipt);
+        }
+        let clob = self.clob()?;
+        let fills = self.cross(clob, &orders, self.planner.leg_timeout).await
+            .map_err(|e| if sets > Decimal::ZERO { ExecutionError::PartiallyExecuted { stage: "flatten sales", source: Box::new(e) } } else { e })?;
+        let left: Decimal = orders.iter().zip(&fills).map(|(order, fill)| order.size - fill).sum();
+        if left > Decimal::ZERO {
+            return Err(ExecutionError::Unflattened { left });
+        }
+        Ok(receipt)
+    }
+
+    /// Executes a neg-risk basket arb with `amount` USDC, spent on as many complete sets as it buys
+    /// at the legs' limits. Every leg is bought on the CLOB at its limit: the YES of each member
+    /// for a "Long", which one of them redeems at $1; the NO of each member for a "Short", whose
+    /// complete sets are then converted through the NegRiskAdapter into n - 1 USDC apiece. Uneven
+    /// fills are held, like a ladder's.
+    #[instrument(name = "execute", skip_all, fields(kind = "neg_risk_basket", basket = %op.neg_risk_market_id, %amount))]
+    pub async fn execute_neg_risk_basket(&self, op: &BasketOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        info!(side = %op.opportunity_type, legs = op.legs.len(), "executing neg-risk basket");
+        let long = match op.opportunity_type.as_str() {
+            "Long" => true,
+            "Short" => false,
+            other => return Err(ExecutionError::Config(format!("unknown basket side {:?}", other))),
+        };
+        if op.legs.is_empty() || op.legs.len() != op.question_ids.len() {
+            return Err(ExecutionError::Config(format!("{} legs for {} questions", op.legs.len(), op.question_ids.len())));
+        }
+        // Resolve the conversion up front so a bad id or a missing approval fails before anything
+        // is bought; the adapter pulls the NO legs from the wallet
+        let conversion = if long { None } else {
+            let market_id = H256::from_str(&op.neg_risk_market_id)
+                .map_err(|e| ExecutionError::Config(format!("bad neg-risk market id {}: {}", op.neg_risk_market_id, e)))?;
+            let index_set = question_index_set(&op.question_ids)?;
+            self.ensure_operator(self.neg_risk_adapter.address()).await?;
+            Some((market_id, index_set))
+        };
+
+        let clob = self.clob()?;
+        let cost = op.cost_per_set();
+        let sets = op.sets_for(amount).round_dp_with_strategy(2, RoundingStrategy::ToZero);
+        self.balances.check(sets * cost, Decimal::ZERO)?;
+        let units = UsdcAmount(sets * cost).to_units().filter(|u| !u.is_zero()).ok_or(ExecutionError::InvalidAmount(amount))?;
+        let exchange = clob.exchange_for(&op.legs[0].asset_id).await?;
+        self.ensure_usdc_allowance(exchange, units).await?;
+
+        let tif = self.tif(OpportunityKind::NegRiskBasket);
+        let requests: Vec<OrderRequest> = op.legs.iter()
+            .map(|leg| OrderRequest { asset_id: leg.asset_id.clone(), price: leg.price, size: sets, side: OrderSide::Buy, tif })
+            .collect();
+        let (filled, failure) = match self.style(OpportunityKind::NegRiskBasket) {
+            ExecutionStyle::Taker => self.batch_legs(clob, &requests).await?,
+            style => (self.fill_legs(clob, &requests, style).await?, None),
+        };
+        let spent: Decimal = filled.iter().zip(&op.legs).map(|(fill, leg)| fill * leg.price).sum();
+        self.balances.spend(spent, Decimal::ZERO);
+        self.allowances.spend(exchange, UsdcAmount(spent).to_units().unwrap_or_default());
+
+        let (least, most) = (filled.iter().copied().min().unwrap_or_default(), filled.iter().copied().max().unwrap_or_default());
+        let mut receipt = TransactionReceipt::default();
+        if let Some((market_id, index_set)) = conversion.filter(|_| least > Decimal::ZERO) {
+            let amount = ShareAmount(least).to_units().ok_or(ExecutionError::InvalidAmount(least))?;
+            let call = self.neg_risk_adapter.convert_positions(market_id.0, index_set, amount);
+            receipt = self.send_call(call, Some(op.expected_profit(least))).await
+                .map_err(|e| ExecutionError::PartiallyExecuted { stage: "conversion", source: Box::new(e) })?;
+        }
+        if least == most {
+            return match failure {
+                Some(e) => Err(e),
+                None if least.is_zero() => Err(ExecutionError::Unfilled { filled: least, wanted: sets }),
+                None => Ok(receipt),
+            };
+        }
+        let reason = match failure {
+            Some(e) => e.to_string(),
+            None => format!("legs filled between {} and {} of {}", least, most, sets),
+        };
+        warn!(%least, %most, %reason, "basket left unbalanced, holding excess");
+        Err(ExecutionError::Unbalanced { reason, excess: most - least })
+    }
+}
+
+/// Index set the NegRiskAdapter converts `question_ids` under: bit i for the question whose id
+/// ends in byte i.
+fn question_index_set(question_ids: &[String]) -> Result<U256, ExecutionError> {
+    let mut index_set = U256::zero();
+    for question_id in question_ids {
+        let id = H256::from_str(question_id)
+            .map_err(|e| ExecutionError::Config(format!("bad question id {:?}: {}", question_id, e)))?;
+        let bit = U256::one() << id.0[31];
+        if !(index_set & bit).is_zero() {
+            return Err(ExecutionError::Config(format!("question index {} repeats in the basket", id.0[31])));
+        }
+        index_set |= bit;
+    }
+    Ok(index_set)
 }
 
 pub struct BlockchainCollector {
-    contract: CtfExchange<Provider<Http>>,
+    contract: CtfExchange<Provider<ProviderPool>>,
+    conditional_tokens: ConditionalTokens<Provider<ProviderPool>>,
 }
 
 impl BlockchainCollector {
-    pub fn new(rpc_url: &str, drpc_key: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
-        let url = Url::from_str(rpc_url)?;
-        
-        let mut headers = HeaderMap::new();
-        if let Some(key) = drpc_key {
-            headers.insert("Drpc-Key", HeaderValue::from_str(&key)?);
-        }
-
-        let http_client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
+    pub fn new(rpc_url: &str, drpc_key: Option<String>) -> Result<Self, ExecutionError> {
+        Self::from_pool(ProviderPool::new(&[rpc_url.to_string()], drpc_key, PoolConfig::default())?)
+    }
 
-        let http_provider = Http::new_with_client(url, http_client);
-        let provider = Provider::new(http_provider);
-        let client = Arc::new(provider);
-        let address_str = env::var("CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_CTF_EXCHANGE_ADDRESS.to_string());
-        let address = Address::from_str(&address_str)?;
-        let contract = CtfExchange::new(address, client.clone());
+    /// Reads through `pool`, round-robin across its healthy endpoints.
+    pub fn from_pool(pool: ProviderPool) -> Result<Self, ExecutionError> {
+        let client = Arc::new(Provider::new(pool));
+        let contract = CtfExchange::new(ctf_exchange_address()?, client.clone());
+        let ctf_str = env::var("CONDITIONAL_TOKENS_ADDRESS").unwrap_or_else(|_| DEFAULT_CONDITIONAL_TOKENS_ADDRESS.to_string());
+        let conditional_tokens = ConditionalTokens::new(parse_address(&ctf_str)?, client.clone());
 
-        Ok(Self { contract })
+        Ok(Self { contract, conditional_tokens })
     }
 
-    pub async fn fetch_bids_batched(&self, from_block: u64, to_block: u64) -> Result<Vec<OrderFilledFilter>, Box<dyn std::error::Error>> {
+    pub async fn fetch_bids_batched(&self, from_block: u64, to_block: u64) -> Result<Vec<OrderFilledFilter>, ExecutionError> {
         let filter = self.contract.order_filled_filter().from_block(from_block).to_block(to_block);
-        let logs = filter.query().await?;
+        let logs = filter.query().await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
         Ok(logs)
     }
+
+    /// Every `OrderFilled` log in the range, with the metadata needed to key and order it.
+    pub async fn fetch_fills_with_meta(&self, from_block: u64, to_block: u64) -> Result<Vec<(OrderFilledFilter, LogMeta)>, ExecutionError> {
+        let filter = self.contract.order_filled_filter().from_block(from_block).to_block(to_block);
+        filter.query_with_meta().await.map_err(|e| ExecutionError::Contract(e.to_string()))
+    }
+
+    /// `ConditionResolution` logs in the range: the oracle reporting payouts for a condition.
+    pub async fn fetch_resolutions(&self, from_block: u64, to_block: u64) -> Result<Vec<(ConditionResolutionFilter, LogMeta)>, ExecutionError> {
+        let filter = self.conditional_tokens.condition_resolution_filter().from_block(from_block).to_block(to_block);
+        filter.query_with_meta().await.map_err(|e| ExecutionError::Contract(e.to_string()))
+    }
+
+    /// `PayoutRedemption` logs in the range for the given redeemers.
+    pub async fn fetch_redemptions(&self, from_block: u64, to_block: u64, redeemers: Vec<Address>) -> Result<Vec<(PayoutRedemptionFilter, LogMeta)>, ExecutionError> {
+        let redeemers: Vec<H256> = redeemers.into_iter().map(H256::from).collect();
+        let filter = self.conditional_tokens.payout_redemption_filter().from_block(from_block).to_block(to_block).topic1(redeemers);
+        filter.query_with_meta().await.map_err(|e| ExecutionError::Contract(e.to_string()))
+    }
+
+    /// Unix timestamp of `block`.
+    pub async fn block_timestamp(&self, block: u64) -> Result<u64, ExecutionError> {
+        let block = self.contract.client().get_block(block).await?
+            .ok_or_else(|| ExecutionError::Contract(format!("block {} not found", block)))?;
+        Ok(block.timestamp.as_u64())
+    }
+
+    pub async fn latest_block(&self) -> Result<u64, ExecutionError> {
+        Ok(self.contract.client().get_block_number().await?.as_u64())
+    }
+
+    pub async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError> {
+        let block = self.contract.client().get_block(block).await?;
+        Ok(block.and_then(|b| b.hash).map(|h| format!("{:?}", h)))
+    }
+
+    /// `OrderFilled` logs for the given order hashes, each paired with a `tx_hash:log_index` id.
+    pub async fn fetch_order_fills(&self, from_block: u64, to_block: u64, order_hashes: Vec<H256>) -> Result<Vec<(OrderFilledFilter, String)>, ExecutionError> {
+        let filter = self.contract.order_filled_filter().from_block(from_block).to_block(to_block).topic1(order_hashes);
+        let logs = filter.query_with_meta().await.map_err(|e| ExecutionError::Contract(e.to_string()))?;
+        Ok(logs.into_iter().map(|(fill, meta)| (fill, format!("{:?}:{}", meta.transaction_hash, meta.log_index))).collect())
+    }
+}
+
+/// How a rebalancing opportunity trades: complete sets are bought leg by leg and merged, or
+/// split and sold leg by leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebalancePlan {
+    pub side: OrderSide,
+    /// Complete sets, rounded down to the CLOB's 0.01 share size step.
+    pub sets: Decimal,
+}
+
+/// Sizes a rebalancing trade of `amount` USDC, capped at the sets the books can fill. A split
+/// turns each USDC into one set; buying costs at most the sum of the legs' limit prices per set.
+pub fn plan_rebalancing(market: &Market, op: &RebalancingOpportunity, amount: Decimal) -> Result<RebalancePlan, ExecutionError> {
+    let side = match op.opportunity_type.as_str() {
+        "Long" => OrderSide::Buy,
+        "Short" => OrderSide::Sell,
+        other => return Err(ExecutionError::Config(format!("unknown rebalancing side {:?}", other))),
+    };
+    if op.limit_prices.len() != market.conditions.len() {
+        return Err(ExecutionError::Config(format!("{} limit prices for {} legs", op.limit_prices.len(), market.conditions.len())));
+    }
+    let sum: Decimal = op.limit_prices.iter().sum();
+    if market.conditions.len() < 2 || sum <= Decimal::ZERO || amount <= Decimal::ZERO {
+        return Err(ExecutionError::InvalidAmount(amount));
+    }
+    let sets = match side {
+        OrderSide::Buy => amount / sum,
+        OrderSide::Sell => amount,
+    }
+    .min(op.size)
+    .round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    if sets.is_zero() {
+        return Err(ExecutionError::InvalidAmount(amount));
+    }
+    Ok(RebalancePlan { side, sets })
+}
+
+/// How much of a quote for `size` shares has matched. A dry-run quote has no id and counts as filled.
+async fn quote_matched(clob: &ClobClient, id: &str, size: Decimal) -> Result<Decimal, ExecutionError> {
+    if id.is_empty() {
+        return Ok(size);
+    }
+    Ok(clob.get_order_status(id).await?.map_or(Decimal::ZERO, |order| order.size_matched.min(size)))
+}
+
+/// One index set per outcome slot: [0b01, 0b10] for a binary condition.
+fn partition(outcomes: usize) -> Vec<U256> {
+    (0..outcomes).map(|i| U256::one() << i).collect()
 }
 
 pub struct VwapCalculator;
@@ -112,8 +1592,8 @@ impl VwapCalculator {
         let mut total_cost = Decimal::ZERO;
 
         for fill in fills {
-            let maker_amt = Decimal::from_str(&fill.maker_fill_amount.to_string()).unwrap_or_default();
-            let taker_amt = Decimal::from_str(&fill.taker_fill_amount.to_string()).unwrap_or_default();
+            let maker_amt = from_units(fill.maker_fill_amount, TOKEN_DECIMALS).unwrap_or_default();
+            let taker_amt = from_units(fill.taker_fill_amount, TOKEN_DECIMALS).unwrap_or_default();
 
             if maker_amt.is_zero() { continue; }
             total_vol += maker_amt;
@@ -126,4 +1606,75 @@ impl VwapCalculator {
             total_cost / total_vol
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use rust_decimal_macros::dec;
+
+    fn market(prices: &[Decimal]) -> Market {
+        Market {
+            id: "m".to_string(),
+            title: "Test".to_string(),
+            conditions: prices.iter().enumerate().map(|(i, p)| Condition { name: format!("c{}", i), price: *p, outcome: None, asset_id: i.to_string(), kind: OutcomeKind::Other, last_updated: None }).collect(),
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_rebalancing_sizes_complete_sets() {
+        // Buying costs the sum of the limit prices per set; sizes round down to 0.01 shares
+        let cheap = market(&[dec!(0.40), dec!(0.40)]);
+        let op = RebalancingOpportunity { market_id: "m".to_string(), profit: dec!(0.05), opportunity_type: "Long".to_string(), size: dec!(500), limit_prices: vec![dec!(0.45), dec!(0.50)] };
+        assert_eq!(plan_rebalancing(&cheap, &op, dec!(100)).unwrap(), RebalancePlan { side: OrderSide::Buy, sets: dec!(105.26) });
+
+        // Splitting turns each USDC into one set, up to what the bids absorb
+        let dear = market(&[dec!(0.55), dec!(0.52)]);
+        let op = RebalancingOpportunity { market_id: "m".to_string(), profit: dec!(0.07), opportunity_type: "Short".to_string(), size: dec!(80), limit_prices: vec![dec!(0.55), dec!(0.52)] };
+        assert_eq!(plan_rebalancing(&dear, &op, dec!(100)).unwrap(), RebalancePlan { side: OrderSide::Sell, sets: dec!(80) });
+        assert!(matches!(plan_rebalancing(&dear, &op, dec!(0.001)), Err(ExecutionError::InvalidAmount(_))));
+
+        assert_eq!(partition(2), vec![U256::from(1), U256::from(2)]);
+    }
+
+    #[test]
+    fn test_decodes_revert_reasons() {
+        let require = [&ERROR_SELECTOR[..], &abi::encode(&[abi::Token::String("not enough collateral".to_string())])].concat();
+        assert_eq!(decode_revert(&require), "not enough collateral");
+        let panic = [&PANIC_SELECTOR[..], &abi::encode(&[abi::Token::Uint(U256::from(0x11))])].concat();
+        assert_eq!(decode_revert(&panic), "arithmetic overflow (panic 0x11)");
+        assert_eq!(decode_revert(&[0xde, 0xad, 0xbe, 0xef, 0x01]), "custom error 0xdeadbeef");
+        assert_eq!(decode_revert(&[]), "no revert data");
+    }
+
+    #[test]
+    fn test_question_index_set_reads_the_last_byte() {
+        let id = |index: u8| format!("0x{}{:02x}", "ab".repeat(31), index);
+        assert_eq!(question_index_set(&[id(0), id(2), id(5)]).unwrap(), U256::from(0b100101));
+        assert!(matches!(question_index_set(&[id(1), id(1)]), Err(ExecutionError::Config(_))));
+        assert!(matches!(question_index_set(&["".to_string()]), Err(ExecutionError::Config(_))));
+    }
+
+    #[test]
+    fn test_pool_prefers_healthy_fast_endpoints() {
+        let urls: Vec<String> = ["http://a.rpc", "http://b.rpc", "http://c.rpc", "http://d.rpc"].iter().map(|u| u.to_string()).collect();
+        let pool = ProviderPool::new(&urls, None, PoolConfig::default()).unwrap();
+        let set = |i: usize, latency: f64, block: u64| {
+            let mut health = pool.inner.endpoints[i].health.lock().unwrap();
+            (health.latency_ms, health.block) = (Some(latency), Some(block));
+        };
+        set(0, 80.0, 100);
+        set(1, 20.0, 100);
+        set(2, 10.0, 90);
+        set(3, 40.0, 99);
+        // c lags the head by 10 blocks; the rest by latency
+        assert_eq!(pool.fastest().candidates(), vec![1, 3, 0, 2]);
+
+        pool.record_failure(1);
+        assert_eq!(pool.fastest().candidates(), vec![3, 0, 2, 1]);
+        // Reads rotate over the healthy endpoints
+        assert_eq!(pool.candidates()[..2], [0, 3]);
+        assert_eq!(pool.candidates()[..2], [3, 0]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::arbitrage_engine::deadline_stem;
+use crate::entities::extract_entities;
+use crate::shared_types::{Entity, Market};
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Inverted index over markets used to generate the pairs worth an `are_markets_related` check.
+/// Two markets can only be related when they share an event, or share a tag and also a named
+/// entity (teams for sports matchups), an end date within the expiry tolerance (title
+/// similarity) or a deadline stem (deadline ladders). Every other pair is skipped without
+/// comparing titles.
+#[derive(Debug, Default)]
+pub struct CandidateIndex {
+    by_event: HashMap<String, Vec<usize>>,
+    by_entity: HashMap<Entity, Vec<usize>>,
+    /// Keyed by UTC day; lookups cover every day the tolerance reaches.
+    by_end_date: HashMap<NaiveDate, Vec<usize>>,
+    by_deadline_stem: HashMap<String, Vec<usize>>,
+    expiry_tolerance: Duration,
+}
+
+impl CandidateIndex {
+    pub fn build(markets: &[Market], expiry_tolerance: Duration) -> Self {
+        let mut index = Self { expiry_tolerance, ..Self::default() };
+        for (idx, market) in markets.iter().enumerate() {
+            if let Some(event) = &market.event_id {
+                index.by_event.entry(event.clone()).or_default().push(idx);
+            }
+            for entity in market_entities(market) {
+                index.by_entity.entry(entity).or_default().push(idx);
+            }
+            index.by_end_date.entry(market.end_date.date_naive()).or_default().push(idx);
+            if let Some(stem) = deadline_stem(market) {
+                index.by_deadline_stem.entry(stem).or_default().push(idx);
+            }
+        }
+        index
+    }
+
+    /// Markets that may be related to `markets[idx]`, excluding itself.
+    pub fn candidates(&self, markets: &[Market], idx: usize) -> BTreeSet<usize> {
+        let market = &markets[idx];
+        let mut found: BTreeSet<usize> = market.event_id.iter()
+            .flat_map(|e| self.by_event.get(e).into_iter().flatten().copied())
+            .collect();
+
+        let keyed = market_entities(market).into_iter()
+            .filter_map(|e| self.by_entity.get(&e))
+            .chain(self.end_date_days(market).filter_map(|day| self.by_end_date.get(&day)))
+            .chain(deadline_stem(market).and_then(|s| self.by_deadline_stem.get(&s)))
+            .flatten()
+            .copied();
+        let shares_tag = |other: usize| market.tags.iter().any(|t| markets[other].tags.contains(t));
+        found.extend(keyed.filter(|&other| shares_tag(other)));
+        found.remove(&idx);
+        found
+    }
+
+    /// UTC days within the expiry tolerance of `market`'s end.
+    fn end_date_days(&self, market: &Market) -> impl Iterator<Item = NaiveDate> {
+        let first = (market.end_date - self.expiry_tolerance).date_naive();
+        let last = (market.end_date + self.expiry_tolerance).date_naive();
+        first.iter_days().take_while(move |day| *day <= last)
+    }
+
+    /// Sorted index pairs (i < j, j >= first_new) that may be related.
+    pub fn pairs(&self, markets: &[Market], first_new: usize) -> Vec<(usize, usize)> {
+        (first_new..markets.len())
+            .flat_map(|j| self.candidates(markets, j).into_iter().filter(move |&i| i < j).map(move |i| (i, j)))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Named entities from the title and outcome names; bare numbers match too much to block on.
+fn market_entities(market: &Market) -> HashSet<Entity> {
+    std::iter::once(market.title.as_str())
+        .chain(market.conditions.iter().map(|c| c.name.as_str()))
+        .flat_map(extract_entities)
+        .filter(|e| !matches!(e, Entity::NumericalValue(_)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use crate::shared_types::{Condition, OutcomeKind};
+    use rust_decimal_macros::dec;
+
+    fn market(id: &str, title: &str, tags: &[&str], end: u32, event: Option<&str>) -> Market {
+        Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2025, 6, end, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: id.to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            event_id: event.map(str::to_string),
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_pairs_need_an_event_or_a_tag_plus_another_key() {
+        let markets = vec![
+            market("a", "will_trump_win_the_presidential_election", &["Politics"], 1, None),
+            market("b", "will_trump_win_pennsylvania", &["Politics"], 2, None),
+            market("c", "government_shut_down_by_june_1", &["Politics"], 3, Some("e1")),
+            market("d", "government_shut_down_by_may_1", &["Politics"], 4, None),
+            market("e", "fed_cuts_rates", &["Economy"], 1, Some("e1")),
+            market("f", "spacex_launches_starship", &["Politics"], 5, None),
+        ];
+        let index = CandidateIndex::build(&markets, Duration::zero());
+
+        // a-b share an entity, c-d a deadline stem, c-e an event; a-e share only an end date
+        assert_eq!(index.pairs(&markets, 0), vec![(0, 1), (2, 3), (2, 4)]);
+        assert_eq!(index.pairs(&markets, 4), vec![(2, 4)]);
+        assert!(index.candidates(&markets, 5).is_empty());
+
+        // A day's tolerance reaches the Politics market ending the day before
+        let index = CandidateIndex::build(&markets, Duration::hours(24));
+        assert_eq!(index.candidates(&markets, 5).into_iter().collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::fees::FeeModel;
+use crate::profit_model::ProfitModelConfig;
+use crate::shared_types::Market;
+use crate::topic_classifier::{MarketCategory, TopicClassifier};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One category's replacements for the global thresholds; unset fields keep the global value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryOverrides {
+    /// Replaces `FeeModel::min_edge`.
+    pub fee_threshold: Option<Decimal>,
+    /// Replaces `ProfitModelConfig::min_net_profit`.
+    pub min_net_profit: Option<Decimal>,
+    /// Replaces the opportunity gate's cooldown.
+    pub cooldown: Option<Duration>,
+    /// Replaces the fixed fraction, or the Kelly multiplier, of the sizing strategy.
+    pub sizing_fraction: Option<Decimal>,
+    pub max_market_exposure: Option<Decimal>,
+}
+
+impl CategoryOverrides {
+    pub fn fees(&self, base: &FeeModel) -> FeeModel {
+        FeeModel { min_edge: self.fee_threshold.unwrap_or(base.min_edge), ..base.clone() }
+    }
+
+    pub fn profit(&self, base: &ProfitModelConfig) -> ProfitModelConfig {
+        ProfitModelConfig { min_net_profit: self.min_net_profit.unwrap_or(base.min_net_profit), ..base.clone() }
+    }
+}
+
+/// Threshold overrides per market category, found through the topic classifier. With none
+/// configured, markets are never classified and the globals apply unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CategoryParams {
+    by_category: HashMap<MarketCategory, CategoryOverrides>,
+}
+
+impl CategoryParams {
+    pub fn new(by_category: HashMap<MarketCategory, CategoryOverrides>) -> Self {
+        Self { by_category }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_category.is_empty()
+    }
+
+    pub fn for_category(&self, category: &MarketCategory) -> Option<&CategoryOverrides> {
+        self.by_category.get(category)
+    }
+
+    pub fn for_market(&self, market: &Market) -> Option<&CategoryOverrides> {
+        if self.is_empty() {
+            return None;
+        }
+        self.for_category(&TopicClassifier::classify(market))
+    }
+
+    /// `base` with `market`'s category's fee threshold.
+    pub fn fees(&self, base: &FeeModel, market: &Market) -> FeeModel {
+        self.for_market(market).map_or_else(|| base.clone(), |o| o.fees(base))
+    }
+
+    /// `base` with `market`'s category's minimum net profit.
+    pub fn profit(&self, base: &ProfitModelConfig, market: &Market) -> ProfitModelConfig {
+        self.for_market(market).map_or_else(|| base.clone(), |o| o.profit(base))
+    }
+
+    pub fn cooldown(&self, base: Duration, category: &MarketCategory) -> Duration {
+        self.for_category(category).and_then(|o| o.cooldown).unwrap_or(base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn market(title: &str, tags: &[&str]) -> Market {
+        Market {
+            title: title.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_overrides_apply_to_the_classified_category_only() {
+        let crypto = CategoryOverrides { fee_threshold: Some(dec!(0.005)), min_net_profit: Some(dec!(0.25)), cooldown: Some(Duration::from_secs(5)), ..CategoryOverrides::default() };
+        let params = CategoryParams::new(HashMap::from([(MarketCategory::Crypto, crypto)]));
+        let (fees, profit) = (FeeModel::default(), ProfitModelConfig::default());
+
+        let btc = market("Will BTC close above $100k?", &["Crypto"]);
+        assert_eq!(params.fees(&fees, &btc).min_edge, dec!(0.005));
+        assert_eq!(params.profit(&profit, &btc).min_net_profit, dec!(0.25));
+        assert_eq!(params.profit(&profit, &btc).gas_cost, profit.gas_cost);
+        assert_eq!(params.cooldown(Duration::from_secs(30), &MarketCategory::Crypto), Duration::from_secs(5));
+
+        let election = market("Presidential election winner", &["Politics"]);
+        assert_eq!(params.fees(&fees, &election), fees);
+        assert_eq!(params.cooldown(Duration::from_secs(30), &MarketCategory::Politics), Duration::from_secs(30));
+        assert!(CategoryParams::default().for_market(&btc).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, NaiveDate};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::str::FromStr;
+use thiserror::Error;
+
+const USAGE: &str = "\
+Polymarket arbitrage bot
+
+Usage: polymarket_bot [--config <path>] [command]
+
+Commands:
+  scan             Detect opportunities and log them; never trades
+  trade            Detect and execute opportunities; needs wallet credentials
+  record           Scan while recording the websocket streams for backtests
+  backtest         Replay a recording through the detectors with simulated fills
+  analyze-wallets  Rank on-chain wallets by PnL from the fill store
+  rearm            Resume execution after the kill switch tripped
+
+Without a command the bot trades when wallet credentials are set and scans otherwise.
+
+Options:
+  -c, --config <path>  bot.toml to load (default: BOT_CONFIG, then ./bot.toml)
+  -h, --help           Print help; `<command> --help` describes a command's flags";
+
+const SCAN_USAGE: &str = "\
+Detect opportunities and log them without trading, even when wallet credentials are set.
+
+Usage: polymarket_bot scan";
+
+const TRADE_USAGE: &str = "\
+Detect and execute opportunities. Fails at startup unless POLYGON_RPC_URL and a wallet
+(PRIVATE_KEY, or the [signer] backend) are set; DRY_RUN still applies.
+
+Usage: polymarket_bot trade";
+
+const RECORD_USAGE: &str = "\
+Scan without trading while writing the websocket streams to gzipped JSONL segments.
+
+Usage: polymarket_bot record [--dir <path>]
+
+Options:
+  --dir <path>  Directory for the segments (default: RECORD_DIR)";
+
+const BACKTEST_USAGE: &str = "\
+Replay a recorded price stream through the detectors with a simulated fill model. The
+recording is a RECORD_DIR, one of its segments, a JSONL file of price updates, or a fill
+store (replayed as one-minute VWAP bars).
+
+Usage: polymarket_bot backtest <recording> [options]
+
+Options:
+  --from <time>        Skip updates before this time
+  --to <time>          Stop before this time
+  --trade-size <usdc>  USDC committed per simulated trade
+  --fill-ratio <r>     Share of the trade size assumed to fill
+  --slippage <s>       Edge lost to slippage, as a fraction of notional
+
+Times are RFC 3339 (2024-11-05T14:00:00Z) or a UTC date (2024-11-05).";
+
+const ANALYZE_USAGE: &str = "\
+Rank the wallets in the fill store by realized plus mark-to-market PnL, settling positions in
+markets that resolved. Wallets meeting the copy-trading criteria are flagged.
+
+Usage: polymarket_bot analyze-wallets [options]
+
+Options:
+  --db <path>     Fill store to read (default: FILLS_DB_PATH)
+  --since <time>  Only fills at or after this time
+  --top <n>       Wallets to print (default: 20)
+
+Times are RFC 3339 (2024-11-05T14:00:00Z) or a UTC date (2024-11-05).";
+
+const REARM_USAGE: &str = "\
+Re-arm a kill switch tripped by drawdown, window losses or the execution error rate. Asks the
+running bot through its control API (sending CONTROL_API_TOKEN when set); when no bot is
+listening, clears the halt persisted at KILL_SWITCH_PATH so the next start trades.
+
+Usage: polymarket_bot rearm [--addr <host:port>]
+
+Options:
+  --addr <host:port>  Control API of the running bot (default: METRICS_ADDR)";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CliError {
+    /// `--help` was asked for; the text is printed and the process exits cleanly.
+    #[error("{0}")]
+    Help(&'static str),
+    #[error("{message}\n\n{usage}")]
+    Usage { message: String, usage: &'static str },
+}
+
+/// How the live bot treats wallet credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Trade when credentials are set, scan otherwise.
+    Auto,
+    Scan,
+    Trade,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestArgs {
+    pub recording: PathBuf,
+    /// Unix milliseconds, inclusive.
+    pub from: Option<i64>,
+    /// Unix milliseconds, exclusive.
+    pub to: Option<i64>,
+    pub trade_size: Option<Decimal>,
+    pub fill_ratio: Option<Decimal>,
+    pub slippage: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeArgs {
+    pub db: Option<String>,
+    /// Unix seconds.
+    pub since: Option<u64>,
+    pub top: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run(Mode),
+    Record { dir: Option<String> },
+    Backtest(BacktestArgs),
+    AnalyzeWallets(AnalyzeArgs),
+    Rearm { addr: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    /// Overrides BOT_CONFIG.
+    pub config: Option<PathBuf>,
+    pub command: Command,
+}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut args = Args { rest: args.into_iter().collect(), usage: USAGE };
+        let mut config = None;
+        let command = loop {
+            let Some(arg) = args.rest.pop_front() else { break Command::Run(Mode::Auto) };
+            match arg.as_str() {
+                "-h" | "--help" | "help" => return Err(CliError::Help(USAGE)),
+                "-c" | "--config" => config = Some(PathBuf::from(args.value(&arg)?)),
+                "scan" => break args.finish(SCAN_USAGE, Command::Run(Mode::Scan))?,
+                "trade" => break args.finish(TRADE_USAGE, Command::Run(Mode::Trade))?,
+                "record" => break args.record()?,
+                "backtest" => break args.backtest()?,
+                "analyze-wallets" => break args.analyze()?,
+                "rearm" => break args.rearm()?,
+                _ => return Err(args.error(format!("unknown command or option `{}`", arg))),
+            }
+        };
+        Ok(Self { config, command })
+    }
+}
+
+struct Args {
+    rest: VecDeque<String>,
+    usage: &'static str,
+}
+
+impl Args {
+    fn error(&self, message: String) -> CliError {
+        CliError::Usage { message, usage: self.usage }
+    }
+
+    fn value(&mut self, flag: &str) -> Result<String, CliError> {
+        self.rest.pop_front().ok_or_else(|| self.error(format!("`{}` needs a value", flag)))
+    }
+
+    fn parsed<T: FromStr>(&mut self, flag: &str) -> Result<T, CliError> {
+        let value = self.value(flag)?;
+        value.parse().map_err(|_| self.error(format!("`{}` is not a valid value for `{}`", value, flag)))
+    }
+
+    fn time(&mut self, flag: &str) -> Result<i64, CliError> {
+        let value = self.value(flag)?;
+        parse_time(&value).ok_or_else(|| self.error(format!("`{}` is not a valid time for `{}`", value, flag)))
+    }
+
+    /// Walks a command's flags, handing each to `flag` along with the remaining arguments.
+    fn flags(&mut self, usage: &'static str, mut flag: impl FnMut(&mut Self, &str) -> Result<bool, CliError>) -> Result<(), CliError> {
+        self.usage = usage;
+        while let Some(arg) = self.rest.pop_front() {
+            if arg == "-h" || arg == "--help" {
+                return Err(CliError::Help(usage));
+            }
+            if !flag(self, &arg)? {
+                return Err(self.error(format!("unexpected argument `{}`", arg)));
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, usage: &'static str, command: Command) -> Result<Command, CliError> {
+        self.flags(usage, |_, _| Ok(false))?;
+        Ok(command)
+    }
+
+    fn record(&mut self) -> Result<Command, CliError> {
+        let mut dir = None;
+        self.flags(RECORD_USAGE, |args, arg| {
+            match arg {
+                "--dir" => dir = Some(args.value(arg)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Command::Record { dir })
+    }
+
+    fn rearm(&mut self) -> Result<Command, CliError> {
+        let mut addr = None;
+        self.flags(REARM_USAGE, |args, arg| {
+            match arg {
+                "--addr" => addr = Some(args.value(arg)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Command::Rearm { addr })
+    }
+
+    fn backtest(&mut self) -> Result<Command, CliError> {
+        let (mut recording, mut from, mut to) = (None, None, None);
+        let (mut trade_size, mut fill_ratio, mut slippage) = (None, None, None);
+        self.flags(BACKTEST_USAGE, |args, arg| {
+            match arg {
+                "--from" => from = Some(args.time(arg)?),
+                "--to" => to = Some(args.time(arg)?),
+                "--trade-size" => trade_size = Some(args.parsed(arg)?),
+                "--fill-ratio" => fill_ratio = Some(args.parsed(arg)?),
+                "--slippage" => slippage = Some(args.parsed(arg)?),
+                _ if !arg.starts_with('-') && recording.is_none() => recording = Some(PathBuf::from(arg)),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        let recording = recording.ok_or_else(|| self.error("missing <recording>".to_string()))?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from >= to {
+                return Err(self.error("`--from` must be before `--to`".to_string()));
+            }
+        }
+        Ok(Command::Backtest(BacktestArgs { recording, from, to, trade_size, fill_ratio, slippage }))
+    }
+
+    fn analyze(&mut self) -> Result<Command, CliError> {
+        let (mut db, mut since, mut top) = (None, None, 20);
+        self.flags(ANALYZE_USAGE, |args, arg| {
+            match arg {
+                "--db" => db = Some(args.value(arg)?),
+                "--since" => since = Some((args.time(arg)? / 1000).max(0) as u64),
+                "--top" => top = args.parsed(arg)?,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Command::AnalyzeWallets(AnalyzeArgs { db, since, top }))
+    }
+}
+
+/// RFC 3339 timestamp or UTC date, as Unix milliseconds.
+fn parse_time(value: &str) -> Option<i64> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.timestamp_millis());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0).map(|t| t.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn parse(args: &str) -> Result<Cli, CliError> {
+        Cli::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parses_subcommands_and_flags() {
+        assert_eq!(parse("").unwrap().command, Command::Run(Mode::Auto));
+        let cli = parse("--config prod.toml scan").unwrap();
+        assert_eq!((cli.config, cli.command), (Some(PathBuf::from("prod.toml")), Command::Run(Mode::Scan)));
+        assert_eq!(parse("record --dir recordings").unwrap().command, Command::Record { dir: Some("recordings".to_string()) });
+        assert_eq!(parse("rearm --addr 127.0.0.1:9090").unwrap().command, Command::Rearm { addr: Some("127.0.0.1:9090".to_string()) });
+
+        let Command::Backtest(args) = parse("backtest recordings/ --from 2024-11-05 --to 2024-11-06T12:00:00Z --fill-ratio 0.8").unwrap().command else { panic!() };
+        assert_eq!(args.recording, PathBuf::from("recordings/"));
+        assert_eq!((args.from, args.to), (Some(1_730_764_800_000), Some(1_730_894_400_000)));
+        assert_eq!((args.trade_size, args.fill_ratio), (None, Some(dec!(0.8))));
+
+        let Command::AnalyzeWallets(args) = parse("analyze-wallets --top 5 --since 2024-11-05").unwrap().command else { panic!() };
+        assert_eq!((args.db, args.since, args.top), (None, Some(1_730_764_800), 5));
+    }
+
+    #[test]
+    fn test_rejects_bad_arguments_with_the_commands_usage() {
+        assert_eq!(parse("backtest --help"), Err(CliError::Help(BACKTEST_USAGE)));
+        assert_eq!(parse("--help trade"), Err(CliError::Help(USAGE)));
+        for (args, usage) in [
+            ("backtest", BACKTEST_USAGE),
+            ("backtest a b", BACKTEST_USAGE),
+            ("backtest a --from 2024-11-06 --to 2024-11-05", BACKTEST_USAGE),
+            ("analyze-wallets --top many", ANALYZE_USAGE),
+            ("scan --dir x", SCAN_USAGE),
+            ("serve", USAGE),
+            ("--config", USAGE),
+        ] {
+            assert!(matches!(parse(args), Err(CliError::Usage { usage: u, .. }) if u == usage), "{}", args);
+        }
+    }
+}
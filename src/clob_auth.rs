@@ -0,0 +1,125 @@
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::TypedData;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::env;
+use crate::clob_client::ClobError;
+
+const CLOB_AUTH_MESSAGE: &str = "This message attests that I control the given wallet";
+
+/// L2 API credentials issued by the CLOB for a wallet.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiCredentials {
+    #[serde(rename = "apiKey")]
+    pub api_key: String,
+    pub secret: String,
+    pub passphrase: String,
+}
+
+impl ApiCredentials {
+    /// Reads POLY_API_KEY / POLY_API_SECRET / POLY_PASSPHRASE if all three are set.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            api_key: env::var("POLY_API_KEY").ok()?,
+            secret: env::var("POLY_API_SECRET").ok()?,
+            passphrase: env::var("POLY_PASSPHRASE").ok()?,
+        })
+    }
+}
+
+/// L1 headers: an EIP-712 `ClobAuth` signature proving control of the wallet.
+pub async fn l1_headers<S: Signer>(wallet: &S, chain_id: u64, nonce: u64) -> Result<HeaderMap, ClobError> {
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "version", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+            ],
+            "ClobAuth": [
+                {"name": "address", "type": "address"},
+                {"name": "timestamp", "type": "string"},
+                {"name": "nonce", "type": "uint256"},
+                {"name": "message", "type": "string"},
+            ],
+        },
+        "primaryType": "ClobAuth",
+        "domain": {"name": "ClobAuthDomain", "version": "1", "chainId": chain_id},
+        "message": {
+            "address": format!("{:?}", wallet.address()),
+            "timestamp": timestamp,
+            "nonce": nonce,
+            "message": CLOB_AUTH_MESSAGE,
+        },
+    }))?;
+    let signature = wallet.sign_typed_data(&typed_data).await.map_err(|e| ClobError::Signing(e.to_string()))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("POLY_ADDRESS", header_value(&format!("{:?}", wallet.address()))?);
+    headers.insert("POLY_SIGNATURE", header_value(&format!("0x{}", signature))?);
+    headers.insert("POLY_TIMESTAMP", header_value(&timestamp)?);
+    headers.insert("POLY_NONCE", header_value(&nonce.to_string())?);
+    Ok(headers)
+}
+
+/// L2 headers: an HMAC over `timestamp + method + path + body` keyed by the API secret.
+pub fn l2_headers(creds: &ApiCredentials, address: Address, method: &str, path: &str, body: &str) -> Result<HeaderMap, ClobError> {
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let signature = hmac_signature(&creds.secret, &timestamp, method, path, body)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("POLY_ADDRESS", header_value(&format!("{:?}", address))?);
+    headers.insert("POLY_SIGNATURE", header_value(&signature)?);
+    headers.insert("POLY_TIMESTAMP", header_value(&timestamp)?);
+    headers.insert("POLY_API_KEY", header_value(&creds.api_key)?);
+    headers.insert("POLY_PASSPHRASE", header_value(&creds.passphrase)?);
+    Ok(headers)
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, ClobError> {
+    HeaderValue::from_str(value).map_err(|e| ClobError::Auth(e.to_string()))
+}
+
+fn hmac_signature(secret: &str, timestamp: &str, method: &str, path: &str, body: &str) -> Result<String, ClobError> {
+    let key = URL_SAFE.decode(secret).map_err(|e| ClobError::Auth(format!("API secret is not base64: {}", e)))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).map_err(|e| ClobError::Auth(e.to_string()))?;
+    mac.update(format!("{}{}{}{}", timestamp, method, path, body).as_bytes());
+    Ok(URL_SAFE.encode(mac.finalize().into_bytes()))
+}
+
+/// Fetches existing API credentials for the wallet, creating them on first use.
+pub async fn derive_api_credentials<S: Signer>(http: &reqwest::Client, rest_url: &str, wallet: &S, chain_id: u64) -> Result<ApiCredentials, ClobError> {
+    let derived = http.get(format!("{}/auth/derive-api-key", rest_url))
+        .headers(l1_headers(wallet, chain_id, 0).await?)
+        .send()
+        .await?;
+    if derived.status().is_success() {
+        return Ok(derived.json().await?);
+    }
+
+    let created = http.post(format!("{}/auth/api-key", rest_url))
+        .headers(l1_headers(wallet, chain_id, 0).await?)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(created.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_signature_is_url_safe_and_deterministic() {
+        let secret = URL_SAFE.encode(b"super-secret-key");
+        let a = hmac_signature(&secret, "1700000000", "POST", "/order", "{}").unwrap();
+        let b = hmac_signature(&secret, "1700000000", "POST", "/order", "{}").unwrap();
+        assert_eq!(a, b);
+        assert!(!a.contains('+') && !a.contains('/'));
+    }
+}
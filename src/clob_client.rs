@@ -3,8 +3,85 @@ use serde::{Deserialize, Serialize};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 use rust_decimal::Decimal;
-use tokio::time::{sleep, Duration};
+use rust_decimal_macros::dec;
+use tokio::time::{interval, sleep, sleep_until, Duration, Instant};
+use tokio::sync::mpsc;
+use std::collections::HashMap;
 use std::env;
+use std::str::FromStr;
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::TypedData;
+use crate::order_book::{BookSide, PriceLevel, SharedOrderBooks};
+use async_trait::async_trait;
+use crate::clob_auth::{self, ApiCredentials};
+use crate::latency::{self, Stage};
+use crate::metrics;
+use crate::recorder::SharedRecorder;
+use crate::order_lifecycle::{OrderLifecycle, SharedOrderTracker};
+use crate::blockchain::{DEFAULT_CTF_EXCHANGE_ADDRESS, DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS};
+use crate::market_state::SharedMarketState;
+use crate::shared_types::DEFAULT_TICK_SIZE;
+use crate::signals::SharedSignals;
+use crate::rate_limit::{self, retry_after, Endpoint};
+use crate::units::{ShareAmount, UsdcAmount};
+use crate::signer::WalletSigner;
+use crate::proxy_wallet::ProxyWallet;
+use thiserror::Error;
+use tracing::{info, instrument, warn};
+
+pub(crate) const POLYGON_CHAIN_ID: u64 = 137;
+// Cursor values of the CLOB's paginated `/data/*` endpoints
+const FIRST_CURSOR: &str = "MA==";
+const END_CURSOR: &str = "LTE=";
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+/// Assets per `/prices` request; each asking for both sides.
+const PRICES_BATCH: usize = 250;
+/// The CLOB rejects good-til-date orders that expire within this long of being posted.
+const GTD_SECURITY_THRESHOLD: u64 = 60;
+
+#[derive(Debug, Error)]
+pub enum ClobError {
+    #[error("invalid websocket URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("connection closed by server")]
+    ConnectionClosed,
+    #[error("no websocket traffic for {0:?}")]
+    Stale(Duration),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("authentication failed: {0}")]
+    Auth(String),
+    #[error("order signing failed: {0}")]
+    Signing(String),
+    #[error("no wallet attached; call with_wallet first")]
+    NoWallet,
+    #[error("invalid order: {0}")]
+    InvalidOrder(String),
+    #[error("order rejected: {0}")]
+    OrderRejected(String),
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ClobError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        ClobError::WebSocket(Box::new(e))
+    }
+}
+
+impl ClobError {
+    /// Transport hiccups worth reconnecting or retrying on. Auth, signing and
+    /// rejected orders need intervention and should not be retried blindly.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ClobError::WebSocket(_) | ClobError::ConnectionClosed | ClobError::Stale(_) => true,
+            ClobError::Http(e) => e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error() || s.as_u16() == 429),
+            _ => false,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SubscriptionMessage {
@@ -13,75 +90,1399 @@ pub struct SubscriptionMessage {
     pub asset_ids: Vec<String>,
 }
 
-#[derive(Deserialize, Debug)]
+/// A change to a live connection's asset set, sent while the stream runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subscription {
+    Add(Vec<String>),
+    /// Assets of markets that resolved or expired; the server stops pushing them.
+    Remove(Vec<String>),
+}
+
+#[derive(Debug)]
 pub struct PriceUpdate {
     pub asset_id: String,
     pub price: Decimal,
+    /// When the websocket frame carrying it arrived.
+    pub received_at: std::time::Instant,
+}
+
+/// Full L2 snapshot pushed on subscribe and after every trade.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BookMessage {
+    pub asset_id: String,
+    #[serde(default)]
+    pub market: String,
+    #[serde(default)]
+    pub bids: Vec<PriceLevel>,
+    #[serde(default)]
+    pub asks: Vec<PriceLevel>,
+}
+
+/// A single level update. `size` is the new total resting at `price`; zero removes the level.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PriceChange {
+    pub asset_id: String,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub side: OrderSide,
+    #[serde(default)]
+    pub best_bid: Option<Decimal>,
+    #[serde(default)]
+    pub best_ask: Option<Decimal>,
+}
+
+impl PriceChange {
+    /// Midpoint of the top of book after this change, falling back to the changed level.
+    pub fn reference_price(&self) -> Decimal {
+        match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) => (bid + ask) / dec!(2),
+            _ => self.price,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PriceChangeMessage {
+    #[serde(default)]
+    pub market: String,
+    #[serde(default)]
+    pub price_changes: Vec<PriceChange>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LastTradePrice {
+    pub asset_id: String,
+    #[serde(default)]
+    pub market: String,
+    pub price: Decimal,
+    #[serde(default)]
+    pub size: Decimal,
+    pub side: Option<OrderSide>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TickSizeChange {
+    pub asset_id: String,
+    pub old_tick_size: Decimal,
+    pub new_tick_size: Decimal,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderUpdateKind {
+    Placement,
+    Update,
+    Cancellation,
+}
+
+/// An `order` event on the user channel: one of the wallet's orders was placed, matched
+/// further, or cancelled.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserOrderUpdate {
+    pub id: String,
+    #[serde(default)]
+    pub market: String,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub original_size: Decimal,
+    pub size_matched: Decimal,
+    pub price: Decimal,
+    #[serde(rename = "type")]
+    pub kind: OrderUpdateKind,
+}
+
+/// Messages on the CLOB market and user channels, discriminated by `event_type`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum ClobEvent {
+    Book(BookMessage),
+    PriceChange(PriceChangeMessage),
+    LastTradePrice(LastTradePrice),
+    TickSizeChange(TickSizeChange),
+    Trade(Trade),
+    Order(UserOrderUpdate),
+    #[serde(other)]
+    Unknown,
+}
+
+/// Parses a websocket frame, which carries either one event or a batch of them.
+/// Malformed entries in a batch are skipped rather than failing the whole frame.
+pub fn parse_events(text: &str) -> Vec<ClobEvent> {
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Array(items)) => items.into_iter().filter_map(|v| serde_json::from_value(v).ok()).collect(),
+        Ok(value) => serde_json::from_value(value).map(|e| vec![e]).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Typed callbacks for market and user channel events. Every method defaults to a no-op so
+/// handlers only implement what they consume.
+#[async_trait]
+pub trait ClobEventHandler: Send {
+    async fn on_book(&mut self, _book: BookMessage) {}
+    async fn on_price_change(&mut self, _change: PriceChange) {}
+    async fn on_last_trade(&mut self, _trade: LastTradePrice) {}
+    async fn on_tick_size_change(&mut self, _change: TickSizeChange) {}
+    async fn on_trade(&mut self, _trade: Trade) {}
+    async fn on_order_update(&mut self, _update: UserOrderUpdate) {}
+    /// Called with each frame's arrival time before its events are dispatched.
+    fn on_frame(&mut self, _received_at: std::time::Instant) {}
+}
+
+async fn dispatch<H: ClobEventHandler>(handler: &mut H, event: ClobEvent) {
+    match event {
+        ClobEvent::Book(book) => handler.on_book(book).await,
+        ClobEvent::PriceChange(msg) => {
+            for change in msg.price_changes {
+                handler.on_price_change(change).await;
+            }
+        }
+        ClobEvent::LastTradePrice(trade) => handler.on_last_trade(trade).await,
+        ClobEvent::TickSizeChange(change) => handler.on_tick_size_change(change).await,
+        ClobEvent::Trade(trade) => handler.on_trade(trade).await,
+        ClobEvent::Order(update) => handler.on_order_update(update).await,
+        ClobEvent::Unknown => {}
+    }
+}
+
+/// Forwards price changes and trades to a `PriceUpdate` callback.
+struct PriceForwarder<F> {
+    callback: F,
+    received_at: std::time::Instant,
+}
+
+#[async_trait]
+impl<F, Fut> ClobEventHandler for PriceForwarder<F>
+where
+    F: Fn(PriceUpdate) -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    async fn on_price_change(&mut self, change: PriceChange) {
+        let price = change.reference_price();
+        (self.callback)(PriceUpdate { asset_id: change.asset_id, price, received_at: self.received_at }).await;
+    }
+
+    async fn on_last_trade(&mut self, trade: LastTradePrice) {
+        (self.callback)(PriceUpdate { asset_id: trade.asset_id, price: trade.price, received_at: self.received_at }).await;
+    }
+
+    fn on_frame(&mut self, received_at: std::time::Instant) {
+        self.received_at = received_at;
+    }
+}
+
+/// Keeps shared L2 books in sync with snapshots and incremental level changes, and feeds the
+/// resulting mids and the taker trades to `signals`.
+struct BookSync {
+    books: SharedOrderBooks,
+    signals: SharedSignals,
+    received_at: std::time::Instant,
+}
+
+#[async_trait]
+impl ClobEventHandler for BookSync {
+    async fn on_book(&mut self, book: BookMessage) {
+        let mut books = self.books.write().await;
+        let entry = books.entry(book.asset_id.clone()).or_default();
+        entry.apply_snapshot(&book.bids, &book.asks);
+        if let Some(mid) = entry.mid_price() {
+            self.signals.record_mid(&book.asset_id, mid, self.received_at);
+        }
+    }
+
+    async fn on_price_change(&mut self, change: PriceChange) {
+        let side = match change.side {
+            OrderSide::Buy => BookSide::Bid,
+            OrderSide::Sell => BookSide::Ask,
+        };
+        let mut books = self.books.write().await;
+        let entry = books.entry(change.asset_id.clone()).or_default();
+        entry.apply_level(side, change.price, change.size);
+        if let Some(mid) = entry.mid_price() {
+            self.signals.record_mid(&change.asset_id, mid, self.received_at);
+        }
+    }
+
+    async fn on_last_trade(&mut self, trade: LastTradePrice) {
+        if let Some(side) = trade.side {
+            self.signals.record_trade(&trade.asset_id, side, trade.size, self.received_at);
+        }
+    }
+
+    fn on_frame(&mut self, received_at: std::time::Instant) {
+        self.received_at = received_at;
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        }
+    }
+
+    fn as_u8(&self) -> u8 {
+        match self {
+            OrderSide::Buy => 0,
+            OrderSide::Sell => 1,
+        }
+    }
+}
+
+/// Order payload in the shape expected by `POST /order`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedOrder {
+    pub salt: u64,
+    pub maker: String,
+    pub signer: String,
+    pub taker: String,
+    pub token_id: String,
+    pub maker_amount: String,
+    pub taker_amount: String,
+    pub expiration: String,
+    pub nonce: String,
+    pub fee_rate_bps: String,
+    pub side: String,
+    pub signature_type: u8,
+    pub signature: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderStatus {
+    /// Resting on the book.
+    Live,
+    /// Fully matched on submission.
+    Matched,
+    /// Held by the matching engine's delay before going live.
+    Delayed,
+    /// Marketable but could not be matched.
+    Unmatched,
+    Canceled,
+    #[serde(other)]
+    Unknown,
+}
+
+/// An order as reported by `GET /data/orders` and `GET /data/order/{id}`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenOrder {
+    pub id: String,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub market: String,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub original_size: Decimal,
+    pub size_matched: Decimal,
+    pub price: Decimal,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default)]
+    pub order_type: String,
+    /// Unix seconds.
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub associate_trades: Vec<String>,
+}
+
+impl OpenOrder {
+    pub fn remaining_size(&self) -> Decimal {
+        (self.original_size - self.size_matched).max(Decimal::ZERO)
+    }
+}
+
+/// Settlement progress of a match. Trades move MATCHED -> MINED -> CONFIRMED, or to
+/// RETRYING/FAILED when the settlement transaction does not land.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TradeStatus {
+    Matched,
+    Mined,
+    Confirmed,
+    Retrying,
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A maker order filled as part of a trade.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MakerOrderFill {
+    pub order_id: String,
+    pub asset_id: String,
+    pub matched_amount: Decimal,
+    pub price: Decimal,
+}
+
+/// A fill involving the wallet, as reported by `GET /data/trades`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Trade {
+    pub id: String,
+    pub taker_order_id: String,
+    #[serde(default)]
+    pub market: String,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub size: Decimal,
+    pub price: Decimal,
+    pub status: TradeStatus,
+    /// Unix seconds, as a string.
+    #[serde(default, alias = "matchtime")]
+    pub match_time: String,
+    #[serde(default)]
+    pub transaction_hash: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub maker_orders: Vec<MakerOrderFill>,
+}
+
+/// Lists the CLOB sends as `null` instead of leaving out.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// One asset's entry in a `/prices` response.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+struct SideQuotes {
+    #[serde(rename = "BUY")]
+    buy: Option<Decimal>,
+    #[serde(rename = "SELL")]
+    sell: Option<Decimal>,
+}
+
+impl SideQuotes {
+    /// Same reference as the stream: the midpoint, or the one side quoted.
+    fn reference_price(&self) -> Option<Decimal> {
+        match (self.buy, self.sell) {
+            (Some(buy), Some(sell)) => Some((buy + sell) / dec!(2)),
+            (side, None) | (None, side) => side,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Page<T> {
+    data: Vec<T>,
+    next_cursor: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OrderResponse {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(rename = "errorMsg", default)]
+    pub error_msg: String,
+    #[serde(rename = "orderID", default)]
+    pub order_id: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+impl OrderResponse {
+    /// Stand-in for an order that was built and signed but never posted.
+    fn dry_run() -> Self {
+        Self { success: true, error_msg: String::new(), order_id: String::new(), status: "dry_run".to_string() }
+    }
+}
+
+/// One limit order to sign and submit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    pub asset_id: String,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub side: OrderSide,
+    pub tif: TimeInForce,
+}
+
+/// How long an order may work on the book, sent as the CLOB's `orderType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeInForce {
+    /// Rests until filled or cancelled.
+    #[default]
+    Gtc,
+    /// Rests until the unix timestamp (seconds), after which the exchange no longer fills it.
+    Gtd(u64),
+    /// Fills in full on arrival or not at all.
+    Fok,
+    /// Fills what it can on arrival and cancels the rest; the CLOB's "FAK".
+    Ioc,
+}
+
+impl TimeInForce {
+    /// Good-til-date, resting for `lifetime` from now on top of the CLOB's one minute
+    /// security threshold.
+    pub fn good_for(lifetime: Duration) -> Self {
+        TimeInForce::Gtd(chrono::Utc::now().timestamp() as u64 + GTD_SECURITY_THRESHOLD + lifetime.as_secs())
+    }
+
+    pub fn order_type(&self) -> &'static str {
+        match self {
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Gtd(_) => "GTD",
+            TimeInForce::Fok => "FOK",
+            TimeInForce::Ioc => "FAK",
+        }
+    }
+
+    /// The signed order's `expiration`; zero for anything but good-til-date.
+    pub fn expiration(&self) -> u64 {
+        match self {
+            TimeInForce::Gtd(at) => *at,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for TimeInForce {
+    type Err = String;
+
+    /// Good-til-date needs a timestamp, so only the other three parse.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gtc" => Ok(TimeInForce::Gtc),
+            "fok" => Ok(TimeInForce::Fok),
+            "ioc" | "fak" => Ok(TimeInForce::Ioc),
+            other => Err(format!("time in force must be gtc, fok or ioc, got \"{}\"", other)),
+        }
+    }
 }
 
 pub struct ClobClient {
     pub ws_url: String,
+    pub user_ws_url: String,
+    pub rest_url: String,
+    http: reqwest::Client,
+    wallet: Option<WalletSigner>,
+    credentials: Option<ApiCredentials>,
+    /// Proxy wallet named as the maker of every order, when the funds sit there.
+    proxy: Option<ProxyWallet>,
+    /// When set, orders are built, signed and authenticated but the request is only logged.
+    pub dry_run: bool,
+    recorder: Option<SharedRecorder>,
+    order_tracker: Option<SharedOrderTracker>,
+    /// Live markets, for each order's tick size and minimum size.
+    markets: Option<SharedMarketState>,
+    /// A stream with no frames, pongs included, for this long is treated as dead.
+    stale_after: Duration,
+}
+
+impl Default for ClobClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ClobClient {
     pub fn new() -> Self {
         let ws_url = env::var("CLOB_WS_URL").unwrap_or_else(|_| "wss://ws-subscriptions-clob.polymarket.com/ws/market".to_string());
+        let user_ws_url = env::var("CLOB_USER_WS_URL").unwrap_or_else(|_| "wss://ws-subscriptions-clob.polymarket.com/ws/user".to_string());
+        let rest_url = env::var("CLOB_REST_URL").unwrap_or_else(|_| "https://clob.polymarket.com".to_string());
         Self {
             ws_url,
+            user_ws_url,
+            rest_url,
+            http: reqwest::Client::new(),
+            wallet: None,
+            credentials: None,
+            proxy: None,
+            dry_run: false,
+            recorder: None,
+            order_tracker: None,
+            markets: None,
+            stale_after: Duration::from_secs(60),
+        }
+    }
+
+    /// Points the client at explicit websocket and REST endpoints.
+    pub fn with_urls(mut self, ws_url: &str, rest_url: &str) -> Self {
+        self.ws_url = ws_url.to_string();
+        self.rest_url = rest_url.to_string();
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Places orders from `proxy`'s funds, signed by the attached wallet.
+    pub fn with_proxy_wallet(mut self, proxy: ProxyWallet) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn with_user_ws_url(mut self, user_ws_url: &str) -> Self {
+        self.user_ws_url = user_ws_url.to_string();
+        self
+    }
+
+    /// Treats a stream with no frame for `stale_after` as dead and reconnects it.
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Appends every websocket frame this client receives to `recorder`.
+    pub fn with_recorder(mut self, recorder: SharedRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Rounds order prices to their market's tick and refuses orders below its minimum size
+    /// before signing. Without it orders are submitted as given.
+    pub fn with_markets(mut self, markets: SharedMarketState) -> Self {
+        self.markets = Some(markets);
+        self
+    }
+
+    /// Registers every accepted order with `tracker` so its fills can be reconciled.
+    pub fn with_order_tracker(mut self, tracker: SharedOrderTracker) -> Self {
+        self.order_tracker = Some(tracker);
+        self
+    }
+
+    fn record(&self, topic: &str, text: &str) {
+        let Some(recorder) = &self.recorder else { return };
+        let result = match recorder.lock() {
+            Ok(mut recorder) => recorder.record(topic, text),
+            // A panic mid-write leaves at worst a truncated line; keep recording
+            Err(poisoned) => poisoned.into_inner().record(topic, text),
+        };
+        if let Err(e) = result {
+            warn!(error = %e, "failed to record websocket message");
+        }
+    }
+
+    /// Sends `request` within `endpoint`'s share of the global CLOB budget. A 429 tightens
+    /// that budget before the response is handed back.
+    async fn send(&self, endpoint: Endpoint, request: reqwest::RequestBuilder) -> Result<reqwest::Response, ClobError> {
+        rate_limit::limiter().acquire(endpoint).await;
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            rate_limit::limiter().throttled(endpoint, retry_after(&response));
+        }
+        Ok(response)
+    }
+
+    fn signer(&self) -> Result<(&WalletSigner, &ApiCredentials), ClobError> {
+        match (&self.wallet, &self.credentials) {
+            (Some(w), Some(c)) => Ok((w, c)),
+            _ => Err(ClobError::NoWallet),
         }
     }
 
-    pub async fn stream_prices<F, Fut>(&self, asset_ids: Vec<String>, callback: F) -> Result<(), Box<dyn std::error::Error>> 
+    /// Attaches a signing wallet and L2 API credentials. Credentials come from the
+    /// POLY_API_* env vars when present, otherwise they are derived via L1 auth.
+    pub async fn with_wallet(self, private_key: &str) -> Result<Self, ClobError> {
+        let wallet = WalletSigner::from_key(private_key).map_err(|e| ClobError::Signing(e.to_string()))?;
+        self.with_signer(wallet).await
+    }
+
+    /// `with_wallet` for a key held by any signer backend.
+    pub async fn with_signer(self, wallet: WalletSigner) -> Result<Self, ClobError> {
+        self.attach_wallet(wallet, ApiCredentials::from_env()).await
+    }
+
+    /// Like `with_signer`, but always derives the L2 credentials. Used for the extra pool
+    /// wallets, since the POLY_API_* env vars belong to the primary wallet.
+    pub async fn with_derived_signer(self, wallet: WalletSigner) -> Result<Self, ClobError> {
+        self.attach_wallet(wallet, None).await
+    }
+
+    async fn attach_wallet(mut self, wallet: WalletSigner, credentials: Option<ApiCredentials>) -> Result<Self, ClobError> {
+        let credentials = match credentials {
+            Some(creds) => creds,
+            None => clob_auth::derive_api_credentials(&self.http, &self.rest_url, &wallet, POLYGON_CHAIN_ID).await?,
+        };
+        self.wallet = Some(wallet);
+        self.credentials = Some(credentials);
+        Ok(self)
+    }
+
+    /// Streams price updates for `asset_ids`. Changes received on `new_subscriptions` are
+    /// applied to the live connection without reconnecting.
+    pub async fn stream_prices<F, Fut>(&self, asset_ids: Vec<String>, new_subscriptions: &mut mpsc::UnboundedReceiver<Subscription>, callback: F) -> Result<(), ClobError> 
     where
-        F: Fn(PriceUpdate) -> Fut,
-        Fut: std::future::Future<Output = ()>,
+        F: Fn(PriceUpdate) -> Fut + Send + Sync,
+        Fut: std::future::Future<Output = ()> + Send,
     {
-        let url = Url::parse(&self.ws_url)?;
-        let (ws_stream, _) = connect_async(url).await?;
-        let (mut write, mut read) = ws_stream.split();
+        self.stream_events("prices", asset_ids, new_subscriptions, &mut PriceForwarder { callback, received_at: std::time::Instant::now() }).await
+    }
 
-        println!("Connected to CLOB WebSocket. Batching subscriptions...");
+    /// Subscribes to the `book` channel and keeps `books` in sync with every snapshot and level
+    /// change, recording mids and trades in `signals`.
+    pub async fn stream_books(&self, asset_ids: Vec<String>, new_subscriptions: &mut mpsc::UnboundedReceiver<Subscription>, books: SharedOrderBooks, signals: SharedSignals) -> Result<(), ClobError> {
+        self.stream_events("book", asset_ids, new_subscriptions, &mut BookSync { books, signals, received_at: std::time::Instant::now() }).await
+    }
 
-        for chunk in asset_ids.chunks(50) {
-            let sub = serde_json::json!({
-                "type": "subscribe",
-                "topic": "prices",
-                "asset_ids": chunk.to_vec(),
-            });
-            write.send(Message::Text(sub.to_string())).await?;
-            sleep(Duration::from_millis(100)).await;
-        }
+    /// Subscribes `asset_ids` on `topic` and routes every parsed event to `handler` until the
+    /// connection drops.
+    pub async fn stream_events<H: ClobEventHandler>(&self, topic: &str, asset_ids: Vec<String>, new_subscriptions: &mut mpsc::UnboundedReceiver<Subscription>, handler: &mut H) -> Result<(), ClobError> {
+        let (ws_stream, _) = connect_async(Url::parse(&self.ws_url)?).await?;
+        let (mut write, read) = ws_stream.split();
+
+        send_subscriptions(&mut write, "subscribe", topic, &asset_ids).await?;
+        info!(topic, assets = asset_ids.len(), "subscribed, entering live stream");
+        self.pump(topic, write, read, Some(new_subscriptions), handler).await
+    }
 
-        println!("All {} assets subscribed. Entering live stream.", asset_ids.len());
+    /// Streams the wallet's own order and trade events from the authenticated `user` channel.
+    /// An empty `markets` list subscribes to every market.
+    pub async fn stream_user<H: ClobEventHandler>(&self, markets: Vec<String>, handler: &mut H) -> Result<(), ClobError> {
+        let (_, creds) = self.signer()?;
+        let (ws_stream, _) = connect_async(Url::parse(&self.user_ws_url)?).await?;
+        let (mut write, read) = ws_stream.split();
 
+        let sub = serde_json::json!({
+            "auth": { "apiKey": creds.api_key, "secret": creds.secret, "passphrase": creds.passphrase },
+            "type": "user",
+            "markets": markets,
+        });
+        rate_limit::limiter().acquire(Endpoint::MarketData).await;
+        write.send(Message::Text(sub.to_string())).await?;
+        info!(topic = "user", "subscribed, entering live stream");
+        self.pump("user", write, read, None, handler).await
+    }
+
+    #[instrument(name = "ws", skip_all, fields(topic = %topic))]
+    async fn pump<W, R, H>(&self, topic: &str, mut write: W, mut read: R, mut new_subscriptions: Option<&mut mpsc::UnboundedReceiver<Subscription>>, handler: &mut H) -> Result<(), ClobError>
+    where
+        W: SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+        R: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+        H: ClobEventHandler,
+    {
+        // Pings keep a quiet but healthy feed talking; silence past `stale_after` means the
+        // connection died without a close frame
+        let mut ping = interval(PING_INTERVAL.min(self.stale_after / 2));
+        let mut last_frame = Instant::now();
         loop {
             tokio::select! {
+                Some(change) = next_subscription(&mut new_subscriptions) => match change {
+                    Subscription::Add(ids) => {
+                        send_subscriptions(&mut write, "subscribe", topic, &ids).await?;
+                        info!(assets = ids.len(), "subscribed to new assets");
+                    }
+                    Subscription::Remove(ids) => {
+                        send_subscriptions(&mut write, "unsubscribe", topic, &ids).await?;
+                        info!(assets = ids.len(), "unsubscribed from assets");
+                    }
+                },
                 msg = read.next() => {
+                    if matches!(msg, Some(Ok(_))) {
+                        last_frame = Instant::now();
+                    }
                     match msg {
                         Some(Ok(Message::Text(text))) => {
-                            if let Ok(update) = serde_json::from_str::<PriceUpdate>(&text) {
-                                callback(update).await;
+                            let received_at = std::time::Instant::now();
+                            self.record(topic, &text);
+                            let events = parse_events(&text);
+                            latency::record(Stage::Deserialize, received_at.elapsed());
+                            handler.on_frame(received_at);
+                            for event in events {
+                                metrics::WS_MESSAGES.with_label_values(&[topic]).inc();
+                                dispatch(handler, event).await;
                             }
                         }
                         Some(Ok(Message::Ping(payload))) => {
                             let _ = write.send(Message::Pong(payload)).await;
                         }
                         Some(Ok(Message::Close(_))) | None => {
-                            return Err("Connection closed by server".into());
+                            return Err(ClobError::ConnectionClosed);
                         }
-                        Some(Err(e)) => return Err(Box::new(e)),
+                        Some(Err(e)) => return Err(e.into()),
                         _ => (),
                     }
                 }
-                _ = sleep(Duration::from_secs(20)) => {
+                _ = sleep_until(last_frame + self.stale_after) => {
+                    metrics::WS_STALE.with_label_values(&[topic]).inc();
+                    warn!(silent_secs = self.stale_after.as_secs(), "no websocket traffic, forcing reconnect");
+                    return Err(ClobError::Stale(self.stale_after));
+                }
+                _ = ping.tick() => {
                     let _ = write.send(Message::Ping(vec![])).await;
                 }
             }
         }
     }
 
-    pub async fn place_order(&self, asset_id: &str, price: Decimal, size: Decimal, side: &str) -> Result<(), Box<dyn std::error::Error>> {
-        println!("[CLOB] Placing {} order for {} at {} (Size: {})", side, asset_id, price, size);
-        Ok(())
+    /// Builds, signs and submits a limit order for `size` shares of `asset_id` at `price`,
+    /// working the book for as long as `tif` allows.
+    #[instrument(skip(self))]
+    pub async fn place_order(&self, asset_id: &str, price: Decimal, size: Decimal, side: OrderSide, tif: TimeInForce) -> Result<OrderResponse, ClobError> {
+        let (wallet, creds) = self.signer()?;
+        let price = self.conform(asset_id, price, size, side).await?;
+
+        info!("placing order");
+        let order = build_signed_order(wallet, self.proxy, self.exchange_for(asset_id).await?, asset_id, price, size, side, tif.expiration()).await?;
+
+        let body = serde_json::json!({
+            "order": order,
+            "owner": creds.api_key,
+            "orderType": tif.order_type(),
+        }).to_string();
+        let headers = clob_auth::l2_headers(creds, wallet.address(), "POST", "/order", &body)?;
+
+        if self.dry_run {
+            info!(url = %format!("{}/order", self.rest_url), %body, "dry run: order not posted");
+            return Ok(OrderResponse::dry_run());
+        }
+
+        let request = self.http.post(format!("{}/order", self.rest_url))
+            .headers(headers)
+            .header("Content-Type", "application/json")
+            .body(body);
+        let response: OrderResponse = self.send(Endpoint::Orders, request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if !response.success {
+            return Err(ClobError::OrderRejected(response.error_msg));
+        }
+        info!(order_id = %response.order_id, status = %response.status, "order accepted");
+        self.track(&response, asset_id, side, price, size).await;
+        Ok(response)
+    }
+
+    /// Signs and submits several orders in one `POST /orders` request. The CLOB still matches
+    /// each order on its own, so this narrows the gap between legs without making them atomic.
+    /// Responses are in request order; rejected orders come back with `success == false`.
+    #[instrument(skip_all, fields(orders = orders.len()))]
+    pub async fn place_orders(&self, orders: &[OrderRequest]) -> Result<Vec<OrderResponse>, ClobError> {
+        let (wallet, creds) = self.signer()?;
+
+        let mut conformed = Vec::with_capacity(orders.len());
+        for o in orders {
+            conformed.push(OrderRequest { price: self.conform(&o.asset_id, o.price, o.size, o.side).await?, ..o.clone() });
+        }
+        let orders = &conformed[..];
+
+        let mut entries = Vec::with_capacity(orders.len());
+        for o in orders {
+            let exchange = self.exchange_for(&o.asset_id).await?;
+            let order = build_signed_order(wallet, self.proxy, exchange, &o.asset_id, o.price, o.size, o.side, o.tif.expiration()).await?;
+            entries.push(serde_json::json!({ "order": order, "owner": creds.api_key, "orderType": o.tif.order_type() }));
+        }
+        let body = serde_json::Value::Array(entries).to_string();
+        let headers = clob_auth::l2_headers(creds, wallet.address(), "POST", "/orders", &body)?;
+
+        if self.dry_run {
+            info!(url = %format!("{}/orders", self.rest_url), %body, "dry run: orders not posted");
+            return Ok(orders.iter().map(|_| OrderResponse::dry_run()).collect());
+        }
+
+        let request = self.http.post(format!("{}/orders", self.rest_url))
+            .headers(headers)
+            .header("Content-Type", "application/json")
+            .body(body);
+        let responses: Vec<OrderResponse> = self.send(Endpoint::Orders, request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        for (o, response) in orders.iter().zip(&responses) {
+            if response.success {
+                info!(order_id = %response.order_id, status = %response.status, asset_id = %o.asset_id, "order accepted");
+                self.track(response, &o.asset_id, o.side, o.price, o.size).await;
+            } else {
+                warn!(asset_id = %o.asset_id, error = %response.error_msg, "order rejected");
+            }
+        }
+        Ok(responses)
+    }
+
+    /// The exchange that settles `asset_id`, picked by whether its market is neg-risk. Assets
+    /// outside the tracked markets settle on the CTF Exchange.
+    pub async fn exchange_for(&self, asset_id: &str) -> Result<Address, ClobError> {
+        let neg_risk = match &self.markets {
+            Some(markets) => {
+                let state = markets.read().await;
+                state.markets.resolve(asset_id).and_then(|id| state.markets.get(id.market)).is_some_and(|m| m.neg_risk_market_id.is_some())
+            }
+            None => false,
+        };
+        exchange_address(neg_risk)
+    }
+
+    /// `price` on the asset's tick grid, or an error when the order is below the market minimum.
+    async fn conform(&self, asset_id: &str, price: Decimal, size: Decimal, side: OrderSide) -> Result<Decimal, ClobError> {
+        let Some(markets) = &self.markets else { return Ok(price) };
+        let state = markets.read().await;
+        let (tick, min_size) = match state.markets.resolve(asset_id).and_then(|id| state.markets.get(id.market)) {
+            Some(market) => (market.tick_size, market.min_order_size),
+            None => (DEFAULT_TICK_SIZE, Decimal::ZERO),
+        };
+        conform_order(price, size, side, tick, min_size)
+    }
+
+    async fn track(&self, response: &OrderResponse, asset_id: &str, side: OrderSide, price: Decimal, size: Decimal) {
+        if let (Some(tracker), false) = (&self.order_tracker, response.order_id.is_empty()) {
+            let lifecycle = OrderLifecycle::new(&response.order_id, asset_id, side, price, size, chrono::Utc::now());
+            tracker.write().await.track(lifecycle);
+        }
+    }
+
+    /// Cancels every open order belonging to the attached wallet.
+    pub async fn cancel_all(&self) -> Result<CancelResponse, ClobError> {
+        let (wallet, creds) = self.signer()?;
+
+        let headers = clob_auth::l2_headers(creds, wallet.address(), "DELETE", "/cancel-all", "")?;
+        if self.dry_run {
+            info!(url = %format!("{}/cancel-all", self.rest_url), "dry run: cancel-all not sent");
+            return Ok(CancelResponse::default());
+        }
+        let request = self.http.delete(format!("{}/cancel-all", self.rest_url)).headers(headers);
+        let response: CancelResponse = self.send(Endpoint::Cancels, request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        info!(count = response.canceled.len(), "cancelled open orders");
+        Ok(response)
+    }
+
+    /// Cancels a single order by id.
+    pub async fn cancel_order(&self, order_id: &str) -> Result<CancelResponse, ClobError> {
+        let (wallet, creds) = self.signer()?;
+        let body = serde_json::json!({ "orderID": order_id }).to_string();
+        let headers = clob_auth::l2_headers(creds, wallet.address(), "DELETE", "/order", &body)?;
+        if self.dry_run {
+            info!(url = %format!("{}/order", self.rest_url), %body, "dry run: cancel not sent");
+            return Ok(CancelResponse { canceled: vec![order_id.to_string()], ..CancelResponse::default() });
+        }
+        let request = self.http.delete(format!("{}/order", self.rest_url))
+            .headers(headers)
+            .header("Content-Type", "application/json")
+            .body(body);
+        let response: CancelResponse = self.send(Endpoint::Cancels, request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response)
+    }
+
+    /// The wallet's live orders, optionally narrowed to one market (condition id) or asset.
+    pub async fn get_open_orders(&self, market: Option<&str>, asset_id: Option<&str>) -> Result<Vec<OpenOrder>, ClobError> {
+        let filters = [("market", market), ("asset_id", asset_id)];
+        self.get_paginated("/data/orders", &filters).await
+    }
+
+    /// Current state of one order, or `None` if the CLOB does not know it.
+    pub async fn get_order_status(&self, order_id: &str) -> Result<Option<OpenOrder>, ClobError> {
+        let (wallet, creds) = self.signer()?;
+        let path = format!("/data/order/{}", order_id);
+        let headers = clob_auth::l2_headers(creds, wallet.address(), "GET", &path, "")?;
+        let request = self.http.get(format!("{}{}", self.rest_url, path)).headers(headers);
+        let response = self.send(Endpoint::MarketData, request).await?.error_for_status()?;
+        // Unknown ids come back as an empty body or `null`
+        let text = response.text().await?;
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// The wallet's trades, optionally narrowed to one market (condition id) or asset.
+    pub async fn get_trades(&self, market: Option<&str>, asset_id: Option<&str>) -> Result<Vec<Trade>, ClobError> {
+        let filters = [("market", market), ("asset_id", asset_id)];
+        self.get_paginated("/data/trades", &filters).await
+    }
+
+    /// Current reference prices for `asset_ids` from the public `/prices` endpoint, used to
+    /// catch up on moves missed while the price stream was down. Unquoted assets are omitted.
+    pub async fn fetch_prices(&self, asset_ids: &[String]) -> Result<HashMap<String, Decimal>, ClobError> {
+        let mut prices = HashMap::new();
+        for batch in asset_ids.chunks(PRICES_BATCH) {
+            let body: Vec<_> = batch.iter()
+                .flat_map(|id| ["BUY", "SELL"].map(|side| serde_json::json!({ "token_id": id, "side": side })))
+                .collect();
+            let request = self.http.post(format!("{}/prices", self.rest_url)).json(&body);
+            let quotes: HashMap<String, SideQuotes> = self.send(Endpoint::MarketData, request)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            prices.extend(quotes.into_iter().filter_map(|(id, q)| Some((id, q.reference_price()?))));
+        }
+        Ok(prices)
+    }
+
+    /// Cancels live orders older than `max_age`, returning the ids that were cancelled.
+    pub async fn cancel_stale_orders(&self, max_age: Duration) -> Result<Vec<String>, ClobError> {
+        let cutoff = chrono::Utc::now().timestamp() - max_age.as_secs() as i64;
+        let mut cancelled = Vec::new();
+        for order in self.get_open_orders(None, None).await? {
+            if order.status == OrderStatus::Live && order.created_at < cutoff {
+                info!(order_id = %order.id, asset_id = %order.asset_id, remaining = %order.remaining_size(), price = %order.price, "cancelling stale order");
+                cancelled.extend(self.cancel_order(&order.id).await?.canceled);
+            }
+        }
+        Ok(cancelled)
+    }
+
+    /// Follows `next_cursor` until the CLOB signals the last page. Filters are query
+    /// parameters, which are not part of the signed path.
+    async fn get_paginated<T: serde::de::DeserializeOwned>(&self, path: &str, filters: &[(&str, Option<&str>)]) -> Result<Vec<T>, ClobError> {
+        let (wallet, creds) = self.signer()?;
+        let filters: Vec<(&str, &str)> = filters.iter().filter_map(|(k, v)| v.map(|v| (*k, v))).collect();
+        let mut cursor = FIRST_CURSOR.to_string();
+        let mut items = Vec::new();
+        while cursor != END_CURSOR {
+            let mut query = filters.clone();
+            query.push(("next_cursor", &cursor));
+            let headers = clob_auth::l2_headers(creds, wallet.address(), "GET", path, "")?;
+            let request = self.http.get(format!("{}{}", self.rest_url, path)).headers(headers).query(&query);
+            let page: Page<T> = self.send(Endpoint::MarketData, request)
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            items.extend(page.data);
+            if page.next_cursor.is_empty() {
+                break;
+            }
+            cursor = page.next_cursor;
+        }
+        Ok(items)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CancelResponse {
+    #[serde(default)]
+    pub canceled: Vec<String>,
+    #[serde(default)]
+    pub not_canceled: serde_json::Value,
+}
+
+/// Next subscription change, or never when the stream takes no new subscriptions.
+async fn next_subscription(rx: &mut Option<&mut mpsc::UnboundedReceiver<Subscription>>) -> Option<Subscription> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Sends `kind` ("subscribe" or "unsubscribe") frames for `asset_ids` in batches of 50 to stay
+/// under the server's frame limits, each drawing on the market data budget.
+async fn send_subscriptions<S>(write: &mut S, kind: &str, topic: &str, asset_ids: &[String]) -> Result<(), ClobError>
+where
+    S: SinkExt<Message, Error = tokio_tungstenite::tungstenite::Error> + Unpin,
+{
+    for chunk in asset_ids.chunks(50) {
+        let sub = serde_json::json!({
+            "type": kind,
+            "topic": topic,
+            "asset_ids": chunk.to_vec(),
+        });
+        rate_limit::limiter().acquire(Endpoint::MarketData).await;
+        write.send(Message::Text(sub.to_string())).await?;
+        sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
+
+/// Rounds `price` onto the `tick` grid in our favour, buys down and sells up, so a price
+/// computed as a limit is never crossed. Orders below `min_size` shares, or whose price
+/// rounds out of (0, 1), are refused.
+pub fn conform_order(price: Decimal, size: Decimal, side: OrderSide, tick: Decimal, min_size: Decimal) -> Result<Decimal, ClobError> {
+    if size < min_size {
+        return Err(ClobError::InvalidOrder(format!("size {} below market minimum {}", size, min_size)));
+    }
+    let ticks = price / tick;
+    let rounded = match side {
+        OrderSide::Buy => ticks.floor(),
+        OrderSide::Sell => ticks.ceil(),
+    } * tick;
+    if rounded <= Decimal::ZERO || rounded >= Decimal::ONE {
+        return Err(ClobError::InvalidOrder(format!("price {} rounds outside (0, 1) on a {} tick", price, tick)));
+    }
+    Ok(rounded)
+}
+
+/// Converts price/size into (makerAmount, takerAmount) in 6-decimal base units.
+/// A buyer gives USDC and receives shares; a seller gives shares and receives USDC.
+pub fn order_amounts(price: Decimal, size: Decimal, side: OrderSide) -> Result<(U256, U256), ClobError> {
+    let out_of_range = || ClobError::InvalidOrder(format!("amount out of range: {} @ {}", size, price));
+    let shares = ShareAmount(size).to_units().ok_or_else(out_of_range)?;
+    let collateral = UsdcAmount(price * size).to_units().ok_or_else(out_of_range)?;
+    Ok(match side {
+        OrderSide::Buy => (collateral, shares),
+        OrderSide::Sell => (shares, collateral),
+    })
+}
+
+/// The exchange that verifies orders as their EIP-712 verifying contract: the NegRisk CTF
+/// Exchange for neg-risk markets, the CTF Exchange otherwise.
+fn exchange_address(neg_risk: bool) -> Result<Address, ClobError> {
+    let exchange_str = if neg_risk {
+        env::var("NEG_RISK_CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS.to_string())
+    } else {
+        env::var("CTF_EXCHANGE_ADDRESS").unwrap_or_else(|_| DEFAULT_CTF_EXCHANGE_ADDRESS.to_string())
+    };
+    Address::from_str(&exchange_str).map_err(|e| ClobError::InvalidOrder(format!("bad exchange address: {}", e)))
+}
+
+/// EIP-712 typed data for a CTF Exchange order.
+#[allow(clippy::too_many_arguments)]
+pub fn order_typed_data(exchange: Address, chain_id: u64, salt: u64, signer: Address, funder: Option<ProxyWallet>, token_id: U256, maker_amount: U256, taker_amount: U256, side: OrderSide, expiration: u64) -> Result<TypedData, serde_json::Error> {
+    let (maker, signature_type) = funder.map_or((signer, 0), |p| (p.address, p.kind.signature_type()));
+    serde_json::from_value(serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "version", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+                {"name": "verifyingContract", "type": "address"},
+            ],
+            "Order": [
+                {"name": "salt", "type": "uint256"},
+                {"name": "maker", "type": "address"},
+                {"name": "signer", "type": "address"},
+                {"name": "taker", "type": "address"},
+                {"name": "tokenId", "type": "uint256"},
+                {"name": "makerAmount", "type": "uint256"},
+                {"name": "takerAmount", "type": "uint256"},
+                {"name": "expiration", "type": "uint256"},
+                {"name": "nonce", "type": "uint256"},
+                {"name": "feeRateBps", "type": "uint256"},
+                {"name": "side", "type": "uint8"},
+                {"name": "signatureType", "type": "uint8"},
+            ],
+        },
+        "primaryType": "Order",
+        "domain": {
+            "name": "Polymarket CTF Exchange",
+            "version": "1",
+            "chainId": chain_id,
+            "verifyingContract": format!("{:?}", exchange),
+        },
+        "message": {
+            "salt": salt,
+            "maker": format!("{:?}", maker),
+            "signer": format!("{:?}", signer),
+            "taker": format!("{:?}", Address::zero()),
+            "tokenId": token_id.to_string(),
+            "makerAmount": maker_amount.to_string(),
+            "takerAmount": taker_amount.to_string(),
+            "expiration": expiration.to_string(),
+            "nonce": "0",
+            "feeRateBps": "0",
+            "side": side.as_u8(),
+            "signatureType": signature_type,
+        },
+    }))
+}
+
+/// Builds the order struct and signs it with the wallet key. The maker is `funder` when the
+/// funds sit in a proxy wallet, the wallet itself otherwise. An `expiration` of zero never expires.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_signed_order<S: Signer>(wallet: &S, funder: Option<ProxyWallet>, exchange: Address, asset_id: &str, price: Decimal, size: Decimal, side: OrderSide, expiration: u64) -> Result<SignedOrder, ClobError> {
+    let token_id = U256::from_dec_str(asset_id).map_err(|e| ClobError::InvalidOrder(format!("bad token id {}: {}", asset_id, e)))?;
+    let (maker_amount, taker_amount) = order_amounts(price, size, side)?;
+    let salt = ethers::core::rand::random::<u32>() as u64;
+    let typed_data = order_typed_data(exchange, wallet.chain_id(), salt, wallet.address(), funder, token_id, maker_amount, taker_amount, side, expiration)?;
+    let signature = wallet.sign_typed_data(&typed_data).await.map_err(|e| ClobError::Signing(e.to_string()))?;
+
+    Ok(SignedOrder {
+        salt,
+        maker: format!("{:?}", funder.map_or(wallet.address(), |p| p.address)),
+        signer: format!("{:?}", wallet.address()),
+        taker: format!("{:?}", Address::zero()),
+        token_id: token_id.to_string(),
+        maker_amount: maker_amount.to_string(),
+        taker_amount: taker_amount.to_string(),
+        expiration: expiration.to_string(),
+        nonce: "0".to_string(),
+        fee_rate_bps: "0".to_string(),
+        side: side.as_str().to_string(),
+        signature_type: funder.map_or(0, |p| p.kind.signature_type()),
+        signature: format!("0x{}", signature),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage_engine::PatternRegistry;
+    use crate::market_state::MarketState;
+    use crate::proxy_wallet::WalletKind;
+    use crate::shared_types::{Condition, Market, OutcomeKind};
+    use std::sync::Arc;
+    use crate::signals::{SignalConfig, Signals};
+    use ethers::types::transaction::eip712::Eip712;
+
+    #[test]
+    fn test_order_amounts_by_side() {
+        let (maker, taker) = order_amounts(dec!(0.45), dec!(10), OrderSide::Buy).unwrap();
+        assert_eq!(maker, U256::from(4_500_000u64));
+        assert_eq!(taker, U256::from(10_000_000u64));
+
+        let (maker, taker) = order_amounts(dec!(0.45), dec!(10), OrderSide::Sell).unwrap();
+        assert_eq!(maker, U256::from(10_000_000u64));
+        assert_eq!(taker, U256::from(4_500_000u64));
+    }
+
+    #[test]
+    fn test_conform_order_rounds_to_tick_in_our_favour() {
+        assert_eq!(conform_order(dec!(0.4567), dec!(10), OrderSide::Buy, dec!(0.01), dec!(5)).unwrap(), dec!(0.45));
+        assert_eq!(conform_order(dec!(0.4512), dec!(10), OrderSide::Sell, dec!(0.01), dec!(5)).unwrap(), dec!(0.46));
+        assert_eq!(conform_order(dec!(0.9712), dec!(10), OrderSide::Buy, dec!(0.001), dec!(5)).unwrap(), dec!(0.971));
+        assert!(matches!(conform_order(dec!(0.45), dec!(4.99), OrderSide::Buy, dec!(0.01), dec!(5)), Err(ClobError::InvalidOrder(_))));
+        assert!(conform_order(dec!(0.004), dec!(10), OrderSide::Buy, dec!(0.01), Decimal::ZERO).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signed_order_recovers_to_maker() {
+        let wallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse::<LocalWallet>().unwrap().with_chain_id(POLYGON_CHAIN_ID);
+        let exchange = Address::from_str(DEFAULT_CTF_EXCHANGE_ADDRESS).unwrap();
+        let order = build_signed_order(&wallet, None, exchange, "1234", dec!(0.5), dec!(2), OrderSide::Buy, 0).await.unwrap();
+
+        let typed_data = order_typed_data(
+            exchange, POLYGON_CHAIN_ID, order.salt, wallet.address(), None, U256::from(1234),
+            U256::from_dec_str(&order.maker_amount).unwrap(), U256::from_dec_str(&order.taker_amount).unwrap(), OrderSide::Buy, 0,
+        ).unwrap();
+        let signature = Signature::from_str(&order.signature).unwrap();
+        let hash = typed_data.encode_eip712().unwrap();
+        assert_eq!(signature.recover(hash).unwrap(), wallet.address());
+
+        // A Safe-funded order names the Safe as maker but is still signed by the owner key, and
+        // a good-til-date expiration is part of what it signs
+        let safe = ProxyWallet { kind: WalletKind::GnosisSafe, address: Address::from_low_u64_be(0x5afe) };
+        let order = build_signed_order(&wallet, Some(safe), exchange, "1234", dec!(0.5), dec!(2), OrderSide::Buy, 1_700_000_000).await.unwrap();
+        assert_eq!((order.maker, order.signature_type, order.expiration.as_str()), (format!("{:?}", safe.address), 2, "1700000000"));
+        let typed_data = order_typed_data(
+            exchange, POLYGON_CHAIN_ID, order.salt, wallet.address(), Some(safe), U256::from(1234),
+            U256::from_dec_str(&order.maker_amount).unwrap(), U256::from_dec_str(&order.taker_amount).unwrap(), OrderSide::Buy, 1_700_000_000,
+        ).unwrap();
+        let signature = Signature::from_str(&order.signature).unwrap();
+        assert_eq!(signature.recover(typed_data.encode_eip712().unwrap()).unwrap(), wallet.address());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_order_is_signed_but_not_sent() {
+        let client = ClobClient {
+            ws_url: String::new(),
+            user_ws_url: String::new(),
+            rest_url: "http://127.0.0.1:9".to_string(),
+            http: reqwest::Client::new(),
+            wallet: Some(WalletSigner::from_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap()),
+            credentials: Some(ApiCredentials { api_key: "key".to_string(), secret: "c2VjcmV0".to_string(), passphrase: "pass".to_string() }),
+            proxy: None,
+            dry_run: true,
+            recorder: None,
+            order_tracker: None,
+            markets: None,
+            stale_after: Duration::from_secs(60),
+        };
+        let response = client.place_order("1234", dec!(0.5), dec!(2), OrderSide::Buy, TimeInForce::Fok).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.status, "dry_run");
+    }
+
+    #[tokio::test]
+    async fn test_neg_risk_markets_sign_for_the_neg_risk_exchange() {
+        let condition = |asset_id: &str| Condition { name: "Yes".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: asset_id.to_string(), kind: OutcomeKind::Yes, last_updated: None };
+        let markets = vec![
+            Market { id: "plain".to_string(), conditions: vec![condition("1")], ..Market::default() },
+            Market { id: "member".to_string(), conditions: vec![condition("2")], neg_risk_market_id: Some("0xbasket".to_string()), ..Market::default() },
+        ];
+        let state = MarketState::build(markets, 0.8, Arc::new(PatternRegistry::default()));
+        let client = ClobClient::new().with_markets(Arc::new(tokio::sync::RwLock::new(state)));
+        let exchange = |address: &str| Address::from_str(address).unwrap();
+        assert_eq!(client.exchange_for("1").await.unwrap(), exchange(DEFAULT_CTF_EXCHANGE_ADDRESS));
+        assert_eq!(client.exchange_for("2").await.unwrap(), exchange(DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS));
+        assert_eq!(client.exchange_for("unknown").await.unwrap(), exchange(DEFAULT_CTF_EXCHANGE_ADDRESS));
+    }
+
+    #[test]
+    fn test_time_in_force_maps_to_order_fields() {
+        let fields = |tif: TimeInForce| (tif.order_type(), tif.expiration());
+        assert_eq!(fields(TimeInForce::default()), ("GTC", 0));
+        assert_eq!(fields(TimeInForce::Gtd(1_700_000_000)), ("GTD", 1_700_000_000));
+        assert_eq!(fields(TimeInForce::Fok), ("FOK", 0));
+        assert_eq!(fields(TimeInForce::Ioc), ("FAK", 0));
+
+        // Expiring sooner than the security threshold would be rejected
+        let now = chrono::Utc::now().timestamp() as u64;
+        assert!(TimeInForce::good_for(Duration::from_secs(30)).expiration() >= now + GTD_SECURITY_THRESHOLD + 30);
+
+        assert_eq!("FAK".parse::<TimeInForce>(), Ok(TimeInForce::Ioc));
+        assert!("gtd".parse::<TimeInForce>().is_err());
+    }
+
+    #[test]
+    fn test_rest_order_and_trade_pages_deserialize() {
+        let orders: Page<OpenOrder> = serde_json::from_str(r#"{
+            "data": [{"id": "0xabc", "status": "LIVE", "market": "0xcond", "asset_id": "1234", "side": "BUY",
+                      "original_size": "100", "size_matched": "40", "price": "0.45", "outcome": "Yes",
+                      "order_type": "GTC", "created_at": 1700000000, "associate_trades": ["t1"], "owner": "key"}],
+            "next_cursor": "LTE=", "limit": 100, "count": 1
+        }"#).unwrap();
+        assert_eq!(orders.next_cursor, END_CURSOR);
+        assert_eq!(orders.data[0].status, OrderStatus::Live);
+        assert_eq!(orders.data[0].remaining_size(), dec!(60));
+
+        let trades: Page<Trade> = serde_json::from_str(r#"{
+            "data": [{"id": "t1", "taker_order_id": "0xdef", "market": "0xcond", "asset_id": "1234", "side": "SELL",
+                      "size": "40", "price": "0.45", "status": "MINED", "match_time": "1700000001",
+                      "maker_orders": [{"order_id": "0xabc", "asset_id": "1234", "matched_amount": "40", "price": "0.45", "outcome": "Yes"}]}],
+            "next_cursor": "MTAw"
+        }"#).unwrap();
+        assert_eq!(trades.data[0].status, TradeStatus::Mined);
+        assert_eq!(trades.data[0].maker_orders[0].order_id, "0xabc");
+    }
+
+    #[test]
+    fn test_parse_user_channel_events() {
+        let text = r#"[
+            {"event_type": "order", "id": "0xabc", "market": "0xcond", "asset_id": "1234", "side": "BUY",
+             "original_size": "10", "size_matched": "4", "price": "0.5", "type": "UPDATE", "timestamp": "1700000000"},
+            {"event_type": "trade", "id": "t1", "taker_order_id": "0xdef", "market": "0xcond", "asset_id": "1234",
+             "side": "SELL", "size": "4", "price": "0.5", "status": "MATCHED", "matchtime": "1700000000", "type": "TRADE",
+             "maker_orders": [{"order_id": "0xabc", "asset_id": "1234", "matched_amount": "4", "price": "0.5"}]}
+        ]"#;
+        let events = parse_events(text);
+        assert!(matches!(&events[0], ClobEvent::Order(o) if o.kind == OrderUpdateKind::Update && o.size_matched == dec!(4)));
+        assert!(matches!(&events[1], ClobEvent::Trade(t) if t.match_time == "1700000000" && t.maker_orders.len() == 1));
+    }
+
+    #[test]
+    fn test_parse_events_handles_batches_and_unknown_types() {
+        let text = r#"[
+            {"event_type": "book", "asset_id": "1", "market": "0xabc", "bids": [{"price": "0.48", "size": "30"}], "asks": [{"price": "0.52", "size": "25"}]},
+            {"event_type": "price_change", "market": "0xabc", "price_changes": [{"asset_id": "1", "price": "0.49", "size": "10", "side": "BUY", "best_bid": "0.49", "best_ask": "0.52"}]},
+            {"event_type": "last_trade_price", "asset_id": "1", "price": "0.5", "size": "5", "side": "SELL"},
+            {"event_type": "tick_size_change", "asset_id": "1", "old_tick_size": "0.01", "new_tick_size": "0.001"},
+            {"event_type": "something_new"}
+        ]"#;
+        let events = parse_events(text);
+        assert_eq!(events.len(), 5);
+        assert!(matches!(&events[0], ClobEvent::Book(b) if b.bids[0].price == dec!(0.48)));
+        match &events[1] {
+            ClobEvent::PriceChange(msg) => {
+                assert_eq!(msg.price_changes[0].side, OrderSide::Buy);
+                assert_eq!(msg.price_changes[0].reference_price(), dec!(0.505));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(matches!(&events[2], ClobEvent::LastTradePrice(t) if t.side == Some(OrderSide::Sell)));
+        assert!(matches!(events[4], ClobEvent::Unknown));
+
+        let single = parse_events(r#"{"event_type": "last_trade_price", "asset_id": "2", "price": "0.1"}"#);
+        assert!(matches!(&single[0], ClobEvent::LastTradePrice(t) if t.asset_id == "2"));
+    }
+
+    #[tokio::test]
+    async fn test_book_sync_applies_snapshots_and_level_changes() {
+        let books: SharedOrderBooks = std::sync::Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new()));
+        let signals = std::sync::Arc::new(Signals::new(SignalConfig::default()));
+        let mut sync = BookSync { books: books.clone(), signals: signals.clone(), received_at: std::time::Instant::now() };
+        let text = r#"[
+            {"event_type": "book", "asset_id": "1", "bids": [{"price": "0.48", "size": "30"}], "asks": [{"price": "0.52", "size": "25"}]},
+            {"event_type": "price_change", "price_changes": [{"asset_id": "1", "price": "0.52", "size": "0", "side": "SELL"}, {"asset_id": "1", "price": "0.55", "size": "7", "side": "SELL"}]},
+            {"event_type": "last_trade_price", "asset_id": "1", "price": "0.48", "size": "5", "side": "SELL"}
+        ]"#;
+        for event in parse_events(text) {
+            dispatch(&mut sync, event).await;
+        }
+        let books = books.read().await;
+        assert_eq!(books["1"].best_bid().unwrap().price, dec!(0.48));
+        assert_eq!(books["1"].best_ask().unwrap().price, dec!(0.55));
+        let signal = signals.signal("1", None, std::time::Instant::now());
+        assert_eq!((signal.imbalance, signal.momentum), (Some(dec!(-1)), Some(dec!(0.03))));
+    }
+
+    #[test]
+    fn test_prices_response_resolves_to_reference_prices() {
+        let quotes: HashMap<String, SideQuotes> = serde_json::from_str(r#"{
+            "1": {"BUY": "0.48", "SELL": "0.52"},
+            "2": {"SELL": "0.3"},
+            "3": {}
+        }"#).unwrap();
+        assert_eq!(quotes["1"].reference_price(), Some(dec!(0.50)));
+        assert_eq!(quotes["2"].reference_price(), Some(dec!(0.3)));
+        assert_eq!(quotes["3"].reference_price(), None);
+    }
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("{}/tests/fixtures/clob/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn test_recorded_websocket_frames_parse() {
+        let books = parse_events(&fixture("ws_book.json"));
+        let [ClobEvent::Book(full), ClobEvent::Book(empty)] = &books[..] else { panic!("{:?}", books) };
+        assert_eq!((full.bids.len(), full.asks.len()), (2, 2));
+        assert!(empty.bids.is_empty() && empty.asks.is_empty());
+
+        let [ClobEvent::PriceChange(msg)] = &parse_events(&fixture("ws_price_change.json"))[..] else { panic!() };
+        assert_eq!(msg.price_changes[0].reference_price(), dec!(0.2855));
+        // A removed level without top of book falls back to its own price
+        assert_eq!((msg.price_changes[1].size, msg.price_changes[1].reference_price()), (Decimal::ZERO, dec!(0.715)));
+
+        // Unknown event types are kept as such and the entry missing its price is dropped
+        let events = parse_events(&fixture("ws_market_events.json"));
+        assert!(matches!(&events[..], [ClobEvent::LastTradePrice(t), ClobEvent::TickSizeChange(c), ClobEvent::Unknown]
+            if t.side == Some(OrderSide::Buy) && t.size == dec!(41.5) && c.new_tick_size == dec!(0.001)));
+
+        let events = parse_events(&fixture("ws_user_events.json"));
+        let [ClobEvent::Trade(trade), ClobEvent::Order(order)] = &events[..] else { panic!("{:?}", events) };
+        assert_eq!((trade.status, trade.match_time.as_str(), trade.maker_orders[0].matched_amount), (TradeStatus::Matched, "1731002737", dec!(10)));
+        assert_eq!((order.kind, order.size_matched), (OrderUpdateKind::Update, dec!(10)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_recorded_rest_responses_parse() {
+        let orders: Page<OpenOrder> = serde_json::from_str(&fixture("rest_orders.json")).unwrap();
+        assert_eq!(orders.next_cursor, "LTE=");
+        assert_eq!((orders.data[0].status, orders.data[0].remaining_size(), orders.data[0].created_at), (OrderStatus::Live, dec!(15), 1731002700));
+        // Unknown statuses and null trade lists do not fail the page
+        assert_eq!((orders.data[1].status, orders.data[1].associate_trades.len()), (OrderStatus::Unknown, 0));
+
+        let trades: Page<Trade> = serde_json::from_str(&fixture("rest_trades.json")).unwrap();
+        assert_eq!((trades.data[0].status, trades.data[0].maker_orders.len()), (TradeStatus::Confirmed, 1));
+        assert!(trades.data[0].transaction_hash.starts_with("0x9d5e"));
+
+        let prices: HashMap<String, SideQuotes> = serde_json::from_str(&fixture("rest_prices.json")).unwrap();
+        let mut references: Vec<Option<Decimal>> = prices.values().map(SideQuotes::reference_price).collect();
+        references.sort();
+        assert_eq!(references, vec![None, Some(dec!(0.2855)), Some(dec!(0.715))]);
+
+        let posted: Vec<OrderResponse> = serde_json::from_str(&fixture("rest_post_orders.json")).unwrap();
+        assert!(posted[0].success && posted[0].status == "matched");
+        assert_eq!((posted[1].success, posted[1].error_msg.as_str()), (false, "not enough balance / allowance"));
+
+        let cancel: CancelResponse = serde_json::from_str(&fixture("rest_cancel.json")).unwrap();
+        assert_eq!(cancel.canceled.len(), 1);
+        assert!(cancel.not_canceled.is_object());
+    }
+
+    #[tokio::test]
+    async fn test_silent_stream_is_reported_stale() {
+        let client = ClobClient::new().with_stale_after(Duration::from_millis(50));
+        let write = futures::sink::drain().sink_map_err(|e: std::convert::Infallible| match e {});
+        let read = futures::stream::pending();
+        let mut handler = PriceForwarder { callback: |_| async {}, received_at: std::time::Instant::now() };
+        let result = tokio::time::timeout(Duration::from_secs(2), client.pump("prices", write, read, None, &mut handler)).await;
+        assert!(matches!(result, Ok(Err(ClobError::Stale(_)))));
+    }
+}
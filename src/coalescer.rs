@@ -0,0 +1,125 @@
+use crate::clob_client::PriceUpdate;
+use crate::metrics::EVALUATIONS_SUPPRESSED;
+use futures::stream::{self, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+struct Slot {
+    price: Decimal,
+    first_seen: Instant,
+    /// Arrival of the oldest tick folded in, so latency covers the whole wait.
+    received_at: Instant,
+}
+
+/// Folds rapid ticks on the same asset into one engine run. The first tick for an asset opens
+/// a `window`; later ticks only replace the pending price, and when the window closes the engine
+/// runs once at the latest price. A zero window evaluates at the next chance the runner gets.
+pub struct Coalescer {
+    window: Duration,
+    pending: Mutex<HashMap<String, Slot>>,
+    wake: Notify,
+}
+
+impl Coalescer {
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: Mutex::new(HashMap::new()), wake: Notify::new() }
+    }
+
+    /// Queues a tick. Cheap enough to call from the websocket read loop.
+    pub fn offer(&self, update: PriceUpdate) {
+        self.offer_at(update, Instant::now());
+    }
+
+    fn offer_at(&self, update: PriceUpdate, now: Instant) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.get_mut(&update.asset_id) {
+            Some(slot) => {
+                slot.price = update.price;
+                EVALUATIONS_SUPPRESSED.inc();
+            }
+            None => {
+                pending.insert(update.asset_id, Slot { price: update.price, first_seen: now, received_at: update.received_at });
+                drop(pending);
+                self.wake.notify_one();
+            }
+        }
+    }
+
+    /// Removes the assets whose window has closed, oldest first, and returns the next deadline.
+    fn take_due(&self, now: Instant) -> (Vec<PriceUpdate>, Option<Instant>) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let mut due: Vec<_> = pending.iter()
+            .filter(|(_, slot)| slot.first_seen + self.window <= now)
+            .map(|(asset_id, slot)| (slot.first_seen, asset_id.clone()))
+            .collect();
+        due.sort();
+        let due = due.into_iter()
+            .filter_map(|(_, asset_id)| pending.remove_entry(&asset_id))
+            .map(|(asset_id, slot)| PriceUpdate { asset_id, price: slot.price, received_at: slot.received_at })
+            .collect();
+        let next = pending.values().map(|slot| slot.first_seen + self.window).min();
+        (due, next)
+    }
+
+    /// Runs `evaluate` once per closed window until the task is dropped. Assets whose windows
+    /// close together are evaluated concurrently.
+    pub async fn run<F, Fut>(self: Arc<Self>, evaluate: F)
+    where
+        F: Fn(PriceUpdate) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        loop {
+            let (due, next) = self.take_due(Instant::now());
+            if !due.is_empty() {
+                // Each asset appears once per batch, so its runs never overlap
+                stream::iter(due).for_each_concurrent(None, &evaluate).await;
+                continue;
+            }
+            match next {
+                Some(deadline) => tokio::select! {
+                    _ = tokio::time::sleep_until(deadline.into()) => {}
+                    _ = self.wake.notified() => {}
+                },
+                None => self.wake.notified().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn tick(asset_id: &str, price: Decimal) -> PriceUpdate {
+        PriceUpdate { asset_id: asset_id.to_string(), price, received_at: Instant::now() }
+    }
+
+    #[test]
+    fn test_ticks_within_a_window_collapse_to_the_latest_price() {
+        let coalescer = Coalescer::new(Duration::from_millis(25));
+        let start = Instant::now();
+        coalescer.offer_at(tick("a", dec!(0.40)), start);
+        coalescer.offer_at(tick("a", dec!(0.41)), start + Duration::from_millis(5));
+        coalescer.offer_at(tick("b", dec!(0.70)), start + Duration::from_millis(10));
+        coalescer.offer_at(tick("a", dec!(0.42)), start + Duration::from_millis(20));
+
+        let (due, next) = coalescer.take_due(start + Duration::from_millis(24));
+        assert!(due.is_empty());
+        assert_eq!(next, Some(start + Duration::from_millis(25)));
+
+        let (due, next) = coalescer.take_due(start + Duration::from_millis(30));
+        let due: Vec<_> = due.into_iter().map(|u| (u.asset_id, u.price)).collect();
+        assert_eq!(due, vec![("a".to_string(), dec!(0.42))]);
+        assert_eq!(next, Some(start + Duration::from_millis(35)));
+
+        // A tick after the flush opens a fresh window
+        coalescer.offer_at(tick("a", dec!(0.43)), start + Duration::from_millis(31));
+        let (due, _) = coalescer.take_due(start + Duration::from_millis(40));
+        assert_eq!(due.iter().map(|u| u.asset_id.as_str()).collect::<Vec<_>>(), vec!["b"]);
+    }
+}
@@ -0,0 +1,1575 @@
+use crate::approvals::ApprovalPolicy;
+use crate::arbitrage_engine::PatternRegistry;
+use crate::copy_trader::CopyConfig;
+use crate::balances::BalanceLimits;
+use crate::blockchain::PoolConfig;
+use crate::category_params::{CategoryOverrides, CategoryParams};
+use crate::execution_guard::{CircuitBreaker, ExecutionGuard, RetryPolicy};
+use crate::clob_client::TimeInForce;
+use crate::execution_analyzer::FollowCriteria;
+use crate::execution_planner::{ExecutionStyle, LegSubmission, PlannerConfig, RollbackPolicy};
+use crate::opportunity_store::OpportunityKind;
+use crate::quoting::{QuotingConfig, RebalancingMode};
+use crate::fees::FeeModel;
+use crate::fill_ingest::IngestConfig;
+use crate::expiry::ExpiryPolicy;
+use crate::gas::{GasPolicy, GasPreset};
+use crate::kill_switch::KillSwitchConfig;
+use crate::logging::LogFormat;
+use crate::market_fetcher::{MarketFilter, Paging};
+use crate::market_rules::MarketRule;
+use crate::notifier::ALERT_KINDS;
+use crate::profit_model::ProfitModelConfig;
+use crate::rate_limit::Budgets;
+use crate::resolution_risk::ResolutionRiskConfig;
+use crate::reporting::{ReportConfig, ReportFormat};
+use crate::rest_client::RestConfig;
+use crate::risk::{SizingConfig, SizingStrategy};
+use crate::scheduler::SchedulerConfig;
+use crate::signals::SignalConfig;
+use crate::signer::SignerBackend;
+use crate::proxy_wallet::{ProxyWallet, WalletKind};
+use crate::similarity::SimilarityBackend;
+use crate::strategy::{CombinatorialStrategy, StrategyRegistry};
+use crate::topic_classifier::MarketCategory;
+use crate::wallet_pool::SelectionStrategy;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_CONFIG_PATH: &str = "bot.toml";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("failed to parse {path}: {source}")]
+    Parse { path: String, source: toml::de::Error },
+    #[error("environment variable {key}={value} is invalid: {reason}")]
+    Env { key: String, value: String, reason: String },
+    #[error("invalid config: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointConfig {
+    pub polygon_rpc_url: Option<String>,
+    /// Further HTTP RPCs pooled with `polygon_rpc_url`: reads round-robin across the healthy
+    /// ones, execution prefers the fastest, and transport errors fail over.
+    pub polygon_rpc_fallback_urls: Vec<String>,
+    /// RPC pool health checks: how often each endpoint's head is polled, how many blocks
+    /// behind the highest it may be, and how long a failed endpoint is skipped.
+    pub rpc_health_check_seconds: u64,
+    pub rpc_max_block_lag: u64,
+    pub rpc_failure_cooldown_seconds: u64,
+    /// Websocket RPC streaming `OrderFilled` logs as they land, ahead of the HTTP polls.
+    /// DRPC_API_KEY is added to it as the `dkey` parameter.
+    pub polygon_ws_url: Option<String>,
+    pub clob_ws_url: String,
+    pub clob_rest_url: String,
+    /// Authenticated channel carrying the wallet's own order and trade events.
+    pub clob_user_ws_url: String,
+    /// Websocket connections silent (no messages or pongs) this long are reconnected.
+    pub ws_stale_seconds: u64,
+    pub metrics_addr: Option<String>,
+    /// Read-only web dashboard and its JSON API; disabled when unset.
+    pub dashboard_addr: Option<String>,
+    /// Protected `eth_sendRawTransaction` endpoint for profitable trades. Its auth header is
+    /// read from PRIVATE_RELAY_AUTH only.
+    pub private_relay_url: Option<String>,
+    /// Gamma REST calls: per-request timeout, attempts on 429/5xx/timeouts, and request rate.
+    pub gamma_timeout_seconds: u64,
+    pub gamma_retry_attempts: u32,
+    pub gamma_requests_per_second: u32,
+    /// CLOB requests per second shared by every client and stream, per endpoint. 0 disables
+    /// the endpoint's limit; a 429 halves it for a minute.
+    pub clob_orders_per_second: u32,
+    pub clob_cancels_per_second: u32,
+    pub clob_market_data_per_second: u32,
+}
+
+impl Default for EndpointConfig {
+    fn default() -> Self {
+        Self {
+            polygon_rpc_url: None,
+            polygon_rpc_fallback_urls: Vec::new(),
+            rpc_health_check_seconds: 15,
+            rpc_max_block_lag: 5,
+            rpc_failure_cooldown_seconds: 30,
+            polygon_ws_url: None,
+            clob_ws_url: "wss://ws-subscriptions-clob.polymarket.com/ws/market".to_string(),
+            clob_rest_url: "https://clob.polymarket.com".to_string(),
+            clob_user_ws_url: "wss://ws-subscriptions-clob.polymarket.com/ws/user".to_string(),
+            ws_stale_seconds: 60,
+            metrics_addr: None,
+            dashboard_addr: None,
+            private_relay_url: None,
+            gamma_timeout_seconds: 10,
+            gamma_retry_attempts: 4,
+            gamma_requests_per_second: 10,
+            clob_orders_per_second: 20,
+            clob_cancels_per_second: 20,
+            clob_market_data_per_second: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdConfig {
+    /// Edge per dollar still required after modeled fees before a rebalancing, basket or
+    /// ladder arb is flagged.
+    pub fee_threshold: Decimal,
+    /// Minimum title similarity (0-1) under `similarity_backend` for two markets to be related.
+    pub similarity_cutoff: f64,
+    /// "levenshtein" (normalized Damerau-Levenshtein) or "tfidf" (cosine of TF-IDF word vectors).
+    pub similarity_backend: String,
+    /// Dependencies scored below this (0-1) are not traded or linked into chains.
+    pub min_dependency_confidence: f64,
+    /// Hours apart two markets may expire and still have their titles compared.
+    pub expiry_tolerance_hours: u64,
+    /// Dependency patterns to skip, by name (e.g. "state_national").
+    pub disabled_patterns: Vec<String>,
+    /// Combinatorial pairs whose resolution-risk score (0-1) exceeds this are not traded.
+    pub max_resolution_risk: f64,
+    /// Share of a pair's resolution-risk score taken off its net profit.
+    pub resolution_risk_discount: f64,
+    pub min_net_profit: Decimal,
+    pub taker_fee_bps: Decimal,
+    pub gas_cost_usd: Decimal,
+    pub max_gas_profit_fraction: Decimal,
+    pub pol_usd_price: Decimal,
+}
+
+impl Default for ThresholdConfig {
+    fn default() -> Self {
+        let profit = ProfitModelConfig::default();
+        let gas = GasPolicy::default();
+        let resolution = ResolutionRiskConfig::default();
+        Self {
+            fee_threshold: dec!(0.02),
+            similarity_cutoff: 0.6,
+            similarity_backend: "levenshtein".to_string(),
+            min_dependency_confidence: 0.5,
+            expiry_tolerance_hours: PatternRegistry::default().expiry_tolerance().num_hours() as u64,
+            disabled_patterns: Vec::new(),
+            max_resolution_risk: resolution.max_risk,
+            resolution_risk_discount: resolution.discount,
+            min_net_profit: profit.min_net_profit,
+            taker_fee_bps: profit.taker_fee_bps,
+            gas_cost_usd: profit.gas_cost,
+            max_gas_profit_fraction: gas.max_profit_fraction,
+            pol_usd_price: gas.pol_usd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SizingSection {
+    /// "fixed" or "kelly".
+    pub strategy: String,
+    /// Fraction of balance (fixed) or Kelly multiplier (kelly). Defaults to 0.05 / 0.25.
+    pub fraction: Option<Decimal>,
+    pub bankroll: Decimal,
+    pub max_exposure: Decimal,
+    pub max_market_exposure: Decimal,
+    pub min_trade_size: Decimal,
+    pub success_probability: Decimal,
+    /// Max combined exposure per market category, e.g. `politics = 2000`.
+    pub category_limits: HashMap<String, Decimal>,
+    /// Max combined exposure across markets naming an entity, by its canonical name, e.g. `trump = 2000`.
+    pub entity_limits: HashMap<String, Decimal>,
+}
+
+impl Default for SizingSection {
+    fn default() -> Self {
+        let sizing = SizingConfig::default();
+        Self {
+            strategy: "fixed".to_string(),
+            fraction: None,
+            bankroll: dec!(1000),
+            max_exposure: sizing.max_exposure,
+            max_market_exposure: sizing.max_market_exposure,
+            min_trade_size: sizing.min_trade_size,
+            success_probability: sizing.success_probability,
+            category_limits: HashMap::new(),
+            entity_limits: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Market categories to trade. Empty means every category.
+    pub categories: Vec<String>,
+    /// Markets with less order book liquidity than this (USDC) are dropped at ingestion.
+    pub min_liquidity: Decimal,
+    /// Markets that traded less than this (USDC) in the last 24h are dropped at ingestion.
+    pub min_volume_24hr: Decimal,
+    /// Markets quoted wider than this are dropped at ingestion. Unset keeps every spread.
+    pub max_spread: Option<Decimal>,
+    /// Gamma events per page and pages fetched in parallel.
+    pub page_size: usize,
+    pub page_concurrency: usize,
+    /// Cap on markets fetched per refresh, before filtering. Unset fetches every open market.
+    pub max_markets: Option<usize>,
+    /// When set, only matching markets are fetched.
+    pub include: MarketRuleConfig,
+    /// Matching markets are still fetched and priced but never traded.
+    pub exclude: MarketRuleConfig,
+}
+
+/// Markets picked by id, title regex, tag or category; any one matching is enough.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketRuleConfig {
+    pub ids: Vec<String>,
+    /// Regexes matched against the question as Gamma returns it.
+    pub titles: Vec<String>,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+impl MarketRuleConfig {
+    fn build(&self, section: &str) -> Result<MarketRule, ConfigError> {
+        let categories = self.categories.iter()
+            .map(|c| MarketCategory::from_str(c).map_err(|e| ConfigError::Invalid(format!("filters.{}.categories: {}", section, e))))
+            .collect::<Result<_, _>>()?;
+        MarketRule::new(&self.ids, &self.titles, &self.tags, categories)
+            .map_err(|e| ConfigError::Invalid(format!("filters.{}.titles: {}", section, e)))
+    }
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        let paging = Paging::default();
+        Self {
+            categories: Vec::new(),
+            min_liquidity: Decimal::ZERO,
+            min_volume_24hr: Decimal::ZERO,
+            max_spread: None,
+            page_size: paging.page_size,
+            page_concurrency: paging.concurrency,
+            max_markets: paging.max_markets,
+            include: MarketRuleConfig::default(),
+            exclude: MarketRuleConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionConfig {
+    pub dry_run: bool,
+    /// Merge complete sets and sell every other holding on the CLOB when shutting down.
+    pub flatten_on_shutdown: bool,
+    /// "cheap", "normal" or "aggressive".
+    pub gas_preset: String,
+    pub gas_poll_seconds: u64,
+    pub max_chain_depth: usize,
+    pub market_refresh_minutes: u64,
+    pub opportunity_db_path: String,
+    /// Resting CLOB orders older than this are cancelled and marked expired.
+    pub order_ttl_seconds: u64,
+    /// Minimum time between executions of the same opportunity on the same market.
+    pub opportunity_cooldown_seconds: u64,
+    /// Ticks on one asset within this window are evaluated once, at the latest price.
+    pub coalesce_window_ms: u64,
+    /// Executions in flight at once; queued opportunities run best margin first.
+    pub max_concurrent_executions: usize,
+    /// Queued opportunities not started within this long are dropped.
+    pub opportunity_ttl_ms: u64,
+    /// Trades and mids considered by the order-flow signals.
+    pub signal_window_seconds: u64,
+    /// Book levels per side in the depth imbalance.
+    pub signal_depth_levels: usize,
+    /// Adverse order-flow imbalance (0 to 1) that holds back arbs with resting legs. Unset
+    /// disables the check; 0 requires neutral or favorable flow.
+    pub max_adverse_imbalance: Option<Decimal>,
+    /// Adverse mid move over the signal window, as a fraction, that holds back arbs with resting legs.
+    pub max_adverse_momentum: Option<Decimal>,
+    /// JSON alias file merged into the built-in entity dictionary (same shape as `data/entities.json`).
+    pub entity_aliases_path: Option<String>,
+    /// JSON topic rules merged into the built-in classifier table (same shape as `data/topics.json`).
+    pub topic_rules_path: Option<String>,
+    /// Directory for hourly websocket recordings. Recording is off when unset.
+    pub record_dir: Option<String>,
+    /// Directory for the daily PnL and exposure report. No files are written when unset.
+    pub report_dir: Option<String>,
+    /// "json" or "csv".
+    pub report_format: String,
+    /// Attempts per execution when the RPC fails transiently (1 disables retries).
+    pub retry_attempts: u32,
+    /// First retry delay; later ones double, with full jitter.
+    pub retry_base_delay_ms: u64,
+    /// Failed executions within `breaker_window_seconds` that pause trading.
+    pub breaker_failures: usize,
+    pub breaker_window_seconds: u64,
+    /// How long trading stays paused once the breaker trips. Scanning continues.
+    pub breaker_cooldown_seconds: u64,
+    /// Kill switch: USD below peak equity (realized plus marked positions) that halts execution
+    /// until re-armed. Unset disables the check, as for the other kill switch limits.
+    pub max_drawdown: Option<Decimal>,
+    /// Net realized losses (USD) within `loss_window_minutes` that halt execution.
+    pub max_window_loss: Option<Decimal>,
+    pub loss_window_minutes: u64,
+    /// Failed share of executions within `error_window_minutes` that halts execution.
+    pub max_error_rate: Option<Decimal>,
+    pub error_window_minutes: u64,
+    /// Executions the error window needs before the rate is judged.
+    pub error_rate_min_executions: usize,
+    /// File holding a tripped kill switch, so a restart does not re-arm it.
+    pub kill_switch_path: String,
+    /// USDC approved per spender at a time. Unset approves an unlimited allowance once.
+    pub usdc_approval_amount: Option<Decimal>,
+    /// "sequential" (buy leg, then a sell sized to its fill) or "batch" (one request).
+    pub leg_submission: String,
+    /// How long each CLOB leg may rest before its remainder is cancelled.
+    pub leg_timeout_seconds: u64,
+    /// "take" crosses the spread on every leg of a Long rebalancing set; "make" quotes inside it.
+    pub rebalancing_mode: String,
+    /// How often maker quotes are checked for fills and repriced.
+    pub quote_reprice_ms: u64,
+    /// How long maker quotes may rest without a fill.
+    pub quote_ttl_seconds: u64,
+    /// Execution style per strategy ("taker", "mid" or "iceberg"), e.g. `rebalancing = "iceberg"`.
+    /// Supported for rebalancing and threshold_ladder; others always take.
+    pub styles: HashMap<String, String>,
+    /// Time in force of each strategy's taking legs ("gtc", "fok" or "ioc"), overriding what the
+    /// strategy requires, e.g. `combinatorial = "fok"`.
+    pub time_in_force: HashMap<String, String>,
+    /// How long "mid" orders rest at the mid before crossing.
+    pub mid_timeout_seconds: u64,
+    /// "iceberg" slices orders worth at least this much USDC.
+    pub iceberg_threshold: Decimal,
+    /// Approximate USDC per iceberg round.
+    pub iceberg_slice: Decimal,
+    /// "unwind" crosses the book to flatten an unhedged leg; "hedge" first tops up a short
+    /// second leg; "hold" keeps the imbalance.
+    pub rollback_policy: String,
+    /// Max price concession per share when unwinding or hedging.
+    pub rollback_max_slippage: Decimal,
+    /// Trades expected to make at least this much (USDC) go through the private relay.
+    pub private_min_profit: Decimal,
+    /// POL kept in reserve for gas; transactions that would dip below it are refused.
+    pub min_pol_balance: Decimal,
+    /// Low-balance alert thresholds.
+    pub low_pol_balance: Decimal,
+    pub low_usdc_balance: Decimal,
+    pub balance_poll_seconds: u64,
+    /// How trades are spread over PRIVATE_KEY and PRIVATE_KEYS: "round_robin",
+    /// "lowest_inflight" or "balance_weighted".
+    pub wallet_selection: String,
+    /// SQLite file the `ingest_fills` backfill writes decoded `OrderFilled` events to.
+    pub fills_db_path: String,
+    /// Blocks per log query; halved automatically when the provider refuses a range.
+    pub ingest_chunk_blocks: u64,
+    /// Blocks behind the tip the backfill stops at, to stay clear of reorgs.
+    pub ingest_confirmations: u64,
+    /// How often resolved markets are picked up from `ConditionResolution` events.
+    pub resolution_poll_seconds: u64,
+    /// Redeem winning outcome tokens for USDC as markets resolve.
+    pub auto_redeem: bool,
+    /// Wait before retrying a failed redemption.
+    pub redeem_retry_seconds: u64,
+    /// No new positions are opened in markets ending within this many minutes.
+    pub entry_cutoff_minutes: u64,
+    /// Unwind holdings hedged only until resolution this many minutes before a market ends.
+    /// Unset keeps them to resolution.
+    pub flatten_before_expiry_minutes: Option<u64>,
+    /// How often markets past their end date are dropped and unsubscribed.
+    pub expiry_poll_seconds: u64,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            flatten_on_shutdown: false,
+            gas_preset: "normal".to_string(),
+            gas_poll_seconds: 15,
+            max_chain_depth: 4,
+            market_refresh_minutes: 10,
+            opportunity_db_path: "opportunities.db".to_string(),
+            order_ttl_seconds: 300,
+            opportunity_cooldown_seconds: 30,
+            coalesce_window_ms: 25,
+            max_concurrent_executions: 4,
+            opportunity_ttl_ms: 2000,
+            signal_window_seconds: 30,
+            signal_depth_levels: 5,
+            max_adverse_imbalance: None,
+            max_adverse_momentum: None,
+            entity_aliases_path: None,
+            topic_rules_path: None,
+            record_dir: None,
+            report_dir: None,
+            report_format: "json".to_string(),
+            retry_attempts: 3,
+            retry_base_delay_ms: 200,
+            breaker_failures: 5,
+            breaker_window_seconds: 60,
+            breaker_cooldown_seconds: 300,
+            max_drawdown: None,
+            max_window_loss: None,
+            loss_window_minutes: 60,
+            max_error_rate: None,
+            error_window_minutes: 15,
+            error_rate_min_executions: 10,
+            kill_switch_path: "kill_switch.json".to_string(),
+            usdc_approval_amount: None,
+            leg_submission: "sequential".to_string(),
+            leg_timeout_seconds: 10,
+            rebalancing_mode: "take".to_string(),
+            quote_reprice_ms: 1000,
+            quote_ttl_seconds: 30,
+            styles: HashMap::new(),
+            time_in_force: HashMap::new(),
+            mid_timeout_seconds: 5,
+            iceberg_threshold: dec!(500),
+            iceberg_slice: dec!(100),
+            rollback_policy: "unwind".to_string(),
+            rollback_max_slippage: dec!(0.05),
+            private_min_profit: dec!(1),
+            min_pol_balance: dec!(1),
+            low_pol_balance: dec!(5),
+            low_usdc_balance: dec!(50),
+            balance_poll_seconds: 60,
+            wallet_selection: "round_robin".to_string(),
+            fills_db_path: "fills.db".to_string(),
+            ingest_chunk_blocks: 2000,
+            ingest_confirmations: 5,
+            resolution_poll_seconds: 60,
+            auto_redeem: true,
+            redeem_retry_seconds: 300,
+            entry_cutoff_minutes: 60,
+            flatten_before_expiry_minutes: None,
+            expiry_poll_seconds: 60,
+        }
+    }
+}
+
+/// Alert destinations and limits. The Telegram bot token is read from TELEGRAM_BOT_TOKEN only.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    pub telegram_chat_id: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    /// Receives `{"kind", "text", "fields"}` JSON for every alert.
+    pub webhook_url: Option<String>,
+    /// Opportunities with a smaller per-share profit are not alerted.
+    pub min_opportunity_profit: Decimal,
+    pub max_per_minute: usize,
+    /// UTC hour at which the daily PnL summary is sent and the daily report built.
+    pub daily_summary_hour_utc: u32,
+    /// Send the daily report's headline figures as an alert.
+    pub daily_report: bool,
+    /// Per-kind message templates with `{field}` placeholders, overriding the built-ins.
+    pub templates: HashMap<String, String>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            telegram_chat_id: None,
+            discord_webhook_url: None,
+            webhook_url: None,
+            min_opportunity_profit: dec!(0.05),
+            max_per_minute: 20,
+            daily_summary_hour_utc: 0,
+            daily_report: false,
+            templates: HashMap::new(),
+        }
+    }
+}
+
+/// Smart-money follower: tracks per-address profitability from on-chain fills and can
+/// mirror entries of the wallets that qualify.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CopyTradingConfig {
+    pub enabled: bool,
+    /// Place mirrored orders; when false flagged wallets are only logged.
+    pub mirror: bool,
+    pub delay_seconds: u64,
+    /// Fraction of a followed entry's USDC notional to mirror.
+    pub scale: Decimal,
+    /// Largest mirrored entry, in USDC.
+    pub max_size: Decimal,
+    pub poll_seconds: u64,
+    /// Rolling window for the per-address stats.
+    pub window_hours: u64,
+    pub min_closed_trades: usize,
+    pub min_win_rate: Decimal,
+    pub min_realized_pnl: Decimal,
+}
+
+impl Default for CopyTradingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mirror: false,
+            delay_seconds: 5,
+            scale: dec!(0.1),
+            max_size: dec!(25),
+            poll_seconds: 10,
+            window_hours: 168,
+            min_closed_trades: 10,
+            min_win_rate: dec!(0.6),
+            min_realized_pnl: dec!(100),
+        }
+    }
+}
+
+/// Where the primary wallet's key is held. PRIVATE_KEYS pool wallets are always raw keys.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignerConfig {
+    /// "env" (PRIVATE_KEY), "keystore", "aws_kms", "gcp_kms" or "ledger".
+    pub backend: String,
+    /// Encrypted JSON keystore; the passphrase comes from KEYSTORE_PASSPHRASE or a prompt.
+    pub keystore_path: Option<String>,
+    /// Key id, alias or ARN of an ECC_SECG_P256K1 signing key.
+    pub aws_kms_key_id: Option<String>,
+    pub aws_region: Option<String>,
+    /// Full `projects/.../cryptoKeyVersions/N` name of an EC_SIGN_SECP256K1_SHA256 key.
+    pub gcp_kms_key: Option<String>,
+    /// Ledger Live account index.
+    pub ledger_index: usize,
+    /// Where the primary wallet's funds sit: "eoa" (the key's own address), "poly_proxy"
+    /// (email/Magic accounts) or "safe" (browser-wallet accounts).
+    pub proxy_type: String,
+    /// The proxy's address, shown as the deposit address on Polymarket.
+    pub proxy_address: Option<String>,
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self { backend: "env".to_string(), keystore_path: None, aws_kms_key_id: None, aws_region: None, gcp_kms_key: None, ledger_index: 0, proxy_type: "eoa".to_string(), proxy_address: None }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// "text" or "json".
+    pub format: String,
+    /// `RUST_LOG`-style directives, e.g. "info,polymarket_bot::clob_client=debug".
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self { format: "text".to_string(), filter: "info".to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyConfig {
+    /// Strategies to skip, by name: rebalancing, neg_risk_basket, threshold_ladder,
+    /// combinatorial, multi_leg.
+    pub disabled: Vec<String>,
+    /// Net profit per dollar each named strategy's opportunities need to be queued.
+    pub min_margin: HashMap<String, Decimal>,
+    /// Opportunities with any leg priced longer ago than this are refused; 0 trusts any price.
+    pub max_price_age_seconds: u64,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self { disabled: Vec::new(), min_margin: HashMap::new(), max_price_age_seconds: 60 }
+    }
+}
+
+/// Replacements for the global thresholds in one market category, under `[categories.<name>]`.
+/// Unset keys keep the global value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategoryOverridesConfig {
+    /// Replaces `thresholds.fee_threshold`.
+    pub fee_threshold: Option<Decimal>,
+    /// Replaces `thresholds.min_net_profit`.
+    pub min_net_profit: Option<Decimal>,
+    /// Replaces `execution.opportunity_cooldown_seconds`.
+    pub cooldown_seconds: Option<u64>,
+    /// Replaces `sizing.fraction`.
+    pub sizing_fraction: Option<Decimal>,
+    /// Replaces `sizing.max_market_exposure`.
+    pub max_market_exposure: Option<Decimal>,
+}
+
+/// Bot configuration loaded from `bot.toml`, with every key overridable by its legacy env var.
+/// Secrets (private key, API keys) stay in the environment only.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+    pub endpoints: EndpointConfig,
+    pub signer: SignerConfig,
+    pub thresholds: ThresholdConfig,
+    pub sizing: SizingSection,
+    pub filters: FilterConfig,
+    pub execution: ExecutionConfig,
+    pub alerts: AlertConfig,
+    pub copy_trading: CopyTradingConfig,
+    pub strategies: StrategyConfig,
+    /// Threshold overrides by market category name (e.g. "crypto").
+    pub categories: HashMap<String, CategoryOverridesConfig>,
+    pub logging: LoggingConfig,
+}
+
+impl BotConfig {
+    /// Loads the file named by BOT_CONFIG (which must exist) or `bot.toml` (if present),
+    /// applies env overrides and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(None)
+    }
+
+    /// `load`, reading `path` in place of BOT_CONFIG when given.
+    pub fn load_from(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = path.map(Path::to_path_buf).or_else(|| env::var("BOT_CONFIG").ok().map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?,
+            None => Self::default(),
+        };
+        config.apply_env_overrides()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let display = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io { path: display.clone(), source })?;
+        Self::from_toml(&text).map_err(|source| ConfigError::Parse { path: display, source })
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    fn apply_env_overrides(&mut self) -> Result<(), ConfigError> {
+        let e = &mut self.endpoints;
+        override_option("POLYGON_RPC_URL", &mut e.polygon_rpc_url);
+        if let Ok(value) = env::var("POLYGON_RPC_FALLBACK_URLS") {
+            e.polygon_rpc_fallback_urls = value.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect();
+        }
+        override_value("RPC_HEALTH_CHECK_SECONDS", &mut e.rpc_health_check_seconds)?;
+        override_value("RPC_MAX_BLOCK_LAG", &mut e.rpc_max_block_lag)?;
+        override_value("RPC_FAILURE_COOLDOWN_SECONDS", &mut e.rpc_failure_cooldown_seconds)?;
+        override_option("POLYGON_WS_URL", &mut e.polygon_ws_url);
+        override_value("CLOB_WS_URL", &mut e.clob_ws_url)?;
+        override_value("CLOB_REST_URL", &mut e.clob_rest_url)?;
+        override_value("CLOB_USER_WS_URL", &mut e.clob_user_ws_url)?;
+        override_value("WS_STALE_SECONDS", &mut e.ws_stale_seconds)?;
+        override_option("METRICS_ADDR", &mut e.metrics_addr);
+        override_option("DASHBOARD_ADDR", &mut e.dashboard_addr);
+        override_option("PRIVATE_RELAY_URL", &mut e.private_relay_url);
+        override_value("GAMMA_TIMEOUT_SECONDS", &mut e.gamma_timeout_seconds)?;
+        override_value("GAMMA_RETRY_ATTEMPTS", &mut e.gamma_retry_attempts)?;
+        override_value("GAMMA_REQUESTS_PER_SECOND", &mut e.gamma_requests_per_second)?;
+        override_value("CLOB_ORDERS_PER_SECOND", &mut e.clob_orders_per_second)?;
+        override_value("CLOB_CANCELS_PER_SECOND", &mut e.clob_cancels_per_second)?;
+        override_value("CLOB_MARKET_DATA_PER_SECOND", &mut e.clob_market_data_per_second)?;
+
+        let k = &mut self.signer;
+        override_value("SIGNER_BACKEND", &mut k.backend)?;
+        override_option("KEYSTORE_PATH", &mut k.keystore_path);
+        override_option("AWS_KMS_KEY_ID", &mut k.aws_kms_key_id);
+        override_option("AWS_REGION", &mut k.aws_region);
+        override_option("GCP_KMS_KEY", &mut k.gcp_kms_key);
+        override_value("LEDGER_INDEX", &mut k.ledger_index)?;
+        override_value("PROXY_WALLET_TYPE", &mut k.proxy_type)?;
+        override_option("PROXY_WALLET_ADDRESS", &mut k.proxy_address);
+
+        let t = &mut self.thresholds;
+        override_value("FEE_THRESHOLD", &mut t.fee_threshold)?;
+        override_value("SIMILARITY_CUTOFF", &mut t.similarity_cutoff)?;
+        override_value("SIMILARITY_BACKEND", &mut t.similarity_backend)?;
+        override_value("MIN_DEPENDENCY_CONFIDENCE", &mut t.min_dependency_confidence)?;
+        override_value("EXPIRY_TOLERANCE_HOURS", &mut t.expiry_tolerance_hours)?;
+        override_value("MAX_RESOLUTION_RISK", &mut t.max_resolution_risk)?;
+        override_value("RESOLUTION_RISK_DISCOUNT", &mut t.resolution_risk_discount)?;
+        if let Ok(value) = env::var("DISABLED_PATTERNS") {
+            t.disabled_patterns = value.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+        }
+        override_value("MIN_NET_PROFIT", &mut t.min_net_profit)?;
+        override_value("TAKER_FEE_BPS", &mut t.taker_fee_bps)?;
+        override_value("GAS_COST_USD", &mut t.gas_cost_usd)?;
+        override_value("MAX_GAS_PROFIT_FRACTION", &mut t.max_gas_profit_fraction)?;
+        override_value("POL_USD_PRICE", &mut t.pol_usd_price)?;
+
+        let s = &mut self.sizing;
+        override_value("SIZING_STRATEGY", &mut s.strategy)?;
+        if let Ok(value) = env::var("SIZING_FRACTION") {
+            s.fraction = Some(parse_env("SIZING_FRACTION", &value)?);
+        }
+        override_value("TRADING_BANKROLL", &mut s.bankroll)?;
+        override_value("MAX_EXPOSURE", &mut s.max_exposure)?;
+        override_value("MAX_MARKET_EXPOSURE", &mut s.max_market_exposure)?;
+        override_value("MIN_TRADE_SIZE", &mut s.min_trade_size)?;
+        override_value("ARB_SUCCESS_PROBABILITY", &mut s.success_probability)?;
+        if let Ok(value) = env::var("CATEGORY_EXPOSURE_LIMITS") {
+            s.category_limits = parse_pairs("CATEGORY_EXPOSURE_LIMITS", &value)?;
+        }
+        if let Ok(value) = env::var("ENTITY_EXPOSURE_LIMITS") {
+            s.entity_limits = parse_pairs("ENTITY_EXPOSURE_LIMITS", &value)?;
+        }
+
+        if let Ok(value) = env::var("MARKET_CATEGORIES") {
+            self.filters.categories = value.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect();
+        }
+        override_value("MIN_LIQUIDITY", &mut self.filters.min_liquidity)?;
+        override_value("MIN_VOLUME_24HR", &mut self.filters.min_volume_24hr)?;
+        if let Ok(value) = env::var("MAX_SPREAD") {
+            self.filters.max_spread = Some(parse_env("MAX_SPREAD", &value)?);
+        }
+        override_value("GAMMA_PAGE_SIZE", &mut self.filters.page_size)?;
+        override_value("GAMMA_PAGE_CONCURRENCY", &mut self.filters.page_concurrency)?;
+        if let Ok(value) = env::var("MAX_MARKETS") {
+            self.filters.max_markets = Some(parse_env("MAX_MARKETS", &value)?);
+        }
+        // Title regexes are separated by ';' since they may contain commas
+        let list = |value: String, separator: char| value.split(separator).map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect::<Vec<_>>();
+        let (include, exclude) = (&mut self.filters.include, &mut self.filters.exclude);
+        for (key, field, separator) in [
+            ("INCLUDE_MARKET_IDS", &mut include.ids, ','),
+            ("INCLUDE_MARKET_TITLES", &mut include.titles, ';'),
+            ("INCLUDE_MARKET_TAGS", &mut include.tags, ','),
+            ("EXCLUDE_MARKET_IDS", &mut exclude.ids, ','),
+            ("EXCLUDE_MARKET_TITLES", &mut exclude.titles, ';'),
+            ("EXCLUDE_MARKET_TAGS", &mut exclude.tags, ','),
+            ("EXCLUDE_MARKET_CATEGORIES", &mut exclude.categories, ','),
+        ] {
+            if let Ok(value) = env::var(key) {
+                *field = list(value, separator);
+            }
+        }
+
+        let x = &mut self.execution;
+        override_flag("DRY_RUN", &mut x.dry_run);
+        override_flag("FLATTEN_ON_SHUTDOWN", &mut x.flatten_on_shutdown);
+        override_value("GAS_PRESET", &mut x.gas_preset)?;
+        override_value("GAS_POLL_SECONDS", &mut x.gas_poll_seconds)?;
+        override_value("MAX_CHAIN_DEPTH", &mut x.max_chain_depth)?;
+        override_value("MARKET_REFRESH_MINUTES", &mut x.market_refresh_minutes)?;
+        override_value("OPPORTUNITY_DB_PATH", &mut x.opportunity_db_path)?;
+        override_value("ORDER_TTL_SECONDS", &mut x.order_ttl_seconds)?;
+        override_value("OPPORTUNITY_COOLDOWN_SECONDS", &mut x.opportunity_cooldown_seconds)?;
+        override_value("COALESCE_WINDOW_MS", &mut x.coalesce_window_ms)?;
+        override_value("MAX_CONCURRENT_EXECUTIONS", &mut x.max_concurrent_executions)?;
+        override_value("OPPORTUNITY_TTL_MS", &mut x.opportunity_ttl_ms)?;
+        override_value("SIGNAL_WINDOW_SECONDS", &mut x.signal_window_seconds)?;
+        override_value("SIGNAL_DEPTH_LEVELS", &mut x.signal_depth_levels)?;
+        for (key, field) in [("MAX_ADVERSE_IMBALANCE", &mut x.max_adverse_imbalance), ("MAX_ADVERSE_MOMENTUM", &mut x.max_adverse_momentum)] {
+            if let Ok(value) = env::var(key) {
+                *field = Some(parse_env(key, &value)?);
+            }
+        }
+        override_option("ENTITY_ALIASES_PATH", &mut x.entity_aliases_path);
+        override_option("TOPIC_RULES_PATH", &mut x.topic_rules_path);
+        override_option("RECORD_DIR", &mut x.record_dir);
+        override_option("REPORT_DIR", &mut x.report_dir);
+        override_value("REPORT_FORMAT", &mut x.report_format)?;
+        override_value("EXECUTION_RETRY_ATTEMPTS", &mut x.retry_attempts)?;
+        override_value("EXECUTION_RETRY_BASE_MS", &mut x.retry_base_delay_ms)?;
+        override_value("BREAKER_FAILURES", &mut x.breaker_failures)?;
+        override_value("BREAKER_WINDOW_SECONDS", &mut x.breaker_window_seconds)?;
+        override_value("BREAKER_COOLDOWN_SECONDS", &mut x.breaker_cooldown_seconds)?;
+        for (key, field) in [("MAX_DRAWDOWN", &mut x.max_drawdown), ("MAX_WINDOW_LOSS", &mut x.max_window_loss), ("MAX_ERROR_RATE", &mut x.max_error_rate)] {
+            if let Ok(value) = env::var(key) {
+                *field = Some(parse_env(key, &value)?);
+            }
+        }
+        override_value("LOSS_WINDOW_MINUTES", &mut x.loss_window_minutes)?;
+        override_value("ERROR_WINDOW_MINUTES", &mut x.error_window_minutes)?;
+        override_value("ERROR_RATE_MIN_EXECUTIONS", &mut x.error_rate_min_executions)?;
+        override_value("KILL_SWITCH_PATH", &mut x.kill_switch_path)?;
+        if let Ok(value) = env::var("USDC_APPROVAL_AMOUNT") {
+            x.usdc_approval_amount = Some(parse_env("USDC_APPROVAL_AMOUNT", &value)?);
+        }
+        override_value("LEG_SUBMISSION", &mut x.leg_submission)?;
+        override_value("LEG_TIMEOUT_SECONDS", &mut x.leg_timeout_seconds)?;
+        override_value("REBALANCING_MODE", &mut x.rebalancing_mode)?;
+        override_value("QUOTE_REPRICE_MS", &mut x.quote_reprice_ms)?;
+        override_value("QUOTE_TTL_SECONDS", &mut x.quote_ttl_seconds)?;
+        if let Ok(value) = env::var("EXECUTION_STYLES") {
+            x.styles = parse_pairs("EXECUTION_STYLES", &value)?;
+        }
+        if let Ok(value) = env::var("EXECUTION_TIME_IN_FORCE") {
+            x.time_in_force = parse_pairs("EXECUTION_TIME_IN_FORCE", &value)?;
+        }
+        override_value("MID_TIMEOUT_SECONDS", &mut x.mid_timeout_seconds)?;
+        override_value("ICEBERG_THRESHOLD", &mut x.iceberg_threshold)?;
+        override_value("ICEBERG_SLICE", &mut x.iceberg_slice)?;
+        override_value("ROLLBACK_POLICY", &mut x.rollback_policy)?;
+        override_value("ROLLBACK_MAX_SLIPPAGE", &mut x.rollback_max_slippage)?;
+        override_value("PRIVATE_RELAY_MIN_PROFIT", &mut x.private_min_profit)?;
+        override_value("MIN_POL_BALANCE", &mut x.min_pol_balance)?;
+        override_value("LOW_POL_BALANCE", &mut x.low_pol_balance)?;
+        override_value("LOW_USDC_BALANCE", &mut x.low_usdc_balance)?;
+        override_value("BALANCE_POLL_SECONDS", &mut x.balance_poll_seconds)?;
+        override_value("WALLET_SELECTION", &mut x.wallet_selection)?;
+        override_value("FILLS_DB_PATH", &mut x.fills_db_path)?;
+        override_value("INGEST_CHUNK_BLOCKS", &mut x.ingest_chunk_blocks)?;
+        override_value("INGEST_CONFIRMATIONS", &mut x.ingest_confirmations)?;
+        override_value("RESOLUTION_POLL_SECONDS", &mut x.resolution_poll_seconds)?;
+        override_flag("AUTO_REDEEM", &mut x.auto_redeem);
+        override_value("REDEEM_RETRY_SECONDS", &mut x.redeem_retry_seconds)?;
+        override_value("ENTRY_CUTOFF_MINUTES", &mut x.entry_cutoff_minutes)?;
+        if let Ok(value) = env::var("FLATTEN_BEFORE_EXPIRY_MINUTES") {
+            x.flatten_before_expiry_minutes = Some(parse_env("FLATTEN_BEFORE_EXPIRY_MINUTES", &value)?);
+        }
+        override_value("EXPIRY_POLL_SECONDS", &mut x.expiry_poll_seconds)?;
+
+        let a = &mut self.alerts;
+        override_option("TELEGRAM_CHAT_ID", &mut a.telegram_chat_id);
+        override_option("DISCORD_WEBHOOK_URL", &mut a.discord_webhook_url);
+        override_option("ALERT_WEBHOOK_URL", &mut a.webhook_url);
+        override_value("ALERT_MIN_PROFIT", &mut a.min_opportunity_profit)?;
+        override_value("ALERT_MAX_PER_MINUTE", &mut a.max_per_minute)?;
+        override_value("DAILY_SUMMARY_HOUR_UTC", &mut a.daily_summary_hour_utc)?;
+        override_flag("DAILY_REPORT", &mut a.daily_report);
+
+        let c = &mut self.copy_trading;
+        override_flag("COPY_TRADING", &mut c.enabled);
+        override_flag("COPY_MIRROR", &mut c.mirror);
+        override_value("COPY_DELAY_SECONDS", &mut c.delay_seconds)?;
+        override_value("COPY_SCALE", &mut c.scale)?;
+        override_value("COPY_MAX_SIZE", &mut c.max_size)?;
+        override_value("COPY_POLL_SECONDS", &mut c.poll_seconds)?;
+        override_value("COPY_WINDOW_HOURS", &mut c.window_hours)?;
+        override_value("COPY_MIN_CLOSED_TRADES", &mut c.min_closed_trades)?;
+        override_value("COPY_MIN_WIN_RATE", &mut c.min_win_rate)?;
+        override_value("COPY_MIN_REALIZED_PNL", &mut c.min_realized_pnl)?;
+
+        if let Ok(value) = env::var("DISABLED_STRATEGIES") {
+            self.strategies.disabled = value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+        }
+        if let Ok(value) = env::var("STRATEGY_MIN_MARGIN") {
+            self.strategies.min_margin = parse_pairs("STRATEGY_MIN_MARGIN", &value)?;
+        }
+        override_value("STRATEGY_MAX_PRICE_AGE_SECONDS", &mut self.strategies.max_price_age_seconds)?;
+        self.override_categories("CATEGORY_FEE_THRESHOLDS", |c, v| c.fee_threshold = Some(v))?;
+        self.override_categories("CATEGORY_MIN_NET_PROFIT", |c, v| c.min_net_profit = Some(v))?;
+        self.override_categories("CATEGORY_COOLDOWN_SECONDS", |c, v| c.cooldown_seconds = Some(v))?;
+        self.override_categories("CATEGORY_SIZING_FRACTIONS", |c, v| c.sizing_fraction = Some(v))?;
+        self.override_categories("CATEGORY_MAX_MARKET_EXPOSURE", |c, v| c.max_market_exposure = Some(v))?;
+
+        override_value("LOG_FORMAT", &mut self.logging.format)?;
+        override_value("RUST_LOG", &mut self.logging.filter)?;
+        Ok(())
+    }
+
+    /// Applies `category:value` pairs from `key` to the matching `[categories.*]` entries.
+    fn override_categories<T: FromStr>(&mut self, key: &str, set: impl Fn(&mut CategoryOverridesConfig, T)) -> Result<(), ConfigError>
+    where
+        T::Err: Display,
+    {
+        let Ok(value) = env::var(key) else { return Ok(()) };
+        for (name, v) in parse_pairs::<T>(key, &value)? {
+            let name = self.categories.keys().find(|k| k.eq_ignore_ascii_case(&name)).cloned().unwrap_or(name);
+            set(self.categories.entry(name).or_default(), v);
+        }
+        Ok(())
+    }
+
+    /// Rejects values that would make the bot misbehave rather than fail loudly later.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+        let t = &self.thresholds;
+        if t.fee_threshold < Decimal::ZERO || t.fee_threshold >= Decimal::ONE {
+            return invalid(format!("thresholds.fee_threshold must be in [0, 1), got {}", t.fee_threshold));
+        }
+        if !(t.similarity_cutoff > 0.0 && t.similarity_cutoff < 1.0) {
+            return invalid(format!("thresholds.similarity_cutoff must be in (0, 1), got {}", t.similarity_cutoff));
+        }
+        if t.expiry_tolerance_hours > 24 * 365 {
+            return invalid(format!("thresholds.expiry_tolerance_hours must be at most a year (8760), got {}", t.expiry_tolerance_hours));
+        }
+        if !(0.0..=1.0).contains(&t.min_dependency_confidence) {
+            return invalid(format!("thresholds.min_dependency_confidence must be in [0, 1], got {}", t.min_dependency_confidence));
+        }
+        if !(0.0..=1.0).contains(&t.max_resolution_risk) || !(0.0..=1.0).contains(&t.resolution_risk_discount) {
+            return invalid(format!(
+                "thresholds.max_resolution_risk and resolution_risk_discount must be in [0, 1], got {} and {}",
+                t.max_resolution_risk, t.resolution_risk_discount
+            ));
+        }
+        self.pattern_registry()?;
+        if t.taker_fee_bps < Decimal::ZERO || t.gas_cost_usd < Decimal::ZERO || t.pol_usd_price <= Decimal::ZERO {
+            return invalid("thresholds.taker_fee_bps and gas_cost_usd must be >= 0 and pol_usd_price > 0".to_string());
+        }
+        if t.max_gas_profit_fraction <= Decimal::ZERO {
+            return invalid(format!("thresholds.max_gas_profit_fraction must be > 0, got {}", t.max_gas_profit_fraction));
+        }
+
+        self.sizing_config()?;
+        let s = &self.sizing;
+        if s.bankroll <= Decimal::ZERO {
+            return invalid(format!("sizing.bankroll must be > 0, got {}", s.bankroll));
+        }
+        if s.max_market_exposure > s.max_exposure {
+            return invalid(format!("sizing.max_market_exposure ({}) exceeds sizing.max_exposure ({})", s.max_market_exposure, s.max_exposure));
+        }
+        if s.success_probability <= Decimal::ZERO || s.success_probability > Decimal::ONE {
+            return invalid(format!("sizing.success_probability must be in (0, 1], got {}", s.success_probability));
+        }
+
+        if self.endpoints.polygon_rpc_url.is_none() && !self.endpoints.polygon_rpc_fallback_urls.is_empty() {
+            return invalid("endpoints.polygon_rpc_fallback_urls needs polygon_rpc_url".to_string());
+        }
+        if self.endpoints.rpc_health_check_seconds == 0 || self.endpoints.rpc_failure_cooldown_seconds == 0 {
+            return invalid("endpoints.rpc_health_check_seconds and rpc_failure_cooldown_seconds must be > 0".to_string());
+        }
+        if let Some(url) = &self.endpoints.polygon_ws_url {
+            if !(url.starts_with("ws://") || url.starts_with("wss://")) {
+                return invalid(format!("endpoints.polygon_ws_url must be a ws:// or wss:// URL, got \"{}\"", url));
+            }
+        }
+        if self.endpoints.gamma_timeout_seconds == 0 || self.endpoints.gamma_retry_attempts == 0 || self.endpoints.ws_stale_seconds == 0 {
+            return invalid("endpoints.gamma_timeout_seconds, gamma_retry_attempts and ws_stale_seconds must be > 0".to_string());
+        }
+        self.signer_backend()?;
+        self.proxy_wallet()?;
+        self.categories()?;
+        self.filters.include.build("include")?;
+        self.filters.exclude.build("exclude")?;
+        let f = &self.filters;
+        if f.min_liquidity < Decimal::ZERO || f.min_volume_24hr < Decimal::ZERO {
+            return invalid("filters.min_liquidity and min_volume_24hr must be >= 0".to_string());
+        }
+        if let Some(spread) = f.max_spread.filter(|s| *s <= Decimal::ZERO || *s > Decimal::ONE) {
+            return invalid(format!("filters.max_spread must be in (0, 1], got {}", spread));
+        }
+        if f.page_size == 0 || f.page_concurrency == 0 || f.max_markets == Some(0) {
+            return invalid("filters.page_size, page_concurrency and max_markets must be > 0".to_string());
+        }
+        self.gas_policy()?;
+        let x = &self.execution;
+        if x.max_chain_depth < 2 {
+            return invalid(format!("execution.max_chain_depth must be at least 2, got {}", x.max_chain_depth));
+        }
+        if x.market_refresh_minutes == 0 || x.gas_poll_seconds == 0 || x.order_ttl_seconds == 0 {
+            return invalid("execution.market_refresh_minutes, gas_poll_seconds and order_ttl_seconds must be > 0".to_string());
+        }
+        if x.max_concurrent_executions == 0 || x.opportunity_ttl_ms == 0 {
+            return invalid("execution.max_concurrent_executions and opportunity_ttl_ms must be > 0".to_string());
+        }
+        if x.signal_window_seconds == 0 || x.signal_depth_levels == 0 {
+            return invalid("execution.signal_window_seconds and signal_depth_levels must be > 0".to_string());
+        }
+        if let Some(value) = x.max_adverse_imbalance.filter(|v| *v < Decimal::ZERO || *v > Decimal::ONE) {
+            return invalid(format!("execution.max_adverse_imbalance must be between 0 and 1, got {}", value));
+        }
+        if let Some(value) = x.max_adverse_momentum.filter(|v| *v < Decimal::ZERO) {
+            return invalid(format!("execution.max_adverse_momentum must be >= 0, got {}", value));
+        }
+        if x.retry_attempts == 0 || x.breaker_failures == 0 || x.breaker_window_seconds == 0 {
+            return invalid("execution.retry_attempts, breaker_failures and breaker_window_seconds must be > 0".to_string());
+        }
+        if let Some(limit) = [x.max_drawdown, x.max_window_loss].into_iter().flatten().find(|l| *l <= Decimal::ZERO) {
+            return invalid(format!("execution.max_drawdown and max_window_loss must be > 0, got {}", limit));
+        }
+        if let Some(rate) = x.max_error_rate.filter(|r| *r <= Decimal::ZERO || *r >= Decimal::ONE) {
+            return invalid(format!("execution.max_error_rate must be between 0 and 1, got {}", rate));
+        }
+        if x.loss_window_minutes == 0 || x.error_window_minutes == 0 {
+            return invalid("execution.loss_window_minutes and error_window_minutes must be > 0".to_string());
+        }
+        if let Some(amount) = x.usdc_approval_amount.filter(|a| *a <= Decimal::ZERO) {
+            return invalid(format!("execution.usdc_approval_amount must be > 0, got {}", amount));
+        }
+        self.planner_config()?;
+        self.quoting_config()?;
+        self.execution_styles()?;
+        self.time_in_force()?;
+        self.wallet_selection()?;
+        self.report_config()?;
+        if x.ingest_chunk_blocks == 0 {
+            return invalid("execution.ingest_chunk_blocks must be > 0".to_string());
+        }
+        if x.resolution_poll_seconds == 0 || x.redeem_retry_seconds == 0 {
+            return invalid("execution.resolution_poll_seconds and redeem_retry_seconds must be > 0".to_string());
+        }
+        if x.expiry_poll_seconds == 0 {
+            return invalid("execution.expiry_poll_seconds must be > 0".to_string());
+        }
+        if x.flatten_before_expiry_minutes.is_some_and(|m| m > x.entry_cutoff_minutes) {
+            return invalid("execution.flatten_before_expiry_minutes must not exceed entry_cutoff_minutes, or positions would reopen after flattening".to_string());
+        }
+        if x.private_min_profit < Decimal::ZERO {
+            return invalid(format!("execution.private_min_profit must be >= 0, got {}", x.private_min_profit));
+        }
+        if x.min_pol_balance < Decimal::ZERO || x.low_pol_balance < Decimal::ZERO || x.low_usdc_balance < Decimal::ZERO || x.balance_poll_seconds == 0 {
+            return invalid("execution balance limits must be >= 0 and balance_poll_seconds > 0".to_string());
+        }
+        self.metrics_addr()?;
+        self.dashboard_addr()?;
+        let a = &self.alerts;
+        if a.max_per_minute == 0 || a.daily_summary_hour_utc > 23 || a.min_opportunity_profit < Decimal::ZERO {
+            return invalid("alerts.max_per_minute must be > 0, daily_summary_hour_utc in 0..=23 and min_opportunity_profit >= 0".to_string());
+        }
+        if let Some(kind) = a.templates.keys().find(|k| !ALERT_KINDS.contains(&k.as_str())) {
+            return invalid(format!("alerts.templates: unknown alert kind \"{}\", expected one of {:?}", kind, ALERT_KINDS));
+        }
+        let c = &self.copy_trading;
+        if c.scale <= Decimal::ZERO || c.scale > Decimal::ONE || c.max_size <= Decimal::ZERO || c.poll_seconds == 0 || c.window_hours == 0 {
+            return invalid("copy_trading.scale must be in (0, 1], max_size > 0, poll_seconds and window_hours > 0".to_string());
+        }
+        if c.min_win_rate < Decimal::ZERO || c.min_win_rate > Decimal::ONE {
+            return invalid(format!("copy_trading.min_win_rate must be in [0, 1], got {}", c.min_win_rate));
+        }
+        self.strategy_registry()?;
+        self.log_format()?;
+        self.log_filter()?;
+        Ok(())
+    }
+
+    pub fn sizing_config(&self) -> Result<SizingConfig, ConfigError> {
+        let s = &self.sizing;
+        let strategy = match s.strategy.to_lowercase().as_str() {
+            "fixed" => SizingStrategy::FixedFraction(s.fraction.unwrap_or(dec!(0.05))),
+            "kelly" => SizingStrategy::KellyFraction(s.fraction.unwrap_or(dec!(0.25))),
+            other => return Err(ConfigError::Invalid(format!("sizing.strategy must be \"fixed\" or \"kelly\", got \"{}\"", other))),
+        };
+        let (SizingStrategy::FixedFraction(f) | SizingStrategy::KellyFraction(f)) = strategy;
+        if f <= Decimal::ZERO || f > Decimal::ONE {
+            return Err(ConfigError::Invalid(format!("sizing.fraction must be in (0, 1], got {}", f)));
+        }
+        let mut category_limits = HashMap::new();
+        for (category, limit) in &s.category_limits {
+            let category = MarketCategory::from_str(category).map_err(|e| ConfigError::Invalid(format!("sizing.category_limits: {}", e)))?;
+            category_limits.insert(category, positive_limit("sizing.category_limits", *limit)?);
+        }
+        let mut entity_limits = HashMap::new();
+        for (entity, limit) in &s.entity_limits {
+            entity_limits.insert(entity.trim().to_lowercase(), positive_limit("sizing.entity_limits", *limit)?);
+        }
+        Ok(SizingConfig {
+            strategy,
+            max_exposure: s.max_exposure,
+            max_market_exposure: s.max_market_exposure,
+            min_trade_size: s.min_trade_size,
+            success_probability: s.success_probability,
+            category_limits,
+            entity_limits,
+            categories: self.category_params()?,
+        })
+    }
+
+    /// The `[categories.*]` overrides, keyed by parsed category.
+    pub fn category_params(&self) -> Result<CategoryParams, ConfigError> {
+        let mut by_category = HashMap::new();
+        for (name, c) in &self.categories {
+            let field = |key: &str| format!("categories.{}.{}", name, key);
+            let category = MarketCategory::from_str(name).map_err(|e| ConfigError::Invalid(format!("categories: {}", e)))?;
+            if c.fee_threshold.is_some_and(|f| f < Decimal::ZERO || f >= Decimal::ONE) {
+                return Err(ConfigError::Invalid(format!("{} must be in [0, 1), got {}", field("fee_threshold"), c.fee_threshold.unwrap_or_default())));
+            }
+            if c.min_net_profit.is_some_and(|p| p < Decimal::ZERO) {
+                return Err(ConfigError::Invalid(format!("{} must be >= 0, got {}", field("min_net_profit"), c.min_net_profit.unwrap_or_default())));
+            }
+            if c.sizing_fraction.is_some_and(|f| f <= Decimal::ZERO || f > Decimal::ONE) {
+                return Err(ConfigError::Invalid(format!("{} must be in (0, 1], got {}", field("sizing_fraction"), c.sizing_fraction.unwrap_or_default())));
+            }
+            let overrides = CategoryOverrides {
+                fee_threshold: c.fee_threshold,
+                min_net_profit: c.min_net_profit,
+                cooldown: c.cooldown_seconds.map(Duration::from_secs),
+                sizing_fraction: c.sizing_fraction,
+                max_market_exposure: c.max_market_exposure.map(|m| positive_limit(&field("max_market_exposure"), m)).transpose()?,
+            };
+            if by_category.insert(category, overrides).is_some() {
+                return Err(ConfigError::Invalid(format!("categories: {} is configured twice", name)));
+            }
+        }
+        Ok(CategoryParams::new(by_category))
+    }
+
+    pub fn fee_model(&self) -> FeeModel {
+        FeeModel {
+            default_taker_fee_bps: self.thresholds.taker_fee_bps,
+            gas_cost: self.thresholds.gas_cost_usd,
+            min_edge: self.thresholds.fee_threshold,
+        }
+    }
+
+    pub fn profit_model(&self) -> ProfitModelConfig {
+        ProfitModelConfig {
+            taker_fee_bps: self.thresholds.taker_fee_bps,
+            gas_cost: self.thresholds.gas_cost_usd,
+            min_net_profit: self.thresholds.min_net_profit,
+        }
+    }
+
+    pub fn resolution_risk(&self) -> ResolutionRiskConfig {
+        ResolutionRiskConfig { max_risk: self.thresholds.max_resolution_risk, discount: self.thresholds.resolution_risk_discount }
+    }
+
+    pub fn gas_policy(&self) -> Result<GasPolicy, ConfigError> {
+        let preset = GasPreset::from_str(&self.execution.gas_preset)
+            .map_err(|_| ConfigError::Invalid(format!("execution.gas_preset must be cheap, normal or aggressive, got \"{}\"", self.execution.gas_preset)))?;
+        Ok(GasPolicy {
+            preset,
+            max_profit_fraction: self.thresholds.max_gas_profit_fraction,
+            pol_usd: self.thresholds.pol_usd_price,
+        })
+    }
+
+    pub fn execution_guard(&self) -> ExecutionGuard {
+        let x = &self.execution;
+        let retry = RetryPolicy { max_attempts: x.retry_attempts, base_delay: Duration::from_millis(x.retry_base_delay_ms), ..RetryPolicy::default() };
+        let breaker = CircuitBreaker::new(x.breaker_failures, Duration::from_secs(x.breaker_window_seconds), Duration::from_secs(x.breaker_cooldown_seconds));
+        ExecutionGuard::new(retry, breaker)
+    }
+
+    pub fn kill_switch_config(&self) -> KillSwitchConfig {
+        let x = &self.execution;
+        KillSwitchConfig {
+            max_drawdown: x.max_drawdown,
+            max_window_loss: x.max_window_loss,
+            loss_window: Duration::from_secs(x.loss_window_minutes * 60),
+            max_error_rate: x.max_error_rate,
+            error_window: Duration::from_secs(x.error_window_minutes * 60),
+            min_executions: x.error_rate_min_executions,
+            path: PathBuf::from(&x.kill_switch_path),
+        }
+    }
+
+    pub fn signal_config(&self) -> SignalConfig {
+        let x = &self.execution;
+        SignalConfig {
+            window: Duration::from_secs(x.signal_window_seconds),
+            depth_levels: x.signal_depth_levels,
+            max_adverse_imbalance: x.max_adverse_imbalance,
+            max_adverse_momentum: x.max_adverse_momentum,
+        }
+    }
+
+    pub fn scheduler_config(&self) -> SchedulerConfig {
+        let x = &self.execution;
+        SchedulerConfig { max_concurrent: x.max_concurrent_executions, ttl: Duration::from_millis(x.opportunity_ttl_ms) }
+    }
+
+    pub fn approval_policy(&self) -> ApprovalPolicy {
+        self.execution.usdc_approval_amount.map_or(ApprovalPolicy::Max, ApprovalPolicy::Bounded)
+    }
+
+    pub fn balance_limits(&self) -> BalanceLimits {
+        let x = &self.execution;
+        BalanceLimits { min_pol: x.min_pol_balance, low_pol: x.low_pol_balance, low_usdc: x.low_usdc_balance }
+    }
+
+    pub fn planner_config(&self) -> Result<PlannerConfig, ConfigError> {
+        let x = &self.execution;
+        let submission = LegSubmission::from_str(&x.leg_submission)
+            .map_err(|_| ConfigError::Invalid(format!("execution.leg_submission must be sequential or batch, got \"{}\"", x.leg_submission)))?;
+        let rollback = match x.rollback_policy.to_lowercase().as_str() {
+            "hold" => RollbackPolicy::Hold,
+            "unwind" => RollbackPolicy::Unwind { max_slippage: x.rollback_max_slippage },
+            "hedge" => RollbackPolicy::Hedge { max_slippage: x.rollback_max_slippage },
+            other => return Err(ConfigError::Invalid(format!("execution.rollback_policy must be unwind, hedge or hold, got \"{}\"", other))),
+        };
+        if x.leg_timeout_seconds == 0 || x.rollback_max_slippage < Decimal::ZERO || x.rollback_max_slippage >= Decimal::ONE {
+            return Err(ConfigError::Invalid("execution.leg_timeout_seconds must be > 0 and rollback_max_slippage in [0, 1)".to_string()));
+        }
+        Ok(PlannerConfig { submission, leg_timeout: Duration::from_secs(x.leg_timeout_seconds), rollback })
+    }
+
+    /// Maker quoting for Long rebalancing sets, or `None` when they are taken.
+    pub fn quoting_config(&self) -> Result<Option<QuotingConfig>, ConfigError> {
+        let x = &self.execution;
+        let mode = RebalancingMode::from_str(&x.rebalancing_mode)
+            .map_err(|_| ConfigError::Invalid(format!("execution.rebalancing_mode must be take or make, got \"{}\"", x.rebalancing_mode)))?;
+        if x.quote_reprice_ms == 0 || x.quote_ttl_seconds == 0 {
+            return Err(ConfigError::Invalid("execution.quote_reprice_ms and quote_ttl_seconds must be > 0".to_string()));
+        }
+        Ok((mode == RebalancingMode::Make).then(|| QuotingConfig {
+            reprice_interval: Duration::from_millis(x.quote_reprice_ms),
+            quote_ttl: Duration::from_secs(x.quote_ttl_seconds),
+        }))
+    }
+
+    pub fn execution_styles(&self) -> Result<HashMap<OpportunityKind, ExecutionStyle>, ConfigError> {
+        let x = &self.execution;
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+        if x.mid_timeout_seconds == 0 || x.iceberg_threshold < Decimal::ZERO || x.iceberg_slice <= Decimal::ZERO {
+            return invalid("execution.mid_timeout_seconds and iceberg_slice must be > 0 and iceberg_threshold >= 0".to_string());
+        }
+        let mut styles = HashMap::new();
+        for (strategy, style) in &x.styles {
+            let kind = OpportunityKind::from_str(strategy).map_err(|e| ConfigError::Invalid(format!("execution.styles: {}", e)))?;
+            if !matches!(kind, OpportunityKind::Rebalancing | OpportunityKind::ThresholdLadder) {
+                return invalid(format!("execution.styles: {} does not support execution styles", strategy));
+            }
+            let style = match style.to_lowercase().as_str() {
+                "taker" => ExecutionStyle::Taker,
+                "mid" => ExecutionStyle::MidThenCross { timeout: Duration::from_secs(x.mid_timeout_seconds) },
+                "iceberg" => ExecutionStyle::Iceberg { threshold: x.iceberg_threshold, slice: x.iceberg_slice },
+                other => return invalid(format!("execution.styles.{} must be taker, mid or iceberg, got \"{}\"", strategy, other)),
+            };
+            styles.insert(kind, style);
+        }
+        Ok(styles)
+    }
+
+    /// What each strategy requires of its taking legs, with `execution.time_in_force` applied.
+    pub fn time_in_force(&self) -> Result<HashMap<OpportunityKind, TimeInForce>, ConfigError> {
+        let mut tifs: HashMap<OpportunityKind, TimeInForce> = self.strategy_registry()?.time_in_force().into_iter()
+            .filter_map(|(name, tif)| OpportunityKind::from_str(name).ok().map(|kind| (kind, tif)))
+            .collect();
+        for (strategy, tif) in &self.execution.time_in_force {
+            let kind = OpportunityKind::from_str(strategy).map_err(|e| ConfigError::Invalid(format!("execution.time_in_force: {}", e)))?;
+            let tif = TimeInForce::from_str(tif).map_err(|e| ConfigError::Invalid(format!("execution.time_in_force.{}: {}", strategy, e)))?;
+            tifs.insert(kind, tif);
+        }
+        Ok(tifs)
+    }
+
+    pub fn copy_config(&self) -> CopyConfig {
+        let c = &self.copy_trading;
+        CopyConfig {
+            mirror: c.mirror,
+            delay: Duration::from_secs(c.delay_seconds),
+            scale: c.scale,
+            max_size: c.max_size,
+            poll_interval: Duration::from_secs(c.poll_seconds),
+            window: Duration::from_secs(c.window_hours * 3600),
+            criteria: FollowCriteria { min_closed_trades: c.min_closed_trades, min_win_rate: c.min_win_rate, min_realized_pnl: c.min_realized_pnl },
+        }
+    }
+
+    pub fn report_config(&self) -> Result<ReportConfig, ConfigError> {
+        let x = &self.execution;
+        let format = match x.report_format.to_lowercase().as_str() {
+            "json" => ReportFormat::Json,
+            "csv" => ReportFormat::Csv,
+            other => return Err(ConfigError::Invalid(format!("execution.report_format must be \"json\" or \"csv\", got \"{}\"", other))),
+        };
+        Ok(ReportConfig { dir: x.report_dir.as_ref().map(PathBuf::from), format, push: self.alerts.daily_report, hour_utc: self.alerts.daily_summary_hour_utc })
+    }
+
+    pub fn expiry_policy(&self) -> ExpiryPolicy {
+        let x = &self.execution;
+        ExpiryPolicy {
+            entry_cutoff: chrono::Duration::minutes(x.entry_cutoff_minutes as i64),
+            flatten_before: x.flatten_before_expiry_minutes.map(|m| chrono::Duration::minutes(m as i64)),
+        }
+    }
+
+    /// The primary RPC then its fallbacks; empty without POLYGON_RPC_URL.
+    pub fn rpc_urls(&self) -> Vec<String> {
+        let e = &self.endpoints;
+        e.polygon_rpc_url.iter().chain(e.polygon_rpc_fallback_urls.iter().filter(|_| e.polygon_rpc_url.is_some())).cloned().collect()
+    }
+
+    pub fn rpc_pool_config(&self) -> PoolConfig {
+        let e = &self.endpoints;
+        PoolConfig {
+            max_block_lag: e.rpc_max_block_lag,
+            failure_cooldown: Duration::from_secs(e.rpc_failure_cooldown_seconds),
+            health_check_interval: Duration::from_secs(e.rpc_health_check_seconds),
+        }
+    }
+
+    pub fn ingest_config(&self) -> IngestConfig {
+        IngestConfig { chunk_blocks: self.execution.ingest_chunk_blocks, confirmations: self.execution.ingest_confirmations }
+    }
+
+    pub fn signer_backend(&self) -> Result<SignerBackend, ConfigError> {
+        let k = &self.signer;
+        let required = |value: &Option<String>, key: &str| {
+            value.clone().ok_or_else(|| ConfigError::Invalid(format!("signer.backend = \"{}\" needs signer.{}", k.backend, key)))
+        };
+        match k.backend.to_lowercase().as_str() {
+            "env" => Ok(SignerBackend::Env),
+            "keystore" => Ok(SignerBackend::Keystore(PathBuf::from(required(&k.keystore_path, "keystore_path")?))),
+            "aws_kms" => Ok(SignerBackend::AwsKms { key_id: required(&k.aws_kms_key_id, "aws_kms_key_id")?, region: required(&k.aws_region, "aws_region")? }),
+            "gcp_kms" => Ok(SignerBackend::GcpKms { key_name: required(&k.gcp_kms_key, "gcp_kms_key")? }),
+            "ledger" => Ok(SignerBackend::Ledger { index: k.ledger_index }),
+            other => Err(ConfigError::Invalid(format!("signer.backend must be env, keystore, aws_kms, gcp_kms or ledger, got \"{}\"", other))),
+        }
+    }
+
+    /// The primary wallet's proxy; `None` when it trades from its own address.
+    pub fn proxy_wallet(&self) -> Result<Option<ProxyWallet>, ConfigError> {
+        let k = &self.signer;
+        let kind = WalletKind::from_str(&k.proxy_type)
+            .map_err(|_| ConfigError::Invalid(format!("signer.proxy_type must be eoa, poly_proxy or safe, got \"{}\"", k.proxy_type)))?;
+        match (kind, &k.proxy_address) {
+            (WalletKind::Eoa, _) => Ok(None),
+            (kind, Some(address)) => {
+                let address = address.parse().map_err(|e| ConfigError::Invalid(format!("signer.proxy_address {}: {}", address, e)))?;
+                Ok(Some(ProxyWallet { kind, address }))
+            }
+            (_, None) => Err(ConfigError::Invalid(format!("signer.proxy_type = \"{}\" needs signer.proxy_address", k.proxy_type))),
+        }
+    }
+
+    pub fn wallet_selection(&self) -> Result<SelectionStrategy, ConfigError> {
+        SelectionStrategy::from_str(&self.execution.wallet_selection).map_err(|_| {
+            ConfigError::Invalid(format!(
+                "execution.wallet_selection must be round_robin, lowest_inflight or balance_weighted, got \"{}\"",
+                self.execution.wallet_selection
+            ))
+        })
+    }
+
+    pub fn gamma_client(&self) -> RestConfig {
+        let e = &self.endpoints;
+        let retry = RetryPolicy { max_attempts: e.gamma_retry_attempts, ..RestConfig::default().retry };
+        RestConfig { timeout: Duration::from_secs(e.gamma_timeout_seconds), retry, requests_per_second: e.gamma_requests_per_second }
+    }
+
+    pub fn clob_budgets(&self) -> Budgets {
+        let e = &self.endpoints;
+        Budgets { orders: e.clob_orders_per_second, cancels: e.clob_cancels_per_second, market_data: e.clob_market_data_per_second }
+    }
+
+    pub fn market_filter(&self) -> Result<MarketFilter, ConfigError> {
+        let f = &self.filters;
+        Ok(MarketFilter {
+            categories: self.categories()?,
+            min_liquidity: f.min_liquidity,
+            min_volume_24hr: f.min_volume_24hr,
+            max_spread: f.max_spread,
+            include: f.include.build("include")?,
+            exclude: f.exclude.build("exclude")?,
+            paging: Paging { page_size: f.page_size, concurrency: f.page_concurrency, max_markets: f.max_markets },
+        })
+    }
+
+    /// Built-in dependency patterns minus the disabled ones.
+    pub fn pattern_registry(&self) -> Result<PatternRegistry, ConfigError> {
+        let mut registry = PatternRegistry::default()
+            .with_min_confidence(self.thresholds.min_dependency_confidence)
+            .with_expiry_tolerance(chrono::Duration::hours(self.thresholds.expiry_tolerance_hours as i64))
+            .with_similarity(SimilarityBackend::from_str(&self.thresholds.similarity_backend)
+                .map_err(|_| ConfigError::Invalid(format!("thresholds.similarity_backend must be levenshtein or tfidf, got \"{}\"", self.thresholds.similarity_backend)))?);
+        for name in &self.thresholds.disabled_patterns {
+            registry.set_enabled(name, false).map_err(|e| ConfigError::Invalid(format!("thresholds.disabled_patterns: {}", e)))?;
+        }
+        Ok(registry)
+    }
+
+    /// Built-in strategies minus the disabled ones, with their margin floors and staleness horizon.
+    pub fn strategy_registry(&self) -> Result<StrategyRegistry, ConfigError> {
+        let s = &self.strategies;
+        let categories = Arc::new(self.category_params()?);
+        let mut registry = StrategyRegistry::with_categories(self.fee_model(), self.profit_model(), self.execution.max_chain_depth, categories.clone());
+        registry.register(Box::new(CombinatorialStrategy { profit: self.profit_model(), resolution: self.resolution_risk(), categories }));
+        for name in &s.disabled {
+            registry.set_enabled(name, false).map_err(|e| ConfigError::Invalid(format!("strategies.disabled: {}", e)))?;
+        }
+        for (name, margin) in &s.min_margin {
+            if *margin < Decimal::ZERO {
+                return Err(ConfigError::Invalid(format!("strategies.min_margin.{} must be >= 0, got {}", name, margin)));
+            }
+            registry.set_min_margin(name, *margin).map_err(|e| ConfigError::Invalid(format!("strategies.min_margin: {}", e)))?;
+        }
+        registry.set_max_price_age((s.max_price_age_seconds > 0).then(|| chrono::Duration::seconds(s.max_price_age_seconds as i64)));
+        Ok(registry)
+    }
+
+    pub fn categories(&self) -> Result<Vec<MarketCategory>, ConfigError> {
+        self.filters.categories.iter()
+            .map(|c| MarketCategory::from_str(c).map_err(|e| ConfigError::Invalid(format!("filters.categories: {}", e))))
+            .collect()
+    }
+
+    pub fn log_format(&self) -> Result<LogFormat, ConfigError> {
+        match self.logging.format.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(ConfigError::Invalid(format!("logging.format must be \"text\" or \"json\", got \"{}\"", other))),
+        }
+    }
+
+    pub fn log_filter(&self) -> Result<EnvFilter, ConfigError> {
+        EnvFilter::try_new(&self.logging.filter)
+            .map_err(|e| ConfigError::Invalid(format!("logging.filter \"{}\": {}", self.logging.filter, e)))
+    }
+
+    pub fn dashboard_addr(&self) -> Result<Option<SocketAddr>, ConfigError> {
+        self.endpoints.dashboard_addr.as_deref()
+            .map(|a| a.parse().map_err(|e| ConfigError::Invalid(format!("endpoints.dashboard_addr \"{}\": {}", a, e))))
+            .transpose()
+    }
+
+    pub fn metrics_addr(&self) -> Result<Option<SocketAddr>, ConfigError> {
+        self.endpoints.metrics_addr.as_deref()
+            .map(|a| a.parse().map_err(|e| ConfigError::Invalid(format!("endpoints.metrics_addr \"{}\": {}", a, e))))
+            .transpose()
+    }
+}
+
+fn positive_limit(field: &str, limit: Decimal) -> Result<Decimal, ConfigError> {
+    if limit <= Decimal::ZERO {
+        return Err(ConfigError::Invalid(format!("{} must be > 0, got {}", field, limit)));
+    }
+    Ok(limit)
+}
+
+/// Parses `name:limit` pairs separated by commas, e.g. `politics:2000,crypto:500`.
+fn parse_pairs<T: FromStr>(key: &str, value: &str) -> Result<HashMap<String, T>, ConfigError>
+where
+    T::Err: Display,
+{
+    value.split(',').map(str::trim).filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once(':').ok_or_else(|| ConfigError::Env {
+                key: key.to_string(),
+                value: value.to_string(),
+                reason: format!("expected name:value, got \"{}\"", pair),
+            })?;
+            Ok((name.trim().to_string(), parse_env(key, value.trim())?))
+        })
+        .collect()
+}
+
+fn parse_env<T: FromStr>(key: &str, value: &str) -> Result<T, ConfigError>
+where
+    T::Err: Display,
+{
+    value.parse().map_err(|e: T::Err| ConfigError::Env { key: key.to_string(), value: value.to_string(), reason: e.to_string() })
+}
+
+fn override_value<T: FromStr>(key: &str, field: &mut T) -> Result<(), ConfigError>
+where
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(key) {
+        *field = parse_env(key, &value)?;
+    }
+    Ok(())
+}
+
+fn override_option(key: &str, field: &mut Option<String>) {
+    if let Ok(value) = env::var(key) {
+        *field = Some(value);
+    }
+}
+
+fn override_flag(key: &str, field: &mut bool) {
+    if let Ok(value) = env::var(key) {
+        *field = value == "true" || value == "1";
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_file_keeps_defaults_and_validates() {
+        let config = BotConfig::from_toml(r#"
+            [thresholds]
+            fee_threshold = 0.03
+            similarity_cutoff = 0.7
+            similarity_backend = "tfidf"
+            disabled_patterns = ["state_national"]
+
+            [sizing]
+            strategy = "kelly"
+
+            [sizing.category_limits]
+            Politics = 2000
+
+            [sizing.entity_limits]
+            Trump = 1500
+
+            [filters]
+            categories = ["Politics", "crypto"]
+
+            [execution.styles]
+            rebalancing = "iceberg"
+
+            [execution.time_in_force]
+            combinatorial = "fok"
+
+            [strategies]
+            disabled = ["multi_leg"]
+
+            [strategies.min_margin]
+            combinatorial = 0.03
+
+            [categories.crypto]
+            fee_threshold = 0.01
+            cooldown_seconds = 5
+        "#).unwrap();
+
+        config.validate().unwrap();
+        assert_eq!(config.thresholds.fee_threshold, dec!(0.03));
+        assert_eq!(config.execution.max_chain_depth, 4);
+        let sizing = config.sizing_config().unwrap();
+        assert_eq!(sizing.strategy, SizingStrategy::KellyFraction(dec!(0.25)));
+        assert_eq!((sizing.category_limits[&MarketCategory::Politics], sizing.entity_limits["trump"]), (dec!(2000), dec!(1500)));
+        assert_eq!(config.categories().unwrap(), vec![MarketCategory::Politics, MarketCategory::Crypto]);
+        assert!(!config.pattern_registry().unwrap().enabled().any(|p| p == "state_national"));
+        assert_eq!(config.pattern_registry().unwrap().similarity(), SimilarityBackend::TfIdf);
+        assert!(!config.strategy_registry().unwrap().enabled().any(|s| s == "multi_leg"));
+        let crypto = config.category_params().unwrap().for_category(&MarketCategory::Crypto).cloned().unwrap();
+        assert_eq!((crypto.fee_threshold, crypto.cooldown, crypto.min_net_profit), (Some(dec!(0.01)), Some(Duration::from_secs(5)), None));
+        let styles = config.execution_styles().unwrap();
+        assert_eq!(styles[&OpportunityKind::Rebalancing], ExecutionStyle::Iceberg { threshold: dec!(500), slice: dec!(100) });
+        let tifs = config.time_in_force().unwrap();
+        assert_eq!((tifs[&OpportunityKind::Combinatorial], tifs[&OpportunityKind::ThresholdLadder]), (TimeInForce::Fok, TimeInForce::Fok));
+        assert_eq!(tifs[&OpportunityKind::Rebalancing], TimeInForce::Ioc);
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        assert!(BotConfig::from_toml("[thresholds]\nfee_treshold = 0.02").is_err());
+        assert!(BotConfig::from_toml("[thresholds]\ndisabled_patterns = [\"astrology\"]").unwrap().validate().is_err());
+        assert!(BotConfig::from_toml("[strategies.min_margin]\nastrology = 0.01").unwrap().validate().is_err());
+
+        let config = BotConfig::from_toml("[sizing]\nmax_exposure = 100\nmax_market_exposure = 500").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("max_market_exposure")));
+
+        let config = BotConfig::from_toml("[filters]\ncategories = [\"weather\"]").unwrap();
+        assert!(config.validate().is_err());
+        let config = BotConfig::from_toml("[sizing.category_limits]\nweather = 100").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("category_limits")));
+
+        let config = BotConfig::from_toml("[execution]\nusdc_approval_amount = 0").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("usdc_approval_amount")));
+
+        let config = BotConfig::from_toml("[execution.styles]\ncombinatorial = \"mid\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("does not support")));
+        let config = BotConfig::from_toml("[execution.time_in_force]
+rebalancing = \"gtd\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("time_in_force.rebalancing")));
+
+        let config = BotConfig::from_toml("[execution]\nrollback_policy = \"pray\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("rollback_policy")));
+
+        let config = BotConfig::from_toml("[categories.weather]\nfee_threshold = 0.01").unwrap();
+        assert!(config.validate().is_err());
+        let config = BotConfig::from_toml("[categories.crypto]\nsizing_fraction = 1.5").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("categories.crypto.sizing_fraction")));
+        let config = BotConfig::from_toml("[endpoints]\npolygon_rpc_fallback_urls = [\"https://polygon.llamarpc.com\"]").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("polygon_rpc_fallback_urls")));
+        let config = BotConfig::from_toml("[signer]\nbackend = \"aws_kms\"\naws_region = \"us-east-1\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("signer.aws_kms_key_id")));
+        let config = BotConfig::from_toml("[signer]\nproxy_type = \"safe\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("signer.proxy_address")));
+        let config = BotConfig::from_toml("[logging]\nformat = \"xml\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("logging.format")));
+        let config = BotConfig::from_toml("[logging]\nfilter = \"polymarket_bot=loud\"").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("logging.filter")));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::clob_client::{OrderRequest, OrderSide, TimeInForce};
+use crate::execution_analyzer::{FollowCriteria, WalletTracker};
+use crate::execution_guard::GuardedExecutor;
+use crate::fill_ingest::{FillRecord, FillSource};
+use crate::fill_stream::FillEvent;
+use crate::market_state::SharedMarketState;
+use crate::notifier::{Alert, NotifierHandle};
+use crate::risk::{Exposure, ExposureGroups};
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopyConfig {
+    /// Place our own orders behind flagged wallets; otherwise they are only tracked and logged.
+    pub mirror: bool,
+    /// Wait before mirroring an entry, so a wash trade that is reversed immediately is not copied.
+    pub delay: Duration,
+    /// Fraction of the followed trade's USDC notional to mirror.
+    pub scale: Decimal,
+    /// Largest mirrored entry, in USDC.
+    pub max_size: Decimal,
+    pub poll_interval: Duration,
+    /// History kept per address for the profitability stats.
+    pub window: Duration,
+    pub criteria: FollowCriteria,
+}
+
+impl Default for CopyConfig {
+    fn default() -> Self {
+        Self {
+            mirror: false,
+            delay: Duration::from_secs(5),
+            scale: dec!(0.1),
+            max_size: dec!(25),
+            poll_interval: Duration::from_secs(10),
+            window: Duration::from_secs(7 * 24 * 3600),
+            criteria: FollowCriteria::default(),
+        }
+    }
+}
+
+/// The order that mirrors a followed buy: `scale` of its notional, capped at `max_size` USDC,
+/// limited at the followed price so we never pay more than the wallet we copy. `None` for
+/// exits and entries too small to place.
+pub fn mirror_order(fill: &FillRecord, scale: Decimal, max_size: Decimal) -> Option<OrderRequest> {
+    if fill.maker_side() != OrderSide::Buy {
+        return None;
+    }
+    let price = fill.price()?.round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    if price <= Decimal::ZERO || price >= Decimal::ONE {
+        return None;
+    }
+    let notional = (fill.maker_amount * scale).min(max_size);
+    let size = (notional / price).round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    (size > Decimal::ZERO).then(|| OrderRequest { asset_id: fill.asset_id().to_string(), price, size, side: OrderSide::Buy, tif: TimeInForce::Gtc })
+}
+
+/// Reads new fills from `source` every poll interval, or as they arrive on `live` while that
+/// stream is open.
+async fn next_fills<S: FillSource>(source: &S, live: &mut Option<broadcast::Receiver<FillEvent>>, cursor: &mut Option<u64>, poll_interval: Duration) -> Vec<FillRecord> {
+    if let Some(rx) = live {
+        match rx.recv().await {
+            Ok(FillEvent::Filled(fill)) => {
+                *cursor = Some(fill.block_number);
+                return vec![fill];
+            }
+            // Wallet stats keep the fill; they are rolling estimates, not accounting
+            Ok(FillEvent::Removed(fill)) => {
+                debug!(wallet = %fill.maker, tx = %fill.tx_hash, "followed fill removed by a reorg");
+                return Vec::new();
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "follower fell behind the fill stream");
+                return Vec::new();
+            }
+            Err(RecvError::Closed) => {
+                warn!("fill stream closed, follower falls back to polling");
+                *live = None;
+            }
+        }
+    }
+    sleep(poll_interval).await;
+    let tip = match source.latest_block().await {
+        Ok(tip) => tip,
+        Err(e) => {
+            warn!(error = %e, "follower failed to read block number");
+            return Vec::new();
+        }
+    };
+    let from = cursor.map_or(tip, |c| c + 1);
+    if from > tip {
+        return Vec::new();
+    }
+    match source.fills_in_range(from, tip).await {
+        Ok(fills) => {
+            *cursor = Some(tip);
+            fills
+        }
+        Err(e) => {
+            warn!(error = %e, from, to = tip, "follower failed to read fills");
+            Vec::new()
+        }
+    }
+}
+
+/// Follows new `OrderFilled` events, keeps rolling stats per address (primed with `history`,
+/// e.g. from the fill store) and mirrors entries of consistently profitable wallets in markets
+/// the bot tracks. With `live` set, fills come from the realtime stream instead of polling.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_follower<S: FillSource + 'static>(
+    source: Arc<S>,
+    mut live: Option<broadcast::Receiver<FillEvent>>,
+    history: Vec<FillRecord>,
+    state: SharedMarketState,
+    executor: Option<Arc<GuardedExecutor>>,
+    exposure: Arc<RwLock<Exposure>>,
+    config: CopyConfig,
+    alerts: NotifierHandle,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tracker = WalletTracker::new(config.window.as_secs());
+        for fill in &history {
+            tracker.record(fill);
+        }
+        info!(primed_with = history.len(), smart_wallets = tracker.smart_wallets(&config.criteria).len(), "copy-trading follower started");
+        let mut cursor: Option<u64> = None;
+        loop {
+            for fill in next_fills(source.as_ref(), &mut live, &mut cursor, config.poll_interval).await {
+                let was_smart = tracker.is_smart(&fill.maker, &config.criteria);
+                tracker.record(&fill);
+                if !was_smart && tracker.is_smart(&fill.maker, &config.criteria) {
+                    let stats = tracker.stats(&fill.maker).expect("just recorded");
+                    info!(wallet = %fill.maker, pnl = %stats.realized_pnl(), win_rate = %stats.win_rate(), trades = stats.closed_trades(), "wallet flagged as consistently profitable");
+                }
+                // Only wallets flagged before this fill are mirrored
+                if !config.mirror || !was_smart {
+                    continue;
+                }
+                let (Some(executor), Some(order)) = (&executor, mirror_order(&fill, config.scale, config.max_size)) else { continue };
+                let market = {
+                    let state = state.read().await;
+                    state.markets.resolve(&order.asset_id).and_then(|id| state.markets.get(id.market)).map(|m| (m.id.clone(), ExposureGroups::of(m)))
+                };
+                let Some((market_id, groups)) = market else {
+                    debug!(wallet = %fill.maker, asset_id = %order.asset_id, "followed entry outside tracked markets");
+                    continue;
+                };
+
+                let (executor, exposure, alerts, wallet) = (executor.clone(), exposure.clone(), alerts.clone(), fill.maker.clone());
+                tokio::spawn(async move {
+                    sleep(config.delay).await;
+                    let amount = order.price * order.size;
+                    match executor.execute_copy_trade(&market_id, &order).await {
+                        Ok(_) => {
+                            info!(%wallet, %market_id, %amount, "mirrored entry");
+                            exposure.write().await.record(&market_id, &groups, amount);
+                            alerts.notify(Alert::Execution { strategy: "copy", market_id, amount, expected_pnl: Decimal::ZERO });
+                        }
+                        Err(e) => warn!(%wallet, %market_id, error = %e, "failed to mirror entry"),
+                    }
+                });
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill_ingest::USDC_ASSET_ID;
+
+    #[test]
+    fn test_mirror_order_scales_and_caps_entries() {
+        let mut fill = FillRecord {
+            block_number: 1,
+            timestamp: 0,
+            tx_hash: "0x1".to_string(),
+            log_index: 0,
+            order_hash: "0x01".to_string(),
+            maker: "0xaa".to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: USDC_ASSET_ID.to_string(),
+            taker_asset_id: "7".to_string(),
+            maker_amount: dec!(400),
+            taker_amount: dec!(1000),
+            fee: Decimal::ZERO,
+            market: None,
+        };
+        // 10% of 400 USDC at 0.40
+        assert_eq!(mirror_order(&fill, dec!(0.1), dec!(100)), Some(OrderRequest { asset_id: "7".to_string(), price: dec!(0.40), size: dec!(100), side: OrderSide::Buy, tif: TimeInForce::Gtc }));
+        assert_eq!(mirror_order(&fill, dec!(0.1), dec!(20)).map(|o| o.size), Some(dec!(50)));
+
+        // The wallet selling out of its position is not an entry
+        std::mem::swap(&mut fill.maker_asset_id, &mut fill.taker_asset_id);
+        assert_eq!(mirror_order(&fill, dec!(0.1), dec!(100)), None);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::kill_switch::{Halt, KillSwitch};
+use crate::market_state::SharedMarketState;
+use crate::metrics::EXPECTED_PNL;
+use crate::order_lifecycle::SharedOrderTracker;
+use crate::positions::SharedPositions;
+use crate::shared_types::Opportunity;
+use axum::extract::State;
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Opportunities kept for the dashboard.
+const RECENT_OPPORTUNITIES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OpportunitySummary {
+    pub id: u64,
+    pub detected_at: DateTime<Utc>,
+    pub strategy: &'static str,
+    pub markets: Vec<String>,
+    pub asset_ids: Vec<String>,
+    pub size: Decimal,
+    pub gross_profit: Decimal,
+    pub net_profit: Decimal,
+}
+
+/// The latest detections, newest first, bounded so a noisy market cannot grow it.
+#[derive(Debug)]
+pub struct RecentOpportunities {
+    capacity: usize,
+    items: Mutex<VecDeque<OpportunitySummary>>,
+}
+
+impl Default for RecentOpportunities {
+    fn default() -> Self {
+        Self::new(RECENT_OPPORTUNITIES)
+    }
+}
+
+impl RecentOpportunities {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, items: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    pub fn push(&self, opportunity: &Opportunity) {
+        let meta = opportunity.meta();
+        let summary = OpportunitySummary {
+            id: meta.id,
+            detected_at: meta.detected_at,
+            strategy: meta.strategy,
+            markets: opportunity.markets(),
+            asset_ids: meta.asset_ids.clone(),
+            size: meta.size,
+            gross_profit: meta.gross_profit,
+            net_profit: meta.net_profit,
+        };
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        if items.len() == self.capacity {
+            items.pop_back();
+        }
+        items.push_front(summary);
+    }
+
+    pub fn snapshot(&self) -> Vec<OpportunitySummary> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StreamStatus {
+    pub connected: bool,
+    /// When the stream last connected or dropped.
+    pub since: DateTime<Utc>,
+    pub last_message: Option<DateTime<Utc>>,
+    pub reconnects: u64,
+    pub last_error: Option<String>,
+}
+
+/// Connection state of each websocket stream, by topic ("prices", "book", "user").
+#[derive(Debug, Default)]
+pub struct StreamHealth {
+    streams: Mutex<BTreeMap<&'static str, StreamStatus>>,
+}
+
+impl StreamHealth {
+    fn update(&self, topic: &'static str, f: impl FnOnce(&mut StreamStatus)) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let status = streams.entry(topic).or_insert_with(|| StreamStatus {
+            connected: false,
+            since: Utc::now(),
+            last_message: None,
+            reconnects: 0,
+            last_error: None,
+        });
+        f(status);
+    }
+
+    pub fn connecting(&self, topic: &'static str) {
+        self.update(topic, |s| {
+            s.connected = true;
+            s.since = Utc::now();
+        });
+    }
+
+    pub fn dropped(&self, topic: &'static str, error: &str) {
+        self.update(topic, |s| {
+            s.connected = false;
+            s.since = Utc::now();
+            s.reconnects += 1;
+            s.last_error = Some(error.to_string());
+        });
+    }
+
+    pub fn message(&self, topic: &'static str) {
+        self.update(topic, |s| s.last_message = Some(Utc::now()));
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, StreamStatus> {
+        self.streams.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Everything the dashboard reads. All of it is shared with the trading loop; handlers only
+/// take read locks.
+#[derive(Clone)]
+pub struct Dashboard {
+    pub state: SharedMarketState,
+    pub positions: SharedPositions,
+    pub orders: SharedOrderTracker,
+    pub opportunities: Arc<RecentOpportunities>,
+    pub health: Arc<StreamHealth>,
+    pub kill_switch: Arc<KillSwitch>,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutcomeView {
+    name: String,
+    asset_id: String,
+    price: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+struct MarketView {
+    id: String,
+    title: String,
+    end_date: String,
+    outcomes: Vec<OutcomeView>,
+}
+
+#[derive(Debug, Serialize)]
+struct OrderView {
+    order_id: String,
+    asset_id: String,
+    side: &'static str,
+    price: Decimal,
+    size: Decimal,
+    filled: Decimal,
+    state: String,
+    placed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct PositionView {
+    asset_id: String,
+    market_id: Option<String>,
+    outcome: Option<String>,
+    shares: Decimal,
+    cost: Decimal,
+    price: Option<Decimal>,
+    unrealized_pnl: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+struct PnlView {
+    /// Expected PnL of completed executions since start.
+    expected: Decimal,
+    unrealized: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthView {
+    started_at: DateTime<Utc>,
+    /// Why the kill switch stopped execution, while it does.
+    halted: Option<Halt>,
+    tracked_markets: usize,
+    streams: BTreeMap<&'static str, StreamStatus>,
+}
+
+async fn markets(State(d): State<Dashboard>) -> Json<Vec<MarketView>> {
+    let state = d.state.read().await;
+    let markets = state.markets.iter()
+        .filter_map(|(id, _)| state.priced(id))
+        .map(|m| MarketView {
+            id: m.id,
+            title: m.title,
+            end_date: m.end_date.to_rfc3339(),
+            outcomes: m.conditions.into_iter().map(|c| OutcomeView { name: c.name, asset_id: c.asset_id, price: c.price }).collect(),
+        })
+        .collect();
+    Json(markets)
+}
+
+async fn opportunities(State(d): State<Dashboard>) -> Json<Vec<OpportunitySummary>> {
+    Json(d.opportunities.snapshot())
+}
+
+async fn orders(State(d): State<Dashboard>) -> Json<Vec<OrderView>> {
+    let tracker = d.orders.read().await;
+    let orders = tracker.open_orders()
+        .map(|o| OrderView {
+            order_id: o.order_id.clone(),
+            asset_id: o.asset_id.clone(),
+            side: o.side.as_str(),
+            price: o.price,
+            size: o.size,
+            filled: o.filled(),
+            state: o.state.to_string(),
+            placed_at: o.placed_at,
+        })
+        .collect();
+    Json(orders)
+}
+
+async fn positions(State(d): State<Dashboard>) -> Json<Vec<PositionView>> {
+    let state = d.state.read().await;
+    let tracker = d.positions.read().await;
+    let positions = tracker.holdings()
+        .map(|(asset_id, &shares)| {
+            let handle = state.markets.resolve(asset_id);
+            let market = handle.and_then(|h| state.markets.get(h.market));
+            let price = handle.and_then(|h| state.price(h));
+            let cost = tracker.cost(asset_id);
+            PositionView {
+                asset_id: asset_id.clone(),
+                market_id: market.map(|m| m.id.clone()),
+                outcome: market.and_then(|m| m.conditions.iter().find(|c| &c.asset_id == asset_id)).map(|c| c.name.clone()),
+                shares,
+                cost,
+                price,
+                unrealized_pnl: price.map(|p| shares * p - cost),
+            }
+        })
+        .collect();
+    Json(positions)
+}
+
+async fn pnl(State(d): State<Dashboard>) -> Json<PnlView> {
+    let state = d.state.read().await;
+    let unrealized = d.positions.read().await.unrealized_pnl(&state);
+    let expected = Decimal::from_f64(EXPECTED_PNL.get()).unwrap_or_default().round_dp(6);
+    Json(PnlView { expected, unrealized })
+}
+
+async fn health(State(d): State<Dashboard>) -> Json<HealthView> {
+    let tracked_markets = d.state.read().await.markets.iter().count();
+    Json(HealthView { started_at: d.started_at, halted: d.kill_switch.halted(), tracked_markets, streams: d.health.snapshot() })
+}
+
+pub fn router(dashboard: Dashboard) -> Router {
+    Router::new()
+        .route("/", get(|| async { Html(INDEX) }))
+        .route("/api/markets", get(markets))
+        .route("/api/opportunities", get(opportunities))
+        .route("/api/orders", get(orders))
+        .route("/api/positions", get(positions))
+        .route("/api/pnl", get(pnl))
+        .route("/api/health", get(health))
+        .with_state(dashboard)
+}
+
+/// Serves the dashboard page and its JSON API on `addr` until the task is dropped.
+pub async fn serve(addr: SocketAddr, dashboard: Dashboard) -> Result<(), hyper::Error> {
+    info!(%addr, "serving the dashboard");
+    axum::Server::bind(&addr).serve(router(dashboard).into_make_service()).await
+}
+
+const INDEX: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Polymarket bot</title>
+<style>
+  body { font: 13px system-ui, sans-serif; margin: 1.5em; color: #222; }
+  h2 { font-size: 15px; margin: 1.5em 0 0.4em; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: 2px 8px; border-bottom: 1px solid #eee; white-space: nowrap; }
+  .down { color: #b00; } .up { color: #070; }
+</style>
+</head>
+<body>
+<h1>Polymarket bot</h1>
+<div id="summary"></div>
+<h2>Connections</h2><table id="health"></table>
+<h2>Opportunities</h2><table id="opportunities"></table>
+<h2>Open orders</h2><table id="orders"></table>
+<h2>Positions</h2><table id="positions"></table>
+<h2>Markets</h2><table id="markets"></table>
+<script>
+const get = path => fetch(path).then(r => r.json());
+const esc = v => String(v ?? "").replace(/[&<>"]/g, c => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" })[c]);
+function table(id, columns, rows) {
+  const head = "<tr>" + columns.map(c => `<th>${c[0]}</th>`).join("") + "</tr>";
+  const body = rows.map(r => "<tr>" + columns.map(c => `<td>${c[1](r)}</td>`).join("") + "</tr>").join("");
+  document.getElementById(id).innerHTML = head + body;
+}
+async function refresh() {
+  const [health, pnl, opportunities, orders, positions] = await Promise.all(
+    ["health", "pnl", "opportunities", "orders", "positions"].map(p => get("/api/" + p)));
+  document.getElementById("summary").innerHTML =
+    `${health.tracked_markets} markets tracked, ` +
+    `expected PnL $${esc(pnl.expected)}, unrealized $${esc(pnl.unrealized)}` +
+    (health.halted ? ' <b class="down">HALTED</b>' : "");
+  table("health", [["Stream", ([k]) => esc(k)],
+    ["State", ([, s]) => s.connected ? '<span class="up">connected</span>' : '<span class="down">down</span>'],
+    ["Since", ([, s]) => esc(s.since)], ["Last message", ([, s]) => esc(s.last_message)],
+    ["Reconnects", ([, s]) => esc(s.reconnects)], ["Last error", ([, s]) => esc(s.last_error)]], Object.entries(health.streams));
+  table("opportunities", [["Id", o => esc(o.id)], ["Detected", o => esc(o.detected_at)], ["Strategy", o => esc(o.strategy)],
+    ["Markets", o => esc(o.markets.join(", "))], ["Size", o => esc(o.size)], ["Net", o => esc(o.net_profit)]], opportunities);
+  table("orders", [["Order", o => esc(o.order_id)], ["Asset", o => esc(o.asset_id)], ["Side", o => esc(o.side)],
+    ["Price", o => esc(o.price)], ["Filled", o => `${esc(o.filled)} / ${esc(o.size)}`], ["State", o => esc(o.state)]], orders);
+  table("positions", [["Market", p => esc(p.market_id)], ["Outcome", p => esc(p.outcome)], ["Shares", p => esc(p.shares)],
+    ["Cost", p => esc(p.cost)], ["Price", p => esc(p.price)], ["Unrealized", p => esc(p.unrealized_pnl)]], positions);
+}
+async function refreshMarkets() {
+  const markets = await get("/api/markets");
+  table("markets", [["Market", m => esc(m.title)], ["Ends", m => esc(m.end_date)],
+    ["Prices", m => m.outcomes.map(o => `${esc(o.name)} ${esc(o.price)}`).join(" / ")]], markets);
+}
+refresh(); refreshMarkets();
+setInterval(refresh, 2000); setInterval(refreshMarkets, 15000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kill_switch::KillSwitchConfig;
+    use crate::market_state::MarketState;
+    use crate::notifier::NotifierHandle;
+    use crate::order_lifecycle::OrderTracker;
+    use crate::positions::PositionTracker;
+    use crate::shared_types::{Leg, MultiLegOpportunity};
+    use rust_decimal_macros::dec;
+    use tokio::sync::RwLock;
+
+    #[tokio::test]
+    async fn test_api_serves_opportunities_and_health() {
+        let opportunities = Arc::new(RecentOpportunities::new(2));
+        let leg = |market: &str| Leg { market_id: market.to_string(), condition_name: "Yes".to_string(), asset_id: format!("{}-yes", market), price: dec!(0.4) };
+        for profit in [dec!(0.01), dec!(0.02), dec!(0.03)] {
+            opportunities.push(&Opportunity::multi_leg(MultiLegOpportunity { legs: vec![leg("a"), leg("b")], is_cycle: false, profit }));
+        }
+        let health = Arc::new(StreamHealth::default());
+        health.connecting("prices");
+        health.dropped("book", "stale");
+        let dashboard = Dashboard {
+            state: Arc::new(RwLock::new(MarketState::build(Vec::new(), 0.6, Default::default()))),
+            positions: Arc::new(RwLock::new(PositionTracker::default())),
+            orders: Arc::new(RwLock::new(OrderTracker::default())),
+            opportunities,
+            health,
+            kill_switch: Arc::new(KillSwitch::new(KillSwitchConfig::default(), NotifierHandle::disabled())),
+            started_at: Utc::now(),
+        };
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(router(dashboard).into_make_service()));
+        let get = |path: &str| reqwest::get(format!("http://{}{}", addr, path));
+
+        let found: serde_json::Value = get("/api/opportunities").await.unwrap().json().await.unwrap();
+        // Capacity two keeps the newest, first
+        let nets: Vec<&str> = found.as_array().unwrap().iter().map(|o| o["net_profit"].as_str().unwrap()).collect();
+        assert_eq!(nets, ["0.03", "0.02"]);
+        assert_eq!(found[0]["markets"], serde_json::json!(["a", "b"]));
+
+        let status: serde_json::Value = get("/api/health").await.unwrap().json().await.unwrap();
+        assert_eq!((status["streams"]["prices"]["connected"].as_bool(), status["streams"]["book"]["reconnects"].as_u64()), (Some(true), Some(1)));
+        assert!(status["halted"].is_null());
+        assert!(get("/").await.unwrap().text().await.unwrap().contains("/api/markets"));
+        server.abort();
+    }
+}
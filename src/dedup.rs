@@ -0,0 +1,124 @@
+use crate::metrics::OPPORTUNITIES_SUPPRESSED;
+use crate::opportunity_store::OpportunityKind;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Key = (String, OpportunityKind);
+
+#[derive(Debug, Default)]
+struct Slot {
+    in_flight: bool,
+    /// When the last attempt finished; the cooldown runs from here.
+    finished_at: Option<Instant>,
+    /// Cooldown the last claim asked for.
+    cooldown: Duration,
+}
+
+impl Slot {
+    fn cooling(&self, now: Instant) -> bool {
+        self.finished_at.is_some_and(|t| now.duration_since(t) < self.cooldown)
+    }
+}
+
+/// Suppresses repeat executions of the same opportunity. While an imbalance persists every
+/// tick re-detects it, so each `(market_id, kind)` may only have one execution in flight and
+/// must wait its cooldown after it completes before being traded again. The cooldown defaults
+/// to `cooldown` and may be set per claim, e.g. per market category.
+#[derive(Debug)]
+pub struct OpportunityGate {
+    cooldown: Duration,
+    slots: Mutex<HashMap<Key, Slot>>,
+}
+
+impl OpportunityGate {
+    pub fn new(cooldown: Duration) -> Self {
+        Self { cooldown, slots: Mutex::new(HashMap::new()) }
+    }
+
+    /// The cooldown used when a claim sets none of its own.
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Claims the opportunity for execution, or returns `None` if it is in flight or cooling
+    /// down. The claim is released, and `cooldown` started, when the guard is dropped.
+    pub fn try_begin(&self, market_id: &str, kind: OpportunityKind, cooldown: Duration) -> Option<InFlight<'_>> {
+        let claim = self.try_begin_at(market_id, kind, cooldown, Instant::now());
+        if claim.is_none() {
+            OPPORTUNITIES_SUPPRESSED.with_label_values(&[kind.as_str()]).inc();
+        }
+        claim
+    }
+
+    fn try_begin_at(&self, market_id: &str, kind: OpportunityKind, cooldown: Duration, now: Instant) -> Option<InFlight<'_>> {
+        let key = (market_id.to_string(), kind);
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = slots.entry(key.clone()).or_default();
+        if slot.in_flight || slot.cooling(now) {
+            return None;
+        }
+        slot.in_flight = true;
+        slot.cooldown = cooldown;
+        Some(InFlight { gate: self, key: Some(key) })
+    }
+
+    fn finish(&self, key: Key, now: Instant) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = slots.entry(key).or_default();
+        slot.in_flight = false;
+        slot.finished_at = Some(now);
+        // Entries whose cooldown has lapsed carry no information
+        slots.retain(|_, s| s.in_flight || s.cooling(now));
+    }
+
+    pub fn is_in_flight(&self, market_id: &str, kind: OpportunityKind) -> bool {
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.get(&(market_id.to_string(), kind)).map(|s| s.in_flight).unwrap_or(false)
+    }
+}
+
+/// An execution claim from [`OpportunityGate::try_begin`].
+pub struct InFlight<'a> {
+    gate: &'a OpportunityGate,
+    key: Option<Key>,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.gate.finish(key, Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_then_cooldown_per_market_and_kind() {
+        let gate = OpportunityGate::new(Duration::from_secs(30));
+        let cooldown = gate.cooldown();
+        let start = Instant::now();
+
+        let claim = gate.try_begin_at("m1", OpportunityKind::Rebalancing, cooldown, start).unwrap();
+        assert!(gate.try_begin_at("m1", OpportunityKind::Rebalancing, cooldown, start).is_none());
+        assert!(gate.is_in_flight("m1", OpportunityKind::Rebalancing));
+        // Other markets and other strategies on the same market are independent
+        assert!(gate.try_begin_at("m2", OpportunityKind::Rebalancing, cooldown, start).is_some());
+        assert!(gate.try_begin_at("m1", OpportunityKind::MultiLeg, cooldown, start).is_some());
+
+        let mut claim = claim;
+        let key = claim.key.take().unwrap();
+        gate.finish(key, start);
+        assert!(!gate.is_in_flight("m1", OpportunityKind::Rebalancing));
+        assert!(gate.try_begin_at("m1", OpportunityKind::Rebalancing, cooldown, start + Duration::from_secs(10)).is_none());
+        assert!(gate.try_begin_at("m1", OpportunityKind::Rebalancing, cooldown, start + Duration::from_secs(31)).is_some());
+
+        // A claim's own cooldown applies after it finishes
+        let mut fast = gate.try_begin_at("m3", OpportunityKind::Rebalancing, Duration::from_secs(5), start).unwrap();
+        gate.finish(fast.key.take().unwrap(), start);
+        assert!(gate.try_begin_at("m3", OpportunityKind::Rebalancing, cooldown, start + Duration::from_secs(6)).is_some());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::shared_types::Entity;
+use lazy_static::lazy_static;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+use thiserror::Error;
+
+const BUILTIN_ALIASES: &str = include_str!("../data/entities.json");
+const MONTHS: [&str; 12] = ["january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december"];
+
+#[derive(Debug, Error)]
+pub enum EntityError {
+    #[error("failed to read alias file {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("failed to parse alias file {path}: {source}")]
+    Parse { path: String, source: serde_json::Error },
+}
+
+/// Canonical name => surface forms.
+type AliasGroup = HashMap<String, Vec<String>>;
+
+/// Alias dictionary in the shape of `data/entities.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AliasFile {
+    pub candidates: AliasGroup,
+    pub locations: AliasGroup,
+    pub teams: AliasGroup,
+    pub events: AliasGroup,
+}
+
+/// Dictionary-driven entity extractor. Titles are tokenized, alias phrases are matched
+/// longest-first so "new york knicks" is a team rather than a location, and dates and
+/// numbers ("$100k", "5%", "march 31 2025") become `Entity::NumericalValue`.
+#[derive(Debug, Default)]
+pub struct EntityExtractor {
+    phrases: HashMap<Vec<String>, Entity>,
+    longest_phrase: usize,
+}
+
+impl EntityExtractor {
+    /// The built-in dictionary shipped in `data/entities.json`.
+    pub fn builtin() -> Self {
+        let mut extractor = Self::default();
+        let aliases: AliasFile = serde_json::from_str(BUILTIN_ALIASES).expect("built-in entity aliases are valid JSON");
+        extractor.add_aliases(aliases);
+        extractor
+    }
+
+    /// Adds every alias in `file`. Later aliases override earlier ones for the same phrase.
+    pub fn add_aliases(&mut self, file: AliasFile) {
+        let groups = [
+            (file.candidates, Entity::Candidate as fn(String) -> Entity),
+            (file.locations, Entity::Location),
+            (file.teams, Entity::Team),
+            (file.events, Entity::Event),
+        ];
+        for (group, make) in groups {
+            for (canonical, aliases) in group {
+                let entity = make(canonical.to_lowercase());
+                for alias in aliases.iter().chain(std::iter::once(&canonical)) {
+                    let phrase = tokenize(alias);
+                    if phrase.is_empty() {
+                        continue;
+                    }
+                    self.longest_phrase = self.longest_phrase.max(phrase.len());
+                    self.phrases.insert(phrase, entity.clone());
+                }
+            }
+        }
+    }
+
+    /// The entity `text` names in full, when it is exactly one alias.
+    pub fn lookup(&self, text: &str) -> Option<&Entity> {
+        self.phrases.get(&tokenize(text))
+    }
+
+    pub fn extract(&self, text: &str) -> HashSet<Entity> {
+        let tokens = tokenize(text);
+        let mut entities = HashSet::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Some((date, used)) = parse_date(&tokens[i..]) {
+                entities.insert(Entity::NumericalValue(date));
+                i += used;
+                continue;
+            }
+            let longest = self.longest_phrase.min(tokens.len() - i);
+            let matched = (1..=longest).rev().find_map(|len| self.phrases.get(&tokens[i..i + len]).map(|e| (e, len)));
+            if let Some((entity, len)) = matched {
+                entities.insert(entity.clone());
+                i += len;
+                continue;
+            }
+            if let Some(value) = parse_number(&tokens[i]) {
+                entities.insert(Entity::NumericalValue(value));
+            }
+            i += 1;
+        }
+        entities
+    }
+}
+
+lazy_static! {
+    static ref EXTRACTOR: RwLock<EntityExtractor> = RwLock::new(EntityExtractor::builtin());
+}
+
+/// Extracts entities with the process-wide dictionary (built-ins plus any loaded aliases).
+pub fn extract_entities(text: &str) -> HashSet<Entity> {
+    EXTRACTOR.read().unwrap_or_else(|e| e.into_inner()).extract(text)
+}
+
+/// Looks `text` up as a whole in the process-wide dictionary.
+pub fn lookup_entity(text: &str) -> Option<Entity> {
+    EXTRACTOR.read().unwrap_or_else(|e| e.into_inner()).lookup(text).cloned()
+}
+
+/// Merges a custom alias file into the process-wide dictionary.
+pub fn load_aliases(path: &Path) -> Result<(), EntityError> {
+    let display = path.display().to_string();
+    let text = std::fs::read_to_string(path).map_err(|source| EntityError::Io { path: display.clone(), source })?;
+    let file: AliasFile = serde_json::from_str(&text).map_err(|source| EntityError::Parse { path: display, source })?;
+    EXTRACTOR.write().unwrap_or_else(|e| e.into_inner()).add_aliases(file);
+    Ok(())
+}
+
+/// Lowercases and splits on anything that is not part of a word or number. Normalized
+/// titles use `_` as the separator, so both raw and normalized text tokenize the same way.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '$' | '%' | '.' | ',')))
+        .map(|t| t.trim_matches(|c: char| matches!(c, '.' | ',')))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// "$100k" => 100000, "2.5m" => 2500000, "5%" => 5, "1,000" => 1000.
+pub(crate) fn parse_number(token: &str) -> Option<Decimal> {
+    let token = token.trim_start_matches('$').replace(',', "");
+    let (digits, multiplier) = match token.char_indices().find(|(_, c)| c.is_alphabetic() || *c == '%') {
+        Some((idx, _)) => {
+            let multiplier = match &token[idx..] {
+                "k" => Decimal::from(1_000),
+                "m" | "mm" => Decimal::from(1_000_000),
+                "b" | "bn" => Decimal::from(1_000_000_000),
+                "%" => Decimal::ONE,
+                _ => return None,
+            };
+            (&token[..idx], multiplier)
+        }
+        None => (token.as_str(), Decimal::ONE),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    Decimal::from_str(digits).ok().map(|v| v * multiplier)
+}
+
+/// "march 31 2025" (with optional ordinal suffix) => 20250331.
+fn parse_date(tokens: &[String]) -> Option<(Decimal, usize)> {
+    let first = tokens.first()?;
+    let month = MONTHS.iter().position(|m| m == first)? as u32 + 1;
+    let day: u32 = tokens.get(1)?.trim_end_matches(|c: char| c.is_alphabetic()).parse().ok()?;
+    let year: u32 = tokens.get(2)?.parse().ok()?;
+    if !(1..=31).contains(&day) || !(1900..=2100).contains(&year) {
+        return None;
+    }
+    Some((Decimal::from(year * 10_000 + month * 100 + day), 3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_aliases_gazetteers_and_numbers() {
+        let extractor = EntityExtractor::builtin();
+        let entities = extractor.extract("Will DJT beat Kamala in Pennsylvania by 5%?");
+        assert!(entities.contains(&Entity::Candidate("trump".to_string())));
+        assert!(entities.contains(&Entity::Candidate("harris".to_string())));
+        assert!(entities.contains(&Entity::Location("pennsylvania".to_string())));
+        assert!(entities.contains(&Entity::NumericalValue(dec!(5))));
+
+        // Longest match wins and normalized titles tokenize the same as raw ones
+        let entities = extractor.extract("new_york_knicks_win_nba_finals");
+        assert!(entities.contains(&Entity::Team("knicks".to_string())));
+        assert!(!entities.contains(&Entity::Location("new york".to_string())));
+
+        let entities = extractor.extract("BTC above $100k by March 31, 2025");
+        assert!(entities.contains(&Entity::Event("bitcoin".to_string())));
+        assert!(entities.contains(&Entity::NumericalValue(dec!(100000))));
+        assert!(entities.contains(&Entity::NumericalValue(dec!(20250331))));
+    }
+
+    #[test]
+    fn test_custom_aliases_extend_builtins() {
+        let mut extractor = EntityExtractor::builtin();
+        extractor.add_aliases(serde_json::from_str(r#"{"candidates": {"vance": ["veep"]}}"#).unwrap());
+        assert!(extractor.extract("veep_2028").contains(&Entity::Candidate("vance".to_string())));
+        assert!(extractor.extract("jd_vance").contains(&Entity::Candidate("vance".to_string())));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::arbitrage_engine::EngineError;
+use crate::backtest::BacktestError;
+use crate::blockchain::ExecutionError;
+use crate::cli::CliError;
+use crate::clob_client::ClobError;
+use crate::config::ConfigError;
+use crate::entities::EntityError;
+use crate::kill_switch::KillSwitchError;
+use crate::market_fetcher::FetchError;
+use crate::opportunity_store::StoreError;
+use crate::topic_classifier::TopicError;
+use thiserror::Error;
+
+/// Top-level error for the bot binary, wrapping each module's error type.
+#[derive(Debug, Error)]
+pub enum BotError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error(transparent)]
+    Clob(#[from] ClobError),
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error(transparent)]
+    Entities(#[from] EntityError),
+    #[error(transparent)]
+    Topics(#[from] TopicError),
+    #[error(transparent)]
+    Backtest(#[from] BacktestError),
+    #[error(transparent)]
+    Cli(#[from] CliError),
+    #[error(transparent)]
+    KillSwitch(#[from] KillSwitchError),
+}
+
+impl BotError {
+    /// Whether the failing operation can reasonably be retried or skipped.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            BotError::Config(_) => false,
+            BotError::Fetch(e) => e.is_retryable(),
+            BotError::Clob(e) => e.is_recoverable(),
+            BotError::Execution(e) => e.is_retryable(),
+            BotError::Engine(_) => true,
+            BotError::Store(_) => false,
+            BotError::Entities(_) => false,
+            BotError::Topics(_) => false,
+            BotError::Backtest(_) => false,
+            BotError::Cli(_) => false,
+            BotError::KillSwitch(_) => false,
+        }
+    }
+}
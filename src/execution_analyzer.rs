@@ -1,58 +1,572 @@
+use crate::blockchain::ConditionResolutionFilter;
+use crate::clob_client::OrderSide;
+use crate::fill_ingest::FillRecord;
+use crate::token_resolver::TokenResolver;
+use crate::units::from_units;
+use ethers::types::H256;
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
-// Stub for User Execution Data
+/// One outcome-token purchase by an address.
 #[derive(Debug, Clone)]
 pub struct UserExecution {
     pub user_address: String,
     pub market_id: String,
     pub outcome_index: usize,
+    /// Shares bought.
     pub amount: Decimal,
+    /// USDC paid per share.
+    pub price: Decimal,
     pub timestamp: u64,
 }
 
+impl UserExecution {
+    /// The maker's purchase in a fill whose token was resolved to a market; `None` for sells
+    /// and unresolved fills.
+    pub fn from_fill(fill: &FillRecord) -> Option<Self> {
+        let market = fill.market.as_ref()?;
+        if fill.maker_side() != OrderSide::Buy {
+            return None;
+        }
+        Some(Self {
+            user_address: fill.maker.clone(),
+            market_id: market.market_id.clone(),
+            outcome_index: market.outcome_index,
+            amount: fill.shares(),
+            price: fill.price()?,
+            timestamp: fill.timestamp,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageHeuristic {
+    /// Longest span between the first and last leg of one position.
+    pub window_secs: u64,
+    /// Combined price of one share of every leg must stay below this; 1 is the payout.
+    pub max_net_cost: Decimal,
+    pub min_confidence: f64,
+    /// Market pairs whose outcomes imply each other; their legs are combined like outcomes
+    /// of one market.
+    pub related_markets: Vec<(String, String)>,
+}
+
+impl Default for ArbitrageHeuristic {
+    fn default() -> Self {
+        Self { window_secs: 300, max_net_cost: Decimal::ONE, min_confidence: 0.5, related_markets: Vec::new() }
+    }
+}
+
+/// Purchases of distinct outcomes by one address that lock in a payout above their cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageSignal {
+    pub user_address: String,
+    pub market_ids: Vec<String>,
+    /// Distinct (market, outcome) positions bought.
+    pub legs: usize,
+    /// Sum of the legs' average prices.
+    pub net_cost: Decimal,
+    /// 0..=1, from the locked-in edge, how quickly the legs followed each other and how evenly
+    /// they were sized.
+    pub confidence: f64,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+}
+
+/// Edge at which the edge component of the confidence saturates.
+const FULL_CONFIDENCE_EDGE: Decimal = dec!(0.05);
+
 pub struct ExecutionAnalyzer;
 
 impl ExecutionAnalyzer {
-    /// Groups executions by user and detects potential arbitrage activity
-    pub fn analyze_executions(executions: &[UserExecution]) -> Vec<String> {
-        let mut arbitrageurs = Vec::new();
-        let mut user_activity: HashMap<String, Vec<&UserExecution>> = HashMap::new();
+    /// Scans each address's purchases for arbitrage positions, strongest signals first.
+    pub fn analyze_executions(executions: &[UserExecution], heuristic: &ArbitrageHeuristic) -> Vec<ArbitrageSignal> {
+        let groups = Self::market_groups(&heuristic.related_markets);
+        let group_of = |market_id: &str| groups.get(market_id).cloned().unwrap_or_else(|| market_id.to_string());
 
-        // Group by user
+        let mut user_activity: HashMap<&str, Vec<&UserExecution>> = HashMap::new();
         for exec in executions {
-            user_activity.entry(exec.user_address.clone()).or_insert_with(Vec::new).push(exec);
+            user_activity.entry(&exec.user_address).or_default().push(exec);
         }
 
-        // Analyze each user's patterns
-        for (user, txs) in user_activity {
-            if Self::is_arbitrage_pattern(txs) {
-                arbitrageurs.push(user);
+        let mut signals = Vec::new();
+        for (user, mut txs) in user_activity {
+            txs.sort_by_key(|tx| tx.timestamp);
+            let mut i = 0;
+            while i < txs.len() {
+                let group = group_of(&txs[i].market_id);
+                let in_window = txs[i..].iter().take_while(|tx| tx.timestamp - txs[i].timestamp <= heuristic.window_secs).count();
+                let legs: Vec<&UserExecution> = txs[i..i + in_window].iter().copied().filter(|tx| group_of(&tx.market_id) == group).collect();
+                match Self::score(user, &legs, heuristic) {
+                    // Legs of a detected position are not reused for a later one
+                    Some(signal) => {
+                        signals.push(signal);
+                        i += in_window;
+                    }
+                    None => i += 1,
+                }
             }
         }
+        signals.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        signals
+    }
 
-        arbitrageurs
+    /// Market id => id of its related group, the smallest id among the markets linked to it.
+    fn market_groups(related: &[(String, String)]) -> HashMap<String, String> {
+        let mut groups: HashMap<String, String> = HashMap::new();
+        for (a, b) in related {
+            let ga = groups.get(a).cloned().unwrap_or_else(|| a.clone());
+            let gb = groups.get(b).cloned().unwrap_or_else(|| b.clone());
+            let root = ga.clone().min(gb.clone());
+            for group in groups.values_mut().filter(|g| **g == ga || **g == gb) {
+                *group = root.clone();
+            }
+            groups.insert(a.clone(), root.clone());
+            groups.insert(b.clone(), root);
+        }
+        groups
     }
 
-    /// Heuristic to detect if a set of transactions looks like arbitrage
-    /// e.g., Buying YES and NO in the same market within a short window
-    fn is_arbitrage_pattern(txs: Vec<&UserExecution>) -> bool {
-        if txs.len() < 2 { return false; }
+    /// Scores purchases in one market group within one window, `None` unless they cover at
+    /// least two outcomes below the cost cap with enough confidence.
+    fn score(user: &str, txs: &[&UserExecution], heuristic: &ArbitrageHeuristic) -> Option<ArbitrageSignal> {
+        // (market, outcome) => (shares, cost)
+        let mut legs: BTreeMap<(&str, usize), (Decimal, Decimal)> = BTreeMap::new();
+        for tx in txs {
+            let leg = legs.entry((&tx.market_id, tx.outcome_index)).or_default();
+            leg.0 += tx.amount;
+            leg.1 += tx.amount * tx.price;
+        }
+        legs.retain(|_, (shares, _)| *shares > Decimal::ZERO);
+        if legs.len() < 2 {
+            return None;
+        }
 
-        let mut market_counts: HashMap<String, usize> = HashMap::new();
-        for tx in &txs {
-            *market_counts.entry(tx.market_id.clone()).or_default() += 1;
+        let net_cost: Decimal = legs.values().map(|(shares, cost)| cost / shares).sum();
+        if net_cost >= heuristic.max_net_cost {
+            return None;
+        }
+        let (first_timestamp, last_timestamp) = (txs.first()?.timestamp, txs.last()?.timestamp);
+        let edge = ((Decimal::ONE - net_cost) / FULL_CONFIDENCE_EDGE).min(Decimal::ONE).max(Decimal::ZERO);
+        let timing = 1.0 - (last_timestamp - first_timestamp) as f64 / heuristic.window_secs.max(1) as f64;
+        let min_shares = legs.values().map(|(shares, _)| *shares).min()?;
+        let max_shares = legs.values().map(|(shares, _)| *shares).max()?;
+        let balance = (min_shares / max_shares).to_f64().unwrap_or(0.0);
+        let confidence = 0.4 * edge.to_f64().unwrap_or(0.0) + 0.3 * timing + 0.3 * balance;
+        if confidence < heuristic.min_confidence {
+            return None;
         }
 
-        // If a user interacts with the same market multiple times (e.g. buying multiple outcomes)
-        for (_, count) in market_counts {
-            if count > 1 {
-                // Further check: distinct outcomes? Short time window?
-                // For this stub, simple count > 1 suggests multi-leg execution
+        let mut market_ids: Vec<String> = legs.keys().map(|(market_id, _)| market_id.to_string()).collect();
+        market_ids.dedup();
+        Some(ArbitrageSignal { user_address: user.to_string(), market_ids, legs: legs.len(), net_cost, confidence, first_timestamp, last_timestamp })
+    }
+}
+
+/// A position reduction and the PnL it realized against the average entry price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClosedTrade {
+    timestamp: u64,
+    pnl: Decimal,
+}
+
+/// One address's open positions and its closed trades within the tracker's window.
+#[derive(Debug, Clone, Default)]
+pub struct WalletStats {
+    /// Asset id => (shares, total cost).
+    positions: HashMap<String, (Decimal, Decimal)>,
+    closed: VecDeque<ClosedTrade>,
+    /// Fills within the window, entries and exits.
+    pub trades: usize,
+    pub volume: Decimal,
+    fills: VecDeque<(u64, Decimal)>,
+}
+
+impl WalletStats {
+    pub fn realized_pnl(&self) -> Decimal {
+        self.closed.iter().map(|t| t.pnl).sum()
+    }
+
+    pub fn closed_trades(&self) -> usize {
+        self.closed.len()
+    }
+
+    /// Share of closed trades that made money.
+    pub fn win_rate(&self) -> Decimal {
+        if self.closed.is_empty() {
+            return Decimal::ZERO;
+        }
+        Decimal::from(self.closed.iter().filter(|t| t.pnl > Decimal::ZERO).count()) / Decimal::from(self.closed.len())
+    }
+
+    fn record(&mut self, asset_id: &str, side: OrderSide, shares: Decimal, price: Decimal, timestamp: u64) {
+        let (held, cost) = self.positions.entry(asset_id.to_string()).or_default();
+        match side {
+            OrderSide::Buy => {
+                *held += shares;
+                *cost += shares * price;
+            }
+            // Shares sold beyond the tracked position were bought before tracking began
+            OrderSide::Sell if *held > Decimal::ZERO => {
+                let closed = shares.min(*held);
+                let entry = *cost / *held;
+                self.closed.push_back(ClosedTrade { timestamp, pnl: closed * (price - entry) });
+                *cost -= closed * entry;
+                *held -= closed;
+            }
+            OrderSide::Sell => {}
+        }
+        self.fills.push_back((timestamp, shares * price));
+        self.trades += 1;
+        self.volume += shares * price;
+    }
+
+    fn expire(&mut self, cutoff: u64) {
+        while self.closed.front().is_some_and(|t| t.timestamp < cutoff) {
+            self.closed.pop_front();
+        }
+        while let Some(&(timestamp, notional)) = self.fills.front() {
+            if timestamp >= cutoff {
+                break;
+            }
+            self.fills.pop_front();
+            self.trades -= 1;
+            self.volume -= notional;
+        }
+    }
+}
+
+/// What it takes for an address to count as consistently profitable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FollowCriteria {
+    pub min_closed_trades: usize,
+    pub min_win_rate: Decimal,
+    pub min_realized_pnl: Decimal,
+}
+
+impl Default for FollowCriteria {
+    fn default() -> Self {
+        Self { min_closed_trades: 10, min_win_rate: Decimal::new(6, 1), min_realized_pnl: Decimal::from(100) }
+    }
+}
+
+/// Rolling profitability per address, fed one on-chain fill at a time. Fills are attributed
+/// to the maker: the taker order of a match emits its own `OrderFilled` with its owner as
+/// maker, so counting makers sees every order exactly once.
+#[derive(Debug, Clone, Default)]
+pub struct WalletTracker {
+    wallets: HashMap<String, WalletStats>,
+    /// Seconds of history kept per address.
+    window: u64,
+}
+
+impl WalletTracker {
+    pub fn new(window_secs: u64) -> Self {
+        Self { wallets: HashMap::new(), window: window_secs }
+    }
+
+    pub fn record(&mut self, fill: &FillRecord) {
+        let Some(price) = fill.price() else { return };
+        let stats = self.wallets.entry(fill.maker.clone()).or_default();
+        stats.record(fill.asset_id(), fill.maker_side(), fill.shares(), price, fill.timestamp);
+        stats.expire(fill.timestamp.saturating_sub(self.window));
+    }
+
+    pub fn stats(&self, address: &str) -> Option<&WalletStats> {
+        self.wallets.get(address)
+    }
+
+    pub fn is_smart(&self, address: &str, criteria: &FollowCriteria) -> bool {
+        self.stats(address).is_some_and(|s| {
+            s.closed_trades() >= criteria.min_closed_trades && s.win_rate() >= criteria.min_win_rate && s.realized_pnl() >= criteria.min_realized_pnl
+        })
+    }
+
+    /// Addresses meeting `criteria`, most profitable first.
+    pub fn smart_wallets(&self, criteria: &FollowCriteria) -> Vec<(&str, &WalletStats)> {
+        let mut smart: Vec<(&str, &WalletStats)> = self.wallets.iter()
+            .filter(|(address, _)| self.is_smart(address, criteria))
+            .map(|(address, stats)| (address.as_str(), stats))
+            .collect();
+        smart.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.realized_pnl()));
+        smart
+    }
+}
+
+/// Payout fraction per outcome of a resolved condition, from its `ConditionResolution` event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketResolution {
+    /// Lowercase bytes32 hex, as Gamma reports `conditionId`.
+    pub condition_id: String,
+    /// USDC paid per share of each outcome, by outcome index; sums to 1.
+    pub payouts: Vec<Decimal>,
+}
+
+impl MarketResolution {
+    /// `None` for a resolution that pays nothing, which the CTF contract rejects anyway.
+    pub fn from_event(event: &ConditionResolutionFilter) -> Option<Self> {
+        let numerators: Vec<Decimal> = event.payout_numerators.iter().map(|n| from_units(*n, 0)).collect::<Option<_>>()?;
+        let total: Decimal = numerators.iter().sum();
+        if total.is_zero() {
+            return None;
+        }
+        Some(Self { condition_id: format!("{:?}", H256::from(event.condition_id)), payouts: numerators.iter().map(|n| n / total).collect() })
+    }
+}
+
+/// An address's open position in one outcome token, at average cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenPosition {
+    pub market_id: Option<String>,
+    pub outcome_index: Option<usize>,
+    pub shares: Decimal,
+    pub cost: Decimal,
+}
+
+/// One address's reconstructed book over its full fill history.
+#[derive(Debug, Clone, Default)]
+pub struct AddressPnl {
+    /// Asset id => position.
+    pub positions: HashMap<String, OpenPosition>,
+    /// From sells against the average entry and from positions settled at resolution.
+    pub realized: Decimal,
+    pub trades: usize,
+    pub volume: Decimal,
+}
+
+impl AddressPnl {
+    /// Open positions valued at `marks` (asset id => price); positions without a mark are
+    /// left out rather than assumed worthless.
+    pub fn unrealized(&self, marks: &HashMap<String, Decimal>) -> Decimal {
+        self.positions.iter()
+            .filter_map(|(asset_id, p)| marks.get(asset_id).map(|mark| p.shares * mark - p.cost))
+            .sum()
+    }
+
+    fn record(&mut self, fill: &FillRecord, price: Decimal) {
+        let shares = fill.shares();
+        let position = self.positions.entry(fill.asset_id().to_string()).or_insert_with(|| OpenPosition {
+            market_id: fill.market.as_ref().map(|m| m.market_id.clone()),
+            outcome_index: fill.market.as_ref().map(|m| m.outcome_index),
+            shares: Decimal::ZERO,
+            cost: Decimal::ZERO,
+        });
+        match fill.maker_side() {
+            OrderSide::Buy => {
+                position.shares += shares;
+                position.cost += shares * price;
+            }
+            // Shares sold beyond the reconstructed position were bought before the history starts
+            OrderSide::Sell if position.shares > Decimal::ZERO => {
+                let closed = shares.min(position.shares);
+                let entry = position.cost / position.shares;
+                self.realized += closed * (price - entry);
+                position.cost -= closed * entry;
+                position.shares -= closed;
+            }
+            OrderSide::Sell => {}
+        }
+        self.trades += 1;
+        self.volume += shares * price;
+        self.positions.retain(|_, p| p.shares > Decimal::ZERO);
+    }
+}
+
+/// Ranked performance of one address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PnlReport {
+    pub address: String,
+    pub realized: Decimal,
+    pub unrealized: Decimal,
+    pub trades: usize,
+    pub volume: Decimal,
+}
+
+impl PnlReport {
+    pub fn total(&self) -> Decimal {
+        self.realized + self.unrealized
+    }
+}
+
+/// Rebuilds every address's positions from its fills (maker-attributed, as in
+/// `WalletTracker`) and settles them when their market resolves.
+#[derive(Debug, Clone, Default)]
+pub struct PnlEngine {
+    addresses: HashMap<String, AddressPnl>,
+    /// Market id => payouts, for fills that arrive after their market resolved.
+    resolved: HashMap<String, Vec<Decimal>>,
+}
+
+impl PnlEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed fills in chain order.
+    pub fn record(&mut self, fill: &FillRecord) {
+        let Some(price) = fill.price() else { return };
+        let book = self.addresses.entry(fill.maker.clone()).or_default();
+        book.record(fill, price);
+        if let Some(payouts) = fill.market.as_ref().and_then(|m| self.resolved.get(&m.market_id)) {
+            Self::settle(book, &fill.market.as_ref().expect("checked above").market_id, payouts);
+        }
+    }
+
+    /// Settles every open position in `market_id` at its outcome's payout.
+    pub fn resolve(&mut self, market_id: &str, payouts: &[Decimal]) {
+        for book in self.addresses.values_mut() {
+            Self::settle(book, market_id, payouts);
+        }
+        self.resolved.insert(market_id.to_string(), payouts.to_vec());
+    }
+
+    /// `resolve` for an on-chain resolution, mapped to its market by `resolver`. Returns
+    /// whether the condition belongs to a known market.
+    pub fn apply_resolution(&mut self, resolution: &MarketResolution, resolver: &TokenResolver) -> bool {
+        let Some(market_id) = resolver.market_for_condition(&resolution.condition_id).map(str::to_string) else { return false };
+        self.resolve(&market_id, &resolution.payouts);
+        true
+    }
+
+    fn settle(book: &mut AddressPnl, market_id: &str, payouts: &[Decimal]) {
+        book.positions.retain(|_, p| {
+            if p.market_id.as_deref() != Some(market_id) {
                 return true;
             }
+            let payout = p.outcome_index.and_then(|i| payouts.get(i)).copied().unwrap_or_default();
+            book.realized += p.shares * payout - p.cost;
+            false
+        });
+    }
+
+    pub fn pnl(&self, address: &str) -> Option<&AddressPnl> {
+        self.addresses.get(address)
+    }
+
+    /// Every address by total PnL, best first, marking open positions at `marks`.
+    pub fn ranking(&self, marks: &HashMap<String, Decimal>) -> Vec<PnlReport> {
+        let mut reports: Vec<PnlReport> = self.addresses.iter()
+            .map(|(address, book)| PnlReport {
+                address: address.clone(),
+                realized: book.realized,
+                unrealized: book.unrealized(marks),
+                trades: book.trades,
+                volume: book.volume,
+            })
+            .collect();
+        reports.sort_by_key(|r| std::cmp::Reverse(r.total()));
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill_ingest::USDC_ASSET_ID;
+    use rust_decimal_macros::dec;
+
+    fn fill(maker: &str, side: OrderSide, shares: Decimal, usdc: Decimal, timestamp: u64) -> FillRecord {
+        let (maker_asset_id, taker_asset_id, maker_amount, taker_amount) = match side {
+            OrderSide::Buy => (USDC_ASSET_ID, "7", usdc, shares),
+            OrderSide::Sell => ("7", USDC_ASSET_ID, shares, usdc),
+        };
+        FillRecord {
+            block_number: 1,
+            timestamp,
+            tx_hash: format!("0x{:x}", timestamp),
+            log_index: 0,
+            order_hash: "0x01".to_string(),
+            maker: maker.to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: maker_asset_id.to_string(),
+            taker_asset_id: taker_asset_id.to_string(),
+            maker_amount,
+            taker_amount,
+            fee: Decimal::ZERO,
+            market: None,
         }
-        
-        false
+    }
+
+    #[test]
+    fn test_tracker_flags_profitable_wallets_within_window() {
+        let criteria = FollowCriteria { min_closed_trades: 2, min_win_rate: dec!(0.5), min_realized_pnl: dec!(5) };
+        let mut tracker = WalletTracker::new(1000);
+        // Buys 100 at 0.40 and 100 at 0.50, then sells 50 at 0.60 twice: +7.5 each against the 0.45 average
+        tracker.record(&fill("smart", OrderSide::Buy, dec!(100), dec!(40), 10));
+        tracker.record(&fill("smart", OrderSide::Buy, dec!(100), dec!(50), 20));
+        tracker.record(&fill("smart", OrderSide::Sell, dec!(50), dec!(30), 30));
+        assert!(!tracker.is_smart("smart", &criteria));
+        tracker.record(&fill("smart", OrderSide::Sell, dec!(50), dec!(30), 40));
+        let stats = tracker.stats("smart").unwrap();
+        assert_eq!((stats.realized_pnl(), stats.win_rate(), stats.trades), (dec!(15), Decimal::ONE, 4));
+        assert!(tracker.is_smart("smart", &criteria));
+
+        // A seller without a tracked position realizes nothing
+        tracker.record(&fill("other", OrderSide::Sell, dec!(10), dec!(9), 40));
+        assert_eq!(tracker.smart_wallets(&criteria).len(), 1);
+
+        // Closed trades age out of the window
+        tracker.record(&fill("smart", OrderSide::Buy, dec!(1), dec!(0.5), 1035));
+        assert_eq!(tracker.stats("smart").unwrap().closed_trades(), 1);
+        assert!(!tracker.is_smart("smart", &criteria));
+    }
+    fn buy(user: &str, market_id: &str, outcome_index: usize, amount: Decimal, price: Decimal, timestamp: u64) -> UserExecution {
+        UserExecution { user_address: user.to_string(), market_id: market_id.to_string(), outcome_index, amount, price, timestamp }
+    }
+
+    #[test]
+    fn test_detects_windowed_outcome_pairs_below_payout() {
+        let heuristic = ArbitrageHeuristic { related_markets: vec![("a".to_string(), "b".to_string())], ..Default::default() };
+        let executions = [
+            // YES and NO of one market for 0.95 within 10s
+            buy("arb", "m", 0, dec!(100), dec!(0.45), 0),
+            buy("arb", "m", 1, dec!(100), dec!(0.50), 10),
+            // Both sides, but for more than the payout
+            buy("hedger", "m", 0, dec!(100), dec!(0.55), 0),
+            buy("hedger", "m", 1, dec!(100), dec!(0.50), 5),
+            // Both sides, but an hour apart
+            buy("swing", "m", 0, dec!(100), dec!(0.30), 0),
+            buy("swing", "m", 1, dec!(100), dec!(0.40), 3600),
+            // Complementary outcomes of related markets
+            buy("cross", "a", 0, dec!(50), dec!(0.40), 100),
+            buy("cross", "b", 1, dec!(50), dec!(0.50), 130),
+        ];
+
+        let signals = ExecutionAnalyzer::analyze_executions(&executions, &heuristic);
+        let users: Vec<&str> = signals.iter().map(|s| s.user_address.as_str()).collect();
+        // Both lock in the full-confidence edge; the tighter pair ranks first
+        assert_eq!(users, ["arb", "cross"]);
+        assert_eq!((signals[0].legs, signals[0].net_cost, signals[0].market_ids.clone()), (2, dec!(0.95), vec!["m".to_string()]));
+        assert_eq!((signals[1].net_cost, signals[1].market_ids.clone()), (dec!(0.90), vec!["a".to_string(), "b".to_string()]));
+        assert!(signals[0].confidence <= 1.0);
+    }
+
+    #[test]
+    fn test_pnl_engine_realizes_sells_and_settles_resolutions() {
+        let tagged = |maker: &str, side: OrderSide, shares: Decimal, usdc: Decimal, timestamp: u64| {
+            let mut fill = fill(maker, side, shares, usdc, timestamp);
+            fill.market = Some(crate::token_resolver::TokenInfo { market_id: "m".to_string(), question: "Q?".to_string(), outcome: "Yes".to_string(), outcome_index: 0 });
+            fill
+        };
+        let mut engine = PnlEngine::new();
+        // Winner buys 100 YES at 0.40, sells 40 at 0.50 and holds 60 into a NO resolution
+        engine.record(&tagged("winner", OrderSide::Buy, dec!(100), dec!(40), 1));
+        engine.record(&tagged("winner", OrderSide::Sell, dec!(40), dec!(20), 2));
+        // Holder buys 50 YES at 0.60 and is still open
+        engine.record(&tagged("holder", OrderSide::Buy, dec!(50), dec!(30), 3));
+        let marks = HashMap::from([("7".to_string(), dec!(0.70))]);
+        assert_eq!(engine.pnl("winner").unwrap().realized, dec!(4));
+        assert_eq!(engine.ranking(&marks).iter().map(|r| (r.address.as_str(), r.total())).collect::<Vec<_>>(), [("winner", dec!(22)), ("holder", dec!(5))]);
+
+        engine.resolve("m", &[Decimal::ZERO, Decimal::ONE]);
+        let ranking = engine.ranking(&marks);
+        // 4 - 24 for winner's 60 worthless shares, holder loses their 30 cost
+        assert_eq!(ranking.iter().map(|r| (r.address.as_str(), r.realized, r.unrealized)).collect::<Vec<_>>(), [("winner", dec!(-20), Decimal::ZERO), ("holder", dec!(-30), Decimal::ZERO)]);
+
+        // A buy after resolution settles immediately
+        engine.record(&tagged("late", OrderSide::Buy, dec!(10), dec!(1), 4));
+        assert_eq!((engine.pnl("late").unwrap().realized, engine.pnl("late").unwrap().positions.len()), (dec!(-1), 0));
     }
 }
@@ -0,0 +1,267 @@
+use crate::blockchain::ExecutionError;
+use crate::clob_client::OrderRequest;
+use crate::kill_switch::KillSwitch;
+use crate::metrics::TRADING_PAUSED;
+use crate::profit_model::ExecutionEstimate;
+use crate::shared_types::{BasketOpportunity, CombinatorialOpportunity, LadderOpportunity, Market, MultiLegOpportunity, Opportunity, RebalancingOpportunity};
+use crate::wallet_pool::ExecutorPool;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::types::TransactionReceipt;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Retries for transient RPC failures. Delays double from `base_delay` up to `max_delay`
+/// and are fully jittered so parallel executions do not retry in lockstep.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay: Duration::from_millis(200), max_delay: Duration::from_secs(5) }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `attempt + 1`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let cap = self.base_delay.saturating_mul(1 << attempt.min(16)).min(self.max_delay);
+        cap.mul_f64(thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Opens after `max_failures` failed executions within `window` and stays open for `cooldown`.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    max_failures: usize,
+    window: Duration,
+    cooldown: Duration,
+    failures: VecDeque<Instant>,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(max_failures: usize, window: Duration, cooldown: Duration) -> Self {
+        Self { max_failures, window, cooldown, failures: VecDeque::new(), open_until: None }
+    }
+
+    /// Time left before trading resumes, or `None` while closed. Resets once the cooldown has passed.
+    pub fn remaining(&mut self, now: Instant) -> Option<Duration> {
+        match self.open_until {
+            Some(until) if until > now => Some(until - now),
+            Some(_) => {
+                self.open_until = None;
+                self.failures.clear();
+                TRADING_PAUSED.set(0);
+                info!("circuit breaker reset, trading resumed");
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures.clear();
+    }
+
+    /// Returns true if this failure tripped the breaker.
+    pub fn record_failure(&mut self, now: Instant) -> bool {
+        self.failures.push_back(now);
+        while self.failures.front().is_some_and(|t| now.duration_since(*t) > self.window) {
+            self.failures.pop_front();
+        }
+        if self.open_until.is_none() && self.failures.len() >= self.max_failures {
+            self.open_until = Some(now + self.cooldown);
+            TRADING_PAUSED.set(1);
+            return true;
+        }
+        false
+    }
+}
+
+/// Retry, circuit-breaker and kill-switch policy applied to every execution.
+pub struct ExecutionGuard {
+    retry: RetryPolicy,
+    breaker: Mutex<CircuitBreaker>,
+    kill_switch: Option<Arc<KillSwitch>>,
+}
+
+impl ExecutionGuard {
+    pub fn new(retry: RetryPolicy, breaker: CircuitBreaker) -> Self {
+        Self { retry, breaker: Mutex::new(breaker), kill_switch: None }
+    }
+
+    /// Refuses executions while `kill_switch` is halted and feeds it every counted outcome.
+    pub fn with_kill_switch(mut self, kill_switch: Arc<KillSwitch>) -> Self {
+        self.kill_switch = Some(kill_switch);
+        self
+    }
+
+    fn breaker(&self) -> std::sync::MutexGuard<'_, CircuitBreaker> {
+        self.breaker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.kill_switch.as_ref().is_some_and(|k| k.halted().is_some()) || self.breaker().remaining(Instant::now()).is_some()
+    }
+
+    /// Runs `op`, retrying retryable errors. Fails fast with `ExecutionError::Halted` while the
+    /// kill switch is tripped and `ExecutionError::Paused` while the breaker is open. Gas, sizing
+    /// and balance rejections are decisions, not failures, and do not count.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, ExecutionError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ExecutionError>>,
+    {
+        if let Some(halt) = self.kill_switch.as_ref().and_then(|k| k.halted()) {
+            return Err(ExecutionError::Halted(halt.reason));
+        }
+        if let Some(remaining) = self.breaker().remaining(Instant::now()) {
+            return Err(ExecutionError::Paused(remaining));
+        }
+
+        let mut attempt = 0;
+        let result = loop {
+            match op().await {
+                Err(e) if e.is_retryable() && attempt + 1 < self.retry.max_attempts => {
+                    let delay = self.retry.delay(attempt);
+                    warn!(error = %e, attempt = attempt + 1, retry_in_ms = delay.as_millis() as u64, "transient execution failure, retrying");
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                other => break other,
+            }
+        };
+
+        let counted = match &result {
+            Ok(_) => {
+                self.breaker().record_success();
+                Some(true)
+            }
+            Err(ExecutionError::GasTooExpensive { .. } | ExecutionError::InvalidAmount(_) | ExecutionError::InsufficientBalance { .. }) => None,
+            Err(e) => {
+                if self.breaker().record_failure(Instant::now()) {
+                    error!(error = %e, "circuit breaker tripped, pausing trading");
+                }
+                Some(false)
+            }
+        };
+        if let (Some(kill_switch), Some(ok)) = (&self.kill_switch, counted) {
+            kill_switch.record_outcome(Instant::now(), ok);
+        }
+        result
+    }
+}
+
+/// The wallet pool behind an `ExecutionGuard`. Scanning continues while paused; only the
+/// execute calls are refused.
+pub struct GuardedExecutor {
+    executor: Arc<ExecutorPool>,
+    guard: ExecutionGuard,
+}
+
+impl GuardedExecutor {
+    pub fn new(executor: Arc<ExecutorPool>, guard: ExecutionGuard) -> Self {
+        Self { executor, guard }
+    }
+
+    /// The unguarded pool, for reads such as balance syncs.
+    pub fn executor(&self) -> &ExecutorPool {
+        &self.executor
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.guard.is_paused()
+    }
+
+    /// Trades `amount` USDC of a scheduled opportunity, whatever its kind.
+    pub async fn execute(&self, opportunity: &Opportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        match opportunity {
+            Opportunity::Rebalancing { market, op, .. } => self.execute_rebalancing(market, op, amount).await,
+            Opportunity::NegRiskBasket { op, .. } => self.execute_neg_risk_basket(op, amount).await,
+            Opportunity::ThresholdLadder { op, .. } => self.execute_threshold_ladder(op, amount).await,
+            Opportunity::Combinatorial { op, estimate, .. } => self.execute_combinatorial(op, estimate, amount).await,
+            Opportunity::MultiLeg { op, .. } => self.execute_multi_leg(op, amount).await,
+        }
+    }
+
+    pub async fn execute_rebalancing(&self, market: &Market, op: &RebalancingOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_rebalancing(market, op, amount)).await
+    }
+
+    pub async fn execute_combinatorial(&self, op: &CombinatorialOpportunity, estimate: &ExecutionEstimate, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_combinatorial(op, estimate, amount)).await
+    }
+
+    pub async fn execute_multi_leg(&self, op: &MultiLegOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_multi_leg(op, amount)).await
+    }
+
+    pub async fn execute_threshold_ladder(&self, op: &LadderOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_threshold_ladder(op, amount)).await
+    }
+
+    pub async fn execute_neg_risk_basket(&self, op: &BasketOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_neg_risk_basket(op, amount)).await
+    }
+
+    pub async fn execute_copy_trade(&self, market_id: &str, order: &OrderRequest) -> Result<TransactionReceipt, ExecutionError> {
+        self.guard.run(|| self.executor.execute_copy_trade(market_id, order)).await
+    }
+
+    /// Unwinding bypasses the breaker: shutdown must be able to flatten even after failures.
+    pub async fn flatten_position(&self, market: &Market, held: &HashMap<String, Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        self.executor.flatten_position(market, held).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(1), max_delay: Duration::from_millis(2) }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_only() {
+        let guard = ExecutionGuard::new(fast_retry(), CircuitBreaker::new(10, Duration::from_secs(60), Duration::from_secs(60)));
+        let calls = AtomicU32::new(0);
+        let result = guard.run(|| async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 { Err(ExecutionError::Dropped) } else { Ok(7) }
+        }).await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = guard.run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(ExecutionError::Contract("reverted".to_string()))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_trips_in_window_and_resets_after_cooldown() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(10), Duration::from_secs(30));
+        let start = Instant::now();
+        assert!(!breaker.record_failure(start));
+        // The first failure ages out of the window before the third arrives
+        assert!(!breaker.record_failure(start + Duration::from_secs(8)));
+        assert!(!breaker.record_failure(start + Duration::from_secs(12)));
+        assert!(breaker.record_failure(start + Duration::from_secs(13)));
+
+        assert_eq!(breaker.remaining(start + Duration::from_secs(23)), Some(Duration::from_secs(20)));
+        assert_eq!(breaker.remaining(start + Duration::from_secs(43)), None);
+        assert!(!breaker.record_failure(start + Duration::from_secs(44)));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::blockchain::ExecutionError;
+use crate::clob_client::{OrderRequest, OrderSide, TimeInForce};
+use crate::order_book::OrderBook;
+use crate::profit_model::ExecutionEstimate;
+use crate::shared_types::CombinatorialOpportunity;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+use tokio::time::Duration;
+
+// Outcome tokens trade between one cent and 99 cents
+const MIN_PRICE: Decimal = dec!(0.01);
+const MAX_PRICE: Decimal = dec!(0.99);
+
+/// How the two legs of a pair trade reach the CLOB. CLOB orders are signed off-chain and
+/// matched by the operator, so they cannot share a multicall; batching is the closest bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LegSubmission {
+    /// Buy leg first; the sell leg is sized to what actually filled.
+    #[default]
+    Sequential,
+    /// Both legs in one `POST /orders` request.
+    Batch,
+}
+
+impl FromStr for LegSubmission {
+    type Err = ExecutionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sequential" => Ok(LegSubmission::Sequential),
+            "batch" => Ok(LegSubmission::Batch),
+            other => Err(ExecutionError::Config(format!("unknown leg submission mode: {}", other))),
+        }
+    }
+}
+
+/// What happens to the unhedged shares when one leg fills and the other fails or times out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackPolicy {
+    /// Keep the position and report it.
+    Hold,
+    /// Immediately cross the book to flatten the excess, conceding up to `max_slippage` per
+    /// share from the leg's own limit price.
+    Unwind { max_slippage: Decimal },
+    /// Top up a second leg that filled short of the first, conceding up to `max_slippage` per
+    /// share, then unwind whatever is still unhedged.
+    Hedge { max_slippage: Decimal },
+}
+
+impl Default for RollbackPolicy {
+    fn default() -> Self {
+        RollbackPolicy::Unwind { max_slippage: dec!(0.05) }
+    }
+}
+
+/// How a strategy's legs meet the book, so large orders need not sweep thin books.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionStyle {
+    /// Cross at the limit price right away.
+    #[default]
+    Taker,
+    /// Rest at the mid for `timeout`, then cross at the limit price for what is left.
+    MidThenCross { timeout: Duration },
+    /// Orders worth at least `threshold` USDC are crossed in slices of about `slice` USDC,
+    /// stopping at the first slice the book cannot fill.
+    Iceberg { threshold: Decimal, slice: Decimal },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannerConfig {
+    pub submission: LegSubmission,
+    /// How long each leg may rest before the remainder is cancelled.
+    pub leg_timeout: Duration,
+    pub rollback: RollbackPolicy,
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self { submission: LegSubmission::default(), leg_timeout: Duration::from_secs(10), rollback: RollbackPolicy::default() }
+    }
+}
+
+/// A buy of the cheap implied condition hedged by a buy of the dear implying condition's
+/// complement. Whichever way the implying market resolves, one of the two pays out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoLegPlan {
+    pub first: OrderRequest,
+    pub second: OrderRequest,
+}
+
+/// Turns `amount` USDC of a combinatorial opportunity's implied leg into two equally sized
+/// limit buys working the book per `tif`: the implied condition at the deepest ask the profit
+/// model walked, and the implying condition's complement at one minus the lowest implying bid
+/// it walked. `None` when the size rounds to zero or the implying market has no complement.
+pub fn plan_combinatorial(op: &CombinatorialOpportunity, estimate: &ExecutionEstimate, amount: Decimal, tif: TimeInForce) -> Option<TwoLegPlan> {
+    let complement = op.implying_complement_asset_id.clone()?;
+    if estimate.avg_buy_price <= Decimal::ZERO {
+        return None;
+    }
+    let shares = (amount / estimate.avg_buy_price)
+        .min(estimate.size)
+        .round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    if shares <= Decimal::ZERO {
+        return None;
+    }
+    Some(TwoLegPlan {
+        first: OrderRequest { asset_id: op.implied_asset_id.clone(), price: estimate.max_buy_price, size: shares, side: OrderSide::Buy, tif },
+        second: OrderRequest { asset_id: complement, price: (Decimal::ONE - estimate.min_sell_price).clamp(MIN_PRICE, MAX_PRICE), size: shares, side: OrderSide::Buy, tif },
+    })
+}
+
+/// The order that flattens whichever leg filled more than the other, or `None` when the legs
+/// are balanced. The excess is crossed at the leg's price moved `max_slippage` against us and
+/// rests until it fills.
+pub fn unwind_order(plan: &TwoLegPlan, filled_first: Decimal, filled_second: Decimal, max_slippage: Decimal) -> Option<OrderRequest> {
+    let (leg, excess) = if filled_first > filled_second {
+        (&plan.first, filled_first - filled_second)
+    } else if filled_second > filled_first {
+        (&plan.second, filled_second - filled_first)
+    } else {
+        return None;
+    };
+    let (side, price) = match leg.side {
+        OrderSide::Buy => (OrderSide::Sell, (leg.price - max_slippage).max(MIN_PRICE)),
+        OrderSide::Sell => (OrderSide::Buy, (leg.price + max_slippage).min(MAX_PRICE)),
+    };
+    Some(OrderRequest { asset_id: leg.asset_id.clone(), price, size: excess, side, tif: TimeInForce::Gtc })
+}
+
+/// The order that brings a lagging second leg up to the first leg's fill, or `None` when it
+/// already matches or leads. One of the pair pays out share for share, so the hedged position
+/// holds one complement share per implied share.
+pub fn hedge_order(plan: &TwoLegPlan, filled_first: Decimal, filled_second: Decimal, max_slippage: Decimal) -> Option<OrderRequest> {
+    if filled_second >= filled_first {
+        return None;
+    }
+    let leg = &plan.second;
+    let price = match leg.side {
+        OrderSide::Buy => (leg.price + max_slippage).min(MAX_PRICE),
+        OrderSide::Sell => (leg.price - max_slippage).max(MIN_PRICE),
+    };
+    Some(OrderRequest { asset_id: leg.asset_id.clone(), price, size: filled_first - filled_second, side: leg.side, tif: leg.tif })
+}
+
+/// The sale that flattens `shares` of `asset_id`, priced `max_slippage` below the best bid, or
+/// below `last_price` without a bid. `None` when the holding rounds to nothing at the 0.01 size
+/// step.
+pub fn flatten_order(asset_id: &str, shares: Decimal, book: Option<&OrderBook>, last_price: Decimal, max_slippage: Decimal) -> Option<OrderRequest> {
+    let size = shares.round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    if size <= Decimal::ZERO {
+        return None;
+    }
+    let bid = book.and_then(OrderBook::best_bid).map_or(last_price, |level| level.price);
+    let price = (bid - max_slippage).max(MIN_PRICE);
+    Some(OrderRequest { asset_id: asset_id.to_string(), price, size, side: OrderSide::Sell, tif: TimeInForce::Gtc })
+}
+
+/// `order` repriced to rest at its book's mid, never past its own limit. Without a two-sided
+/// book the order is left at its limit.
+pub fn at_mid(order: &OrderRequest, book: Option<&OrderBook>) -> OrderRequest {
+    let price = match (book.and_then(OrderBook::mid_price), order.side) {
+        (Some(mid), OrderSide::Buy) => mid.min(order.price),
+        (Some(mid), OrderSide::Sell) => mid.max(order.price),
+        (None, _) => order.price,
+    };
+    OrderRequest { price, ..order.clone() }
+}
+
+/// Splits orders traded together into rounds of about `slice` USDC, each round carrying the
+/// same share of every order. Orders worth less than `threshold` go out in one round.
+pub fn iceberg_slices(orders: &[OrderRequest], threshold: Decimal, slice: Decimal) -> Vec<Vec<OrderRequest>> {
+    let notional: Decimal = orders.iter().map(|o| o.price * o.size).sum();
+    if notional < threshold || slice <= Decimal::ZERO {
+        return vec![orders.to_vec()];
+    }
+    let rounds = (notional / slice).ceil();
+    let per_round: Vec<Decimal> = orders.iter().map(|o| (o.size / rounds).round_dp_with_strategy(2, RoundingStrategy::ToZero)).collect();
+    if per_round.iter().any(|size| size.is_zero()) {
+        return vec![orders.to_vec()];
+    }
+    let rounds = rounds.to_usize().unwrap_or(1);
+    (0..rounds).map(|round| {
+        orders.iter().zip(&per_round).map(|(order, &size)| {
+            // The last round picks up what rounding left over
+            let size = if round + 1 == rounds { order.size - size * Decimal::from(rounds - 1) } else { size };
+            OrderRequest { size, ..order.clone() }
+        }).collect()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::PriceLevel;
+
+    fn plan() -> TwoLegPlan {
+        let op = CombinatorialOpportunity {
+            market_id_1: "a".to_string(),
+            market_id_2: "b".to_string(),
+            condition_name_1: "a_yes".to_string(),
+            condition_name_2: "b_yes".to_string(),
+            implying_asset_id: "dear".to_string(),
+            implied_asset_id: "cheap".to_string(),
+            implying_complement_asset_id: Some("dear-no".to_string()),
+            profit: dec!(0.1),
+            implying_fees: None,
+            implied_fees: None,
+            confidence: 0.9,
+            explanation: String::new(),
+        };
+        let estimate = ExecutionEstimate {
+            size: dec!(200),
+            avg_buy_price: dec!(0.42),
+            avg_sell_price: dec!(0.53),
+            max_buy_price: dec!(0.45),
+            min_sell_price: dec!(0.50),
+            slippage: Decimal::ZERO,
+            gross_profit: dec!(22),
+            fees: Decimal::ZERO,
+            gas_cost: Decimal::ZERO,
+            net_profit: dec!(22),
+        };
+        plan_combinatorial(&op, &estimate, dec!(50), TimeInForce::Ioc).unwrap()
+    }
+
+    #[test]
+    fn test_plan_sizes_both_legs_from_buy_notional() {
+        let plan = plan();
+        // 50 / 0.42 = 119.047.. shares, rounded down to the 0.01 size step
+        assert_eq!(plan.first, OrderRequest { asset_id: "cheap".to_string(), price: dec!(0.45), size: dec!(119.04), side: OrderSide::Buy, tif: TimeInForce::Ioc });
+        // The hedge buys the implying market's NO at one minus its YES bid
+        assert_eq!(plan.second, OrderRequest { asset_id: "dear-no".to_string(), price: dec!(0.50), size: dec!(119.04), side: OrderSide::Buy, tif: TimeInForce::Ioc });
+    }
+
+    #[test]
+    fn test_unwind_flattens_the_excess_leg() {
+        let plan = plan();
+        assert_eq!(unwind_order(&plan, dec!(100), dec!(100), dec!(0.05)), None);
+
+        let sell = unwind_order(&plan, dec!(100), dec!(40), dec!(0.05)).unwrap();
+        assert_eq!((sell.asset_id.as_str(), sell.side, sell.price, sell.size), ("cheap", OrderSide::Sell, dec!(0.40), dec!(60)));
+        // Unlike the legs it flattens, the unwind rests until it fills
+        assert_eq!(sell.tif, TimeInForce::Gtc);
+
+        // Batched legs can fill the other way round; the excess hedge is sold back
+        let excess = unwind_order(&plan, dec!(0), dec!(25), dec!(0.60)).unwrap();
+        assert_eq!((excess.asset_id.as_str(), excess.side, excess.price, excess.size), ("dear-no", OrderSide::Sell, dec!(0.01), dec!(25)));
+    }
+
+    #[test]
+    fn test_mid_and_iceberg_styles_shape_orders() {
+        let buy = OrderRequest { asset_id: "a".to_string(), price: dec!(0.45), size: dec!(1000), side: OrderSide::Buy, tif: TimeInForce::Gtc };
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&[PriceLevel { price: dec!(0.40), size: dec!(10) }], &[PriceLevel { price: dec!(0.44), size: dec!(10) }]);
+        assert_eq!(at_mid(&buy, Some(&book)).price, dec!(0.42));
+        assert_eq!(at_mid(&buy, None).price, dec!(0.45));
+
+        // 450 USDC in slices of 100 is five rounds of 200 shares
+        let slices = iceberg_slices(std::slice::from_ref(&buy), dec!(250), dec!(100));
+        assert_eq!(slices.iter().map(|round| round[0].size).collect::<Vec<_>>(), vec![dec!(200); 5]);
+        let uneven = OrderRequest { size: dec!(1000.01), ..buy.clone() };
+        let slices = iceberg_slices(&[uneven], dec!(250), dec!(100));
+        assert_eq!(slices.iter().map(|round| round[0].size).sum::<Decimal>(), dec!(1000.01));
+        assert_eq!(slices.last().unwrap()[0].size, dec!(200.01));
+        // Below the threshold the order goes out whole
+        assert_eq!(iceberg_slices(std::slice::from_ref(&buy), dec!(500), dec!(100)), vec![vec![buy]]);
+    }
+
+    #[test]
+    fn test_flatten_sells_below_the_bid() {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&[PriceLevel { price: dec!(0.40), size: dec!(10) }], &[PriceLevel { price: dec!(0.44), size: dec!(10) }]);
+        let sell = flatten_order("a", dec!(12.345), Some(&book), dec!(0.5), dec!(0.05)).unwrap();
+        assert_eq!((sell.side, sell.price, sell.size), (OrderSide::Sell, dec!(0.35), dec!(12.34)));
+        // Without a book the last price stands in for the bid, never below one cent
+        assert_eq!(flatten_order("a", dec!(3), None, dec!(0.03), dec!(0.05)).unwrap().price, dec!(0.01));
+        assert_eq!(flatten_order("a", dec!(0.004), None, dec!(0.5), dec!(0.05)), None);
+    }
+
+    #[test]
+    fn test_hedge_tops_up_only_a_lagging_second_leg() {
+        let plan = plan();
+        let top_up = hedge_order(&plan, dec!(100), dec!(70), dec!(0.05)).unwrap();
+        assert_eq!((top_up.asset_id.as_str(), top_up.side, top_up.price, top_up.size, top_up.tif), ("dear-no", OrderSide::Buy, dec!(0.55), dec!(30), TimeInForce::Ioc));
+        // A second leg ahead of the first is trimmed by the unwind instead
+        assert_eq!(hedge_order(&plan, dec!(60), dec!(100), dec!(0.05)), None);
+        assert_eq!(hedge_order(&plan, dec!(100), dec!(100), dec!(0.05)), None);
+    }
+}
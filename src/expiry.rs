@@ -0,0 +1,202 @@
+use crate::execution_guard::GuardedExecutor;
+use crate::market_state::{forward_subscriptions, MarketState, RefreshDiff, SharedMarketState, SubscriptionSender};
+use crate::notifier::{Alert, NotifierHandle};
+use crate::positions::{PositionTracker, SharedPositions};
+use crate::risk::Exposure;
+use crate::shared_types::Market;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+/// How markets are handled as their end date nears.
+#[derive(Debug, Clone)]
+pub struct ExpiryPolicy {
+    /// No new positions are opened in a market this close to its end date.
+    pub entry_cutoff: Duration,
+    /// When set, holdings that are only hedged until resolution are unwound this long before
+    /// the end date.
+    pub flatten_before: Option<Duration>,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self { entry_cutoff: Duration::hours(1), flatten_before: None }
+    }
+}
+
+impl ExpiryPolicy {
+    pub fn blocks_entry(&self, market: &Market, now: DateTime<Utc>) -> bool {
+        now + self.entry_cutoff >= market.end_date
+    }
+
+    pub fn should_flatten(&self, market: &Market, now: DateTime<Utc>) -> bool {
+        self.flatten_before.is_some_and(|before| now + before >= market.end_date)
+    }
+}
+
+/// Notional of `market`'s holdings beyond a complete set. Equal shares of every outcome pay out
+/// the same whatever the result, so only the excess, hedged in some other market if at all,
+/// is exposed to how this one resolves.
+pub fn directional_exposure(market: &Market, positions: &PositionTracker, state: &MarketState) -> Decimal {
+    let shares: Vec<Decimal> = market.conditions.iter().map(|c| positions.shares(&c.asset_id)).collect();
+    let complete_sets = shares.iter().copied().min().unwrap_or_default();
+    market.conditions.iter().zip(shares)
+        .filter_map(|(c, held)| {
+            let price = state.markets.resolve(&c.asset_id).and_then(|id| state.price(id))?;
+            Some((held - complete_sets) * price)
+        })
+        .sum()
+}
+
+struct Flattener {
+    executor: Arc<GuardedExecutor>,
+    positions: SharedPositions,
+    exposure: Arc<RwLock<Exposure>>,
+    alerts: NotifierHandle,
+}
+
+/// Drops markets past their end date from the live set and unsubscribes their assets. With
+/// flattening attached, it also unwinds directional holdings in markets inside the policy's
+/// flatten window, once per market that flattened cleanly.
+pub struct ExpiryWatcher {
+    policy: ExpiryPolicy,
+    state: SharedMarketState,
+    subscribers: Vec<SubscriptionSender>,
+    flattener: Option<Flattener>,
+    flattened: HashSet<String>,
+}
+
+impl ExpiryWatcher {
+    pub fn new(policy: ExpiryPolicy, state: SharedMarketState, subscribers: Vec<SubscriptionSender>) -> Self {
+        Self { policy, state, subscribers, flattener: None, flattened: HashSet::new() }
+    }
+
+    /// Flattens through `executor` when the policy sets a flatten window, re-reading the
+    /// touched positions afterwards.
+    pub fn with_flattening(mut self, executor: Arc<GuardedExecutor>, positions: SharedPositions, exposure: Arc<RwLock<Exposure>>, alerts: NotifierHandle) -> Self {
+        if self.policy.flatten_before.is_some() {
+            self.flattener = Some(Flattener { executor, positions, exposure, alerts });
+        }
+        self
+    }
+
+    /// Flattens what is due at `now`, then drops and unsubscribes expired markets.
+    pub async fn tick(&mut self, now: DateTime<Utc>) -> RefreshDiff {
+        self.flatten_due(now).await;
+        let diff = self.state.write().await.drop_expired(now);
+        if !diff.removed_markets.is_empty() {
+            info!(expired = diff.removed_markets.len(), assets = diff.removed_asset_ids.len(), "expired markets removed");
+            forward_subscriptions(&diff, &self.subscribers);
+        }
+        diff
+    }
+
+    async fn flatten_due(&mut self, now: DateTime<Utc>) {
+        let Some(f) = &self.flattener else { return };
+        let due: Vec<(Market, Decimal, HashMap<String, Decimal>)> = {
+            let state = self.state.read().await;
+            let tracker = f.positions.read().await;
+            state.markets.iter()
+                .map(|(_, m)| m)
+                .filter(|m| self.policy.should_flatten(m, now) && !self.flattened.contains(&m.id))
+                .map(|m| (m.clone(), directional_exposure(m, &tracker, &state), tracker.held_in(m)))
+                .filter(|(_, amount, _)| *amount > Decimal::ZERO)
+                .collect()
+        };
+        if due.is_empty() {
+            return;
+        }
+        for (market, amount, held) in &due {
+            info!(market_id = %market.id, %amount, end_date = %market.end_date, "flattening ahead of expiry");
+            match f.executor.flatten_position(market, held).await {
+                Ok(_) => {
+                    self.flattened.insert(market.id.clone());
+                }
+                // Retried on the next tick while the market is still live
+                Err(e) => {
+                    error!(market_id = %market.id, error = %e, "failed to flatten position");
+                    f.alerts.notify(Alert::Error { context: format!("pre-expiry flatten {}", market.id), message: e.to_string() });
+                }
+            }
+        }
+
+        let touched: Vec<String> = due.iter().flat_map(|(m, _, _)| m.conditions.iter().map(|c| c.asset_id.clone())).collect();
+        let state = self.state.read().await;
+        let mut tracker = f.positions.write().await;
+        match tracker.sync(f.executor.executor(), &state, &touched).await {
+            Ok(()) => *f.exposure.write().await = tracker.exposure(&state),
+            Err(e) => warn!(error = %e, "position sync after flattening failed"),
+        }
+    }
+
+    pub fn spawn(mut self, interval: std::time::Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                self.tick(Utc::now()).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage_engine::PatternRegistry;
+    use crate::clob_client::{OrderSide, Subscription};
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use tokio::sync::mpsc;
+
+    fn market(id: &str, end_date: DateTime<Utc>) -> Market {
+        let condition = |name: &str, price: Decimal| Condition { name: name.to_string(), price, outcome: None, asset_id: format!("{}-{}", id, name), kind: OutcomeKind::Other, last_updated: None };
+        Market {
+            id: id.to_string(),
+            title: id.to_string(),
+            end_date,
+            conditions: vec![condition("Yes", dec!(0.4)), condition("No", dec!(0.6))],
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_policy_windows_and_directional_exposure() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let closing = market("m1", now + Duration::minutes(30));
+        let policy = ExpiryPolicy { entry_cutoff: Duration::hours(1), flatten_before: Some(Duration::minutes(15)) };
+        assert!(policy.blocks_entry(&closing, now) && !policy.should_flatten(&closing, now));
+        assert!(policy.should_flatten(&closing, now + Duration::minutes(20)));
+        assert!(!ExpiryPolicy::default().blocks_entry(&market("m2", now + Duration::hours(2)), now));
+
+        let state = MarketState::build(vec![closing], 0.8, Arc::new(PatternRegistry::default()));
+        let mut positions = PositionTracker::default();
+        positions.apply_fill("m1-Yes", OrderSide::Buy, dec!(10), dec!(0.4));
+        positions.apply_fill("m1-No", OrderSide::Buy, dec!(10), dec!(0.6));
+        // A complete set resolves to the same payout either way
+        assert_eq!(directional_exposure(&state.markets.as_slice()[0], &positions, &state), Decimal::ZERO);
+        positions.apply_fill("m1-Yes", OrderSide::Buy, dec!(5), dec!(0.4));
+        assert_eq!(directional_exposure(&state.markets.as_slice()[0], &positions, &state), dec!(2.0));
+    }
+
+    #[tokio::test]
+    async fn test_tick_drops_and_unsubscribes_expired_markets() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let markets = vec![market("ended", now - Duration::minutes(1)), market("open", now + Duration::days(1))];
+        let state = Arc::new(RwLock::new(MarketState::build(markets, 0.8, Arc::new(PatternRegistry::default()))));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher = ExpiryWatcher::new(ExpiryPolicy::default(), state.clone(), vec![tx]);
+
+        let diff = watcher.tick(now).await;
+        assert_eq!(diff.removed_markets, vec!["ended"]);
+        assert_eq!(rx.try_recv().unwrap(), Subscription::Remove(vec!["ended-Yes".to_string(), "ended-No".to_string()]));
+        let state = state.read().await;
+        assert!(state.markets.handle("ended").is_none() && state.markets.handle("open").is_some());
+        assert!(state.resolved_markets.contains("ended"));
+    }
+}
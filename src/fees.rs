@@ -0,0 +1,65 @@
+use crate::shared_types::Market;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// A market's fee rates from its CLOB metadata, in basis points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FeeSchedule {
+    pub maker_bps: Decimal,
+    pub taker_bps: Decimal,
+}
+
+/// Polymarket's fee on a fill of `shares` at `price`: `bps × min(price, 1 − price)` per share,
+/// so it is largest at 50c and vanishes toward either extreme.
+pub fn fee_for(bps: Decimal, price: Decimal, shares: Decimal) -> Decimal {
+    bps / dec!(10000) * price.min(Decimal::ONE - price).max(Decimal::ZERO) * shares
+}
+
+/// Fees, gas and the edge required on top of both, applied the same way by every detector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeModel {
+    /// Taker rate for markets whose CLOB metadata carries none.
+    pub default_taker_fee_bps: Decimal,
+    /// Flat gas per on-chain transaction, in USDC.
+    pub gas_cost: Decimal,
+    /// Profit per dollar of payout still required once fees are paid.
+    pub min_edge: Decimal,
+}
+
+impl Default for FeeModel {
+    fn default() -> Self {
+        Self { default_taker_fee_bps: Decimal::ZERO, gas_cost: Decimal::ZERO, min_edge: dec!(0.02) }
+    }
+}
+
+impl FeeModel {
+    pub fn taker_bps(&self, market: &Market) -> Decimal {
+        market.fees.map_or(self.default_taker_fee_bps, |f| f.taker_bps)
+    }
+
+    /// Taker fee for crossing the spread on `market` at `price`.
+    pub fn taker_fee(&self, market: &Market, price: Decimal, shares: Decimal) -> Decimal {
+        fee_for(self.taker_bps(market), price, shares)
+    }
+
+    /// Gas for `transactions` on-chain calls spread over `units` shares or sets.
+    pub fn amortized_gas(&self, transactions: u32, units: Decimal) -> Decimal {
+        if units.is_zero() { Decimal::ZERO } else { self.gas_cost * Decimal::from(transactions) / units }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_peaks_at_even_odds_and_amortizes_gas() {
+        assert_eq!(fee_for(dec!(200), dec!(0.5), dec!(100)), dec!(1));
+        assert_eq!(fee_for(dec!(200), dec!(0.9), dec!(100)), dec!(0.2));
+        assert_eq!(fee_for(dec!(200), dec!(0.1), dec!(100)), dec!(0.2));
+
+        let model = FeeModel { gas_cost: dec!(0.05), ..FeeModel::default() };
+        assert_eq!(model.amortized_gas(2, dec!(50)), dec!(0.002));
+        assert_eq!(model.amortized_gas(1, Decimal::ZERO), Decimal::ZERO);
+    }
+}
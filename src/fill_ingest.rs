@@ -0,0 +1,580 @@
+use crate::blockchain::{BlockchainCollector, ExecutionError, OrderFilledFilter};
+use crate::clob_client::OrderSide;
+use crate::units::{from_units, UsdcAmount, TOKEN_DECIMALS};
+use crate::execution_analyzer::MarketResolution;
+use crate::opportunity_store::StoreError;
+use crate::reorg::{self, BlockHashes, Rollback, TRACKED_BLOCKS};
+use crate::resolution_watcher::ResolvedMarket;
+use crate::token_resolver::{TokenInfo, TokenResolver};
+use async_trait::async_trait;
+use ethers::contract::LogMeta;
+use ethers::types::H256;
+use rusqlite::{params, Connection, OptionalExtension};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// The asset id OrderFilled uses for the USDC side of a fill.
+pub const USDC_ASSET_ID: &str = "0";
+
+/// Checkpoint key for the `OrderFilled` backfill.
+pub const ORDER_FILLED_CHECKPOINT: &str = "order_filled";
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error("chain error: {0}")]
+    Chain(#[from] ExecutionError),
+    #[error("fill store error: {0}")]
+    Store(#[from] StoreError),
+}
+
+/// A decoded `OrderFilled` event. Amounts are in whole tokens (USDC and outcome tokens both
+/// use six decimals). Asset ids are decimal ERC-1155 token ids, with "0" for USDC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillRecord {
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub order_hash: String,
+    pub maker: String,
+    pub taker: String,
+    pub maker_asset_id: String,
+    pub taker_asset_id: String,
+    pub maker_amount: Decimal,
+    pub taker_amount: Decimal,
+    pub fee: Decimal,
+    /// Market and outcome of the traded token, once resolved.
+    pub market: Option<TokenInfo>,
+}
+
+impl FillRecord {
+    pub fn from_log(fill: &OrderFilledFilter, meta: &LogMeta, timestamp: u64) -> Self {
+        Self {
+            block_number: meta.block_number.as_u64(),
+            timestamp,
+            tx_hash: format!("{:?}", meta.transaction_hash),
+            log_index: meta.log_index.as_u64(),
+            order_hash: format!("{:?}", H256::from(fill.order_hash)),
+            maker: format!("{:?}", fill.maker),
+            taker: format!("{:?}", fill.taker),
+            maker_asset_id: fill.maker_asset_id.to_string(),
+            taker_asset_id: fill.taker_asset_id.to_string(),
+            maker_amount: from_units(fill.maker_fill_amount, TOKEN_DECIMALS).unwrap_or_default(),
+            taker_amount: from_units(fill.taker_fill_amount, TOKEN_DECIMALS).unwrap_or_default(),
+            fee: UsdcAmount::from_units(fill.fee).unwrap_or_default().value(),
+            market: None,
+        }
+    }
+
+    /// `tx_hash:log_index`, unique per log.
+    pub fn fill_id(&self) -> String {
+        format!("{}:{}", self.tx_hash, self.log_index)
+    }
+
+    /// The maker gives USDC when buying outcome tokens.
+    fn maker_buys(&self) -> bool {
+        self.maker_asset_id == USDC_ASSET_ID
+    }
+
+    /// The outcome token that changed hands.
+    pub fn asset_id(&self) -> &str {
+        if self.maker_buys() { &self.taker_asset_id } else { &self.maker_asset_id }
+    }
+
+    /// The maker order's side.
+    pub fn maker_side(&self) -> OrderSide {
+        if self.maker_buys() { OrderSide::Buy } else { OrderSide::Sell }
+    }
+
+    /// Outcome tokens traded.
+    pub fn shares(&self) -> Decimal {
+        if self.maker_buys() { self.taker_amount } else { self.maker_amount }
+    }
+
+    /// USDC paid per outcome token, or `None` for an empty fill.
+    pub fn price(&self) -> Option<Decimal> {
+        let (usdc, shares) = if self.maker_buys() { (self.maker_amount, self.taker_amount) } else { (self.taker_amount, self.maker_amount) };
+        (!shares.is_zero()).then(|| usdc / shares)
+    }
+}
+
+/// Where historical fills come from. Implemented by `BlockchainCollector`; tests substitute
+/// a source with a fake result limit.
+#[async_trait]
+pub trait FillSource: BlockHashes {
+    async fn latest_block(&self) -> Result<u64, ExecutionError>;
+    async fn fills_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<FillRecord>, ExecutionError>;
+}
+
+#[async_trait]
+impl FillSource for BlockchainCollector {
+    async fn latest_block(&self) -> Result<u64, ExecutionError> {
+        BlockchainCollector::latest_block(self).await
+    }
+
+    async fn fills_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<FillRecord>, ExecutionError> {
+        let logs = self.fetch_fills_with_meta(from_block, to_block).await?;
+        let mut timestamps: HashMap<u64, u64> = HashMap::new();
+        let mut fills = Vec::with_capacity(logs.len());
+        for (fill, meta) in logs {
+            let block_number = meta.block_number.as_u64();
+            let timestamp = match timestamps.get(&block_number) {
+                Some(ts) => *ts,
+                None => {
+                    let ts = self.block_timestamp(block_number).await?;
+                    timestamps.insert(block_number, ts);
+                    ts
+                }
+            };
+            fills.push(FillRecord::from_log(&fill, &meta, timestamp));
+        }
+        Ok(fills)
+    }
+}
+
+/// Whether the provider refused a log query because the range holds too many results or
+/// blocks. Providers word this differently, so match the common phrasings.
+pub fn is_range_limit_error(error: &ExecutionError) -> bool {
+    let message = match error {
+        ExecutionError::Contract(message) => message.to_lowercase(),
+        ExecutionError::Provider(e) => e.to_string().to_lowercase(),
+        _ => return false,
+    };
+    ["limit exceeded", "returned more than", "too many", "block range", "response size exceeded"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// SQLite store of decoded fills, market outcomes and per-pipeline block checkpoints. Fills are keyed by
+/// `(tx_hash, log_index)`, so re-ingesting a range is harmless.
+pub struct FillStore {
+    conn: Mutex<Connection>,
+}
+
+impl FillStore {
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StoreError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS fills (
+                tx_hash TEXT NOT NULL,
+                log_index INTEGER NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                order_hash TEXT NOT NULL,
+                maker TEXT NOT NULL,
+                taker TEXT NOT NULL,
+                maker_asset_id TEXT NOT NULL,
+                taker_asset_id TEXT NOT NULL,
+                maker_amount TEXT NOT NULL,
+                taker_amount TEXT NOT NULL,
+                fee TEXT NOT NULL,
+                market_id TEXT,
+                question TEXT,
+                outcome TEXT,
+                outcome_index INTEGER,
+                PRIMARY KEY (tx_hash, log_index)
+            );
+            CREATE INDEX IF NOT EXISTS idx_fills_block ON fills(block_number);
+            CREATE INDEX IF NOT EXISTS idx_fills_market ON fills(market_id);
+            CREATE TABLE IF NOT EXISTS checkpoints (
+                name TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS block_hashes (
+                block_number INTEGER PRIMARY KEY,
+                hash TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS resolutions (
+                condition_id TEXT PRIMARY KEY,
+                market_id TEXT,
+                payouts TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            );",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Inserts `fills` and returns how many were new.
+    pub fn insert_fills(&self, fills: &[FillRecord]) -> Result<usize, StoreError> {
+        let mut conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let tx = conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO fills (tx_hash, log_index, block_number, timestamp, order_hash, maker, taker, maker_asset_id, taker_asset_id,
+                                              maker_amount, taker_amount, fee, market_id, question, outcome, outcome_index)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+            )?;
+            for f in fills {
+                let market = f.market.as_ref();
+                inserted += stmt.execute(params![
+                    f.tx_hash,
+                    f.log_index as i64,
+                    f.block_number as i64,
+                    f.timestamp as i64,
+                    f.order_hash,
+                    f.maker,
+                    f.taker,
+                    f.maker_asset_id,
+                    f.taker_asset_id,
+                    f.maker_amount.to_string(),
+                    f.taker_amount.to_string(),
+                    f.fee.to_string(),
+                    market.map(|m| &m.market_id),
+                    market.map(|m| &m.question),
+                    market.map(|m| &m.outcome),
+                    market.map(|m| m.outcome_index as i64),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// Last block fully ingested by the pipeline `name`.
+    pub fn checkpoint(&self, name: &str) -> Result<Option<u64>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let block = conn.query_row("SELECT block_number FROM checkpoints WHERE name = ?1", params![name], |row| row.get::<_, i64>(0)).optional()?;
+        Ok(block.map(|b| b as u64))
+    }
+
+    pub fn set_checkpoint(&self, name: &str, block: u64) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        conn.execute(
+            "INSERT INTO checkpoints (name, block_number) VALUES (?1, ?2) ON CONFLICT(name) DO UPDATE SET block_number = excluded.block_number",
+            params![name, block as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Remembers the hash of a checkpointed block, keeping the newest `TRACKED_BLOCKS`.
+    pub fn record_block_hash(&self, block: u64, hash: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        conn.execute("INSERT OR REPLACE INTO block_hashes (block_number, hash) VALUES (?1, ?2)", params![block as i64, hash])?;
+        conn.execute(
+            "DELETE FROM block_hashes WHERE block_number NOT IN (SELECT block_number FROM block_hashes ORDER BY block_number DESC LIMIT ?1)",
+            params![TRACKED_BLOCKS as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Up to `limit` recorded block hashes, newest first.
+    pub fn block_hashes(&self, limit: usize) -> Result<Vec<(u64, String)>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare("SELECT block_number, hash FROM block_hashes ORDER BY block_number DESC LIMIT ?1")?;
+        let rows = stmt.query_map(params![limit as i64], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Drops fills, resolutions and block hashes after `block` and lowers every checkpoint
+    /// to it, so the pipelines re-read the blocks that follow.
+    pub fn rollback_after(&self, block: u64) -> Result<Rollback, StoreError> {
+        let mut conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let tx = conn.transaction()?;
+        let after = block as i64;
+        let fills = tx.execute("DELETE FROM fills WHERE block_number > ?1", params![after])?;
+        let resolutions = tx.execute("DELETE FROM resolutions WHERE block_number > ?1", params![after])?;
+        tx.execute("DELETE FROM block_hashes WHERE block_number > ?1", params![after])?;
+        tx.execute("UPDATE checkpoints SET block_number = ?1 WHERE block_number > ?1", params![after])?;
+        tx.commit()?;
+        Ok(Rollback { fork_block: block, fills, resolutions })
+    }
+
+    /// Fills in blocks `[from_block, to_block]`, in chain order.
+    pub fn fills_between(&self, from_block: u64, to_block: u64) -> Result<Vec<FillRecord>, StoreError> {
+        self.query(
+            "SELECT block_number, timestamp, tx_hash, log_index, order_hash, maker, taker, maker_asset_id, taker_asset_id,
+                    maker_amount, taker_amount, fee, market_id, question, outcome, outcome_index
+             FROM fills WHERE block_number >= ?1 AND block_number <= ?2 ORDER BY block_number, log_index",
+            params![from_block as i64, to_block as i64],
+        )
+    }
+
+    /// Fills with a block timestamp of at least `timestamp` (Unix seconds), in chain order.
+    pub fn fills_since(&self, timestamp: u64) -> Result<Vec<FillRecord>, StoreError> {
+        self.query(
+            "SELECT block_number, timestamp, tx_hash, log_index, order_hash, maker, taker, maker_asset_id, taker_asset_id,
+                    maker_amount, taker_amount, fee, market_id, question, outcome, outcome_index
+             FROM fills WHERE timestamp >= ?1 ORDER BY block_number, log_index",
+            params![timestamp as i64],
+        )
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<FillRecord>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            let market = match row.get::<_, Option<String>>(12)? {
+                Some(market_id) => Some(TokenInfo {
+                    market_id,
+                    question: row.get::<_, Option<String>>(13)?.unwrap_or_default(),
+                    outcome: row.get::<_, Option<String>>(14)?.unwrap_or_default(),
+                    outcome_index: row.get::<_, Option<i64>>(15)?.unwrap_or_default() as usize,
+                }),
+                None => None,
+            };
+            let fill = FillRecord {
+                block_number: row.get::<_, i64>(0)? as u64,
+                timestamp: row.get::<_, i64>(1)? as u64,
+                tx_hash: row.get(2)?,
+                log_index: row.get::<_, i64>(3)? as u64,
+                order_hash: row.get(4)?,
+                maker: row.get(5)?,
+                taker: row.get(6)?,
+                maker_asset_id: row.get(7)?,
+                taker_asset_id: row.get(8)?,
+                maker_amount: Decimal::ZERO,
+                taker_amount: Decimal::ZERO,
+                fee: Decimal::ZERO,
+                market,
+            };
+            Ok((fill, [row.get::<_, String>(9)?, row.get::<_, String>(10)?, row.get::<_, String>(11)?]))
+        })?;
+
+        let decimal = |s: &str| Decimal::from_str(s).map_err(|e| StoreError::Corrupt(e.to_string()));
+        let mut fills = Vec::new();
+        for row in rows {
+            let (mut fill, [maker_amount, taker_amount, fee]) = row?;
+            fill.maker_amount = decimal(&maker_amount)?;
+            fill.taker_amount = decimal(&taker_amount)?;
+            fill.fee = decimal(&fee)?;
+            fills.push(fill);
+        }
+        Ok(fills)
+    }
+
+    /// Records a market's final outcome. Returns whether it was new.
+    pub fn insert_resolution(&self, resolved: &ResolvedMarket) -> Result<bool, StoreError> {
+        let payouts = serde_json::to_string(&resolved.resolution.payouts.iter().map(Decimal::to_string).collect::<Vec<_>>())?;
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO resolutions (condition_id, market_id, payouts, block_number, timestamp) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![resolved.resolution.condition_id, resolved.market_id, payouts, resolved.block_number as i64, resolved.timestamp as i64],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Every recorded outcome in chain order, e.g. as labels for backtests.
+    pub fn resolutions(&self) -> Result<Vec<ResolvedMarket>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare("SELECT condition_id, market_id, payouts, block_number, timestamp FROM resolutions ORDER BY block_number, condition_id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)? as u64, row.get::<_, i64>(4)? as u64))
+        })?;
+        let mut resolved = Vec::new();
+        for row in rows {
+            let (condition_id, market_id, payouts, block_number, timestamp) = row?;
+            let payouts = serde_json::from_str::<Vec<String>>(&payouts)?
+                .iter()
+                .map(|p| Decimal::from_str(p).map_err(|e| StoreError::Corrupt(e.to_string())))
+                .collect::<Result<_, _>>()?;
+            resolved.push(ResolvedMarket { resolution: MarketResolution { condition_id, payouts }, market_id, block_number, timestamp });
+        }
+        Ok(resolved)
+    }
+
+    /// Attaches market metadata to stored fills whose token could not be resolved when they
+    /// were ingested, e.g. after a fresh Gamma fetch. Returns how many fills were updated.
+    pub fn enrich(&self, resolver: &TokenResolver) -> Result<usize, StoreError> {
+        let mut conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let tx = conn.transaction()?;
+        let tokens: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT DISTINCT CASE WHEN maker_asset_id = ?1 THEN taker_asset_id ELSE maker_asset_id END FROM fills WHERE market_id IS NULL",
+            )?;
+            let rows = stmt.query_map(params![USDC_ASSET_ID], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        let mut updated = 0;
+        for token in tokens {
+            let Some(info) = resolver.resolve(&token) else { continue };
+            updated += tx.execute(
+                "UPDATE fills SET market_id = ?1, question = ?2, outcome = ?3, outcome_index = ?4
+                 WHERE market_id IS NULL AND (maker_asset_id = ?5 OR taker_asset_id = ?5)",
+                params![info.market_id, info.question, info.outcome, info.outcome_index as i64, token],
+            )?;
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IngestConfig {
+    /// Blocks per log query. Halved when the provider refuses a range, and grown back after
+    /// each successful query.
+    pub chunk_blocks: u64,
+    /// Blocks behind the tip left unread, so reorged fills are not stored.
+    pub confirmations: u64,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self { chunk_blocks: 2000, confirmations: 5 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IngestSummary {
+    pub from_block: u64,
+    /// Last block ingested, equal to the checkpoint.
+    pub to_block: u64,
+    pub fills: usize,
+    /// Ranges split after the provider refused them.
+    pub bisections: usize,
+}
+
+/// Walks `OrderFilled` events from the checkpoint (or `start_block` on the first run) to the
+/// confirmed tip, storing decoded fills and advancing the checkpoint after every chunk, so an
+/// interrupted backfill resumes where it stopped.
+pub struct FillIngestor<S> {
+    source: S,
+    store: FillStore,
+    config: IngestConfig,
+    resolver: TokenResolver,
+}
+
+impl<S: FillSource> FillIngestor<S> {
+    pub fn new(source: S, store: FillStore, config: IngestConfig) -> Self {
+        Self { source, store, config, resolver: TokenResolver::default() }
+    }
+
+    /// Tags each fill with its market and outcome as it is stored.
+    pub fn with_resolver(mut self, resolver: TokenResolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    pub fn store(&self) -> &FillStore {
+        &self.store
+    }
+
+    /// Rolls back blocks a reorg replaced, then ingests up to the confirmed tip.
+    pub async fn run(&self, start_block: u64) -> Result<IngestSummary, IngestError> {
+        reorg::rewind(&self.source, &self.store).await?;
+        let tip = self.source.latest_block().await?.saturating_sub(self.config.confirmations);
+        let from_block = self.store.checkpoint(ORDER_FILLED_CHECKPOINT)?.map_or(start_block, |b| b + 1);
+        let mut summary = IngestSummary { from_block, to_block: from_block.saturating_sub(1), ..Default::default() };
+        let max_chunk = self.config.chunk_blocks.max(1);
+        let mut chunk = max_chunk;
+        let mut next = from_block;
+
+        while next <= tip {
+            let end = next.saturating_add(chunk - 1).min(tip);
+            match self.source.fills_in_range(next, end).await {
+                Ok(mut fills) => {
+                    for fill in &mut fills {
+                        self.resolver.enrich(fill);
+                    }
+                    summary.fills += self.store.insert_fills(&fills)?;
+                    self.store.set_checkpoint(ORDER_FILLED_CHECKPOINT, end)?;
+                    if let Some(hash) = self.source.block_hash(end).await? {
+                        self.store.record_block_hash(end, &hash)?;
+                    }
+                    debug!(from = next, to = end, fills = fills.len(), "ingested fills");
+                    summary.to_block = end;
+                    next = end + 1;
+                    chunk = (chunk * 2).min(max_chunk);
+                }
+                // A single block that is still too large cannot be split further
+                Err(e) if end > next && is_range_limit_error(&e) => {
+                    chunk = (end - next).div_ceil(2);
+                    summary.bisections += 1;
+                    debug!(from = next, to = end, chunk, "log query refused, bisecting range");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        info!(from = summary.from_block, to = summary.to_block, fills = summary.fills, bisections = summary.bisections, "fill ingestion caught up");
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    /// One fill per block; refuses queries spanning more than `limit` blocks.
+    struct LimitedSource {
+        tip: u64,
+        limit: u64,
+    }
+
+    #[async_trait]
+    impl BlockHashes for LimitedSource {
+        async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError> {
+            Ok(Some(format!("0x{:x}", block)))
+        }
+    }
+
+    #[async_trait]
+    impl FillSource for LimitedSource {
+        async fn latest_block(&self) -> Result<u64, ExecutionError> {
+            Ok(self.tip)
+        }
+
+        async fn fills_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<FillRecord>, ExecutionError> {
+            if to_block - from_block + 1 > self.limit {
+                return Err(ExecutionError::Contract("query limit exceeded".to_string()));
+            }
+            Ok((from_block..=to_block).map(fill).collect())
+        }
+    }
+
+    fn fill(block_number: u64) -> FillRecord {
+        FillRecord {
+            block_number,
+            timestamp: 1_700_000_000 + block_number * 2,
+            tx_hash: format!("0x{:064x}", block_number),
+            log_index: 0,
+            order_hash: "0x01".to_string(),
+            maker: "0xaa".to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: "7".to_string(),
+            taker_asset_id: USDC_ASSET_ID.to_string(),
+            maker_amount: dec!(10),
+            taker_amount: dec!(4.5),
+            fee: Decimal::ZERO,
+            market: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bisects_refused_ranges_and_resumes_from_checkpoint() {
+        let config = IngestConfig { chunk_blocks: 40, confirmations: 5 };
+        let ingestor = FillIngestor::new(LimitedSource { tip: 105, limit: 15 }, FillStore::open_in_memory().unwrap(), config);
+
+        let summary = ingestor.run(1).await.unwrap();
+        assert_eq!((summary.from_block, summary.to_block, summary.fills), (1, 100, 100));
+        assert!(summary.bisections > 0);
+        assert_eq!(ingestor.store().checkpoint(ORDER_FILLED_CHECKPOINT).unwrap(), Some(100));
+        assert_eq!(ingestor.store().fills_between(50, 52).unwrap(), vec![fill(50), fill(51), fill(52)]);
+
+        // The start block only applies before the first checkpoint; nothing new is below the tip
+        let again = ingestor.run(1).await.unwrap();
+        assert_eq!((again.from_block, again.fills), (101, 0));
+    }
+
+    #[test]
+    fn test_limit_errors_and_duplicate_fills() {
+        assert!(is_range_limit_error(&ExecutionError::Contract("Log response size exceeded".to_string())));
+        assert!(!is_range_limit_error(&ExecutionError::Contract("execution reverted".to_string())));
+
+        let store = FillStore::open_in_memory().unwrap();
+        assert_eq!(store.insert_fills(&[fill(1), fill(2)]).unwrap(), 2);
+        assert_eq!(store.insert_fills(&[fill(2), fill(3)]).unwrap(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::blockchain::{ctf_exchange_address, CtfExchange, ExecutionError, OrderFilledFilter};
+use crate::fill_ingest::FillRecord;
+use crate::metrics::CHAIN_REORGS;
+use ethers::abi::RawLog;
+use ethers::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{info, warn};
+use url::Url;
+
+const CHANNEL_CAPACITY: usize = 4096;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Most blocks read back after a reconnect, about half an hour of Polygon.
+const MAX_BACKFILL_BLOCKS: u64 = 1_000;
+
+/// Adds the dRPC key as the `dkey` query parameter; websocket upgrades carry no custom headers.
+pub fn ws_url_with_key(ws_url: &str, drpc_key: Option<&str>) -> Result<Url, ExecutionError> {
+    let mut url = Url::parse(ws_url)?;
+    if let Some(key) = drpc_key {
+        if !url.query_pairs().any(|(k, _)| k == "dkey") {
+            url.query_pairs_mut().append_pair("dkey", key);
+        }
+    }
+    Ok(url)
+}
+
+/// A streamed fill, or one a reorg took back out of the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FillEvent {
+    Filled(FillRecord),
+    /// The log was dropped with its block; the canonical chain's logs follow as new fills.
+    Removed(FillRecord),
+}
+
+/// Realtime `OrderFilled` logs over an `eth_subscribe` websocket, broadcast as `FillEvent`s.
+/// Live fills are stamped with their arrival time rather than waiting on the block header.
+/// After a reconnect the missed blocks are read back and fills already sent are skipped, so
+/// each log reaches subscribers once per block it lands in.
+pub struct FillStream {
+    url: Url,
+    tx: broadcast::Sender<FillEvent>,
+    /// `(block, log_index)` of the last fill sent.
+    last: Option<(u64, u64)>,
+}
+
+impl FillStream {
+    pub fn new(ws_url: &str, drpc_key: Option<&str>) -> Result<Self, ExecutionError> {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Ok(Self { url: ws_url_with_key(ws_url, drpc_key)?, tx, last: None })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FillEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Follows the chain until aborted, reconnecting with backoff.
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                match self.follow().await {
+                    Ok(()) => {
+                        warn!("OrderFilled subscription ended, resubscribing");
+                        backoff = Duration::from_secs(1);
+                    }
+                    Err(e) => warn!(error = %e, retry_in = ?backoff, "OrderFilled stream failed"),
+                }
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        })
+    }
+
+    async fn follow(&mut self) -> Result<(), ExecutionError> {
+        let client = Arc::new(Provider::<Ws>::connect(self.url.as_str()).await?);
+        let contract = CtfExchange::new(ctf_exchange_address()?, client.clone());
+        // Raw logs rather than decoded events, to see the `removed` flag reorgs set
+        let filter = contract.order_filled_filter().filter;
+        let mut stream = client.subscribe_logs(&filter).await?;
+        info!("subscribed to OrderFilled logs");
+
+        // Subscribed first, so nothing between the read-back and the live stream is lost
+        if let Some((block, _)) = self.last {
+            let tip = client.get_block_number().await?.as_u64();
+            let from = block.max(tip.saturating_sub(MAX_BACKFILL_BLOCKS));
+            let missed = contract.order_filled_filter().from_block(from).to_block(tip).query_with_meta().await
+                .map_err(|e| ExecutionError::Contract(e.to_string()))?;
+            let mut timestamps: HashMap<U64, u64> = HashMap::new();
+            for (fill, meta) in missed {
+                let timestamp = match timestamps.get(&meta.block_number) {
+                    Some(ts) => *ts,
+                    None => {
+                        let ts = client.get_block(meta.block_number).await?.map_or_else(now, |b| b.timestamp.as_u64());
+                        *timestamps.entry(meta.block_number).or_insert(ts)
+                    }
+                };
+                self.emit(FillRecord::from_log(&fill, &meta, timestamp));
+            }
+        }
+
+        while let Some(log) = stream.next().await {
+            let removed = log.removed == Some(true);
+            let fill = match <OrderFilledFilter as EthEvent>::decode_log(&RawLog::from(log.clone())) {
+                Ok(fill) => FillRecord::from_log(&fill, &LogMeta::from(&log), now()),
+                Err(e) => {
+                    warn!(error = %e, "undecodable OrderFilled log");
+                    continue;
+                }
+            };
+            if removed {
+                self.retract(fill);
+            } else {
+                self.emit(fill);
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, fill: FillRecord) {
+        let position = (fill.block_number, fill.log_index);
+        if self.last.is_some_and(|last| position <= last) {
+            return;
+        }
+        self.last = Some(position);
+        // No subscribers is not an error
+        let _ = self.tx.send(FillEvent::Filled(fill));
+    }
+
+    /// Passes on a log a reorg removed and rewinds the watermark to just before it, so the
+    /// replacement logs are not skipped as already sent.
+    fn retract(&mut self, fill: FillRecord) {
+        let before = match fill.log_index {
+            0 => (fill.block_number.saturating_sub(1), u64::MAX),
+            index => (fill.block_number, index - 1),
+        };
+        self.last = self.last.map(|last| last.min(before));
+        CHAIN_REORGS.with_label_values(&["stream"]).inc();
+        warn!(block = fill.block_number, tx = %fill.tx_hash, "streamed fill removed by a reorg");
+        let _ = self.tx.send(FillEvent::Removed(fill));
+    }
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn fill(block_number: u64, log_index: u64) -> FillRecord {
+        FillRecord {
+            block_number,
+            timestamp: 0,
+            tx_hash: format!("0x{}", block_number),
+            log_index,
+            order_hash: "0x01".to_string(),
+            maker: "0xaa".to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: "0".to_string(),
+            taker_asset_id: "7".to_string(),
+            maker_amount: Decimal::ONE,
+            taker_amount: Decimal::ONE,
+            fee: Decimal::ZERO,
+            market: None,
+        }
+    }
+
+    #[test]
+    fn test_keyed_url_and_fills_sent_once() {
+        let url = ws_url_with_key("wss://lb.drpc.org/ogws?network=polygon", Some("secret")).unwrap();
+        assert_eq!(url.as_str(), "wss://lb.drpc.org/ogws?network=polygon&dkey=secret");
+        assert_eq!(ws_url_with_key(url.as_str(), Some("other")).unwrap(), url);
+
+        let mut stream = FillStream::new("wss://polygon.example", None).unwrap();
+        let mut rx = stream.subscribe();
+        // A read-back after reconnecting overlaps what the live stream already sent
+        for (block, index) in [(10, 0), (10, 1), (10, 0), (9, 5), (11, 0)] {
+            stream.emit(fill(block, index));
+        }
+        // A reorg drops block 11; its replacement is sent even though it reuses the position
+        stream.retract(fill(11, 0));
+        stream.emit(fill(11, 0));
+        let sent: Vec<(bool, u64, u64)> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|event| match event {
+                FillEvent::Filled(f) => (true, f.block_number, f.log_index),
+                FillEvent::Removed(f) => (false, f.block_number, f.log_index),
+            })
+            .collect();
+        assert_eq!(sent, vec![(true, 10, 0), (true, 10, 1), (true, 11, 0), (false, 11, 0), (true, 11, 0)]);
+    }
+}
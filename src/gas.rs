@@ -0,0 +1,202 @@
+use crate::blockchain::{ExecutionError, ProviderPool};
+use crate::units::PolAmount;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::warn;
+
+// Polygon validators reject tips below 30 gwei
+const MIN_PRIORITY_FEE_GWEI: u64 = 30;
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// How hard to bid for inclusion. Each preset reads a different reward percentile from
+/// recent blocks and pads the base fee by a different number of blocks of growth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasPreset {
+    Cheap,
+    Normal,
+    Aggressive,
+}
+
+impl GasPreset {
+    fn percentile_index(&self) -> usize {
+        match self {
+            GasPreset::Cheap => 0,
+            GasPreset::Normal => 1,
+            GasPreset::Aggressive => 2,
+        }
+    }
+
+    /// Max fee is `base_fee * multiplier + tip`, covering that many full blocks of 12.5% base fee growth.
+    fn base_fee_multiplier(&self) -> u64 {
+        match self {
+            GasPreset::Cheap => 1,
+            GasPreset::Normal => 2,
+            GasPreset::Aggressive => 3,
+        }
+    }
+}
+
+impl FromStr for GasPreset {
+    type Err = ExecutionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cheap" => Ok(GasPreset::Cheap),
+            "normal" => Ok(GasPreset::Normal),
+            "aggressive" => Ok(GasPreset::Aggressive),
+            other => Err(ExecutionError::Config(format!("unknown gas preset: {}", other))),
+        }
+    }
+}
+
+/// EIP-1559 fee parameters, in wei per gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasQuote {
+    pub base_fee: U256,
+    pub priority_fee: U256,
+    pub max_fee: U256,
+}
+
+impl GasQuote {
+    /// Worst-case cost of `gas_units` at this quote, in POL.
+    pub fn cost_pol(&self, gas_units: U256) -> Decimal {
+        wei_to_pol(gas_units.saturating_mul(self.max_fee))
+    }
+
+    /// Worst-case cost of `gas_units` at this quote, in USD.
+    pub fn cost_usd(&self, gas_units: U256, pol_usd: Decimal) -> Decimal {
+        self.cost_pol(gas_units) * pol_usd
+    }
+}
+
+/// Converts a wei amount into POL. Amounts beyond Decimal's range saturate.
+pub fn wei_to_pol(wei: U256) -> Decimal {
+    PolAmount::from_units(wei).map_or(Decimal::MAX, PolAmount::value)
+}
+
+/// Derives a quote from `eth_feeHistory` requested with the 10th, 50th and 90th reward percentiles.
+pub fn quote_from_history(history: &FeeHistory, preset: GasPreset) -> Option<GasQuote> {
+    // The last entry is the base fee of the next (pending) block
+    let base_fee = *history.base_fee_per_gas.last()?;
+    let tips: Vec<U256> = history.reward.iter().filter_map(|r| r.get(preset.percentile_index()).copied()).collect();
+    let average_tip = if tips.is_empty() {
+        U256::zero()
+    } else {
+        tips.iter().fold(U256::zero(), |acc, t| acc + t) / U256::from(tips.len())
+    };
+    let priority_fee = average_tip.max(U256::from(MIN_PRIORITY_FEE_GWEI) * U256::exp10(9));
+    let max_fee = base_fee * U256::from(preset.base_fee_multiplier()) + priority_fee;
+    Some(GasQuote { base_fee, priority_fee, max_fee })
+}
+
+#[derive(Debug, Clone)]
+pub struct GasPolicy {
+    pub preset: GasPreset,
+    /// Abort when worst-case gas would eat more than this share of expected profit.
+    pub max_profit_fraction: Decimal,
+    /// POL price used to convert gas into USD.
+    pub pol_usd: Decimal,
+}
+
+impl Default for GasPolicy {
+    fn default() -> Self {
+        Self { preset: GasPreset::Normal, max_profit_fraction: dec!(0.25), pol_usd: dec!(0.5) }
+    }
+}
+
+impl GasPolicy {
+    /// Fails with `GasTooExpensive` when the transaction is not worth sending.
+    pub fn check(&self, quote: &GasQuote, gas_units: U256, expected_profit: Decimal) -> Result<Decimal, ExecutionError> {
+        let cost = quote.cost_usd(gas_units, self.pol_usd);
+        if cost > expected_profit * self.max_profit_fraction {
+            return Err(ExecutionError::GasTooExpensive { cost, profit: expected_profit });
+        }
+        Ok(cost)
+    }
+}
+
+/// Caches the latest Polygon fee quote, refreshed on demand or by a background poller.
+#[derive(Clone)]
+pub struct GasOracle {
+    provider: Arc<Provider<ProviderPool>>,
+    pub policy: GasPolicy,
+    latest: Arc<RwLock<Option<GasQuote>>>,
+}
+
+impl GasOracle {
+    pub fn new(provider: Arc<Provider<ProviderPool>>, policy: GasPolicy) -> Self {
+        Self { provider, policy, latest: Arc::new(RwLock::new(None)) }
+    }
+
+    /// Fetches a fresh quote from the node and caches it.
+    pub async fn refresh(&self) -> Result<GasQuote, ExecutionError> {
+        let history = self.provider.fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[10.0, 50.0, 90.0]).await?;
+        let quote = quote_from_history(&history, self.policy.preset)
+            .ok_or_else(|| ExecutionError::Config("node returned an empty fee history".to_string()))?;
+        *self.latest.write().await = Some(quote);
+        Ok(quote)
+    }
+
+    /// Latest cached quote, fetching one if the poller has not run yet.
+    pub async fn current(&self) -> Result<GasQuote, ExecutionError> {
+        if let Some(quote) = *self.latest.read().await {
+            return Ok(quote);
+        }
+        self.refresh().await
+    }
+
+    /// Refreshes the quote every `interval` until the task is aborted.
+    pub fn spawn_poller(&self, interval: Duration) -> JoinHandle<()> {
+        let oracle = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = oracle.refresh().await {
+                    warn!(error = %e, "gas oracle refresh failed");
+                }
+                sleep(interval).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(n: u64) -> U256 {
+        U256::from(n) * U256::exp10(9)
+    }
+
+    #[test]
+    fn test_presets_pick_percentile_and_floor_tip() {
+        let history = FeeHistory {
+            base_fee_per_gas: vec![gwei(90), gwei(100)],
+            gas_used_ratio: vec![0.5],
+            oldest_block: U256::zero(),
+            reward: vec![vec![gwei(1), gwei(40), gwei(80)], vec![gwei(1), gwei(60), gwei(120)]],
+        };
+
+        let cheap = quote_from_history(&history, GasPreset::Cheap).unwrap();
+        assert_eq!(cheap.priority_fee, gwei(30));
+        assert_eq!(cheap.max_fee, gwei(130));
+
+        let aggressive = quote_from_history(&history, GasPreset::Aggressive).unwrap();
+        assert_eq!(aggressive.priority_fee, gwei(100));
+        assert_eq!(aggressive.max_fee, gwei(400));
+    }
+
+    #[test]
+    fn test_policy_rejects_gas_above_profit_fraction() {
+        let policy = GasPolicy { preset: GasPreset::Normal, max_profit_fraction: dec!(0.25), pol_usd: dec!(1) };
+        let quote = GasQuote { base_fee: gwei(100), priority_fee: gwei(30), max_fee: gwei(1000) };
+        // 200k gas at 1000 gwei = 0.2 POL = $0.20
+        assert_eq!(policy.check(&quote, U256::from(200_000), dec!(1)).unwrap(), dec!(0.2));
+        assert!(matches!(policy.check(&quote, U256::from(200_000), dec!(0.5)), Err(ExecutionError::GasTooExpensive { .. })));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::metrics::TRADING_HALTED;
+use crate::notifier::{Alert, NotifierHandle};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use thiserror::Error;
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+#[derive(Debug, Error)]
+pub enum KillSwitchError {
+    #[error("failed to clear {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("control API request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("control API refused to re-arm ({status}): {body}")]
+    Refused { status: u16, body: String },
+}
+
+/// Limits that halt execution until someone re-arms the switch. Unset limits never trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KillSwitchConfig {
+    /// USD below the session's peak equity (booked execution PnL plus marked positions).
+    pub max_drawdown: Option<Decimal>,
+    /// USD of net booked losses (expected profit less gas) within `loss_window`.
+    pub max_window_loss: Option<Decimal>,
+    pub loss_window: Duration,
+    /// Share of failed executions within `error_window`, once `min_executions` have run.
+    pub max_error_rate: Option<Decimal>,
+    pub error_window: Duration,
+    pub min_executions: usize,
+    /// Where a halt is persisted so restarts stay halted.
+    pub path: PathBuf,
+}
+
+impl Default for KillSwitchConfig {
+    fn default() -> Self {
+        Self {
+            max_drawdown: None,
+            max_window_loss: None,
+            loss_window: Duration::from_secs(3600),
+            max_error_rate: None,
+            error_window: Duration::from_secs(900),
+            min_executions: 10,
+            path: PathBuf::from("kill_switch.json"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Halt {
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct Supervisor {
+    booked: Decimal,
+    unrealized: Decimal,
+    peak: Decimal,
+    pnl: VecDeque<(Instant, Decimal)>,
+    outcomes: VecDeque<(Instant, bool)>,
+    halt: Option<Halt>,
+}
+
+impl Supervisor {
+    fn equity(&self) -> Decimal {
+        self.booked + self.unrealized
+    }
+}
+
+/// Risk supervisor behind the execution guard. Once tripped, every execution is refused
+/// (scanning carries on) until `rearm` is called from the control API or the CLI.
+pub struct KillSwitch {
+    config: KillSwitchConfig,
+    alerts: NotifierHandle,
+    state: Mutex<Supervisor>,
+}
+
+impl KillSwitch {
+    /// Starts halted when a previous run left a halt at `config.path`.
+    pub fn new(config: KillSwitchConfig, alerts: NotifierHandle) -> Self {
+        let halt = match std::fs::read_to_string(&config.path) {
+            Ok(text) => match serde_json::from_str::<Halt>(&text) {
+                Ok(halt) => Some(halt),
+                Err(e) => {
+                    // Unreadable is treated as halted: someone has to look before trading resumes
+                    warn!(path = %config.path.display(), error = %e, "unreadable kill switch state");
+                    Some(Halt { reason: format!("unreadable {}", config.path.display()), at: Utc::now() })
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!(path = %config.path.display(), error = %e, "unreadable kill switch state");
+                Some(Halt { reason: format!("unreadable {}", config.path.display()), at: Utc::now() })
+            }
+        };
+        if let Some(halt) = &halt {
+            TRADING_HALTED.set(1);
+            warn!(reason = %halt.reason, since = %halt.at, "kill switch still halted from a previous run, re-arm to trade");
+        }
+        Self { config, alerts, state: Mutex::new(Supervisor { halt, ..Supervisor::default() }) }
+    }
+
+    fn state(&self) -> MutexGuard<'_, Supervisor> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn halted(&self) -> Option<Halt> {
+        self.state().halt.clone()
+    }
+
+    /// Books an execution's net PnL (expected profit less gas).
+    pub fn record_pnl(&self, now: Instant, pnl: Decimal) {
+        let mut state = self.state();
+        state.booked += pnl;
+        state.pnl.push_back((now, pnl));
+        while state.pnl.front().is_some_and(|(t, _)| now.duration_since(*t) > self.config.loss_window) {
+            state.pnl.pop_front();
+        }
+        let window: Decimal = state.pnl.iter().map(|(_, p)| *p).sum();
+        if let Some(max) = self.config.max_window_loss.filter(|max| -window > *max) {
+            let reason = format!("lost ${} in {} minutes, limit ${}", -window, self.config.loss_window.as_secs() / 60, max);
+            self.trip(&mut state, reason);
+        }
+        self.check_drawdown(&mut state);
+    }
+
+    /// Marks open positions at their current value less cost.
+    pub fn mark(&self, unrealized: Decimal) {
+        let mut state = self.state();
+        state.unrealized = unrealized;
+        self.check_drawdown(&mut state);
+    }
+
+    /// Counts an execution that completed (`ok`) or failed.
+    pub fn record_outcome(&self, now: Instant, ok: bool) {
+        let mut state = self.state();
+        state.outcomes.push_back((now, ok));
+        while state.outcomes.front().is_some_and(|(t, _)| now.duration_since(*t) > self.config.error_window) {
+            state.outcomes.pop_front();
+        }
+        let total = state.outcomes.len();
+        let Some(max) = self.config.max_error_rate.filter(|_| total >= self.config.min_executions.max(1)) else { return };
+        let failed = state.outcomes.iter().filter(|(_, ok)| !ok).count();
+        let rate = Decimal::from(failed) / Decimal::from(total);
+        if rate > max {
+            let reason = format!("{} of the last {} executions failed, limit {}%", failed, total, (max * Decimal::ONE_HUNDRED).normalize());
+            self.trip(&mut state, reason);
+        }
+    }
+
+    fn check_drawdown(&self, state: &mut Supervisor) {
+        let equity = state.equity();
+        state.peak = state.peak.max(equity);
+        let drawdown = state.peak - equity;
+        if let Some(max) = self.config.max_drawdown.filter(|max| drawdown > *max) {
+            let reason = format!("drawdown ${} from peak ${}, limit ${}", drawdown.round_dp(2), state.peak.round_dp(2), max);
+            self.trip(state, reason);
+        }
+    }
+
+    fn trip(&self, state: &mut Supervisor, reason: String) {
+        if state.halt.is_some() {
+            return;
+        }
+        error!(%reason, "kill switch tripped, halting execution until re-armed");
+        TRADING_HALTED.set(1);
+        let halt = Halt { reason: reason.clone(), at: Utc::now() };
+        if let Err(e) = write(&self.config.path, &halt) {
+            warn!(path = %self.config.path.display(), error = %e, "failed to persist kill switch halt");
+        }
+        state.halt = Some(halt);
+        self.alerts.notify(Alert::Error { context: "kill switch".to_string(), message: reason });
+    }
+
+    /// Resumes execution. Drawdown is measured from the current equity and the loss and error
+    /// windows start empty. Returns the halt that was cleared, if any.
+    pub fn rearm(&self) -> Result<Option<Halt>, io::Error> {
+        let mut state = self.state();
+        clear(&self.config.path)?;
+        let halt = state.halt.take();
+        state.peak = state.equity();
+        state.pnl.clear();
+        state.outcomes.clear();
+        TRADING_HALTED.set(0);
+        if let Some(halt) = &halt {
+            info!(reason = %halt.reason, "kill switch re-armed, execution resumed");
+        }
+        Ok(halt)
+    }
+}
+
+fn write(path: &Path, halt: &Halt) -> io::Result<()> {
+    std::fs::write(path, serde_json::to_string(halt)?)
+}
+
+/// Asks the bot serving the control API on `addr` to re-arm, returning its reply.
+pub async fn rearm_remote(addr: &str, token: Option<&str>) -> Result<String, KillSwitchError> {
+    let mut request = reqwest::Client::new().post(format!("http://{}/kill-switch/rearm", addr)).timeout(std::time::Duration::from_secs(10));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(KillSwitchError::Refused { status: status.as_u16(), body: body.trim().to_string() });
+    }
+    Ok(body.trim().to_string())
+}
+
+/// Removes a persisted halt, for re-arming while the bot is not running.
+pub fn clear(path: &Path) -> io::Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn config(name: &str) -> KillSwitchConfig {
+        let path = std::env::temp_dir().join(format!("kill-switch-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        KillSwitchConfig { max_drawdown: Some(dec!(50)), max_window_loss: Some(dec!(20)), max_error_rate: Some(dec!(0.5)), min_executions: 4, path, ..KillSwitchConfig::default() }
+    }
+
+    #[test]
+    fn test_trips_on_drawdown_and_stays_halted_across_restarts_until_rearmed() {
+        let config = config("drawdown");
+        let switch = KillSwitch::new(config.clone(), NotifierHandle::disabled());
+        let now = Instant::now();
+        switch.record_pnl(now, dec!(100));
+        switch.mark(dec!(-40));
+        assert!(switch.halted().is_none());
+        switch.mark(dec!(-60));
+        assert!(switch.halted().unwrap().reason.starts_with("drawdown $60"));
+
+        let restarted = KillSwitch::new(config.clone(), NotifierHandle::disabled());
+        assert!(restarted.halted().is_some());
+        assert!(restarted.rearm().unwrap().is_some());
+        assert!(restarted.halted().is_none() && !config.path.exists());
+        // Re-arming resets the peak, so the loss already taken does not trip it again
+        restarted.mark(dec!(-30));
+        assert!(restarted.halted().is_none());
+    }
+
+    #[test]
+    fn test_trips_on_window_losses_and_error_rate() {
+        let switch = KillSwitch::new(config("window"), NotifierHandle::disabled());
+        let now = Instant::now();
+        switch.record_pnl(now, dec!(-15));
+        // The first loss has left the hour-long window
+        switch.record_pnl(now + Duration::from_secs(3700), dec!(-15));
+        assert!(switch.halted().is_none());
+        switch.record_pnl(now + Duration::from_secs(3800), dec!(-6));
+        assert!(switch.halted().unwrap().reason.starts_with("lost $21"));
+        switch.rearm().unwrap();
+
+        for ok in [false, false, true] {
+            switch.record_outcome(now, ok);
+        }
+        assert!(switch.halted().is_none(), "too few executions to judge");
+        switch.record_outcome(now, false);
+        assert_eq!(switch.halted().unwrap().reason, "3 of the last 4 executions failed, limit 50%");
+        switch.rearm().unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use crate::metrics::{TICK_LATENCY, TICK_LATENCY_QUANTILE};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::trace;
+
+/// Samples per stage the published quantiles are computed over.
+const WINDOW: usize = 1024;
+
+/// Points on the hot path from a websocket frame to an execution decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Parsing one websocket frame into events.
+    Deserialize,
+    /// Frame receipt to the start of its engine run, coalescing window included.
+    Queue,
+    /// One engine run over a tick, state lock wait included.
+    Evaluate,
+    /// Frame receipt to an opportunity being handed to the scheduler.
+    TickToDecision,
+    /// Time a scheduled opportunity waited for an execution slot.
+    Dispatch,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [Stage::Deserialize, Stage::Queue, Stage::Evaluate, Stage::TickToDecision, Stage::Dispatch];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Deserialize => "deserialize",
+            Stage::Queue => "queue",
+            Stage::Evaluate => "evaluate",
+            Stage::TickToDecision => "tick_to_decision",
+            Stage::Dispatch => "dispatch",
+        }
+    }
+}
+
+lazy_static! {
+    static ref RECENT: Mutex<HashMap<Stage, VecDeque<Duration>>> = Mutex::new(HashMap::new());
+}
+
+/// Records one measurement. Per-event timings are logged at trace level, so
+/// `RUST_LOG=polymarket_bot::latency=trace` prints them inside the caller's span.
+pub fn record(stage: Stage, elapsed: Duration) {
+    TICK_LATENCY.with_label_values(&[stage.as_str()]).observe(elapsed.as_secs_f64());
+    trace!(stage = stage.as_str(), micros = elapsed.as_micros() as u64, "latency");
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    let samples = recent.entry(stage).or_default();
+    if samples.len() == WINDOW {
+        samples.pop_front();
+    }
+    samples.push_back(elapsed);
+}
+
+/// Sets the P50/P99 gauges from the recent window; called when metrics are scraped.
+pub fn publish_quantiles() {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    for stage in Stage::ALL {
+        let Some(samples) = recent.get(&stage).filter(|s| !s.is_empty()) else { continue };
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        for (label, q) in [("0.5", 0.5), ("0.99", 0.99)] {
+            TICK_LATENCY_QUANTILE.with_label_values(&[stage.as_str(), label]).set(quantile(&sorted, q).as_secs_f64());
+        }
+    }
+}
+
+/// Nearest-rank quantile of a sorted, non-empty sample.
+fn quantile(sorted: &[Duration], q: f64) -> Duration {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_rank_quantiles() {
+        let sorted: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(quantile(&sorted, 0.5), Duration::from_millis(50));
+        assert_eq!(quantile(&sorted, 0.99), Duration::from_millis(99));
+        assert_eq!(quantile(&sorted[..1], 0.99), Duration::from_millis(1));
+    }
+}
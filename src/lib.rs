@@ -5,4 +5,61 @@ pub mod market_fetcher;
 pub mod blockchain;
 pub mod execution_analyzer;
 pub mod topic_classifier;
-pub mod clob_client;
\ No newline at end of file
+pub mod clob_client;
+pub mod order_book;
+pub mod clob_auth;
+pub mod risk;
+pub mod opportunity_store;
+pub mod shutdown;
+pub mod error;
+pub mod market_state;
+pub mod profit_model;
+pub mod gas;
+pub mod positions;
+pub mod metrics;
+pub mod config;
+pub mod backtest;
+pub mod recorder;
+pub mod dedup;
+pub mod order_lifecycle;
+pub mod entities;
+pub mod logging;
+pub mod notifier;
+pub mod execution_guard;
+pub mod approvals;
+pub mod execution_planner;
+pub mod submission;
+pub mod balances;
+pub mod wallet_pool;
+pub mod fill_ingest;
+pub mod token_resolver;
+pub mod price_series;
+pub mod copy_trader;
+pub mod resolution_watcher;
+pub mod redemption;
+pub mod rest_client;
+pub mod candidate_index;
+pub mod fees;
+pub mod scheduler;
+pub mod coalescer;
+pub mod latency;
+pub mod cli;
+pub mod reporting;
+pub mod kill_switch;
+pub mod signals;
+pub mod quoting;
+pub mod rate_limit;
+pub mod strategy;
+pub mod dashboard;
+pub mod runtime_control;
+pub mod market_rules;
+pub mod resolution_risk;
+pub mod expiry;
+pub mod similarity;
+pub mod category_params;
+pub mod outcomes;
+pub mod units;
+pub mod fill_stream;
+pub mod reorg;
+pub mod signer;
+pub mod proxy_wallet;
@@ -0,0 +1,21 @@
+use tracing_subscriber::EnvFilter;
+
+/// Output format of the global subscriber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines for a terminal.
+    #[default]
+    Text,
+    /// One JSON object per event, with the enclosing spans, for log shippers.
+    Json,
+}
+
+/// Installs the global tracing subscriber. `filter` takes `RUST_LOG` syntax, so levels can be
+/// set per module (`info,polymarket_bot::clob_client=debug`). Must be called at most once.
+pub fn init(format: LogFormat, filter: EnvFilter) {
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_target(true);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+    }
+}
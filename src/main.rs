@@ -1,126 +1,976 @@
-use polymarket_bot::market_fetcher::fetch_markets;
+use polymarket_bot::market_fetcher::{enrich_markets, fetch_markets};
 use polymarket_bot::normalization::normalize_markets;
-use polymarket_bot::arbitrage_engine::{check_rebalancing, are_markets_related, check_combinatorial_pair};
-use polymarket_bot::shared_types::DependencyGraph;
-use polymarket_bot::blockchain::TradeExecutor;
+use polymarket_bot::market_state::{run_refresh_loop, MarketState, SharedMarketState};
+use polymarket_bot::blockchain::{BlockchainCollector, ExecutionError, ProviderPool, TradeExecutor};
+use polymarket_bot::signer::WalletSigner;
 use polymarket_bot::clob_client::ClobClient;
+use polymarket_bot::order_book::SharedOrderBooks;
+use polymarket_bot::signals::{SharedSignals, Signals};
+use polymarket_bot::strategy::MarketView;
+use polymarket_bot::risk::{Exposure, ExposureGroups, PositionSizer};
+use polymarket_bot::positions::{PositionTracker, SharedPositions};
+use polymarket_bot::opportunity_store::{ExecutionRecord, OpportunityKind, OpportunityStore, StoreError};
+use polymarket_bot::coalescer::Coalescer;
+use polymarket_bot::scheduler::{Scheduler, Ticket};
+use polymarket_bot::shared_types::Opportunity;
+use polymarket_bot::shutdown::ShutdownController;
+use polymarket_bot::error::BotError;
+use polymarket_bot::config::{BotConfig, ConfigError};
+use polymarket_bot::backtest::{load_updates, Backtester, FillModel};
+use polymarket_bot::cli::{AnalyzeArgs, BacktestArgs, Cli, CliError, Command, Mode};
+use polymarket_bot::execution_analyzer::{PnlEngine, WalletTracker};
+use polymarket_bot::recorder::StreamRecorder;
+use polymarket_bot::dedup::OpportunityGate;
+use polymarket_bot::category_params::CategoryParams;
+use polymarket_bot::entities;
+use polymarket_bot::execution_guard::GuardedExecutor;
+use polymarket_bot::expiry::{ExpiryPolicy, ExpiryWatcher};
+use polymarket_bot::latency::{self, Stage};
+use polymarket_bot::kill_switch::{self, KillSwitch, KillSwitchError};
+use polymarket_bot::fees::FeeModel;
+use polymarket_bot::gas::wei_to_pol;
+use polymarket_bot::reporting;
+use polymarket_bot::logging;
+use polymarket_bot::balances;
+use polymarket_bot::copy_trader;
+use polymarket_bot::dashboard::{self, Dashboard, RecentOpportunities, StreamHealth};
+use polymarket_bot::runtime_control::RuntimeControl;
+use polymarket_bot::fill_ingest::FillStore;
+use polymarket_bot::fill_stream::FillStream;
+use polymarket_bot::redemption;
+use polymarket_bot::rest_client;
+use polymarket_bot::rate_limit;
+use polymarket_bot::topic_classifier;
+use polymarket_bot::resolution_watcher::ResolutionWatcher;
+use polymarket_bot::submission::{PrivateRelay, SubmissionBackend};
+use polymarket_bot::wallet_pool::ExecutorPool;
+use polymarket_bot::notifier::{self, Alert, DiscordBackend, Notifier, NotifierHandle, TelegramBackend, WebhookBackend};
+use polymarket_bot::order_lifecycle::{run_reconciliation, run_stream_confirmation, OrderTracker, SharedOrderTracker, UserChannelSync};
+use polymarket_bot::metrics::{self, Control, EXECUTION_LATENCY, SIGNAL_BLOCKED, OPEN_POSITIONS, OPPORTUNITIES, EXPECTED_PNL, RECONNECTS};
+use rust_decimal::prelude::ToPrimitive;
 use dotenv::dotenv;
 use std::env;
-use rust_decimal_macros::dec;
+use std::path::Path;
+use rust_decimal::Decimal;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use std::collections::HashMap;
+use std::time::Instant;
 use tokio::time::{sleep, Duration};
+use tracing::{error, info, info_span, warn, Instrument};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok(); 
+async fn main() -> Result<(), BotError> {
+    dotenv().ok();
+    let cli = match Cli::parse(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(CliError::Help(usage)) => {
+            println!("{}", usage);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let mut config = BotConfig::load_from(cli.config.as_deref())?;
+    logging::init(config.log_format()?, config.log_filter()?);
+    rest_client::configure_gamma(config.gamma_client());
+    rate_limit::configure(config.clob_budgets());
+
+    match cli.command {
+        Command::Run(mode) => run(config, mode).await,
+        Command::Record { dir } => {
+            if dir.is_some() {
+                config.execution.record_dir = dir;
+            }
+            if config.execution.record_dir.is_none() {
+                return Err(ConfigError::Invalid("record needs --dir or RECORD_DIR".to_string()).into());
+            }
+            run(config, Mode::Scan).await
+        }
+        Command::Backtest(args) => backtest(&config, args).await,
+        Command::AnalyzeWallets(args) => analyze_wallets(&config, args).await,
+        Command::Rearm { addr } => rearm(&config, addr).await,
+    }
+}
+
+/// Re-arms the running bot's kill switch, or clears the persisted halt when no bot answers.
+async fn rearm(config: &BotConfig, addr: Option<String>) -> Result<(), BotError> {
+    if let Some(addr) = addr.or_else(|| config.endpoints.metrics_addr.clone()) {
+        match kill_switch::rearm_remote(&addr, env::var("CONTROL_API_TOKEN").ok().as_deref()).await {
+            Ok(reply) => {
+                println!("{}", reply);
+                return Ok(());
+            }
+            Err(KillSwitchError::Http(e)) if e.is_connect() => println!("No bot listening on {}, clearing the persisted halt", addr),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let path = config.kill_switch_config().path;
+    match kill_switch::clear(&path) {
+        Ok(true) => println!("Removed {}; the next start trades", path.display()),
+        Ok(false) => println!("Not halted"),
+        Err(source) => return Err(KillSwitchError::Io { path: path.display().to_string(), source }.into()),
+    }
+    Ok(())
+}
+
+/// Replays a recording against the current market set and prints the simulated report.
+async fn backtest(config: &BotConfig, args: BacktestArgs) -> Result<(), BotError> {
+    let mut fill_model = FillModel::default();
+    for (field, arg) in [(&mut fill_model.trade_size, args.trade_size), (&mut fill_model.fill_ratio, args.fill_ratio), (&mut fill_model.slippage, args.slippage)] {
+        if let Some(value) = arg {
+            *field = value;
+        }
+    }
+
+    let mut updates = load_updates(&args.recording)?;
+    updates.retain(|u| args.from.is_none_or(|from| u.timestamp_ms >= from) && args.to.is_none_or(|to| u.timestamp_ms < to));
+    println!("Loaded {} recorded updates from {}", updates.len(), args.recording.display());
 
-    println!("Fetching markets from Polymarket...");
-    let mut markets = fetch_markets().await?;
-    println!("Fetched {} markets. Normalizing...", markets.len());
+    let mut markets = fetch_markets(&config.market_filter()?).await?;
+    normalize_markets(&mut markets);
+    let state = MarketState::build(markets, config.thresholds.similarity_cutoff, Arc::new(config.pattern_registry()?));
+    println!("Replaying against {} markets ({} related pairs)...", state.markets.len(), state.dependency_graph.related_markets.len());
+
+    let report = Backtester::new(state, config.strategy_registry()?, fill_model).run(updates);
+    println!("{}", report);
+    Ok(())
+}
+
+/// Ranks the fill store's wallets by PnL, settling resolved markets and marking open positions
+/// at Gamma's current prices.
+async fn analyze_wallets(config: &BotConfig, args: AnalyzeArgs) -> Result<(), BotError> {
+    let store = FillStore::open(args.db.as_deref().unwrap_or(&config.execution.fills_db_path))?;
+    let fills = store.fills_since(args.since.unwrap_or_default())?;
+    let copy = config.copy_config();
+    let mut tracker = WalletTracker::new(copy.window.as_secs());
+    let mut engine = PnlEngine::new();
+    for fill in &fills {
+        tracker.record(fill);
+        engine.record(fill);
+    }
+    for resolved in store.resolutions()? {
+        if let Some(market_id) = &resolved.market_id {
+            engine.resolve(market_id, &resolved.resolution.payouts);
+        }
+    }
+
+    let marks: HashMap<String, Decimal> = fetch_markets(&config.market_filter()?).await?.iter()
+        .flat_map(|m| m.conditions.iter().map(|c| (c.asset_id.clone(), c.price)))
+        .collect();
+    let ranking = engine.ranking(&marks);
+    println!("{} fills from {} wallets", fills.len(), ranking.len());
+    println!("{:<44} {:>12} {:>12} {:>12} {:>7} {:>14}  smart", "wallet", "total", "realized", "unrealized", "trades", "volume");
+    for report in ranking.iter().take(args.top) {
+        println!(
+            "{:<44} {:>12.2} {:>12.2} {:>12.2} {:>7} {:>14.2}  {}",
+            report.address, report.total(), report.realized, report.unrealized, report.trades, report.volume,
+            if tracker.is_smart(&report.address, &copy.criteria) { "yes" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// The live bot: fetches markets, streams prices and runs the detectors. Trades when `mode`
+/// allows it and wallet credentials are set.
+async fn run(config: BotConfig, mode: Mode) -> Result<(), BotError> {
+    let shutdown = ShutdownController::new();
+    shutdown.listen_for_signals();
+
+    let a = &config.alerts;
+    let mut alert_sink = Notifier::new(a.max_per_minute, a.min_opportunity_profit).with_templates(a.templates.clone());
+    if let (Some(chat_id), Ok(token)) = (&a.telegram_chat_id, env::var("TELEGRAM_BOT_TOKEN")) {
+        alert_sink = alert_sink.with_backend(TelegramBackend::new(&token, chat_id));
+    }
+    if let Some(url) = &a.discord_webhook_url {
+        alert_sink = alert_sink.with_backend(DiscordBackend::new(url));
+    }
+    if let Some(url) = &a.webhook_url {
+        alert_sink = alert_sink.with_backend(WebhookBackend::new(url));
+    }
+    let (alerts, alert_task) = if alert_sink.is_empty() {
+        (NotifierHandle::disabled(), None)
+    } else {
+        let (handle, task) = alert_sink.spawn();
+        (handle, Some(task))
+    };
+
+    // Halts execution on drawdown, window losses or error spikes until re-armed
+    let kill_switch = Arc::new(KillSwitch::new(config.kill_switch_config(), alerts.clone()));
+    // Pause, thresholds, blacklist and refreshes, changed through /rpc without a restart
+    let runtime = Arc::new(RuntimeControl::new(config.strategy_registry()?));
+    info!(enabled = ?runtime.strategies().enabled().collect::<Vec<_>>(), "strategies loaded");
+    if let Some(addr) = config.metrics_addr()? {
+        let control = Control { kill_switch: kill_switch.clone(), runtime: runtime.clone(), token: env::var("CONTROL_API_TOKEN").ok() };
+        if control.token.is_none() && !addr.ip().is_loopback() {
+            warn!(%addr, "CONTROL_API_TOKEN is unset, anyone reaching the metrics address can re-arm the kill switch and pause trading");
+        }
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr, control).await {
+                error!(error = %e, "metrics server stopped");
+            }
+        });
+    }
+
+    if config.execution.dry_run {
+        info!("DRY_RUN enabled: orders and transactions are built, signed and estimated but never broadcast");
+    }
+
+    if let Some(path) = &config.execution.entity_aliases_path {
+        entities::load_aliases(Path::new(path))?;
+        info!(%path, "loaded custom entity aliases");
+    }
+    if let Some(path) = &config.execution.topic_rules_path {
+        topic_classifier::load_rules(Path::new(path))?;
+        info!(%path, "loaded custom topic rules");
+    }
+
+    let market_filter = config.market_filter()?;
+    if !market_filter.categories.is_empty() {
+        info!(categories = ?market_filter.categories, "restricting to categories");
+    }
+    if !market_filter.min_liquidity.is_zero() || !market_filter.min_volume_24hr.is_zero() || market_filter.max_spread.is_some() {
+        info!(
+            min_liquidity = %market_filter.min_liquidity,
+            min_volume_24hr = %market_filter.min_volume_24hr,
+            max_spread = ?market_filter.max_spread,
+            "dropping illiquid markets"
+        );
+    }
+    let mut markets = fetch_markets(&market_filter).await?;
+    info!(markets = markets.len(), "fetched markets");
+    let excluded = market_filter.excluded(&markets);
+    if !excluded.is_empty() {
+        info!(excluded = excluded.len(), "tracking markets matched by the exclude rules without trading them");
+    }
     normalize_markets(&mut markets);
+    let enriched = enrich_markets(&mut markets, &config.endpoints.clob_rest_url, market_filter.paging.concurrency).await;
+    info!(enriched, "loaded CLOB trading parameters");
+
+    let mut state = MarketState::build(markets, config.thresholds.similarity_cutoff, Arc::new(config.pattern_registry()?));
+    state.excluded = excluded;
+    info!(
+        related_pairs = state.dependency_graph.related_markets.len(),
+        neg_risk_baskets = state.neg_risk_baskets.len(),
+        "dependency graph built"
+    );
+    let shared_state: SharedMarketState = Arc::new(RwLock::new(state));
     
+    // The [signer] backend holds the primary wallet; PRIVATE_KEYS adds more to the execution pool
+    let wallets: Vec<WalletSigner> = match mode {
+        Mode::Scan => Vec::new(),
+        _ => match config.signer_backend()?.open().await.map_err(ExecutionError::from)? {
+            Some(primary) => {
+                let mut wallets = vec![primary];
+                for key in env::var("PRIVATE_KEYS").unwrap_or_default().split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                    wallets.push(WalletSigner::from_key(key).map_err(ExecutionError::from)?);
+                }
+                wallets
+            }
+            None => Vec::new(),
+        },
+    };
+
+    let proxy_wallet = config.proxy_wallet()?;
+
+    // One health-checked pool over the configured RPCs, shared by execution and the chain readers
+    let rpc_pool = match config.rpc_urls() {
+        urls if urls.is_empty() => None,
+        urls => Some(ProviderPool::new(&urls, env::var("DRPC_API_KEY").ok(), config.rpc_pool_config())?),
+    };
+    let rpc_health_task = rpc_pool.as_ref().filter(|pool| pool.len() > 1).map(ProviderPool::spawn_health_checks);
+
     // Initialize Trader with dRPC support
-    let executor = if let (Ok(rpc), Ok(key)) = (env::var("POLYGON_RPC_URL"), env::var("PRIVATE_KEY")) {
-        println!("Wallet credentials found. Initializing Trade Executor...");
-        let drpc_key = env::var("DRPC_API_KEY").ok();
-        if drpc_key.is_some() {
-            println!("dRPC API Key detected. Enabling MEV-protected HFT execution path.");
+    let executor = match (&rpc_pool, wallets.is_empty()) {
+        (Some(rpc_pool), false) => {
+            info!(wallets = wallets.len(), rpc_endpoints = rpc_pool.len(), "wallet credentials found, initializing trade executor");
+            if env::var("DRPC_API_KEY").is_ok() {
+                info!("dRPC API key detected, enabling MEV-protected execution path");
+            }
+            let relay = match &config.endpoints.private_relay_url {
+                Some(url) => {
+                    let relay = PrivateRelay::new(url, env::var("PRIVATE_RELAY_AUTH").ok().as_deref())?;
+                    info!(relay = relay.name(), min_profit = %config.execution.private_min_profit, "routing profitable trades through private relay");
+                    Some(Arc::new(relay))
+                }
+                None => None,
+            };
+            let mut executors: Vec<TradeExecutor> = Vec::with_capacity(wallets.len());
+            for (i, wallet) in wallets.iter().enumerate() {
+                let mut executor = TradeExecutor::from_pool(rpc_pool.clone(), wallet.clone()).await?
+                    .with_gas_policy(config.gas_policy()?)
+                    .with_dry_run(config.execution.dry_run)
+                    .with_approval_policy(config.approval_policy())
+                    .with_planner(config.planner_config()?)
+                    .with_balance_limits(config.balance_limits());
+                // One fee oracle (and one poller) for the whole pool
+                if let Some(primary) = executors.first() {
+                    executor = executor.with_gas_oracle(primary.gas_oracle().clone());
+                }
+                if let Some(relay) = &relay {
+                    executor = executor.with_private_relay(relay.clone(), config.execution.private_min_profit);
+                }
+                if let Some(proxy) = proxy_wallet.filter(|_| i == 0) {
+                    info!(kind = ?proxy.kind, proxy = ?proxy.address, "trading through the primary wallet's proxy");
+                    executor = executor.with_proxy_wallet(proxy).await?;
+                }
+                executor.ensure_approvals().await?;
+                executors.push(executor);
+            }
+            Some(executors)
+        }
+        _ if mode == Mode::Trade => {
+            return Err(ConfigError::Invalid("trade needs POLYGON_RPC_URL and a wallet (PRIVATE_KEY or a [signer] backend)".to_string()).into());
+        }
+        _ if mode == Mode::Scan => {
+            info!("scan mode, opportunities are logged but never traded");
+            None
+        }
+        _ => {
+            info!("no wallet credentials found, running in scan-only mode");
+            None
         }
-        Some(Arc::new(TradeExecutor::new(&rpc, &key, drpc_key).await?))
-    } else {
-        println!("No wallet credentials found. Running in Scan-Only mode.");
-        None
     };
 
-    println!("Building Dependency Graph...");
-    let mut dependency_graph = DependencyGraph::default();
-    let mut market_id_to_idx = HashMap::new();
-    for (i, m) in markets.iter().enumerate() {
-        market_id_to_idx.insert(m.id.clone(), i);
+    let gas_poll = Duration::from_secs(config.execution.gas_poll_seconds);
+    let gas_task = executor.as_ref().and_then(|w| w.first()).map(|e| e.gas_oracle().spawn_poller(gas_poll));
+    let recorder = match &config.execution.record_dir {
+        Some(dir) => {
+            let recorder = StreamRecorder::new(dir).map_err(|source| ConfigError::Io { path: dir.clone(), source })?;
+            info!(%dir, "recording websocket streams");
+            Some(recorder.shared())
+        }
+        None => None,
+    };
+    let clob_client_for = || {
+        let client = ClobClient::new()
+            .with_urls(&config.endpoints.clob_ws_url, &config.endpoints.clob_rest_url)
+            .with_user_ws_url(&config.endpoints.clob_user_ws_url)
+            .with_stale_after(Duration::from_secs(config.endpoints.ws_stale_seconds))
+            .with_dry_run(config.execution.dry_run)
+            .with_markets(shared_state.clone());
+        match &recorder {
+            Some(recorder) => client.with_recorder(recorder.clone()),
+            None => client,
+        }
+    };
+
+    // Authenticated CLOB client, used to cancel resting orders on shutdown
+    let order_tracker: SharedOrderTracker = Arc::new(RwLock::new(OrderTracker::default()));
+    let trading_client = match wallets.first() {
+        Some(wallet) => match clob_client_for().with_signer(wallet.clone()).await {
+            Ok(client) => {
+                let client = client.with_order_tracker(order_tracker.clone());
+                Some(Arc::new(match proxy_wallet {
+                    Some(proxy) => client.with_proxy_wallet(proxy),
+                    None => client,
+                }))
+            }
+            Err(e) => {
+                warn!(error = %e, "CLOB authentication failed, open orders will not be cancelled on shutdown");
+                None
+            }
+        },
+        None => None,
+    };
+    // Each extra pool wallet trades under its own derived CLOB credentials
+    let mut pool_clients = Vec::new();
+    for wallet in wallets.iter().skip(1) {
+        match clob_client_for().with_derived_signer(wallet.clone()).await {
+            Ok(client) => pool_clients.push(Some(Arc::new(client))),
+            Err(e) => {
+                warn!(error = %e, "CLOB authentication failed for pool wallet, its order book legs will fail");
+                pool_clients.push(None);
+            }
+        }
     }
 
-    let mut adjacency_list: HashMap<usize, Vec<usize>> = HashMap::new();
-    let market_count = markets.len();
-    for i in 0..market_count {
-        for j in (i + 1)..market_count {
-            if are_markets_related(&markets[i], &markets[j]) {
-                dependency_graph.related_markets.push((markets[i].id.clone(), markets[j].id.clone()));
-                adjacency_list.entry(i).or_default().push(j);
-                adjacency_list.entry(j).or_default().push(i);
+    // Split/merge trades place their order book legs through each wallet's authenticated client
+    let wallet_selection = config.wallet_selection()?;
+    let order_books: SharedOrderBooks = Arc::new(RwLock::new(HashMap::new()));
+    let quoting = config.quoting_config()?;
+    let styles = config.execution_styles()?;
+    let time_in_force = config.time_in_force()?;
+    if quoting.is_some() && executor.is_some() {
+        info!("quoting Long rebalancing sets inside the spread");
+    }
+    let executor = executor.map(|wallets| {
+        let clients = std::iter::once(trading_client.clone()).chain(pool_clients.iter().cloned());
+        let wallets = wallets.into_iter().zip(clients).map(|(e, client)| {
+            let e = e.with_order_books(order_books.clone()).with_execution_styles(styles.clone()).with_time_in_force(time_in_force.clone());
+            let e = match quoting {
+                Some(quoting) => e.with_quoting(quoting),
+                None => e,
+            };
+            match client {
+                Some(client) => Arc::new(e.with_clob(client)),
+                None => Arc::new(e),
             }
+        });
+        Arc::new(ExecutorPool::new(wallets.collect(), wallet_selection))
+    });
+
+    // Realtime OrderFilled logs for fill confirmation and the copy-trading follower
+    let fill_stream = config.endpoints.polygon_ws_url.as_deref()
+        .map(|url| FillStream::new(url, env::var("DRPC_API_KEY").ok().as_deref()))
+        .transpose()?;
+    let confirm_task = match (&fill_stream, &trading_client) {
+        (Some(stream), Some(_)) => Some(tokio::spawn(run_stream_confirmation(order_tracker.clone(), stream.subscribe()))),
+        _ => None,
+    };
+
+    // Follows placed CLOB orders to Filled/Cancelled/Expired using chain logs and order status
+    let reconcile_task = match (&trading_client, &rpc_pool) {
+        (Some(client), Some(rpc_pool)) => {
+            let collector = BlockchainCollector::from_pool(rpc_pool.clone())?;
+            let ttl = Duration::from_secs(config.execution.order_ttl_seconds);
+            Some(tokio::spawn(run_reconciliation(order_tracker.clone(), collector, client.clone(), ttl)))
         }
+        _ => None,
+    };
+
+    let stream_health = Arc::new(StreamHealth::default());
+    // Our own order and trade events, pushed as they happen
+    if let Some(client) = trading_client.clone() {
+        let user_shutdown = shutdown.clone();
+        let health = stream_health.clone();
+        let mut sync = UserChannelSync { tracker: order_tracker.clone() };
+        tokio::spawn(async move {
+            let mut reconnect_delay = 2;
+            while !user_shutdown.is_triggered() {
+                health.connecting("user");
+                let result = tokio::select! {
+                    res = client.stream_user(Vec::new(), &mut sync) => res,
+                    _ = user_shutdown.wait() => break,
+                };
+                if let Err(e) = &result {
+                    health.dropped("user", &e.to_string());
+                }
+                match result {
+                    Err(e) if !e.is_recoverable() => {
+                        warn!(error = %e, "user channel unavailable, relying on reconciliation polling");
+                        break;
+                    }
+                    Err(e) => {
+                        warn!(topic = "user", error = %e, retry_in_secs = reconnect_delay, "websocket error, reconnecting");
+                        RECONNECTS.with_label_values(&["user"]).inc();
+                        tokio::select! {
+                            _ = sleep(Duration::from_secs(reconnect_delay)) => {}
+                            _ = user_shutdown.wait() => break,
+                        }
+                        reconnect_delay = std::cmp::min(reconnect_delay * 2, 60);
+                    }
+                    Ok(()) => reconnect_delay = 2,
+                }
+            }
+        });
     }
-    
-    println!("Found {} related market pairs.", dependency_graph.related_markets.len());
 
-    let mut asset_map = HashMap::new();
-    let mut asset_ids = Vec::new();
-    for (m_idx, market) in markets.iter().enumerate() {
-        for (c_idx, condition) in market.conditions.iter().enumerate() {
-            if !condition.asset_id.is_empty() {
-                asset_map.insert(condition.asset_id.clone(), (m_idx, c_idx));
-                asset_ids.push(condition.asset_id.clone());
+    // Retries transient RPC failures and pauses trading after repeated ones
+    let shared_executor = executor.map(|e| Arc::new(GuardedExecutor::new(e, config.execution_guard().with_kill_switch(kill_switch.clone()))));
+    let sizer = Arc::new(PositionSizer::new(config.sizing_config()?));
+    let exposure = Arc::new(RwLock::new(Exposure::default()));
+    let positions: SharedPositions = Arc::new(RwLock::new(PositionTracker::default()));
+    if let Some(e) = &shared_executor {
+        let state = shared_state.read().await;
+        let mut tracker = positions.write().await;
+        match tracker.sync(e.executor(), &state, &state.asset_ids()).await {
+            Ok(()) => {
+                *exposure.write().await = tracker.exposure(&state);
+                OPEN_POSITIONS.set(tracker.holdings().count() as i64);
+                info!(assets_held = tracker.holdings().count(), "synced on-chain positions");
             }
+            Err(e) => warn!(error = %e, "position sync failed, starting with empty exposure"),
         }
     }
+    let db_path = &config.execution.opportunity_db_path;
+    let store = Arc::new(OpportunityStore::open(db_path)?);
+    info!(%db_path, "logging opportunities");
+    let recent = Arc::new(RecentOpportunities::default());
+    if let Some(addr) = config.dashboard_addr()? {
+        let dashboard = Dashboard {
+            state: shared_state.clone(),
+            positions: positions.clone(),
+            orders: order_tracker.clone(),
+            opportunities: recent.clone(),
+            health: stream_health.clone(),
+            kill_switch: kill_switch.clone(),
+            started_at: chrono::Utc::now(),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = dashboard::serve(addr, dashboard).await {
+                error!(error = %e, "dashboard stopped");
+            }
+        });
+    }
+    let bankroll = config.sizing.bankroll;
+    let fees = Arc::new(config.fee_model());
+    let gate = Arc::new(OpportunityGate::new(Duration::from_secs(config.execution.opportunity_cooldown_seconds)));
+    // Cached USDC/POL balances let the executor refuse trades that would revert
+    let balance_poll = Duration::from_secs(config.execution.balance_poll_seconds);
+    let balance_task = shared_executor.clone().map(|e| balances::spawn_poller(e, balance_poll, alerts.clone()));
+    let summary_task = alert_task.is_some().then(|| notifier::spawn_daily_summary(alerts.clone(), store.clone(), config.alerts.daily_summary_hour_utc));
+    let report = config.report_config()?;
+    let report_task = (shared_executor.is_some() && (report.dir.is_some() || report.push))
+        .then(|| reporting::spawn_daily_report(report, store.clone(), positions.clone(), shared_state.clone(), alerts.clone()));
+
+    // Smart-money follower, primed with the window's history from the ingest_fills store
+    let copy_task = match (config.copy_trading.enabled, &rpc_pool) {
+        (true, Some(rpc_pool)) => {
+            let copy = config.copy_config();
+            let fills_db = &config.execution.fills_db_path;
+            let history = if Path::new(fills_db).exists() {
+                let since = chrono::Utc::now().timestamp().max(0) as u64;
+                FillStore::open(fills_db).and_then(|s| s.fills_since(since.saturating_sub(copy.window.as_secs()))).unwrap_or_else(|e| {
+                    warn!(error = %e, %fills_db, "failed to load fill history, follower starts cold");
+                    Vec::new()
+                })
+            } else {
+                Vec::new()
+            };
+            let collector = Arc::new(BlockchainCollector::from_pool(rpc_pool.clone())?);
+            let executor = shared_executor.clone().filter(|_| copy.mirror);
+            let live = fill_stream.as_ref().map(FillStream::subscribe);
+            Some(copy_trader::spawn_follower(collector, live, history, shared_state.clone(), executor, exposure.clone(), copy, alerts.clone()))
+        }
+        (true, None) => {
+            warn!("copy trading needs POLYGON_RPC_URL, follower disabled");
+            None
+        }
+        (false, _) => None,
+    };
+    let fill_stream_task = fill_stream.map(FillStream::spawn);
+
+    // Asset changes for the open price and book connections
+    let (price_sub_tx, mut price_sub_rx) = mpsc::unbounded_channel();
+    let (book_sub_tx, mut book_sub_rx) = mpsc::unbounded_channel();
+    let subscribers = vec![price_sub_tx, book_sub_tx];
+
+    // Drops markets as the oracle resolves them, keeps their outcomes as backtest labels and
+    // redeems winning tokens
+    let mut redeem_task = None;
+    let resolution_task = match &rpc_pool {
+        Some(rpc_pool) => {
+            let collector = Arc::new(BlockchainCollector::from_pool(rpc_pool.clone())?);
+            let fills = FillStore::open(&config.execution.fills_db_path)?;
+            let wallets = shared_executor.as_ref().map(|e| e.executor().wallets().iter().map(|w| w.address()).collect()).unwrap_or_default();
+            let mut watcher = ResolutionWatcher::new(collector, fills, shared_state.clone(), wallets, config.ingest_config()).with_subscribers(subscribers.clone());
+            if let (true, Some(e)) = (config.execution.auto_redeem, &shared_executor) {
+                let (redeem_tx, redeem_rx) = mpsc::unbounded_channel();
+                let retry = Duration::from_secs(config.execution.redeem_retry_seconds);
+                redeem_task = Some(redemption::spawn_redeemer(e.clone(), redeem_rx, retry, alerts.clone()));
+                watcher = watcher.with_redeemer(redeem_tx);
+            }
+            Some(watcher.spawn(Duration::from_secs(config.execution.resolution_poll_seconds), alerts.clone()))
+        }
+        None => None,
+    };
 
-    let shared_markets = Arc::new(RwLock::new(markets));
-    let shared_asset_map = Arc::new(asset_map);
-    let shared_adjacency = Arc::new(adjacency_list);
-    let shared_executor = executor;
+    // L2 books are maintained on their own connection so depth is available to the engine
+    let signals: SharedSignals = Arc::new(Signals::new(config.signal_config()));
+    let books = order_books.clone();
+    let book_signals = signals.clone();
+    let book_state = shared_state.clone();
+    let book_shutdown = shutdown.clone();
+    let book_client = clob_client_for();
+    let book_health = stream_health.clone();
+    tokio::spawn(async move {
+        let book_client = book_client;
+        let mut reconnect_delay = 2;
+        while !book_shutdown.is_triggered() {
+            let book_ids = book_state.read().await.asset_ids();
+            book_health.connecting("book");
+            let result = tokio::select! {
+                res = book_client.stream_books(book_ids, &mut book_sub_rx, books.clone(), book_signals.clone()) => res,
+                _ = book_shutdown.wait() => break,
+            };
+            if let Err(e) = result {
+                book_health.dropped("book", &e.to_string());
+                warn!(topic = "book", error = %e, retry_in_secs = reconnect_delay, "websocket error, reconnecting");
+                RECONNECTS.with_label_values(&["book"]).inc();
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(reconnect_delay)) => {}
+                    _ = book_shutdown.wait() => break,
+                }
+                reconnect_delay = std::cmp::min(reconnect_delay * 2, 60);
+            }
+        }
+    });
+
+    // Periodically pick up new markets and drop resolved ones
+    let refresh_minutes = config.execution.market_refresh_minutes;
+    let refresh_task = tokio::spawn(run_refresh_loop(
+        shared_state.clone(),
+        Duration::from_secs(refresh_minutes * 60),
+        runtime.refresh_trigger(),
+        market_filter,
+        config.endpoints.clob_rest_url.clone(),
+        subscribers.clone(),
+    ));
 
-    println!("--- ENTERING FERRARI MODE (WebSocket Streaming) ---");
-    let clob_client = ClobClient::new();
+    // Markets past their end date leave the live set; directional holdings are unwound ahead
+    // of it when configured
+    let expiry = config.expiry_policy();
+    let mut expiry_watcher = ExpiryWatcher::new(expiry.clone(), shared_state.clone(), subscribers);
+    if let Some(e) = &shared_executor {
+        expiry_watcher = expiry_watcher.with_flattening(e.clone(), positions.clone(), exposure.clone(), alerts.clone());
+    }
+    let expiry_task = expiry_watcher.spawn(Duration::from_secs(config.execution.expiry_poll_seconds));
+
+    info!("entering websocket streaming mode");
+    let clob_client = clob_client_for();
     let mut reconnect_delay = 2; 
 
-    loop {
-        let markets_lock = shared_markets.clone();
-        let asset_map = shared_asset_map.clone();
-        let adjacency = shared_adjacency.clone();
-        let exec = shared_executor.clone();
-        let ids = asset_ids.clone();
-
-        let callback = move |update: polymarket_bot::clob_client::PriceUpdate| {
-            let markets_lock = markets_lock.clone();
-            let asset_map = asset_map.clone();
-            let adjacency = adjacency.clone();
-            let exec = exec.clone();
-
-            async move {
-                if let Some(&(m_idx, c_idx)) = asset_map.get(&update.asset_id) {
-                    let mut markets = markets_lock.write().await;
-                    markets[m_idx].conditions[c_idx].price = update.price;
-                    
-                    if let Some(op) = check_rebalancing(&markets[m_idx]) {
-                        println!("⚡ [HFT] Rebalancing Opp: {} Profit: {}", op.market_id, op.profit);
-                        if let Some(e) = &exec { let _ = e.execute_rebalancing(&op.market_id, dec!(100)).await; }
-                    }
+    // Detections are queued and executed best margin first, at most one per market at a time
+    let scheduler = Arc::new(Scheduler::new(config.scheduler_config()));
+    let pol_usd = config.gas_policy()?.pol_usd;
+    let categories = Arc::new(config.category_params()?);
+    let dispatch_task = shared_executor.clone().map(|executor| {
+        let dispatcher = Dispatcher {
+            executor,
+            sizer: sizer.clone(),
+            exposure: exposure.clone(),
+            positions: positions.clone(),
+            state: shared_state.clone(),
+            store: store.clone(),
+            gate: gate.clone(),
+            alerts: alerts.clone(),
+            fees: fees.clone(),
+            pol_usd,
+            kill_switch: kill_switch.clone(),
+            runtime: runtime.clone(),
+            expiry,
+            categories: categories.clone(),
+            books: order_books.clone(),
+            signals: signals.clone(),
+            bankroll,
+        };
+        tokio::spawn(dispatcher.run(scheduler.clone()))
+    });
+    let scheduler = shared_executor.is_some().then_some(scheduler);
 
-                    if let Some(related_indices) = adjacency.get(&m_idx) {
-                        for &r_idx in related_indices {
-                            let ops = check_combinatorial_pair(&markets[m_idx], &markets[r_idx]);
-                            for op in ops {
-                                println!("⚡ [HFT] Combinatorial Opp: {} <-> {} Profit: {}", op.market_id_1, op.market_id_2, op.profit);
-                                if let Some(e) = &exec { let _ = e.execute_combinatorial(&op.market_id_1, &op.market_id_2, dec!(100)).await; }
-                            }
-                        }
-                    }
+    // Runs every detector against a tick and queues what it finds
+    let state_lock = shared_state.clone();
+    let update_store = store.clone();
+    let order_books = order_books.clone();
+    let update_alerts = alerts.clone();
+    let update_recent = recent.clone();
+
+    let evaluate = move |update: polymarket_bot::clob_client::PriceUpdate| {
+        let state_lock = state_lock.clone();
+        let scheduler = scheduler.clone();
+        let store = update_store.clone();
+        let order_books = order_books.clone();
+        let runtime = runtime.clone();
+        let alerts = update_alerts.clone();
+        let recent = update_recent.clone();
+        let span = info_span!("price_update", asset_id = %update.asset_id, price = %update.price);
+
+        async move {
+            let started = Instant::now();
+            latency::record(Stage::Queue, started.duration_since(update.received_at));
+            // A read lock plus this asset's price shard, so ticks on other markets evaluate alongside
+            let state = state_lock.read().await;
+            if let Some(id) = state.markets.resolve(&update.asset_id) {
+                let now = chrono::Utc::now();
+                state.set_price(&update.asset_id, update.price, now);
+                let Some(market) = state.priced(id.market) else { return };
+
+                let books = order_books.read().await;
+                let view = MarketView { state: &state, id: id.market, market: &market, asset_id: &update.asset_id, books: &books, now };
+                for opportunity in runtime.evaluate(&view) {
+                    let record = announce(&opportunity, &store, &alerts, &recent);
+                    enqueue(&scheduler, opportunity, record, update.received_at);
                 }
             }
+            latency::record(Stage::Evaluate, started.elapsed());
+        }
+        .instrument(span)
+    };
+
+    // Bursts of ticks on one asset run the engine once, at the latest price
+    let coalescer = Arc::new(Coalescer::new(Duration::from_millis(config.execution.coalesce_window_ms)));
+    let coalesce_task = tokio::spawn(coalescer.clone().run(evaluate));
+
+    let mut reconnecting = false;
+    while !shutdown.is_triggered() {
+        let ids = shared_state.read().await.asset_ids();
+        // Prices may have moved while the stream was down; catch up before evaluating again.
+        // Books need no backfill, the book channel pushes snapshots on resubscribe.
+        if reconnecting {
+            match clob_client.fetch_prices(&ids).await {
+                Ok(prices) => {
+                    let updated = shared_state.read().await.apply_prices(&prices);
+                    info!(assets = ids.len(), updated, "backfilled prices after reconnect");
+                }
+                Err(e) => warn!(error = %e, "price backfill failed, resuming from last streamed prices"),
+            }
+        }
+        reconnecting = true;
+        stream_health.connecting("prices");
+        let callback = |update| {
+            stream_health.message("prices");
+            coalescer.offer(update);
+            std::future::ready(())
         };
 
-        match clob_client.stream_prices(ids, callback).await {
+        let result = tokio::select! {
+            res = clob_client.stream_prices(ids, &mut price_sub_rx, callback) => res,
+            _ = shutdown.wait() => break,
+        };
+
+        if let Err(e) = &result {
+            stream_health.dropped("prices", &e.to_string());
+        }
+        match result {
             Ok(_) => {
-                println!("WebSocket stream finished normally.");
+                info!(topic = "prices", "websocket stream finished normally");
                 reconnect_delay = 2; 
             }
+            Err(e) if !e.is_recoverable() => {
+                error!(topic = "prices", error = %e, "fatal websocket error");
+                alerts.notify(Alert::Error { context: "price stream".to_string(), message: e.to_string() });
+                shutdown.trigger();
+                break;
+            }
             Err(e) => {
-                eprintln!("WebSocket Error: {}. Reconnecting in {}s...", e, reconnect_delay);
-                sleep(Duration::from_secs(reconnect_delay)).await;
+                warn!(topic = "prices", error = %e, retry_in_secs = reconnect_delay, "websocket error, reconnecting");
+                RECONNECTS.with_label_values(&["prices"]).inc();
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(reconnect_delay)) => {}
+                    _ = shutdown.wait() => break,
+                }
                 reconnect_delay = std::cmp::min(reconnect_delay * 2, 60);
             }
         }
     }
-}
\ No newline at end of file
+
+    // --- Shutdown: cancel resting orders, optionally flatten, flush state ---
+    refresh_task.abort();
+    for task in rpc_health_task.iter().chain(gas_task.iter()).chain(reconcile_task.iter()).chain(summary_task.iter()).chain(report_task.iter()).chain(balance_task.iter()).chain(copy_task.iter()).chain(confirm_task.iter()).chain(fill_stream_task.iter()).chain(resolution_task.iter()).chain(redeem_task.iter()).chain(dispatch_task.iter()).chain([&coalesce_task, &expiry_task]) {
+        task.abort();
+    }
+    for client in trading_client.iter().chain(pool_clients.iter().flatten()) {
+        if let Err(e) = client.cancel_all().await {
+            error!(error = %e, "failed to cancel open orders");
+            alerts.notify(Alert::Error { context: "shutdown cancel-all".to_string(), message: e.to_string() });
+        }
+    }
+    // The book task may still hold a handle, so close the segment explicitly
+    if let Some(recorder) = &recorder {
+        if let Err(e) = recorder.lock().map(|mut r| r.finish()).unwrap_or(Ok(())) {
+            warn!(error = %e, "failed to close recording");
+        }
+    }
+
+    let flatten = config.execution.flatten_on_shutdown;
+    if let (true, Some(e)) = (flatten, &shared_executor) {
+        // Unwind what the wallet actually holds, not what we think we sent
+        let state = shared_state.read().await;
+        let mut tracker = positions.write().await;
+        if let Err(err) = tracker.sync(e.executor(), &state, &state.asset_ids()).await {
+            warn!(error = %err, "position sync failed, flattening tracked exposure");
+        } else {
+            *exposure.write().await = tracker.exposure(&state);
+        }
+        for market in state.markets.iter().map(|(_, m)| m) {
+            let held = tracker.held_in(market);
+            if held.is_empty() {
+                continue;
+            }
+            if let Err(err) = e.flatten_position(market, &held).await {
+                error!(market_id = %market.id, error = %err, "failed to flatten position");
+                alerts.notify(Alert::Error { context: format!("flatten {}", market.id), message: err.to_string() });
+            }
+        }
+    }
+
+    if let Ok(rates) = store.hit_rates() {
+        for rate in rates {
+            info!(kind = %rate.kind, detected = rate.detected, acted_on = rate.acted_on, "session summary");
+        }
+    }
+    // Let queued alerts (e.g. shutdown errors) go out before the runtime stops
+    drop(alerts);
+    if let Some(task) = alert_task {
+        let _ = tokio::time::timeout(Duration::from_secs(5), task).await;
+    }
+    info!("shutdown complete");
+    Ok(())
+}
+
+/// Logs, alerts on, counts and stores a detected opportunity, and shows it on the dashboard.
+fn announce(opportunity: &Opportunity, store: &OpportunityStore, alerts: &NotifierHandle, recent: &RecentOpportunities) -> Result<i64, StoreError> {
+    let meta = opportunity.meta();
+    OPPORTUNITIES.with_label_values(&[meta.strategy]).inc();
+    info!(
+        id = meta.id, strategy = meta.strategy, markets = %opportunity.markets().join(", "), size = %meta.size,
+        gross_profit = %meta.gross_profit, net_profit = %meta.net_profit, "opportunity detected"
+    );
+    alerts.notify(Alert::opportunity(opportunity));
+    recent.push(opportunity);
+    store.record(opportunity, false)
+}
+
+/// Logs a failed store write and hands the detection to the scheduler when trading is enabled.
+/// Multi-leg chains are only recorded, since there is no execution for them yet.
+fn enqueue(scheduler: &Option<Arc<Scheduler>>, opportunity: Opportunity, record: Result<i64, StoreError>, received_at: Instant) {
+    latency::record(Stage::TickToDecision, received_at.elapsed());
+    let record_id = record.inspect_err(|e| warn!(error = %e, "failed to log opportunity")).ok();
+    match scheduler {
+        Some(_) if opportunity.kind() == OpportunityKind::MultiLeg => {}
+        Some(scheduler) => scheduler.push(opportunity, record_id),
+        None => {}
+    }
+}
+
+/// Runs scheduled opportunities: sizes each against the remaining bankroll, executes it and
+/// re-reads the traded positions from chain.
+struct Dispatcher {
+    executor: Arc<GuardedExecutor>,
+    sizer: Arc<PositionSizer>,
+    exposure: Arc<RwLock<Exposure>>,
+    positions: SharedPositions,
+    state: SharedMarketState,
+    store: Arc<OpportunityStore>,
+    gate: Arc<OpportunityGate>,
+    alerts: NotifierHandle,
+    fees: Arc<FeeModel>,
+    /// Prices the gas paid by each execution for the daily report.
+    pol_usd: Decimal,
+    kill_switch: Arc<KillSwitch>,
+    runtime: Arc<RuntimeControl>,
+    /// Keeps new positions out of markets about to end.
+    expiry: ExpiryPolicy,
+    /// Per-category cooldowns for the opportunity gate.
+    categories: Arc<CategoryParams>,
+    books: SharedOrderBooks,
+    signals: SharedSignals,
+    bankroll: Decimal,
+}
+
+impl Dispatcher {
+    async fn run(self, scheduler: Arc<Scheduler>) {
+        let dispatcher = Arc::new(self);
+        loop {
+            let ticket = scheduler.next().await;
+            let span = info_span!(
+                "execution", id = ticket.opportunity.meta().id, kind = ticket.opportunity.kind().as_str(),
+                market_id = %ticket.opportunity.exposure_market(),
+                waited_ms = ticket.waited.as_millis() as u64
+            );
+            latency::record(Stage::Dispatch, ticket.waited);
+            let dispatcher = dispatcher.clone();
+            tokio::spawn(async move { dispatcher.execute(ticket).await }.instrument(span));
+        }
+    }
+
+    async fn execute(&self, ticket: Ticket) {
+        let opportunity = &ticket.opportunity;
+        let kind = opportunity.kind();
+        if self.executor.is_paused() || !self.runtime.allows(opportunity) {
+            return;
+        }
+        let market_id = opportunity.exposure_market();
+        let margin = opportunity.margin();
+        let groups = {
+            let state = self.state.read().await;
+            let now = chrono::Utc::now();
+            let mut traded = opportunity.markets().into_iter().filter_map(|id| state.markets.handle(&id).and_then(|h| state.markets.get(h)));
+            if let Some(closing) = traded.find(|m| self.expiry.blocks_entry(m, now)) {
+                info!(market_id = %closing.id, end_date = %closing.end_date, "market ends inside the entry cutoff, skipping");
+                return;
+            }
+            match state.markets.handle(market_id).and_then(|id| state.markets.get(id)) {
+                Some(market) => ExposureGroups::of(market),
+                None => return,
+            }
+        };
+        let cooldown = self.categories.cooldown(self.gate.cooldown(), &groups.category);
+        let Some(_claim) = self.gate.try_begin(opportunity.gate_key(), kind, cooldown) else { return };
+        // Legs that rest on the book are held back while the flow runs against them
+        if self.signals.config().gates() {
+            let legs = opportunity.resting_legs();
+            if !legs.is_empty() {
+                if let Some(reason) = self.signals.adverse(&legs, &*self.books.read().await, Instant::now()) {
+                    info!(%reason, "order flow against a resting leg, skipping");
+                    SIGNAL_BLOCKED.with_label_values(&[kind.as_str()]).inc();
+                    return;
+                }
+            }
+        }
+        let mut exposure = self.exposure.write().await;
+        let amount = self.sizer.size(market_id, &groups, margin, self.bankroll - exposure.total, &exposure);
+        let amount = opportunity.max_amount().map_or(amount, |max| amount.min(max));
+        if amount.is_zero() {
+            return;
+        }
+        // Reserved up front so executions running alongside size against what is left
+        exposure.record(market_id, &groups, amount);
+        drop(exposure);
+
+        let timer = EXECUTION_LATENCY.with_label_values(&[kind.as_str()]).start_timer();
+        let result = self.executor.execute(opportunity, amount).await;
+        drop(timer);
+        let mut execution = ExecutionRecord {
+            executed_at: chrono::Utc::now(),
+            opportunity_id: ticket.record_id,
+            kind: kind.as_str().to_string(),
+            market_id: market_id.to_string(),
+            amount,
+            expected_pnl: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            gas: Decimal::ZERO,
+            succeeded: false,
+        };
+        let receipt = match result {
+            Ok(receipt) => receipt,
+            Err(_) => {
+                self.exposure.write().await.release(market_id, &groups, amount);
+                self.record_execution(&execution);
+                return;
+            }
+        };
+
+        let expected_pnl = opportunity.expected_profit(amount);
+        execution.expected_pnl = expected_pnl;
+        execution.fees = opportunity.fees(&self.fees, amount);
+        execution.gas = receipt.gas_used.zip(receipt.effective_gas_price)
+            .map_or(Decimal::ZERO, |(used, price)| wei_to_pol(used.saturating_mul(price)) * self.pol_usd);
+        execution.succeeded = true;
+        self.record_execution(&execution);
+        self.kill_switch.record_pnl(tokio::time::Instant::now(), expected_pnl - execution.gas);
+        EXPECTED_PNL.add(expected_pnl.to_f64().unwrap_or_default());
+        self.alerts.notify(Alert::Execution { strategy: kind.as_str(), market_id: market_id.to_string(), amount, expected_pnl });
+        if let Some(id) = ticket.record_id {
+            if let Err(e) = self.store.mark_acted_on(id) {
+                warn!(error = %e, "failed to mark opportunity acted on");
+            }
+        }
+
+        let traded = opportunity.markets();
+        let state = self.state.read().await;
+        let touched: Vec<String> = state.markets.iter()
+            .map(|(_, m)| m)
+            .filter(|m| traded.contains(&m.id))
+            .flat_map(|m| m.conditions.iter().map(|c| c.asset_id.clone()))
+            .collect();
+        let mut tracker = self.positions.write().await;
+        match tracker.sync(self.executor.executor(), &state, &touched).await {
+            Ok(()) => {
+                *self.exposure.write().await = tracker.exposure(&state);
+                OPEN_POSITIONS.set(tracker.holdings().count() as i64);
+                self.kill_switch.mark(tracker.unrealized_pnl(&state));
+            }
+            Err(err) => warn!(error = %err, "position sync failed"),
+        }
+    }
+
+    fn record_execution(&self, execution: &ExecutionRecord) {
+        if let Err(e) = self.store.record_execution(execution) {
+            warn!(error = %e, "failed to log execution");
+        }
+    }
+}
@@ -1,11 +1,43 @@
 use serde::Deserialize;
-use crate::shared_types::{Market, Condition};
+use crate::outcomes::parse_outcome;
+use crate::shared_types::{Condition, Event, Market, OutcomeKind, ResolutionRules, DEFAULT_TICK_SIZE};
+use crate::fees::FeeSchedule;
+use crate::rest_client::{gamma, is_transient};
+use crate::market_rules::MarketRule;
+use crate::topic_classifier::{MarketCategory, TopicClassifier};
 use rust_decimal::Decimal;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use futures::future::try_join_all;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
 use std::env;
+use std::future::Future;
+use std::str::FromStr;
+use url::Url;
+use thiserror::Error;
+use tracing::{debug, instrument, warn};
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("Gamma API request failed: {0}")]
+    Http(#[from] reqwest::Error),
+}
+
+impl FetchError {
+    /// Timeouts, connection failures, 429s and 5xx responses are worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FetchError::Http(e) => is_transient(e),
+        }
+    }
+}
 
 #[derive(Deserialize, Debug)]
 struct ApiEvent {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    title: String,
     #[serde(rename = "endDate")]
     end_date: Option<String>,
     #[serde(default)]
@@ -22,87 +54,451 @@ struct ApiTag {
 #[derive(Deserialize, Debug)]
 struct ApiMarket {
     id: String,
+    #[serde(default)]
     question: String,
     #[serde(rename = "negRiskMarketID")]
     neg_risk_market_id: Option<String>,
-    outcomes: Option<String>, // Often a JSON string like "["Yes", "No"]"
-    #[serde(rename = "outcomePrices")]
-    outcome_prices: Option<String>, // Often a JSON string like "["0.5", "0.5"]"
-    #[serde(rename = "clobTokenIds")]
-    clob_token_ids: Option<String>, // JSON string of token addresses
-}
-
-pub async fn fetch_markets() -> Result<Vec<Market>, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let api_url = env::var("POLY_MARKET_API_URL").unwrap_or_else(|_| "https://gamma-api.polymarket.com/events?closed=false&limit=50".to_string());
-    // Fetching open events with a limit to avoid too much data initially
-    // Using a user-agent is often good practice
-    let events: Vec<ApiEvent> = client.get(&api_url)
-        .header("User-Agent", "PolymarketArbitrageBot/1.0")
-        .send()
-        .await?
-        .json()
-        .await?;
+    #[serde(rename = "endDate")]
+    end_date: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    outcomes: Vec<String>, // Often a JSON string like "["Yes", "No"]"
+    #[serde(rename = "outcomePrices", default, deserialize_with = "lenient_list")]
+    outcome_prices: Vec<String>, // Often a JSON string like "["0.5", "0.5"]"
+    #[serde(rename = "clobTokenIds", default, deserialize_with = "lenient_list")]
+    clob_token_ids: Vec<String>, // JSON string of token addresses
+    #[serde(rename = "liquidityNum", default, deserialize_with = "lenient_decimal")]
+    liquidity: Option<Decimal>,
+    #[serde(rename = "volume24hr", default, deserialize_with = "lenient_decimal")]
+    volume_24hr: Option<Decimal>,
+    #[serde(default, deserialize_with = "lenient_decimal")]
+    spread: Option<Decimal>,
+    #[serde(rename = "conditionId", default)]
+    condition_id: String,
+    #[serde(rename = "questionID", default)]
+    question_id: String,
+    #[serde(rename = "orderPriceMinTickSize", default, deserialize_with = "lenient_decimal")]
+    tick_size: Option<Decimal>,
+    #[serde(rename = "orderMinSize", default, deserialize_with = "lenient_decimal")]
+    min_order_size: Option<Decimal>,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "resolutionSource", default)]
+    resolution_source: String,
+    #[serde(rename = "resolvedBy", default)]
+    resolved_by: String,
+}
 
-    let mut markets = Vec::new();
+/// Trading parameters from the CLOB's `/markets/{condition_id}`.
+#[derive(Deserialize, Debug)]
+struct ClobMarket {
+    #[serde(default)]
+    question_id: String,
+    #[serde(default, deserialize_with = "lenient_decimal")]
+    minimum_order_size: Option<Decimal>,
+    #[serde(default, deserialize_with = "lenient_decimal")]
+    minimum_tick_size: Option<Decimal>,
+    /// Base fee rates in basis points.
+    #[serde(default, deserialize_with = "lenient_decimal")]
+    maker_base_fee: Option<Decimal>,
+    #[serde(default, deserialize_with = "lenient_decimal")]
+    taker_base_fee: Option<Decimal>,
+}
 
-    for event in events {
-        // Need a valid end_date
-        let end_date = match event.end_date {
-            Some(d) => match d.split('T').next().unwrap_or("").parse::<NaiveDate>() {
-                Ok(date) => date,
-                Err(_) => continue,
-            },
-            None => continue,
+impl ClobMarket {
+    fn apply(self, market: &mut Market) {
+        if !self.question_id.is_empty() {
+            market.question_id = self.question_id;
+        }
+        if let Some(tick) = self.minimum_tick_size.filter(|t| *t > Decimal::ZERO) {
+            market.tick_size = tick;
+        }
+        if let Some(size) = self.minimum_order_size.filter(|s| *s >= Decimal::ZERO) {
+            market.min_order_size = size;
+        }
+        if self.maker_base_fee.is_some() || self.taker_base_fee.is_some() {
+            market.fees = Some(FeeSchedule {
+                maker_bps: self.maker_base_fee.unwrap_or_default(),
+                taker_bps: self.taker_base_fee.unwrap_or_default(),
+            });
+        }
+    }
+}
+
+/// Gamma sends numeric fields as numbers, strings or null depending on the endpoint. A value
+/// that does not parse is treated as missing rather than failing the whole response.
+fn lenient_decimal<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Decimal>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Number(n) => Decimal::from_str(&n.to_string()).or_else(|_| Decimal::from_scientific(&n.to_string())).ok(),
+        serde_json::Value::String(s) => Decimal::from_str(s.trim()).ok(),
+        _ => None,
+    })
+}
+
+/// Gamma's list fields usually arrive as stringified JSON (`"[\"Yes\", \"No\"]"`) but sometimes
+/// as real arrays, with prices as strings or numbers. Anything else reads as empty.
+fn lenient_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let value = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => serde_json::from_str(&s).unwrap_or_default(),
+        value => value,
+    };
+    let serde_json::Value::Array(items) = value else { return Ok(Vec::new()) };
+    Ok(items.into_iter().filter_map(|item| match item {
+        serde_json::Value::String(s) => Some(s),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }).collect())
+}
+
+/// How the Gamma event list is paged through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paging {
+    /// Events per request.
+    pub page_size: usize,
+    /// Pages requested in parallel.
+    pub concurrency: usize,
+    /// Stop once this many markets were fetched, before filtering. `None` fetches every open market.
+    pub max_markets: Option<usize>,
+}
+
+impl Default for Paging {
+    fn default() -> Self {
+        Self { page_size: 100, concurrency: 4, max_markets: None }
+    }
+}
+
+/// Narrows the trading universe at ingestion, so filtered markets never reach the
+/// dependency graph, websocket subscriptions or execution.
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    /// Categories to keep. Empty keeps every category.
+    pub categories: Vec<MarketCategory>,
+    /// Minimum order book liquidity, in USDC.
+    pub min_liquidity: Decimal,
+    /// Minimum 24h traded volume, in USDC.
+    pub min_volume_24hr: Decimal,
+    /// Widest acceptable bid/ask spread. Markets without a reported spread are kept.
+    pub max_spread: Option<Decimal>,
+    /// Markets to keep regardless of the rest. Empty keeps every market.
+    pub include: MarketRule,
+    /// Markets tracked but never traded; see `excludes`.
+    pub exclude: MarketRule,
+    pub paging: Paging,
+}
+
+impl MarketFilter {
+    pub fn accepts(&self, market: &Market) -> bool {
+        let category_ok = self.categories.is_empty() || self.categories.contains(&TopicClassifier::classify(market));
+        let spread_ok = match (self.max_spread, market.spread) {
+            (Some(max), Some(spread)) => spread <= max,
+            _ => true,
         };
-        
-        let tags: Vec<String> = event.tags.into_iter().map(|t| t.label).collect();
-
-        for api_market in event.markets {
-            let outcomes_str = api_market.outcomes.unwrap_or_else(|| "[]".to_string());
-            let prices_str = api_market.outcome_prices.unwrap_or_else(|| "[]".to_string());
-            let token_ids_str = api_market.clob_token_ids.unwrap_or_else(|| "[]".to_string());
-            
-            // Need to parse these JSON strings manually as they are often stringified JSON in the API
-            let outcomes: Vec<String> = serde_json::from_str(&outcomes_str).unwrap_or_default();
-            let prices: Vec<String> = serde_json::from_str(&prices_str).unwrap_or_default();
-            let token_ids: Vec<String> = serde_json::from_str(&token_ids_str).unwrap_or_default();
-
-            if outcomes.len() != prices.len() || outcomes.len() != token_ids.len() {
-                continue; 
-            }
+        let include_ok = self.include.is_empty() || self.include.matches(market);
+        include_ok && category_ok && spread_ok && market.liquidity >= self.min_liquidity && market.volume_24hr >= self.min_volume_24hr
+    }
+
+    /// Whether a fetched market is kept out of trading. Check before `normalize_markets`, which
+    /// rewrites titles.
+    pub fn excludes(&self, market: &Market) -> bool {
+        self.exclude.matches(market)
+    }
 
-            let mut conditions = Vec::new();
-            for (i, outcome_name) in outcomes.iter().enumerate() {
-                let name_lower = outcome_name.to_lowercase();
-                let outcome_bool = if name_lower == "yes" {
-                    Some(true)
-                } else if name_lower == "no" {
-                    Some(false)
-                } else {
-                    None
-                };
-
-                if let Ok(price) = prices[i].parse::<Decimal>() {
-                    conditions.push(Condition {
-                        name: outcome_name.clone(),
-                        price,
-                        outcome: outcome_bool,
-                        asset_id: token_ids[i].clone(),
-                    });
-                }
+    /// Ids of the markets in `markets` that are kept out of trading.
+    pub fn excluded(&self, markets: &[Market]) -> HashSet<String> {
+        markets.iter().filter(|m| self.excludes(m)).map(|m| m.id.clone()).collect()
+    }
+}
+
+/// Fetches every open market, page by page, and drops those `filter` rejects.
+#[instrument(name = "fetch", skip_all)]
+pub async fn fetch_markets(filter: &MarketFilter) -> Result<Vec<Market>, FetchError> {
+    let mut markets = fetch_all_markets(&filter.paging).await?;
+    let fetched = markets.len();
+    markets.retain(|m| filter.accepts(m));
+    debug!(fetched, kept = markets.len(), "fetched markets");
+    Ok(markets)
+}
+
+async fn fetch_all_markets(paging: &Paging) -> Result<Vec<Market>, FetchError> {
+    let api_url = env::var("POLY_MARKET_API_URL").unwrap_or_else(|_| "https://gamma-api.polymarket.com/events?closed=false".to_string());
+    let events = fetch_pages(paging, |offset| {
+        let url = page_url(&api_url, offset, paging.page_size);
+        async move { Ok(gamma().get_json::<Vec<ApiEvent>>(&url).await?) }
+    }).await?;
+
+    let mut seen = HashSet::new();
+    let mut markets: Vec<Market> = events.into_iter().filter_map(parse_event).flat_map(|e| e.markets).filter(|m| seen.insert(m.id.clone())).collect();
+    if let Some(max) = paging.max_markets {
+        markets.truncate(max);
+    }
+    Ok(markets)
+}
+
+/// Overwrites each market's question id, tick size and minimum order size with the CLOB's,
+/// `concurrency` lookups at a time. The CLOB is authoritative for what it accepts; markets it
+/// cannot be asked about keep what Gamma reported. Returns how many markets were updated.
+#[instrument(name = "enrich", skip_all, fields(markets = markets.len()))]
+pub async fn enrich_markets(markets: &mut [Market], clob_rest_url: &str, concurrency: usize) -> usize {
+    let base = clob_rest_url.trim_end_matches('/');
+    let urls: Vec<(usize, String)> = markets.iter().enumerate()
+        .filter(|(_, m)| !m.condition_id.is_empty())
+        .map(|(i, m)| (i, format!("{}/markets/{}", base, m.condition_id)))
+        .collect();
+    let lookups = urls.into_iter().map(|(i, url)| async move { (i, gamma().get_json::<ClobMarket>(&url).await) });
+    let results: Vec<_> = stream::iter(lookups).buffer_unordered(concurrency.max(1)).collect().await;
+
+    let mut enriched = 0;
+    for (i, result) in results {
+        match result {
+            Ok(params) => {
+                params.apply(&mut markets[i]);
+                enriched += 1;
             }
+            Err(e) => debug!(market_id = %markets[i].id, error = %e, "CLOB market lookup failed"),
+        }
+    }
+    if enriched < markets.len() {
+        warn!(enriched, markets = markets.len(), "some markets kept Gamma trading parameters");
+    }
+    enriched
+}
+
+/// `base` with its `limit` and `offset` query parameters replaced by the page's.
+fn page_url(base: &str, offset: usize, limit: usize) -> String {
+    let Ok(mut url) = Url::parse(base) else {
+        return format!("{}{}limit={}&offset={}", base, if base.contains('?') { '&' } else { '?' }, limit, offset);
+    };
+    let kept: Vec<(String, String)> = url.query_pairs().filter(|(k, _)| k != "limit" && k != "offset").map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+    url.query_pairs_mut().clear().extend_pairs(kept).append_pair("limit", &limit.to_string()).append_pair("offset", &offset.to_string());
+    url.to_string()
+}
+
+/// Requests pages `concurrency` at a time until one comes back short, which marks the end of
+/// the list, or until the pages hold `max_markets` markets. Returns items in page order.
+async fn fetch_pages<T, F, Fut>(paging: &Paging, fetch: F) -> Result<Vec<T>, FetchError>
+where
+    T: MarketCount,
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, FetchError>>,
+{
+    let (page_size, concurrency) = (paging.page_size.max(1), paging.concurrency.max(1));
+    let mut items = Vec::new();
+    let mut markets = 0;
+    let mut next_page = 0;
+    loop {
+        let wave = (next_page..next_page + concurrency).map(|page| fetch(page * page_size));
+        let pages = try_join_all(wave).await?;
+        next_page += concurrency;
+        let done = pages.iter().any(|page| page.len() < page_size);
+        for page in pages {
+            markets += page.iter().map(MarketCount::markets).sum::<usize>();
+            items.extend(page);
+        }
+        if done || paging.max_markets.is_some_and(|max| markets >= max) {
+            debug!(pages = next_page, items = items.len(), "fetched all pages");
+            return Ok(items);
+        }
+    }
+}
+
+/// Markets an item of a page contributes, for the overall cap.
+trait MarketCount {
+    fn markets(&self) -> usize;
+}
+
+impl MarketCount for ApiEvent {
+    fn markets(&self) -> usize {
+        self.markets.len()
+    }
+}
+
+/// Gamma end dates are RFC 3339 timestamps, or bare dates taken as midnight UTC.
+fn parse_end_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim()).map(|d| d.with_timezone(&Utc)).ok()
+        .or_else(|| value.split(['T', ' ']).next()?.parse::<NaiveDate>().ok().map(|d| d.and_time(NaiveTime::MIN).and_utc()))
+}
+
+fn parse_event(event: ApiEvent) -> Option<Event> {
+    // Need a valid end_date; markets may carry their own
+    let end_date = parse_end_date(event.end_date.as_deref()?)?;
+    let event_id = (!event.id.is_empty()).then(|| event.id.clone());
+
+    let tags: Vec<String> = event.tags.into_iter().map(|t| t.label).collect();
+    let mut markets = Vec::new();
+
+    for api_market in event.markets {
+        let (outcomes, prices, token_ids) = (&api_market.outcomes, &api_market.outcome_prices, &api_market.clob_token_ids);
+        if outcomes.is_empty() || outcomes.len() != prices.len() || outcomes.len() != token_ids.len() {
+            // Markets not yet listed on the CLOB have no token ids
+            debug!(market_id = %api_market.id, outcomes = outcomes.len(), prices = prices.len(), token_ids = token_ids.len(), "skipping market with incomplete outcomes");
+            continue;
+        }
+
+        let market_end = api_market.end_date.as_deref().and_then(parse_end_date).unwrap_or(end_date);
+        let mut conditions = Vec::new();
+        for (i, outcome_name) in outcomes.iter().enumerate() {
+            let kind = parse_outcome(outcome_name, market_end);
+            let outcome_bool = match kind {
+                OutcomeKind::Yes => Some(true),
+                OutcomeKind::No => Some(false),
+                _ => None,
+            };
 
-            markets.push(Market {
-                id: api_market.id,
-                title: api_market.question, // Using question as title for the market
-                end_date,
-                conditions,
-                neg_risk_market_id: api_market.neg_risk_market_id,
-                tags: tags.clone(),
+            // A set missing a leg would look underpriced, so an unreadable price drops the market
+            let Some(price) = Decimal::from_str(prices[i].trim()).or_else(|_| Decimal::from_scientific(prices[i].trim())).ok() else {
+                debug!(market_id = %api_market.id, price = %prices[i], "skipping market with unparseable price");
+                break;
+            };
+            conditions.push(Condition {
+                name: outcome_name.clone(),
+                price,
+                outcome: outcome_bool,
+                asset_id: token_ids[i].clone(),
+                kind,
+                last_updated: None,
             });
         }
+        if conditions.len() != outcomes.len() {
+            continue;
+        }
+
+        markets.push(Market {
+            id: api_market.id,
+            title: api_market.question, // Using question as title for the market
+            end_date: market_end,
+            conditions,
+            neg_risk_market_id: api_market.neg_risk_market_id,
+            tags: tags.clone(),
+            liquidity: api_market.liquidity.unwrap_or_default(),
+            volume_24hr: api_market.volume_24hr.unwrap_or_default(),
+            spread: api_market.spread,
+            condition_id: api_market.condition_id,
+            question_id: api_market.question_id,
+            tick_size: api_market.tick_size.filter(|t| *t > Decimal::ZERO).unwrap_or(DEFAULT_TICK_SIZE),
+            min_order_size: api_market.min_order_size.unwrap_or_default(),
+            event_id: event_id.clone(),
+            resolution: ResolutionRules {
+                description: api_market.description,
+                source: api_market.resolution_source,
+                resolved_by: api_market.resolved_by,
+            },
+            fees: None,
+        });
     }
 
-    Ok(markets)
+    Some(Event { id: event.id, title: event.title, markets })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_filter_keeps_selected_categories() {
+        let market = |title: &str, tag: &str| Market {
+            id: title.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            tags: vec![tag.to_string()],
+            ..Market::default()
+        };
+        let filter = MarketFilter { categories: vec![MarketCategory::Politics, MarketCategory::Crypto], ..MarketFilter::default() };
+        assert!(filter.accepts(&market("Will Trump win?", "Elections")));
+        assert!(filter.accepts(&market("BTC above 100k?", "Bitcoin")));
+        assert!(!filter.accepts(&market("Lakers vs Celtics", "NBA")));
+        assert!(MarketFilter::default().accepts(&market("Lakers vs Celtics", "NBA")));
+    }
+
+    #[test]
+    fn test_liquidity_fields_parse_leniently_and_filter() {
+        let api: ApiMarket = serde_json::from_str(r#"{
+            "id": "1", "question": "q", "liquidity": "1500.5", "liquidityNum": 1500.5, "volume24hr": 2.5e3, "spread": "bad"
+        }"#).unwrap();
+        assert_eq!(api.liquidity, Some(dec!(1500.5)));
+        assert_eq!(api.volume_24hr, Some(dec!(2500)));
+        assert_eq!(api.spread, None);
+
+        let filter = MarketFilter { min_liquidity: dec!(1000), min_volume_24hr: dec!(100), max_spread: Some(dec!(0.05)), ..MarketFilter::default() };
+        let mut market = Market {
+            id: "1".to_string(),
+            title: "q".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            liquidity: dec!(1500),
+            volume_24hr: dec!(2500),
+            spread: Some(dec!(0.02)),
+            ..Market::default()
+        };
+        assert!(filter.accepts(&market));
+        market.spread = Some(dec!(0.1));
+        assert!(!filter.accepts(&market));
+        market.spread = None;
+        market.liquidity = dec!(500);
+        assert!(!filter.accepts(&market));
+    }
+
+    impl MarketCount for usize {
+        fn markets(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_recorded_gamma_events_parse() {
+        let fixture = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gamma/events.json")).unwrap();
+        let events: Vec<ApiEvent> = serde_json::from_str(&fixture).unwrap();
+        let parsed: Vec<Event> = events.into_iter().filter_map(parse_event).collect();
+        // The undated event is dropped
+        assert_eq!(parsed.len(), 2);
+
+        // Stringified lists, numeric prices inside them, and numbers sent as strings all parse;
+        // the market not yet on the CLOB has no token ids and is skipped
+        let election = &parsed[0].markets;
+        assert_eq!(election.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["531202", "531203"]);
+        assert_eq!(election[0].conditions.iter().map(|c| (c.outcome, c.price)).collect::<Vec<_>>(), vec![(Some(true), dec!(0.285)), (Some(false), dec!(0.715))]);
+        assert_eq!(election[1].conditions[0].price, dec!(0.185));
+        assert!(election[1].conditions[1].asset_id.starts_with("328942"));
+        assert_eq!((election[0].liquidity, election[0].tick_size, election[0].min_order_size), (dec!(412503.5521), dec!(0.001), dec!(5)));
+        assert_eq!((election[1].volume_24hr, election[1].spread), (Decimal::ZERO, Some(dec!(0.01))));
+        assert_eq!(election[0].tags, vec!["Politics", "Elections"]);
+        // A market's own end date wins over its event's
+        assert_eq!(election[0].end_date, Utc.with_ymd_and_hms(2028, 11, 7, 12, 0, 0).unwrap());
+        assert_eq!(election[1].end_date, Utc.with_ymd_and_hms(2028, 11, 7, 0, 0, 0).unwrap());
+        assert!(election[0].resolution.description.ends_with("consensus of credible reporting."));
+        assert_eq!((election[0].resolution.source.as_str(), election[1].resolution.source.as_str()), ("https://www.270towin.com", ""));
+        assert!(election.iter().all(|m| m.neg_risk_market_id.is_some() && m.event_id.as_deref() == Some("16085")));
+
+        // Plain arrays parse too, missing optional fields default, and a market with an
+        // unreadable price is dropped rather than kept with a missing leg
+        let fed = &parsed[1].markets;
+        assert_eq!(fed.len(), 1);
+        assert_eq!((fed[0].conditions.len(), fed[0].tick_size, fed[0].liquidity, fed[0].spread), (2, DEFAULT_TICK_SIZE, Decimal::ZERO, None));
+        assert_eq!(fed[0].neg_risk_market_id, None);
+        assert_eq!(fed[0].end_date, Utc.with_ymd_and_hms(2025, 12, 10, 12, 0, 0).unwrap());
+        assert_eq!(parse_end_date("2025-12-10"), Some(Utc.with_ymd_and_hms(2025, 12, 10, 0, 0, 0).unwrap()));
+
+        let clob: ClobMarket = serde_json::from_str(&std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/clob/market.json")).unwrap()).unwrap();
+        let mut market = fed[0].clone();
+        clob.apply(&mut market);
+        assert_eq!((market.tick_size, market.min_order_size), (dec!(0.001), dec!(5)));
+        assert_eq!(market.fees.map(|f| (f.maker_bps, f.taker_bps)), Some((Decimal::ZERO, Decimal::ZERO)));
+    }
+
+    #[tokio::test]
+    async fn test_pages_until_short_page_or_cap() {
+        // 250 events, one market each
+        let events = |size: usize| move |offset: usize| async move { Ok::<_, FetchError>((offset..(offset + size).min(250)).collect::<Vec<usize>>()) };
+        let paging = Paging { page_size: 100, concurrency: 2, max_markets: None };
+        assert_eq!(fetch_pages(&paging, events(100)).await.unwrap(), (0..250).collect::<Vec<_>>());
+
+        // The wave that reaches the cap is the last one
+        let capped = Paging { page_size: 50, concurrency: 2, max_markets: Some(60) };
+        assert_eq!(fetch_pages(&capped, events(50)).await.unwrap().len(), 100);
+
+        assert_eq!(
+            page_url("https://gamma-api.polymarket.com/events?closed=false&limit=50", 200, 100),
+            "https://gamma-api.polymarket.com/events?closed=false&limit=100&offset=200"
+        );
+    }
 }
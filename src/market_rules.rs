@@ -0,0 +1,66 @@
+use crate::shared_types::Market;
+use crate::topic_classifier::{MarketCategory, TopicClassifier};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Markets picked out by hand: by id, title regex, tag or category. A market matches when any
+/// one of them does. Titles are matched as Gamma returns them, before normalization.
+#[derive(Debug, Clone, Default)]
+pub struct MarketRule {
+    pub ids: HashSet<String>,
+    pub titles: Vec<Regex>,
+    /// Lowercased; tags match case-insensitively.
+    pub tags: HashSet<String>,
+    pub categories: Vec<MarketCategory>,
+}
+
+impl MarketRule {
+    pub fn new(ids: &[String], titles: &[String], tags: &[String], categories: Vec<MarketCategory>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            ids: ids.iter().cloned().collect(),
+            titles: titles.iter().map(|t| Regex::new(t)).collect::<Result<_, _>>()?,
+            tags: tags.iter().map(|t| t.to_lowercase()).collect(),
+            categories,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.titles.is_empty() && self.tags.is_empty() && self.categories.is_empty()
+    }
+
+    pub fn matches(&self, market: &Market) -> bool {
+        self.ids.contains(&market.id)
+            || self.titles.iter().any(|r| r.is_match(&market.title))
+            || market.tags.iter().any(|t| self.tags.contains(&t.to_lowercase()))
+            || (!self.categories.is_empty() && self.categories.contains(&TopicClassifier::classify(market)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn market(id: &str, title: &str, tags: &[&str]) -> Market {
+        Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 12, 31, 0, 0, 0).unwrap(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_rule_matches_any_of_id_title_tag_or_category() {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let rule = MarketRule::new(&strings(&["m1"]), &strings(&["(?i)^will .* say "]), &strings(&["mentions"]), vec![MarketCategory::Sports]).unwrap();
+
+        assert!(rule.matches(&market("m1", "Fed cuts rates?", &[])));
+        assert!(rule.matches(&market("m2", "Will Powell say \"recession\"?", &[])));
+        assert!(rule.matches(&market("m3", "Fed cuts rates?", &["Mentions"])));
+        assert!(!rule.matches(&market("m4", "Fed cuts rates?", &["Economy"])));
+        assert!(MarketRule::default().is_empty() && !MarketRule::default().matches(&market("m1", "x", &[])));
+        assert!(MarketRule::new(&[], &strings(&["("]), &[], vec![]).is_err());
+    }
+}
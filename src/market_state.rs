@@ -0,0 +1,366 @@
+use crate::arbitrage_engine::{build_dependency_graph, group_neg_risk_baskets, group_threshold_ladders, PatternRegistry};
+use crate::clob_client::Subscription;
+use crate::market_fetcher::{enrich_markets, fetch_markets, MarketFilter};
+use crate::normalization::normalize_markets;
+use crate::similarity::TitleSimilarity;
+use crate::shared_types::{ConditionId, DependencyGraph, LivePrices, Market, MarketId, MarketRegistry};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify, RwLock};
+use tokio::time::{sleep, Duration};
+use tracing::{info, instrument, warn};
+
+pub type SharedMarketState = Arc<RwLock<MarketState>>;
+
+/// Live market set plus every index derived from it. Indices hold stable handles, so a market
+/// removed by a refresh drops out of lookups instead of aliasing the one that took its slot.
+#[derive(Debug, Default)]
+pub struct MarketState {
+    /// Markets as fetched. Streamed prices are kept in `live`; read them through `price` or
+    /// `priced`, which need only a read lock.
+    pub markets: MarketRegistry,
+    pub live: LivePrices,
+    pub dependency_graph: DependencyGraph,
+    pub adjacency: HashMap<MarketId, Vec<MarketId>>,
+    pub neg_risk_baskets: HashMap<String, Vec<MarketId>>,
+    /// Crypto threshold ladders by `threshold_ladder_key`, loosest rung first.
+    pub threshold_ladders: HashMap<String, Vec<MarketId>>,
+    /// Title similarity required for two markets to be compared for dependencies.
+    pub similarity_cutoff: f64,
+    /// The configured title similarity, fitted to the markets seen.
+    pub similarity: TitleSimilarity,
+    /// Patterns used to find implications between related markets.
+    pub patterns: Arc<PatternRegistry>,
+    /// Markets resolved on-chain or past their end date, kept out even while Gamma still lists
+    /// them as active.
+    pub resolved_markets: HashSet<String>,
+    /// Markets matched by the exclude rules: priced and graphed, but never traded.
+    pub excluded: HashSet<String>,
+}
+
+/// What changed in a refresh.
+#[derive(Debug, Default)]
+pub struct RefreshDiff {
+    pub added_markets: Vec<String>,
+    pub removed_markets: Vec<String>,
+    pub added_asset_ids: Vec<String>,
+    pub removed_asset_ids: Vec<String>,
+    pub new_pairs: usize,
+}
+
+impl RefreshDiff {
+    fn merge(&mut self, other: RefreshDiff) {
+        self.added_markets.extend(other.added_markets);
+        self.removed_markets.extend(other.removed_markets);
+        self.added_asset_ids.extend(other.added_asset_ids);
+        self.removed_asset_ids.extend(other.removed_asset_ids);
+        self.new_pairs += other.new_pairs;
+    }
+}
+
+impl MarketState {
+    /// Builds the full state, comparing every pair of markets for relatedness.
+    #[instrument(name = "graph_build", skip_all, fields(markets = markets.len()))]
+    pub fn build(markets: Vec<Market>, similarity_cutoff: f64, patterns: Arc<PatternRegistry>) -> Self {
+        let similarity = TitleSimilarity::fit(patterns.similarity(), markets.iter().map(|m| m.title.as_str()));
+        let graph = build_dependency_graph(&markets, &similarity, similarity_cutoff, &patterns);
+        let mut state = Self { markets: MarketRegistry::new(markets), dependency_graph: graph, similarity_cutoff, similarity, patterns, ..Self::default() };
+        state.rebuild_indices();
+        state
+    }
+
+    pub fn asset_ids(&self) -> Vec<String> {
+        self.markets.asset_ids().cloned().collect()
+    }
+
+    /// Records a price streamed `at`. Returns false for assets outside the live set.
+    pub fn set_price(&self, asset_id: &str, price: Decimal, at: DateTime<Utc>) -> bool {
+        let known = self.markets.resolve(asset_id).is_some();
+        if known {
+            self.live.set(asset_id, price, at);
+        }
+        known
+    }
+
+    /// Overwrites outcome prices from a REST snapshot, returning how many assets were known.
+    pub fn apply_prices(&self, prices: &HashMap<String, Decimal>) -> usize {
+        let now = Utc::now();
+        prices.iter().filter(|(asset_id, &price)| self.set_price(asset_id, price, now)).count()
+    }
+
+    /// When an outcome's price was last streamed, if it has been.
+    pub fn last_updated(&self, asset_id: &str) -> Option<DateTime<Utc>> {
+        self.live.quote(asset_id).map(|(_, at)| at)
+    }
+
+    /// Current price of one outcome, if its market is still tracked.
+    pub fn price(&self, id: ConditionId) -> Option<Decimal> {
+        self.markets.condition(id).map(|condition| self.live.price_of(condition))
+    }
+
+    /// A copy of a tracked market at current prices, for the detectors.
+    pub fn priced(&self, id: MarketId) -> Option<Market> {
+        let mut market = self.markets.get(id)?.clone();
+        for condition in &mut market.conditions {
+            if let Some((price, at)) = self.live.quote(&condition.asset_id) {
+                condition.price = price;
+                condition.last_updated = Some(at);
+            }
+        }
+        Some(market)
+    }
+
+    /// Reconciles the live set with a freshly fetched one. Markets that disappeared are dropped
+    /// together with their edges, new markets are only compared against the rest, and markets
+    /// present in both keep their live (websocket-fed) prices.
+    pub fn apply_refresh(&mut self, fresh: Vec<Market>) -> RefreshDiff {
+        let mut diff = RefreshDiff::default();
+        let fresh: Vec<Market> = fresh.into_iter().filter(|m| !self.resolved_markets.contains(&m.id)).collect();
+        let fresh_ids: HashSet<&str> = fresh.iter().map(|m| m.id.as_str()).collect();
+
+        let stale: Vec<String> = self.markets.iter().filter(|(_, m)| !fresh_ids.contains(m.id.as_str())).map(|(_, m)| m.id.clone()).collect();
+        for id in &stale {
+            diff.merge(self.remove_market(id));
+        }
+        for market in fresh {
+            diff.merge(self.add_market(market));
+        }
+        diff
+    }
+
+    /// Drops markets that resolved on-chain with their edges, and keeps them out of later
+    /// refreshes.
+    pub fn drop_resolved(&mut self, market_ids: &[String]) -> RefreshDiff {
+        let mut diff = RefreshDiff::default();
+        self.resolved_markets.extend(market_ids.iter().cloned());
+        for id in market_ids {
+            diff.merge(self.remove_market(id));
+        }
+        diff
+    }
+
+    /// Drops markets whose end date is at or before `now`, as `drop_resolved` does.
+    pub fn drop_expired(&mut self, now: DateTime<Utc>) -> RefreshDiff {
+        let expired: Vec<String> = self.markets.iter().filter(|(_, m)| m.end_date <= now).map(|(_, m)| m.id.clone()).collect();
+        self.drop_resolved(&expired)
+    }
+
+    /// Adds one market, comparing it against the live set and indexing its assets. Known and
+    /// resolved markets are ignored.
+    pub fn add_market(&mut self, market: Market) -> RefreshDiff {
+        let mut diff = RefreshDiff::default();
+        if self.resolved_markets.contains(&market.id) {
+            return diff;
+        }
+        let known: HashSet<String> = self.markets.asset_ids().cloned().collect();
+        let Some(id) = self.markets.insert(market) else { return diff };
+        let Some(slot) = self.markets.slot(id) else { return diff };
+        let markets = self.markets.as_slice();
+        diff.added_markets.push(markets[slot].id.clone());
+        self.similarity.observe(&markets[slot].title);
+
+        let related: Vec<MarketId> = self.dependency_graph.add_market(markets, slot, &self.similarity, self.similarity_cutoff, &self.patterns)
+            .into_iter()
+            .map(|j| self.markets.id_at(j))
+            .collect();
+        diff.new_pairs = related.len();
+        for &j in &related {
+            self.adjacency.entry(j).or_default().push(id);
+        }
+        if !related.is_empty() {
+            self.adjacency.insert(id, related);
+        }
+        diff.added_asset_ids = markets[slot].conditions.iter()
+            .filter(|c| !c.asset_id.is_empty() && !known.contains(&c.asset_id))
+            .map(|c| c.asset_id.clone())
+            .collect();
+        self.regroup();
+        diff
+    }
+
+    /// Removes one market with its edges. Its handle stops resolving; no other market's
+    /// entries change.
+    pub fn remove_market(&mut self, market_id: &str) -> RefreshDiff {
+        let mut diff = RefreshDiff::default();
+        let Some((id, market)) = self.markets.remove(market_id) else { return diff };
+        self.dependency_graph.remove_market(&market);
+        for condition in &market.conditions {
+            self.live.remove(&condition.asset_id);
+            if !condition.asset_id.is_empty() && self.markets.resolve(&condition.asset_id).is_none() {
+                diff.removed_asset_ids.push(condition.asset_id.clone());
+            }
+        }
+        for neighbour in self.adjacency.remove(&id).unwrap_or_default() {
+            if let Some(list) = self.adjacency.get_mut(&neighbour) {
+                list.retain(|&n| n != id);
+            }
+        }
+        diff.removed_markets.push(market.id);
+        self.regroup();
+        diff
+    }
+
+    fn rebuild_indices(&mut self) {
+        self.adjacency.clear();
+        for (a, b) in &self.dependency_graph.related_markets {
+            if let (Some(i), Some(j)) = (self.markets.handle(a), self.markets.handle(b)) {
+                self.adjacency.entry(i).or_default().push(j);
+                self.adjacency.entry(j).or_default().push(i);
+            }
+        }
+        self.regroup();
+    }
+
+    /// Recomputes basket and ladder membership, a linear pass with no pairwise comparisons.
+    fn regroup(&mut self) {
+        let markets = &self.markets;
+        let to_ids = |groups: HashMap<String, Vec<usize>>| -> HashMap<String, Vec<MarketId>> {
+            groups.into_iter().map(|(key, slots)| (key, slots.into_iter().map(|slot| markets.id_at(slot)).collect())).collect()
+        };
+        self.neg_risk_baskets = to_ids(group_neg_risk_baskets(markets.as_slice()));
+        self.threshold_ladders = to_ids(group_threshold_ladders(markets.as_slice()));
+    }
+}
+
+/// Channels that receive added and removed asset ids so open websocket connections can follow
+/// the live set.
+pub type SubscriptionSender = mpsc::UnboundedSender<Subscription>;
+
+/// Sends `diff`'s added and removed assets to every subscriber.
+pub fn forward_subscriptions(diff: &RefreshDiff, subscribers: &[SubscriptionSender]) {
+    for tx in subscribers {
+        if !diff.added_asset_ids.is_empty() {
+            let _ = tx.send(Subscription::Add(diff.added_asset_ids.clone()));
+        }
+        if !diff.removed_asset_ids.is_empty() {
+            let _ = tx.send(Subscription::Remove(diff.removed_asset_ids.clone()));
+        }
+    }
+}
+
+/// Re-fetches the Gamma API every `interval`, or as soon as `trigger` is notified, loads CLOB
+/// trading parameters for markets not yet tracked, applies the diff to `state` and forwards
+/// added and removed asset ids to every subscription channel. Runs until the task is dropped.
+pub async fn run_refresh_loop(state: SharedMarketState, interval: Duration, trigger: Arc<Notify>, filter: MarketFilter, clob_rest_url: String, subscribers: Vec<SubscriptionSender>) {
+    loop {
+        tokio::select! {
+            _ = sleep(interval) => {}
+            _ = trigger.notified() => info!("market refresh requested"),
+        }
+        let mut fresh = match fetch_markets(&filter).await {
+            Ok(markets) => markets,
+            Err(e) => {
+                warn!(error = %e, "market refresh failed, keeping current set");
+                continue;
+            }
+        };
+        let excluded = filter.excluded(&fresh);
+        normalize_markets(&mut fresh);
+        // Tracked markets keep their entry, so only new ones need a lookup
+        let tracked_ids: HashSet<String> = state.read().await.markets.iter().map(|(_, m)| m.id.clone()).collect();
+        let (tracked, mut new): (Vec<Market>, Vec<Market>) = fresh.into_iter().partition(|m| tracked_ids.contains(&m.id));
+        enrich_markets(&mut new, &clob_rest_url, filter.paging.concurrency).await;
+        fresh = tracked.into_iter().chain(new).collect();
+
+        let diff = {
+            let mut state = state.write().await;
+            state.excluded = excluded;
+            state.apply_refresh(fresh)
+        };
+        info!(
+            added = diff.added_markets.len(),
+            removed = diff.removed_markets.len(),
+            new_pairs = diff.new_pairs,
+            new_assets = diff.added_asset_ids.len(),
+            "market refresh applied"
+        );
+        forward_subscriptions(&diff, &subscribers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn market(id: &str, title: &str, price: Decimal) -> Market {
+        Market {
+            id: id.to_string(),
+            title: title.to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price, outcome: Some(true), asset_id: format!("{}-yes", id), kind: OutcomeKind::Other, last_updated: None }],
+            tags: vec!["Politics".to_string()],
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_refresh_diffs_markets_and_keeps_live_prices() {
+        let mut state = MarketState::build(vec![
+            market("a", "trump_win_election", dec!(0.5)),
+            market("b", "trump_win_election_by_5", dec!(0.3)),
+            market("c", "fed_cut_rates", dec!(0.2)),
+        ], 0.6, Arc::default());
+        assert_eq!(state.dependency_graph.related_markets.len(), 1);
+        assert!(state.set_price("a-yes", dec!(0.55), Utc::now()) && state.set_price("c-yes", dec!(0.25), Utc::now()));
+        assert!(!state.set_price("z-yes", dec!(0.1), Utc::now()));
+
+        let diff = state.apply_refresh(vec![
+            market("a", "trump_win_election", dec!(0.4)),
+            market("b", "trump_win_election_by_5", dec!(0.3)),
+            market("d", "trump_win_election_by_10", dec!(0.1)),
+        ]);
+
+        assert_eq!(diff.removed_markets, vec!["c".to_string()]);
+        assert_eq!(diff.added_markets, vec!["d".to_string()]);
+        assert_eq!(diff.added_asset_ids, vec!["d-yes".to_string()]);
+        assert!(diff.new_pairs > 0);
+        assert!(state.markets.resolve("c-yes").is_none());
+        let a_yes = state.markets.resolve("a-yes").unwrap();
+        assert_eq!(state.price(a_yes), Some(dec!(0.55)));
+        let priced = state.priced(a_yes.market).unwrap();
+        assert_eq!(priced.conditions[a_yes.index].price, dec!(0.55));
+        assert!(priced.conditions[a_yes.index].last_updated.is_some());
+        assert_eq!(state.live.get("c-yes"), None);
+        let d = state.markets.handle("d").unwrap();
+        assert!(!state.adjacency[&d].is_empty());
+
+        // A market resolved on-chain is dropped and stays out while Gamma still lists it
+        let diff = state.drop_resolved(&["d".to_string()]);
+        assert_eq!((diff.removed_markets, diff.removed_asset_ids), (vec!["d".to_string()], vec!["d-yes".to_string()]));
+        assert!(state.dependency_graph.related_markets.iter().all(|(a, b)| a != "d" && b != "d"));
+        let diff = state.apply_refresh(vec![market("a", "trump_win_election", dec!(0.4)), market("d", "trump_win_election_by_10", dec!(0.1))]);
+        assert!(diff.added_markets.is_empty() && state.markets.resolve("d-yes").is_none());
+    }
+
+    #[test]
+    fn test_handles_survive_removals_and_go_stale_with_their_market() {
+        let mut state = MarketState::build(vec![
+            market("a", "trump_win_election", dec!(0.5)),
+            market("b", "fed_cut_rates", dec!(0.2)),
+            market("c", "trump_win_election_by_5", dec!(0.3)),
+        ], 0.6, Arc::default());
+        let [a, c] = ["a", "c"].map(|id| state.markets.handle(id).unwrap());
+        assert_eq!(state.adjacency[&a], vec![c]);
+
+        // "c" takes a's storage slot, but its handle still resolves to it
+        let diff = state.remove_market("a");
+        assert_eq!(diff.removed_asset_ids, vec!["a-yes".to_string()]);
+        assert!(state.adjacency.values().all(|n| n.is_empty()));
+        assert!(state.markets.get(a).is_none() && state.priced(a).is_none());
+        assert_eq!(state.markets.get(c).unwrap().id, "c");
+        assert_eq!(state.markets.resolve("c-yes").map(|id| id.market), Some(c));
+
+        // A returning market gets a fresh handle; the old one stays dead
+        let diff = state.add_market(market("a", "trump_win_election", dec!(0.5)));
+        assert_eq!((diff.new_pairs, diff.added_asset_ids), (1, vec!["a-yes".to_string()]));
+        let a_again = state.markets.handle("a").unwrap();
+        assert_ne!(a_again, a);
+        assert!(state.markets.get(a).is_none());
+        assert_eq!((state.adjacency[&a_again].clone(), state.adjacency[&c].clone()), (vec![c], vec![a_again]));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::kill_switch::KillSwitch;
+use crate::runtime_control::RuntimeControl;
+use hyper::header::AUTHORIZATION;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{register_gauge, register_gauge_vec, register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge};
+use prometheus::{Encoder, Gauge, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+lazy_static! {
+    pub static ref WS_MESSAGES: IntCounterVec = register_int_counter_vec!(
+        "polymarket_ws_messages_total", "Websocket events received, by channel", &["channel"]
+    ).unwrap();
+    pub static ref OPPORTUNITIES: IntCounterVec = register_int_counter_vec!(
+        "polymarket_opportunities_total", "Arbitrage opportunities detected, by kind", &["kind"]
+    ).unwrap();
+    pub static ref OPPORTUNITIES_SUPPRESSED: IntCounterVec = register_int_counter_vec!(
+        "polymarket_opportunities_suppressed_total", "Opportunities not executed because the same one was in flight or cooling down, by kind", &["kind"]
+    ).unwrap();
+    pub static ref SIGNAL_BLOCKED: IntCounterVec = register_int_counter_vec!(
+        "polymarket_signal_blocked_total", "Opportunities not executed because order-flow imbalance or momentum ran against a resting leg, by kind", &["kind"]
+    ).unwrap();
+    pub static ref RATE_LIMITED: IntCounterVec = register_int_counter_vec!(
+        "polymarket_rate_limited_total", "429 responses from the CLOB, by endpoint budget", &["endpoint"]
+    ).unwrap();
+    pub static ref EVALUATIONS_SUPPRESSED: IntCounter = register_int_counter!(
+        "polymarket_evaluations_suppressed_total", "Price ticks folded into an already pending engine run for the same asset"
+    ).unwrap();
+    pub static ref STALE_PRICE_REFUSED: IntCounterVec = register_int_counter_vec!(
+        "polymarket_stale_price_refused_total", "Opportunities refused because a leg's price was older than the staleness horizon, by kind", &["kind"]
+    ).unwrap();
+    pub static ref OPPORTUNITIES_EXPIRED: IntCounterVec = register_int_counter_vec!(
+        "polymarket_opportunities_expired_total", "Queued opportunities dropped after waiting longer than the scheduler TTL, by kind", &["kind"]
+    ).unwrap();
+    pub static ref SCHEDULER_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "polymarket_scheduler_queue_depth", "Opportunities waiting for an execution slot"
+    ).unwrap();
+    pub static ref EXECUTION_LATENCY: HistogramVec = register_histogram_vec!(
+        "polymarket_execution_latency_seconds", "Time from execution start to result, by kind", &["kind"],
+        vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+    ).unwrap();
+    pub static ref TICK_LATENCY: HistogramVec = register_histogram_vec!(
+        "polymarket_tick_latency_seconds", "Hot-path latency from websocket frame to execution decision, by stage", &["stage"],
+        vec![0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]
+    ).unwrap();
+    pub static ref TICK_LATENCY_QUANTILE: GaugeVec = register_gauge_vec!(
+        "polymarket_tick_latency_quantile_seconds", "P50/P99 of the last 1024 hot-path latency samples, by stage", &["stage", "quantile"]
+    ).unwrap();
+    pub static ref RECONNECTS: IntCounterVec = register_int_counter_vec!(
+        "polymarket_ws_reconnects_total", "Websocket reconnects, by stream", &["stream"]
+    ).unwrap();
+    pub static ref SIMULATED_REVERTS: IntCounter = register_int_counter!(
+        "polymarket_simulated_reverts_total", "Transactions not broadcast because their eth_call simulation reverted"
+    ).unwrap();
+    pub static ref RPC_FAILOVERS: IntCounterVec = register_int_counter_vec!(
+        "polymarket_rpc_failovers_total", "RPC requests and health checks that failed on an endpoint, by endpoint host", &["endpoint"]
+    ).unwrap();
+    pub static ref RPC_ENDPOINT_LATENCY: GaugeVec = register_gauge_vec!(
+        "polymarket_rpc_endpoint_latency_ms", "Moving average RPC round trip, by endpoint host", &["endpoint"]
+    ).unwrap();
+    pub static ref CHAIN_REORGS: IntCounterVec = register_int_counter_vec!(
+        "polymarket_chain_reorgs_total", "Chain reorgs that rolled back ingested fills or streamed fills, by source", &["source"]
+    ).unwrap();
+    pub static ref WS_STALE: IntCounterVec = register_int_counter_vec!(
+        "polymarket_ws_stale_total", "Websocket connections dropped after going silent past the heartbeat window, by stream", &["stream"]
+    ).unwrap();
+    pub static ref OPEN_POSITIONS: IntGauge = register_int_gauge!(
+        "polymarket_open_positions", "Outcome tokens currently held by the trading wallet"
+    ).unwrap();
+    pub static ref EXPECTED_PNL: Gauge = register_gauge!(
+        "polymarket_expected_pnl_usd", "Expected profit of completed executions at their sized amount, in USD"
+    ).unwrap();
+    pub static ref TRADING_PAUSED: IntGauge = register_int_gauge!(
+        "polymarket_trading_paused", "1 while the execution circuit breaker is open"
+    ).unwrap();
+    pub static ref TRADING_HALTED: IntGauge = register_int_gauge!(
+        "polymarket_trading_halted", "1 while the kill switch holds execution until re-armed"
+    ).unwrap();
+}
+
+/// Renders every registered metric in the Prometheus text exposition format.
+pub fn render() -> String {
+    crate::latency::publish_quantiles();
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        warn!(error = %e, "failed to encode metrics");
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// The kill switch and JSON-RPC control endpoints served next to `/metrics`. Re-arming and
+/// `/rpc` need `Authorization: Bearer <token>` when a token is set.
+#[derive(Clone)]
+pub struct Control {
+    pub kill_switch: Arc<KillSwitch>,
+    pub runtime: Arc<RuntimeControl>,
+    pub token: Option<String>,
+}
+
+impl Control {
+    fn authorized(&self, req: &Request<Body>) -> bool {
+        let bearer = req.headers().get(AUTHORIZATION).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+        self.token.as_deref().is_none_or(|token| bearer == Some(token))
+    }
+}
+
+async fn handle(req: Request<Body>, control: &Control) -> Response<Body> {
+    let text = |status: StatusCode, body: String| Response::builder().status(status).body(Body::from(body));
+    let unauthorized = || text(StatusCode::UNAUTHORIZED, "missing or wrong bearer token\n".to_string());
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", TextEncoder::new().format_type())
+            .body(Body::from(render())),
+        (&Method::GET, "/kill-switch") => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::json!({ "halted": control.kill_switch.halted() }).to_string())),
+        (&Method::POST, "/kill-switch/rearm") => {
+            if !control.authorized(&req) {
+                unauthorized()
+            } else {
+                match control.kill_switch.rearm() {
+                    Ok(Some(halt)) => text(StatusCode::OK, format!("re-armed, was halted: {}\n", halt.reason)),
+                    Ok(None) => text(StatusCode::OK, "not halted\n".to_string()),
+                    Err(e) => text(StatusCode::INTERNAL_SERVER_ERROR, format!("failed to clear the persisted halt: {}\n", e)),
+                }
+            }
+        }
+        (&Method::POST, "/rpc") => {
+            if !control.authorized(&req) {
+                unauthorized()
+            } else {
+                match hyper::body::to_bytes(req.into_body()).await {
+                    Ok(body) => Response::builder()
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(control.runtime.rpc(&body, &control.kill_switch).to_string())),
+                    Err(e) => text(StatusCode::BAD_REQUEST, format!("failed to read the request body: {}\n", e)),
+                }
+            }
+        }
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    response.unwrap_or_default()
+}
+
+/// Serves `/metrics`, the kill switch endpoints and `/rpc` on `addr` until the task is dropped.
+pub async fn serve(addr: SocketAddr, control: Control) -> Result<(), hyper::Error> {
+    let make_svc = make_service_fn(move |_| {
+        let control = control.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let control = control.clone();
+                async move { Ok::<_, Infallible>(handle(req, &control).await) }
+            }))
+        }
+    });
+    info!(%addr, "serving Prometheus metrics on /metrics, the kill switch on /kill-switch and runtime control on /rpc");
+    Server::bind(&addr).serve(make_svc).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fees::FeeModel;
+    use crate::kill_switch::KillSwitchConfig;
+    use crate::notifier::NotifierHandle;
+    use crate::profit_model::ProfitModelConfig;
+    use crate::strategy::StrategyRegistry;
+
+    #[test]
+    fn test_render_includes_labelled_counters() {
+        OPPORTUNITIES.with_label_values(&["rebalancing"]).inc();
+        EXECUTION_LATENCY.with_label_values(&["rebalancing"]).observe(0.2);
+        let text = render();
+        assert!(text.contains("polymarket_opportunities_total{kind=\"rebalancing\"}"));
+        assert!(text.contains("polymarket_execution_latency_seconds_bucket{kind=\"rebalancing\",le=\"0.25\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_control_endpoints_need_the_bearer_token() {
+        let path = std::env::temp_dir().join(format!("kill-switch-api-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"reason":"drawdown","at":"2025-01-02T00:00:00Z"}"#).unwrap();
+        let config = KillSwitchConfig { path, ..KillSwitchConfig::default() };
+        let runtime = Arc::new(RuntimeControl::new(StrategyRegistry::new(FeeModel::default(), ProfitModelConfig::default(), 4)));
+        let control = Control { kill_switch: Arc::new(KillSwitch::new(config, NotifierHandle::disabled())), runtime, token: Some("secret".to_string()) };
+        let post = |path: &str, auth: &str, body: &'static str| Request::post(path).header(AUTHORIZATION, auth).body(Body::from(body)).unwrap();
+
+        let pause = r#"{"jsonrpc":"2.0","id":1,"method":"pause"}"#;
+        assert_eq!(handle(post("/rpc", "Bearer wrong", pause), &control).await.status(), StatusCode::UNAUTHORIZED);
+        assert!(!control.runtime.is_paused());
+        assert_eq!(handle(post("/rpc", "Bearer secret", pause), &control).await.status(), StatusCode::OK);
+        assert!(control.runtime.is_paused());
+
+        assert_eq!(handle(post("/kill-switch/rearm", "Bearer wrong", ""), &control).await.status(), StatusCode::UNAUTHORIZED);
+        assert!(control.kill_switch.halted().is_some());
+        assert_eq!(handle(post("/kill-switch/rearm", "Bearer secret", ""), &control).await.status(), StatusCode::OK);
+        assert!(control.kill_switch.halted().is_none());
+    }
+}
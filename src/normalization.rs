@@ -9,7 +9,7 @@ pub fn normalize_markets(markets: &mut Vec<Market>) {
     let mut neg_risk_groups: HashMap<String, Vec<&mut Market>> = HashMap::new();
     for market in markets.iter_mut() {
         if let Some(ref neg_id) = market.neg_risk_market_id {
-            neg_risk_groups.entry(neg_id.clone()).or_insert_with(Vec::new).push(market);
+            neg_risk_groups.entry(neg_id.clone()).or_default().push(market);
         }
     }
 
@@ -0,0 +1,397 @@
+use crate::metrics::EXPECTED_PNL;
+use crate::opportunity_store::OpportunityStore;
+use crate::shared_types::Opportunity;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tracing::{debug, warn};
+
+/// Alert kinds, as used for template keys.
+pub const ALERT_KINDS: [&str; 7] = ["opportunity", "execution", "error", "daily_summary", "daily_report", "low_balance", "market_resolved"];
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{backend} rejected the alert with status {status}")]
+    Rejected { backend: &'static str, status: reqwest::StatusCode },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alert {
+    /// `profit` is net of modeled fees, per share.
+    Opportunity { id: u64, strategy: &'static str, markets: String, profit: Decimal },
+    Execution { strategy: &'static str, market_id: String, amount: Decimal, expected_pnl: Decimal },
+    Error { context: String, message: String },
+    DailySummary { date: NaiveDate, detected: usize, acted_on: usize, expected_pnl: Decimal },
+    DailyReport { date: NaiveDate, executions: usize, win_rate: Decimal, realized_pnl: Decimal, unrealized_pnl: Decimal, fees: Decimal, gas: Decimal },
+    LowBalance { wallet: String, asset: &'static str, balance: Decimal, threshold: Decimal },
+    MarketResolved { market_id: String, question: String, outcome: String },
+}
+
+impl Alert {
+    pub fn opportunity(opportunity: &Opportunity) -> Self {
+        let meta = opportunity.meta();
+        Alert::Opportunity {
+            id: meta.id,
+            strategy: meta.strategy,
+            markets: opportunity.markets().join(", "),
+            profit: meta.profit_per_share().round_dp(4),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Alert::Opportunity { .. } => "opportunity",
+            Alert::Execution { .. } => "execution",
+            Alert::Error { .. } => "error",
+            Alert::DailySummary { .. } => "daily_summary",
+            Alert::DailyReport { .. } => "daily_report",
+            Alert::LowBalance { .. } => "low_balance",
+            Alert::MarketResolved { .. } => "market_resolved",
+        }
+    }
+
+    /// Placeholder values available to this alert's template.
+    pub fn fields(&self) -> HashMap<&'static str, String> {
+        let pairs: Vec<(&'static str, String)> = match self {
+            Alert::Opportunity { id, strategy, markets, profit } => {
+                vec![("id", id.to_string()), ("strategy", strategy.to_string()), ("markets", markets.clone()), ("profit", profit.to_string())]
+            }
+            Alert::Execution { strategy, market_id, amount, expected_pnl } => vec![
+                ("strategy", strategy.to_string()),
+                ("market_id", market_id.clone()),
+                ("amount", amount.to_string()),
+                ("expected_pnl", expected_pnl.round_dp(2).to_string()),
+            ],
+            Alert::Error { context, message } => vec![("context", context.clone()), ("message", message.clone())],
+            Alert::DailySummary { date, detected, acted_on, expected_pnl } => vec![
+                ("date", date.to_string()),
+                ("detected", detected.to_string()),
+                ("acted_on", acted_on.to_string()),
+                ("expected_pnl", expected_pnl.round_dp(2).to_string()),
+            ],
+            Alert::DailyReport { date, executions, win_rate, realized_pnl, unrealized_pnl, fees, gas } => vec![
+                ("date", date.to_string()),
+                ("executions", executions.to_string()),
+                ("win_rate", (win_rate * Decimal::ONE_HUNDRED).round_dp(1).to_string()),
+                ("realized_pnl", realized_pnl.round_dp(2).to_string()),
+                ("unrealized_pnl", unrealized_pnl.round_dp(2).to_string()),
+                ("fees", fees.round_dp(2).to_string()),
+                ("gas", gas.round_dp(2).to_string()),
+            ],
+            Alert::LowBalance { wallet, asset, balance, threshold } => vec![
+                ("wallet", wallet.clone()),
+                ("asset", asset.to_string()),
+                ("balance", balance.round_dp(4).to_string()),
+                ("threshold", threshold.to_string()),
+            ],
+            Alert::MarketResolved { market_id, question, outcome } => {
+                vec![("market_id", market_id.clone()), ("question", question.clone()), ("outcome", outcome.clone())]
+            }
+        };
+        pairs.into_iter().collect()
+    }
+}
+
+fn default_template(kind: &str) -> &'static str {
+    match kind {
+        "opportunity" => "Opportunity #{id} ({strategy}) on {markets}: {profit}/share",
+        "execution" => "Executed {strategy} on {market_id}: {amount} USDC, expected PnL ${expected_pnl}",
+        "error" => "Error in {context}: {message}",
+        "low_balance" => "Low {asset} balance on {wallet}: {balance} (alert below {threshold})",
+        "market_resolved" => "Resolved {question} ({market_id}): {outcome}",
+        "daily_report" => "Daily report {date}: {executions} executions, {win_rate}% won, realized ${realized_pnl}, unrealized ${unrealized_pnl}, fees ${fees}, gas ${gas}",
+        _ => "Daily summary {date}: {detected} opportunities, {acted_on} executed, expected PnL ${expected_pnl}",
+    }
+}
+
+/// Replaces every `{field}` in `template` with the alert's value. Unknown placeholders are kept.
+pub fn render(template: &str, alert: &Alert) -> String {
+    alert.fields().iter().fold(template.to_string(), |text, (key, value)| text.replace(&format!("{{{}}}", key), value))
+}
+
+/// A destination for rendered alerts.
+#[async_trait]
+pub trait AlertBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn send(&self, alert: &Alert, text: &str) -> Result<(), NotifyError>;
+}
+
+async fn post_json(http: &reqwest::Client, backend: &'static str, url: &str, body: serde_json::Value) -> Result<(), NotifyError> {
+    let response = http.post(url).json(&body).send().await?;
+    if !response.status().is_success() {
+        return Err(NotifyError::Rejected { backend, status: response.status() });
+    }
+    Ok(())
+}
+
+/// Telegram Bot API `sendMessage` to a single chat.
+pub struct TelegramBackend {
+    http: reqwest::Client,
+    token: String,
+    chat_id: String,
+}
+
+impl TelegramBackend {
+    pub fn new(token: &str, chat_id: &str) -> Self {
+        Self { http: reqwest::Client::new(), token: token.to_string(), chat_id: chat_id.to_string() }
+    }
+}
+
+#[async_trait]
+impl AlertBackend for TelegramBackend {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send(&self, _alert: &Alert, text: &str) -> Result<(), NotifyError> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.token);
+        post_json(&self.http, self.name(), &url, serde_json::json!({ "chat_id": self.chat_id, "text": text })).await
+    }
+}
+
+/// Discord channel webhook.
+pub struct DiscordBackend {
+    http: reqwest::Client,
+    webhook_url: String,
+}
+
+impl DiscordBackend {
+    pub fn new(webhook_url: &str) -> Self {
+        Self { http: reqwest::Client::new(), webhook_url: webhook_url.to_string() }
+    }
+}
+
+#[async_trait]
+impl AlertBackend for DiscordBackend {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, _alert: &Alert, text: &str) -> Result<(), NotifyError> {
+        post_json(&self.http, self.name(), &self.webhook_url, serde_json::json!({ "content": text })).await
+    }
+}
+
+/// Any HTTP endpoint. Receives `{"kind", "text", "fields"}` so it can route on structure.
+pub struct WebhookBackend {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl WebhookBackend {
+    pub fn new(url: &str) -> Self {
+        Self { http: reqwest::Client::new(), url: url.to_string() }
+    }
+}
+
+#[async_trait]
+impl AlertBackend for WebhookBackend {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, alert: &Alert, text: &str) -> Result<(), NotifyError> {
+        let body = serde_json::json!({ "kind": alert.kind(), "text": text, "fields": alert.fields() });
+        post_json(&self.http, self.name(), &self.url, body).await
+    }
+}
+
+/// Sliding one-minute window shared by every backend.
+#[derive(Debug)]
+struct RateLimiter {
+    max: usize,
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    fn allow(&mut self, now: Instant) -> bool {
+        while self.sent.front().is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.max {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+/// Renders alerts and fans them out to every backend. Opportunities below
+/// `min_opportunity_profit` are skipped and at most `max_per_minute` alerts are sent;
+/// daily summaries bypass the limit.
+pub struct Notifier {
+    backends: Vec<Box<dyn AlertBackend>>,
+    templates: HashMap<String, String>,
+    limiter: RateLimiter,
+    min_opportunity_profit: Decimal,
+}
+
+impl Notifier {
+    pub fn new(max_per_minute: usize, min_opportunity_profit: Decimal) -> Self {
+        Self {
+            backends: Vec::new(),
+            templates: HashMap::new(),
+            limiter: RateLimiter { max: max_per_minute, sent: VecDeque::new() },
+            min_opportunity_profit,
+        }
+    }
+
+    pub fn with_backend(mut self, backend: impl AlertBackend + 'static) -> Self {
+        self.backends.push(Box::new(backend));
+        self
+    }
+
+    /// Overrides the built-in template of each kind present in `templates`.
+    pub fn with_templates(mut self, templates: HashMap<String, String>) -> Self {
+        self.templates.extend(templates);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    pub fn render(&self, alert: &Alert) -> String {
+        let kind = alert.kind();
+        render(self.templates.get(kind).map_or(default_template(kind), String::as_str), alert)
+    }
+
+    /// Sends `alert` now unless it is filtered or rate limited. Backend failures are logged.
+    pub async fn dispatch(&mut self, alert: &Alert) {
+        match alert {
+            Alert::Opportunity { profit, .. } if *profit < self.min_opportunity_profit => return,
+            Alert::DailySummary { .. } | Alert::DailyReport { .. } => {}
+            _ if !self.limiter.allow(Instant::now()) => {
+                debug!(kind = alert.kind(), "alert dropped by rate limit");
+                return;
+            }
+            _ => {}
+        }
+        let text = self.render(alert);
+        for backend in &self.backends {
+            if let Err(e) = backend.send(alert, &text).await {
+                warn!(backend = backend.name(), error = %e, "failed to send alert");
+            }
+        }
+    }
+
+    /// Moves the notifier onto its own task so callers never wait on a backend.
+    pub fn spawn(mut self) -> (NotifierHandle, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Alert>();
+        let task = tokio::spawn(async move {
+            while let Some(alert) = rx.recv().await {
+                self.dispatch(&alert).await;
+            }
+        });
+        (NotifierHandle { tx: Some(tx) }, task)
+    }
+}
+
+/// Cheap, cloneable sender for alerts. A disabled handle drops everything.
+#[derive(Debug, Clone, Default)]
+pub struct NotifierHandle {
+    tx: Option<mpsc::UnboundedSender<Alert>>,
+}
+
+impl NotifierHandle {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn notify(&self, alert: Alert) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(alert);
+        }
+    }
+}
+
+/// Time from `now` until the next `hour`:00 UTC.
+pub(crate) fn until_next(now: DateTime<Utc>, hour: u32) -> chrono::Duration {
+    let today = now.date_naive().and_hms_opt(hour, 0, 0).unwrap_or_default().and_utc();
+    let next = if today > now { today } else { today + chrono::Duration::days(1) };
+    next - now
+}
+
+/// Sends a summary of the last 24 hours every day at `hour` UTC: opportunities detected and
+/// executed from the store, and the expected PnL of executions since the previous summary. Runs until aborted.
+pub fn spawn_daily_summary(alerts: NotifierHandle, store: Arc<OpportunityStore>, hour: u32) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut pnl_at_last = EXPECTED_PNL.get();
+        loop {
+            let wait = until_next(Utc::now(), hour).to_std().unwrap_or_default();
+            sleep(wait).await;
+            let now = Utc::now();
+            let (detected, acted_on) = match store.between(now - chrono::Duration::days(1), now) {
+                Ok(records) => (records.len(), records.iter().filter(|r| r.acted_on).count()),
+                Err(e) => {
+                    warn!(error = %e, "daily summary could not read the opportunity store");
+                    (0, 0)
+                }
+            };
+            let pnl = EXPECTED_PNL.get();
+            let expected_pnl = Decimal::from_f64(pnl - pnl_at_last).unwrap_or_default();
+            pnl_at_last = pnl;
+            alerts.notify(Alert::DailySummary { date: now.date_naive(), detected, acted_on, expected_pnl });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl AlertBackend for Capture {
+        fn name(&self) -> &'static str {
+            "capture"
+        }
+
+        async fn send(&self, _alert: &Alert, text: &str) -> Result<(), NotifyError> {
+            self.0.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_templates_threshold_and_rate_limit() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let templates = HashMap::from([("error".to_string(), "[{context}] {message} {unknown}".to_string())]);
+        let mut notifier = Notifier::new(2, dec!(0.05)).with_backend(Capture(sent.clone())).with_templates(templates);
+
+        let opportunity = |profit| Alert::Opportunity { id: 7, strategy: "rebalancing", markets: "m1".to_string(), profit };
+        notifier.dispatch(&opportunity(dec!(0.01))).await;
+        notifier.dispatch(&opportunity(dec!(0.08))).await;
+        notifier.dispatch(&Alert::Error { context: "prices".to_string(), message: "closed".to_string() }).await;
+        // Third alert inside the window is dropped, but the daily summary still goes out
+        notifier.dispatch(&opportunity(dec!(0.09))).await;
+        let summary = Alert::DailySummary { date: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(), detected: 7, acted_on: 2, expected_pnl: dec!(12.346) };
+        notifier.dispatch(&summary).await;
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            "Opportunity #7 (rebalancing) on m1: 0.08/share".to_string(),
+            "[prices] closed {unknown}".to_string(),
+            "Daily summary 2025-01-02: 7 opportunities, 2 executed, expected PnL $12.35".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_until_next_summary_hour() {
+        let now = DateTime::parse_from_rfc3339("2025-01-02T10:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(until_next(now, 12), chrono::Duration::minutes(90));
+        assert_eq!(until_next(now, 0), chrono::Duration::minutes(13 * 60 + 30));
+    }
+}
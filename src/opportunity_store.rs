@@ -0,0 +1,379 @@
+use crate::shared_types::{Leg, Opportunity};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Mutex;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("corrupt record: {0}")]
+    Corrupt(String),
+    #[error("opportunity store lock poisoned")]
+    LockPoisoned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpportunityKind {
+    Rebalancing,
+    Combinatorial,
+    NegRiskBasket,
+    MultiLeg,
+    ThresholdLadder,
+}
+
+impl OpportunityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpportunityKind::Rebalancing => "rebalancing",
+            OpportunityKind::Combinatorial => "combinatorial",
+            OpportunityKind::NegRiskBasket => "neg_risk_basket",
+            OpportunityKind::MultiLeg => "multi_leg",
+            OpportunityKind::ThresholdLadder => "threshold_ladder",
+        }
+    }
+}
+
+impl FromStr for OpportunityKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rebalancing" => Ok(OpportunityKind::Rebalancing),
+            "combinatorial" => Ok(OpportunityKind::Combinatorial),
+            "neg_risk_basket" => Ok(OpportunityKind::NegRiskBasket),
+            "multi_leg" => Ok(OpportunityKind::MultiLeg),
+            "threshold_ladder" => Ok(OpportunityKind::ThresholdLadder),
+            other => Err(format!("unknown strategy: {}", other)),
+        }
+    }
+}
+
+/// A single logged opportunity as read back from the store.
+#[derive(Debug, Clone)]
+pub struct OpportunityRecord {
+    pub id: i64,
+    pub detected_at: DateTime<Utc>,
+    pub kind: String,
+    pub market_id_1: String,
+    pub market_id_2: Option<String>,
+    pub detail: String,
+    pub prices: Vec<Decimal>,
+    /// Net profit per share.
+    pub profit: Decimal,
+    pub acted_on: bool,
+    /// `OpportunityMeta` fields, absent on rows logged before they were stored.
+    pub opportunity_id: Option<u64>,
+    pub asset_ids: Vec<String>,
+    pub size: Option<Decimal>,
+    pub net_profit: Option<Decimal>,
+}
+
+/// One execution attempt. Failed attempts are kept with no PnL, since they count against the
+/// strategy's win rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionRecord {
+    pub executed_at: DateTime<Utc>,
+    /// Row of the opportunity executed, when it was logged.
+    pub opportunity_id: Option<i64>,
+    pub kind: String,
+    pub market_id: String,
+    /// USDC committed.
+    pub amount: Decimal,
+    /// Net profit the detector priced in, booked when the execution completes.
+    pub expected_pnl: Decimal,
+    /// Taker fees on the legs, in USDC.
+    pub fees: Decimal,
+    /// Gas paid by the execution's transaction, in USD.
+    pub gas: Decimal,
+    pub succeeded: bool,
+}
+
+/// Detected vs. acted-on counts for a single opportunity kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitRate {
+    pub kind: String,
+    pub detected: u64,
+    pub acted_on: u64,
+}
+
+impl HitRate {
+    pub fn rate(&self) -> f64 {
+        if self.detected == 0 { 0.0 } else { self.acted_on as f64 / self.detected as f64 }
+    }
+}
+
+/// SQLite-backed log of every opportunity the engine detects.
+pub struct OpportunityStore {
+    conn: Mutex<Connection>,
+}
+
+impl OpportunityStore {
+    pub fn open(path: &str) -> Result<Self, StoreError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StoreError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS opportunities (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                detected_at TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                market_id_1 TEXT NOT NULL,
+                market_id_2 TEXT,
+                detail TEXT NOT NULL,
+                prices TEXT NOT NULL,
+                profit TEXT NOT NULL,
+                acted_on INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_opportunities_detected_at ON opportunities(detected_at);
+            CREATE TABLE IF NOT EXISTS executions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                executed_at TEXT NOT NULL,
+                opportunity_id INTEGER,
+                kind TEXT NOT NULL,
+                market_id TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                expected_pnl TEXT NOT NULL,
+                fees TEXT NOT NULL,
+                gas TEXT NOT NULL,
+                succeeded INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_executions_executed_at ON executions(executed_at);",
+        )?;
+        // Columns added since the table was first created
+        let columns: Vec<String> = conn.prepare("SELECT name FROM pragma_table_info('opportunities')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for (column, ty) in [("opportunity_id", "INTEGER"), ("asset_ids", "TEXT"), ("size", "TEXT"), ("net_profit", "TEXT")] {
+            if !columns.iter().any(|c| c == column) {
+                conn.execute_batch(&format!("ALTER TABLE opportunities ADD COLUMN {} {}", column, ty))?;
+            }
+        }
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    pub fn record(&self, opportunity: &Opportunity, acted_on: bool) -> Result<i64, StoreError> {
+        let legs = |legs: &[Leg]| legs.iter().map(|l| format!("{}:{}", l.market_id, l.condition_name)).collect::<Vec<_>>();
+        let (market_id_1, market_id_2, detail) = match opportunity {
+            Opportunity::Rebalancing { op, .. } => (&op.market_id, None, op.opportunity_type.clone()),
+            Opportunity::NegRiskBasket { op, .. } => (&op.neg_risk_market_id, None, format!("{} [{}]", op.opportunity_type, op.market_ids.join(","))),
+            Opportunity::ThresholdLadder { op, .. } => {
+                (&op.legs[0].market_id, op.legs.last().map(|l| &l.market_id), format!("{} [{}]", op.ladder, legs(&op.legs).join(", ")))
+            }
+            Opportunity::Combinatorial { op, .. } => {
+                let detail = format!("{} => {} ({}, confidence {:.2})", op.condition_name_1, op.condition_name_2, op.explanation, op.confidence);
+                (&op.market_id_1, Some(&op.market_id_2), detail)
+            }
+            Opportunity::MultiLeg { op, .. } => {
+                let detail = format!("{}{}", if op.is_cycle { "cycle " } else { "" }, legs(&op.legs).join(" => "));
+                (&op.legs[0].market_id, op.legs.last().map(|l| &l.market_id), detail)
+            }
+        };
+        let meta = opportunity.meta();
+        let prices_json = serde_json::to_string(&meta.prices.iter().map(|p| p.to_string()).collect::<Vec<_>>())?;
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        conn.execute(
+            "INSERT INTO opportunities (detected_at, kind, market_id_1, market_id_2, detail, prices, profit, acted_on, opportunity_id, asset_ids, size, net_profit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                meta.detected_at.to_rfc3339(), meta.strategy, market_id_1, market_id_2, detail, prices_json,
+                meta.profit_per_share().to_string(), acted_on, meta.id as i64, serde_json::to_string(&meta.asset_ids)?,
+                meta.size.to_string(), meta.net_profit.to_string(),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Flags a previously logged opportunity as executed.
+    pub fn mark_acted_on(&self, id: i64) -> Result<(), StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        conn.execute("UPDATE opportunities SET acted_on = 1 WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn record_execution(&self, execution: &ExecutionRecord) -> Result<i64, StoreError> {
+        let e = execution;
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        conn.execute(
+            "INSERT INTO executions (executed_at, opportunity_id, kind, market_id, amount, expected_pnl, fees, gas, succeeded)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                e.executed_at.to_rfc3339(), e.opportunity_id, e.kind, e.market_id, e.amount.to_string(),
+                e.expected_pnl.to_string(), e.fees.to_string(), e.gas.to_string(), e.succeeded
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Executions attempted within `[from, to)`, oldest first.
+    pub fn executions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<ExecutionRecord>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare(
+            "SELECT executed_at, opportunity_id, kind, market_id, amount, expected_pnl, fees, gas, succeeded
+             FROM executions WHERE executed_at >= ?1 AND executed_at < ?2 ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![from.to_rfc3339(), to.to_rfc3339()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                [row.get::<_, String>(4)?, row.get::<_, String>(5)?, row.get::<_, String>(6)?, row.get::<_, String>(7)?],
+                row.get::<_, bool>(8)?,
+            ))
+        })?;
+
+        let decimal = |text: &str| Decimal::from_str(text).map_err(|e| StoreError::Corrupt(e.to_string()));
+        let mut executions = Vec::new();
+        for row in rows {
+            let (executed_at, opportunity_id, kind, market_id, [amount, expected_pnl, fees, gas], succeeded) = row?;
+            executions.push(ExecutionRecord {
+                executed_at: DateTime::parse_from_rfc3339(&executed_at).map_err(|e| StoreError::Corrupt(e.to_string()))?.with_timezone(&Utc),
+                opportunity_id,
+                kind,
+                market_id,
+                amount: decimal(&amount)?,
+                expected_pnl: decimal(&expected_pnl)?,
+                fees: decimal(&fees)?,
+                gas: decimal(&gas)?,
+                succeeded,
+            });
+        }
+        Ok(executions)
+    }
+
+    /// Most recent opportunities, newest first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<OpportunityRecord>, StoreError> {
+        self.query("SELECT id, detected_at, kind, market_id_1, market_id_2, detail, prices, profit, acted_on, opportunity_id, asset_ids, size, net_profit
+                    FROM opportunities ORDER BY id DESC LIMIT ?1", params![limit as i64])
+    }
+
+    /// Opportunities detected within `[from, to)`.
+    pub fn between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<OpportunityRecord>, StoreError> {
+        self.query("SELECT id, detected_at, kind, market_id_1, market_id_2, detail, prices, profit, acted_on, opportunity_id, asset_ids, size, net_profit
+                    FROM opportunities WHERE detected_at >= ?1 AND detected_at < ?2 ORDER BY id", params![from.to_rfc3339(), to.to_rfc3339()])
+    }
+
+    /// Detected and acted-on counts grouped by opportunity kind.
+    pub fn hit_rates(&self) -> Result<Vec<HitRate>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare("SELECT kind, COUNT(*), SUM(acted_on) FROM opportunities GROUP BY kind ORDER BY kind")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(HitRate { kind: row.get(0)?, detected: row.get::<_, i64>(1)? as u64, acted_on: row.get::<_, i64>(2)? as u64 })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<OpportunityRecord>, StoreError> {
+        let conn = self.conn.lock().map_err(|_| StoreError::LockPoisoned)?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, bool>(8)?,
+                row.get::<_, Option<i64>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+                row.get::<_, Option<String>>(11)?,
+                row.get::<_, Option<String>>(12)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (id, detected_at, kind, market_id_1, market_id_2, detail, prices, profit, acted_on, opportunity_id, asset_ids, size, net_profit) = row?;
+            let prices: Vec<String> = serde_json::from_str(&prices)?;
+            let optional = |value: Option<String>| value.map(|v| Decimal::from_str(&v).map_err(|e| StoreError::Corrupt(e.to_string()))).transpose();
+            records.push(OpportunityRecord {
+                id,
+                detected_at: DateTime::parse_from_rfc3339(&detected_at).map_err(|e| StoreError::Corrupt(e.to_string()))?.with_timezone(&Utc),
+                kind,
+                market_id_1,
+                market_id_2,
+                detail,
+                prices: prices.iter().filter_map(|p| Decimal::from_str(p).ok()).collect(),
+                profit: Decimal::from_str(&profit).map_err(|e| StoreError::Corrupt(e.to_string()))?,
+                acted_on,
+                opportunity_id: opportunity_id.map(|id| id as u64),
+                asset_ids: asset_ids.map(|ids| serde_json::from_str(&ids)).transpose()?.unwrap_or_default(),
+                size: optional(size)?,
+                net_profit: optional(net_profit)?,
+            });
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profit_model::ExecutionEstimate;
+    use crate::shared_types::{CombinatorialOpportunity, MultiLegOpportunity};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_record_and_hit_rates() {
+        let store = OpportunityStore::open_in_memory().unwrap();
+        let leg = |market: &str, price| Leg { market_id: market.to_string(), condition_name: "Yes".to_string(), asset_id: format!("{}-yes", market), price };
+        let chain = || Opportunity::multi_leg(MultiLegOpportunity { legs: vec![leg("m1", dec!(0.45)), leg("m2", dec!(0.5))], is_cycle: false, profit: dec!(0.05) });
+        let comb = CombinatorialOpportunity {
+            market_id_1: "m1".to_string(),
+            market_id_2: "m2".to_string(),
+            condition_name_1: "a".to_string(),
+            condition_name_2: "b".to_string(),
+            implying_asset_id: "a-yes".to_string(),
+            implied_asset_id: "b-yes".to_string(),
+            implying_complement_asset_id: Some("a-no".to_string()),
+            profit: dec!(0.1),
+            implying_fees: None,
+            implied_fees: None,
+            confidence: 0.9,
+            explanation: String::new(),
+        };
+        let estimate = ExecutionEstimate {
+            size: dec!(50),
+            avg_buy_price: dec!(0.5),
+            avg_sell_price: dec!(0.6),
+            max_buy_price: dec!(0.5),
+            min_sell_price: dec!(0.6),
+            slippage: Decimal::ZERO,
+            gross_profit: dec!(5),
+            fees: dec!(0.5),
+            gas_cost: dec!(0.5),
+            net_profit: dec!(4),
+        };
+        let comb = Opportunity::combinatorial(comb, estimate, vec![dec!(0.6), dec!(0.5)]);
+
+        let id = store.record(&chain(), false).unwrap();
+        store.record(&chain(), false).unwrap();
+        store.record(&comb, true).unwrap();
+        store.mark_acted_on(id).unwrap();
+
+        let rates = store.hit_rates().unwrap();
+        assert_eq!(rates[0], HitRate { kind: "combinatorial".to_string(), detected: 1, acted_on: 1 });
+        assert_eq!(rates[1], HitRate { kind: "multi_leg".to_string(), detected: 2, acted_on: 1 });
+
+        let recent = store.recent(1).unwrap();
+        assert_eq!(recent[0].market_id_2.as_deref(), Some("m2"));
+        assert_eq!(recent[0].prices, vec![dec!(0.6), dec!(0.5)]);
+        assert_eq!(recent[0].opportunity_id, Some(comb.meta().id));
+        assert_eq!(recent[0].asset_ids, vec!["a-yes", "b-yes"]);
+        assert_eq!((recent[0].size, recent[0].net_profit, recent[0].profit), (Some(dec!(50)), Some(dec!(4)), dec!(0.08)));
+    }
+}
@@ -0,0 +1,140 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Order books keyed by asset_id, shared between the websocket task and the engine.
+pub type SharedOrderBooks = Arc<RwLock<HashMap<String, OrderBook>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+/// L2 ladder for a single outcome token.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl OrderBook {
+    /// Replaces both ladders with a full snapshot from the `book` channel.
+    pub fn apply_snapshot(&mut self, bids: &[PriceLevel], asks: &[PriceLevel]) {
+        self.bids.clear();
+        self.asks.clear();
+        for level in bids {
+            self.apply_level(BookSide::Bid, level.price, level.size);
+        }
+        for level in asks {
+            self.apply_level(BookSide::Ask, level.price, level.size);
+        }
+    }
+
+    /// Sets the resting size at a price level. A zero size removes the level.
+    pub fn apply_level(&mut self, side: BookSide, price: Decimal, size: Decimal) {
+        let ladder = self.ladder_mut(side);
+        if size.is_zero() {
+            ladder.remove(&price);
+        } else {
+            ladder.insert(price, size);
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(|(&price, &size)| PriceLevel { price, size })
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(|(&price, &size)| PriceLevel { price, size })
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Some((bid.price + ask.price) / Decimal::TWO),
+            _ => None,
+        }
+    }
+
+    /// Size resting at exactly `price` on the given side.
+    pub fn depth_at_price(&self, side: BookSide, price: Decimal) -> Decimal {
+        self.ladder(side).get(&price).copied().unwrap_or_default()
+    }
+
+    /// Cumulative size available to a taker up to (and including) `limit_price`.
+    /// Asks are consumed upwards from the best ask, bids downwards from the best bid.
+    pub fn fillable_size(&self, side: BookSide, limit_price: Decimal) -> Decimal {
+        match side {
+            BookSide::Ask => self.asks.range(..=limit_price).map(|(_, &size)| size).sum(),
+            BookSide::Bid => self.bids.range(limit_price..).map(|(_, &size)| size).sum(),
+        }
+    }
+
+    /// Levels ordered from the top of the book outwards.
+    pub fn levels(&self, side: BookSide) -> Vec<PriceLevel> {
+        let iter = self.ladder(side).iter().map(|(&price, &size)| PriceLevel { price, size });
+        match side {
+            BookSide::Bid => iter.rev().collect(),
+            BookSide::Ask => iter.collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    fn ladder(&self, side: BookSide) -> &BTreeMap<Decimal, Decimal> {
+        match side {
+            BookSide::Bid => &self.bids,
+            BookSide::Ask => &self.asks,
+        }
+    }
+
+    fn ladder_mut(&mut self, side: BookSide) -> &mut BTreeMap<Decimal, Decimal> {
+        match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn sample_book() -> OrderBook {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(
+            &[PriceLevel { price: dec!(0.48), size: dec!(100) }, PriceLevel { price: dec!(0.47), size: dec!(250) }],
+            &[PriceLevel { price: dec!(0.52), size: dec!(80) }, PriceLevel { price: dec!(0.55), size: dec!(300) }],
+        );
+        book
+    }
+
+    #[test]
+    fn test_best_levels_and_depth() {
+        let book = sample_book();
+        assert_eq!(book.best_bid().unwrap().price, dec!(0.48));
+        assert_eq!(book.best_ask().unwrap().price, dec!(0.52));
+        assert_eq!(book.mid_price(), Some(dec!(0.50)));
+        assert_eq!(book.depth_at_price(BookSide::Ask, dec!(0.55)), dec!(300));
+        assert_eq!(book.fillable_size(BookSide::Ask, dec!(0.53)), dec!(80));
+        assert_eq!(book.fillable_size(BookSide::Bid, dec!(0.47)), dec!(350));
+    }
+
+    #[test]
+    fn test_zero_size_removes_level() {
+        let mut book = sample_book();
+        book.apply_level(BookSide::Ask, dec!(0.52), dec!(0));
+        assert_eq!(book.best_ask().unwrap().price, dec!(0.55));
+    }
+}
@@ -0,0 +1,441 @@
+use crate::blockchain::{BlockchainCollector, OrderFilledFilter};
+use crate::clob_client::{ClobClient, ClobEventHandler, OpenOrder, OrderSide, OrderStatus, OrderUpdateKind, Trade, TradeStatus, UserOrderUpdate};
+use crate::fill_ingest::FillRecord;
+use crate::fill_stream::FillEvent;
+use crate::units::ShareAmount;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::types::H256;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::RwLock;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+pub type SharedOrderTracker = Arc<RwLock<OrderTracker>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    Placed,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+impl OrderState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderState::Filled | OrderState::Cancelled | OrderState::Expired)
+    }
+}
+
+impl fmt::Display for OrderState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            OrderState::Placed => "placed",
+            OrderState::PartiallyFilled => "partially_filled",
+            OrderState::Filled => "filled",
+            OrderState::Cancelled => "cancelled",
+            OrderState::Expired => "expired",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Where a fill report came from. The chain and the CLOB report the same matches, so each
+/// source keeps its own running total and the order is as filled as the furthest one says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillSource {
+    Chain,
+    Clob,
+}
+
+/// State machine for one placed order: Placed -> PartiallyFilled -> Filled, or
+/// Cancelled/Expired while resting. Fills that arrive after a cancel still count, since the
+/// match may have happened before the cancel was processed.
+#[derive(Debug, Clone)]
+pub struct OrderLifecycle {
+    pub order_id: String,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub state: OrderState,
+    pub placed_at: DateTime<Utc>,
+    chain_filled: Decimal,
+    clob_filled: Decimal,
+    seen_fills: HashSet<String>,
+}
+
+impl OrderLifecycle {
+    pub fn new(order_id: &str, asset_id: &str, side: OrderSide, price: Decimal, size: Decimal, placed_at: DateTime<Utc>) -> Self {
+        Self {
+            order_id: normalize_order_id(order_id),
+            asset_id: asset_id.to_string(),
+            side,
+            price,
+            size,
+            state: OrderState::Placed,
+            placed_at,
+            chain_filled: Decimal::ZERO,
+            clob_filled: Decimal::ZERO,
+            seen_fills: HashSet::new(),
+        }
+    }
+
+    /// Shares filled so far.
+    pub fn filled(&self) -> Decimal {
+        self.chain_filled.max(self.clob_filled).min(self.size)
+    }
+
+    pub fn remaining(&self) -> Decimal {
+        self.size - self.filled()
+    }
+
+    /// Applies a fill of `shares`. `fill_id` identifies the report so replays are ignored.
+    pub fn record_fill(&mut self, source: FillSource, fill_id: &str, shares: Decimal) -> OrderState {
+        if self.seen_fills.insert(format!("{:?}:{}", source, fill_id)) {
+            match source {
+                FillSource::Chain => self.chain_filled += shares,
+                FillSource::Clob => self.clob_filled += shares,
+            }
+        }
+        self.settle()
+    }
+
+    /// Undoes `record_fill` for a fill that is no longer on the chain. A fill-driven state
+    /// steps back; cancels and expiries stay.
+    pub fn revert_fill(&mut self, source: FillSource, fill_id: &str, shares: Decimal) -> OrderState {
+        if self.seen_fills.remove(&format!("{:?}:{}", source, fill_id)) {
+            match source {
+                FillSource::Chain => self.chain_filled -= shares,
+                FillSource::Clob => self.clob_filled -= shares,
+            }
+            let filled = self.filled();
+            if matches!(self.state, OrderState::Filled | OrderState::PartiallyFilled) && filled < self.size {
+                self.state = if filled > Decimal::ZERO { OrderState::PartiallyFilled } else { OrderState::Placed };
+            }
+        }
+        self.state
+    }
+
+    /// Takes the CLOB's cumulative matched size as authoritative for the CLOB source.
+    pub fn record_status(&mut self, order: &OpenOrder) -> OrderState {
+        self.clob_filled = self.clob_filled.max(order.size_matched);
+        match order.status {
+            OrderStatus::Canceled | OrderStatus::Unmatched => self.cancel(),
+            _ => self.settle(),
+        }
+    }
+
+    /// Applies a user-channel order event.
+    pub fn record_order_update(&mut self, update: &UserOrderUpdate) -> OrderState {
+        self.clob_filled = self.clob_filled.max(update.size_matched);
+        match update.kind {
+            OrderUpdateKind::Cancellation => self.cancel(),
+            OrderUpdateKind::Placement | OrderUpdateKind::Update => self.settle(),
+        }
+    }
+
+    pub fn cancel(&mut self) -> OrderState {
+        self.settle();
+        if !self.state.is_terminal() {
+            self.state = OrderState::Cancelled;
+        }
+        self.state
+    }
+
+    pub fn expire(&mut self) -> OrderState {
+        self.settle();
+        if !self.state.is_terminal() {
+            self.state = OrderState::Expired;
+        }
+        self.state
+    }
+
+    fn settle(&mut self) -> OrderState {
+        let filled = self.filled();
+        if filled >= self.size {
+            // A complete fill wins even over a cancel that raced it
+            self.state = OrderState::Filled;
+        } else if !self.state.is_terminal() && filled > Decimal::ZERO {
+            self.state = OrderState::PartiallyFilled;
+        }
+        self.state
+    }
+}
+
+/// Order hashes arrive as `0x`-prefixed hex in mixed case from the CLOB and as `H256` from logs.
+fn normalize_order_id(id: &str) -> String {
+    let hex = id.trim().trim_start_matches("0x").to_lowercase();
+    format!("0x{}", hex)
+}
+
+/// Every CLOB order this process placed, keyed by order hash.
+#[derive(Debug, Default)]
+pub struct OrderTracker {
+    orders: HashMap<String, OrderLifecycle>,
+}
+
+impl OrderTracker {
+    pub fn track(&mut self, order: OrderLifecycle) {
+        self.orders.insert(order.order_id.clone(), order);
+    }
+
+    pub fn get(&self, order_id: &str) -> Option<&OrderLifecycle> {
+        self.orders.get(&normalize_order_id(order_id))
+    }
+
+    pub fn open_orders(&self) -> impl Iterator<Item = &OrderLifecycle> {
+        self.orders.values().filter(|o| !o.state.is_terminal())
+    }
+
+    pub fn open_order_hashes(&self) -> Vec<H256> {
+        self.open_orders().filter_map(|o| o.order_id.parse().ok()).collect()
+    }
+
+    /// Applies an on-chain `OrderFilled` log. `fill_id` should be unique per log, e.g. `tx:index`.
+    pub fn apply_chain_fill(&mut self, fill: &OrderFilledFilter, fill_id: &str) -> Option<OrderState> {
+        let order = self.orders.get_mut(&format!("{:?}", H256::from(fill.order_hash)))?;
+        // The maker gives USDC when buying and shares when selling
+        let shares = match order.side {
+            OrderSide::Buy => ShareAmount::from_units(fill.taker_fill_amount)?,
+            OrderSide::Sell => ShareAmount::from_units(fill.maker_fill_amount)?,
+        };
+        Some(order.record_fill(FillSource::Chain, fill_id, shares.value()))
+    }
+
+    /// Applies a fill decoded from the `OrderFilled` stream; same effect as `apply_chain_fill`
+    /// for the log it came from.
+    pub fn apply_fill_record(&mut self, fill: &FillRecord) -> Option<OrderState> {
+        let order = self.orders.get_mut(&fill.order_hash)?;
+        Some(order.record_fill(FillSource::Chain, &fill.fill_id(), fill.shares()))
+    }
+
+    /// Takes back a streamed fill whose block a reorg replaced.
+    pub fn revert_fill_record(&mut self, fill: &FillRecord) -> Option<OrderState> {
+        let order = self.orders.get_mut(&fill.order_hash)?;
+        Some(order.revert_fill(FillSource::Chain, &fill.fill_id(), fill.shares()))
+    }
+
+    /// Applies a CLOB trade, whether our order was the taker or one of the makers.
+    /// Failed settlements are ignored; the CLOB retries them under the same trade id.
+    pub fn apply_trade(&mut self, trade: &Trade) -> Vec<(String, OrderState)> {
+        if trade.status == TradeStatus::Failed {
+            return Vec::new();
+        }
+        let mut updated = Vec::new();
+        let taker = (trade.taker_order_id.as_str(), trade.size);
+        let makers = trade.maker_orders.iter().map(|m| (m.order_id.as_str(), m.matched_amount));
+        for (order_id, shares) in std::iter::once(taker).chain(makers) {
+            if let Some(order) = self.orders.get_mut(&normalize_order_id(order_id)) {
+                let state = order.record_fill(FillSource::Clob, &trade.id, shares);
+                updated.push((order.order_id.clone(), state));
+            }
+        }
+        updated
+    }
+
+    pub fn apply_status(&mut self, status: &OpenOrder) -> Option<OrderState> {
+        self.orders.get_mut(&normalize_order_id(&status.id)).map(|o| o.record_status(status))
+    }
+
+    pub fn apply_order_update(&mut self, update: &UserOrderUpdate) -> Option<OrderState> {
+        self.orders.get_mut(&normalize_order_id(&update.id)).map(|o| o.record_order_update(update))
+    }
+
+    /// Marks open orders placed before `cutoff` as expired and returns their ids.
+    pub fn expire_older_than(&mut self, cutoff: DateTime<Utc>) -> Vec<String> {
+        let mut expired = Vec::new();
+        for order in self.orders.values_mut().filter(|o| !o.state.is_terminal() && o.placed_at < cutoff) {
+            order.expire();
+            expired.push(order.order_id.clone());
+        }
+        expired
+    }
+
+    /// Forgets orders that finished before `cutoff`.
+    pub fn prune(&mut self, cutoff: DateTime<Utc>) {
+        self.orders.retain(|_, o| !o.state.is_terminal() || o.placed_at >= cutoff);
+    }
+}
+
+/// Feeds the CLOB user channel into the tracker, so fills and cancels land as they happen
+/// instead of on the next reconciliation poll.
+pub struct UserChannelSync {
+    pub tracker: SharedOrderTracker,
+}
+
+#[async_trait]
+impl ClobEventHandler for UserChannelSync {
+    async fn on_trade(&mut self, trade: Trade) {
+        for (order_id, state) in self.tracker.write().await.apply_trade(&trade) {
+            info!(%order_id, %state, trade_id = %trade.id, trade_status = ?trade.status, "order updated by trade");
+        }
+    }
+
+    async fn on_order_update(&mut self, update: UserOrderUpdate) {
+        if let Some(state) = self.tracker.write().await.apply_order_update(&update) {
+            info!(order_id = %update.id, %state, kind = ?update.kind, matched = %update.size_matched, "order updated");
+        }
+    }
+}
+
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5);
+// Terminal orders are kept this long so late fills can still be attributed
+const RETENTION: chrono::Duration = chrono::Duration::hours(1);
+
+/// Polls the chain for `OrderFilled` logs of tracked orders and the CLOB for their status,
+/// cancelling and expiring orders that have rested longer than `order_ttl`. Runs until aborted.
+pub async fn run_reconciliation(tracker: SharedOrderTracker, collector: BlockchainCollector, client: Arc<ClobClient>, order_ttl: Duration) {
+    let mut from_block = match collector.latest_block().await {
+        Ok(block) => block,
+        Err(e) => {
+            warn!(error = %e, "reconciliation could not read the latest block");
+            0
+        }
+    };
+
+    loop {
+        sleep(RECONCILE_INTERVAL).await;
+        let (hashes, ids): (Vec<H256>, Vec<String>) = {
+            let t = tracker.read().await;
+            (t.open_order_hashes(), t.open_orders().map(|o| o.order_id.clone()).collect())
+        };
+        if ids.is_empty() {
+            continue;
+        }
+
+        match collector.latest_block().await {
+            Ok(latest) if latest >= from_block => match collector.fetch_order_fills(from_block, latest, hashes).await {
+                Ok(fills) => {
+                    let mut t = tracker.write().await;
+                    for (fill, fill_id) in fills {
+                        if let Some(state) = t.apply_chain_fill(&fill, &fill_id) {
+                            info!(order_hash = ?H256::from(fill.order_hash), %state, "order filled on-chain");
+                        }
+                    }
+                    from_block = latest + 1;
+                }
+                Err(e) => warn!(error = %e, "failed to fetch order fills"),
+            },
+            Ok(_) => {}
+            Err(e) => warn!(error = %e, "failed to read latest block"),
+        }
+
+        for id in &ids {
+            match client.get_order_status(id).await {
+                Ok(Some(status)) => {
+                    tracker.write().await.apply_status(&status);
+                }
+                Ok(None) => {}
+                Err(e) => warn!(order_id = %id, error = %e, "failed to fetch order status"),
+            }
+        }
+
+        let now = Utc::now();
+        let ttl = chrono::Duration::from_std(order_ttl).unwrap_or(RETENTION);
+        let expired = tracker.write().await.expire_older_than(now - ttl);
+        for id in expired {
+            info!(order_id = %id, ttl = ?order_ttl, "order rested past its TTL, cancelling");
+            if let Err(e) = client.cancel_order(&id).await {
+                warn!(order_id = %id, error = %e, "failed to cancel expired order");
+            }
+        }
+        tracker.write().await.prune(now - RETENTION);
+    }
+}
+
+/// Confirms fills of tracked orders as the `OrderFilled` stream delivers them, ahead of the
+/// reconciliation poll, and takes back fills a reorg removed. Runs until the stream closes.
+pub async fn run_stream_confirmation(tracker: SharedOrderTracker, mut fills: broadcast::Receiver<FillEvent>) {
+    loop {
+        match fills.recv().await {
+            Ok(FillEvent::Filled(fill)) => {
+                if let Some(state) = tracker.write().await.apply_fill_record(&fill) {
+                    info!(order_hash = %fill.order_hash, %state, "order fill streamed");
+                }
+            }
+            Ok(FillEvent::Removed(fill)) => {
+                if let Some(state) = tracker.write().await.revert_fill_record(&fill) {
+                    warn!(order_hash = %fill.order_hash, %state, "order fill removed by a reorg");
+                }
+            }
+            // Skipped fills are still picked up by the reconciliation poll
+            Err(RecvError::Lagged(skipped)) => warn!(skipped, "fill confirmation fell behind the stream"),
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clob_client::MakerOrderFill;
+    use ethers::types::{Address, U256};
+    use rust_decimal_macros::dec;
+
+    const HASH: &str = "0x00000000000000000000000000000000000000000000000000000000000000AB";
+
+    fn order() -> OrderLifecycle {
+        OrderLifecycle::new(HASH, "1234", OrderSide::Buy, dec!(0.5), dec!(100), Utc::now())
+    }
+
+    #[test]
+    fn test_chain_and_clob_fills_do_not_double_count() {
+        let mut tracker = OrderTracker::default();
+        tracker.track(order());
+
+        // Buy maker: 20 USDC for 40 shares
+        let fill = OrderFilledFilter {
+            order_hash: HASH.parse::<H256>().unwrap().0,
+            maker: Address::zero(),
+            taker: Address::zero(),
+            maker_asset_id: U256::zero(),
+            taker_asset_id: U256::from(7u64),
+            maker_fill_amount: U256::from(20_000_000u64),
+            taker_fill_amount: U256::from(40_000_000u64),
+            fee: U256::zero(),
+        };
+        assert_eq!(tracker.apply_chain_fill(&fill, "0x1:0"), Some(OrderState::PartiallyFilled));
+        assert_eq!(tracker.apply_chain_fill(&fill, "0x1:0"), Some(OrderState::PartiallyFilled));
+        assert_eq!(tracker.get(HASH).unwrap().filled(), dec!(40));
+
+        // The CLOB reports the same 40 shares, then the rest
+        let trade = |id: &str, shares: Decimal| Trade {
+            id: id.to_string(),
+            taker_order_id: "0xother".to_string(),
+            market: String::new(),
+            asset_id: "1234".to_string(),
+            side: OrderSide::Sell,
+            size: shares,
+            price: dec!(0.5),
+            status: TradeStatus::Matched,
+            match_time: String::new(),
+            transaction_hash: String::new(),
+            maker_orders: vec![MakerOrderFill { order_id: HASH.to_lowercase(), asset_id: "1234".to_string(), matched_amount: shares, price: dec!(0.5) }],
+        };
+        tracker.apply_trade(&trade("t1", dec!(40)));
+        assert_eq!(tracker.get(HASH).unwrap().filled(), dec!(40));
+        assert_eq!(tracker.apply_trade(&trade("t2", dec!(60))), vec![(HASH.to_lowercase(), OrderState::Filled)]);
+        assert!(tracker.open_order_hashes().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_expiry_keep_partial_fills() {
+        let mut lifecycle = order();
+        lifecycle.record_fill(FillSource::Clob, "t1", dec!(30));
+        assert_eq!(lifecycle.cancel(), OrderState::Cancelled);
+        assert_eq!(lifecycle.remaining(), dec!(70));
+        // A fill that raced the cancel still completes the order
+        assert_eq!(lifecycle.record_fill(FillSource::Chain, "0x2:1", dec!(100)), OrderState::Filled);
+
+        let mut tracker = OrderTracker::default();
+        tracker.track(order());
+        assert_eq!(tracker.expire_older_than(Utc::now() + chrono::Duration::seconds(1)).len(), 1);
+        assert_eq!(tracker.get(HASH).unwrap().state, OrderState::Expired);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::entities::{lookup_entity, parse_number};
+use crate::shared_types::{Entity, OutcomeKind};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_decimal::Decimal;
+
+const NUMBER: &str = r"\$?\d[\d,]*(?:\.\d+)?\s*(?:k|mm|m|bn|b)?%?";
+
+lazy_static! {
+    static ref RE_BETWEEN: Regex = Regex::new(&format!(r"^({n})\s*(?:-|–|to)\s*({n})$", n = NUMBER)).unwrap();
+    static ref RE_ABOVE: Regex = Regex::new(&format!(r"^(?:>=?|≥|over|above|more than|at least)\s*({n})$|^({n})\s*(?:\+|or more|or higher|and above)$", n = NUMBER)).unwrap();
+    static ref RE_BELOW: Regex = Regex::new(&format!(r"^(?:<=?|≤|under|below|less than)\s*({n})$|^({n})\s*(?:or less|or fewer|or lower|and below)$", n = NUMBER)).unwrap();
+    static ref RE_MONTH_DAY: Regex = Regex::new(
+        r"^(?:(?:by|before|on)\s+)?(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(\d{1,2})(?:st|nd|rd|th)?(?:,?\s*(\d{4}))?$"
+    ).unwrap();
+    static ref RE_ISO_DATE: Regex = Regex::new(r"^(\d{4})-(\d{2})-(\d{2})$").unwrap();
+}
+
+/// Reads an outcome name. Yes/No first, then dates, numeric ranges ("5-10%", "<50", "$100k+"),
+/// and finally candidate and team names from the entity dictionary. A date without a year
+/// takes the latest one not after `end_date`.
+pub fn parse_outcome(name: &str, end_date: DateTime<Utc>) -> OutcomeKind {
+    let text = name.trim().to_lowercase();
+    match text.as_str() {
+        "yes" => return OutcomeKind::Yes,
+        "no" => return OutcomeKind::No,
+        _ => {}
+    }
+    if let Some(date) = parse_date(&text, end_date) {
+        return OutcomeKind::Date(date);
+    }
+    if let Some((low, high)) = parse_range(&text) {
+        return OutcomeKind::Range { low, high };
+    }
+    match lookup_entity(&text) {
+        Some(Entity::Candidate(name)) => OutcomeKind::Candidate(name),
+        Some(Entity::Team(name)) => OutcomeKind::Team(name),
+        _ => OutcomeKind::Other,
+    }
+}
+
+fn number(text: &str) -> Option<Decimal> {
+    parse_number(&text.split_whitespace().collect::<String>())
+}
+
+/// Inclusive bounds of a range outcome; open ends are 0 and `Decimal::MAX`.
+fn parse_range(text: &str) -> Option<(Decimal, Decimal)> {
+    if let Some(caps) = RE_BETWEEN.captures(text) {
+        let (low, high) = (number(&caps[1])?, number(&caps[2])?);
+        return (low <= high).then_some((low, high));
+    }
+    let bound = |re: &Regex| re.captures(text).and_then(|caps| caps.get(1).or(caps.get(2)).and_then(|m| number(m.as_str())));
+    if let Some(low) = bound(&RE_ABOVE) {
+        return Some((low, Decimal::MAX));
+    }
+    bound(&RE_BELOW).map(|high| (Decimal::ZERO, high))
+}
+
+fn parse_date(text: &str, end_date: DateTime<Utc>) -> Option<NaiveDate> {
+    if let Some(caps) = RE_ISO_DATE.captures(text) {
+        return NaiveDate::from_ymd_opt(caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?);
+    }
+    let caps = RE_MONTH_DAY.captures(text)?;
+    let month = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"].iter().position(|m| *m == &caps[1])? as u32 + 1;
+    let day: u32 = caps[2].parse().ok()?;
+    if let Some(year) = caps.get(3) {
+        return NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, month, day);
+    }
+    let end = end_date.date_naive();
+    NaiveDate::from_ymd_opt(end.year(), month, day)
+        .filter(|date| *date <= end)
+        .or_else(|| NaiveDate::from_ymd_opt(end.year() - 1, month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parses_ranges_dates_and_names() {
+        let end = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let range = |low, high| OutcomeKind::Range { low, high };
+        assert_eq!(parse_outcome("Yes", end), OutcomeKind::Yes);
+        assert_eq!(parse_outcome("5-10%", end), range(dec!(5), dec!(10)));
+        assert_eq!(parse_outcome("$90,000 - $95,000", end), range(dec!(90000), dec!(95000)));
+        assert_eq!(parse_outcome("<0.5%", end), range(Decimal::ZERO, dec!(0.5)));
+        assert_eq!(parse_outcome("$100k+", end), range(dec!(100000), Decimal::MAX));
+        assert_eq!(parse_outcome("Over 2.5", end), range(dec!(2.5), Decimal::MAX));
+
+        assert_eq!(parse_outcome("March 31, 2025", end), OutcomeKind::Date(NaiveDate::from_ymd_opt(2025, 3, 31).unwrap()));
+        // No year: the latest such date by the market's end
+        assert_eq!(parse_outcome("Dec 31", end), OutcomeKind::Date(NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()));
+        assert_eq!(parse_outcome("2025-01-10", end), OutcomeKind::Date(NaiveDate::from_ymd_opt(2025, 1, 10).unwrap()));
+
+        assert_eq!(parse_outcome("Donald Trump", end), OutcomeKind::Candidate("trump".to_string()));
+        assert_eq!(parse_outcome("Lakers", end), OutcomeKind::Team("lakers".to_string()));
+        assert_eq!(parse_outcome("Someone else", end), OutcomeKind::Other);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::blockchain::ExecutionError;
+use crate::clob_client::OrderSide;
+use crate::market_state::MarketState;
+use crate::risk::{Exposure, ExposureGroups};
+use crate::shared_types::Market;
+use crate::wallet_pool::ExecutorPool;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub type SharedPositions = Arc<RwLock<PositionTracker>>;
+
+/// Outcome token holdings of the trading wallet, keyed by asset_id. Chain balances are the
+/// source of truth; fills are applied optimistically in between syncs. Each holding carries
+/// its average cost, so open positions can be marked to market.
+#[derive(Debug, Default, Clone)]
+pub struct PositionTracker {
+    shares: HashMap<String, Decimal>,
+    /// USDC paid for the shares still held.
+    cost: HashMap<String, Decimal>,
+}
+
+impl PositionTracker {
+    /// Overwrites holdings for `asset_ids` with their on-chain ERC-1155 balances. Shares gained
+    /// since the last sync are costed at the current price in `state`.
+    pub async fn sync(&mut self, executor: &ExecutorPool, state: &MarketState, asset_ids: &[String]) -> Result<(), ExecutionError> {
+        let balances = executor.fetch_balances(asset_ids).await?;
+        self.apply_balances(balances, state);
+        Ok(())
+    }
+
+    pub fn apply_balances(&mut self, balances: HashMap<String, Decimal>, state: &MarketState) {
+        for (asset_id, shares) in balances {
+            let price = state.markets.resolve(&asset_id).and_then(|id| state.price(id)).unwrap_or_default();
+            self.set(&asset_id, shares, price);
+        }
+    }
+
+    /// Applies a CLOB fill of `size` shares at `price` before the next chain sync confirms it.
+    pub fn apply_fill(&mut self, asset_id: &str, side: OrderSide, size: Decimal, price: Decimal) {
+        let held = self.shares(asset_id);
+        let shares = match side {
+            OrderSide::Buy => held + size,
+            OrderSide::Sell => (held - size).max(Decimal::ZERO),
+        };
+        self.set(asset_id, shares, price);
+    }
+
+    /// Moves a holding to `shares`: gains are costed at `price`, reductions release cost pro rata.
+    fn set(&mut self, asset_id: &str, shares: Decimal, price: Decimal) {
+        let held = self.shares(asset_id);
+        if shares.is_zero() {
+            self.shares.remove(asset_id);
+            self.cost.remove(asset_id);
+            return;
+        }
+        let cost = self.cost.entry(asset_id.to_string()).or_default();
+        if shares > held {
+            *cost += (shares - held) * price;
+        } else if !held.is_zero() {
+            *cost = *cost * shares / held;
+        }
+        self.shares.insert(asset_id.to_string(), shares);
+    }
+
+    pub fn shares(&self, asset_id: &str) -> Decimal {
+        self.shares.get(asset_id).copied().unwrap_or_default()
+    }
+
+    /// USDC paid for the shares of `asset_id` still held.
+    pub fn cost(&self, asset_id: &str) -> Decimal {
+        self.cost.get(asset_id).copied().unwrap_or_default()
+    }
+
+    /// Shares held of each of `market`'s outcomes, keyed by asset id, leaving out empty ones.
+    pub fn held_in(&self, market: &Market) -> HashMap<String, Decimal> {
+        market.conditions.iter()
+            .filter_map(|c| self.shares.get(&c.asset_id).map(|shares| (c.asset_id.clone(), *shares)))
+            .collect()
+    }
+
+    /// Every asset with a non-zero balance.
+    pub fn holdings(&self) -> impl Iterator<Item = (&String, &Decimal)> {
+        self.shares.iter()
+    }
+
+    /// Marks holdings to the current prices in `state` and aggregates them per market.
+    /// Assets no longer in the tracked set are ignored.
+    pub fn exposure(&self, state: &MarketState) -> Exposure {
+        let mut exposure = Exposure::default();
+        for (asset_id, shares) in &self.shares {
+            let Some(id) = state.markets.resolve(asset_id) else { continue };
+            if let (Some(market), Some(price)) = (state.markets.get(id.market), state.price(id)) {
+                exposure.record(&market.id, &ExposureGroups::of(market), *shares * price);
+            }
+        }
+        exposure
+    }
+
+    /// Market value of the holdings at the current prices in `state` less what they cost.
+    /// Assets no longer in the tracked set are ignored.
+    pub fn unrealized_pnl(&self, state: &MarketState) -> Decimal {
+        self.shares.iter()
+            .filter_map(|(asset_id, shares)| {
+                let price = state.price(state.markets.resolve(asset_id)?)?;
+                Some(*shares * price - self.cost.get(asset_id).copied().unwrap_or_default())
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Condition, Market, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_exposure_marks_holdings_per_market() {
+        let condition = |name: &str, price: Decimal, asset_id: &str| Condition { name: name.to_string(), price, outcome: None, asset_id: asset_id.to_string(), kind: OutcomeKind::Other, last_updated: None };
+        let state = MarketState::build(vec![Market {
+            id: "m1".to_string(),
+            title: "fed_cut_rates".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 12, 18, 0, 0, 0).unwrap(),
+            conditions: vec![condition("Yes", dec!(0.4), "1"), condition("No", dec!(0.6), "2")],
+            ..Market::default()
+        }], 0.6, Default::default());
+
+        let mut positions = PositionTracker::default();
+        positions.apply_balances(HashMap::from([("1".to_string(), dec!(100)), ("2".to_string(), dec!(0)), ("9".to_string(), dec!(5))]), &state);
+        positions.apply_fill("2", OrderSide::Buy, dec!(50), dec!(0.5));
+        positions.apply_fill("1", OrderSide::Sell, dec!(20), dec!(0.4));
+
+        let exposure = positions.exposure(&state);
+        assert_eq!(positions.shares("1"), dec!(80));
+        assert_eq!(exposure.market("m1"), dec!(32) + dec!(30));
+        assert_eq!(exposure.total, dec!(62));
+
+        // "1" was costed at 0.40 and is still marked there; "2" cost 0.50 and marks at 0.60
+        assert_eq!(positions.unrealized_pnl(&state), dec!(5));
+        state.set_price("2", dec!(0.3), Utc::now());
+        assert_eq!(positions.unrealized_pnl(&state), dec!(-10));
+    }
+}
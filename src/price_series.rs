@@ -0,0 +1,149 @@
+use crate::backtest::RecordedUpdate;
+use crate::blockchain::VwapCalculator;
+use crate::fill_ingest::FillRecord;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Width of a VWAP bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BucketInterval {
+    #[default]
+    OneMinute,
+    FiveMinutes,
+    OneHour,
+}
+
+impl BucketInterval {
+    pub fn seconds(&self) -> u64 {
+        match self {
+            BucketInterval::OneMinute => 60,
+            BucketInterval::FiveMinutes => 300,
+            BucketInterval::OneHour => 3600,
+        }
+    }
+}
+
+impl FromStr for BucketInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "1m" => Ok(BucketInterval::OneMinute),
+            "5m" => Ok(BucketInterval::FiveMinutes),
+            "1h" => Ok(BucketInterval::OneHour),
+            other => Err(format!("unknown bucket interval {:?}, expected 1m, 5m or 1h", other)),
+        }
+    }
+}
+
+/// Fills of one outcome token within `[start, end)`, in Unix seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VwapBar {
+    pub asset_id: String,
+    pub start: u64,
+    pub end: u64,
+    /// USDC paid per share across the bar's fills.
+    pub vwap: Decimal,
+    /// Shares traded.
+    pub volume: Decimal,
+    /// USDC traded.
+    pub notional: Decimal,
+    pub trades: usize,
+}
+
+impl VwapBar {
+    /// The bar as a price observation at its close, for the backtester.
+    pub fn to_update(&self) -> RecordedUpdate {
+        RecordedUpdate { timestamp_ms: (self.end * 1000) as i64, asset_id: self.asset_id.clone(), price: self.vwap }
+    }
+}
+
+impl VwapCalculator {
+    /// Per-asset VWAP series, bars in time order. Buckets without fills are omitted rather
+    /// than carried forward, so a gap in the series is a gap in trading.
+    pub fn bucketed(fills: &[FillRecord], interval: BucketInterval) -> BTreeMap<String, Vec<VwapBar>> {
+        let width = interval.seconds();
+        let mut buckets: BTreeMap<(String, u64), VwapBar> = BTreeMap::new();
+        for fill in fills {
+            let (shares, Some(price)) = (fill.shares(), fill.price()) else { continue };
+            let start = fill.timestamp - fill.timestamp % width;
+            let bar = buckets.entry((fill.asset_id().to_string(), start)).or_insert_with(|| VwapBar {
+                asset_id: fill.asset_id().to_string(),
+                start,
+                end: start + width,
+                vwap: Decimal::ZERO,
+                volume: Decimal::ZERO,
+                notional: Decimal::ZERO,
+                trades: 0,
+            });
+            bar.volume += shares;
+            bar.notional += shares * price;
+            bar.trades += 1;
+        }
+
+        let mut series: BTreeMap<String, Vec<VwapBar>> = BTreeMap::new();
+        for ((asset_id, _), mut bar) in buckets {
+            bar.vwap = bar.notional / bar.volume;
+            series.entry(asset_id).or_default().push(bar);
+        }
+        series
+    }
+}
+
+/// Flattens VWAP series into time-ordered price observations for the backtester.
+pub fn to_updates(series: &BTreeMap<String, Vec<VwapBar>>) -> Vec<RecordedUpdate> {
+    let mut updates: Vec<RecordedUpdate> = series.values().flatten().map(VwapBar::to_update).collect();
+    updates.sort_by_key(|u| u.timestamp_ms);
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill_ingest::USDC_ASSET_ID;
+    use rust_decimal_macros::dec;
+
+    fn sell(asset_id: &str, timestamp: u64, shares: Decimal, usdc: Decimal) -> FillRecord {
+        FillRecord {
+            block_number: 1,
+            timestamp,
+            tx_hash: format!("0x{:x}", timestamp),
+            log_index: 0,
+            order_hash: "0x01".to_string(),
+            maker: "0xaa".to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: asset_id.to_string(),
+            taker_asset_id: USDC_ASSET_ID.to_string(),
+            maker_amount: shares,
+            taker_amount: usdc,
+            fee: Decimal::ZERO,
+            market: None,
+        }
+    }
+
+    #[test]
+    fn test_buckets_fills_per_asset_and_interval() {
+        let fills = [
+            sell("a", 600, dec!(10), dec!(4)),
+            sell("a", 659, dec!(30), dec!(15)),
+            sell("a", 660, dec!(5), dec!(3)),
+            sell("b", 610, dec!(2), dec!(1.8)),
+            sell("a", 700, Decimal::ZERO, Decimal::ZERO),
+        ];
+
+        let minute = VwapCalculator::bucketed(&fills, BucketInterval::OneMinute);
+        let a = &minute["a"];
+        assert_eq!(a.len(), 2);
+        // (4 + 15) / 40 shares
+        assert_eq!((a[0].start, a[0].end, a[0].vwap, a[0].volume, a[0].trades), (600, 660, dec!(0.475), dec!(40), 2));
+        assert_eq!((a[1].start, a[1].vwap), (660, dec!(0.6)));
+        assert_eq!(minute["b"][0].vwap, dec!(0.9));
+
+        let five = VwapCalculator::bucketed(&fills, BucketInterval::FiveMinutes);
+        assert_eq!((five["a"].len(), five["a"][0].notional, five["a"][0].trades), (1, dec!(22), 3));
+
+        let updates = to_updates(&minute);
+        assert_eq!(updates.iter().map(|u| (u.timestamp_ms, u.asset_id.as_str())).collect::<Vec<_>>(), [(660_000, "a"), (660_000, "b"), (720_000, "a")]);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::fees::fee_for;
+use crate::order_book::{BookSide, OrderBook};
+use crate::fees::FeeSchedule;
+use crate::shared_types::CombinatorialOpportunity;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct ProfitModelConfig {
+    /// Taker rate for markets whose CLOB metadata carries none, in basis points.
+    pub taker_fee_bps: Decimal,
+    /// Flat gas cost per execution, in USDC.
+    pub gas_cost: Decimal,
+    /// Opportunities netting less than this (in USDC) are dropped.
+    pub min_net_profit: Decimal,
+}
+
+impl Default for ProfitModelConfig {
+    fn default() -> Self {
+        Self { taker_fee_bps: dec!(0), gas_cost: dec!(0.05), min_net_profit: dec!(1) }
+    }
+}
+
+/// Result of walking both books for a buy-low / sell-high pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionEstimate {
+    pub size: Decimal,
+    pub avg_buy_price: Decimal,
+    pub avg_sell_price: Decimal,
+    /// Deepest ask and bid the walk reached, i.e. limit prices that fill the full size.
+    pub max_buy_price: Decimal,
+    pub min_sell_price: Decimal,
+    /// Cost versus executing the full size at top of book on both legs.
+    pub slippage: Decimal,
+    pub gross_profit: Decimal,
+    pub fees: Decimal,
+    pub gas_cost: Decimal,
+    pub net_profit: Decimal,
+}
+
+impl ExecutionEstimate {
+    /// Net profit per dollar of capital committed to the buy leg.
+    pub fn net_margin(&self) -> Decimal {
+        let notional = self.size * self.avg_buy_price;
+        if notional.is_zero() { Decimal::ZERO } else { self.net_profit / notional }
+    }
+}
+
+/// Walks the asks of `buy_book` and the bids of `sell_book` level by level, consuming size while
+/// the marginal share still earns more than the taker fees of both legs (each at its market's
+/// rate). Returns None when nothing is fillable or the result nets less than
+/// `config.min_net_profit`.
+pub fn estimate_pair(buy_book: &OrderBook, sell_book: &OrderBook, buy_fee_bps: Decimal, sell_fee_bps: Decimal, config: &ProfitModelConfig) -> Option<ExecutionEstimate> {
+    let asks = buy_book.levels(BookSide::Ask);
+    let bids = sell_book.levels(BookSide::Bid);
+    let best_ask = asks.first()?.price;
+    let best_bid = bids.first()?.price;
+
+    let (mut ai, mut bi) = (0, 0);
+    let mut ask_left = asks[0].size;
+    let mut bid_left = bids[0].size;
+    let (mut size, mut cost, mut proceeds, mut fees) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+    let (mut max_buy_price, mut min_sell_price) = (best_ask, best_bid);
+
+    while ai < asks.len() && bi < bids.len() {
+        let (ask, bid) = (asks[ai].price, bids[bi].price);
+        let fee = fee_for(buy_fee_bps, ask, Decimal::ONE) + fee_for(sell_fee_bps, bid, Decimal::ONE);
+        let marginal = bid - ask - fee;
+        if marginal <= Decimal::ZERO {
+            break;
+        }
+
+        let take = ask_left.min(bid_left);
+        size += take;
+        cost += take * ask;
+        proceeds += take * bid;
+        fees += take * fee;
+        max_buy_price = ask;
+        min_sell_price = bid;
+        ask_left -= take;
+        bid_left -= take;
+
+        if ask_left.is_zero() {
+            ai += 1;
+            if let Some(level) = asks.get(ai) { ask_left = level.size; }
+        }
+        if bid_left.is_zero() {
+            bi += 1;
+            if let Some(level) = bids.get(bi) { bid_left = level.size; }
+        }
+    }
+
+    if size.is_zero() {
+        return None;
+    }
+
+    let avg_buy_price = cost / size;
+    let avg_sell_price = proceeds / size;
+    let gross_profit = proceeds - cost;
+    let net_profit = gross_profit - fees - config.gas_cost;
+    let slippage = (cost - size * best_ask) + (size * best_bid - proceeds);
+
+    if net_profit < config.min_net_profit {
+        return None;
+    }
+
+    Some(ExecutionEstimate {
+        size,
+        avg_buy_price,
+        avg_sell_price,
+        max_buy_price,
+        min_sell_price,
+        slippage,
+        gross_profit,
+        fees,
+        gas_cost: config.gas_cost,
+        net_profit,
+    })
+}
+
+/// Prices a combinatorial opportunity against live books: the implied leg (cheap) is bought at
+/// the asks and the implying leg (dear) is priced at its bids, which the asks of its complement,
+/// bought as the hedge, mirror.
+pub fn evaluate_combinatorial(op: &CombinatorialOpportunity, books: &HashMap<String, OrderBook>, config: &ProfitModelConfig) -> Option<ExecutionEstimate> {
+    let buy_book = books.get(&op.implied_asset_id)?;
+    let sell_book = books.get(&op.implying_asset_id)?;
+    let taker_bps = |fees: Option<FeeSchedule>| fees.map_or(config.taker_fee_bps, |f| f.taker_bps);
+    estimate_pair(buy_book, sell_book, taker_bps(op.implied_fees), taker_bps(op.implying_fees), config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::PriceLevel;
+
+    fn book(bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> OrderBook {
+        let to_levels = |levels: &[(Decimal, Decimal)]| levels.iter().map(|&(price, size)| PriceLevel { price, size }).collect::<Vec<_>>();
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&to_levels(bids), &to_levels(asks));
+        book
+    }
+
+    #[test]
+    fn test_walks_book_until_spread_closes() {
+        let buy = book(&[], &[(dec!(0.40), dec!(100)), (dec!(0.45), dec!(100)), (dec!(0.60), dec!(500))]);
+        let sell = book(&[(dec!(0.55), dec!(150)), (dec!(0.50), dec!(200))], &[]);
+        let config = ProfitModelConfig { taker_fee_bps: dec!(0), gas_cost: dec!(0.5), min_net_profit: dec!(1) };
+
+        let est = estimate_pair(&buy, &sell, Decimal::ZERO, Decimal::ZERO, &config).unwrap();
+        // 100 @ 0.40/0.55, 50 @ 0.45/0.55, 50 @ 0.45/0.50, then 0.60 ask > 0.50 bid
+        assert_eq!(est.size, dec!(200));
+        assert_eq!(est.gross_profit, dec!(15) + dec!(5) + dec!(2.5));
+        assert_eq!(est.net_profit, dec!(22));
+        assert_eq!(est.slippage, dec!(5) + dec!(2.5));
+        assert_eq!((est.max_buy_price, est.min_sell_price), (dec!(0.45), dec!(0.50)));
+    }
+
+    #[test]
+    fn test_below_threshold_is_dropped() {
+        let buy = book(&[], &[(dec!(0.49), dec!(10))]);
+        let sell = book(&[(dec!(0.50), dec!(10))], &[]);
+        assert!(estimate_pair(&buy, &sell, Decimal::ZERO, Decimal::ZERO, &ProfitModelConfig::default()).is_none());
+
+        // 0.45 / 0.55 clears 10c gross, but 20% of min(p, 1 - p) on each leg is 18c
+        let buy = book(&[], &[(dec!(0.45), dec!(100))]);
+        let sell = book(&[(dec!(0.55), dec!(100))], &[]);
+        let config = ProfitModelConfig { gas_cost: Decimal::ZERO, ..ProfitModelConfig::default() };
+        assert!(estimate_pair(&buy, &sell, dec!(2000), dec!(2000), &config).is_none());
+        assert_eq!(estimate_pair(&buy, &sell, dec!(100), dec!(100), &config).unwrap().fees, dec!(0.9));
+    }
+}
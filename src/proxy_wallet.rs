@@ -0,0 +1,157 @@
+use crate::blockchain::ExecutionError;
+use ethers::abi::AbiEncode;
+use ethers::prelude::*;
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Polymarket's factory for the proxy wallets of email/Magic accounts (Default: Mainnet)
+pub const DEFAULT_PROXY_FACTORY_ADDRESS: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
+
+abigen!(
+    ProxyWalletFactory,
+    r#"[
+        struct ProxiedCall { uint8 typeCode; address to; uint256 value; bytes data; }
+        function proxy(ProxiedCall[] calls) external payable returns (bytes[])
+    ]"#
+);
+
+abigen!(
+    GnosisSafe,
+    r#"[
+        function execTransaction(address to, uint256 value, bytes data, uint8 operation, uint256 safeTxGas, uint256 baseGas, uint256 gasPrice, address gasToken, address refundReceiver, bytes signatures) external payable returns (bool)
+        function getOwners() external view returns (address[])
+    ]"#
+);
+
+// `ProxyWalletFactory` call type for a plain CALL (0 is invalid, 2 DELEGATECALL)
+const PROXY_CALL: u8 = 1;
+
+/// How a Polymarket account holds its funds, which is also the CLOB order `signatureType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WalletKind {
+    /// The signing key's own address.
+    #[default]
+    Eoa,
+    /// Polymarket proxy of an email/Magic account, driven through the proxy factory.
+    PolyProxy,
+    /// 1-of-1 Gnosis Safe of a browser-wallet account, owned by the signing key.
+    GnosisSafe,
+}
+
+impl WalletKind {
+    pub fn signature_type(&self) -> u8 {
+        match self {
+            WalletKind::Eoa => 0,
+            WalletKind::PolyProxy => 1,
+            WalletKind::GnosisSafe => 2,
+        }
+    }
+}
+
+impl FromStr for WalletKind {
+    type Err = ExecutionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "eoa" | "none" => Ok(WalletKind::Eoa),
+            "poly_proxy" | "proxy" | "magic" => Ok(WalletKind::PolyProxy),
+            "safe" | "gnosis_safe" => Ok(WalletKind::GnosisSafe),
+            other => Err(ExecutionError::Config(format!("unknown proxy wallet type: {}", other))),
+        }
+    }
+}
+
+/// The proxy holding an account's USDC and positions. The signing key pays gas and
+/// relays every call through it; orders name the proxy as maker and the key as signer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyWallet {
+    pub kind: WalletKind,
+    pub address: Address,
+}
+
+impl ProxyWallet {
+    /// The transaction target and calldata that make the proxy call `to` with `data`, when
+    /// sent from `owner`.
+    pub fn route(&self, owner: Address, to: Address, data: Bytes) -> Result<(Address, Bytes), ExecutionError> {
+        match self.kind {
+            WalletKind::Eoa => Ok((to, data)),
+            WalletKind::PolyProxy => {
+                let calldata = ProxyCall { calls: vec![ProxiedCall { type_code: PROXY_CALL, to, value: U256::zero(), data }] }.encode();
+                Ok((proxy_factory_address()?, calldata.into()))
+            }
+            WalletKind::GnosisSafe => {
+                let calldata = ExecTransactionCall {
+                    to,
+                    value: U256::zero(),
+                    data,
+                    operation: 0,
+                    safe_tx_gas: U256::zero(),
+                    base_gas: U256::zero(),
+                    gas_price: U256::zero(),
+                    gas_token: Address::zero(),
+                    refund_receiver: Address::zero(),
+                    signatures: approved_hash_signature(owner),
+                }.encode();
+                Ok((self.address, calldata.into()))
+            }
+        }
+    }
+
+    /// Fails unless `owner` can drive the proxy. Only a Safe exposes its owners; Polymarket
+    /// proxies are bound to their owner by the factory.
+    pub async fn verify_owner<M: Middleware + 'static>(&self, client: Arc<M>, owner: Address) -> Result<(), ExecutionError> {
+        if self.kind != WalletKind::GnosisSafe {
+            return Ok(());
+        }
+        let owners = GnosisSafe::new(self.address, client).get_owners().call().await
+            .map_err(|e| ExecutionError::Contract(format!("reading owners of Safe {:?}: {}", self.address, e)))?;
+        if !owners.contains(&owner) {
+            return Err(ExecutionError::Config(format!("{:?} is not an owner of Safe {:?}", owner, self.address)));
+        }
+        Ok(())
+    }
+}
+
+/// PROXY_FACTORY_ADDRESS, or the mainnet factory.
+fn proxy_factory_address() -> Result<Address, ExecutionError> {
+    let s = env::var("PROXY_FACTORY_ADDRESS").unwrap_or_else(|_| DEFAULT_PROXY_FACTORY_ADDRESS.to_string());
+    Address::from_str(&s).map_err(|e| ExecutionError::Config(format!("bad address {}: {}", s, e)))
+}
+
+/// Safe signature type 1 ("approved hash"): valid without a signature when the owner it
+/// names is the transaction sender. Encoded as r = owner, s = 0, v = 1.
+fn approved_hash_signature(owner: Address) -> Bytes {
+    let mut signature = [0u8; 65];
+    signature[12..32].copy_from_slice(owner.as_bytes());
+    signature[64] = 1;
+    Bytes::from(signature.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::AbiDecode;
+
+    #[test]
+    fn test_routes_calls_through_the_proxy() {
+        let owner = Address::from_low_u64_be(0xa11ce);
+        let usdc = Address::from_low_u64_be(0x05dc);
+        let data = Bytes::from(vec![0x09, 0x5e, 0xa7, 0xb3, 0x01]);
+
+        let eoa = ProxyWallet { kind: WalletKind::Eoa, address: owner };
+        assert_eq!(eoa.route(owner, usdc, data.clone()).unwrap(), (usdc, data.clone()));
+
+        let safe = ProxyWallet { kind: WalletKind::GnosisSafe, address: Address::from_low_u64_be(0x5afe) };
+        let (target, calldata) = safe.route(owner, usdc, data.clone()).unwrap();
+        let call = ExecTransactionCall::decode(calldata).unwrap();
+        assert_eq!((target, call.to, call.data), (safe.address, usdc, data.clone()));
+        assert_eq!(&call.signatures[12..32], owner.as_bytes());
+        assert_eq!(call.signatures[64], 1);
+
+        let proxy = ProxyWallet { kind: WalletKind::PolyProxy, address: Address::from_low_u64_be(0xbeef) };
+        let (target, calldata) = proxy.route(owner, usdc, data.clone()).unwrap();
+        assert_eq!(target, Address::from_str(DEFAULT_PROXY_FACTORY_ADDRESS).unwrap());
+        assert_eq!(ProxyCall::decode(calldata).unwrap().calls, vec![ProxiedCall { type_code: PROXY_CALL, to: usdc, value: U256::zero(), data }]);
+    }
+}
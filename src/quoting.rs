@@ -0,0 +1,94 @@
+use crate::blockchain::ExecutionError;
+use crate::order_book::OrderBook;
+use crate::shared_types::Market;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::time::Duration;
+
+/// How a rebalancing arb that buys every leg of an underpriced set reaches the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RebalancingMode {
+    /// Cross the spread on every leg at once.
+    #[default]
+    Take,
+    /// Rest bids inside the spread; once one fills, pull the others and take the missing legs.
+    Make,
+}
+
+impl FromStr for RebalancingMode {
+    type Err = ExecutionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "take" => Ok(RebalancingMode::Take),
+            "make" => Ok(RebalancingMode::Make),
+            other => Err(ExecutionError::Config(format!("unknown rebalancing mode: {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotingConfig {
+    /// How often resting quotes are checked for fills and repriced against the book.
+    pub reprice_interval: Duration,
+    /// How long quotes may rest without a fill before they are pulled.
+    pub quote_ttl: Duration,
+}
+
+impl Default for QuotingConfig {
+    fn default() -> Self {
+        Self { reprice_interval: Duration::from_secs(1), quote_ttl: Duration::from_secs(30) }
+    }
+}
+
+/// Bid for each leg of a Long set: one tick above the best bid while that stays below the ask,
+/// otherwise joining the bid. Quotes never exceed the taker `limit_prices`, so a filled quote
+/// always beats taking the leg. `None` when a leg has no book.
+pub fn quote_prices(market: &Market, books: &HashMap<String, OrderBook>, limit_prices: &[Decimal]) -> Option<Vec<Decimal>> {
+    let tick = market.tick_size;
+    market.conditions.iter().zip(limit_prices).map(|(condition, &limit)| {
+        let book = books.get(&condition.asset_id)?;
+        let bid = book.best_bid().map_or(Decimal::ZERO, |level| level.price);
+        let improved = bid + tick;
+        let quote = match book.best_ask() {
+            Some(ask) if improved >= ask.price => bid,
+            _ => improved,
+        };
+        Some(quote.max(tick).min(limit))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::PriceLevel;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use rust_decimal_macros::dec;
+
+    fn book(bid: Decimal, ask: Decimal) -> OrderBook {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&[PriceLevel { price: bid, size: dec!(100) }], &[PriceLevel { price: ask, size: dec!(100) }]);
+        book
+    }
+
+    #[test]
+    fn test_quotes_inside_the_spread_without_passing_the_taker_price() {
+        let market = Market {
+            id: "m".to_string(),
+            title: "Test".to_string(),
+            conditions: (0..3).map(|i| Condition { name: format!("c{}", i), price: Decimal::ZERO, outcome: None, asset_id: i.to_string(), kind: OutcomeKind::Other, last_updated: None }).collect(),
+            ..Market::default()
+        };
+        let books = HashMap::from([
+            ("0".to_string(), book(dec!(0.20), dec!(0.25))),
+            // A one-tick spread can only be joined
+            ("1".to_string(), book(dec!(0.30), dec!(0.31))),
+            // Bid up past the price the opportunity was detected at
+            ("2".to_string(), book(dec!(0.40), dec!(0.45))),
+        ]);
+        let quotes = quote_prices(&market, &books, &[dec!(0.25), dec!(0.31), dec!(0.38)]).unwrap();
+        assert_eq!(quotes, vec![dec!(0.21), dec!(0.30), dec!(0.38)]);
+        assert_eq!(quote_prices(&market, &HashMap::new(), &[dec!(0.25), dec!(0.31), dec!(0.38)]), None);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::metrics::RATE_LIMITED;
+use reqwest::header::RETRY_AFTER;
+use reqwest::Response;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::warn;
+
+/// A server asking for a longer pause than this is waited on for this long instead.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+/// How long a 429 keeps its endpoint's budget tightened.
+const TIGHTEN_FOR: Duration = Duration::from_secs(60);
+/// Each 429 halves the rate, down to this fraction of the budget.
+const MIN_FACTOR: f64 = 0.125;
+
+/// CLOB request classes with separate budgets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Order placement, single or batched.
+    Orders,
+    Cancels,
+    /// Prices, order and trade reads, and websocket subscribe frames.
+    MarketData,
+}
+
+impl Endpoint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Endpoint::Orders => "orders",
+            Endpoint::Cancels => "cancels",
+            Endpoint::MarketData => "market_data",
+        }
+    }
+}
+
+/// Requests per second for each endpoint, each allowed to burst one second's worth.
+/// 0 disables the endpoint's limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budgets {
+    pub orders: u32,
+    pub cancels: u32,
+    pub market_data: u32,
+}
+
+impl Default for Budgets {
+    fn default() -> Self {
+        Self { orders: 20, cancels: 20, market_data: 10 }
+    }
+}
+
+impl Budgets {
+    fn rate(&self, endpoint: Endpoint) -> u32 {
+        match endpoint {
+            Endpoint::Orders => self.orders,
+            Endpoint::Cancels => self.cancels,
+            Endpoint::MarketData => self.market_data,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+    /// Fraction of `rate` allowed while tightened after a 429.
+    factor: f64,
+    tightened_until: Instant,
+    /// No request starts before this, per the server's `Retry-After`.
+    blocked_until: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u32, now: Instant) -> Self {
+        let rate = f64::from(rate);
+        Self { rate, tokens: rate, updated: now, factor: 1.0, tightened_until: now, blocked_until: now }
+    }
+
+    /// Takes a token and returns when the caller may start. Tokens go negative under load, so
+    /// concurrent callers queue behind each other instead of bursting together.
+    fn reserve(&mut self, now: Instant) -> Instant {
+        if now >= self.tightened_until {
+            self.factor = 1.0;
+        }
+        let rate = self.rate * self.factor;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(self.rate * self.factor);
+        self.updated = now;
+        self.tokens -= 1.0;
+        let wait = if self.tokens >= 0.0 { Duration::ZERO } else { Duration::from_secs_f64(-self.tokens / rate) };
+        (now + wait).max(self.blocked_until)
+    }
+
+    fn throttled(&mut self, now: Instant, retry_after: Option<Duration>) {
+        self.factor = (self.factor / 2.0).max(MIN_FACTOR);
+        self.tightened_until = now + TIGHTEN_FOR;
+        self.tokens = self.tokens.min(0.0);
+        self.updated = now;
+        if let Some(pause) = retry_after {
+            self.blocked_until = self.blocked_until.max(now + pause);
+        }
+    }
+}
+
+/// Token buckets per endpoint that every CLOB client draws from, so wallets and streams
+/// sharing an API key also share its limits.
+#[derive(Debug)]
+pub struct RateLimiter {
+    budgets: Budgets,
+    buckets: Mutex<HashMap<Endpoint, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(budgets: Budgets) -> Self {
+        Self { budgets, buckets: Mutex::default() }
+    }
+
+    /// Waits for a request slot on `endpoint`.
+    pub async fn acquire(&self, endpoint: Endpoint) {
+        if let Some(at) = self.reserve(endpoint, Instant::now()) {
+            sleep_until(at).await;
+        }
+    }
+
+    fn reserve(&self, endpoint: Endpoint, now: Instant) -> Option<Instant> {
+        let rate = self.budgets.rate(endpoint);
+        if rate == 0 {
+            return None;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        Some(buckets.entry(endpoint).or_insert_with(|| Bucket::new(rate, now)).reserve(now))
+    }
+
+    /// Feeds back a 429: the endpoint's rate is halved for a while and nothing is sent before
+    /// `retry_after` passes.
+    pub fn throttled(&self, endpoint: Endpoint, retry_after: Option<Duration>) {
+        self.throttled_at(endpoint, retry_after, Instant::now());
+    }
+
+    fn throttled_at(&self, endpoint: Endpoint, retry_after: Option<Duration>, now: Instant) {
+        RATE_LIMITED.with_label_values(&[endpoint.as_str()]).inc();
+        let rate = self.budgets.rate(endpoint);
+        if rate == 0 {
+            return;
+        }
+        warn!(endpoint = endpoint.as_str(), retry_after_ms = retry_after.map(|d| d.as_millis() as u64), "rate limited by the CLOB, tightening budget");
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.entry(endpoint).or_insert_with(|| Bucket::new(rate, now)).throttled(now, retry_after);
+    }
+}
+
+/// `Retry-After` in seconds; the HTTP-date form is rare on APIs and is ignored.
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// Sets the CLOB budgets. Only the first call takes effect, so call it at startup before any
+/// client sends.
+pub fn configure(budgets: Budgets) {
+    let _ = LIMITER.set(RateLimiter::new(budgets));
+}
+
+pub fn limiter() -> &'static RateLimiter {
+    LIMITER.get_or_init(|| RateLimiter::new(Budgets::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bursts_then_spaces_requests_per_endpoint() {
+        let limiter = RateLimiter::new(Budgets { orders: 4, cancels: 0, market_data: 10 });
+        let now = Instant::now();
+        let slots: Vec<Duration> = (0..6).map(|_| limiter.reserve(Endpoint::Orders, now).unwrap() - now).collect();
+        // One second's worth goes at once, then a quarter second apart
+        assert_eq!(slots, vec![Duration::ZERO, Duration::ZERO, Duration::ZERO, Duration::ZERO, Duration::from_millis(250), Duration::from_millis(500)]);
+        assert_eq!(limiter.reserve(Endpoint::MarketData, now), Some(now));
+        assert_eq!(limiter.reserve(Endpoint::Cancels, now), None);
+    }
+
+    #[test]
+    fn test_429_tightens_the_budget_until_it_cools_down() {
+        let limiter = RateLimiter::new(Budgets { market_data: 10, ..Budgets::default() });
+        let now = Instant::now();
+        limiter.throttled_at(Endpoint::MarketData, Some(Duration::from_secs(2)), now);
+        // Nothing goes before Retry-After, and afterwards the rate is halved
+        assert_eq!(limiter.reserve(Endpoint::MarketData, now).unwrap() - now, Duration::from_secs(2));
+        let later = now + Duration::from_secs(10);
+        limiter.reserve(Endpoint::MarketData, later);
+        let spaced: Vec<Duration> = (0..5).map(|_| limiter.reserve(Endpoint::MarketData, later).unwrap() - later).collect();
+        assert_eq!(spaced.last(), Some(&Duration::from_millis(200)));
+
+        let cooled = now + TIGHTEN_FOR + Duration::from_secs(1);
+        assert_eq!(limiter.reserve(Endpoint::MarketData, cooled), Some(cooled));
+    }
+}
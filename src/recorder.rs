@@ -0,0 +1,171 @@
+use crate::backtest::RecordedUpdate;
+use crate::clob_client::{parse_events, ClobEvent};
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+pub type SharedRecorder = Arc<Mutex<StreamRecorder>>;
+
+// Flush the gzip stream every this many messages so a crash loses little data
+const FLUSH_EVERY: usize = 100;
+const SEGMENT_EXTENSION: &str = ".jsonl.gz";
+
+/// A raw websocket frame as it arrived, tagged with the local receive time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedMessage {
+    pub received_ms: i64,
+    pub topic: String,
+    pub message: String,
+}
+
+/// Appends websocket frames to gzip-compressed JSONL segments, one per UTC hour
+/// (`stream-YYYYMMDD-HH.jsonl.gz`). Reopening an existing segment appends a new gzip
+/// member, which `read_segment` handles transparently.
+pub struct StreamRecorder {
+    dir: PathBuf,
+    segment: Option<(String, GzEncoder<BufWriter<File>>)>,
+    pending: usize,
+}
+
+impl StreamRecorder {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, segment: None, pending: 0 })
+    }
+
+    pub fn shared(self) -> SharedRecorder {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn record(&mut self, topic: &str, message: &str) -> io::Result<()> {
+        self.record_at(Utc::now(), topic, message)
+    }
+
+    fn record_at(&mut self, received: DateTime<Utc>, topic: &str, message: &str) -> io::Result<()> {
+        let name = format!("stream-{}{}", received.format("%Y%m%d-%H"), SEGMENT_EXTENSION);
+        if self.segment.as_ref().map(|(current, _)| current != &name).unwrap_or(true) {
+            self.rotate(name)?;
+        }
+
+        let record = RecordedMessage { received_ms: received.timestamp_millis(), topic: topic.to_string(), message: message.to_string() };
+        if let Some((_, encoder)) = &mut self.segment {
+            serde_json::to_writer(&mut *encoder, &record)?;
+            encoder.write_all(b"\n")?;
+            self.pending += 1;
+            if self.pending >= FLUSH_EVERY {
+                encoder.flush()?;
+                self.pending = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self, name: String) -> io::Result<()> {
+        self.finish()?;
+        let file = OpenOptions::new().create(true).append(true).open(self.dir.join(&name))?;
+        self.segment = Some((name, GzEncoder::new(BufWriter::new(file), Compression::default())));
+        Ok(())
+    }
+
+    /// Completes the current segment so it is a valid gzip file on disk.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some((_, encoder)) = self.segment.take() {
+            encoder.finish()?.flush()?;
+        }
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+impl Drop for StreamRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!(error = %e, "failed to close recording segment");
+        }
+    }
+}
+
+/// Reads every message from one segment file.
+pub fn read_segment(path: &Path) -> io::Result<Vec<RecordedMessage>> {
+    let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut messages = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            messages.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(messages)
+}
+
+/// Segment files under `dir`, oldest first.
+pub fn list_segments(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut segments: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.to_string_lossy().ends_with(SEGMENT_EXTENSION))
+        .collect();
+    segments.sort();
+    Ok(segments)
+}
+
+/// Extracts the price observations a backtest needs from raw recorded frames.
+pub fn to_price_updates(messages: &[RecordedMessage]) -> Vec<RecordedUpdate> {
+    let mut updates = Vec::new();
+    for recorded in messages {
+        for event in parse_events(&recorded.message) {
+            let timestamp_ms = recorded.received_ms;
+            match event {
+                ClobEvent::PriceChange(msg) => updates.extend(msg.price_changes.into_iter().map(|c| RecordedUpdate {
+                    timestamp_ms,
+                    price: c.reference_price(),
+                    asset_id: c.asset_id,
+                })),
+                ClobEvent::LastTradePrice(t) => updates.push(RecordedUpdate { timestamp_ms, asset_id: t.asset_id, price: t.price }),
+                _ => {}
+            }
+        }
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_segments_rotate_hourly_and_round_trip() {
+        let dir = std::env::temp_dir().join(format!("recorder-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let trade = r#"{"event_type": "last_trade_price", "asset_id": "1", "price": "0.42"}"#;
+
+        let mut recorder = StreamRecorder::new(&dir).unwrap();
+        recorder.record_at(Utc.with_ymd_and_hms(2024, 11, 5, 9, 59, 0).unwrap(), "prices", trade).unwrap();
+        recorder.record_at(Utc.with_ymd_and_hms(2024, 11, 5, 10, 0, 1).unwrap(), "prices", trade).unwrap();
+        recorder.finish().unwrap();
+        // Reopening the same hour appends a second gzip member
+        recorder.record_at(Utc.with_ymd_and_hms(2024, 11, 5, 10, 30, 0).unwrap(), "book", "{}").unwrap();
+        drop(recorder);
+
+        let segments = list_segments(&dir).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].ends_with("stream-20241105-09.jsonl.gz"));
+
+        let messages = read_segment(&segments[1]).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].topic, "book");
+
+        let updates = to_price_updates(&messages);
+        assert_eq!(updates, vec![RecordedUpdate { timestamp_ms: messages[0].received_ms, asset_id: "1".to_string(), price: dec!(0.42) }]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+use crate::execution_guard::GuardedExecutor;
+use crate::notifier::{Alert, NotifierHandle};
+use crate::shared_types::Market;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// A resolved market whose winning tokens can be redeemed.
+#[derive(Debug, Clone)]
+pub struct RedemptionTarget {
+    pub market: Market,
+    /// USDC per share of each outcome, by outcome index.
+    pub payouts: Vec<Decimal>,
+}
+
+impl RedemptionTarget {
+    /// USDC the tokens in `held` (asset id => shares) redeem for.
+    pub fn payout(&self, held: &HashMap<String, Decimal>) -> Decimal {
+        self.market.conditions.iter()
+            .zip(&self.payouts)
+            .map(|(condition, payout)| held.get(&condition.asset_id).copied().unwrap_or_default() * payout)
+            .sum()
+    }
+}
+
+/// Redeems markets as the resolution watcher reports them. Resolutions that arrive together
+/// are redeemed as one batch; markets a wallet failed to redeem are retried every `retry_interval`.
+pub fn spawn_redeemer(
+    executor: Arc<GuardedExecutor>,
+    mut resolved: mpsc::UnboundedReceiver<RedemptionTarget>,
+    retry_interval: Duration,
+    alerts: NotifierHandle,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut pending: Vec<RedemptionTarget> = Vec::new();
+        loop {
+            tokio::select! {
+                target = resolved.recv() => match target {
+                    Some(target) => pending.push(target),
+                    None if pending.is_empty() => return,
+                    None => sleep(retry_interval).await,
+                },
+                _ = sleep(retry_interval), if !pending.is_empty() => {}
+            }
+            while let Ok(target) = resolved.try_recv() {
+                pending.push(target);
+            }
+
+            // Redemption only ever returns USDC, so it does not go through the breaker
+            let (redeemed, failed) = executor.executor().redeem_positions(&pending).await;
+            for (market_id, usdc) in redeemed.into_iter().filter(|(_, usdc)| !usdc.is_zero()) {
+                info!(%market_id, %usdc, "redeemed winning positions");
+                alerts.notify(Alert::Execution { strategy: "redeem", market_id, amount: usdc, expected_pnl: Decimal::ZERO });
+            }
+            let mut retry: Vec<Option<String>> = Vec::new();
+            for (wallet, market_id, e) in failed {
+                warn!(?wallet, market_id = market_id.as_deref().unwrap_or("*"), error = %e, retry_in_secs = retry_interval.as_secs(), "redemption failed");
+                retry.push(market_id);
+            }
+            pending.retain(|t| retry.iter().any(|m| m.as_deref().is_none_or(|id| id == t.market.id)));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_payout_counts_only_paying_outcomes() {
+        let condition = |name: &str| Condition { name: name.to_string(), price: dec!(0.5), outcome: None, asset_id: name.to_lowercase(), kind: OutcomeKind::Other, last_updated: None };
+        let target = RedemptionTarget {
+            market: Market {
+                id: "m".to_string(),
+                title: "Q?".to_string(),
+                end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+                conditions: vec![condition("Yes"), condition("No")],
+                tags: Vec::new(),
+                condition_id: "0x01".to_string(),
+                ..Market::default()
+            },
+            payouts: vec![Decimal::ONE, Decimal::ZERO],
+        };
+        let held = HashMap::from([("yes".to_string(), dec!(12.5)), ("no".to_string(), dec!(40))]);
+        assert_eq!(target.payout(&held), dec!(12.5));
+        assert_eq!(target.payout(&HashMap::from([("no".to_string(), dec!(40))])), Decimal::ZERO);
+
+        let split = RedemptionTarget { payouts: vec![dec!(0.5), dec!(0.5)], ..target };
+        assert_eq!(split.payout(&held), dec!(26.25));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::blockchain::{BlockchainCollector, ExecutionError};
+use crate::fill_ingest::{FillStore, IngestError};
+use crate::metrics::CHAIN_REORGS;
+use async_trait::async_trait;
+use tracing::warn;
+
+/// Checkpoint hashes kept in the store, newest first; a reorg deeper than this many
+/// checkpoints rolls back to below the oldest.
+pub const TRACKED_BLOCKS: usize = 64;
+
+/// Canonical block hashes, for checking ingested blocks are still on the chain.
+#[async_trait]
+pub trait BlockHashes: Send + Sync {
+    /// `None` when the node does not know the block.
+    async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError>;
+}
+
+#[async_trait]
+impl BlockHashes for BlockchainCollector {
+    async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError> {
+        BlockchainCollector::block_hash(self, block).await
+    }
+}
+
+/// What a rollback removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rollback {
+    /// Last block kept; every checkpoint is at or below it.
+    pub fork_block: u64,
+    pub fills: usize,
+    pub resolutions: usize,
+}
+
+/// Re-reads the hashes recorded at past checkpoints and, when the newest no longer matches
+/// the chain, rolls the store back to the newest that does. The pipelines then re-fetch the
+/// canonical blocks from their lowered checkpoints.
+pub async fn rewind<S: BlockHashes + ?Sized>(source: &S, store: &FillStore) -> Result<Option<Rollback>, IngestError> {
+    let recorded = store.block_hashes(TRACKED_BLOCKS)?;
+    let mut fork_block = None;
+    for (i, (block, hash)) in recorded.iter().enumerate() {
+        if source.block_hash(*block).await?.as_deref() == Some(hash.as_str()) {
+            if i == 0 {
+                return Ok(None);
+            }
+            fork_block = Some(*block);
+            break;
+        }
+    }
+    let Some(fork_block) = fork_block.or_else(|| recorded.last().map(|(oldest, _)| oldest.saturating_sub(1))) else {
+        return Ok(None);
+    };
+    let rollback = store.rollback_after(fork_block)?;
+    CHAIN_REORGS.with_label_values(&["store"]).inc();
+    warn!(fork_block, fills = rollback.fills, resolutions = rollback.resolutions, "chain reorg: ingested blocks rolled back");
+    Ok(Some(rollback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hashes every block by number, with blocks from `fork` on replaced.
+    struct Chain {
+        fork: Option<u64>,
+    }
+
+    #[async_trait]
+    impl BlockHashes for Chain {
+        async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError> {
+            let branch = if self.fork.is_some_and(|f| block >= f) { "b" } else { "a" };
+            Ok(Some(format!("{}{}", branch, block)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rewinds_to_the_newest_canonical_checkpoint() {
+        let store = FillStore::open_in_memory().unwrap();
+        for block in [100, 110, 120] {
+            store.record_block_hash(block, &format!("a{}", block)).unwrap();
+        }
+        store.set_checkpoint("order_filled", 120).unwrap();
+        store.set_checkpoint("condition_resolution", 105).unwrap();
+        assert_eq!(rewind(&Chain { fork: None }, &store).await.unwrap(), None);
+
+        let rollback = rewind(&Chain { fork: Some(115) }, &store).await.unwrap().unwrap();
+        assert_eq!(rollback.fork_block, 110);
+        assert_eq!(store.checkpoint("order_filled").unwrap(), Some(110));
+        assert_eq!(store.checkpoint("condition_resolution").unwrap(), Some(105));
+        assert_eq!(store.block_hashes(TRACKED_BLOCKS).unwrap(), vec![(110, "a110".to_string()), (100, "a100".to_string())]);
+
+        // Nothing recorded survives: back to before the oldest
+        let rollback = rewind(&Chain { fork: Some(0) }, &store).await.unwrap().unwrap();
+        assert_eq!(rollback.fork_block, 99);
+        assert!(store.block_hashes(TRACKED_BLOCKS).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::market_state::SharedMarketState;
+use crate::notifier::{until_next, Alert, NotifierHandle};
+use crate::opportunity_store::{ExecutionRecord, OpportunityStore, StoreError};
+use crate::positions::SharedPositions;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("failed to write report: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to read executions: {0}")]
+    Store(#[from] StoreError),
+    #[error("failed to encode report: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Where the daily report goes and when it is built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportConfig {
+    /// Directory for `report-<date>.<format>` files; none are written when unset.
+    pub dir: Option<PathBuf>,
+    pub format: ReportFormat,
+    /// Also send the headline figures through the notifier.
+    pub push: bool,
+    pub hour_utc: u32,
+}
+
+/// One opportunity type's executions over the day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyReport {
+    pub kind: String,
+    pub executions: usize,
+    /// Executions that completed and earned more than the gas they paid.
+    pub wins: usize,
+    pub win_rate: Decimal,
+    pub realized_pnl: Decimal,
+    pub fees: Decimal,
+    pub gas: Decimal,
+}
+
+/// A day of trading: executions from the opportunity store plus open positions marked at the
+/// time the report is built. Realized PnL is the expected PnL of completed executions, as
+/// booked into `polymarket_realized_pnl_usd`; failed attempts count as losses.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyReport {
+    pub date: NaiveDate,
+    pub executions: usize,
+    pub win_rate: Decimal,
+    pub realized_pnl: Decimal,
+    /// Open positions at current prices less what they cost.
+    pub unrealized_pnl: Decimal,
+    /// Open positions at current prices.
+    pub exposure: Decimal,
+    pub fees: Decimal,
+    pub gas: Decimal,
+    pub strategies: Vec<StrategyReport>,
+}
+
+impl DailyReport {
+    pub fn build(date: NaiveDate, executions: &[ExecutionRecord], unrealized_pnl: Decimal, exposure: Decimal) -> Self {
+        let mut by_kind: BTreeMap<&str, StrategyReport> = BTreeMap::new();
+        for e in executions {
+            let strategy = by_kind.entry(e.kind.as_str()).or_insert_with(|| StrategyReport {
+                kind: e.kind.clone(),
+                executions: 0,
+                wins: 0,
+                win_rate: Decimal::ZERO,
+                realized_pnl: Decimal::ZERO,
+                fees: Decimal::ZERO,
+                gas: Decimal::ZERO,
+            });
+            strategy.executions += 1;
+            strategy.fees += e.fees;
+            strategy.gas += e.gas;
+            if e.succeeded {
+                strategy.realized_pnl += e.expected_pnl;
+                if e.expected_pnl > e.gas {
+                    strategy.wins += 1;
+                }
+            }
+        }
+        let mut strategies: Vec<StrategyReport> = by_kind.into_values().collect();
+        for s in &mut strategies {
+            s.win_rate = rate(s.wins, s.executions);
+        }
+        Self {
+            date,
+            executions: executions.len(),
+            win_rate: rate(strategies.iter().map(|s| s.wins).sum(), executions.len()),
+            realized_pnl: strategies.iter().map(|s| s.realized_pnl).sum(),
+            unrealized_pnl,
+            exposure,
+            fees: strategies.iter().map(|s| s.fees).sum(),
+            gas: strategies.iter().map(|s| s.gas).sum(),
+            strategies,
+        }
+    }
+
+    /// One row per strategy and a `total` row carrying the position figures.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,strategy,executions,wins,win_rate,realized_pnl,unrealized_pnl,exposure,fees,gas\n");
+        for s in &self.strategies {
+            csv += &format!("{},{},{},{},{},{},,,{},{}\n", self.date, s.kind, s.executions, s.wins, s.win_rate, s.realized_pnl, s.fees, s.gas);
+        }
+        let wins: usize = self.strategies.iter().map(|s| s.wins).sum();
+        csv += &format!(
+            "{},total,{},{},{},{},{},{},{},{}\n",
+            self.date, self.executions, wins, self.win_rate, self.realized_pnl, self.unrealized_pnl, self.exposure, self.fees, self.gas
+        );
+        csv
+    }
+
+    /// Writes `report-<date>.<format>` into `dir`, returning its path.
+    pub fn write(&self, dir: &Path, format: ReportFormat) -> Result<PathBuf, ReportError> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("report-{}.{}", self.date, format.extension()));
+        let body = match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Csv => self.to_csv(),
+        };
+        std::fs::write(&path, body)?;
+        Ok(path)
+    }
+
+    pub fn alert(&self) -> Alert {
+        Alert::DailyReport {
+            date: self.date,
+            executions: self.executions,
+            win_rate: self.win_rate,
+            realized_pnl: self.realized_pnl,
+            unrealized_pnl: self.unrealized_pnl,
+            fees: self.fees,
+            gas: self.gas,
+        }
+    }
+}
+
+fn rate(wins: usize, total: usize) -> Decimal {
+    if total == 0 { Decimal::ZERO } else { (Decimal::from(wins) / Decimal::from(total)).round_dp(4).normalize() }
+}
+
+/// Builds the report for the 24 hours before `config.hour_utc` every day, then writes and
+/// pushes it as configured. Runs until aborted.
+pub fn spawn_daily_report(
+    config: ReportConfig,
+    store: Arc<OpportunityStore>,
+    positions: SharedPositions,
+    state: SharedMarketState,
+    alerts: NotifierHandle,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            sleep(until_next(Utc::now(), config.hour_utc).to_std().unwrap_or_default()).await;
+            let to = Utc::now();
+            let from = to - chrono::Duration::days(1);
+            let executions = match store.executions_between(from, to) {
+                Ok(executions) => executions,
+                Err(e) => {
+                    warn!(error = %e, "daily report could not read executions");
+                    continue;
+                }
+            };
+            let (unrealized_pnl, exposure) = {
+                let state = state.read().await;
+                let positions = positions.read().await;
+                (positions.unrealized_pnl(&state), positions.exposure(&state).total)
+            };
+            let report = DailyReport::build(from.date_naive(), &executions, unrealized_pnl, exposure);
+            if let Some(dir) = &config.dir {
+                match report.write(dir, config.format) {
+                    Ok(path) => info!(path = %path.display(), "daily report written"),
+                    Err(e) => warn!(error = %e, "daily report could not be written"),
+                }
+            }
+            if config.push {
+                alerts.notify(report.alert());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn execution(kind: &str, expected_pnl: Decimal, gas: Decimal, succeeded: bool) -> ExecutionRecord {
+        ExecutionRecord {
+            executed_at: Utc::now(),
+            opportunity_id: None,
+            kind: kind.to_string(),
+            market_id: "m1".to_string(),
+            amount: dec!(100),
+            expected_pnl,
+            fees: dec!(0.5),
+            gas,
+            succeeded,
+        }
+    }
+
+    #[test]
+    fn test_report_aggregates_executions_by_strategy() {
+        let store = OpportunityStore::open_in_memory().unwrap();
+        for e in [
+            execution("rebalancing", dec!(3), dec!(0.1), true),
+            execution("rebalancing", dec!(0.05), dec!(0.1), true),
+            execution("rebalancing", dec!(2), dec!(0), false),
+            execution("combinatorial", dec!(4), dec!(0.2), true),
+        ] {
+            store.record_execution(&e).unwrap();
+        }
+        let now = Utc::now();
+        let executions = store.executions_between(now - chrono::Duration::hours(1), now + chrono::Duration::hours(1)).unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let report = DailyReport::build(date, &executions, dec!(-1.5), dec!(250));
+
+        let rebalancing = &report.strategies[1];
+        assert_eq!((rebalancing.kind.as_str(), rebalancing.executions, rebalancing.wins), ("rebalancing", 3, 1));
+        assert_eq!((rebalancing.win_rate, rebalancing.realized_pnl), (dec!(0.3333), dec!(3.05)));
+        assert_eq!((report.executions, report.win_rate, report.realized_pnl), (4, dec!(0.5), dec!(7.05)));
+        assert_eq!((report.fees, report.gas), (dec!(2.0), dec!(0.4)));
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().nth(1), Some("2025-01-02,combinatorial,1,1,1,4,,,0.5,0.2"));
+        assert_eq!(csv.lines().last(), Some("2025-01-02,total,4,2,0.5,7.05,-1.5,250,2.0,0.4"));
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(json["strategies"][0]["kind"], "combinatorial");
+    }
+}
@@ -0,0 +1,121 @@
+use crate::shared_types::{Market, ResolutionRules};
+use std::collections::HashSet;
+
+/// Phrases that leave a market's outcome to judgement rather than a fixed source.
+const AMBIGUOUS_PHRASES: &[&str] = &[
+    "sole discretion",
+    "at its discretion",
+    "credible reporting",
+    "may be resolved",
+    "subject to clarification",
+    "ambiguous",
+    "50-50",
+    "reasonable interpretation",
+    "in the spirit of",
+];
+
+/// Weights of rule-text mismatch, differing oracles and ambiguous phrasing in the score.
+const TEXT_WEIGHT: f64 = 0.4;
+const ORACLE_WEIGHT: f64 = 0.4;
+const AMBIGUITY_WEIGHT: f64 = 0.2;
+
+/// How combinatorial pairs are penalized for the chance their markets resolve inconsistently.
+#[derive(Debug, Clone)]
+pub struct ResolutionRiskConfig {
+    /// Pairs scoring above this (0-1) are not traded.
+    pub max_risk: f64,
+    /// Share of the score taken off net profit, e.g. 1 turns a 0.2 risk into a 20% haircut.
+    pub discount: f64,
+}
+
+impl Default for ResolutionRiskConfig {
+    fn default() -> Self {
+        Self { max_risk: 0.5, discount: 1.0 }
+    }
+}
+
+impl ResolutionRiskConfig {
+    /// The haircut applied to a pair's profit, or `None` when the pair is too risky to trade.
+    pub fn multiplier(&self, risk: f64) -> Option<f64> {
+        (risk <= self.max_risk).then(|| (1.0 - self.discount * risk).clamp(0.0, 1.0))
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_string)
+        .collect()
+}
+
+/// 1 minus the Jaccard similarity of the two rule texts; 0 when either has none.
+fn text_mismatch(a: &ResolutionRules, b: &ResolutionRules) -> f64 {
+    let (a, b) = (words(&a.description), words(&b.description));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    1.0 - a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// 1 for different oracle adapters, 0.5 for different resolution sources, 0 otherwise.
+fn oracle_mismatch(a: &ResolutionRules, b: &ResolutionRules) -> f64 {
+    let differs = |x: &str, y: &str| !x.is_empty() && !y.is_empty() && !x.trim().eq_ignore_ascii_case(y.trim());
+    if differs(&a.resolved_by, &b.resolved_by) {
+        1.0
+    } else if differs(&a.source, &b.source) {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+/// Half a point per ambiguous phrase across both rule texts, capped at 1.
+fn ambiguity(a: &ResolutionRules, b: &ResolutionRules) -> f64 {
+    let hits = [a, b].iter()
+        .map(|r| r.description.to_lowercase())
+        .map(|text| AMBIGUOUS_PHRASES.iter().filter(|p| text.contains(*p)).count())
+        .sum::<usize>();
+    (hits as f64 * 0.5).min(1.0)
+}
+
+/// Chance-like score (0-1) that `a` and `b` resolve inconsistently, from how far their rule
+/// texts diverge, whether they settle through different oracles or sources, and how much of
+/// their wording is left to judgement.
+pub fn score(a: &Market, b: &Market) -> f64 {
+    let (a, b) = (&a.resolution, &b.resolution);
+    TEXT_WEIGHT * text_mismatch(a, b) + ORACLE_WEIGHT * oracle_mismatch(a, b) + AMBIGUITY_WEIGHT * ambiguity(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(description: &str, source: &str, resolved_by: &str) -> Market {
+        Market {
+            resolution: ResolutionRules { description: description.to_string(), source: source.to_string(), resolved_by: resolved_by.to_string() },
+            ..Market::default()
+        }
+    }
+
+    #[test]
+    fn test_score_rises_with_divergent_rules_oracles_and_ambiguity() {
+        let rules = "This market resolves Yes if the Federal Reserve cuts rates at its December meeting per the FOMC statement.";
+        let fed = market(rules, "https://www.federalreserve.gov", "0xadapter");
+        assert_eq!(score(&fed, &fed), 0.0);
+        // No rule text on either side is no evidence either way
+        assert_eq!(score(&market("", "", ""), &fed), 0.0);
+
+        let other_oracle = market(rules, "https://www.federalreserve.gov", "0xother");
+        assert!((score(&fed, &other_oracle) - ORACLE_WEIGHT).abs() < 1e-9);
+
+        let vague = market("Resolves by consensus of credible reporting, at the sole discretion of the committee.", "", "0xother");
+        let risk = score(&fed, &vague);
+        assert!(risk > 0.8, "{}", risk);
+
+        let config = ResolutionRiskConfig::default();
+        assert_eq!(config.multiplier(0.0), Some(1.0));
+        assert_eq!(config.multiplier(0.25), Some(0.75));
+        assert_eq!(config.multiplier(risk), None);
+    }
+}
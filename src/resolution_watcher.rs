@@ -0,0 +1,270 @@
+use crate::blockchain::{BlockchainCollector, ExecutionError};
+use crate::units::UsdcAmount;
+use crate::execution_analyzer::MarketResolution;
+use crate::fill_ingest::{FillStore, IngestConfig, IngestError};
+use crate::market_state::{forward_subscriptions, SharedMarketState, SubscriptionSender};
+use crate::notifier::{Alert, NotifierHandle};
+use crate::redemption::RedemptionTarget;
+use crate::reorg::{self, BlockHashes};
+use crate::shared_types::Market;
+use async_trait::async_trait;
+use ethers::types::{Address, H256};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// Checkpoint key for the `ConditionResolution` watcher.
+pub const CONDITION_RESOLUTION_CHECKPOINT: &str = "condition_resolution";
+
+/// A condition's final payouts, tagged with its market when the bot has seen it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedMarket {
+    pub resolution: MarketResolution,
+    pub market_id: Option<String>,
+    pub block_number: u64,
+    pub timestamp: u64,
+}
+
+impl ResolvedMarket {
+    /// Index of the outcome paying out in full, `None` for a split resolution.
+    pub fn winning_outcome(&self) -> Option<usize> {
+        self.resolution.payouts.iter().position(|p| *p == Decimal::ONE)
+    }
+}
+
+/// USDC paid out to one of our wallets for redeeming a resolved condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redemption {
+    pub redeemer: Address,
+    pub condition_id: String,
+    pub payout: Decimal,
+    pub block_number: u64,
+}
+
+/// Where resolution events come from. Implemented by `BlockchainCollector`; tests substitute
+/// a canned source.
+#[async_trait]
+pub trait ResolutionSource: BlockHashes {
+    async fn latest_block(&self) -> Result<u64, ExecutionError>;
+    /// Resolutions in `[from_block, to_block]`, not yet tagged with a market.
+    async fn resolutions_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<ResolvedMarket>, ExecutionError>;
+    async fn redemptions_in_range(&self, from_block: u64, to_block: u64, redeemers: &[Address]) -> Result<Vec<Redemption>, ExecutionError>;
+}
+
+#[async_trait]
+impl ResolutionSource for BlockchainCollector {
+    async fn latest_block(&self) -> Result<u64, ExecutionError> {
+        BlockchainCollector::latest_block(self).await
+    }
+
+    async fn resolutions_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<ResolvedMarket>, ExecutionError> {
+        let mut resolved = Vec::new();
+        for (event, meta) in self.fetch_resolutions(from_block, to_block).await? {
+            let Some(resolution) = MarketResolution::from_event(&event) else { continue };
+            let block_number = meta.block_number.as_u64();
+            resolved.push(ResolvedMarket { resolution, market_id: None, block_number, timestamp: self.block_timestamp(block_number).await? });
+        }
+        Ok(resolved)
+    }
+
+    async fn redemptions_in_range(&self, from_block: u64, to_block: u64, redeemers: &[Address]) -> Result<Vec<Redemption>, ExecutionError> {
+        let logs = self.fetch_redemptions(from_block, to_block, redeemers.to_vec()).await?;
+        Ok(logs.into_iter().map(|(event, meta)| Redemption {
+            redeemer: event.redeemer,
+            condition_id: format!("{:?}", H256::from(event.condition_id)),
+            payout: UsdcAmount::from_units(event.payout).unwrap_or_default().value(),
+            block_number: meta.block_number.as_u64(),
+        }).collect())
+    }
+}
+
+/// Follows `ConditionResolution` events from the last checkpoint: records every outcome in
+/// the fill store as a backtest label, drops resolved markets from the live set and graph,
+/// unsubscribes their assets, hands them to the redeemer when one is attached, and logs payouts
+/// redeemed by our wallets.
+pub struct ResolutionWatcher<S> {
+    source: Arc<S>,
+    store: FillStore,
+    state: SharedMarketState,
+    wallets: Vec<Address>,
+    config: IngestConfig,
+    /// Lowercase condition id => market, kept after Gamma stops listing the market, which
+    /// usually happens before the oracle reports.
+    known: HashMap<String, Market>,
+    redeemer: Option<mpsc::UnboundedSender<RedemptionTarget>>,
+    subscribers: Vec<SubscriptionSender>,
+}
+
+impl<S: ResolutionSource + 'static> ResolutionWatcher<S> {
+    pub fn new(source: Arc<S>, store: FillStore, state: SharedMarketState, wallets: Vec<Address>, config: IngestConfig) -> Self {
+        Self { source, store, state, wallets, config, known: HashMap::new(), redeemer: None, subscribers: Vec::new() }
+    }
+
+    /// Sends every resolved market the bot tracked to `redeemer`.
+    pub fn with_redeemer(mut self, redeemer: mpsc::UnboundedSender<RedemptionTarget>) -> Self {
+        self.redeemer = Some(redeemer);
+        self
+    }
+
+    /// Unsubscribes the assets of resolved markets on these stream channels.
+    pub fn with_subscribers(mut self, subscribers: Vec<SubscriptionSender>) -> Self {
+        self.subscribers = subscribers;
+        self
+    }
+
+    /// Processes confirmed blocks after the checkpoint, from the tip on the first run, in
+    /// `chunk_blocks` ranges. Returns the resolutions seen.
+    pub async fn poll(&mut self) -> Result<Vec<ResolvedMarket>, IngestError> {
+        self.remember_markets().await;
+        reorg::rewind(self.source.as_ref(), &self.store).await?;
+        let tip = self.source.latest_block().await?.saturating_sub(self.config.confirmations);
+        let mut next = match self.store.checkpoint(CONDITION_RESOLUTION_CHECKPOINT)? {
+            Some(checkpoint) => checkpoint + 1,
+            None => tip,
+        };
+
+        let mut resolved = Vec::new();
+        while next <= tip {
+            let end = tip.min(next + self.config.chunk_blocks - 1);
+            let mut batch = self.source.resolutions_in_range(next, end).await?;
+            for r in &mut batch {
+                let market = self.known.get(&r.resolution.condition_id.to_lowercase());
+                r.market_id = market.map(|m| m.id.clone());
+                self.store.insert_resolution(r)?;
+                if let (Some(market), Some(redeemer)) = (market, &self.redeemer) {
+                    let _ = redeemer.send(RedemptionTarget { market: market.clone(), payouts: r.resolution.payouts.clone() });
+                }
+            }
+            let market_ids: Vec<String> = batch.iter().filter_map(|r| r.market_id.clone()).collect();
+            if !market_ids.is_empty() {
+                let diff = self.state.write().await.drop_resolved(&market_ids);
+                forward_subscriptions(&diff, &self.subscribers);
+                info!(resolved = market_ids.len(), dropped = diff.removed_markets.len(), assets = diff.removed_asset_ids.len(), "resolved markets removed");
+            }
+            if !self.wallets.is_empty() {
+                for redemption in self.source.redemptions_in_range(next, end, &self.wallets).await? {
+                    info!(wallet = ?redemption.redeemer, condition_id = %redemption.condition_id, payout = %redemption.payout, "position redeemed");
+                }
+            }
+            self.store.set_checkpoint(CONDITION_RESOLUTION_CHECKPOINT, end)?;
+            if let Some(hash) = self.source.block_hash(end).await? {
+                self.store.record_block_hash(end, &hash)?;
+            }
+            resolved.extend(batch);
+            next = end + 1;
+        }
+        Ok(resolved)
+    }
+
+    async fn remember_markets(&mut self) {
+        let state = self.state.read().await;
+        for (_, market) in state.markets.iter().filter(|(_, m)| !m.condition_id.is_empty()) {
+            self.known.entry(market.condition_id.to_lowercase()).or_insert_with(|| market.clone());
+        }
+    }
+
+    /// Polls every `interval` and alerts on each resolved market the bot tracked.
+    pub fn spawn(mut self, interval: Duration, alerts: NotifierHandle) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                let resolved = match self.poll().await {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        warn!(error = %e, "resolution poll failed");
+                        continue;
+                    }
+                };
+                for r in resolved {
+                    let Some(market) = self.known.get(&r.resolution.condition_id.to_lowercase()) else { continue };
+                    let outcome = r.winning_outcome().and_then(|i| market.conditions.get(i)).map_or_else(|| "split".to_string(), |c| c.name.clone());
+                    info!(market_id = %market.id, %outcome, "market resolved");
+                    alerts.notify(Alert::MarketResolved { market_id: market.id.clone(), question: market.title.clone(), outcome });
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_state::MarketState;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use tokio::sync::RwLock;
+
+    struct FakeSource {
+        tip: u64,
+        resolutions: Vec<ResolvedMarket>,
+    }
+
+    #[async_trait]
+    impl BlockHashes for FakeSource {
+        async fn block_hash(&self, block: u64) -> Result<Option<String>, ExecutionError> {
+            Ok(Some(format!("0x{:x}", block)))
+        }
+    }
+
+    #[async_trait]
+    impl ResolutionSource for FakeSource {
+        async fn latest_block(&self) -> Result<u64, ExecutionError> {
+            Ok(self.tip)
+        }
+
+        async fn resolutions_in_range(&self, from_block: u64, to_block: u64) -> Result<Vec<ResolvedMarket>, ExecutionError> {
+            Ok(self.resolutions.iter().filter(|r| (from_block..=to_block).contains(&r.block_number)).cloned().collect())
+        }
+
+        async fn redemptions_in_range(&self, _: u64, _: u64, _: &[Address]) -> Result<Vec<Redemption>, ExecutionError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn market(id: &str, condition_id: &str) -> Market {
+        let condition = |name: &str| Condition { name: name.to_string(), price: dec!(0.5), outcome: None, asset_id: format!("{}-{}", id, name), kind: OutcomeKind::Other, last_updated: None };
+        Market {
+            id: id.to_string(),
+            title: format!("{}?", id),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![condition("Yes"), condition("No")],
+            tags: Vec::new(),
+            condition_id: condition_id.to_string(),
+            ..Market::default()
+        }
+    }
+
+    fn resolved(condition_id: &str, payouts: Vec<Decimal>, block_number: u64) -> ResolvedMarket {
+        ResolvedMarket { resolution: MarketResolution { condition_id: condition_id.to_string(), payouts }, market_id: None, block_number, timestamp: block_number * 2 }
+    }
+
+    #[tokio::test]
+    async fn test_records_outcomes_and_drops_resolved_markets() {
+        let store = FillStore::open_in_memory().unwrap();
+        store.set_checkpoint(CONDITION_RESOLUTION_CHECKPOINT, 100).unwrap();
+        let state: SharedMarketState = Arc::new(RwLock::new(MarketState::build(vec![market("a", "0xAA"), market("b", "0xbb")], 0.6, Default::default())));
+        let source = Arc::new(FakeSource {
+            tip: 130,
+            resolutions: vec![resolved("0xaa", vec![Decimal::ZERO, Decimal::ONE], 110), resolved("0xcc", vec![dec!(0.5), dec!(0.5)], 112), resolved("0xbb", vec![Decimal::ONE, Decimal::ZERO], 127)],
+        });
+        let config = IngestConfig { chunk_blocks: 10, confirmations: 5 };
+        let (redeem_tx, mut redeem_rx) = mpsc::unbounded_channel();
+        let mut watcher = ResolutionWatcher::new(source, store, state.clone(), Vec::new(), config).with_redeemer(redeem_tx);
+
+        // Block 127 is not confirmed yet
+        let seen = watcher.poll().await.unwrap();
+        assert_eq!(seen.iter().map(|r| (r.market_id.as_deref(), r.winning_outcome())).collect::<Vec<_>>(), [(Some("a"), Some(1)), (None, None)]);
+        assert_eq!(state.read().await.markets.iter().map(|(_, m)| m.id.as_str()).collect::<Vec<_>>(), ["b"]);
+        let target = redeem_rx.try_recv().unwrap();
+        assert_eq!((target.market.id.as_str(), target.payouts), ("a", vec![Decimal::ZERO, Decimal::ONE]));
+        assert!(redeem_rx.try_recv().is_err());
+        assert_eq!(watcher.store.checkpoint(CONDITION_RESOLUTION_CHECKPOINT).unwrap(), Some(125));
+        let labels = watcher.store.resolutions().unwrap();
+        assert_eq!((labels.len(), labels[0].market_id.as_deref(), labels[0].resolution.payouts.clone()), (2, Some("a"), vec![Decimal::ZERO, Decimal::ONE]));
+    }
+}
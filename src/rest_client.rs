@@ -0,0 +1,125 @@
+use crate::execution_guard::RetryPolicy;
+use crate::rate_limit::retry_after;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use tracing::warn;
+use url::Url;
+
+const USER_AGENT: &str = "PolymarketArbitrageBot/1.0";
+
+#[derive(Debug, Clone, Copy)]
+pub struct RestConfig {
+    /// Per request, connect to last body byte.
+    pub timeout: Duration,
+    pub retry: RetryPolicy,
+    /// Requests per second to any one host. 0 disables the limit.
+    pub requests_per_second: u32,
+}
+
+impl Default for RestConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            retry: RetryPolicy { max_attempts: 4, base_delay: Duration::from_millis(500), max_delay: Duration::from_secs(10) },
+            requests_per_second: 10,
+        }
+    }
+}
+
+/// Timeouts, connection failures, 429s and 5xx responses are worth retrying.
+pub fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.status().is_some_and(|s| s.is_server_error() || s.as_u16() == 429)
+}
+
+/// JSON-over-HTTP client for public REST APIs: requests are spaced per host, time out, and
+/// transient failures are retried with jittered exponential backoff, honoring `Retry-After`.
+pub struct RestClient {
+    http: Client,
+    config: RestConfig,
+    /// Host => earliest time the next request may start.
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl RestClient {
+    pub fn new(config: RestConfig) -> Self {
+        let http = Client::builder().timeout(config.timeout).user_agent(USER_AGENT).build().unwrap_or_default();
+        Self { http, config, next_slot: Mutex::default() }
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, reqwest::Error> {
+        let mut attempt = 0;
+        loop {
+            self.throttle(url).await;
+            let (error, retry_after) = match self.http.get(url).send().await {
+                Ok(response) => {
+                    let retry_after = retry_after(&response);
+                    match response.error_for_status() {
+                        Ok(response) => return response.json().await,
+                        Err(e) => (e, retry_after),
+                    }
+                }
+                Err(e) => (e, None),
+            };
+            if !is_transient(&error) || attempt + 1 >= self.config.retry.max_attempts {
+                return Err(error);
+            }
+            let delay = retry_after.unwrap_or_else(|| self.config.retry.delay(attempt));
+            warn!(%url, attempt = attempt + 1, error = %error, retry_in_ms = delay.as_millis() as u64, "REST request failed, retrying");
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Waits for the host's next request slot. The slot is reserved before sleeping, so
+    /// concurrent callers line up instead of bursting together.
+    async fn throttle(&self, url: &str) {
+        if self.config.requests_per_second == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(1) / self.config.requests_per_second;
+        let host = Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+        let slot = {
+            let mut slots = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let slot = slots.get(&host).copied().unwrap_or(now).max(now);
+            slots.insert(host, slot + interval);
+            slot
+        };
+        sleep_until(slot).await;
+    }
+}
+
+static GAMMA: OnceLock<RestClient> = OnceLock::new();
+
+/// Sets up the shared Gamma client. Only the first call takes effect, so call it at startup
+/// before anything is fetched.
+pub fn configure_gamma(config: RestConfig) {
+    let _ = GAMMA.set(RestClient::new(config));
+}
+
+/// The client every public market-data request goes through, Gamma and the CLOB's `/markets`
+/// alike, so requests to each host share one rate limit.
+pub fn gamma() -> &'static RestClient {
+    GAMMA.get_or_init(|| RestClient::new(RestConfig::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_throttle_spaces_requests_per_host() {
+        let client = RestClient::new(RestConfig { requests_per_second: 20, ..RestConfig::default() });
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.throttle("https://gamma-api.polymarket.com/events?offset=0").await;
+        }
+        // Another host has its own slots
+        client.throttle("https://clob.polymarket.com/markets").await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100) && elapsed < Duration::from_millis(150), "{:?}", elapsed);
+    }
+}
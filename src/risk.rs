@@ -0,0 +1,255 @@
+use crate::category_params::CategoryParams;
+use crate::entities::extract_entities;
+use crate::shared_types::{Entity, Market};
+use crate::topic_classifier::{MarketCategory, TopicClassifier};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizingStrategy {
+    /// Commit a fixed fraction of available balance to every opportunity.
+    FixedFraction(Decimal),
+    /// Scale the Kelly-optimal fraction by the given multiplier (e.g. 0.25 for quarter-Kelly).
+    KellyFraction(Decimal),
+}
+
+#[derive(Debug, Clone)]
+pub struct SizingConfig {
+    pub strategy: SizingStrategy,
+    pub max_exposure: Decimal,
+    pub max_market_exposure: Decimal,
+    pub min_trade_size: Decimal,
+    /// Probability that a detected arb is actually captured, used as Kelly's `p`.
+    pub success_probability: Decimal,
+    /// Caps on the combined exposure of every market in a category.
+    pub category_limits: HashMap<MarketCategory, Decimal>,
+    /// Caps on the combined exposure of every market naming an entity, keyed by its lowercase
+    /// canonical name from the entity dictionary (e.g. "trump").
+    pub entity_limits: HashMap<String, Decimal>,
+    /// Per-category replacements for the sizing fraction and the per-market cap.
+    pub categories: CategoryParams,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: SizingStrategy::FixedFraction(dec!(0.05)),
+            max_exposure: dec!(1000),
+            max_market_exposure: dec!(200),
+            min_trade_size: dec!(5),
+            success_probability: dec!(0.95),
+            category_limits: HashMap::new(),
+            entity_limits: HashMap::new(),
+            categories: CategoryParams::default(),
+        }
+    }
+}
+
+/// The category and named entities a market's exposure counts against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposureGroups {
+    pub category: MarketCategory,
+    /// Canonical names of the candidates, teams, locations and events in the title and outcomes.
+    pub entities: BTreeSet<String>,
+}
+
+impl ExposureGroups {
+    pub fn of(market: &Market) -> Self {
+        let entities = std::iter::once(&market.title)
+            .chain(market.conditions.iter().map(|c| &c.name))
+            .flat_map(|text| extract_entities(text))
+            .filter_map(|e| match e {
+                Entity::Candidate(name) | Entity::Location(name) | Entity::Team(name) | Entity::Event(name) => Some(name),
+                Entity::NumericalValue(_) => None,
+            })
+            .collect();
+        Self { category: TopicClassifier::classify(market), entities }
+    }
+}
+
+/// Notional currently committed, in total, per market, per category and per entity.
+#[derive(Debug, Default, Clone)]
+pub struct Exposure {
+    pub total: Decimal,
+    pub per_market: HashMap<String, Decimal>,
+    pub per_category: HashMap<MarketCategory, Decimal>,
+    pub per_entity: HashMap<String, Decimal>,
+}
+
+impl Exposure {
+    pub fn record(&mut self, market_id: &str, groups: &ExposureGroups, amount: Decimal) {
+        self.total += amount;
+        *self.per_market.entry(market_id.to_string()).or_default() += amount;
+        *self.per_category.entry(groups.category.clone()).or_default() += amount;
+        for entity in &groups.entities {
+            *self.per_entity.entry(entity.clone()).or_default() += amount;
+        }
+    }
+
+    pub fn release(&mut self, market_id: &str, groups: &ExposureGroups, amount: Decimal) {
+        let release = |current: Option<&mut Decimal>| {
+            if let Some(current) = current {
+                *current = (*current - amount).max(Decimal::ZERO);
+            }
+        };
+        self.total = (self.total - amount).max(Decimal::ZERO);
+        release(self.per_market.get_mut(market_id));
+        release(self.per_category.get_mut(&groups.category));
+        for entity in &groups.entities {
+            release(self.per_entity.get_mut(entity));
+        }
+    }
+
+    pub fn market(&self, market_id: &str) -> Decimal {
+        self.per_market.get(market_id).copied().unwrap_or_default()
+    }
+
+    pub fn category(&self, category: &MarketCategory) -> Decimal {
+        self.per_category.get(category).copied().unwrap_or_default()
+    }
+
+    pub fn entity(&self, entity: &str) -> Decimal {
+        self.per_entity.get(entity).copied().unwrap_or_default()
+    }
+}
+
+pub struct PositionSizer {
+    pub config: SizingConfig,
+}
+
+impl PositionSizer {
+    pub fn new(config: SizingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns the notional to commit to an opportunity on `market_id`, or zero if the trade
+    /// should be skipped. `profit_margin` is the expected profit per dollar of payout. Trades are
+    /// shrunk to the headroom left under every cap the market counts against.
+    pub fn size(&self, market_id: &str, groups: &ExposureGroups, profit_margin: Decimal, available_balance: Decimal, exposure: &Exposure) -> Decimal {
+        if profit_margin <= Decimal::ZERO || available_balance <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        let overrides = self.config.categories.for_category(&groups.category);
+        let fraction = match self.config.strategy {
+            SizingStrategy::FixedFraction(f) => overrides.and_then(|o| o.sizing_fraction).unwrap_or(f),
+            SizingStrategy::KellyFraction(multiplier) => overrides.and_then(|o| o.sizing_fraction).unwrap_or(multiplier) * self.kelly_fraction(profit_margin),
+        };
+        let max_market_exposure = overrides.and_then(|o| o.max_market_exposure).unwrap_or(self.config.max_market_exposure);
+
+        let headroom_total = self.config.max_exposure - exposure.total;
+        let headroom_market = max_market_exposure - exposure.market(market_id);
+        let headroom_groups = self.group_headroom(groups, exposure);
+        let size = (available_balance * fraction)
+            .min(headroom_total)
+            .min(headroom_market)
+            .min(headroom_groups.unwrap_or(available_balance))
+            .min(available_balance);
+
+        if size < self.config.min_trade_size {
+            Decimal::ZERO
+        } else {
+            size.round_dp(2)
+        }
+    }
+
+    /// Least headroom under the category and entity caps `groups` falls under, if any apply.
+    fn group_headroom(&self, groups: &ExposureGroups, exposure: &Exposure) -> Option<Decimal> {
+        let category = self.config.category_limits.get(&groups.category).map(|limit| *limit - exposure.category(&groups.category));
+        let entities = groups.entities.iter()
+            .filter_map(|entity| self.config.entity_limits.get(entity).map(|limit| *limit - exposure.entity(entity)));
+        category.into_iter().chain(entities).min()
+    }
+
+    /// Kelly-optimal fraction f* = p - (1 - p) / b, where the net odds `b` are the profit
+    /// earned per dollar staked on a payout that costs `1 - margin`.
+    fn kelly_fraction(&self, profit_margin: Decimal) -> Decimal {
+        let cost = Decimal::ONE - profit_margin;
+        if cost <= Decimal::ZERO {
+            return Decimal::ONE;
+        }
+        let odds = profit_margin / cost;
+        let p = self.config.success_probability;
+        (p - (Decimal::ONE - p) / odds).clamp(Decimal::ZERO, Decimal::ONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::category_params::CategoryOverrides;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+
+    fn groups(category: MarketCategory, entities: &[&str]) -> ExposureGroups {
+        ExposureGroups { category, entities: entities.iter().map(|e| e.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_fixed_fraction_respects_market_cap() {
+        let sizer = PositionSizer::new(SizingConfig { strategy: SizingStrategy::FixedFraction(dec!(0.5)), ..SizingConfig::default() });
+        let other = groups(MarketCategory::Other, &[]);
+        let mut exposure = Exposure::default();
+        assert_eq!(sizer.size("m1", &other, dec!(0.03), dec!(1000), &exposure), dec!(200));
+
+        exposure.record("m1", &other, dec!(150));
+        assert_eq!(sizer.size("m1", &other, dec!(0.03), dec!(1000), &exposure), dec!(50));
+        exposure.record("m1", &other, dec!(48));
+        assert_eq!(sizer.size("m1", &other, dec!(0.03), dec!(1000), &exposure), Decimal::ZERO);
+
+        // Crypto trades a smaller slice under a tighter per-market cap
+        let crypto = CategoryOverrides { sizing_fraction: Some(dec!(0.05)), max_market_exposure: Some(dec!(40)), ..CategoryOverrides::default() };
+        let sizer = PositionSizer::new(SizingConfig {
+            strategy: SizingStrategy::FixedFraction(dec!(0.5)),
+            categories: CategoryParams::new(HashMap::from([(MarketCategory::Crypto, crypto)])),
+            ..SizingConfig::default()
+        });
+        let btc = groups(MarketCategory::Crypto, &[]);
+        assert_eq!(sizer.size("m2", &btc, dec!(0.03), dec!(600), &Exposure::default()), dec!(30));
+        assert_eq!(sizer.size("m2", &btc, dec!(0.03), dec!(1000), &Exposure::default()), dec!(40));
+        assert_eq!(sizer.size("m2", &other, dec!(0.03), dec!(1000), &Exposure::default()), dec!(200));
+    }
+
+    #[test]
+    fn test_kelly_skips_thin_edges() {
+        let sizer = PositionSizer::new(SizingConfig { strategy: SizingStrategy::KellyFraction(dec!(0.25)), ..SizingConfig::default() });
+        let other = groups(MarketCategory::Other, &[]);
+        let exposure = Exposure::default();
+        // With p = 0.95 a 2% margin has negative Kelly edge
+        assert_eq!(sizer.size("m1", &other, dec!(0.02), dec!(1000), &exposure), Decimal::ZERO);
+        assert!(sizer.size("m1", &other, dec!(0.10), dec!(1000), &exposure) > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_category_and_entity_caps_shrink_then_reject_trades() {
+        let market = Market {
+            id: "m1".to_string(),
+            title: "Will Donald Trump win Pennsylvania?".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![Condition { name: "Yes".to_string(), price: dec!(0.5), outcome: Some(true), asset_id: "a1".to_string(), kind: OutcomeKind::Other, last_updated: None }],
+            tags: vec!["Politics".to_string()],
+            ..Market::default()
+        };
+        let trump = ExposureGroups::of(&market);
+        assert_eq!(trump, groups(MarketCategory::Politics, &["pennsylvania", "trump"]));
+
+        let sizer = PositionSizer::new(SizingConfig {
+            strategy: SizingStrategy::FixedFraction(dec!(0.5)),
+            category_limits: HashMap::from([(MarketCategory::Politics, dec!(500))]),
+            entity_limits: HashMap::from([("trump".to_string(), dec!(250))]),
+            ..SizingConfig::default()
+        });
+        let mut exposure = Exposure::default();
+        // Another Trump market in a different category still counts against the entity cap
+        exposure.record("m2", &groups(MarketCategory::Economics, &["trump"]), dec!(180));
+        assert_eq!(sizer.size("m1", &trump, dec!(0.03), dec!(1000), &exposure), dec!(70));
+        exposure.record("m3", &groups(MarketCategory::Politics, &["harris"]), dec!(460));
+        assert_eq!(sizer.size("m1", &trump, dec!(0.03), dec!(1000), &exposure), dec!(40));
+        exposure.record("m1", &trump, dec!(40));
+        assert_eq!(sizer.size("m1", &trump, dec!(0.03), dec!(1000), &exposure), Decimal::ZERO);
+
+        exposure.release("m1", &trump, dec!(40));
+        assert_eq!((exposure.category(&MarketCategory::Politics), exposure.entity("trump")), (dec!(460), dec!(180)));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::kill_switch::KillSwitch;
+use crate::metrics::{OPEN_POSITIONS, EXPECTED_PNL, SCHEDULER_QUEUE_DEPTH};
+use crate::shared_types::Opportunity;
+use crate::strategy::{MarketView, StrategyRegistry};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::Notify;
+use tracing::info;
+
+/// Operator settings changed while the bot runs, without tearing down streams or in-flight
+/// orders: a trading pause, strategy thresholds, a market blacklist and on-demand refreshes.
+pub struct RuntimeControl {
+    strategies: RwLock<StrategyRegistry>,
+    paused: AtomicBool,
+    blacklist: RwLock<HashSet<String>>,
+    refresh: Arc<Notify>,
+}
+
+impl RuntimeControl {
+    pub fn new(strategies: StrategyRegistry) -> Self {
+        Self { strategies: RwLock::new(strategies), paused: AtomicBool::new(false), blacklist: RwLock::new(HashSet::new()), refresh: Arc::new(Notify::new()) }
+    }
+
+    pub fn strategies(&self) -> RwLockReadGuard<'_, StrategyRegistry> {
+        self.strategies.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn strategies_mut(&self) -> RwLockWriteGuard<'_, StrategyRegistry> {
+        self.strategies.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn blacklist(&self) -> RwLockReadGuard<'_, HashSet<String>> {
+        self.blacklist.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn blacklist_mut(&self) -> RwLockWriteGuard<'_, HashSet<String>> {
+        self.blacklist.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_blacklisted(&self, market_id: &str) -> bool {
+        self.blacklist().contains(market_id)
+    }
+
+    /// Wakes the market refresh loop early; hand it to `run_refresh_loop`.
+    pub fn refresh_trigger(&self) -> Arc<Notify> {
+        self.refresh.clone()
+    }
+
+    /// What the strategies find in `view`, less anything touching a blacklisted market or one
+    /// the config's exclude rules keep out of trading.
+    pub fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let mut found = self.strategies().evaluate(view);
+        let blacklist = self.blacklist();
+        let excluded = &view.state.excluded;
+        if !blacklist.is_empty() || !excluded.is_empty() {
+            found.retain(|o| !o.markets().iter().any(|m| blacklist.contains(m) || excluded.contains(m)));
+        }
+        found
+    }
+
+    /// Whether a queued opportunity may still execute: trading is not paused and none of its
+    /// markets was blacklisted since it was detected.
+    pub fn allows(&self, opportunity: &Opportunity) -> bool {
+        !self.is_paused() && !opportunity.markets().iter().any(|m| self.is_blacklisted(m))
+    }
+
+    /// Answers one JSON-RPC 2.0 request. Failures are returned as JSON-RPC error objects.
+    pub fn rpc(&self, body: &[u8], kill_switch: &KillSwitch) -> Value {
+        let request: RpcRequest = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => return response(Value::Null, Err(RpcError::new(PARSE_ERROR, format!("invalid request: {}", e)))),
+        };
+        let result = self.call(&request.method, request.params, kill_switch);
+        response(request.id, result)
+    }
+
+    fn call(&self, method: &str, params: Value, kill_switch: &KillSwitch) -> Result<Value, RpcError> {
+        match method {
+            "pause" => {
+                let was = self.paused.swap(true, Ordering::Relaxed);
+                info!("trading paused through the control API");
+                Ok(json!({ "paused": true, "was_paused": was }))
+            }
+            "resume" => {
+                let was = self.paused.swap(false, Ordering::Relaxed);
+                info!("trading resumed through the control API");
+                Ok(json!({ "paused": false, "was_paused": was }))
+            }
+            "set_min_margin" => {
+                let StrategyMargin { strategy, margin } = params_of(params)?;
+                if margin < Decimal::ZERO {
+                    return Err(RpcError::new(INVALID_PARAMS, format!("margin must be >= 0, got {}", margin)));
+                }
+                self.strategies_mut().set_min_margin(&strategy, margin).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                info!(%strategy, %margin, "strategy minimum margin set through the control API");
+                Ok(json!({ "strategy": strategy, "min_margin": margin }))
+            }
+            "set_strategy_enabled" => {
+                let StrategyEnabled { strategy, enabled } = params_of(params)?;
+                self.strategies_mut().set_enabled(&strategy, enabled).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?;
+                info!(%strategy, enabled, "strategy toggled through the control API");
+                Ok(json!({ "strategy": strategy, "enabled": enabled }))
+            }
+            "blacklist" | "unblacklist" => {
+                let MarketParam { market_id } = params_of(params)?;
+                let changed = if method == "blacklist" { self.blacklist_mut().insert(market_id.clone()) } else { self.blacklist_mut().remove(&market_id) };
+                info!(%market_id, method, "market blacklist changed through the control API");
+                Ok(json!({ "market_id": market_id, "changed": changed }))
+            }
+            "refresh" => {
+                self.refresh.notify_one();
+                Ok(json!({ "refresh": "requested" }))
+            }
+            "dump_state" => Ok(self.dump(kill_switch)),
+            other => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method {}", other))),
+        }
+    }
+
+    fn dump(&self, kill_switch: &KillSwitch) -> Value {
+        let strategies = self.strategies();
+        json!({
+            "paused": self.is_paused(),
+            "halted": kill_switch.halted(),
+            "blacklist": self.blacklist().iter().collect::<BTreeSet<_>>(),
+            "strategies": {
+                "enabled": strategies.enabled().collect::<Vec<_>>(),
+                "min_margin": strategies.min_margins(),
+            },
+            "queue_depth": SCHEDULER_QUEUE_DEPTH.get(),
+            "open_positions": OPEN_POSITIONS.get(),
+            "expected_pnl": EXPECTED_PNL.get(),
+        })
+    }
+}
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+#[derive(Deserialize)]
+struct StrategyMargin {
+    strategy: String,
+    margin: Decimal,
+}
+
+#[derive(Deserialize)]
+struct StrategyEnabled {
+    strategy: String,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct MarketParam {
+    market_id: String,
+}
+
+fn params_of<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, format!("invalid params: {}", e)))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fees::FeeModel;
+    use crate::kill_switch::KillSwitchConfig;
+    use crate::notifier::NotifierHandle;
+    use crate::profit_model::ProfitModelConfig;
+    use crate::shared_types::{Leg, MultiLegOpportunity};
+
+    #[test]
+    fn test_rpc_pauses_blacklists_and_adjusts_thresholds() {
+        let control = RuntimeControl::new(StrategyRegistry::new(FeeModel::default(), ProfitModelConfig::default(), 4));
+        let path = std::env::temp_dir().join(format!("runtime-control-{}.json", std::process::id()));
+        let kill_switch = KillSwitch::new(KillSwitchConfig { path, ..KillSwitchConfig::default() }, NotifierHandle::disabled());
+        let call = |body: &str| control.rpc(body.as_bytes(), &kill_switch);
+        let leg = |market: &str| Leg { market_id: market.to_string(), condition_name: "Yes".to_string(), asset_id: format!("{}-yes", market), price: Decimal::new(45, 2) };
+        let opportunity = Opportunity::multi_leg(MultiLegOpportunity { legs: vec![leg("m1"), leg("m2")], is_cycle: false, profit: Decimal::new(5, 2) });
+
+        assert_eq!(call(r#"{"jsonrpc":"2.0","id":1,"method":"pause"}"#)["result"]["paused"], true);
+        assert!(control.is_paused() && !control.allows(&opportunity));
+        call(r#"{"jsonrpc":"2.0","id":2,"method":"resume"}"#);
+        assert!(control.allows(&opportunity));
+
+        call(r#"{"jsonrpc":"2.0","id":3,"method":"blacklist","params":{"market_id":"m1"}}"#);
+        assert!(control.is_blacklisted("m1") && !control.allows(&opportunity));
+        let reply = call(r#"{"jsonrpc":"2.0","id":4,"method":"set_min_margin","params":{"strategy":"rebalancing","margin":"0.02"}}"#);
+        assert_eq!(reply["id"], 4);
+        assert_eq!(control.strategies().min_margins().get("rebalancing"), Some(&Decimal::new(2, 2)));
+
+        let dump = call(r#"{"jsonrpc":"2.0","id":5,"method":"dump_state"}"#);
+        assert_eq!(dump["result"]["blacklist"], json!(["m1"]));
+        assert_eq!(call(r#"{"jsonrpc":"2.0","id":6,"method":"set_strategy_enabled","params":{"strategy":"astrology","enabled":false}}"#)["error"]["code"], INVALID_PARAMS);
+        assert_eq!(call(r#"{"jsonrpc":"2.0","id":7,"method":"reboot"}"#)["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(call("not json")["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::metrics::{OPPORTUNITIES_EXPIRED, SCHEDULER_QUEUE_DEPTH};
+use crate::opportunity_store::OpportunityKind;
+use crate::shared_types::Opportunity;
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchedulerConfig {
+    /// Executions allowed in flight at once across all markets.
+    pub max_concurrent: usize,
+    /// Queued opportunities older than this are dropped; their prices have likely moved.
+    pub ttl: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self { max_concurrent: 4, ttl: Duration::from_secs(2) }
+    }
+}
+
+type Key = (String, OpportunityKind);
+
+struct Entry {
+    margin: Decimal,
+    seq: u64,
+    queued_at: Instant,
+    markets: Vec<String>,
+    opportunity: Opportunity,
+    record_id: Option<i64>,
+}
+
+impl Entry {
+    fn key(&self) -> Key {
+        (self.opportunity.gate_key().to_string(), self.opportunity.kind())
+    }
+}
+
+// Highest margin first; among equals, the earliest detection
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.margin.cmp(&other.margin).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+#[derive(Default)]
+struct Queue {
+    heap: BinaryHeap<Entry>,
+    /// Sequence number of the newest detection per opportunity; older heap entries are stale.
+    latest: HashMap<Key, u64>,
+    locked: HashSet<String>,
+    running: usize,
+    next_seq: u64,
+}
+
+/// Orders bursts of detections by margin instead of arrival. At most `max_concurrent`
+/// executions run at once, no two touch the same market, and entries older than `ttl` expire.
+pub struct Scheduler {
+    config: SchedulerConfig,
+    queue: Mutex<Queue>,
+    wake: Notify,
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self { config, queue: Mutex::new(Queue::default()), wake: Notify::new() }
+    }
+
+    /// Queues a detection, replacing any queued one for the same market and strategy.
+    /// `record_id` is its row in the opportunity store, marked acted on if it executes.
+    pub fn push(&self, opportunity: Opportunity, record_id: Option<i64>) {
+        self.push_at(opportunity, record_id, Instant::now());
+    }
+
+    fn push_at(&self, opportunity: Opportunity, record_id: Option<i64>, now: Instant) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = queue.next_seq;
+        queue.next_seq += 1;
+        let entry = Entry { margin: opportunity.margin(), seq, queued_at: now, markets: opportunity.markets(), opportunity, record_id };
+        queue.latest.insert(entry.key(), seq);
+        queue.heap.push(entry);
+        SCHEDULER_QUEUE_DEPTH.set(queue.latest.len() as i64);
+        drop(queue);
+        self.wake.notify_one();
+    }
+
+    /// Waits for the best runnable opportunity. Meant for a single dispatcher task.
+    pub async fn next(self: &Arc<Self>) -> Ticket {
+        loop {
+            if let Some(ticket) = self.try_next_at(Instant::now()) {
+                return ticket;
+            }
+            self.wake.notified().await;
+        }
+    }
+
+    fn try_next_at(self: &Arc<Self>, now: Instant) -> Option<Ticket> {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.running >= self.config.max_concurrent {
+            return None;
+        }
+        let mut blocked = Vec::new();
+        let found = loop {
+            let Some(entry) = queue.heap.pop() else { break None };
+            let key = entry.key();
+            if queue.latest.get(&key) != Some(&entry.seq) {
+                continue;
+            }
+            if now.duration_since(entry.queued_at) > self.config.ttl {
+                queue.latest.remove(&key);
+                OPPORTUNITIES_EXPIRED.with_label_values(&[key.1.as_str()]).inc();
+                continue;
+            }
+            if entry.markets.iter().any(|m| queue.locked.contains(m)) {
+                blocked.push(entry);
+                continue;
+            }
+            queue.latest.remove(&key);
+            break Some(entry);
+        };
+        queue.heap.extend(blocked);
+        SCHEDULER_QUEUE_DEPTH.set(queue.latest.len() as i64);
+        let entry = found?;
+        queue.locked.extend(entry.markets.iter().cloned());
+        queue.running += 1;
+        Some(Ticket { scheduler: self.clone(), markets: entry.markets, opportunity: entry.opportunity, record_id: entry.record_id, waited: now.duration_since(entry.queued_at) })
+    }
+
+    fn release(&self, markets: &[String]) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        for market in markets {
+            queue.locked.remove(market);
+        }
+        queue.running -= 1;
+        drop(queue);
+        self.wake.notify_one();
+    }
+}
+
+/// A dequeued opportunity holding its concurrency slot and market locks until dropped.
+pub struct Ticket {
+    scheduler: Arc<Scheduler>,
+    markets: Vec<String>,
+    pub opportunity: Opportunity,
+    pub record_id: Option<i64>,
+    /// Time spent queued.
+    pub waited: Duration,
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.scheduler.release(&self.markets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Leg, MultiLegOpportunity};
+    use rust_decimal_macros::dec;
+
+    fn multi_leg(markets: &[&str], profit: Decimal) -> Opportunity {
+        let legs = markets.iter().map(|m| Leg { market_id: m.to_string(), condition_name: "Yes".to_string(), asset_id: format!("{}-yes", m), price: dec!(0.4) }).collect();
+        Opportunity::multi_leg(MultiLegOpportunity { legs, is_cycle: false, profit })
+    }
+
+    fn next_market(scheduler: &Arc<Scheduler>, now: Instant) -> Option<(String, Ticket)> {
+        scheduler.try_next_at(now).map(|t| (t.opportunity.exposure_market().to_string(), t))
+    }
+
+    #[test]
+    fn test_pops_by_margin_with_market_locks_limits_and_ttl() {
+        let scheduler = Arc::new(Scheduler::new(SchedulerConfig { max_concurrent: 2, ttl: Duration::from_secs(2) }));
+        let start = Instant::now();
+        scheduler.push_at(multi_leg(&["a", "b"], dec!(0.02)), None, start);
+        scheduler.push_at(multi_leg(&["b", "c"], dec!(0.05)), None, start);
+        scheduler.push_at(multi_leg(&["d"], dec!(0.01)), None, start);
+        // A fresher detection of the same opportunity replaces the queued one
+        scheduler.push_at(multi_leg(&["d"], dec!(0.03)), Some(7), start);
+
+        let (first, first_ticket) = next_market(&scheduler, start).unwrap();
+        assert_eq!(first, "b");
+        // "a" shares market b with the running execution, so "d" goes next
+        let (second, second_ticket) = next_market(&scheduler, start).unwrap();
+        assert_eq!((second.as_str(), second_ticket.record_id), ("d", Some(7)));
+        assert!(next_market(&scheduler, start).is_none());
+
+        drop(second_ticket);
+        assert!(next_market(&scheduler, start).is_none(), "a is still blocked on b");
+        drop(first_ticket);
+        assert!(next_market(&scheduler, start + Duration::from_secs(3)).is_none(), "a expired while queued");
+
+        scheduler.push_at(multi_leg(&["a"], dec!(0.02)), None, start);
+        assert_eq!(next_market(&scheduler, start + Duration::from_secs(1)).unwrap().0, "a");
+    }
+}
@@ -1,15 +1,100 @@
+use crate::arbitrage_engine::{are_markets_related, pair_implications, PatternRegistry};
+use crate::similarity::TitleSimilarity;
+use crate::clob_client::OrderSide;
+use crate::fees::{fee_for, FeeModel, FeeSchedule};
+use crate::opportunity_store::OpportunityKind;
+use crate::profit_model::ExecutionEstimate;
+use lazy_static::lazy_static;
+use rayon::prelude::*;
 use rust_decimal::Decimal;
-use chrono::NaiveDate;
-use std::collections::HashMap;
+use rust_decimal_macros::dec;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Tick size assumed until the CLOB reports a market's own.
+pub const DEFAULT_TICK_SIZE: Decimal = dec!(0.01);
+/// Lock shards in [`LivePrices`].
+const PRICE_SHARDS: usize = 64;
 
 #[derive(Debug, Clone)]
 pub struct Market {
     pub id: String,
     pub title: String,
-    pub end_date: NaiveDate,
+    /// When the market stops trading, in UTC.
+    pub end_date: DateTime<Utc>,
     pub conditions: Vec<Condition>,
     pub neg_risk_market_id: Option<String>,
     pub tags: Vec<String>,
+    /// Resting order book depth reported by Gamma, in USDC.
+    pub liquidity: Decimal,
+    /// Traded volume over the last 24 hours, in USDC.
+    pub volume_24hr: Decimal,
+    /// Best ask minus best bid, when Gamma reports it.
+    pub spread: Option<Decimal>,
+    /// CTF condition id (bytes32 hex), needed to split and merge outcome tokens.
+    pub condition_id: String,
+    /// UMA question id (bytes32 hex) the condition resolves on.
+    pub question_id: String,
+    /// Price increment the CLOB accepts for this market's orders.
+    pub tick_size: Decimal,
+    /// Smallest order the CLOB accepts, in shares.
+    pub min_order_size: Decimal,
+    /// Gamma event the market is listed under, when known.
+    pub event_id: Option<String>,
+    /// How Gamma says the market resolves, for scoring whether two markets resolve consistently.
+    pub resolution: ResolutionRules,
+    /// Maker/taker rates from the CLOB, once enriched.
+    pub fees: Option<FeeSchedule>,
+}
+
+/// An empty market ending 2025-01-01 at the default tick size, for tests to fill in with
+/// struct-update syntax.
+#[cfg(test)]
+impl Default for Market {
+    fn default() -> Self {
+        use chrono::TimeZone;
+        Market {
+            id: String::new(),
+            title: String::new(),
+            end_date: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            conditions: vec![],
+            neg_risk_market_id: None,
+            tags: vec![],
+            liquidity: Decimal::ZERO,
+            volume_24hr: Decimal::ZERO,
+            spread: None,
+            condition_id: String::new(),
+            question_id: String::new(),
+            tick_size: DEFAULT_TICK_SIZE,
+            min_order_size: Decimal::ZERO,
+            event_id: None,
+            resolution: ResolutionRules::default(),
+            fees: None,
+        }
+    }
+}
+
+/// A market's resolution terms as listed on Gamma. Empty when Gamma has none.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolutionRules {
+    /// Rule text, including the resolution criteria.
+    pub description: String,
+    /// Where the outcome is read from, usually a URL.
+    pub source: String,
+    /// Address of the oracle adapter that settles the market.
+    pub resolved_by: String,
+}
+
+/// A Gamma event and its member markets, which all hinge on the same underlying question.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub id: String,
+    pub title: String,
+    pub markets: Vec<Market>,
 }
 
 #[derive(Debug, Clone)]
@@ -18,13 +103,48 @@ pub struct Condition {
     pub price: Decimal,
     pub outcome: Option<bool>, // true for YES, false for NO
     pub asset_id: String,      // The token address/ID for this outcome
+    /// What the outcome name denotes, parsed once when the market is fetched.
+    pub kind: OutcomeKind,
+    /// When `price` was last streamed; `None` while it is still the fetched one.
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// Meaning of an outcome name in a multi-outcome market.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutcomeKind {
+    Yes,
+    No,
+    /// Canonical name from the entity dictionary ("trump").
+    Candidate(String),
+    Team(String),
+    /// Inclusive bounds; open ends are 0 and `Decimal::MAX`. Percentages keep their number.
+    Range { low: Decimal, high: Decimal },
+    Date(NaiveDate),
+    #[default]
+    Other,
 }
 
 #[derive(Debug)]
 pub struct RebalancingOpportunity {
     pub market_id: String,
+    /// Average profit per complete set over `size`.
     pub profit: Decimal,
     pub opportunity_type: String, // "Long" or "Short"
+    /// Complete sets executable while every extra set stays profitable.
+    pub size: Decimal,
+    /// Worst price reached on each leg (asks for Long, bids for Short), in condition order.
+    pub limit_prices: Vec<Decimal>,
+}
+
+impl RebalancingOpportunity {
+    /// USDC needed to trade all of `size`: the asks paid for a Long, one dollar per split set
+    /// for a Short.
+    pub fn max_amount(&self) -> Decimal {
+        match self.opportunity_type.as_str() {
+            "Long" => self.size * (Decimal::ONE - self.profit),
+            _ => self.size,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -33,7 +153,72 @@ pub struct CombinatorialOpportunity {
     pub market_id_2: String,
     pub condition_name_1: String,
     pub condition_name_2: String,
+    /// Token of the implying (overpriced) condition.
+    pub implying_asset_id: String,
+    /// Token of the implied (underpriced) condition.
+    pub implied_asset_id: String,
+    /// The implying market's other outcome, bought to hedge rather than shorting the implying
+    /// token. `None` when that market is not a YES/NO pair, which leaves nothing to hedge with.
+    pub implying_complement_asset_id: Option<String>,
     pub profit: Decimal,
+    /// Fee schedules of the implying and implied conditions' markets.
+    pub implying_fees: Option<FeeSchedule>,
+    pub implied_fees: Option<FeeSchedule>,
+    /// Confidence of the dependency behind the trade.
+    pub confidence: f64,
+    pub explanation: String,
+}
+
+/// Mispricing across all markets sharing a `neg_risk_market_id`.
+/// "Long" buys every YES leg; "Short" buys every NO leg and converts them via the NegRiskAdapter.
+#[derive(Debug)]
+pub struct BasketOpportunity {
+    pub neg_risk_market_id: String,
+    pub market_ids: Vec<String>,
+    pub yes_price_sum: Decimal,
+    /// Profit per set; averaged over the most profitable size when the books were walked.
+    pub profit: Decimal,
+    pub opportunity_type: String, // "Long" or "Short"
+    /// The outcome bought in every member, YES for a Long and NO for a Short, priced at the
+    /// deepest ask the walk took (the quoted price without books).
+    pub legs: Vec<Leg>,
+    /// Question id of every member, in `legs` order; the NegRiskAdapter indexes a question by
+    /// the last byte of its id.
+    pub question_ids: Vec<String>,
+    /// Running (sets, expected profit) the legs' books support, one point per level boundary.
+    /// Empty when a leg had no book.
+    pub frontier: Vec<(Decimal, Decimal)>,
+}
+
+impl BasketOpportunity {
+    /// Cost of one share of every leg at its limit.
+    pub fn cost_per_set(&self) -> Decimal {
+        self.legs.iter().map(|l| l.price).sum()
+    }
+
+    /// Complete sets `amount` USDC buys at the legs' limits.
+    pub fn sets_for(&self, amount: Decimal) -> Decimal {
+        let cost = self.cost_per_set();
+        if cost.is_zero() { Decimal::ZERO } else { amount / cost }
+    }
+
+    /// The frontier point that makes the most.
+    pub fn best_size(&self) -> Option<(Decimal, Decimal)> {
+        self.frontier.iter().copied().max_by_key(|&(_, profit)| profit)
+    }
+
+    /// Expected profit of trading `sets`, interpolated along the frontier. Sets past its end
+    /// find no depth and add nothing; without a frontier every set makes `profit`.
+    pub fn expected_profit(&self, sets: Decimal) -> Decimal {
+        let mut prev = (Decimal::ZERO, Decimal::ZERO);
+        for &(size, profit) in &self.frontier {
+            if sets <= size && size > prev.0 {
+                return prev.1 + (profit - prev.1) * (sets - prev.0) / (size - prev.0);
+            }
+            prev = (size, profit);
+        }
+        self.frontier.last().map_or(self.profit * sets, |&(_, profit)| profit)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -42,29 +227,546 @@ pub enum Direction {
     C2ImpliesC1,
 }
 
+/// One leg of a multi-market implication chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub market_id: String,
+    pub condition_name: String,
+    pub asset_id: String,
+    pub price: Decimal,
+}
+
+/// Chain A ⇒ B ⇒ ... ⇒ Z (or a cycle of equivalent conditions) whose prices violate the
+/// implied ordering. Profit is earned by buying the cheapest leg and shorting the dearest.
+#[derive(Debug, Clone)]
+pub struct MultiLegOpportunity {
+    pub legs: Vec<Leg>,
+    pub is_cycle: bool,
+    pub profit: Decimal,
+}
+
+/// Rungs of a crypto price-threshold ladder ("BTC above 90k / 100k / 110k" on one date) priced
+/// out of order. Each inverted pair is bought as YES on the looser rung plus NO on the stricter
+/// one, which pays at least 1 whatever the price does. `legs` holds every token to buy.
+#[derive(Debug, Clone)]
+pub struct LadderOpportunity {
+    /// Shared title stem, direction and end date of the ladder.
+    pub ladder: String,
+    pub legs: Vec<Leg>,
+    /// Guaranteed profit from buying one share of every leg.
+    pub profit: Decimal,
+}
+
+impl LadderOpportunity {
+    /// Cost of one share of every leg.
+    pub fn cost(&self) -> Decimal {
+        self.legs.iter().map(|l| l.price).sum()
+    }
+}
+
+lazy_static! {
+    /// Seeded with the start time in microseconds, so ids stay unique across restarts.
+    static ref NEXT_OPPORTUNITY_ID: AtomicU64 = AtomicU64::new(Utc::now().timestamp_micros().max(0) as u64);
+}
+
+/// What every opportunity carries, whichever strategy found it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpportunityMeta {
+    pub id: u64,
+    pub detected_at: DateTime<Utc>,
+    /// Name of the strategy that found it.
+    pub strategy: &'static str,
+    /// Every token traded.
+    pub asset_ids: Vec<String>,
+    /// Prices of the traded legs at detection.
+    pub prices: Vec<Decimal>,
+    /// Shares (or complete sets) the profits cover; one where the detector prices top of book only.
+    pub size: Decimal,
+    /// Profit over `size` before taker fees, in USDC.
+    pub gross_profit: Decimal,
+    /// Profit over `size` after the fees and gas the detector modeled, in USDC.
+    pub net_profit: Decimal,
+}
+
+impl OpportunityMeta {
+    fn new(strategy: &'static str, asset_ids: Vec<String>, prices: Vec<Decimal>, size: Decimal, net_profit: Decimal) -> Self {
+        Self {
+            id: NEXT_OPPORTUNITY_ID.fetch_add(1, Ordering::Relaxed),
+            detected_at: Utc::now(),
+            strategy,
+            asset_ids,
+            prices,
+            size,
+            gross_profit: net_profit,
+            net_profit,
+        }
+    }
+
+    /// Net profit per share, as alerts and the store report it.
+    pub fn profit_per_share(&self) -> Decimal {
+        if self.size.is_zero() { self.net_profit } else { self.net_profit / self.size }
+    }
+}
+
+/// A detected opportunity of any kind, as it moves from strategy to scheduler, executor, store
+/// and alerts.
+#[derive(Debug)]
+pub enum Opportunity {
+    Rebalancing { meta: OpportunityMeta, market: Market, op: RebalancingOpportunity },
+    NegRiskBasket { meta: OpportunityMeta, op: BasketOpportunity },
+    ThresholdLadder { meta: OpportunityMeta, op: LadderOpportunity },
+    Combinatorial { meta: OpportunityMeta, op: CombinatorialOpportunity, estimate: ExecutionEstimate },
+    MultiLeg { meta: OpportunityMeta, op: MultiLegOpportunity },
+}
+
+impl Opportunity {
+    pub fn rebalancing(market: Market, op: RebalancingOpportunity, fees: &FeeModel) -> Self {
+        let asset_ids = market.conditions.iter().map(|c| c.asset_id.clone()).collect();
+        let meta = OpportunityMeta::new(OpportunityKind::Rebalancing.as_str(), asset_ids, op.limit_prices.clone(), op.size, op.profit * op.size);
+        Self::Rebalancing { meta, market, op }.with_gross(fees)
+    }
+
+    /// `basket` holds the member markets at the prices the basket was found at.
+    pub fn neg_risk_basket(basket: &[&Market], op: BasketOpportunity, fees: &FeeModel) -> Self {
+        let prices = basket.iter().filter_map(|m| m.conditions.iter().find(|c| c.outcome == Some(true)).map(|c| c.price)).collect();
+        let (size, profit) = op.best_size().unwrap_or((Decimal::ONE, op.profit));
+        let meta = OpportunityMeta::new(OpportunityKind::NegRiskBasket.as_str(), op.legs.iter().map(|l| l.asset_id.clone()).collect(), prices, size, profit);
+        Self::NegRiskBasket { meta, op }.with_gross(fees)
+    }
+
+    pub fn threshold_ladder(op: LadderOpportunity, fees: &FeeModel) -> Self {
+        let meta = OpportunityMeta::new(
+            OpportunityKind::ThresholdLadder.as_str(),
+            op.legs.iter().map(|l| l.asset_id.clone()).collect(),
+            op.legs.iter().map(|l| l.price).collect(),
+            Decimal::ONE,
+            op.profit,
+        );
+        Self::ThresholdLadder { meta, op }.with_gross(fees)
+    }
+
+    pub fn combinatorial(op: CombinatorialOpportunity, estimate: ExecutionEstimate, prices: Vec<Decimal>) -> Self {
+        let asset_ids = vec![op.implying_asset_id.clone(), op.implied_asset_id.clone()];
+        let mut meta = OpportunityMeta::new(OpportunityKind::Combinatorial.as_str(), asset_ids, prices, estimate.size, estimate.net_profit);
+        meta.gross_profit = estimate.gross_profit;
+        Self::Combinatorial { meta, op, estimate }
+    }
+
+    /// Chains are priced without fees, so gross and net profit match.
+    pub fn multi_leg(op: MultiLegOpportunity) -> Self {
+        let meta = OpportunityMeta::new(
+            OpportunityKind::MultiLeg.as_str(),
+            op.legs.iter().map(|l| l.asset_id.clone()).collect(),
+            op.legs.iter().map(|l| l.price).collect(),
+            Decimal::ONE,
+            op.profit,
+        );
+        Self::MultiLeg { meta, op }
+    }
+
+    /// Adds back the taker fees on `meta.size` that the detector netted out.
+    fn with_gross(mut self, fees: &FeeModel) -> Self {
+        let amount = match &self {
+            Opportunity::Rebalancing { op, .. } => op.max_amount(),
+            Opportunity::NegRiskBasket { op, meta } => op.cost_per_set() * meta.size,
+            Opportunity::ThresholdLadder { op, .. } => op.cost(),
+            _ => return self,
+        };
+        let fee = self.fees(fees, amount);
+        self.meta_mut().gross_profit += fee;
+        self
+    }
+
+    pub fn meta(&self) -> &OpportunityMeta {
+        match self {
+            Opportunity::Rebalancing { meta, .. }
+            | Opportunity::NegRiskBasket { meta, .. }
+            | Opportunity::ThresholdLadder { meta, .. }
+            | Opportunity::Combinatorial { meta, .. }
+            | Opportunity::MultiLeg { meta, .. } => meta,
+        }
+    }
+
+    pub fn meta_mut(&mut self) -> &mut OpportunityMeta {
+        match self {
+            Opportunity::Rebalancing { meta, .. }
+            | Opportunity::NegRiskBasket { meta, .. }
+            | Opportunity::ThresholdLadder { meta, .. }
+            | Opportunity::Combinatorial { meta, .. }
+            | Opportunity::MultiLeg { meta, .. } => meta,
+        }
+    }
+
+    pub fn kind(&self) -> OpportunityKind {
+        match self {
+            Opportunity::Rebalancing { .. } => OpportunityKind::Rebalancing,
+            Opportunity::NegRiskBasket { .. } => OpportunityKind::NegRiskBasket,
+            Opportunity::ThresholdLadder { .. } => OpportunityKind::ThresholdLadder,
+            Opportunity::Combinatorial { .. } => OpportunityKind::Combinatorial,
+            Opportunity::MultiLeg { .. } => OpportunityKind::MultiLeg,
+        }
+    }
+
+    /// Identity of the opportunity for the cooldown gate; a newer detection replaces a queued one.
+    pub fn gate_key(&self) -> &str {
+        match self {
+            Opportunity::ThresholdLadder { op, .. } => &op.ladder,
+            _ => self.exposure_market(),
+        }
+    }
+
+    /// Market the position is sized against and its exposure booked to.
+    pub fn exposure_market(&self) -> &str {
+        match self {
+            Opportunity::Rebalancing { op, .. } => &op.market_id,
+            Opportunity::NegRiskBasket { op, .. } => &op.neg_risk_market_id,
+            Opportunity::ThresholdLadder { op, .. } => &op.legs[0].market_id,
+            Opportunity::Combinatorial { op, .. } => &op.market_id_1,
+            Opportunity::MultiLeg { op, .. } => &op.legs[0].market_id,
+        }
+    }
+
+    /// Every market traded, each locked while the opportunity executes.
+    pub fn markets(&self) -> Vec<String> {
+        match self {
+            Opportunity::Rebalancing { op, .. } => vec![op.market_id.clone()],
+            Opportunity::NegRiskBasket { op, .. } => op.market_ids.clone(),
+            Opportunity::ThresholdLadder { op, .. } => op.legs.iter().map(|l| l.market_id.clone()).collect(),
+            Opportunity::Combinatorial { op, .. } => vec![op.market_id_1.clone(), op.market_id_2.clone()],
+            Opportunity::MultiLeg { op, .. } => op.legs.iter().map(|l| l.market_id.clone()).collect(),
+        }
+    }
+
+    /// Net expected profit per dollar committed; the queue's priority.
+    pub fn margin(&self) -> Decimal {
+        match self {
+            Opportunity::Rebalancing { op, .. } => op.profit,
+            Opportunity::NegRiskBasket { op, .. } => {
+                let cost = op.cost_per_set();
+                if cost.is_zero() { Decimal::ZERO } else { op.profit / cost }
+            }
+            Opportunity::ThresholdLadder { op, .. } => op.profit / op.cost(),
+            Opportunity::Combinatorial { estimate, .. } => estimate.net_margin(),
+            Opportunity::MultiLeg { op, .. } => op.profit,
+        }
+    }
+
+    /// Assets traded with limit orders left resting for the leg timeout, and their sides. These
+    /// are the legs the order-flow gate checks; rebalancing takes depth the detector measured, so
+    /// it has none.
+    pub fn resting_legs(&self) -> Vec<(String, OrderSide)> {
+        match self {
+            Opportunity::Rebalancing { .. } => Vec::new(),
+            Opportunity::NegRiskBasket { op, .. } => op.legs.iter().map(|l| (l.asset_id.clone(), OrderSide::Buy)).collect(),
+            Opportunity::ThresholdLadder { op, .. } => op.legs.iter().map(|l| (l.asset_id.clone(), OrderSide::Buy)).collect(),
+            Opportunity::Combinatorial { op, .. } => std::iter::once(&op.implied_asset_id).chain(&op.implying_complement_asset_id)
+                .map(|asset_id| (asset_id.clone(), OrderSide::Buy))
+                .collect(),
+            Opportunity::MultiLeg { op, .. } => {
+                // Buys the cheapest leg and shorts the dearest
+                let cheapest = op.legs.iter().min_by_key(|l| l.price);
+                let dearest = op.legs.iter().max_by_key(|l| l.price);
+                cheapest.map(|l| (l.asset_id.clone(), OrderSide::Buy)).into_iter().chain(dearest.map(|l| (l.asset_id.clone(), OrderSide::Sell))).collect()
+            }
+        }
+    }
+
+    /// Largest amount the books support, when the detector measured depth.
+    pub fn max_amount(&self) -> Option<Decimal> {
+        match self {
+            Opportunity::Rebalancing { op, .. } => Some(op.max_amount()),
+            Opportunity::Combinatorial { estimate, .. } => Some(estimate.size * estimate.avg_buy_price),
+            Opportunity::NegRiskBasket { op, .. } => op.best_size().map(|(sets, _)| sets * op.cost_per_set()),
+            _ => None,
+        }
+    }
+
+    /// Net profit expected from committing `amount`. Baskets read the sets it buys off their depth
+    /// frontier; everything else earns its margin throughout.
+    pub fn expected_profit(&self, amount: Decimal) -> Decimal {
+        match self {
+            Opportunity::NegRiskBasket { op, .. } => op.expected_profit(op.sets_for(amount)),
+            _ => self.margin() * amount,
+        }
+    }
+
+    /// Taker fees on the legs when `amount` USDC is committed. Legs without a market schedule
+    /// at hand are charged the default rate.
+    pub fn fees(&self, model: &FeeModel, amount: Decimal) -> Decimal {
+        // Sets of every leg bought with `amount`, each leg paying its own fee
+        let legs = |prices: &[Decimal]| {
+            let cost: Decimal = prices.iter().sum();
+            if cost.is_zero() { return Decimal::ZERO; }
+            prices.iter().map(|&p| fee_for(model.default_taker_fee_bps, p, amount / cost)).sum()
+        };
+        match self {
+            Opportunity::Rebalancing { market, op, .. } => {
+                let cost: Decimal = op.limit_prices.iter().sum();
+                if cost.is_zero() { return Decimal::ZERO; }
+                op.limit_prices.iter().map(|&p| model.taker_fee(market, p, amount / cost)).sum()
+            }
+            Opportunity::NegRiskBasket { op, .. } => legs(&op.legs.iter().map(|l| l.price).collect::<Vec<_>>()),
+            Opportunity::ThresholdLadder { op, .. } => legs(&op.legs.iter().map(|l| l.price).collect::<Vec<_>>()),
+            Opportunity::MultiLeg { op, .. } => legs(&op.legs.iter().map(|l| l.price).collect::<Vec<_>>()),
+            Opportunity::Combinatorial { estimate, .. } => {
+                let notional = estimate.size * estimate.avg_buy_price;
+                if notional.is_zero() { Decimal::ZERO } else { estimate.fees * amount / notional }
+            }
+        }
+    }
+}
+
+/// A streamed price and when it arrived.
+type Quote = (Decimal, DateTime<Utc>);
+
+/// Latest streamed outcome prices and when they arrived, sharded by asset id so ticks on
+/// unrelated markets take different locks. Where present they override the fetched
+/// `Condition::price`.
+#[derive(Debug)]
+pub struct LivePrices {
+    shards: Vec<RwLock<HashMap<String, Quote>>>,
+}
+
+impl Default for LivePrices {
+    fn default() -> Self {
+        Self { shards: (0..PRICE_SHARDS).map(|_| RwLock::default()).collect() }
+    }
+}
+
+impl LivePrices {
+    fn shard(&self, asset_id: &str) -> &RwLock<HashMap<String, Quote>> {
+        let mut hasher = DefaultHasher::new();
+        asset_id.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    pub fn set(&self, asset_id: &str, price: Decimal, at: DateTime<Utc>) {
+        self.shard(asset_id).write().unwrap_or_else(|e| e.into_inner()).insert(asset_id.to_string(), (price, at));
+    }
+
+    pub fn get(&self, asset_id: &str) -> Option<Decimal> {
+        self.quote(asset_id).map(|(price, _)| price)
+    }
+
+    /// The streamed price and when it arrived.
+    pub fn quote(&self, asset_id: &str) -> Option<Quote> {
+        self.shard(asset_id).read().unwrap_or_else(|e| e.into_inner()).get(asset_id).copied()
+    }
+
+    pub fn remove(&self, asset_id: &str) {
+        self.shard(asset_id).write().unwrap_or_else(|e| e.into_inner()).remove(asset_id);
+    }
+
+    /// The condition's streamed price, or its fetched one.
+    pub fn price_of(&self, condition: &Condition) -> Decimal {
+        self.get(&condition.asset_id).unwrap_or(condition.price)
+    }
+}
+
+/// Stable handle to a tracked market. Handles are never reused, so one held across a refresh
+/// resolves to nothing rather than to whichever market took its storage slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MarketId(u64);
+
+/// Stable handle to one outcome of a tracked market. Not to be confused with the CTF
+/// `Market::condition_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConditionId {
+    pub market: MarketId,
+    pub index: usize,
+}
+
+/// Tracked markets in a dense `Vec`, addressed through stable handles. Removal swaps the last
+/// market into the freed slot; only the registry ever sees slot positions.
+#[derive(Debug, Default)]
+pub struct MarketRegistry {
+    markets: Vec<Market>,
+    ids: Vec<MarketId>,
+    slots: HashMap<MarketId, usize>,
+    by_market: HashMap<String, MarketId>,
+    assets: HashMap<String, ConditionId>,
+    next_id: u64,
+}
+
+impl MarketRegistry {
+    pub fn new(markets: Vec<Market>) -> Self {
+        let mut registry = Self::default();
+        for market in markets {
+            registry.insert(market);
+        }
+        registry
+    }
+
+    /// Tracks a market under a fresh handle. Returns `None` if a market with its id is tracked.
+    pub fn insert(&mut self, market: Market) -> Option<MarketId> {
+        if self.by_market.contains_key(&market.id) {
+            return None;
+        }
+        let id = MarketId(self.next_id);
+        self.next_id += 1;
+        for (index, condition) in market.conditions.iter().enumerate() {
+            if !condition.asset_id.is_empty() {
+                self.assets.entry(condition.asset_id.clone()).or_insert(ConditionId { market: id, index });
+            }
+        }
+        self.by_market.insert(market.id.clone(), id);
+        self.slots.insert(id, self.markets.len());
+        self.ids.push(id);
+        self.markets.push(market);
+        Some(id)
+    }
+
+    /// Stops tracking a market; its handle and asset ids resolve to nothing afterwards.
+    pub fn remove(&mut self, market_id: &str) -> Option<(MarketId, Market)> {
+        let id = self.by_market.remove(market_id)?;
+        let slot = self.slots.remove(&id)?;
+        self.ids.swap_remove(slot);
+        let market = self.markets.swap_remove(slot);
+        if let Some(&moved) = self.ids.get(slot) {
+            self.slots.insert(moved, slot);
+        }
+        for condition in &market.conditions {
+            if self.assets.get(&condition.asset_id).is_some_and(|c| c.market == id) {
+                self.assets.remove(&condition.asset_id);
+            }
+        }
+        Some((id, market))
+    }
+
+    pub fn get(&self, id: MarketId) -> Option<&Market> {
+        self.slots.get(&id).map(|&slot| &self.markets[slot])
+    }
+
+    pub fn condition(&self, id: ConditionId) -> Option<&Condition> {
+        self.get(id.market)?.conditions.get(id.index)
+    }
+
+    /// Handle of the market with this Gamma id.
+    pub fn handle(&self, market_id: &str) -> Option<MarketId> {
+        self.by_market.get(market_id).copied()
+    }
+
+    /// Outcome an asset id trades.
+    pub fn resolve(&self, asset_id: &str) -> Option<ConditionId> {
+        self.assets.get(asset_id).copied()
+    }
+
+    pub fn asset_ids(&self) -> impl Iterator<Item = &String> {
+        self.assets.keys()
+    }
+
+    /// Handle of the market at a position in `as_slice`.
+    pub fn id_at(&self, slot: usize) -> MarketId {
+        self.ids[slot]
+    }
+
+    /// Position of a market in `as_slice`; only valid until the next removal.
+    pub fn slot(&self, id: MarketId) -> Option<usize> {
+        self.slots.get(&id).copied()
+    }
+
+    /// Markets in storage order, for whole-set passes.
+    pub fn as_slice(&self) -> &[Market] {
+        &self.markets
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (MarketId, &Market)> {
+        self.ids.iter().copied().zip(&self.markets)
+    }
+
+    pub fn len(&self) -> usize {
+        self.markets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markets.is_empty()
+    }
+}
+
+/// Related market pairs plus a directed implication graph over outcome tokens (asset_ids).
+/// An edge A -> B means "A resolving YES implies B resolves YES", so P(A) <= P(B) must hold.
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
     pub related_markets: Vec<(String, String)>, // Pairs of market IDs
-    pub implications: HashMap<(String, String), Direction>,
+    pub implications: HashMap<String, HashSet<String>>,
+    pub implied_by: HashMap<String, HashSet<String>>,
+}
+
+impl DependencyGraph {
+    pub fn add_implication(&mut self, from: &str, to: &str) {
+        self.implications.entry(from.to_string()).or_default().insert(to.to_string());
+        self.implied_by.entry(to.to_string()).or_default().insert(from.to_string());
+    }
+
+    /// Drops every implication edge touching `asset_id`.
+    pub fn remove_asset(&mut self, asset_id: &str) {
+        for to in self.implications.remove(asset_id).unwrap_or_default() {
+            if let Some(set) = self.implied_by.get_mut(&to) { set.remove(asset_id); }
+        }
+        for from in self.implied_by.remove(asset_id).unwrap_or_default() {
+            if let Some(set) = self.implications.get_mut(&from) { set.remove(asset_id); }
+        }
+    }
+
+    /// Links `markets[idx]` to every market related to it, returning their indices. Only edges
+    /// touching the new market are added.
+    pub fn add_market(&mut self, markets: &[Market], idx: usize, similarity: &TitleSimilarity, similarity_cutoff: f64, patterns: &PatternRegistry) -> Vec<usize> {
+        let market = &markets[idx];
+        let related: Vec<_> = (0..markets.len())
+            .into_par_iter()
+            .filter(|&j| j != idx && are_markets_related(&markets[j], market, similarity, similarity_cutoff, patterns.expiry_tolerance()))
+            .map(|j| (j, pair_implications(&markets[j], market, patterns)))
+            .collect();
+
+        related.into_iter().map(|(j, edges)| {
+            self.related_markets.push((markets[j].id.clone(), market.id.clone()));
+            for (from, to) in &edges {
+                self.add_implication(from, to);
+            }
+            j
+        }).collect()
+    }
+
+    /// Drops `market`'s related pairs and every implication edge touching its assets.
+    pub fn remove_market(&mut self, market: &Market) {
+        for condition in &market.conditions {
+            self.remove_asset(&condition.asset_id);
+        }
+        self.related_markets.retain(|(a, b)| *a != market.id && *b != market.id);
+    }
+
+    pub fn implication_count(&self) -> usize {
+        self.implications.values().map(|s| s.len()).sum()
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Entity {
     Candidate(String),
     Location(String),
+    Team(String),
     Event(String),
     NumericalValue(Decimal),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum PatternType {
     WinnerMargin,
+    Complement,
+    Deadline,
+    Threshold,
     SubsetImplication,
     NumericRange,
+    Spread,
+    Series,
 }
 
 #[derive(Debug)]
 pub struct Dependency {
     pub pattern: PatternType,
     pub direction: Direction,
+    /// How far the pattern can be trusted, 0-1.
+    pub confidence: f64,
+    /// What matched, for logs.
+    pub explanation: String,
 }
\ No newline at end of file
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+/// Broadcasts a one-shot shutdown request to every long-running task.
+#[derive(Clone)]
+pub struct ShutdownController {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl Default for ShutdownController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+        Self { tx: Arc::new(tx), rx }
+    }
+
+    pub fn trigger(&self) {
+        let _ = self.tx.send(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolves once shutdown has been requested.
+    pub async fn wait(&self) {
+        let mut rx = self.rx.clone();
+        while !*rx.borrow_and_update() {
+            if rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Spawns a task that triggers shutdown on Ctrl-C or SIGTERM.
+    pub fn listen_for_signals(&self) -> JoinHandle<()> {
+        let controller = self.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            info!("shutdown signal received, stopping streams and unwinding");
+            controller.trigger();
+        })
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_resolves_after_trigger() {
+        let controller = ShutdownController::new();
+        let waiter = controller.clone();
+        let handle = tokio::spawn(async move { waiter.wait().await });
+        assert!(!controller.is_triggered());
+        controller.trigger();
+        handle.await.unwrap();
+        assert!(controller.is_triggered());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::clob_client::OrderSide;
+use crate::order_book::{BookSide, OrderBook};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+pub type SharedSignals = Arc<Signals>;
+
+/// Thresholds for the entry gate. A leg is refused when the flow against the position it builds
+/// exceeds either limit; 0 requires a neutral or favorable signal, unset skips the check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalConfig {
+    /// Trades and mid prices older than this are forgotten.
+    pub window: Duration,
+    /// Book levels per side summed for the depth imbalance.
+    pub depth_levels: usize,
+    /// Adverse imbalance, from 0 to 1.
+    pub max_adverse_imbalance: Option<Decimal>,
+    /// Adverse mid move over the window, as a fraction of the starting mid.
+    pub max_adverse_momentum: Option<Decimal>,
+}
+
+impl Default for SignalConfig {
+    fn default() -> Self {
+        Self { window: Duration::from_secs(30), depth_levels: 5, max_adverse_imbalance: None, max_adverse_momentum: None }
+    }
+}
+
+impl SignalConfig {
+    pub fn gates(&self) -> bool {
+        self.max_adverse_imbalance.is_some() || self.max_adverse_momentum.is_some()
+    }
+}
+
+/// Short-horizon read on one asset. Positive values mean buying pressure or a rising price.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Signal {
+    /// Mean of the top-of-book depth imbalance and the taker flow imbalance over the window,
+    /// each `(buy - sell) / (buy + sell)`. `None` without a book or trades.
+    pub imbalance: Option<Decimal>,
+    /// Relative change of the mid price across the window. `None` with fewer than two mids.
+    pub momentum: Option<Decimal>,
+}
+
+#[derive(Debug, Default)]
+struct Flow {
+    /// Taker side and size of each trade.
+    trades: VecDeque<(Instant, OrderSide, Decimal)>,
+    mids: VecDeque<(Instant, Decimal)>,
+}
+
+impl Flow {
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while self.trades.front().is_some_and(|(t, _, _)| now.duration_since(*t) > window) {
+            self.trades.pop_front();
+        }
+        // The newest mid older than the window stays as the momentum baseline
+        while self.mids.get(1).is_some_and(|(t, _)| now.duration_since(*t) > window) {
+            self.mids.pop_front();
+        }
+    }
+}
+
+/// Order-flow imbalance and momentum per asset, fed by the book stream.
+#[derive(Debug)]
+pub struct Signals {
+    config: SignalConfig,
+    flows: Mutex<HashMap<String, Flow>>,
+}
+
+impl Signals {
+    pub fn new(config: SignalConfig) -> Self {
+        Self { config, flows: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn config(&self) -> &SignalConfig {
+        &self.config
+    }
+
+    fn flows(&self) -> MutexGuard<'_, HashMap<String, Flow>> {
+        self.flows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn record_trade(&self, asset_id: &str, side: OrderSide, size: Decimal, now: Instant) {
+        let mut flows = self.flows();
+        let flow = flows.entry(asset_id.to_string()).or_default();
+        flow.trades.push_back((now, side, size));
+        flow.prune(now, self.config.window);
+    }
+
+    /// Samples the mid price; repeats of the last mid are skipped.
+    pub fn record_mid(&self, asset_id: &str, mid: Decimal, now: Instant) {
+        let mut flows = self.flows();
+        let flow = flows.entry(asset_id.to_string()).or_default();
+        if flow.mids.back().is_none_or(|(_, last)| *last != mid) {
+            flow.mids.push_back((now, mid));
+        }
+        flow.prune(now, self.config.window);
+    }
+
+    pub fn signal(&self, asset_id: &str, book: Option<&OrderBook>, now: Instant) -> Signal {
+        let depth = book.and_then(|book| {
+            let side = |side| book.levels(side).iter().take(self.config.depth_levels).map(|l| l.size).sum::<Decimal>();
+            imbalance(side(BookSide::Bid), side(BookSide::Ask))
+        });
+        let mut flows = self.flows();
+        let Some(flow) = flows.get_mut(asset_id) else { return Signal { imbalance: depth, momentum: None } };
+        flow.prune(now, self.config.window);
+        let (bought, sold) = flow.trades.iter().fold((Decimal::ZERO, Decimal::ZERO), |(b, s), (_, side, size)| match side {
+            OrderSide::Buy => (b + size, s),
+            OrderSide::Sell => (b, s + size),
+        });
+        let imbalance = match (depth, imbalance(bought, sold)) {
+            (Some(depth), Some(flow)) => Some((depth + flow) / Decimal::TWO),
+            (depth, flow) => depth.or(flow),
+        };
+        let momentum = match (flow.mids.front(), flow.mids.back()) {
+            (Some((_, first)), Some((_, last))) if flow.mids.len() > 1 && !first.is_zero() => Some((last - first) / first),
+            _ => None,
+        };
+        Signal { imbalance, momentum }
+    }
+
+    /// Why the legs should not be entered now, or `None` when no leg's signal is too adverse.
+    /// Each leg is an asset and the side the arb trades it on.
+    pub fn adverse(&self, legs: &[(String, OrderSide)], books: &HashMap<String, OrderBook>, now: Instant) -> Option<String> {
+        legs.iter().find_map(|(asset_id, side)| {
+            let signal = self.signal(asset_id, books.get(asset_id), now);
+            // Selling pressure and a falling price work against a buy, and the reverse for a sell
+            let against = |value: Decimal| match side {
+                OrderSide::Buy => -value,
+                OrderSide::Sell => value,
+            };
+            let checks = [("imbalance", signal.imbalance, self.config.max_adverse_imbalance), ("momentum", signal.momentum, self.config.max_adverse_momentum)];
+            checks.into_iter().find_map(|(name, value, max)| {
+                let (value, max) = (value?, max?);
+                let adverse = against(value);
+                (adverse > max).then(|| format!("{} {} of {} is {} against the {} leg", name, value.round_dp(4).normalize(), asset_id, adverse.round_dp(4).normalize(), side.as_str()))
+            })
+        })
+    }
+}
+
+fn imbalance(buy: Decimal, sell: Decimal) -> Option<Decimal> {
+    let total = buy + sell;
+    (!total.is_zero()).then(|| (buy - sell) / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::PriceLevel;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_blocks_legs_facing_adverse_flow() {
+        let signals = Signals::new(SignalConfig { max_adverse_imbalance: Some(dec!(0.5)), max_adverse_momentum: Some(dec!(0.02)), ..SignalConfig::default() });
+        let start = Instant::now();
+        let mut book = OrderBook::default();
+        book.apply_snapshot(&[PriceLevel { price: dec!(0.48), size: dec!(20) }], &[PriceLevel { price: dec!(0.52), size: dec!(180) }]);
+        signals.record_trade("a", OrderSide::Buy, dec!(500), start);
+        signals.record_trade("a", OrderSide::Sell, dec!(70), start + Duration::from_secs(35));
+        signals.record_trade("a", OrderSide::Buy, dec!(30), start + Duration::from_secs(35));
+        // Ages out of the 30s window, leaving 0.50 as the baseline
+        signals.record_mid("a", dec!(0.60), start);
+        signals.record_mid("a", dec!(0.50), start + Duration::from_secs(5));
+        signals.record_mid("a", dec!(0.49), start + Duration::from_secs(40));
+
+        let now = start + Duration::from_secs(40);
+        let signal = signals.signal("a", Some(&book), now);
+        // Book (20 vs 180) at -0.8 and recent taker flow (30 vs 70) at -0.4
+        assert_eq!(signal, Signal { imbalance: Some(dec!(-0.6)), momentum: Some(dec!(-0.02)) });
+
+        let books = HashMap::from([("a".to_string(), book)]);
+        let reason = signals.adverse(&[("a".to_string(), OrderSide::Buy)], &books, now).unwrap();
+        assert_eq!(reason, "imbalance -0.6 of a is 0.6 against the BUY leg");
+        assert_eq!(signals.adverse(&[("a".to_string(), OrderSide::Sell)], &books, now), None);
+        // Nothing known about an asset never blocks it
+        assert_eq!(signals.adverse(&[("b".to_string(), OrderSide::Buy)], &books, now), None);
+    }
+}
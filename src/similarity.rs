@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use strsim::normalized_damerau_levenshtein;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("unknown similarity backend: {0}")]
+pub struct UnknownBackend(pub String);
+
+/// How market titles are compared by `are_markets_related`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimilarityBackend {
+    /// Normalized Damerau-Levenshtein distance of the raw titles.
+    #[default]
+    Levenshtein,
+    /// Cosine of TF-IDF word vectors weighted over the live market set, so titles sharing
+    /// rare words match even when worded differently.
+    TfIdf,
+}
+
+impl FromStr for SimilarityBackend {
+    type Err = UnknownBackend;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "levenshtein" => Ok(SimilarityBackend::Levenshtein),
+            "tfidf" | "tf-idf" => Ok(SimilarityBackend::TfIdf),
+            other => Err(UnknownBackend(other.to_string())),
+        }
+    }
+}
+
+fn terms(title: &str) -> impl Iterator<Item = String> + '_ {
+    title.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).map(str::to_lowercase)
+}
+
+/// Document frequencies of title words across the markets seen so far.
+#[derive(Debug, Clone, Default)]
+pub struct TfIdf {
+    doc_freq: HashMap<String, usize>,
+    docs: usize,
+}
+
+impl TfIdf {
+    pub fn fit<'a>(titles: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut model = Self::default();
+        for title in titles {
+            model.add(title);
+        }
+        model
+    }
+
+    pub fn add(&mut self, title: &str) {
+        self.docs += 1;
+        for term in terms(title).collect::<HashSet<_>>() {
+            *self.doc_freq.entry(term).or_default() += 1;
+        }
+    }
+
+    /// Smoothed inverse document frequency; words never seen weigh the most.
+    fn idf(&self, term: &str) -> f64 {
+        let df = self.doc_freq.get(term).copied().unwrap_or_default();
+        ((1 + self.docs) as f64 / (1 + df) as f64).ln() + 1.0
+    }
+
+    fn vector(&self, title: &str) -> HashMap<String, f64> {
+        let mut tf: HashMap<String, f64> = HashMap::new();
+        for term in terms(title) {
+            *tf.entry(term).or_default() += 1.0;
+        }
+        tf.into_iter().map(|(term, count)| {
+            let weight = count * self.idf(&term);
+            (term, weight)
+        }).collect()
+    }
+
+    /// Cosine similarity (0-1) of the two titles' TF-IDF vectors; 0 when either has no words.
+    pub fn cosine(&self, a: &str, b: &str) -> f64 {
+        let (a, b) = (self.vector(a), self.vector(b));
+        let norm = |v: &HashMap<String, f64>| v.values().map(|w| w * w).sum::<f64>().sqrt();
+        let (na, nb) = (norm(&a), norm(&b));
+        if na == 0.0 || nb == 0.0 {
+            return 0.0;
+        }
+        let dot: f64 = a.iter().filter_map(|(term, w)| b.get(term).map(|v| w * v)).sum();
+        dot / (na * nb)
+    }
+}
+
+/// A configured backend, with whatever it has learned from the market set.
+#[derive(Debug, Clone, Default)]
+pub enum TitleSimilarity {
+    #[default]
+    Levenshtein,
+    TfIdf(TfIdf),
+}
+
+impl TitleSimilarity {
+    pub fn fit<'a>(backend: SimilarityBackend, titles: impl IntoIterator<Item = &'a str>) -> Self {
+        match backend {
+            SimilarityBackend::Levenshtein => TitleSimilarity::Levenshtein,
+            SimilarityBackend::TfIdf => TitleSimilarity::TfIdf(TfIdf::fit(titles)),
+        }
+    }
+
+    /// Counts a market added after the fit towards the word weights.
+    pub fn observe(&mut self, title: &str) {
+        if let TitleSimilarity::TfIdf(model) = self {
+            model.add(title);
+        }
+    }
+
+    /// Similarity of two titles in [0, 1].
+    pub fn score(&self, a: &str, b: &str) -> f64 {
+        match self {
+            TitleSimilarity::Levenshtein => normalized_damerau_levenshtein(a, b),
+            TitleSimilarity::TfIdf(model) => model.cosine(a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tfidf_matches_reworded_titles_levenshtein_misses() {
+        let titles = [
+            "Will the Fed cut interest rates in December?",
+            "Fed rate cut at the December FOMC meeting?",
+            "Will Bitcoin reach $100k in December?",
+            "Will the Lakers win the NBA Finals?",
+            "Will the Celtics win the NBA Finals?",
+        ];
+        let tfidf = TitleSimilarity::fit(SimilarityBackend::TfIdf, titles);
+        let levenshtein = TitleSimilarity::fit(SimilarityBackend::Levenshtein, titles);
+
+        let reworded = tfidf.score(titles[0], titles[1]);
+        assert!(reworded > 0.3, "{}", reworded);
+        assert!(levenshtein.score(titles[0], titles[1]) < 0.6);
+        assert!(tfidf.score(titles[0], titles[2]) < reworded);
+        assert!((tfidf.score(titles[3], titles[3]) - 1.0).abs() < 1e-9);
+        assert_eq!(tfidf.score("", titles[0]), 0.0);
+
+        assert_eq!("TF-IDF".parse::<SimilarityBackend>().unwrap(), SimilarityBackend::TfIdf);
+        assert!("onnx".parse::<SimilarityBackend>().is_err());
+    }
+}
@@ -0,0 +1,356 @@
+use crate::arbitrage_engine::{check_combinatorial_pair, check_neg_risk_basket, check_rebalancing, check_threshold_ladder, find_multi_leg_opportunities, threshold_ladder_key, EngineError};
+use crate::category_params::CategoryParams;
+use crate::clob_client::TimeInForce;
+use crate::fees::FeeModel;
+use crate::market_state::MarketState;
+use crate::metrics::STALE_PRICE_REFUSED;
+use crate::order_book::OrderBook;
+use crate::profit_model::{evaluate_combinatorial, ProfitModelConfig};
+use crate::resolution_risk::{self, ResolutionRiskConfig};
+use crate::shared_types::{Market, MarketId, Opportunity};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::debug;
+
+/// What a strategy sees on a tick: the market whose price moved, at current prices, and the
+/// live state and books around it.
+pub struct MarketView<'a> {
+    pub state: &'a MarketState,
+    pub id: MarketId,
+    pub market: &'a Market,
+    /// The asset whose price moved.
+    pub asset_id: &'a str,
+    pub books: &'a HashMap<String, OrderBook>,
+    /// When the tick is evaluated; the recorded time when replaying.
+    pub now: DateTime<Utc>,
+}
+
+impl MarketView<'_> {
+    /// Current copies of `ids` that are still tracked.
+    fn priced(&self, ids: &[MarketId]) -> Vec<Market> {
+        ids.iter().filter_map(|&id| self.state.priced(id)).collect()
+    }
+}
+
+/// One way of finding arbs in a tick. Implement it to add a strategy and add it with
+/// `StrategyRegistry::register`.
+pub trait Strategy: Send + Sync {
+    /// Identifier used to enable, disable and parameterize the strategy from config.
+    fn name(&self) -> &'static str;
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity>;
+
+    /// How the legs that take the strategy's edge work the book. Resting styles such as quotes
+    /// still rest.
+    fn time_in_force(&self) -> TimeInForce {
+        TimeInForce::Gtc
+    }
+}
+
+/// Buys or splits every outcome of a single market whose prices do not sum to $1.
+pub struct RebalancingStrategy {
+    pub fees: FeeModel,
+    pub categories: Arc<CategoryParams>,
+}
+
+impl Strategy for RebalancingStrategy {
+    fn name(&self) -> &'static str { "rebalancing" }
+
+    /// Whatever fills of each leg is merged as far as the legs match.
+    fn time_in_force(&self) -> TimeInForce { TimeInForce::Ioc }
+
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let fees = self.categories.fees(&self.fees, view.market);
+        check_rebalancing(view.market, view.books, &fees)
+            .map(|op| Opportunity::rebalancing(view.market.clone(), op, &fees))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Trades every market of a neg-risk event whose YES prices do not sum to $1.
+pub struct NegRiskBasketStrategy {
+    pub fees: FeeModel,
+    pub categories: Arc<CategoryParams>,
+}
+
+impl Strategy for NegRiskBasketStrategy {
+    fn name(&self) -> &'static str { "neg_risk_basket" }
+
+    /// A basket missing a member is no longer hedged.
+    fn time_in_force(&self) -> TimeInForce { TimeInForce::Fok }
+
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let Some(members) = view.market.neg_risk_market_id.as_ref().and_then(|id| view.state.neg_risk_baskets.get(id)) else { return Vec::new() };
+        let priced = view.priced(members);
+        let basket: Vec<&Market> = priced.iter().collect();
+        let fees = self.categories.fees(&self.fees, view.market);
+        check_neg_risk_basket(&basket, view.books, &fees)
+            .map(|op| Opportunity::neg_risk_basket(&basket, op, &fees))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Buys across the rungs of a crypto price ladder when they are priced out of order.
+pub struct ThresholdLadderStrategy {
+    pub fees: FeeModel,
+    pub categories: Arc<CategoryParams>,
+}
+
+impl Strategy for ThresholdLadderStrategy {
+    fn name(&self) -> &'static str { "threshold_ladder" }
+
+    /// Uneven rungs are held rather than unwound, so each fills whole or not at all.
+    fn time_in_force(&self) -> TimeInForce { TimeInForce::Fok }
+
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let Some(rungs) = threshold_ladder_key(view.market).and_then(|key| view.state.threshold_ladders.get(&key)) else { return Vec::new() };
+        let priced = view.priced(rungs);
+        let ladder: Vec<&Market> = priced.iter().collect();
+        let fees = self.categories.fees(&self.fees, view.market);
+        check_threshold_ladder(&ladder, &fees)
+            .map(|op| Opportunity::threshold_ladder(op, &fees))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Pairs of related markets where one condition implies another but is priced above it. Only
+/// spreads that survive walking both books, fees, gas and the pair's resolution-risk haircut are
+/// returned.
+pub struct CombinatorialStrategy {
+    pub profit: ProfitModelConfig,
+    pub resolution: ResolutionRiskConfig,
+    pub categories: Arc<CategoryParams>,
+}
+
+impl Strategy for CombinatorialStrategy {
+    fn name(&self) -> &'static str { "combinatorial" }
+
+    /// The second leg is sized to what the first actually filled.
+    fn time_in_force(&self) -> TimeInForce { TimeInForce::Ioc }
+
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let Some(related_ids) = view.state.adjacency.get(&view.id) else { return Vec::new() };
+        let profit = self.categories.profit(&self.profit, view.market);
+        let mut found = Vec::new();
+        for related in view.priced(related_ids) {
+            let risk = resolution_risk::score(view.market, &related);
+            let Some(haircut) = self.resolution.multiplier(risk) else {
+                debug!(market_1 = %view.market.id, market_2 = %related.id, risk, "pair blocked on resolution risk");
+                continue;
+            };
+            let haircut = Decimal::from_f64(haircut).unwrap_or(Decimal::ONE);
+            for op in check_combinatorial_pair(view.market, &related, &view.state.patterns) {
+                let Some(mut estimate) = evaluate_combinatorial(&op, view.books, &profit) else { continue };
+                estimate.net_profit *= haircut;
+                if estimate.net_profit < profit.min_net_profit {
+                    continue;
+                }
+                let pair = [view.market, &related];
+                let prices = [&op.condition_name_1, &op.condition_name_2].iter()
+                    .filter_map(|name| pair.iter().flat_map(|m| m.conditions.iter()).find(|c| &&c.name == name).map(|c| c.price))
+                    .collect();
+                found.push(Opportunity::combinatorial(op, estimate, prices));
+            }
+        }
+        found
+    }
+}
+
+/// Implication chains and cycles through the moved asset whose prices break their ordering.
+pub struct MultiLegStrategy {
+    pub max_depth: usize,
+}
+
+impl Strategy for MultiLegStrategy {
+    fn name(&self) -> &'static str { "multi_leg" }
+
+    /// A chain with a leg missing is no longer hedged.
+    fn time_in_force(&self) -> TimeInForce { TimeInForce::Fok }
+
+    fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        let state = view.state;
+        find_multi_leg_opportunities(&state.dependency_graph, &state.markets, view.asset_id, self.max_depth, &state.live)
+            .into_iter()
+            .map(Opportunity::multi_leg)
+            .collect()
+    }
+}
+
+/// The strategies run on every tick, in order, with what each must find to count.
+pub struct StrategyRegistry {
+    strategies: Vec<Box<dyn Strategy>>,
+    disabled: HashSet<&'static str>,
+    /// Net profit per dollar, as the scheduler ranks opportunities, each strategy's finds need.
+    min_margin: HashMap<&'static str, Decimal>,
+    /// Oldest price any leg of an opportunity may be quoted at.
+    max_price_age: Option<chrono::Duration>,
+}
+
+impl std::fmt::Debug for StrategyRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrategyRegistry")
+            .field("enabled", &self.enabled().collect::<Vec<_>>())
+            .field("min_margin", &self.min_margin)
+            .field("max_price_age", &self.max_price_age)
+            .finish()
+    }
+}
+
+impl StrategyRegistry {
+    /// The built-in strategies, all enabled.
+    pub fn new(fees: FeeModel, profit: ProfitModelConfig, max_chain_depth: usize) -> Self {
+        Self::with_categories(fees, profit, max_chain_depth, Arc::default())
+    }
+
+    /// `new`, with fee thresholds and minimum profit overridden per market category.
+    pub fn with_categories(fees: FeeModel, profit: ProfitModelConfig, max_chain_depth: usize, categories: Arc<CategoryParams>) -> Self {
+        Self {
+            strategies: vec![
+                Box::new(RebalancingStrategy { fees: fees.clone(), categories: categories.clone() }),
+                Box::new(NegRiskBasketStrategy { fees: fees.clone(), categories: categories.clone() }),
+                Box::new(ThresholdLadderStrategy { fees, categories: categories.clone() }),
+                Box::new(CombinatorialStrategy { profit, resolution: ResolutionRiskConfig::default(), categories }),
+                Box::new(MultiLegStrategy { max_depth: max_chain_depth }),
+            ],
+            disabled: HashSet::new(),
+            min_margin: HashMap::new(),
+            max_price_age: None,
+        }
+    }
+
+    /// Adds a strategy after the built-in ones. A strategy with an existing name replaces it.
+    pub fn register(&mut self, strategy: Box<dyn Strategy>) {
+        match self.strategies.iter().position(|s| s.name() == strategy.name()) {
+            Some(i) => self.strategies[i] = strategy,
+            None => self.strategies.push(strategy),
+        }
+    }
+
+    fn known(&self, name: &str) -> Result<&'static str, EngineError> {
+        self.strategies.iter().map(|s| s.name()).find(|n| *n == name)
+            .ok_or_else(|| EngineError::UnknownStrategy(name.to_string()))
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), EngineError> {
+        let name = self.known(name)?;
+        if enabled { self.disabled.remove(name); } else { self.disabled.insert(name); }
+        Ok(())
+    }
+
+    pub fn set_min_margin(&mut self, name: &str, margin: Decimal) -> Result<(), EngineError> {
+        let name = self.known(name)?;
+        self.min_margin.insert(name, margin);
+        Ok(())
+    }
+
+    /// Refuses opportunities with any leg priced longer than `age` ago, since the other legs have
+    /// probably moved since. `None` trusts prices of any age.
+    pub fn set_max_price_age(&mut self, age: Option<chrono::Duration>) {
+        self.max_price_age = age;
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.strategies.iter().map(|s| s.name())
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.names().filter(|n| !self.disabled.contains(n))
+    }
+
+    pub fn min_margins(&self) -> &HashMap<&'static str, Decimal> {
+        &self.min_margin
+    }
+
+    /// The time in force every strategy requires of its taking legs.
+    pub fn time_in_force(&self) -> HashMap<&'static str, TimeInForce> {
+        self.strategies.iter().map(|s| (s.name(), s.time_in_force())).collect()
+    }
+
+    /// Everything the enabled strategies find in `view` that clears their margin on fresh
+    /// prices, credited to the strategy that found it.
+    pub fn evaluate(&self, view: &MarketView) -> Vec<Opportunity> {
+        self.strategies.iter()
+            .filter(|s| !self.disabled.contains(s.name()))
+            .flat_map(|s| {
+                let floor = self.min_margin.get(s.name()).copied();
+                s.evaluate(view).into_iter()
+                    .filter(move |o| floor.is_none_or(|floor| o.margin() >= floor))
+                    .filter(move |o| self.fresh(view, o))
+                    .map(|mut o| {
+                        o.meta_mut().strategy = s.name();
+                        o
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether every leg of `opportunity` was quoted within the staleness horizon. A price that
+    /// was never streamed is as old as the last market fetch and counts as stale.
+    fn fresh(&self, view: &MarketView, opportunity: &Opportunity) -> bool {
+        let Some(horizon) = self.max_price_age else { return true };
+        let stale = opportunity.meta().asset_ids.iter()
+            .find(|asset_id| view.state.last_updated(asset_id).is_none_or(|at| view.now - at > horizon));
+        if let Some(asset_id) = stale {
+            debug!(kind = opportunity.kind().as_str(), %asset_id, "leg price is stale, refusing");
+            STALE_PRICE_REFUSED.with_label_values(&[opportunity.kind().as_str()]).inc();
+        }
+        stale.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_registry_runs_enabled_strategies_above_their_margin() {
+        let condition = |name: &str, price: Decimal, asset_id: &str| Condition { name: name.to_string(), price, outcome: None, asset_id: asset_id.to_string(), kind: OutcomeKind::Other, last_updated: None };
+        let state = MarketState::build(vec![Market {
+            id: "m1".to_string(),
+            title: "fed_cut_rates".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 12, 18, 0, 0, 0).unwrap(),
+            conditions: vec![condition("Yes", dec!(0.45), "1"), condition("No", dec!(0.5), "2")],
+            ..Market::default()
+        }], 0.6, Default::default());
+        let id = state.markets.resolve("1").unwrap().market;
+        let market = state.priced(id).unwrap();
+        let books: HashMap<String, OrderBook> = market.conditions.iter().map(|c| {
+            let mut book = OrderBook::default();
+            let level = [crate::order_book::PriceLevel { price: c.price, size: dec!(100) }];
+            book.apply_snapshot(&level, &level);
+            (c.asset_id.clone(), book)
+        }).collect();
+        let view = MarketView { state: &state, id, market: &market, asset_id: "1", books: &books, now: Utc::now() };
+
+        let mut registry = StrategyRegistry::new(FeeModel::default(), ProfitModelConfig::default(), 4);
+        let found = registry.evaluate(&view);
+        assert!(matches!(&found[..], [Opportunity::Rebalancing { meta, .. }] if meta.prices == vec![dec!(0.45), dec!(0.5)] && meta.net_profit == dec!(5)));
+
+        // A 5c edge misses a 10c floor, and a disabled strategy finds nothing
+        registry.set_min_margin("rebalancing", dec!(0.1)).unwrap();
+        assert!(registry.evaluate(&view).is_empty());
+        registry.set_min_margin("rebalancing", dec!(0.05)).unwrap();
+        registry.set_enabled("rebalancing", false).unwrap();
+        assert!(registry.evaluate(&view).is_empty());
+        assert!(registry.set_enabled("astrology", false).is_err());
+        assert_eq!(registry.enabled().count(), 4);
+
+        // Legs never streamed, or streamed too long ago, are stale
+        registry.set_enabled("rebalancing", true).unwrap();
+        registry.set_max_price_age(Some(chrono::Duration::seconds(5)));
+        assert!(registry.evaluate(&view).is_empty());
+        state.set_price("1", dec!(0.45), view.now - chrono::Duration::seconds(1));
+        state.set_price("2", dec!(0.5), view.now - chrono::Duration::seconds(6));
+        assert!(registry.evaluate(&view).is_empty());
+        state.set_price("2", dec!(0.5), view.now);
+        assert_eq!(registry.evaluate(&view).len(), 1);
+    }
+}
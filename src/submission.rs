@@ -0,0 +1,135 @@
+use crate::blockchain::{ExecutionError, ProviderPool};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use url::Url;
+
+/// Where signed transactions are broadcast. Receipts are always polled from the public RPC,
+/// so a backend only has to accept the raw transaction.
+#[async_trait]
+pub trait SubmissionBackend: Send + Sync {
+    fn name(&self) -> &str;
+    async fn submit(&self, raw: Bytes) -> Result<TxHash, ExecutionError>;
+}
+
+/// `eth_sendRawTransaction` on the bot's own RPC. Visible in the public mempool.
+pub struct PublicRpc {
+    provider: Provider<ProviderPool>,
+}
+
+impl PublicRpc {
+    pub fn new(provider: Provider<ProviderPool>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl SubmissionBackend for PublicRpc {
+    fn name(&self) -> &str {
+        "public"
+    }
+
+    async fn submit(&self, raw: Bytes) -> Result<TxHash, ExecutionError> {
+        Ok(self.provider.send_raw_transaction(raw).await?.tx_hash())
+    }
+}
+
+/// A protected endpoint that takes `eth_sendRawTransaction` and forwards the transaction to
+/// block builders without gossiping it (Marlin, bloXroute, dRPC and similar Polygon relays).
+pub struct PrivateRelay {
+    name: String,
+    provider: Provider<Http>,
+}
+
+impl PrivateRelay {
+    /// `auth` is an optional `Header-Name: value` pair sent with every request. The relay is
+    /// named after its host in logs.
+    pub fn new(url: &str, auth: Option<&str>) -> Result<Self, ExecutionError> {
+        let url = Url::from_str(url)?;
+        let name = url.host_str().unwrap_or("private").to_string();
+        let mut headers = HeaderMap::new();
+        if let Some(auth) = auth {
+            let (key, value) = auth.split_once(':')
+                .ok_or_else(|| ExecutionError::Config("relay auth must be \"Header-Name: value\"".to_string()))?;
+            let key = HeaderName::from_str(key.trim()).map_err(|e| ExecutionError::Config(format!("bad relay auth header: {}", e)))?;
+            let value = HeaderValue::from_str(value.trim()).map_err(|e| ExecutionError::Config(format!("bad relay auth value: {}", e)))?;
+            headers.insert(key, value);
+        }
+        let http = reqwest::Client::builder().default_headers(headers).build()?;
+        let provider = Provider::new(Http::new_with_client(url, http));
+        Ok(Self { name, provider })
+    }
+}
+
+#[async_trait]
+impl SubmissionBackend for PrivateRelay {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn submit(&self, raw: Bytes) -> Result<TxHash, ExecutionError> {
+        Ok(self.provider.send_raw_transaction(raw).await?.tx_hash())
+    }
+}
+
+/// Picks a backend per transaction. Trades expected to make at least `private_min_profit` are
+/// worth front-running and go through the private relay; smaller trades and setup
+/// transactions such as approvals use the public RPC.
+#[derive(Clone)]
+pub struct SubmissionRouter {
+    public: Arc<dyn SubmissionBackend>,
+    private: Option<Arc<dyn SubmissionBackend>>,
+    private_min_profit: Decimal,
+}
+
+impl SubmissionRouter {
+    pub fn new(public: Arc<dyn SubmissionBackend>) -> Self {
+        Self { public, private: None, private_min_profit: Decimal::ZERO }
+    }
+
+    pub fn with_private(mut self, backend: Arc<dyn SubmissionBackend>, min_profit: Decimal) -> Self {
+        self.private = Some(backend);
+        self.private_min_profit = min_profit;
+        self
+    }
+
+    pub fn select(&self, expected_profit: Option<Decimal>) -> &dyn SubmissionBackend {
+        match (&self.private, expected_profit) {
+            (Some(private), Some(profit)) if profit >= self.private_min_profit => private.as_ref(),
+            _ => self.public.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl SubmissionBackend for Named {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn submit(&self, _raw: Bytes) -> Result<TxHash, ExecutionError> {
+            Ok(TxHash::zero())
+        }
+    }
+
+    #[test]
+    fn test_router_sends_profitable_trades_privately() {
+        let router = SubmissionRouter::new(Arc::new(Named("public")));
+        assert_eq!(router.select(Some(dec!(100))).name(), "public");
+
+        let router = router.with_private(Arc::new(Named("relay")), dec!(5));
+        assert_eq!(router.select(Some(dec!(5))).name(), "relay");
+        assert_eq!(router.select(Some(dec!(4.99))).name(), "public");
+        assert_eq!(router.select(None).name(), "public");
+    }
+}
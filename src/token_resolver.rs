@@ -0,0 +1,129 @@
+use crate::fill_ingest::FillRecord;
+use crate::shared_types::Market;
+use std::collections::HashMap;
+
+/// The market and outcome an ERC-1155 outcome token belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub market_id: String,
+    pub question: String,
+    /// Outcome name as Gamma lists it ("Yes", "No", a candidate...).
+    pub outcome: String,
+    /// Position in the market's outcome list, matching `Market::conditions`.
+    pub outcome_index: usize,
+}
+
+/// Maps on-chain token ids to markets, built from the `clobTokenIds` Gamma returns. The
+/// CLOB asset ids are the same decimal strings `OrderFilled` carries as uint256.
+#[derive(Debug, Clone, Default)]
+pub struct TokenResolver {
+    tokens: HashMap<String, TokenInfo>,
+    /// Lowercase condition id => market id, to attribute `ConditionResolution` events.
+    conditions: HashMap<String, String>,
+}
+
+impl TokenResolver {
+    pub fn from_markets(markets: &[Market]) -> Self {
+        let mut resolver = Self::default();
+        resolver.extend(markets);
+        resolver
+    }
+
+    /// Adds (or refreshes) the tokens of `markets`, e.g. after a market refresh.
+    pub fn extend(&mut self, markets: &[Market]) {
+        for market in markets {
+            if !market.condition_id.is_empty() {
+                self.conditions.insert(market.condition_id.to_lowercase(), market.id.clone());
+            }
+            for (outcome_index, condition) in market.conditions.iter().enumerate() {
+                self.tokens.insert(condition.asset_id.clone(), TokenInfo {
+                    market_id: market.id.clone(),
+                    question: market.title.clone(),
+                    outcome: condition.name.clone(),
+                    outcome_index,
+                });
+            }
+        }
+    }
+
+    pub fn resolve(&self, token_id: &str) -> Option<&TokenInfo> {
+        self.tokens.get(token_id)
+    }
+
+    /// The market a CTF condition id (bytes32 hex) belongs to.
+    pub fn market_for_condition(&self, condition_id: &str) -> Option<&str> {
+        self.conditions.get(&condition_id.to_lowercase()).map(String::as_str)
+    }
+
+    /// Tags `fill` with the market of the token it traded. Returns whether it resolved.
+    pub fn enrich(&self, fill: &mut FillRecord) -> bool {
+        fill.market = self.resolve(fill.asset_id()).cloned();
+        fill.market.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clob_client::OrderSide;
+    use crate::fill_ingest::{FillStore, USDC_ASSET_ID};
+    use crate::shared_types::{Condition, OutcomeKind};
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn market() -> Market {
+        let condition = |name: &str, asset_id: &str| Condition { name: name.to_string(), price: dec!(0.5), outcome: None, asset_id: asset_id.to_string(), kind: OutcomeKind::Other, last_updated: None };
+        Market {
+            id: "m1".to_string(),
+            title: "Will it rain?".to_string(),
+            end_date: Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap(),
+            conditions: vec![condition("Yes", "111"), condition("No", "222")],
+            tags: Vec::new(),
+            ..Market::default()
+        }
+    }
+
+    fn buy_of_no(block_number: u64) -> FillRecord {
+        // Maker pays 6 USDC for 10 "No" shares
+        FillRecord {
+            block_number,
+            timestamp: 0,
+            tx_hash: format!("0x{:x}", block_number),
+            log_index: 0,
+            order_hash: "0x01".to_string(),
+            maker: "0xaa".to_string(),
+            taker: "0xbb".to_string(),
+            maker_asset_id: USDC_ASSET_ID.to_string(),
+            taker_asset_id: "222".to_string(),
+            maker_amount: dec!(6),
+            taker_amount: dec!(10),
+            fee: Decimal::ZERO,
+            market: None,
+        }
+    }
+
+    #[test]
+    fn test_resolves_fill_tokens_and_backfills_stored_fills() {
+        let resolver = TokenResolver::from_markets(&[market()]);
+        let mut fill = buy_of_no(1);
+        assert_eq!((fill.asset_id(), fill.maker_side(), fill.shares(), fill.price()), ("222", OrderSide::Buy, dec!(10), Some(dec!(0.6))));
+        assert!(resolver.enrich(&mut fill));
+        assert_eq!(fill.market.as_ref().map(|m| (m.market_id.as_str(), m.outcome.as_str(), m.outcome_index)), Some(("m1", "No", 1)));
+
+        // Fills stored before the market was known are tagged afterwards
+        let store = FillStore::open_in_memory().unwrap();
+        store.insert_fills(&[buy_of_no(2)]).unwrap();
+        assert_eq!(store.enrich(&TokenResolver::default()).unwrap(), 0);
+        assert_eq!(store.enrich(&resolver).unwrap(), 1);
+        assert_eq!(store.fills_between(2, 2).unwrap()[0].market, fill.market);
+    }
+}
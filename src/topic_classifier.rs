@@ -1,4 +1,17 @@
 use crate::shared_types::Market;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+use thiserror::Error;
+
+const BUILTIN_RULES: &str = include_str!("../data/topics.json");
+
+/// A tag hit counts this many times a title hit: tags are curated, titles are free text.
+const TAG_WEIGHT: f64 = 2.0;
+const TITLE_WEIGHT: f64 = 1.0;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MarketCategory {
@@ -10,42 +23,217 @@ pub enum MarketCategory {
     Other,
 }
 
-pub struct TopicClassifier;
+impl FromStr for MarketCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "politics" => Ok(MarketCategory::Politics),
+            "crypto" => Ok(MarketCategory::Crypto),
+            "sports" => Ok(MarketCategory::Sports),
+            "economics" => Ok(MarketCategory::Economics),
+            "science" => Ok(MarketCategory::Science),
+            "other" => Ok(MarketCategory::Other),
+            other => Err(format!("unknown market category: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TopicError {
+    #[error("failed to read topic rules {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("failed to parse topic rules {path}: {source}")]
+    Parse { path: String, source: serde_json::Error },
+    #[error("topic rule: {0}")]
+    Category(String),
+}
+
+/// One row of the rule table: tag substrings and title words or phrases that put a market in
+/// `category`, under `label` when set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    pub category: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub titles: Vec<String>,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// Rule table in the shape of `data/topics.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleFile {
+    pub rules: Vec<RuleSpec>,
+}
+
+#[derive(Debug, Clone)]
+struct TopicRule {
+    category: MarketCategory,
+    label: Option<String>,
+    tags: Vec<String>,
+    titles: Vec<Vec<String>>,
+    weight: f64,
+}
+
+impl TopicRule {
+    fn score(&self, tags: &[String], title: &[String]) -> f64 {
+        let tag_hit = tags.iter().any(|t| self.tags.iter().any(|k| t.contains(k.as_str())));
+        let title_hit = self.titles.iter().any(|phrase| title.windows(phrase.len()).any(|w| w == phrase.as_slice()));
+        self.weight * (if tag_hit { TAG_WEIGHT } else { 0.0 } + if title_hit { TITLE_WEIGHT } else { 0.0 })
+    }
+}
+
+/// A market's primary category, the subcategory labels that matched ("Sports::NBA"),
+/// strongest first, and the share of matched rule weight behind the category.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub category: MarketCategory,
+    pub labels: Vec<String>,
+    /// 0-1; 0 for `Other`.
+    pub confidence: f64,
+}
+
+/// Rule-table classifier. Every rule matching a market's tags or title adds its weight to its
+/// category; the heaviest category wins, ties going to the one listed first.
+#[derive(Debug, Default)]
+pub struct TopicClassifier {
+    rules: Vec<TopicRule>,
+}
 
 impl TopicClassifier {
-    pub fn classify(market: &Market) -> MarketCategory {
-        // 1. Check Tags first
-        for tag in &market.tags {
-            let t = tag.to_lowercase();
-            if t.contains("politics") || t.contains("election") || t.contains("white house") {
-                return MarketCategory::Politics;
+    /// The built-in table shipped in `data/topics.json`.
+    pub fn builtin() -> Self {
+        let mut classifier = Self::default();
+        let rules: RuleFile = serde_json::from_str(BUILTIN_RULES).expect("built-in topic rules are valid JSON");
+        classifier.add_rules(rules).expect("built-in topic rules name known categories");
+        classifier
+    }
+
+    /// Adds every rule in `file`. A rule with the category and label of an existing one
+    /// replaces it.
+    pub fn add_rules(&mut self, file: RuleFile) -> Result<(), TopicError> {
+        for spec in file.rules {
+            let category = MarketCategory::from_str(&spec.category).map_err(TopicError::Category)?;
+            if category == MarketCategory::Other || spec.weight.is_nan() || spec.weight <= 0.0 {
+                return Err(TopicError::Category(format!("{} rules must name a category other than Other and a weight > 0", spec.category)));
             }
-            if t.contains("crypto") || t.contains("bitcoin") || t.contains("ethereum") || t.contains("nft") {
-                return MarketCategory::Crypto;
+            let rule = TopicRule {
+                category,
+                label: spec.label,
+                tags: spec.tags.iter().map(|t| t.to_lowercase()).collect(),
+                titles: spec.titles.iter().map(|t| words(t)).filter(|p| !p.is_empty()).collect(),
+                weight: spec.weight,
+            };
+            match self.rules.iter().position(|r| r.category == rule.category && r.label == rule.label) {
+                Some(i) => self.rules[i] = rule,
+                None => self.rules.push(rule),
             }
-            if t.contains("sport") || t.contains("nba") || t.contains("nfl") || t.contains("soccer") {
-                return MarketCategory::Sports;
+        }
+        Ok(())
+    }
+
+    pub fn classification(&self, market: &Market) -> Classification {
+        let tags: Vec<String> = market.tags.iter().map(|t| t.to_lowercase()).collect();
+        let title = words(&market.title);
+        let mut totals: Vec<(MarketCategory, f64)> = Vec::new();
+        let mut labels: HashMap<String, f64> = HashMap::new();
+        for rule in &self.rules {
+            let score = rule.score(&tags, &title);
+            if score == 0.0 {
+                continue;
             }
-            if t.contains("economy") || t.contains("fed") || t.contains("rates") || t.contains("inflation") {
-                return MarketCategory::Economics;
+            match totals.iter_mut().find(|(c, _)| *c == rule.category) {
+                Some((_, total)) => *total += score,
+                None => totals.push((rule.category.clone(), score)),
             }
-            if t.contains("science") || t.contains("space") || t.contains("covid") || t.contains("climate") {
-                return MarketCategory::Science;
+            if let Some(label) = &rule.label {
+                *labels.entry(format!("{:?}::{}", rule.category, label)).or_default() += score;
             }
         }
 
-        // 2. Fallback to Title keywords
-        let title_lower = market.title.to_lowercase();
-        if title_lower.contains("trump") || title_lower.contains("biden") || title_lower.contains("senate") {
-            return MarketCategory::Politics;
-        }
-        if title_lower.contains("btc") || title_lower.contains("eth") || title_lower.contains("sol") {
-            return MarketCategory::Crypto;
-        }
-        if title_lower.contains("game") || title_lower.contains("match") || title_lower.contains("league") {
-            return MarketCategory::Sports;
+        let sum: f64 = totals.iter().map(|(_, s)| s).sum();
+        let Some((category, best)) = totals.into_iter().fold(None, |best: Option<(MarketCategory, f64)>, (c, s)| match best {
+            Some((_, b)) if b >= s => best,
+            _ => Some((c, s)),
+        }) else {
+            return Classification { category: MarketCategory::Other, labels: Vec::new(), confidence: 0.0 };
+        };
+        let mut labels: Vec<(String, f64)> = labels.into_iter().collect();
+        labels.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Classification { category, labels: labels.into_iter().map(|(l, _)| l).collect(), confidence: best / sum }
+    }
+
+    /// Primary category under the process-wide rule table.
+    pub fn classify(market: &Market) -> MarketCategory {
+        classify(market).category
+    }
+}
+
+/// Lowercased words; normalized titles use `_` as the separator, so both forms split the same.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+lazy_static! {
+    static ref CLASSIFIER: RwLock<TopicClassifier> = RwLock::new(TopicClassifier::builtin());
+}
+
+/// Classifies with the process-wide table (built-ins plus any loaded rules).
+pub fn classify(market: &Market) -> Classification {
+    CLASSIFIER.read().unwrap_or_else(|e| e.into_inner()).classification(market)
+}
+
+/// Merges a custom rule file into the process-wide table.
+pub fn load_rules(path: &Path) -> Result<(), TopicError> {
+    let display = path.display().to_string();
+    let text = std::fs::read_to_string(path).map_err(|source| TopicError::Io { path: display.clone(), source })?;
+    let file: RuleFile = serde_json::from_str(&text).map_err(|source| TopicError::Parse { path: display, source })?;
+    CLASSIFIER.write().unwrap_or_else(|e| e.into_inner()).add_rules(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(title: &str, tags: &[&str]) -> Market {
+        Market {
+            title: title.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Market::default()
         }
+    }
+
+    #[test]
+    fn test_classifies_with_labels_confidence_and_custom_rules() {
+        let mut classifier = TopicClassifier::builtin();
+        let nba = classifier.classification(&market("Will the Lakers win the NBA Finals?", &["Sports", "NBA"]));
+        assert_eq!((nba.category, nba.labels, nba.confidence), (MarketCategory::Sports, vec!["Sports::NBA".to_string()], 1.0));
+
+        // A politics tag outweighs an economics word in the title
+        let fed_chair = classifier.classification(&market("Will Trump fire the Fed chair?", &["Politics"]));
+        assert_eq!(fed_chair.category, MarketCategory::Politics);
+        assert_eq!(fed_chair.labels, vec!["Economics::Fed".to_string()]);
+        assert!(fed_chair.confidence > 0.5 && fed_chair.confidence < 1.0);
+
+        // Words, not substrings: "resolve" is not SOL
+        assert_eq!(classifier.classification(&market("btc_above_100k_on_december_31", &[])).labels, vec!["Crypto::BTC".to_string()]);
+        assert_eq!(classifier.classification(&market("Will the committee resolve this?", &[])).category, MarketCategory::Other);
 
-        MarketCategory::Other
+        let rules: RuleFile = serde_json::from_str(r#"{"rules":[{"category":"Sports","label":"F1","titles":["grand prix"]}]}"#).unwrap();
+        classifier.add_rules(rules).unwrap();
+        let f1 = classifier.classification(&market("Who wins the Monaco Grand Prix?", &[]));
+        assert_eq!((f1.category, f1.labels), (MarketCategory::Sports, vec!["Sports::F1".to_string()]));
+        let bad: RuleFile = serde_json::from_str(r#"{"rules":[{"category":"Astrology","titles":["mercury"]}]}"#).unwrap();
+        assert!(classifier.add_rules(bad).is_err());
     }
 }
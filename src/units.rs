@@ -0,0 +1,96 @@
+use ethers::types::U256;
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// Collateral (USDC) and outcome tokens both use 6 decimals.
+pub const TOKEN_DECIMALS: u32 = 6;
+/// Native POL is counted in 18-decimal wei.
+pub const POL_DECIMALS: u32 = 18;
+
+/// Scales `amount` into integer base units with `decimals` places, truncating below the last
+/// place. Negative amounts and amounts beyond u128 yield None.
+pub fn to_units(amount: Decimal, decimals: u32) -> Option<U256> {
+    if amount.is_sign_negative() && !amount.is_zero() {
+        return None;
+    }
+    let scaled = amount.checked_mul(Decimal::from(10u64.checked_pow(decimals)?))?.trunc();
+    u128::try_from(scaled).ok().map(U256::from)
+}
+
+/// Reads integer base units with `decimals` places exactly, without a string round trip.
+/// Values beyond Decimal's 96-bit mantissa yield None.
+pub fn from_units(units: U256, decimals: u32) -> Option<Decimal> {
+    if units > U256::from(u128::MAX) {
+        return None;
+    }
+    Decimal::try_from_i128_with_scale(i128::try_from(units.as_u128()).ok()?, decimals).ok().map(|d| d.normalize())
+}
+
+macro_rules! token_amount {
+    ($(#[$doc:meta])* $name:ident, $decimals:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub Decimal);
+
+        impl $name {
+            pub const DECIMALS: u32 = $decimals;
+
+            pub fn from_units(units: U256) -> Option<Self> {
+                from_units(units, Self::DECIMALS).map(Self)
+            }
+
+            pub fn to_units(self) -> Option<U256> {
+                to_units(self.0, Self::DECIMALS)
+            }
+
+            pub fn value(self) -> Decimal {
+                self.0
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                Self(value)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+token_amount!(
+    /// USDC collateral.
+    UsdcAmount, TOKEN_DECIMALS
+);
+token_amount!(
+    /// Conditional token shares, denominated like their collateral.
+    ShareAmount, TOKEN_DECIMALS
+);
+token_amount!(
+    /// Native POL, paid for gas.
+    PolAmount, POL_DECIMALS
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_round_trips_truncates_and_rejects_out_of_range() {
+        let units = UsdcAmount(dec!(12.345678)).to_units().unwrap();
+        assert_eq!(units, U256::from(12_345_678u64));
+        assert_eq!(UsdcAmount::from_units(units), Some(UsdcAmount(dec!(12.345678))));
+        assert_eq!(ShareAmount(dec!(1.2345679)).to_units(), Some(U256::from(1_234_567u64)));
+        assert_eq!(PolAmount::from_units(U256::exp10(18) * 3 / 2), Some(PolAmount(dec!(1.5))));
+
+        assert_eq!(UsdcAmount(dec!(-1)).to_units(), None);
+        assert_eq!(PolAmount(Decimal::MAX).to_units(), None);
+        assert_eq!(UsdcAmount::from_units(U256::MAX), None);
+        assert_eq!(UsdcAmount::from_units(U256::from(u128::MAX)), None);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::balances::LowBalance;
+use crate::blockchain::{ExecutionError, TradeExecutor};
+use crate::clob_client::OrderRequest;
+use crate::profit_model::ExecutionEstimate;
+use crate::redemption::RedemptionTarget;
+use crate::shared_types::{BasketOpportunity, CombinatorialOpportunity, LadderOpportunity, Market, MultiLegOpportunity, RebalancingOpportunity};
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::types::{Address, TransactionReceipt};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// How the pool picks the wallet for the next trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionStrategy {
+    #[default]
+    RoundRobin,
+    /// The wallet with the fewest executions in flight, so nonce streams do not queue.
+    LowestInflight,
+    /// A random wallet, weighted by its cached USDC balance.
+    BalanceWeighted,
+}
+
+impl FromStr for SelectionStrategy {
+    type Err = ExecutionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round_robin" => Ok(SelectionStrategy::RoundRobin),
+            "lowest_inflight" => Ok(SelectionStrategy::LowestInflight),
+            "balance_weighted" => Ok(SelectionStrategy::BalanceWeighted),
+            other => Err(ExecutionError::Config(format!("unknown wallet selection strategy: {}", other))),
+        }
+    }
+}
+
+/// Index of the wallet to use. `cursor` is the round-robin position and `roll` a uniform
+/// sample in [0, 1) for the weighted draw. Ties and all-zero balances fall back to round robin.
+pub fn pick(strategy: SelectionStrategy, inflight: &[usize], usdc: &[Decimal], cursor: usize, roll: f64) -> usize {
+    let len = inflight.len();
+    let round_robin = cursor % len;
+    match strategy {
+        SelectionStrategy::RoundRobin => round_robin,
+        SelectionStrategy::LowestInflight => {
+            // Scan from the cursor so equally idle wallets still take turns
+            (0..len).map(|i| (round_robin + i) % len).min_by_key(|&i| inflight[i]).unwrap_or(round_robin)
+        }
+        SelectionStrategy::BalanceWeighted => {
+            let total: Decimal = usdc.iter().sum();
+            if total <= Decimal::ZERO {
+                return round_robin;
+            }
+            let mut target = total * Decimal::from_f64_retain(roll).unwrap_or_default();
+            for (i, balance) in usdc.iter().enumerate() {
+                if target < *balance {
+                    return i;
+                }
+                target -= balance;
+            }
+            len - 1
+        }
+    }
+}
+
+/// Decrements a wallet's in-flight count when its execution finishes.
+struct InflightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Several funded wallets behind one execution API. Each wallet has its own nonce stream,
+/// allowances and balance tracker; the pool picks one per trade and remembers which wallet
+/// holds each market so unwinding goes back to it.
+pub struct ExecutorPool {
+    wallets: Vec<Arc<TradeExecutor>>,
+    strategy: SelectionStrategy,
+    cursor: AtomicUsize,
+    inflight: Vec<AtomicUsize>,
+    /// Exposure key (market id) => wallet that last traded it.
+    owners: Mutex<HashMap<String, usize>>,
+}
+
+impl ExecutorPool {
+    /// `wallets` must not be empty; the first one is the primary wallet.
+    pub fn new(wallets: Vec<Arc<TradeExecutor>>, strategy: SelectionStrategy) -> Self {
+        assert!(!wallets.is_empty(), "executor pool needs at least one wallet");
+        let inflight = wallets.iter().map(|_| AtomicUsize::new(0)).collect();
+        Self { wallets, strategy, cursor: AtomicUsize::new(0), inflight, owners: Mutex::default() }
+    }
+
+    pub fn wallets(&self) -> &[Arc<TradeExecutor>] {
+        &self.wallets
+    }
+
+    fn acquire(&self) -> (usize, InflightGuard<'_>) {
+        let inflight: Vec<usize> = self.inflight.iter().map(|n| n.load(Ordering::SeqCst)).collect();
+        let usdc: Vec<Decimal> = self.wallets.iter().map(|w| w.balances().balances().map_or(Decimal::ZERO, |b| b.usdc)).collect();
+        let cursor = self.cursor.fetch_add(1, Ordering::SeqCst);
+        let index = pick(self.strategy, &inflight, &usdc, cursor, thread_rng().gen_range(0.0..1.0));
+        self.inflight[index].fetch_add(1, Ordering::SeqCst);
+        debug!(wallet = ?self.wallets[index].address(), "selected wallet");
+        (index, InflightGuard(&self.inflight[index]))
+    }
+
+    fn record_owner<T>(&self, market_id: &str, index: usize, result: &Result<T, ExecutionError>) {
+        if result.is_ok() {
+            self.owners.lock().unwrap_or_else(|e| e.into_inner()).insert(market_id.to_string(), index);
+        }
+    }
+
+    pub async fn execute_rebalancing(&self, market: &Market, op: &RebalancingOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_rebalancing(market, op, amount).await;
+        self.record_owner(&op.market_id, i, &result);
+        result
+    }
+
+    pub async fn execute_combinatorial(&self, op: &CombinatorialOpportunity, estimate: &ExecutionEstimate, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_combinatorial(op, estimate, amount).await;
+        self.record_owner(&op.market_id_1, i, &result);
+        result
+    }
+
+    pub async fn execute_multi_leg(&self, op: &MultiLegOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_multi_leg(op, amount).await;
+        self.record_owner(&op.legs[0].market_id, i, &result);
+        result
+    }
+
+    pub async fn execute_threshold_ladder(&self, op: &LadderOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_threshold_ladder(op, amount).await;
+        self.record_owner(&op.legs[0].market_id, i, &result);
+        result
+    }
+
+    pub async fn execute_neg_risk_basket(&self, op: &BasketOpportunity, amount: Decimal) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_neg_risk_basket(op, amount).await;
+        self.record_owner(&op.neg_risk_market_id, i, &result);
+        result
+    }
+
+    pub async fn execute_copy_trade(&self, market_id: &str, order: &OrderRequest) -> Result<TransactionReceipt, ExecutionError> {
+        let (i, _inflight) = self.acquire();
+        let result = self.wallets[i].execute_copy_trade(order).await;
+        self.record_owner(market_id, i, &result);
+        result
+    }
+
+    /// Flattens `held` shares of `market`'s outcomes, starting with the wallet that traded it, or
+    /// the primary wallet for positions that predate this run. Each wallet unwinds what it holds
+    /// of what is still left, so holdings spread across wallets are all closed.
+    pub async fn flatten_position(&self, market: &Market, held: &HashMap<String, Decimal>) -> Result<TransactionReceipt, ExecutionError> {
+        let owner = self.owners.lock().unwrap_or_else(|e| e.into_inner()).get(&market.id).copied().unwrap_or(0);
+        let asset_ids: Vec<String> = held.keys().cloned().collect();
+        let mut left = held.clone();
+        let mut receipt = TransactionReceipt::default();
+        for i in std::iter::once(owner).chain((0..self.wallets.len()).filter(|&i| i != owner)) {
+            if left.values().all(|shares| shares.is_zero()) {
+                break;
+            }
+            let own = self.wallets[i].fetch_balances(&asset_ids).await?;
+            let take: HashMap<String, Decimal> = left.iter()
+                .map(|(asset_id, wanted)| (asset_id.clone(), (*wanted).min(own.get(asset_id).copied().unwrap_or_default())))
+                .collect();
+            if take.values().all(|shares| shares.is_zero()) {
+                continue;
+            }
+            receipt = self.wallets[i].flatten_position(market, &take).await?;
+            for (asset_id, shares) in take {
+                *left.entry(asset_id).or_default() -= shares;
+            }
+        }
+        Ok(receipt)
+    }
+
+    /// Outcome token balances summed across every wallet.
+    pub async fn fetch_balances(&self, asset_ids: &[String]) -> Result<HashMap<String, Decimal>, ExecutionError> {
+        let mut totals: HashMap<String, Decimal> = HashMap::new();
+        for wallet in &self.wallets {
+            for (asset_id, shares) in wallet.fetch_balances(asset_ids).await? {
+                *totals.entry(asset_id).or_default() += shares;
+            }
+        }
+        Ok(totals)
+    }
+
+    /// Redeems resolved `targets` from every wallet. Returns the USDC redeemed per market and the
+    /// failures, with no market when a wallet's whole batch failed.
+    pub async fn redeem_positions(&self, targets: &[RedemptionTarget]) -> (HashMap<String, Decimal>, Vec<(Address, Option<String>, ExecutionError)>) {
+        let (mut redeemed, mut failed): (HashMap<String, Decimal>, Vec<_>) = (HashMap::new(), Vec::new());
+        for wallet in &self.wallets {
+            match wallet.redeem_positions(targets).await {
+                Ok(results) => {
+                    for (market_id, result) in results {
+                        match result {
+                            Ok(usdc) => *redeemed.entry(market_id).or_default() += usdc,
+                            Err(e) => failed.push((wallet.address(), Some(market_id), e)),
+                        }
+                    }
+                }
+                Err(e) => failed.push((wallet.address(), None, e)),
+            }
+        }
+        (redeemed, failed)
+    }
+
+    /// Refreshes every wallet's USDC and POL balances. Returns the wallets whose balances just
+    /// went low; a wallet that fails to refresh keeps its cached balances and is reported.
+    pub async fn refresh_balances(&self) -> (Vec<(Address, LowBalance)>, Vec<(Address, ExecutionError)>) {
+        let (mut low, mut failed) = (Vec::new(), Vec::new());
+        for wallet in &self.wallets {
+            match wallet.refresh_balances().await {
+                Ok(crossed) => low.extend(crossed.into_iter().map(|l| (wallet.address(), l))),
+                Err(e) => failed.push((wallet.address(), e)),
+            }
+        }
+        (low, failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_pick_strategies() {
+        let inflight = [2, 0, 0];
+        let usdc = [dec!(100), dec!(300), dec!(0)];
+        assert_eq!(pick(SelectionStrategy::RoundRobin, &inflight, &usdc, 4, 0.0), 1);
+        // Idle wallets 1 and 2 take turns
+        assert_eq!(pick(SelectionStrategy::LowestInflight, &inflight, &usdc, 0, 0.0), 1);
+        assert_eq!(pick(SelectionStrategy::LowestInflight, &inflight, &usdc, 2, 0.0), 2);
+
+        assert_eq!(pick(SelectionStrategy::BalanceWeighted, &inflight, &usdc, 0, 0.2), 0);
+        assert_eq!(pick(SelectionStrategy::BalanceWeighted, &inflight, &usdc, 0, 0.3), 1);
+        assert_eq!(pick(SelectionStrategy::BalanceWeighted, &inflight, &usdc, 0, 0.99), 1);
+        assert_eq!(pick(SelectionStrategy::BalanceWeighted, &inflight, &[Decimal::ZERO; 3], 5, 0.5), 2);
+    }
+}
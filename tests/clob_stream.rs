@@ -0,0 +1,109 @@
+mod support;
+
+use polymarket_bot::clob_client::{ClobClient, ClobError, PriceUpdate, Subscription};
+use polymarket_bot::order_book::SharedOrderBooks;
+use polymarket_bot::signals::{SignalConfig, Signals};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use support::{book, last_trade, price_change, MockClob, Step};
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Duration};
+
+const DEADLINE: Duration = Duration::from_secs(5);
+
+fn client(server: &MockClob) -> ClobClient {
+    ClobClient::new().with_urls(server.url(), "http://127.0.0.1:9")
+}
+
+/// Runs one `stream_prices` session against the mock and returns how it ended plus every
+/// update the callback saw.
+async fn stream_once(client: &ClobClient, ids: &[&str], subscriptions: &mut mpsc::UnboundedReceiver<Subscription>) -> (ClobError, Vec<(String, Decimal)>) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let callback = move |update: PriceUpdate| {
+        let tx = tx.clone();
+        async move { let _ = tx.send((update.asset_id, update.price)); }
+    };
+    let ids = ids.iter().map(|id| id.to_string()).collect();
+    let result = timeout(DEADLINE, client.stream_prices(ids, subscriptions, callback)).await.expect("stream did not end");
+    let mut updates = Vec::new();
+    while let Ok(update) = rx.try_recv() {
+        updates.push(update);
+    }
+    (result.expect_err("streams only end on errors"), updates)
+}
+
+#[tokio::test]
+async fn test_streams_scripted_prices_to_the_callback() {
+    let server = MockClob::start(vec![vec![
+        Step::AwaitSubscribe,
+        Step::Send(price_change("1", "0.49", "0.51")),
+        Step::Send(last_trade("2", "0.3")),
+        Step::Close,
+    ]]).await;
+    let (_sub_tx, mut sub_rx) = mpsc::unbounded_channel();
+
+    let (error, updates) = stream_once(&client(&server), &["1", "2"], &mut sub_rx).await;
+    assert!(matches!(error, ClobError::ConnectionClosed));
+    assert_eq!(updates, vec![("1".to_string(), dec!(0.50)), ("2".to_string(), dec!(0.3))]);
+    assert_eq!(server.subscriptions()[0][0]["topic"], "prices");
+    assert_eq!(server.subscribed_assets(0), vec!["1", "2"]);
+}
+
+#[tokio::test]
+async fn test_new_assets_are_subscribed_on_the_live_connection() {
+    let server = MockClob::start(vec![vec![
+        Step::AwaitSubscribe,
+        Step::AwaitSubscribe,
+        Step::Send(price_change("2", "0.20", "0.22")),
+        Step::Close,
+    ]]).await;
+    let (sub_tx, mut sub_rx) = mpsc::unbounded_channel();
+    sub_tx.send(Subscription::Add(vec!["2".to_string()])).unwrap();
+
+    let (_, updates) = stream_once(&client(&server), &["1"], &mut sub_rx).await;
+    assert_eq!(server.subscriptions().len(), 1);
+    assert_eq!(server.subscribed_assets(0), vec!["1", "2"]);
+    assert_eq!(updates, vec![("2".to_string(), dec!(0.21))]);
+}
+
+#[tokio::test]
+async fn test_reconnects_resubscribe_after_close_and_stall() {
+    let server = MockClob::start(vec![
+        vec![Step::AwaitSubscribe, Step::Send(price_change("1", "0.40", "0.42")), Step::Close],
+        vec![Step::AwaitSubscribe, Step::Send(price_change("1", "0.44", "0.46")), Step::Stall],
+    ]).await;
+    let client = client(&server).with_stale_after(Duration::from_millis(300));
+    let (_sub_tx, mut sub_rx) = mpsc::unbounded_channel();
+
+    // The same retry the price loop in main runs: every recoverable error reconnects with the
+    // full asset list
+    let (closed, first) = stream_once(&client, &["1", "2"], &mut sub_rx).await;
+    assert!(matches!(closed, ClobError::ConnectionClosed) && closed.is_recoverable());
+    let (stalled, second) = stream_once(&client, &["1", "2"], &mut sub_rx).await;
+    assert!(matches!(stalled, ClobError::Stale(_)) && stalled.is_recoverable());
+
+    assert_eq!(server.subscribed_assets(0), server.subscribed_assets(1));
+    assert_eq!(first, vec![("1".to_string(), dec!(0.41))]);
+    assert_eq!(second, vec![("1".to_string(), dec!(0.45))]);
+}
+
+#[tokio::test]
+async fn test_book_stream_keeps_shared_books_in_sync() {
+    let server = MockClob::start(vec![vec![
+        Step::AwaitSubscribe,
+        Step::Send(book("1", ("0.48", "30"), ("0.52", "25"))),
+        Step::Send(price_change("1", "0.50", "0.52")),
+        Step::Close,
+    ]]).await;
+    let books = SharedOrderBooks::default();
+    let signals = Arc::new(Signals::new(SignalConfig::default()));
+    let (_sub_tx, mut sub_rx) = mpsc::unbounded_channel();
+
+    let result = timeout(DEADLINE, client(&server).stream_books(vec!["1".to_string()], &mut sub_rx, books.clone(), signals)).await.unwrap();
+    assert!(matches!(result, Err(ClobError::ConnectionClosed)));
+    assert_eq!(server.subscriptions()[0][0]["topic"], "book");
+    let books = books.read().await;
+    assert_eq!(books["1"].best_bid().map(|level| level.price), Some(dec!(0.50)));
+    assert_eq!(books["1"].mid_price(), Some(dec!(0.51)));
+}
@@ -0,0 +1,95 @@
+//! Contract flows against an anvil fork of Polygon. They need `anvil` on the PATH and a Polygon
+//! RPC in `POLYGON_FORK_URL`, so they are ignored by default:
+//!
+//!     POLYGON_FORK_URL=https://polygon-rpc.com cargo test --test fork_execution -- --ignored
+//!
+//! Markets are fresh conditions prepared on the fork with the test wallet as oracle, so they
+//! can be split, merged and resolved at will. CLOB legs run in dry-run mode and count as filled.
+
+mod support;
+
+use polymarket_bot::blockchain::{TradeExecutor, DEFAULT_CONDITIONAL_TOKENS_ADDRESS, DEFAULT_CTF_EXCHANGE_ADDRESS, DEFAULT_NEG_RISK_ADAPTER_ADDRESS, DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS};
+use polymarket_bot::clob_client::ClobClient;
+use polymarket_bot::gas::GasPolicy;
+use polymarket_bot::redemption::RedemptionTarget;
+use polymarket_bot::shared_types::{Market, RebalancingOpportunity};
+use ethers::types::{Address, U256};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use support::Fork;
+
+async fn executor(fork: &Fork) -> TradeExecutor {
+    // Dry-run orders are signed but never posted, so placeholder L2 credentials do
+    std::env::set_var("POLY_API_KEY", "fork");
+    std::env::set_var("POLY_API_SECRET", "Zm9yaw==");
+    std::env::set_var("POLY_PASSPHRASE", "fork");
+    let clob = ClobClient::new().with_dry_run(true).with_wallet(&fork.private_key()).await.unwrap();
+    let executor = TradeExecutor::new(&fork.endpoint(), &fork.private_key(), None).await.unwrap()
+        .with_clob(Arc::new(clob))
+        // Test arbs are tiny, so gas always outweighs their profit
+        .with_gas_policy(GasPolicy { max_profit_fraction: dec!(1000000), ..GasPolicy::default() });
+    executor.refresh_balances().await.unwrap();
+    executor
+}
+
+fn rebalancing(market: &Market, side: &str, limit_prices: Vec<Decimal>) -> RebalancingOpportunity {
+    RebalancingOpportunity { market_id: market.id.clone(), profit: dec!(0.05), opportunity_type: side.to_string(), size: dec!(10), limit_prices }
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and POLYGON_FORK_URL"]
+async fn test_approvals_are_granted_once_per_spender_and_operator() {
+    let fork = Fork::spawn();
+    let executor = executor(&fork).await;
+
+    executor.ensure_approvals().await.unwrap();
+    for spender in [DEFAULT_CTF_EXCHANGE_ADDRESS, DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS, DEFAULT_CONDITIONAL_TOKENS_ADDRESS, DEFAULT_NEG_RISK_ADAPTER_ADDRESS] {
+        assert_eq!(fork.usdc_allowance(spender.parse::<Address>().unwrap()).await, U256::MAX);
+    }
+    for operator in [DEFAULT_CTF_EXCHANGE_ADDRESS, DEFAULT_NEG_RISK_CTF_EXCHANGE_ADDRESS, DEFAULT_NEG_RISK_ADAPTER_ADDRESS] {
+        assert!(fork.operator_approved(operator.parse::<Address>().unwrap()).await);
+    }
+    // Cached approvals cover the second pass without touching the chain
+    let nonce = fork.nonce().await;
+    executor.ensure_approvals().await.unwrap();
+    assert_eq!(fork.nonce().await, nonce);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and POLYGON_FORK_URL"]
+async fn test_short_splits_and_long_merges_complete_sets() {
+    let fork = Fork::spawn();
+    fork.fund_usdc(dec!(100)).await;
+    let market = fork.binary_market("split and merge").await;
+    let executor = executor(&fork).await;
+    let asset_ids: Vec<String> = market.conditions.iter().map(|c| c.asset_id.clone()).collect();
+
+    // A Short splits 10 USDC into 10 sets and sells the legs
+    executor.execute_rebalancing(&market, &rebalancing(&market, "Short", vec![dec!(0.55), dec!(0.50)]), dec!(10)).await.unwrap();
+    assert_eq!(fork.usdc_balance().await, dec!(90));
+    let held = executor.fetch_balances(&asset_ids).await.unwrap();
+    assert!(asset_ids.iter().all(|id| held[id] == dec!(10)));
+
+    // A Long buys the legs and merges the sets back into USDC
+    executor.execute_rebalancing(&market, &rebalancing(&market, "Long", vec![dec!(0.45), dec!(0.50)]), dec!(9.5)).await.unwrap();
+    assert_eq!(fork.usdc_balance().await, dec!(100));
+    let held = executor.fetch_balances(&asset_ids).await.unwrap();
+    assert!(asset_ids.iter().all(|id| held[id].is_zero()));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and POLYGON_FORK_URL"]
+async fn test_redeems_winning_tokens_after_resolution() {
+    let fork = Fork::spawn();
+    fork.fund_usdc(dec!(100)).await;
+    let market = fork.binary_market("redeem").await;
+    let executor = executor(&fork).await;
+    executor.execute_rebalancing(&market, &rebalancing(&market, "Short", vec![dec!(0.55), dec!(0.50)]), dec!(10)).await.unwrap();
+
+    fork.resolve(&market, [1, 0]).await;
+    let target = RedemptionTarget { market: market.clone(), payouts: vec![Decimal::ONE, Decimal::ZERO] };
+    let redeemed = executor.redeem_positions(&[target]).await.unwrap();
+    assert!(matches!(&redeemed[..], [(id, Ok(payout))] if *id == market.id && *payout == dec!(10)));
+    assert_eq!(fork.usdc_balance().await, dec!(100));
+}
@@ -0,0 +1,140 @@
+use chrono::{TimeZone, Utc};
+use ethers::prelude::*;
+use ethers::signers::Signer;
+use ethers::utils::{hex, Anvil, AnvilInstance};
+use polymarket_bot::blockchain::{DEFAULT_CONDITIONAL_TOKENS_ADDRESS, DEFAULT_USDC_ADDRESS};
+use polymarket_bot::units::UsdcAmount;
+use polymarket_bot::shared_types::{Condition, Market, OutcomeKind, ResolutionRules};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+
+abigen!(
+    ForkConditionalTokens,
+    r#"[
+        function prepareCondition(address oracle, bytes32 questionId, uint256 outcomeSlotCount) external
+        function reportPayouts(bytes32 questionId, uint256[] payouts) external
+        function getConditionId(address oracle, bytes32 questionId, uint256 outcomeSlotCount) external pure returns (bytes32)
+        function getCollectionId(bytes32 parentCollectionId, bytes32 conditionId, uint256 indexSet) external view returns (bytes32)
+        function getPositionId(address collateralToken, bytes32 collectionId) external pure returns (uint256)
+        function isApprovedForAll(address owner, address operator) external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    ForkUsdc,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function transfer(address to, uint256 amount) external returns (bool)
+    ]"#
+);
+
+type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// A local anvil fork of Polygon at `POLYGON_FORK_URL`, with the first dev account as the
+/// trading wallet. The fork is torn down on drop.
+pub struct Fork {
+    anvil: AnvilInstance,
+    pub provider: Provider<Http>,
+    pub wallet: LocalWallet,
+    signer: Arc<Client>,
+}
+
+impl Fork {
+    /// Panics when anvil is not installed or `POLYGON_FORK_URL` is unset.
+    pub fn spawn() -> Self {
+        let url = std::env::var("POLYGON_FORK_URL").expect("POLYGON_FORK_URL must point at a Polygon RPC");
+        let anvil = Anvil::new().fork(url).spawn();
+        let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+        let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+        let signer = Arc::new(SignerMiddleware::new(provider.clone(), wallet.clone()));
+        Self { anvil, provider, wallet, signer }
+    }
+
+    pub fn endpoint(&self) -> String {
+        self.anvil.endpoint()
+    }
+
+    pub fn private_key(&self) -> String {
+        hex::encode(self.wallet.signer().to_bytes())
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    fn usdc(&self) -> ForkUsdc<Provider<Http>> {
+        ForkUsdc::new(DEFAULT_USDC_ADDRESS.parse::<Address>().unwrap(), Arc::new(self.provider.clone()))
+    }
+
+    fn ctf(&self) -> ForkConditionalTokens<Client> {
+        ForkConditionalTokens::new(DEFAULT_CONDITIONAL_TOKENS_ADDRESS.parse::<Address>().unwrap(), self.signer.clone())
+    }
+
+    /// Moves `amount` USDC.e to the wallet from the ConditionalTokens contract, which holds the
+    /// collateral of every plain CTF market, by impersonating it.
+    pub async fn fund_usdc(&self, amount: Decimal) {
+        let whale: Address = DEFAULT_CONDITIONAL_TOKENS_ADDRESS.parse().unwrap();
+        self.provider.request::<_, ()>("anvil_impersonateAccount", [whale]).await.unwrap();
+        self.provider.request::<_, ()>("anvil_setBalance", (whale, U256::exp10(18))).await.unwrap();
+        let tx = self.usdc().transfer(self.address(), UsdcAmount(amount).to_units().unwrap()).from(whale).tx;
+        self.provider.send_transaction(tx, None).await.unwrap().await.unwrap();
+        self.provider.request::<_, ()>("anvil_stopImpersonatingAccount", [whale]).await.unwrap();
+    }
+
+    pub async fn usdc_balance(&self) -> Decimal {
+        UsdcAmount::from_units(self.usdc().balance_of(self.address()).call().await.unwrap()).unwrap().value()
+    }
+
+    pub async fn usdc_allowance(&self, spender: Address) -> U256 {
+        self.usdc().allowance(self.address(), spender).call().await.unwrap()
+    }
+
+    pub async fn operator_approved(&self, operator: Address) -> bool {
+        self.ctf().is_approved_for_all(self.address(), operator).call().await.unwrap()
+    }
+
+    pub async fn nonce(&self) -> U256 {
+        self.provider.get_transaction_count(self.address(), None).await.unwrap()
+    }
+
+    /// Prepares a fresh binary USDC.e condition with the wallet as its oracle and returns it as
+    /// a market whose asset ids are the two outcome position ids.
+    pub async fn binary_market(&self, question: &str) -> Market {
+        let ctf = self.ctf();
+        let question_id = ethers::utils::keccak256(question.as_bytes());
+        ctf.prepare_condition(self.address(), question_id, 2.into()).send().await.unwrap().await.unwrap();
+        let condition_id = ctf.get_condition_id(self.address(), question_id, 2.into()).call().await.unwrap();
+        let mut conditions = Vec::new();
+        for (index_set, name) in [(1u64, "Yes"), (2, "No")] {
+            let collection = ctf.get_collection_id([0u8; 32], condition_id, index_set.into()).call().await.unwrap();
+            let position = ctf.get_position_id(DEFAULT_USDC_ADDRESS.parse().unwrap(), collection).call().await.unwrap();
+            conditions.push(Condition { name: name.to_string(), price: dec!(0.5), outcome: Some(index_set == 1), asset_id: position.to_string(), kind: OutcomeKind::Other, last_updated: None });
+        }
+        Market {
+            id: question.to_string(),
+            title: question.to_string(),
+            end_date: Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap(),
+            conditions,
+            neg_risk_market_id: None,
+            tags: vec![],
+            liquidity: Decimal::ZERO,
+            volume_24hr: Decimal::ZERO,
+            spread: None,
+            condition_id: format!("0x{}", hex::encode(condition_id)),
+            question_id: format!("0x{}", hex::encode(question_id)),
+            tick_size: dec!(0.01),
+            min_order_size: Decimal::ZERO,
+            event_id: None,
+            resolution: ResolutionRules::default(),
+            fees: None,
+        }
+    }
+
+    /// Resolves a market from `binary_market`, as its oracle.
+    pub async fn resolve(&self, market: &Market, payouts: [u64; 2]) {
+        let question_id: [u8; 32] = hex::decode(market.question_id.trim_start_matches("0x")).unwrap().try_into().unwrap();
+        self.ctf().report_payouts(question_id, payouts.iter().map(|&p| p.into()).collect()).send().await.unwrap().await.unwrap();
+    }
+}
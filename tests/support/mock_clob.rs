@@ -0,0 +1,141 @@
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::tungstenite::Message;
+
+/// One scripted action of a mock connection.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Waits for the client's next subscribe frame.
+    AwaitSubscribe,
+    /// Sends a text frame, a single event or a batch.
+    Send(Value),
+    Pause(Duration),
+    /// Sends a close frame and drops the connection.
+    Close,
+    /// Stops reading and writing without closing, so pings go unanswered.
+    Stall,
+}
+
+/// Local CLOB websocket that plays one script per accepted connection, in order, and records
+/// the subscribe frames each connection sent. A connection whose script runs out stays open,
+/// answering pings, until the client leaves.
+pub struct MockClob {
+    url: String,
+    subscriptions: Arc<Mutex<Vec<Vec<Value>>>>,
+    subscribed: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl MockClob {
+    pub async fn start(sessions: Vec<Vec<Step>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock CLOB");
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let subscriptions: Arc<Mutex<Vec<Vec<Value>>>> = Arc::default();
+        let subscribed = Arc::new(Notify::new());
+        let (recorded, notify) = (subscriptions.clone(), subscribed.clone());
+        let task = tokio::spawn(async move {
+            for (connection, script) in sessions.into_iter().enumerate() {
+                let Ok((stream, _)) = listener.accept().await else { return };
+                let Ok(ws) = tokio_tungstenite::accept_async(stream).await else { return };
+                recorded.lock().unwrap().push(Vec::new());
+                let (recorded, notify) = (recorded.clone(), notify.clone());
+                tokio::spawn(async move { play(ws, script, connection, recorded, notify).await });
+            }
+        });
+        Self { url, subscriptions, subscribed, task }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Subscribe frames received so far, per connection.
+    pub fn subscriptions(&self) -> Vec<Vec<Value>> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    /// Asset ids subscribed on `connection`, in the order they were asked for.
+    pub fn subscribed_assets(&self, connection: usize) -> Vec<String> {
+        self.subscriptions().get(connection).into_iter().flatten()
+            .flat_map(|frame| frame["asset_ids"].as_array().cloned().unwrap_or_default())
+            .filter_map(|id| id.as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Waits until `count` subscribe frames have arrived across all connections.
+    pub async fn wait_for_subscriptions(&self, count: usize) {
+        loop {
+            let notified = self.subscribed.notified();
+            if self.subscriptions().iter().map(Vec::len).sum::<usize>() >= count {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+impl Drop for MockClob {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn play<S>(ws: tokio_tungstenite::WebSocketStream<S>, script: Vec<Step>, connection: usize, recorded: Arc<Mutex<Vec<Vec<Value>>>>, notify: Arc<Notify>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws.split();
+    let record = |text: &str| {
+        let frame: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+        let subscribe = frame["type"] == "subscribe" || frame["type"] == "user";
+        if subscribe {
+            recorded.lock().unwrap()[connection].push(frame);
+            notify.notify_waiters();
+        }
+        subscribe
+    };
+    for step in script {
+        match step {
+            Step::AwaitSubscribe => loop {
+                match read.next().await {
+                    Some(Ok(Message::Text(text))) if record(&text) => break,
+                    Some(Ok(Message::Ping(payload))) => {
+                        let _ = write.send(Message::Pong(payload)).await;
+                    }
+                    Some(Ok(_)) => {}
+                    _ => return,
+                }
+            },
+            Step::Send(event) => {
+                if write.send(Message::Text(event.to_string())).await.is_err() {
+                    return;
+                }
+            }
+            Step::Pause(duration) => sleep(duration).await,
+            Step::Close => {
+                let _ = write.send(Message::Close(None)).await;
+                return;
+            }
+            Step::Stall => {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+    while let Some(Ok(message)) = read.next().await {
+        match message {
+            Message::Text(text) => {
+                record(&text);
+            }
+            Message::Ping(payload) => {
+                let _ = write.send(Message::Pong(payload)).await;
+            }
+            Message::Close(_) => return,
+            _ => {}
+        }
+    }
+}
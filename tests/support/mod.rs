@@ -0,0 +1,32 @@
+//! Shared harness for integration tests: a local CLOB websocket that replays scripted events
+//! and an anvil fork of Polygon for the contract calls.
+#![allow(dead_code, unused_imports)]
+
+mod fork;
+mod mock_clob;
+
+pub use fork::Fork;
+pub use mock_clob::{MockClob, Step};
+
+use serde_json::{json, Value};
+
+pub fn price_change(asset_id: &str, best_bid: &str, best_ask: &str) -> Value {
+    json!({
+        "event_type": "price_change",
+        "market": "0xabc",
+        "price_changes": [{ "asset_id": asset_id, "price": best_bid, "size": "10", "side": "BUY", "best_bid": best_bid, "best_ask": best_ask }],
+    })
+}
+
+pub fn last_trade(asset_id: &str, price: &str) -> Value {
+    json!({ "event_type": "last_trade_price", "asset_id": asset_id, "price": price, "size": "5", "side": "SELL" })
+}
+
+pub fn book(asset_id: &str, bid: (&str, &str), ask: (&str, &str)) -> Value {
+    json!({
+        "event_type": "book",
+        "asset_id": asset_id,
+        "bids": [{ "price": bid.0, "size": bid.1 }],
+        "asks": [{ "price": ask.0, "size": ask.1 }],
+    })
+}